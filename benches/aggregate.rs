@@ -0,0 +1,46 @@
+use std::time::Instant;
+
+use p3_uni_stark::{prove, verify};
+use plonky3_cook::aggregate::{prove_aggregate, verify_aggregate};
+use plonky3_cook::config::{default_config, random_perm, Challenger, Val};
+use plonky3_cook::simple_state::{random_trace, SimpleState};
+
+// `K` SimpleState instances proven one by one against the same instances aggregated into one side-by-side
+// proof. The aggregate commits one wider trace, so proving costs about the same; the savings are on the
+// verifier's side, where one FRI proof replaces `K`, and in the bytes shipped.
+
+const K: usize = 10;
+const LOG_N: usize = 12;
+
+fn main() {
+    let perm = random_perm();
+    let config = default_config(&perm);
+    let traces = (0..K).map(|_| random_trace::<Val>(LOG_N)).collect::<Vec<_>>();
+
+    let start = Instant::now();
+    let proofs = traces
+        .iter()
+        .map(|trace| prove(&config, &SimpleState {}, &mut Challenger::new(perm.clone()), trace.clone(), &vec![]))
+        .collect::<Vec<_>>();
+    let prove_time = start.elapsed();
+    let start = Instant::now();
+    for proof in &proofs {
+        verify(&config, &SimpleState {}, &mut Challenger::new(perm.clone()), proof, &vec![]).unwrap();
+    }
+    let verify_time = start.elapsed();
+    let bytes = proofs.iter().map(|proof| bincode::serialize(proof).unwrap().len()).sum::<usize>();
+    println!("{:<11} prove {:>10?}   verify {:>10?}   proofs {:>8} bytes", "separate", prove_time, verify_time, bytes);
+
+    let instances = traces.into_iter().map(|trace| (trace, vec![])).collect();
+    let start = Instant::now();
+    let aggregate = prove_aggregate(&config, &perm, &SimpleState {}, instances);
+    let prove_time = start.elapsed();
+    let start = Instant::now();
+    verify_aggregate(&config, &perm, &SimpleState {}, &aggregate).unwrap();
+    let verify_time = start.elapsed();
+    let bytes = bincode::serialize(&aggregate.proof).unwrap().len();
+    println!(
+        "{:<11} prove {:>10?}   verify {:>10?}   proof  {:>8} bytes",
+        "aggregated", prove_time, verify_time, bytes
+    );
+}
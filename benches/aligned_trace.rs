@@ -0,0 +1,56 @@
+use std::time::{Duration, Instant};
+
+use p3_field::AbstractField;
+use plonky3_cook::alloc::aligned_trace::{alloc_aligned_trace, is_cache_aligned, CacheAlignedAlloc};
+use plonky3_cook::config::Val;
+use plonky3_cook::simple_state::{random_trace, SS_ROW_WIDTH};
+
+#[global_allocator]
+static ALLOC: CacheAlignedAlloc = CacheAlignedAlloc;
+
+// Evaluates the SimpleState transition over a 2^16-row trace stored on a cache line, and over the same data
+// shifted by one element so that rows straddle cache lines the way an unlucky default allocation would.
+//
+// With only 3 columns per row the difference is small; expect it to grow with row width.
+
+const LOG_N: usize = 16;
+const ROUNDS: usize = 200;
+
+fn eval_transitions(values: &[Val]) -> Val {
+    let mut acc = Val::zero();
+    for (local, next) in values.chunks_exact(SS_ROW_WIDTH).zip(values.chunks_exact(SS_ROW_WIDTH).skip(1)) {
+        acc += local[0] + local[1] - local[2] - next[0];
+    }
+    acc
+}
+
+fn time(values: &[Val]) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        assert_eq!(std::hint::black_box(eval_transitions(std::hint::black_box(values))), Val::zero());
+    }
+    start.elapsed() / ROUNDS as u32
+}
+
+fn main() {
+    let source = random_trace::<Val>(LOG_N);
+
+    let mut aligned = alloc_aligned_trace::<Val>(1 << LOG_N, SS_ROW_WIDTH).unwrap();
+    aligned.values.copy_from_slice(&source.values);
+    assert!(is_cache_aligned(&aligned));
+
+    let mut shifted = alloc_aligned_trace::<Val>((1 << LOG_N) + 1, SS_ROW_WIDTH).unwrap();
+    shifted.values[1..1 + source.values.len()].copy_from_slice(&source.values);
+    let misaligned = &shifted.values[1..1 + source.values.len()];
+
+    // warm up
+    time(&aligned.values);
+    time(misaligned);
+
+    let t_aligned = time(&aligned.values);
+    let t_misaligned = time(misaligned);
+    println!("rows: 2^{}", LOG_N);
+    println!("cache-line aligned: {:?}", t_aligned);
+    println!("misaligned:         {:?}", t_misaligned);
+    println!("speedup:            {:.3}x", t_misaligned.as_secs_f64() / t_aligned.as_secs_f64());
+}
@@ -0,0 +1,96 @@
+use std::time::Instant;
+
+use p3_challenger::{HashChallenger, SerializingChallenger32};
+use p3_commit::{ExtensionMmcs, Pcs as _};
+use p3_fri::{FriConfig, TwoAdicFriPcs};
+use p3_keccak::Keccak256Hash;
+use p3_keccak_air::{generate_trace_rows, KeccakAir};
+use p3_matrix::Matrix;
+use p3_merkle_tree::FieldMerkleTreeMmcs;
+use p3_symmetric::{CompressionFunctionFromHasher, SerializingHasher32};
+use p3_uni_stark::{prove, verify, StarkConfig, StarkGenericConfig};
+use plonky3_cook::config::{
+    default_config, hybrid_babybear_config, random_perm, random_perm24, wide_config, Challenge, Challenger, Dft,
+    Val, DEFAULT_LOG_BLOWUP, DEFAULT_NUM_QUERIES, DEFAULT_POW_BITS,
+};
+use rand::random;
+
+// Leaf-hashing cost on the ~2600-column Keccak AIR trace, width-16 (rate 8) vs width-24 (rate 16) sponge.
+// Each row is absorbed in ceil(width / rate) permutation calls, so the wider sponge roughly halves them.
+//
+// The hybrid (Keccak leaves, Poseidon2 nodes) and pure Keccak configs are measured alongside, with the
+// serialized proof size, since the digest type is what a proof mostly consists of.
+
+const NUM_HASHES: usize = 512;
+
+type KeccakMmcs = FieldMerkleTreeMmcs<
+    Val,
+    u8,
+    SerializingHasher32<Keccak256Hash>,
+    CompressionFunctionFromHasher<u8, Keccak256Hash, 2, 32>,
+    32,
+>;
+type KeccakChallenger = SerializingChallenger32<Val, HashChallenger<u8, Keccak256Hash, 32>>;
+type KeccakPcs = TwoAdicFriPcs<Val, Dft, KeccakMmcs, ExtensionMmcs<Val, Challenge, KeccakMmcs>>;
+type KeccakConfig = StarkConfig<KeccakPcs, Challenge, KeccakChallenger>;
+
+fn keccak_config() -> KeccakConfig {
+    let val_mmcs = KeccakMmcs::new(
+        SerializingHasher32::new(Keccak256Hash {}),
+        CompressionFunctionFromHasher::new(Keccak256Hash {}),
+    );
+    let fri_config = FriConfig {
+        log_blowup: DEFAULT_LOG_BLOWUP,
+        num_queries: DEFAULT_NUM_QUERIES,
+        proof_of_work_bits: DEFAULT_POW_BITS,
+        mmcs: ExtensionMmcs::new(val_mmcs.clone()),
+    };
+    KeccakConfig::new(KeccakPcs::new(Dft {}, val_mmcs, fri_config))
+}
+
+fn keccak_challenger() -> KeccakChallenger {
+    SerializingChallenger32::from_hasher(vec![], Keccak256Hash {})
+}
+
+fn main() {
+    let inputs = (0..NUM_HASHES).map(|_| random()).collect::<Vec<_>>();
+    let trace = generate_trace_rows::<Val>(inputs);
+    println!("keccak trace: {} rows x {} columns", trace.height(), trace.width());
+
+    let perm = random_perm();
+    let narrow = default_config(&perm);
+    let wide = wide_config(&perm, &random_perm24());
+    let hybrid = hybrid_babybear_config(&perm);
+    let keccak = keccak_config();
+
+    macro_rules! bench {
+        ($name:expr, $config:expr, $challenger:expr) => {{
+            let config = $config;
+            let pcs = config.pcs();
+            let domain = pcs.natural_domain_for_degree(trace.height());
+
+            let start = Instant::now();
+            let _ = pcs.commit(vec![(domain, trace.clone())]);
+            let commit_time = start.elapsed();
+
+            let start = Instant::now();
+            let mut p_challenger = $challenger;
+            let proof = prove(config, &KeccakAir {}, &mut p_challenger, trace.clone(), &vec![]);
+            let prove_time = start.elapsed();
+
+            let mut v_challenger = $challenger;
+            verify(config, &KeccakAir {}, &mut v_challenger, &proof, &vec![]).unwrap();
+
+            let proof_bytes = bincode::serialize(&proof).unwrap().len();
+            println!(
+                "{:<32} commit {:>10?}   prove {:>10?}   proof {:>8} bytes",
+                $name, commit_time, prove_time, proof_bytes
+            );
+        }};
+    }
+
+    bench!("poseidon2 w16 (rate 8)", &narrow, Challenger::new(perm.clone()));
+    bench!("poseidon2 w24 (rate 16)", &wide, Challenger::new(perm.clone()));
+    bench!("keccak leaves, poseidon2 nodes", &hybrid, Challenger::new(perm.clone()));
+    bench!("keccak", &keccak, keccak_challenger());
+}
@@ -0,0 +1,54 @@
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use p3_field::AbstractField;
+use p3_symmetric::Permutation;
+use plonky3_cook::config::{random_perm, random_perm24, Val};
+
+// Raw Poseidon2 throughput, the cost under every leaf hash, Merkle compression and challenger duplex.
+// Each permutation's output is the next one's input, so the calls can't overlap or be skipped.
+//
+// The width-24 permutation takes 1.5x the state of the width-16 one but absorbs twice the rate (16 elements
+// against 8), so per absorbed element it is the cheaper sponge as long as it costs less than twice as much.
+
+const PERMUTATIONS: usize = 1 << 16;
+const ROUNDS: usize = 5;
+
+/// The fastest of `ROUNDS` runs of `PERMUTATIONS` chained permutations.
+fn time<const WIDTH: usize>(perm: &impl Permutation<[Val; WIDTH]>) -> Duration {
+    (0..ROUNDS)
+        .map(|_| {
+            let mut state: [Val; WIDTH] = core::array::from_fn(Val::from_canonical_usize);
+            let start = Instant::now();
+            for _ in 0..PERMUTATIONS {
+                perm.permute_mut(black_box(&mut state));
+            }
+            black_box(state);
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn report(name: &str, elapsed: Duration, rate: usize) {
+    let per_sec = PERMUTATIONS as f64 / elapsed.as_secs_f64();
+    println!(
+        "{:<20} {:>8.1} ns/perm   {:>12.0} perms/s   {:>12.0} absorbed elements/s",
+        name,
+        elapsed.as_nanos() as f64 / PERMUTATIONS as f64,
+        per_sec,
+        per_sec * rate as f64
+    );
+}
+
+fn main() {
+    let (perm, perm24) = (random_perm(), random_perm24());
+
+    // warm up
+    time(&perm);
+    time(&perm24);
+
+    println!("{} chained permutations, best of {}", PERMUTATIONS, ROUNDS);
+    report("poseidon2 w16 (rate 8)", time(&perm), 8);
+    report("poseidon2 w24 (rate 16)", time(&perm24), 16);
+}
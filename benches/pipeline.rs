@@ -0,0 +1,50 @@
+use std::time::Instant;
+
+use p3_uni_stark::prove;
+use plonky3_cook::config::{default_config, random_perm, Challenger, Val};
+use plonky3_cook::pipeline::{prove_stream, PipelineOptions};
+use plonky3_cook::simple_state::SimpleStateChecked;
+use plonky3_cook::transaction::{trace_from_transactions, validate_all, Transaction};
+use rand::{thread_rng, Rng};
+
+// End-to-end time for 8 segments of 2^14 `SimpleStateChecked` rows: generate-then-prove one segment at a
+// time, versus the pipeline overlapping generation with proving.
+
+const NUM_SEGMENTS: usize = 8;
+const LOG_SEGMENT_ROWS: usize = 14;
+
+fn main() {
+    let mut rng = thread_rng();
+    let mut balance = 100000;
+    let segments = (0..NUM_SEGMENTS)
+        .map(|_| {
+            let txs = (0..1 << LOG_SEGMENT_ROWS)
+                .map(|_| Transaction::new(rng.gen_range(0..1 << 10), rng.gen_range(0..1 << 9)))
+                .collect::<Vec<_>>();
+            let initial = balance;
+            balance = *validate_all(initial, &txs).unwrap().last().unwrap();
+            (initial, txs)
+        })
+        .collect::<Vec<_>>();
+    let generate = |(initial, txs): (u32, Vec<Transaction>)| trace_from_transactions::<Val>(initial, &txs).unwrap();
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+
+    let start = Instant::now();
+    for segment in segments.clone() {
+        let (trace, public_values) = generate(segment);
+        let mut challenger = Challenger::new(perm.clone());
+        let _ = prove(&config, &SimpleStateChecked {}, &mut challenger, trace, &public_values);
+    }
+    println!("{:<28} {:>10?}", "sequential", start.elapsed());
+
+    for generator_threads in [1, 2, 4] {
+        let options = PipelineOptions { generator_threads, queue_depth: 2 };
+        let start = Instant::now();
+        let challenger = Challenger::new(perm.clone());
+        let count = prove_stream(&config, &SimpleStateChecked {}, challenger, segments.clone(), generate, options).count();
+        assert_eq!(count, NUM_SEGMENTS);
+        println!("{:<28} {:>10?}", format!("pipelined, {} generator(s)", generator_threads), start.elapsed());
+    }
+}
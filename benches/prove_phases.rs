@@ -0,0 +1,44 @@
+use p3_uni_stark::{prove, verify};
+use plonky3_cook::config::{default_config, random_perm, Challenger, Val};
+use plonky3_cook::instrument::PermutationCounters;
+use plonky3_cook::simple_state::{random_trace, SimpleState};
+use plonky3_cook::timing::prove_timed;
+
+// Prints how SimpleState proving time splits between trace commitment, quotient and FRI, then how many
+// Poseidon2 calls each proof costs per call site.
+
+fn main() {
+    let perm = random_perm();
+    let config = default_config(&perm);
+
+    println!("{:>8} {:>14} {:>12} {:>10} {:>10} {:>10}", "log_n", "trace_commit", "quotient", "fri", "other", "total");
+    for log_n in [12, 14, 16, 18] {
+        let trace = random_trace::<Val>(log_n);
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let (proof, t) = prove_timed(&config, &SimpleState {}, &mut p_challenger, trace, &vec![]);
+        let mut v_challenger = Challenger::new(perm.clone());
+        verify(&config, &SimpleState {}, &mut v_challenger, &proof, &vec![]).unwrap();
+
+        println!(
+            "{:>8} {:>14?} {:>12?} {:>10?} {:>10?} {:>10?}",
+            log_n, t.trace_commit, t.quotient, t.fri, t.other(), t.total
+        );
+    }
+
+    println!();
+    println!("{:>8} {:>14} {:>14} {:>12} {:>12}", "log_n", "leaf_hashes", "compressions", "challenger", "total");
+    let counters = PermutationCounters::new(&perm);
+    let config = counters.config();
+    for log_n in [12, 14, 16, 18] {
+        counters.reset();
+        let trace = random_trace::<Val>(log_n);
+        let _ = prove(&config, &SimpleState {}, &mut counters.challenger(), trace, &vec![]);
+
+        let c = counters.counts();
+        println!(
+            "{:>8} {:>14} {:>14} {:>12} {:>12}",
+            log_n, c.leaf_hashes, c.compressions, c.challenger, c.total()
+        );
+    }
+}
@@ -0,0 +1,61 @@
+use std::fs::{self, File};
+use std::io::BufReader;
+
+use p3_uni_stark::{prove, verify, Proof};
+use plonky3_cook::alloc::peak::PeakAlloc;
+use plonky3_cook::config::{random_perm, Challenger, FriParams, MyConfig};
+use plonky3_cook::simple_state::{random_checked_trace, SimpleStateChecked};
+use plonky3_cook::streaming_verify::{verify_streamed, write_streamed};
+
+// Peak heap use while verifying one `SimpleStateChecked` proof read from a file: decoding the whole proof and
+// running uni-stark's `verify`, versus `verify_streamed` reading one query response at a time. Both are
+// measured from the same baseline, so the numbers are the verifier's own allocations, decoding included.
+
+#[global_allocator]
+static ALLOC: PeakAlloc = PeakAlloc::new();
+
+fn peak_during<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let baseline = ALLOC.live();
+    ALLOC.reset_peak();
+    let out = f();
+    (out, ALLOC.peak() - baseline)
+}
+
+fn main() {
+    let perm = random_perm();
+    let params = FriParams::default();
+    let config = params.config(&perm);
+    let dir = std::env::temp_dir();
+
+    for log_n in [10, 14, 18] {
+        let (trace, public_values) = random_checked_trace(log_n);
+        let mut challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &SimpleStateChecked {}, &mut challenger, trace, &public_values);
+
+        let whole_path = dir.join(format!("plonky3-cook-proof-{}.bin", log_n));
+        let streamed_path = dir.join(format!("plonky3-cook-proof-{}.stream", log_n));
+        fs::write(&whole_path, bincode::serialize(&proof).unwrap()).unwrap();
+        write_streamed(&proof, File::create(&streamed_path).unwrap()).unwrap();
+        drop(proof);
+
+        let (_, whole) = peak_during(|| {
+            let proof: Proof<MyConfig> = bincode::deserialize(&fs::read(&whole_path).unwrap()).unwrap();
+            let mut challenger = Challenger::new(perm.clone());
+            verify(&config, &SimpleStateChecked {}, &mut challenger, &proof, &public_values).unwrap();
+        });
+        let (_, streamed) = peak_during(|| {
+            let reader = BufReader::new(File::open(&streamed_path).unwrap());
+            verify_streamed(&perm, params, &SimpleStateChecked {}, &public_values, reader).unwrap();
+        });
+
+        println!(
+            "2^{:<3} proof {:>8} bytes   verify {:>8} bytes peak   verify_streamed {:>8} bytes peak",
+            log_n,
+            fs::metadata(&whole_path).unwrap().len(),
+            whole,
+            streamed
+        );
+        let _ = fs::remove_file(whole_path);
+        let _ = fs::remove_file(streamed_path);
+    }
+}
@@ -0,0 +1,25 @@
+use std::env;
+
+// Generates `cook_ffi.h` from the exported items into `OUT_DIR`. The C test program is compiled against it
+// by `tests/c_program.rs`, not here, so ordinary builds need no C compiler.
+
+fn main() {
+    let crate_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let out_dir = env::var("OUT_DIR").unwrap();
+
+    match cbindgen::generate(&crate_dir) {
+        Ok(bindings) => {
+            bindings.write_to_file(format!("{}/cook_ffi.h", out_dir));
+        }
+        // rustc will report the real error if the source doesn't parse
+        Err(e) => println!("cargo:warning=cbindgen: {}", e),
+    }
+
+    // `cc::Build` reads these from the environment of a build script; the test passes them on explicitly
+    for var in ["TARGET", "HOST"] {
+        println!("cargo:rustc-env=COOK_FFI_{}={}", var, env::var(var).unwrap());
+    }
+
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+}
@@ -0,0 +1,106 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::slice;
+
+use p3_field::{AbstractField, PrimeField32};
+use p3_uni_stark::{verify, Proof};
+use plonky3_cook::config::{default_config, perm_from_seed, Challenger, MyConfig, Val};
+use plonky3_cook::error::VerifyFailure;
+use plonky3_cook::simple_state::SimpleStateChecked;
+
+// C entry points for verifying `SimpleStateChecked` proofs. Proofs are bincode-encoded `Proof<MyConfig>`
+// made with the default config over `perm_from_seed(COOK_PERM_SEED)`; public values are `[initial, final]`
+// as canonical u32s. Nothing may unwind into C, so every entry point runs under `catch_unwind`.
+
+/// Seed of the Poseidon2 round constants shared by the prover and the C verifier.
+pub const COOK_PERM_SEED: u64 = 0x636f6f6b;
+
+pub const COOK_OK: i32 = 0;
+/// null pointer, undecodable proof bytes, or a non-canonical public value
+pub const COOK_ERR_DECODE: i32 = -1;
+/// wrong number of public values, or a proof whose shape doesn't match the AIR
+pub const COOK_ERR_SHAPE: i32 = -2;
+/// the proof is well-formed but does not verify
+pub const COOK_ERR_VERIFY: i32 = -3;
+/// the verifier panicked; a bug, reported rather than unwound into the caller
+pub const COOK_ERR_PANIC: i32 = -4;
+
+/// Verifies a `SimpleStateChecked` proof. Returns `COOK_OK` or one of the `COOK_ERR_*` codes.
+///
+/// # Safety
+/// `proof_ptr` must point to `proof_len` readable bytes and `publics_ptr` to `publics_len` readable u32s.
+#[no_mangle]
+pub unsafe extern "C" fn cook_verify_simple_state(
+    proof_ptr: *const u8,
+    proof_len: usize,
+    publics_ptr: *const u32,
+    publics_len: usize,
+) -> i32 {
+    if proof_ptr.is_null() || publics_ptr.is_null() {
+        return COOK_ERR_DECODE;
+    }
+    let proof_bytes = slice::from_raw_parts(proof_ptr, proof_len);
+    let publics = slice::from_raw_parts(publics_ptr, publics_len);
+
+    panic::catch_unwind(AssertUnwindSafe(|| verify_simple_state(proof_bytes, publics)))
+        .unwrap_or(COOK_ERR_PANIC)
+}
+
+fn verify_simple_state(proof_bytes: &[u8], publics: &[u32]) -> i32 {
+    let Ok(proof) = bincode::deserialize::<Proof<MyConfig>>(proof_bytes) else {
+        return COOK_ERR_DECODE;
+    };
+    if publics.iter().any(|&v| v >= Val::ORDER_U32) {
+        return COOK_ERR_DECODE;
+    }
+    if publics.len() != 2 {
+        return COOK_ERR_SHAPE;
+    }
+    let public_values = publics.iter().map(|&v| Val::from_canonical_u32(v)).collect::<Vec<_>>();
+
+    let perm = perm_from_seed(COOK_PERM_SEED);
+    let config = default_config(&perm);
+    let mut challenger = Challenger::new(perm);
+    match verify(&config, &SimpleStateChecked {}, &mut challenger, &proof, &public_values) {
+        Ok(()) => COOK_OK,
+        Err(e) => match VerifyFailure::from(e) {
+            VerifyFailure::ProofShape => COOK_ERR_SHAPE,
+            _ => COOK_ERR_VERIFY,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_uni_stark::prove;
+    use plonky3_cook::simple_state::random_checked_trace;
+
+    use super::*;
+
+    fn fixture() -> (Vec<u8>, Vec<u32>) {
+        let perm = perm_from_seed(COOK_PERM_SEED);
+        let config = default_config(&perm);
+        let (trace, public_values) = random_checked_trace::<Val>(6);
+
+        let mut challenger = Challenger::new(perm);
+        let proof = prove(&config, &SimpleStateChecked {}, &mut challenger, trace, &public_values);
+        let publics = public_values.iter().map(|v| v.as_canonical_u32()).collect();
+        (bincode::serialize(&proof).unwrap(), publics)
+    }
+
+    fn call(proof: &[u8], publics: &[u32]) -> i32 {
+        unsafe { cook_verify_simple_state(proof.as_ptr(), proof.len(), publics.as_ptr(), publics.len()) }
+    }
+
+    #[test]
+    fn test_error_codes() {
+        let (proof, publics) = fixture();
+        assert_eq!(call(&proof, &publics), COOK_OK);
+
+        assert_eq!(call(&proof[..proof.len() / 2], &publics), COOK_ERR_DECODE);
+        assert_eq!(call(&proof, &[publics[0], Val::ORDER_U32]), COOK_ERR_DECODE);
+        assert_eq!(unsafe { cook_verify_simple_state(std::ptr::null(), 0, publics.as_ptr(), 2) }, COOK_ERR_DECODE);
+
+        assert_eq!(call(&proof, &publics[..1]), COOK_ERR_SHAPE);
+        assert_eq!(call(&proof, &[publics[0], publics[1] + 1]), COOK_ERR_VERIFY);
+    }
+}
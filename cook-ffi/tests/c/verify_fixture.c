@@ -0,0 +1,60 @@
+/* Exercises the C API the way a C/C++ caller would.
+ *
+ *   verify_fixture <proof file> <initial> <final>
+ *
+ * Exits with 0 on success, or the number of the failed check. */
+
+#include <stdio.h>
+#include <stdlib.h>
+
+#include "cook_ffi.h"
+
+static int verify_fixture(const uint8_t *proof, size_t proof_len, const uint32_t *publics, size_t publics_len) {
+    if (cook_verify_simple_state(proof, proof_len, publics, publics_len) != COOK_OK) {
+        return 1;
+    }
+
+    /* a truncated proof doesn't decode */
+    if (cook_verify_simple_state(proof, proof_len / 2, publics, publics_len) != COOK_ERR_DECODE) {
+        return 2;
+    }
+
+    /* [initial, final] is required */
+    if (cook_verify_simple_state(proof, proof_len, publics, 1) != COOK_ERR_SHAPE) {
+        return 3;
+    }
+
+    /* a different final balance is rejected */
+    uint32_t wrong[2] = {publics[0], publics[1] + 1};
+    if (cook_verify_simple_state(proof, proof_len, wrong, 2) != COOK_ERR_VERIFY) {
+        return 4;
+    }
+
+    return 0;
+}
+
+int main(int argc, char **argv) {
+    if (argc != 4) {
+        fprintf(stderr, "usage: verify_fixture <proof file> <initial> <final>\n");
+        return 100;
+    }
+
+    FILE *f = fopen(argv[1], "rb");
+    if (f == NULL || fseek(f, 0, SEEK_END) != 0) {
+        perror(argv[1]);
+        return 101;
+    }
+    long proof_len = ftell(f);
+    rewind(f);
+    uint8_t *proof = malloc(proof_len);
+    if (proof == NULL || fread(proof, 1, proof_len, f) != (size_t)proof_len) {
+        perror(argv[1]);
+        return 101;
+    }
+    fclose(f);
+
+    uint32_t publics[2] = {(uint32_t)strtoul(argv[2], NULL, 10), (uint32_t)strtoul(argv[3], NULL, 10)};
+    int status = verify_fixture(proof, (size_t)proof_len, publics, 2);
+    free(proof);
+    return status;
+}
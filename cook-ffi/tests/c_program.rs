@@ -0,0 +1,64 @@
+#![cfg(unix)]
+
+use std::path::Path;
+use std::process::Command;
+use std::{env, fs};
+
+use cc::Build;
+use cook_ffi::COOK_PERM_SEED;
+use p3_field::PrimeField32;
+use p3_uni_stark::prove;
+use plonky3_cook::config::{default_config, perm_from_seed, Challenger, Val};
+use plonky3_cook::simple_state::{random_checked_trace, SimpleStateChecked};
+
+// Compiles `tests/c/verify_fixture.c` against the header the build script wrote to `OUT_DIR`, links it to the
+// shared library built next to this test binary, and runs it on a fresh proof. The compiler is the one
+// `cc::Build` picks for the target (`$CC` and friends are honoured); outside a build script it can't read
+// `TARGET` and `HOST` from the environment, so the build script passes them through as `COOK_FFI_TARGET` and
+// `COOK_FFI_HOST`. `cc` only builds static libraries, so the executable is linked with its compiler command.
+
+#[test]
+fn test_c_program_verifies_fixture() {
+    let perm = perm_from_seed(COOK_PERM_SEED);
+    let config = default_config(&perm);
+    let (trace, public_values) = random_checked_trace::<Val>(6);
+    let mut challenger = Challenger::new(perm);
+    let proof = prove(&config, &SimpleStateChecked {}, &mut challenger, trace, &public_values);
+
+    let tmp = Path::new(env!("CARGO_TARGET_TMPDIR"));
+    let proof_path = tmp.join("cook_ffi_fixture.bin");
+    fs::write(&proof_path, bincode::serialize(&proof).unwrap()).unwrap();
+
+    // `target/<profile>/deps`, which also holds the cdylib
+    let lib_dir = env::current_exe().unwrap().parent().unwrap().to_path_buf();
+    let exe = tmp.join("verify_fixture");
+    let compiler = Build::new()
+        .target(env!("COOK_FFI_TARGET"))
+        .host(env!("COOK_FFI_HOST"))
+        .opt_level(0)
+        .out_dir(tmp)
+        .cargo_metadata(false)
+        .get_compiler();
+    let compiled = compiler
+        .to_command()
+        .arg(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/c/verify_fixture.c"))
+        .arg("-I")
+        .arg(env!("OUT_DIR"))
+        .arg("-L")
+        .arg(&lib_dir)
+        .arg("-lcook_ffi")
+        .arg("-o")
+        .arg(&exe)
+        .status()
+        .expect("a C compiler is needed; set CC");
+    assert!(compiled.success(), "compiling the C test program failed");
+
+    let status = Command::new(&exe)
+        .arg(&proof_path)
+        .args(public_values.iter().map(|v| v.as_canonical_u32().to_string()))
+        .env("LD_LIBRARY_PATH", &lib_dir)
+        .env("DYLD_LIBRARY_PATH", &lib_dir)
+        .status()
+        .unwrap();
+    assert_eq!(status.code(), Some(0), "C test program failed at check {:?}", status.code());
+}
@@ -0,0 +1,303 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_field::{AbstractExtensionField, AbstractField, Field};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use plonky3_cook::config::{default_config, random_perm, Challenge, MyConfig, Perm, Val};
+use plonky3_cook::error::CookError;
+use plonky3_cook::lookups::{
+    assert_ext_eq, ext, ext_add, ext_scale, ext_sub, ext_times, ext_values, lift, prove_two_round, verify_two_round,
+    TwoRoundAir, TwoRoundProof, EXT,
+};
+use rand::{thread_rng, Rng};
+use tracing_forest::{util::LevelFilter, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+// `reversed_value` is `value` in bit-reversed order, the input order of a Cooley-Tukey NTT:
+//   reversed_value[i] == value[bit_reverse(i, LOG_N)]
+// over a trace of exactly `2^LOG_N` rows. Row `i` carries `forward_index = i` and
+// `reversed_index = bit_reverse(i)`, and two LogUp sums under the challenges `alpha` and `z` tie them
+// together, with the fingerprint `fp(a, b) = a + alpha * b`:
+//   lookup:       sum of 1 / (z - fp(forward_index, reversed_index))  ==  sum over i of 1 / (z - fp(i, rev(i)))
+//   permutation:  sum of 1 / (z - fp(forward_index, value))  ==  sum of 1 / (z - fp(reversed_index, reversed_value))
+// The lookup's right side is the bit-reversal table, which the verifier computes itself, as `xor.rs` does
+// with its table side; every entry is used once, so no multiplicities are needed. `forward_index` counts
+// 0, 1, 2, ..., so with the lookup every row pairs `i` with `rev(i)`, and the permutation then says the
+// pairs `(i, value[i])` are the pairs `(rev(i), reversed_value[i])`, which is the claim.
+//
+// The four data columns are the first round of a `lookups::TwoRoundAir`, as in `xor.rs`: they are committed,
+// `alpha` and `z` are drawn from `Challenge` after the commitment, and the commitment is opened at the
+// proof's out-of-domain point and compared with the proven trace. The inverses, the accumulators and the
+// table sum are extension elements, `EXT` columns or values each.
+
+const LOG_N: usize = 10;
+
+// the data columns, committed before the challenges are drawn
+const DATA_WIDTH: usize = 4;
+const BR_ROW_WIDTH: usize = DATA_WIDTH + 5 * EXT;
+
+struct BitReversalAir<const LOG_N: usize> {}
+
+impl<F, const LOG_N: usize> BaseAir<F> for BitReversalAir<LOG_N> {
+    fn width(&self) -> usize {
+        BR_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues<F = Val>, const LOG_N: usize> Air<AB> for BitReversalAir<LOG_N> {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &BitReversalRow<AB::Var> = (*local).borrow();
+        let next: &BitReversalRow<AB::Var> = (*next).borrow();
+
+        let pis = builder.public_values();
+        let (alpha, z, table_sum): ([AB::Expr; EXT], [AB::Expr; EXT], [AB::Expr; EXT]) =
+            (ext(&pis[..EXT]), ext(&pis[EXT..2 * EXT]), ext(&pis[2 * EXT..]));
+
+        builder.when_first_row().assert_zero(local.forward_index);
+        builder.when_transition().assert_eq(next.forward_index, local.forward_index + AB::Expr::one());
+
+        let denominator =
+            |a: AB::Var, b: AB::Var| ext_sub(z.clone(), ext_add(lift(a.into()), ext_scale(alpha.clone(), b.into())));
+        let one = lift(AB::Expr::one());
+        let lookup_den = denominator(local.forward_index, local.reversed_index);
+        assert_ext_eq(builder, ext_times(&ext(&local.lookup_inv), &lookup_den), one.clone());
+        let value_den = denominator(local.forward_index, local.value);
+        assert_ext_eq(builder, ext_times(&ext(&local.value_inv), &value_den), one.clone());
+        let reversed_den = denominator(local.reversed_index, local.reversed_value);
+        assert_ext_eq(builder, ext_times(&ext(&local.reversed_inv), &reversed_den), one);
+
+        let (lookup_acc, next_lookup_acc) = (ext::<AB::Expr, _>(&local.lookup_acc), ext(&next.lookup_acc));
+        assert_ext_eq(&mut builder.when_first_row(), lookup_acc.clone(), ext(&local.lookup_inv));
+        let lookup_step = ext_add(lookup_acc.clone(), ext(&next.lookup_inv));
+        assert_ext_eq(&mut builder.when_transition(), next_lookup_acc, lookup_step);
+        assert_ext_eq(&mut builder.when_last_row(), lookup_acc, table_sum);
+
+        let delta = |row: &BitReversalRow<AB::Var>| ext_sub(ext::<AB::Expr, _>(&row.value_inv), ext(&row.reversed_inv));
+        let perm_acc = ext::<AB::Expr, _>(&local.perm_acc);
+        assert_ext_eq(&mut builder.when_first_row(), perm_acc.clone(), delta(local));
+        assert_ext_eq(&mut builder.when_transition(), ext(&next.perm_acc), ext_add(perm_acc.clone(), delta(next)));
+        assert_ext_eq(&mut builder.when_last_row(), perm_acc, lift(AB::Expr::zero()));
+    }
+}
+
+struct BitReversalRow<F> {
+    pub forward_index: F,
+    pub reversed_index: F,
+    pub value: F,
+    /// `value` at row `reversed_index`
+    pub reversed_value: F,
+    // filled after the challenges are drawn
+    pub lookup_inv: [F; EXT],
+    pub value_inv: [F; EXT],
+    pub reversed_inv: [F; EXT],
+    pub lookup_acc: [F; EXT],
+    pub perm_acc: [F; EXT],
+}
+
+impl<F> Borrow<BitReversalRow<F>> for [F] {
+    fn borrow(&self) -> &BitReversalRow<F> {
+        debug_assert_eq!(self.len(), BR_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<BitReversalRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+fn rows_mut<F>(trace: &mut RowMajorMatrix<F>) -> &mut [BitReversalRow<F>] {
+    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<BitReversalRow<F>>() };
+    assert!(prefix.is_empty(), "Alignment should match");
+    assert!(suffix.is_empty(), "Alignment should match");
+    rows
+}
+
+fn bit_reverse(i: usize, log_n: usize) -> usize {
+    if log_n == 0 {
+        0
+    } else {
+        i.reverse_bits() >> (usize::BITS as usize - log_n)
+    }
+}
+
+/// The trace with the data columns filled in and the LogUp columns left zero.
+fn data_trace(values: &[Val], log_n: usize) -> RowMajorMatrix<Val> {
+    assert_eq!(values.len(), 1 << log_n, "one value per index");
+
+    let mut trace = RowMajorMatrix::new(vec![Val::zero(); values.len() * BR_ROW_WIDTH], BR_ROW_WIDTH);
+    for (i, row) in rows_mut(&mut trace).iter_mut().enumerate() {
+        let r = bit_reverse(i, log_n);
+        row.forward_index = Val::from_canonical_usize(i);
+        row.reversed_index = Val::from_canonical_usize(r);
+        row.value = values[i];
+        row.reversed_value = values[r];
+    }
+    trace
+}
+
+fn fingerprint(alpha: Challenge, a: Val, b: Val) -> Challenge {
+    alpha * b + a
+}
+
+/// Fills the LogUp columns for the challenges `alpha` and `z`.
+fn fill_logup(trace: &mut RowMajorMatrix<Val>, alpha: Challenge, z: Challenge) {
+    let (mut lookup_acc, mut perm_acc) = (Challenge::zero(), Challenge::zero());
+    for row in rows_mut(trace) {
+        let lookup_inv = (z - fingerprint(alpha, row.forward_index, row.reversed_index)).inverse();
+        let value_inv = (z - fingerprint(alpha, row.forward_index, row.value)).inverse();
+        let reversed_inv = (z - fingerprint(alpha, row.reversed_index, row.reversed_value)).inverse();
+        lookup_acc += lookup_inv;
+        perm_acc += value_inv - reversed_inv;
+        row.lookup_inv.copy_from_slice(lookup_inv.as_base_slice());
+        row.value_inv.copy_from_slice(value_inv.as_base_slice());
+        row.reversed_inv.copy_from_slice(reversed_inv.as_base_slice());
+        row.lookup_acc.copy_from_slice(lookup_acc.as_base_slice());
+        row.perm_acc.copy_from_slice(perm_acc.as_base_slice());
+    }
+}
+
+/// The bit-reversal table's side of the lookup, as the verifier computes it.
+fn table_sum(log_n: usize, alpha: Challenge, z: Challenge) -> Challenge {
+    (0..1 << log_n)
+        .map(|i| {
+            let (i_f, r_f) = (Val::from_canonical_usize(i), Val::from_canonical_usize(bit_reverse(i, log_n)));
+            (z - fingerprint(alpha, i_f, r_f)).inverse()
+        })
+        .sum()
+}
+
+impl<const LOG_N: usize> TwoRoundAir for BitReversalAir<LOG_N> {
+    fn committed_width(&self) -> usize {
+        DATA_WIDTH
+    }
+
+    fn num_challenges(&self) -> usize {
+        2
+    }
+
+    fn num_public_values(&self) -> usize {
+        EXT
+    }
+
+    fn complete_trace(&self, trace: &mut RowMajorMatrix<Val>, challenges: &[Challenge]) -> Vec<Val> {
+        let (alpha, z) = (challenges[0], challenges[1]);
+        fill_logup(trace, alpha, z);
+        ext_values(&[table_sum(LOG_N, alpha, z)])
+    }
+}
+
+fn prove_bit_reversal(config: &MyConfig, perm: &Perm, values: &[Val]) -> TwoRoundProof {
+    prove_two_round(config, perm, &BitReversalAir::<LOG_N> {}, data_trace(values, LOG_N), &[])
+}
+
+fn verify_bit_reversal(config: &MyConfig, perm: &Perm, proof: &TwoRoundProof) -> Result<(), CookError> {
+    let challenges = verify_two_round(config, perm, &BitReversalAir::<LOG_N> {}, &[], proof)?;
+    if proof.public_values[2 * EXT..] != ext_values(&[table_sum(LOG_N, challenges[0], challenges[1])]) {
+        return Err(CookError::PublicValues("the table sum is not the bit-reversal table's".to_string()));
+    }
+    Ok(())
+}
+
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+    let mut rng = thread_rng();
+    let values = (0..1 << LOG_N).map(|_| rng.gen()).collect::<Vec<Val>>();
+
+    let proof = prove_bit_reversal(&config, &perm, &values);
+    verify_bit_reversal(&config, &perm, &proof).unwrap();
+
+    println!("proven: a column of {} values and its bit-reversed order", values.len());
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky3_cook::{assert_constraints_fail, assert_constraints_ok};
+
+    use super::*;
+
+    const TEST_LOG_N: usize = 3;
+    const REVERSED_INDEX_COL: usize = 1;
+    const REVERSED_VALUE_COL: usize = 3;
+
+    fn values() -> Vec<Val> {
+        (0..1 << TEST_LOG_N).map(|i| Val::from_canonical_u32(100 + i)).collect()
+    }
+
+    fn pis_and_fill(trace: &mut RowMajorMatrix<Val>) -> Vec<Val> {
+        let challenge = |seed: usize| Challenge::from_base_fn(|i| Val::from_canonical_usize(seed + i));
+        let (alpha, z) = (challenge(123_456), challenge(987_654_321));
+        fill_logup(trace, alpha, z);
+        ext_values(&[alpha, z, table_sum(TEST_LOG_N, alpha, z)])
+    }
+
+    #[test]
+    fn test_bit_reverse() {
+        assert_eq!((0..8).map(|i| bit_reverse(i, 3)).collect::<Vec<_>>(), vec![0, 4, 2, 6, 1, 5, 3, 7]);
+        assert_eq!(bit_reverse(1, 10), 512);
+    }
+
+    #[test]
+    fn test_bit_reversal_passes() {
+        let mut trace = data_trace(&values(), TEST_LOG_N);
+        let pis = pis_and_fill(&mut trace);
+        assert_constraints_ok!(&BitReversalAir::<TEST_LOG_N> {}, &trace, &pis);
+        // row 1 holds value 4
+        assert_eq!(trace.row_slice(1)[REVERSED_VALUE_COL], Val::from_canonical_u32(104));
+    }
+
+    #[test]
+    fn test_wrong_reversed_index_fails() {
+        // swapping two rows' reversed index and value keeps the permutation but leaves the table
+        let mut trace = data_trace(&values(), TEST_LOG_N);
+        for col in [REVERSED_INDEX_COL, REVERSED_VALUE_COL] {
+            let (a, b) = (trace.row_slice(1)[col], trace.row_slice(2)[col]);
+            trace.row_mut(1)[col] = b;
+            trace.row_mut(2)[col] = a;
+        }
+        let pis = pis_and_fill(&mut trace);
+        assert_constraints_fail!(&BitReversalAir::<TEST_LOG_N> {}, &trace, &pis, 7);
+    }
+
+    #[test]
+    fn test_wrong_reversed_value_fails() {
+        // the indices are right, but two values are out of order
+        let mut trace = data_trace(&values(), TEST_LOG_N);
+        let (a, b) = (trace.row_slice(1)[REVERSED_VALUE_COL], trace.row_slice(2)[REVERSED_VALUE_COL]);
+        trace.row_mut(1)[REVERSED_VALUE_COL] = b;
+        trace.row_mut(2)[REVERSED_VALUE_COL] = a;
+        let pis = pis_and_fill(&mut trace);
+        assert_constraints_fail!(&BitReversalAir::<TEST_LOG_N> {}, &trace, &pis, 7);
+    }
+
+    #[test]
+    fn test_bit_reversal_round_trip() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let mut rng = thread_rng();
+        let values = (0..1 << LOG_N).map(|_| rng.gen()).collect::<Vec<Val>>();
+
+        let mut proof = prove_bit_reversal(&config, &perm, &values);
+        verify_bit_reversal(&config, &perm, &proof).unwrap();
+
+        // a reordered column other than the committed one shows up at `zeta`
+        proof.committed_opening[REVERSED_VALUE_COL] += Challenge::one();
+        assert!(matches!(verify_bit_reversal(&config, &perm, &proof), Err(CookError::PublicValues(_))));
+
+        proof = prove_bit_reversal(&config, &perm, &values);
+        proof.public_values[2 * EXT] += Val::one();
+        assert!(verify_bit_reversal(&config, &perm, &proof).is_err());
+    }
+}
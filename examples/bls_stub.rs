@@ -0,0 +1,285 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_field::{AbstractField, Field};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::{prove, verify};
+use plonky3_cook::config::{default_config, random_perm, Challenger, Val};
+use rand::{thread_rng, Rng};
+use tracing_forest::{util::LevelFilter, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+// The shape of a BLS signature check, not the real thing. BLS verifies `e(sig, G) == e(H(msg), pk)`; each
+// pairing is a Miller loop followed by a final exponentiation, and the check is usually done as one
+// product of two Miller loops and a single final exponentiation. This example lays out one Miller loop.
+//
+// The Miller loop walks the bits of the Ate loop parameter from the top. Every step doubles a point `T`
+// (starting at `Q`) and multiplies the accumulator `f` by the tangent line at `T` evaluated at `P`,
+//   f <- f^2 * l_T(P),  T <- 2T
+// and on a set bit also adds `Q`, multiplying by the chord through `T` and `Q`,
+//   f <- f * l_{T,Q}(P),  T <- T + Q
+// One row per step, with the slopes, the doubled point and the intermediate products as witness columns
+// so every constraint stays at degree 3. The step's bit is pinned by accumulating the bits into the loop
+// parameter.
+//
+// What's simplified: all values are single BabyBear elements, on the curve `y^2 = x^3 + b` through `Q`
+// (only `a = 0` enters the formulas), and the loop parameter is the top 16 bits of BLS12-381's
+// `|x| = 0xd201000000010000` (the other 48 bits hold a single set bit). A real circuit has `P` in G1 over Fp,
+// `Q` in G2 over Fp2, lines and `f` in Fp12, and the final exponentiation after the loop.
+//
+// Which field: BLS12-381's base field is a 381-bit prime and none of Plonky3's fields (BabyBear, KoalaBear
+// and Mersenne31 at 31 bits, Goldilocks at 64) can hold it, so every Fp element becomes a vector of limbs
+// with range-checked carries, and a product is checked as a polynomial identity over the limbs. In a 31-bit
+// field the limb products of a schoolbook multiplication must sum below the modulus, which caps limbs near
+// 12 bits: 32 limbs per Fp element, 384 per Fp12 value. Goldilocks allows limbs roughly twice as wide. No
+// Plonky3 field is native to the pairing, so either way the circuit pays for non-native arithmetic; the
+// 31-bit fields are the usual pick for prover speed, with extension-field challenges for soundness.
+
+/// the top 16 bits of BLS12-381's `|x|`
+const LOOP_PARAM: u32 = 0xd201;
+const LOOP_BITS: usize = 16;
+
+const BLS_ROW_WIDTH: usize = 12;
+const BLS_HEIGHT: usize = LOOP_BITS;
+
+/// `[x_P, y_P, x_Q, y_Q, f]`
+const NUM_PUBLIC_VALUES: usize = 5;
+
+struct MillerLoopAir {}
+
+impl<F> BaseAir<F> for MillerLoopAir {
+    fn width(&self) -> usize {
+        BLS_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues> Air<AB> for MillerLoopAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &MillerRow<AB::Var> = (*local).borrow();
+        let next: &MillerRow<AB::Var> = (*next).borrow();
+
+        let pis = builder.public_values().iter().map(|&v| v.into()).collect::<Vec<AB::Expr>>();
+        let (x_p, y_p, x_q, y_q, f_out) = (&pis[0], &pis[1], &pis[2], &pis[3], &pis[4]);
+
+        // the loop parameter, most significant bit first; the leading 1 is the starting point
+        builder.assert_bool(local.bit);
+        builder.when_first_row().assert_one(local.bit);
+        builder.when_first_row().assert_one(local.acc);
+        builder.when_transition().assert_eq(next.acc, local.acc * AB::Expr::two() + next.bit);
+        builder.when_last_row().assert_eq(local.acc, AB::Expr::from_canonical_u32(LOOP_PARAM));
+
+        // T = Q, f = 1
+        builder.when_first_row().assert_eq(local.x, x_q.clone());
+        builder.when_first_row().assert_eq(local.y, y_q.clone());
+        builder.when_first_row().assert_one(local.f);
+
+        let mut step = builder.when_transition();
+
+        // doubling: the tangent at T, evaluated at P
+        let three_x_sq = local.x * local.x * AB::Expr::from_canonical_u32(3);
+        step.assert_eq(next.lambda_dbl * local.y * AB::Expr::two(), three_x_sq);
+        step.assert_eq(next.x_dbl, next.lambda_dbl * next.lambda_dbl - local.x * AB::Expr::two());
+        step.assert_eq(next.y_dbl, next.lambda_dbl * (local.x - next.x_dbl) - local.y);
+        let tangent = y_p.clone() - local.y - next.lambda_dbl * (x_p.clone() - local.x);
+        step.assert_eq(next.f_sq, local.f * local.f);
+        step.assert_eq(next.f_dbl, next.f_sq * tangent);
+
+        // addition: the chord through 2T and Q, evaluated at P
+        step.assert_eq(next.chord, y_p.clone() - next.y_dbl - next.lambda_add * (x_p.clone() - next.x_dbl));
+        let mut add = step.when(next.bit);
+        add.assert_eq(next.lambda_add * (x_q.clone() - next.x_dbl), y_q.clone() - next.y_dbl);
+        add.assert_eq(next.x, next.lambda_add * next.lambda_add - next.x_dbl - x_q.clone());
+        add.assert_eq(next.y, next.lambda_add * (next.x_dbl - next.x) - next.y_dbl);
+        add.assert_eq(next.f, next.f_dbl * next.chord);
+
+        let mut no_add = step.when(AB::Expr::one() - next.bit);
+        no_add.assert_eq(next.x, next.x_dbl);
+        no_add.assert_eq(next.y, next.y_dbl);
+        no_add.assert_eq(next.f, next.f_dbl);
+
+        builder.when_last_row().assert_eq(local.f, f_out.clone());
+    }
+}
+
+struct MillerRow<F> {
+    pub bit: F,
+    /// the loop parameter's bits so far
+    pub acc: F,
+    // the state after this row's step
+    pub x: F,
+    pub y: F,
+    pub f: F,
+    // this row's step, applied to the previous row's state
+    pub lambda_dbl: F,
+    pub x_dbl: F,
+    pub y_dbl: F,
+    pub f_sq: F,
+    pub f_dbl: F,
+    pub lambda_add: F,
+    pub chord: F,
+}
+
+impl<F> Borrow<MillerRow<F>> for [F] {
+    fn borrow(&self) -> &MillerRow<F> {
+        debug_assert_eq!(self.len(), BLS_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<MillerRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+/// `l(P)` for the line of slope `lambda` through `(x, y)`.
+fn line_at(p: (Val, Val), (x, y): (Val, Val), lambda: Val) -> Val {
+    p.1 - y - lambda * (p.0 - x)
+}
+
+/// The Miller loop computed directly, as a reference for the trace.
+fn miller_loop(p: (Val, Val), q: (Val, Val)) -> Val {
+    let (mut t, mut f) = (q, Val::one());
+    for i in (0..LOOP_BITS - 1).rev() {
+        let lambda = t.0.square() * Val::from_canonical_u32(3) / t.1.double();
+        f = f.square() * line_at(p, t, lambda);
+        let x = lambda.square() - t.0.double();
+        t = (x, lambda * (t.0 - x) - t.1);
+
+        if (LOOP_PARAM >> i) & 1 == 1 {
+            let lambda = (q.1 - t.1) / (q.0 - t.0);
+            f *= line_at(p, t, lambda);
+            let x = lambda.square() - t.0 - q.0;
+            t = (x, lambda * (t.0 - x) - t.1);
+        }
+    }
+    f
+}
+
+/// Returns the trace together with `[x_P, y_P, x_Q, y_Q, f]`.
+fn generate_trace(p: (Val, Val), q: (Val, Val)) -> (RowMajorMatrix<Val>, Vec<Val>) {
+    let mut trace = RowMajorMatrix::new(vec![Val::zero(); BLS_HEIGHT * BLS_ROW_WIDTH], BLS_ROW_WIDTH);
+
+    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<MillerRow<Val>>() };
+    assert!(prefix.is_empty(), "Alignment should match");
+    assert!(suffix.is_empty(), "Alignment should match");
+    assert_eq!(rows.len(), BLS_HEIGHT);
+
+    rows[0].bit = Val::one();
+    rows[0].acc = Val::one();
+    (rows[0].x, rows[0].y, rows[0].f) = (q.0, q.1, Val::one());
+
+    for r in 1..BLS_HEIGHT {
+        let (prev_x, prev_y, prev_f, prev_acc) = (rows[r - 1].x, rows[r - 1].y, rows[r - 1].f, rows[r - 1].acc);
+        let row = &mut rows[r];
+        let bit = (LOOP_PARAM >> (LOOP_BITS - 1 - r)) & 1 == 1;
+        row.bit = Val::from_bool(bit);
+        row.acc = prev_acc.double() + row.bit;
+
+        row.lambda_dbl = prev_x.square() * Val::from_canonical_u32(3) / prev_y.double();
+        row.x_dbl = row.lambda_dbl.square() - prev_x.double();
+        row.y_dbl = row.lambda_dbl * (prev_x - row.x_dbl) - prev_y;
+        row.f_sq = prev_f.square();
+        row.f_dbl = row.f_sq * line_at(p, (prev_x, prev_y), row.lambda_dbl);
+
+        if bit {
+            row.lambda_add = (q.1 - row.y_dbl) / (q.0 - row.x_dbl);
+            row.x = row.lambda_add.square() - row.x_dbl - q.0;
+            row.y = row.lambda_add * (row.x_dbl - row.x) - row.y_dbl;
+        } else {
+            (row.x, row.y) = (row.x_dbl, row.y_dbl);
+        }
+        row.chord = line_at(p, (row.x_dbl, row.y_dbl), row.lambda_add);
+        row.f = if bit { row.f_dbl * row.chord } else { row.f_dbl };
+    }
+
+    let f = rows[BLS_HEIGHT - 1].f;
+    (trace, vec![p.0, p.1, q.0, q.1, f])
+}
+
+fn random_point() -> (Val, Val) {
+    let mut rng = thread_rng();
+    (rng.gen(), rng.gen())
+}
+
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+
+    let (p, q) = (random_point(), random_point());
+    let (trace, public_values) = generate_trace(p, q);
+    assert_eq!(public_values.len(), NUM_PUBLIC_VALUES);
+
+    let mut p_challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &MillerLoopAir {}, &mut p_challenger, trace, &public_values);
+    let mut v_challenger = Challenger::new(perm);
+    verify(&config, &MillerLoopAir {}, &mut v_challenger, &proof, &public_values).unwrap();
+
+    println!("proven: the toy Miller loop of P = {:?}, Q = {:?} is {}", p, q, public_values[4]);
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky3_cook::{assert_constraints_fail, assert_constraints_ok};
+
+    use super::*;
+
+    const LAMBDA_DBL_COL: usize = 5;
+
+    #[test]
+    fn test_trace_matches_reference_loop() {
+        let (p, q) = (random_point(), random_point());
+        let (trace, public_values) = generate_trace(p, q);
+        assert_constraints_ok!(&MillerLoopAir {}, &trace, &public_values);
+        assert_eq!(public_values[4], miller_loop(p, q));
+    }
+
+    #[test]
+    fn test_wrong_slope_fails() {
+        let (mut trace, public_values) = generate_trace(random_point(), random_point());
+        trace.row_mut(4)[LAMBDA_DBL_COL] += Val::one();
+        // the step into row 4 is checked on row 3's window
+        assert_constraints_fail!(&MillerLoopAir {}, &trace, &public_values, 3);
+    }
+
+    #[test]
+    fn test_wrong_result_fails() {
+        let (trace, mut public_values) = generate_trace(random_point(), random_point());
+        public_values[4] += Val::one();
+        assert_constraints_fail!(&MillerLoopAir {}, &trace, &public_values, BLS_HEIGHT - 1);
+    }
+
+    #[test]
+    fn test_other_loop_parameter_fails() {
+        let (mut trace, public_values) = generate_trace(random_point(), random_point());
+        // flip the last bit; the accumulator still follows the bits, but ends off the parameter
+        let last = BLS_HEIGHT - 1;
+        let prev_acc = trace.row_slice(last - 1)[1];
+        let row = trace.row_mut(last);
+        row[0] = Val::one() - row[0];
+        row[1] = prev_acc.double() + row[0];
+        assert_constraints_fail!(&MillerLoopAir {}, &trace, &public_values, last - 1);
+    }
+
+    #[test]
+    fn test_bls_stub_round_trip() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let (trace, public_values) = generate_trace(random_point(), random_point());
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &MillerLoopAir {}, &mut p_challenger, trace, &public_values);
+        let mut v_challenger = Challenger::new(perm);
+        verify(&config, &MillerLoopAir {}, &mut v_challenger, &proof, &public_values).unwrap();
+    }
+}
@@ -0,0 +1,306 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_field::{AbstractExtensionField, AbstractField, Field, PrimeField32};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use plonky3_cook::config::{default_config, random_perm, Challenge, MyConfig, Perm, Val};
+use plonky3_cook::error::CookError;
+use plonky3_cook::gadgets::less_than::{assert_lt, lt_witness};
+use plonky3_cook::gadgets::optional::assert_optional_columns;
+use plonky3_cook::lookups::{
+    assert_ext_eq, ext, ext_add, ext_scale, ext_sub, ext_times, ext_values, lift, prove_two_round, verify_two_round,
+    TwoRoundAir, TwoRoundProof, EXT,
+};
+use rand::{thread_rng, Rng};
+use tracing_forest::{util::LevelFilter, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+// Causal consistency of an event stream: no effect precedes its cause.
+//
+// Row `i` is event `i` (`event_id`), with strictly increasing timestamps. An effect row (`is_effect == 1`)
+// names its cause by `(cause_id, cause_ts)` and must have `cause_ts < timestamp`. What makes that claim
+// mean anything is a lookup into the stream itself: `(cause_id, cause_ts)` must be the `(event_id,
+// timestamp)` of some row. The lookup is a LogUp sum over the tuple fingerprint `id + alpha * ts`:
+//   sum over rows of  multiplicity / (z - (event_id + alpha * timestamp))
+//   == sum over effect rows of  1 / (z - (cause_id + alpha * cause_ts))
+// accumulated in `acc`, which must end at zero. `multiplicity` counts how many effects cite the row.
+//
+// The event columns are the first round of a `lookups::TwoRoundAir`: they are committed, `alpha` and `z`
+// are drawn from `Challenge` after that commitment, and the commitment is opened at the proof's
+// out-of-domain point `zeta` and compared with the proven trace's opening there. So the events the lookup
+// runs over are the ones fixed before the challenges were known, and a prover can't pick them to cancel
+// the sum. The inverses and the accumulator are extension elements, `EXT` columns each.
+
+const TS_BITS: usize = 16;
+
+// the event columns, committed before the challenges are drawn
+const EVENT_WIDTH: usize = 5 + 2 * TS_BITS + 1;
+const CS_ROW_WIDTH: usize = EVENT_WIDTH + 3 * EXT;
+
+struct CausalStreamAir {}
+
+impl<F> BaseAir<F> for CausalStreamAir {
+    fn width(&self) -> usize {
+        CS_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues<F = Val>> Air<AB> for CausalStreamAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &CausalRow<AB::Var> = (*local).borrow();
+        let next: &CausalRow<AB::Var> = (*next).borrow();
+
+        let pis = builder.public_values();
+        let (alpha, z): ([AB::Expr; EXT], [AB::Expr; EXT]) = (ext(&pis[..EXT]), ext(&pis[EXT..2 * EXT]));
+
+        builder.when_first_row().assert_zero(local.event_id);
+        builder.when_transition().assert_eq(next.event_id, local.event_id + AB::Expr::one());
+        assert_lt(&mut builder.when_transition(), local.timestamp, next.timestamp, &local.step_bits);
+
+        let mut cause_columns = vec![local.cause_id, local.cause_ts];
+        cause_columns.extend(local.cause_lt_bits);
+        assert_optional_columns(builder, local.is_effect, &cause_columns);
+        assert_lt(&mut builder.when(local.is_effect), local.cause_ts, local.timestamp, &local.cause_lt_bits);
+
+        // the lookup
+        let denominator = |id: AB::Var, ts: AB::Var| {
+            ext_sub(z.clone(), ext_add(lift(id.into()), ext_scale(alpha.clone(), ts.into())))
+        };
+        let one = lift(AB::Expr::one());
+        let table = denominator(local.event_id, local.timestamp);
+        assert_ext_eq(builder, ext_times(&ext(&local.table_inv), &table), one.clone());
+        let query = denominator(local.cause_id, local.cause_ts);
+        assert_ext_eq(builder, ext_times(&ext(&local.query_inv), &query), one);
+
+        let delta = |row: &CausalRow<AB::Var>| {
+            ext_sub(
+                ext_scale(ext::<AB::Expr, _>(&row.table_inv), row.multiplicity.into()),
+                ext_scale(ext(&row.query_inv), row.is_effect.into()),
+            )
+        };
+        let acc = ext::<AB::Expr, _>(&local.acc);
+        assert_ext_eq(&mut builder.when_first_row(), acc.clone(), delta(local));
+        assert_ext_eq(&mut builder.when_transition(), ext(&next.acc), ext_add(acc.clone(), delta(next)));
+        assert_ext_eq(&mut builder.when_last_row(), acc, lift(AB::Expr::zero()));
+    }
+}
+
+struct CausalRow<F> {
+    pub event_id: F,
+    pub timestamp: F,
+    pub is_effect: F,
+    pub cause_id: F,
+    pub cause_ts: F,
+    /// bits of `timestamp - cause_ts - 1`
+    pub cause_lt_bits: [F; TS_BITS],
+    /// bits of `next.timestamp - timestamp - 1`
+    pub step_bits: [F; TS_BITS],
+    pub multiplicity: F,
+    // filled after the challenges are drawn
+    pub table_inv: [F; EXT],
+    pub query_inv: [F; EXT],
+    pub acc: [F; EXT],
+}
+
+impl<F> Borrow<CausalRow<F>> for [F] {
+    fn borrow(&self) -> &CausalRow<F> {
+        debug_assert_eq!(self.len(), CS_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<CausalRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Event {
+    timestamp: u32,
+    /// `(cause_id, cause_ts)` as claimed by an effect
+    cause: Option<(u32, u32)>,
+}
+
+/// A random stream of `n` events where each effect cites an earlier event.
+fn random_events(n: usize) -> Vec<Event> {
+    let mut rng = thread_rng();
+    let mut events: Vec<Event> = Vec::with_capacity(n);
+    let mut timestamp = 0;
+    for i in 0..n {
+        timestamp += rng.gen_range(1..100);
+        let cause = (i > 0 && rng.gen_bool(0.5)).then(|| {
+            let cause_id = rng.gen_range(0..i);
+            (cause_id as u32, events[cause_id].timestamp)
+        });
+        events.push(Event { timestamp, cause });
+    }
+    events
+}
+
+fn rows_mut<F>(trace: &mut RowMajorMatrix<F>) -> &mut [CausalRow<F>] {
+    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<CausalRow<F>>() };
+    assert!(prefix.is_empty(), "Alignment should match");
+    assert!(suffix.is_empty(), "Alignment should match");
+    rows
+}
+
+/// The trace with the event columns filled in and the lookup columns left zero.
+fn event_trace<F: PrimeField32>(events: &[Event]) -> RowMajorMatrix<F> {
+    let n = events.len();
+    assert!(n.is_power_of_two(), "trace height must be a power of two");
+
+    let mut trace = RowMajorMatrix::new(vec![F::zero(); n * CS_ROW_WIDTH], CS_ROW_WIDTH);
+    let rows = rows_mut(&mut trace);
+
+    let mut multiplicities = vec![0u32; n];
+    for event in events {
+        if let Some((cause_id, _)) = event.cause {
+            multiplicities[cause_id as usize] += 1;
+        }
+    }
+
+    for (i, event) in events.iter().enumerate() {
+        let row = &mut rows[i];
+        row.event_id = F::from_canonical_usize(i);
+        row.timestamp = F::from_canonical_u32(event.timestamp);
+        row.multiplicity = F::from_canonical_u32(multiplicities[i]);
+
+        if let Some((cause_id, cause_ts)) = event.cause {
+            row.is_effect = F::one();
+            row.cause_id = F::from_canonical_u32(cause_id);
+            row.cause_ts = F::from_canonical_u32(cause_ts);
+            // an effect claiming a later cause has no valid witness; its bits stay zero and the row fails
+            if cause_ts < event.timestamp {
+                let bits = lt_witness(row.cause_ts, row.timestamp, TS_BITS);
+                row.cause_lt_bits.copy_from_slice(&bits);
+            }
+        }
+        if let Some(next) = events.get(i + 1) {
+            let bits = lt_witness(row.timestamp, F::from_canonical_u32(next.timestamp), TS_BITS);
+            row.step_bits.copy_from_slice(&bits);
+        }
+    }
+
+    trace
+}
+
+/// Fills the lookup columns for the challenges `alpha` and `z`.
+fn fill_lookup(trace: &mut RowMajorMatrix<Val>, alpha: Challenge, z: Challenge) {
+    let mut acc = Challenge::zero();
+    for row in rows_mut(trace) {
+        let table_inv = (z - (alpha * row.timestamp + row.event_id)).inverse();
+        let query_inv = (z - (alpha * row.cause_ts + row.cause_id)).inverse();
+        acc += table_inv * row.multiplicity - query_inv * row.is_effect;
+        row.table_inv.copy_from_slice(table_inv.as_base_slice());
+        row.query_inv.copy_from_slice(query_inv.as_base_slice());
+        row.acc.copy_from_slice(acc.as_base_slice());
+    }
+}
+
+impl TwoRoundAir for CausalStreamAir {
+    fn committed_width(&self) -> usize {
+        EVENT_WIDTH
+    }
+
+    fn num_challenges(&self) -> usize {
+        2
+    }
+
+    fn num_public_values(&self) -> usize {
+        0
+    }
+
+    fn complete_trace(&self, trace: &mut RowMajorMatrix<Val>, challenges: &[Challenge]) -> Vec<Val> {
+        fill_lookup(trace, challenges[0], challenges[1]);
+        vec![]
+    }
+}
+
+fn prove_causal(config: &MyConfig, perm: &Perm, events: &[Event]) -> TwoRoundProof {
+    prove_two_round(config, perm, &CausalStreamAir {}, event_trace::<Val>(events), &[])
+}
+
+fn verify_causal(config: &MyConfig, perm: &Perm, proof: &TwoRoundProof) -> Result<(), CookError> {
+    verify_two_round(config, perm, &CausalStreamAir {}, &[], proof).map(|_| ())
+}
+
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+
+    let events = random_events(512);
+    let proof = prove_causal(&config, &perm, &events);
+    verify_causal(&config, &perm, &proof).unwrap();
+
+    let effects = events.iter().filter(|e| e.cause.is_some()).count();
+    println!("proven: {} events, {} effects, all after their causes", events.len(), effects);
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky3_cook::{assert_constraints_fail, assert_constraints_ok};
+
+    use super::*;
+
+    fn trace_with_challenges(events: &[Event]) -> (RowMajorMatrix<Val>, Vec<Val>) {
+        let challenge = |seed: usize| Challenge::from_base_fn(|i| Val::from_canonical_usize(seed + i));
+        let (alpha, z) = (challenge(123_456), challenge(987_654_321));
+        let mut trace = event_trace(events);
+        fill_lookup(&mut trace, alpha, z);
+        (trace, ext_values(&[alpha, z]))
+    }
+
+    #[test]
+    fn test_causal_stream_constraints() {
+        let events = random_events(1 << 6);
+        let (trace, pis) = trace_with_challenges(&events);
+        assert_constraints_ok!(&CausalStreamAir {}, &trace, &pis);
+    }
+
+    #[test]
+    fn test_cause_not_in_stream_fails_lookup() {
+        // the effect is still after its claimed cause, but no event has that timestamp
+        let mut events = random_events(1 << 6);
+        let i = (1..events.len())
+            .find(|&i| matches!(events[i].cause, Some((_, cause_ts)) if cause_ts + 1 < events[i].timestamp))
+            .unwrap();
+        let (cause_id, cause_ts) = events[i].cause.unwrap();
+        events[i].cause = Some((cause_id, cause_ts + 1));
+
+        let (trace, pis) = trace_with_challenges(&events);
+        assert_constraints_fail!(&CausalStreamAir {}, &trace, &pis, (1 << 6) - 1);
+    }
+
+    #[test]
+    fn test_effect_before_cause_fails() {
+        let mut events = random_events(1 << 6);
+        events[3].cause = Some((10, events[10].timestamp));
+
+        let (trace, pis) = trace_with_challenges(&events);
+        assert_constraints_fail!(&CausalStreamAir {}, &trace, &pis, 3);
+    }
+
+    #[test]
+    fn test_causal_round_trip() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+
+        let mut proof = prove_causal(&config, &perm, &random_events(64));
+        verify_causal(&config, &perm, &proof).unwrap();
+
+        // events other than the committed ones show up at `zeta`
+        proof.committed_opening[1] += Challenge::one();
+        assert!(matches!(verify_causal(&config, &perm, &proof), Err(CookError::PublicValues(_))));
+    }
+}
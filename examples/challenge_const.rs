@@ -0,0 +1,211 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_challenger::{CanObserve, CanSample};
+use p3_commit::Pcs as _;
+use p3_field::{AbstractField, Field};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_symmetric::Hash;
+use p3_uni_stark::{prove, verify, Proof, StarkGenericConfig};
+use plonky3_cook::config::{default_config, random_perm, Challenger, MyConfig, Perm, Val};
+use rand::{distributions::{Distribution, Standard}, thread_rng, Rng};
+use tracing_forest::{util::LevelFilter, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+// A column holding one verifier challenge `alpha` on every row, used to evaluate the `value` column as a
+// polynomial at `alpha` (Horner: `acc' = acc * alpha + value`), the usual random-linear-combination check.
+//
+// `alpha` has to be drawn after the values are committed, or the prover could pick values around it.
+// uni-stark at this revision has no second trace phase: its main trace is committed and immediately followed
+// by the constraint challenge, with no hook for the AIR to draw randomness in between. So the rounds are
+// done by hand around it:
+//   1. commit to the `value` column alone, and observe that commitment
+//   2. sample `alpha` from the challenger, and build the full trace with it
+//   3. `prove` on the same challenger, with `[alpha, evaluation]` as public values
+// The verifier replays 1 and 2 from the carried commitment and checks `alpha` is the public value.
+//
+// Caveat: nothing here ties the phase-1 commitment to the `value` column of the proven trace, which uni-stark
+// commits again on its own. A sound version needs a real multi-phase prover; this example shows the
+// transcript order and the constant-column constraints, not that binding.
+
+const CC_ROW_WIDTH: usize = 3;
+
+struct ChallengeConstAir {}
+
+impl<F> BaseAir<F> for ChallengeConstAir {
+    fn width(&self) -> usize {
+        CC_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues> Air<AB> for ChallengeConstAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &ChallengeConstRow<AB::Var> = (*local).borrow();
+        let next: &ChallengeConstRow<AB::Var> = (*next).borrow();
+
+        let pis = builder.public_values();
+        let (alpha, evaluation): (AB::Expr, AB::Expr) = (pis[0].into(), pis[1].into());
+
+        // the constant column
+        builder.when_first_row().assert_eq(local.challenge, alpha);
+        builder.when_transition().assert_eq(next.challenge, local.challenge);
+
+        builder.when_first_row().assert_eq(local.acc, local.value);
+        builder.when_transition().assert_eq(next.acc, local.acc * local.challenge + next.value);
+        builder.when_last_row().assert_eq(local.acc, evaluation);
+    }
+}
+
+struct ChallengeConstRow<F> {
+    pub value: F,
+    pub challenge: F,
+    pub acc: F,
+}
+
+impl<F> Borrow<ChallengeConstRow<F>> for [F] {
+    fn borrow(&self) -> &ChallengeConstRow<F> {
+        debug_assert_eq!(self.len(), CC_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<ChallengeConstRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+/// Evaluates the polynomial with coefficients `values` (highest degree first) at `alpha`.
+fn horner<F: Field>(values: &[F], alpha: F) -> F {
+    values.iter().fold(F::zero(), |acc, &v| acc * alpha + v)
+}
+
+/// Returns the trace together with the evaluation at `alpha`.
+fn generate_trace<F: Field>(values: &[F], alpha: F) -> (RowMajorMatrix<F>, F) {
+    let n = values.len();
+    assert!(n.is_power_of_two(), "trace height must be a power of two");
+
+    let mut trace = RowMajorMatrix::new(vec![F::zero(); n * CC_ROW_WIDTH], CC_ROW_WIDTH);
+
+    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<ChallengeConstRow<F>>() };
+    assert!(prefix.is_empty(), "Alignment should match");
+    assert!(suffix.is_empty(), "Alignment should match");
+    assert_eq!(rows.len(), n);
+
+    let mut acc = F::zero();
+    for (row, &value) in rows.iter_mut().zip(values) {
+        acc = acc * alpha + value;
+        *row = ChallengeConstRow { value, challenge: alpha, acc };
+    }
+
+    (trace, acc)
+}
+
+struct ChallengeConstProof {
+    values_commitment: Hash<Val, Val, 8>,
+    public_values: Vec<Val>,
+    proof: Proof<MyConfig>,
+}
+
+fn prove_with_challenge(config: &MyConfig, perm: &Perm, values: &[Val]) -> ChallengeConstProof {
+    // round 1: commit to the values alone
+    let pcs = config.pcs();
+    let domain = pcs.natural_domain_for_degree(values.len());
+    let (values_commitment, _) = pcs.commit(vec![(domain, RowMajorMatrix::new(values.to_vec(), 1))]);
+
+    let mut challenger = Challenger::new(perm.clone());
+    challenger.observe(values_commitment.clone());
+    let alpha: Val = challenger.sample();
+
+    // round 2: everything that depends on alpha
+    let (trace, evaluation) = generate_trace(values, alpha);
+    let public_values = vec![alpha, evaluation];
+    let proof = prove(config, &ChallengeConstAir {}, &mut challenger, trace, &public_values);
+
+    ChallengeConstProof { values_commitment, public_values, proof }
+}
+
+fn verify_with_challenge(config: &MyConfig, perm: &Perm, proof: &ChallengeConstProof) -> Result<(), String> {
+    let mut challenger = Challenger::new(perm.clone());
+    challenger.observe(proof.values_commitment.clone());
+    let alpha: Val = challenger.sample();
+    if proof.public_values[0] != alpha {
+        return Err(format!("alpha {} was not drawn from the transcript ({})", proof.public_values[0], alpha));
+    }
+
+    verify(config, &ChallengeConstAir {}, &mut challenger, &proof.proof, &proof.public_values)
+        .map_err(|e| format!("{:?}", e))
+}
+
+fn random_values<F>(n: usize) -> Vec<F> where Standard: Distribution<F> {
+    let mut rng = thread_rng();
+    (0..n).map(|_| rng.gen()).collect()
+}
+
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+
+    let values = random_values::<Val>(1024);
+    let proof = prove_with_challenge(&config, &perm, &values);
+    verify_with_challenge(&config, &perm, &proof).unwrap();
+
+    println!("proven: values(alpha = {}) = {}", proof.public_values[0], proof.public_values[1]);
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky3_cook::{assert_constraints_fail, assert_constraints_ok};
+
+    use super::*;
+
+    #[test]
+    fn test_challenge_column_constraints() {
+        let values = random_values::<Val>(1 << 8);
+        let alpha = Val::from_canonical_u32(7);
+        let (mut trace, evaluation) = generate_trace(&values, alpha);
+        assert_eq!(evaluation, horner(&values, alpha));
+        assert_constraints_ok!(&ChallengeConstAir {}, &trace, &[alpha, evaluation]);
+
+        // the challenge may not change between rows
+        trace.row_mut(9)[1] += Val::one();
+        assert_constraints_fail!(&ChallengeConstAir {}, &trace, &[alpha, evaluation], 8);
+    }
+
+    #[test]
+    fn test_challenge_round_trip() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+
+        let values = random_values::<Val>(64);
+        let proof = prove_with_challenge(&config, &perm, &values);
+        assert_eq!(proof.public_values[1], horner(&values, proof.public_values[0]));
+        verify_with_challenge(&config, &perm, &proof).unwrap();
+    }
+
+    #[test]
+    fn test_alpha_must_come_from_the_transcript() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+
+        // a prover choosing its own alpha produces a valid uni-stark proof, but not a valid transcript
+        let values = random_values::<Val>(64);
+        let mut proof = prove_with_challenge(&config, &perm, &values);
+        let alpha = proof.public_values[0] + Val::one();
+        let (trace, evaluation) = generate_trace(&values, alpha);
+        proof.public_values = vec![alpha, evaluation];
+        proof.proof = prove(&config, &ChallengeConstAir {}, &mut Challenger::new(perm.clone()), trace, &proof.public_values);
+
+        assert!(verify_with_challenge(&config, &perm, &proof).is_err());
+    }
+}
@@ -0,0 +1,206 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_field::AbstractField;
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_symmetric::Permutation;
+use p3_uni_stark::{prove, verify};
+use plonky3_cook::config::{default_config, random_perm, Challenger, Val};
+use plonky3_cook::poseidon2_air::{
+    eval_permutation, generate_permutation, PermutationCols, Poseidon2Constants, PERMUTATION_WIDTH, WIDTH,
+};
+use rand::{thread_rng, Rng};
+use tracing_forest::{util::LevelFilter, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+// A running checksum over the `input` column.
+//
+// Each row absorbs its input with one BabyBear Poseidon2 permutation from `poseidon2_air`:
+//   checksum' = Poseidon2([checksum, input, 0, ...])[..8]
+// starting from zero, so the checksum is a Merkle-Damgård style hash of the inputs in order. The checksum
+// after the last row is the public value, eight field elements. The permutation's rounds are laid out on
+// the row next to the input, keeping every constraint at degree 3.
+
+const CHECKSUM_LEN: usize = 8;
+
+const CS_ROW_WIDTH: usize = 1 + PERMUTATION_WIDTH;
+
+struct ChecksumAir {
+    constants: Poseidon2Constants,
+}
+
+impl<F> BaseAir<F> for ChecksumAir {
+    fn width(&self) -> usize {
+        CS_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues<F = Val>> Air<AB> for ChecksumAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &ChecksumRow<AB::Var> = (*local).borrow();
+        let next: &ChecksumRow<AB::Var> = (*next).borrow();
+
+        let final_checksum: Vec<AB::Expr> = builder.public_values().iter().map(|&v| v.into()).collect();
+
+        let out = eval_permutation(builder, &self.constants, &local.perm);
+        let inputs = local.perm.inputs;
+        builder.assert_eq(inputs[CHECKSUM_LEN], local.input);
+        for &input in &inputs[CHECKSUM_LEN + 1..] {
+            builder.assert_zero(input);
+        }
+
+        for i in 0..CHECKSUM_LEN {
+            builder.when_first_row().assert_zero(inputs[i]);
+            builder.when_transition().assert_eq(next.perm.inputs[i], out[i].clone());
+            builder.when_last_row().assert_eq(out[i].clone(), final_checksum[i].clone());
+        }
+    }
+}
+
+struct ChecksumRow<F> {
+    pub input: F,
+    /// `[checksum, input, 0, ..., 0]`
+    pub perm: PermutationCols<F>,
+}
+
+impl<F> Borrow<ChecksumRow<F>> for [F] {
+    fn borrow(&self) -> &ChecksumRow<F> {
+        debug_assert_eq!(self.len(), CS_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<ChecksumRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+fn absorb_inputs(checksum: [Val; CHECKSUM_LEN], input: Val) -> [Val; WIDTH] {
+    let mut inputs = [Val::zero(); WIDTH];
+    inputs[..CHECKSUM_LEN].copy_from_slice(&checksum);
+    inputs[CHECKSUM_LEN] = input;
+    inputs
+}
+
+/// Computes the checksum of `inputs` directly, without building a trace.
+fn checksum_of(c: &Poseidon2Constants, inputs: &[Val]) -> [Val; CHECKSUM_LEN] {
+    let perm = c.perm();
+    inputs.iter().fold([Val::zero(); CHECKSUM_LEN], |checksum, &input| {
+        perm.permute(absorb_inputs(checksum, input))[..CHECKSUM_LEN].try_into().unwrap()
+    })
+}
+
+/// Returns the trace together with the final checksum, which is the public value.
+fn generate_trace(c: &Poseidon2Constants, inputs: &[Val]) -> (RowMajorMatrix<Val>, Vec<Val>) {
+    let n = inputs.len();
+    assert!(n.is_power_of_two(), "trace height must be a power of two");
+
+    let mut trace = RowMajorMatrix::new(vec![Val::zero(); n * CS_ROW_WIDTH], CS_ROW_WIDTH);
+
+    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<ChecksumRow<Val>>() };
+    assert!(prefix.is_empty(), "Alignment should match");
+    assert!(suffix.is_empty(), "Alignment should match");
+    assert_eq!(rows.len(), n);
+
+    let mut checksum = [Val::zero(); CHECKSUM_LEN];
+    for (row, &input) in rows.iter_mut().zip(inputs) {
+        row.input = input;
+        let out = generate_permutation(c, absorb_inputs(checksum, input), &mut row.perm);
+        checksum.copy_from_slice(&out[..CHECKSUM_LEN]);
+    }
+
+    (trace, checksum.to_vec())
+}
+
+fn random_inputs(n: usize) -> Vec<Val> {
+    let mut rng = thread_rng();
+    (0..n).map(|_| rng.gen()).collect()
+}
+
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+    let air = ChecksumAir { constants: Poseidon2Constants::from_seed(0x636b736d) };
+
+    let inputs = random_inputs(1024);
+    let (trace, checksum) = generate_trace(&air.constants, &inputs);
+
+    let mut p_challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &air, &mut p_challenger, trace, &checksum);
+    let mut v_challenger = Challenger::new(perm);
+    verify(&config, &air, &mut v_challenger, &proof, &checksum).unwrap();
+
+    println!("proven checksum: {:?}", checksum);
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky3_cook::{assert_constraints_fail, assert_constraints_ok};
+
+    use super::*;
+
+    fn air() -> ChecksumAir {
+        ChecksumAir { constants: Poseidon2Constants::from_seed(0x636b736d) }
+    }
+
+    #[test]
+    fn test_checksum_constraints() {
+        let air = air();
+        let inputs = random_inputs(1 << 8);
+        let (mut trace, checksum) = generate_trace(&air.constants, &inputs);
+        assert_constraints_ok!(&air, &trace, &checksum);
+
+        // claiming a different final checksum breaks the last row
+        let mut wrong = checksum.clone();
+        wrong[3] += Val::one();
+        assert_constraints_fail!(&air, &trace, &wrong, (1 << 8) - 1);
+
+        // so does tampering with an input mid-way
+        trace.row_mut(7)[0] += Val::one();
+        assert_constraints_fail!(&air, &trace, &checksum, 7);
+    }
+
+    #[test]
+    fn test_proven_checksum_matches_direct_computation() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let air = air();
+
+        let inputs = random_inputs(256);
+        let (trace, checksum) = generate_trace(&air.constants, &inputs);
+        assert_eq!(checksum, checksum_of(&air.constants, &inputs));
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &air, &mut p_challenger, trace, &checksum);
+
+        let mut v_challenger = Challenger::new(perm.clone());
+        verify(&config, &air, &mut v_challenger, &proof, &checksum_of(&air.constants, &inputs).to_vec()).unwrap();
+
+        // the proof must not certify any other checksum
+        let mut wrong = checksum;
+        wrong[0] += Val::one();
+        let mut v_challenger = Challenger::new(perm);
+        assert!(verify(&config, &air, &mut v_challenger, &proof, &wrong).is_err());
+    }
+
+    #[test]
+    fn test_checksum_depends_on_order() {
+        let c = air().constants;
+        let inputs = random_inputs(8);
+        let mut swapped = inputs.clone();
+        swapped.swap(0, 1);
+
+        assert_ne!(checksum_of(&c, &inputs), checksum_of(&c, &swapped));
+    }
+}
@@ -0,0 +1,182 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_field::{AbstractField, Field};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::{prove, verify};
+use plonky3_cook::config::{default_config, random_perm, Challenger, Val};
+use plonky3_cook::gadgets::inverse_or_zero::{assert_is_zero, is_zero_witness};
+use plonky3_cook::gadgets::one_hot::select_if;
+use rand::{thread_rng, Rng};
+use tracing_forest::{util::LevelFilter, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+// A state machine whose update rule depends on the value of the row's action:
+//   action == 0:  state' = state + 1        (an idle tick)
+//   action != 0:  state' = state * action  (a scaling step)
+//
+// Both candidates are computed on every row, in `new_state_if_zero` and `new_state_if_nonzero`, and the
+// is-zero gadget turns `action` into the boolean `is_zero_indicator` (with `inv` as its witness), which
+// picks one of them with `select_if`. The public values are the initial and final states.
+
+const CS_ROW_WIDTH: usize = 6;
+
+struct ConditionalStateAir {}
+
+impl<F> BaseAir<F> for ConditionalStateAir {
+    fn width(&self) -> usize {
+        CS_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues> Air<AB> for ConditionalStateAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &ConditionalStateRow<AB::Var> = (*local).borrow();
+        let next: &ConditionalStateRow<AB::Var> = (*next).borrow();
+
+        let pis = builder.public_values();
+        let (initial, final_): (AB::Expr, AB::Expr) = (pis[0].into(), pis[1].into());
+
+        assert_is_zero(builder, local.action, local.inv, local.is_zero_indicator);
+
+        builder.assert_eq(local.new_state_if_zero, local.state + AB::Expr::one());
+        builder.assert_eq(local.new_state_if_nonzero, local.state * local.action);
+
+        let new_state: AB::Expr =
+            select_if(local.is_zero_indicator, local.new_state_if_zero, local.new_state_if_nonzero);
+        builder.when_first_row().assert_eq(local.state, initial);
+        builder.when_transition().assert_eq(next.state, new_state);
+        builder.when_last_row().assert_eq(local.state, final_);
+    }
+}
+
+struct ConditionalStateRow<F> {
+    pub state: F,
+    pub action: F,
+    pub new_state_if_zero: F,
+    pub new_state_if_nonzero: F,
+    pub is_zero_indicator: F,
+    pub inv: F,
+}
+
+impl<F> Borrow<ConditionalStateRow<F>> for [F] {
+    fn borrow(&self) -> &ConditionalStateRow<F> {
+        debug_assert_eq!(self.len(), CS_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<ConditionalStateRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+/// Returns the trace together with `[initial, final]`, the public values.
+fn generate_trace<F: Field>(initial: F, actions: &[F]) -> (RowMajorMatrix<F>, Vec<F>) {
+    let n = actions.len();
+    assert!(n.is_power_of_two(), "trace height must be a power of two");
+
+    let mut trace = RowMajorMatrix::new(vec![F::zero(); n * CS_ROW_WIDTH], CS_ROW_WIDTH);
+
+    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<ConditionalStateRow<F>>() };
+    assert!(prefix.is_empty(), "Alignment should match");
+    assert!(suffix.is_empty(), "Alignment should match");
+    assert_eq!(rows.len(), n);
+
+    let mut state = initial;
+    for (row, &action) in rows.iter_mut().zip(actions) {
+        let (inv, is_zero_indicator) = is_zero_witness(action);
+        *row = ConditionalStateRow {
+            state,
+            action,
+            new_state_if_zero: state + F::one(),
+            new_state_if_nonzero: state * action,
+            is_zero_indicator,
+            inv,
+        };
+        state = if action.is_zero() { row.new_state_if_zero } else { row.new_state_if_nonzero };
+    }
+
+    let final_ = rows[n - 1].state;
+    (trace, vec![initial, final_])
+}
+
+/// Actions in `0..4`, so roughly a quarter of the rows tick.
+fn random_actions<F: Field>(n: usize) -> Vec<F> {
+    let mut rng = thread_rng();
+    (0..n).map(|_| F::from_canonical_u32(rng.gen_range(0..4))).collect()
+}
+
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+
+    let (trace, public_values) = generate_trace(Val::one(), &random_actions(1 << 10));
+
+    let mut p_challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &ConditionalStateAir {}, &mut p_challenger, trace, &public_values);
+    let mut v_challenger = Challenger::new(perm);
+    verify(&config, &ConditionalStateAir {}, &mut v_challenger, &proof, &public_values).unwrap();
+
+    println!("proven: state {} -> {}", public_values[0], public_values[1]);
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky3_cook::{assert_constraints_fail, assert_constraints_ok};
+
+    use super::*;
+
+    fn actions() -> Vec<Val> {
+        let mut actions = random_actions::<Val>(1 << 6);
+        (actions[3], actions[4]) = (Val::zero(), Val::two());
+        actions
+    }
+
+    #[test]
+    fn test_conditional_state_constraints() {
+        let (trace, public_values) = generate_trace(Val::one(), &actions());
+        assert_constraints_ok!(&ConditionalStateAir {}, &trace, &public_values);
+    }
+
+    #[test]
+    fn test_indicator_cannot_lie() {
+        // claim the nonzero action on row 4 is zero, to take the tick branch
+        let (mut trace, public_values) = generate_trace(Val::one(), &actions());
+        trace.row_mut(4)[4] = Val::one();
+        trace.row_mut(4)[5] = Val::zero();
+        assert_constraints_fail!(&ConditionalStateAir {}, &trace, &public_values, 4);
+    }
+
+    #[test]
+    fn test_wrong_branch_fails() {
+        // row 3 has action 0, so the next state must be the tick
+        let (mut trace, public_values) = generate_trace(Val::one(), &actions());
+        let scaled = trace.row_slice(3)[3];
+        trace.row_mut(4)[0] = scaled;
+        assert_constraints_fail!(&ConditionalStateAir {}, &trace, &public_values, 3);
+    }
+
+    #[test]
+    fn test_conditional_state_round_trip() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let (trace, public_values) = generate_trace(Val::one(), &actions());
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &ConditionalStateAir {}, &mut p_challenger, trace, &public_values);
+        let mut v_challenger = Challenger::new(perm);
+        verify(&config, &ConditionalStateAir {}, &mut v_challenger, &proof, &public_values).unwrap();
+    }
+}
@@ -0,0 +1,227 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_dft::TwoAdicSubgroupDft;
+use p3_field::{AbstractField, TwoAdicField};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::{prove, verify};
+use plonky3_cook::config::{default_config, random_perm, Challenger, Dft, Val};
+use rand::{thread_rng, Rng};
+use tracing_forest::{util::LevelFilter, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+// What `TwoAdicFriPcs` hashes. A trace column holds `N` values on the subgroup `<h>` of order `N`; to commit
+// it, the PCS interpolates them to the coefficients `c_0..c_{N-1}` of a polynomial `p` of degree below `N`,
+// evaluates `p` on the coset `g * <omega>` of the larger subgroup of order `N << LOG_BLOWUP` (`g` is
+// `Val::generator()`, which keeps the coset off the trace domain), and Merkle-hashes the evaluations. FRI
+// then queries that oracle at random positions and checks that it is close to a low-degree polynomial; the
+// constraint quotient is checked against it at a random point. Everything rests on the oracle being `p` on
+// the coset, which this AIR states row by row:
+//   first row:          x == g
+//   every transition:   next.x == local.x * omega
+//   last row:           x == g * omega^(N << LOG_BLOWUP - 1)
+//   every row:          lde == c_{N-1} x^{N-1} + ... + c_0, by Horner's rule
+// with the coefficients as public values and one row per coset position; the last-row check is what stops a
+// shorter trace from covering only the first few positions. Horner's rule keeps the constraints at degree 2:
+// `horner[k]` is `horner[k - 1] * x + c_{N-2-k}`, starting from `c_{N-1}`.
+//
+// The PCS stores the oracle in bit-reversed row order, so that a FRI fold reads adjacent leaves; the rows
+// here are in natural order, position `i` being `g * omega^i`.
+
+const LOG_N: usize = 3;
+const N: usize = 1 << LOG_N;
+const LOG_BLOWUP: usize = 2;
+const LOG_LDE_HEIGHT: usize = LOG_N + LOG_BLOWUP;
+
+const CH_ROW_WIDTH: usize = N + 1;
+
+/// The LDE of the polynomial with the public coefficients on the coset `shift * <omega>`.
+struct CosetHash {
+    shift: Val,
+}
+
+impl<F> BaseAir<F> for CosetHash {
+    fn width(&self) -> usize {
+        CH_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues<F = Val>> Air<AB> for CosetHash {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &CosetRow<AB::Var> = (*local).borrow();
+        let next: &CosetRow<AB::Var> = (*next).borrow();
+        let coeffs = builder.public_values().iter().map(|&c| c.into()).collect::<Vec<AB::Expr>>();
+
+        // the row's coset position
+        let omega = Val::two_adic_generator(LOG_LDE_HEIGHT);
+        builder.when_first_row().assert_eq(local.x, AB::Expr::from_f(self.shift));
+        builder.when_transition().assert_eq(next.x, local.x * omega);
+        let last_x = self.shift * omega.exp_u64((N << LOG_BLOWUP) as u64 - 1);
+        builder.when_last_row().assert_eq(local.x, AB::Expr::from_f(last_x));
+
+        // p(x) by Horner's rule, one coefficient per column
+        builder.assert_eq(local.horner[0], local.x * coeffs[N - 1].clone() + coeffs[N - 2].clone());
+        for k in 1..N - 1 {
+            builder.assert_eq(local.horner[k], local.horner[k - 1] * local.x + coeffs[N - 2 - k].clone());
+        }
+        builder.assert_eq(local.lde, local.horner[N - 2]);
+    }
+}
+
+struct CosetRow<F> {
+    pub x: F,
+    /// `horner[k]` is `c_{N-1} x^{k+1} + ... + c_{N-2-k}`, so the last is `p(x)`
+    pub horner: [F; N - 1],
+    /// the oracle value the PCS commits at this position
+    pub lde: F,
+}
+
+impl<F> Borrow<CosetRow<F>> for [F] {
+    fn borrow(&self) -> &CosetRow<F> {
+        debug_assert_eq!(self.len(), CH_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<CosetRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+/// The LDE of `column` the way `TwoAdicFriPcs` computes it, in natural order, and the coefficients.
+fn coset_lde(column: &[Val], shift: Val) -> (Vec<Val>, Vec<Val>) {
+    assert_eq!(column.len(), N);
+    let coeffs = Dft {}.idft(column.to_vec());
+    let mut padded = coeffs.clone();
+    padded.resize(N << LOG_BLOWUP, Val::zero());
+    (Dft {}.coset_dft(padded, shift), coeffs)
+}
+
+/// The trace checking the LDE of `column`, and the coefficients as public values.
+fn generate_trace(column: &[Val], shift: Val) -> (RowMajorMatrix<Val>, Vec<Val>) {
+    let (lde, coeffs) = coset_lde(column, shift);
+    let height = lde.len();
+
+    let mut trace = RowMajorMatrix::new(vec![Val::zero(); height * CH_ROW_WIDTH], CH_ROW_WIDTH);
+    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<CosetRow<Val>>() };
+    assert!(prefix.is_empty(), "Alignment should match");
+    assert!(suffix.is_empty(), "Alignment should match");
+
+    let omega = Val::two_adic_generator(LOG_LDE_HEIGHT);
+    let mut x = shift;
+    for (row, &value) in rows.iter_mut().zip(&lde) {
+        row.x = x;
+        let mut acc = coeffs[N - 1];
+        for k in 0..N - 1 {
+            acc = acc * x + coeffs[N - 2 - k];
+            row.horner[k] = acc;
+        }
+        row.lde = value;
+        x *= omega;
+    }
+
+    (trace, coeffs)
+}
+
+fn random_column() -> Vec<Val> {
+    let mut rng = thread_rng();
+    (0..N).map(|_| rng.gen()).collect()
+}
+
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+
+    let air = CosetHash { shift: Val::generator() };
+    let (trace, public_values) = generate_trace(&random_column(), air.shift);
+
+    let mut p_challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &air, &mut p_challenger, trace, &public_values);
+    let mut v_challenger = Challenger::new(perm);
+    verify(&config, &air, &mut v_challenger, &proof, &public_values).unwrap();
+
+    println!("proven: the {} LDE positions are the public polynomial on the coset", N << LOG_BLOWUP);
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky3_cook::{assert_constraints_fail, assert_constraints_ok};
+
+    use super::*;
+
+    const LDE_COL: usize = CH_ROW_WIDTH - 1;
+
+    #[test]
+    fn test_lde_matches_horner() {
+        let air = CosetHash { shift: Val::generator() };
+        let (trace, public_values) = generate_trace(&random_column(), air.shift);
+        assert_constraints_ok!(&air, &trace, &public_values);
+    }
+
+    #[test]
+    fn test_lde_extends_the_column() {
+        // every `1 << LOG_BLOWUP`-th position of the unshifted LDE is back on the trace domain
+        let column = random_column();
+        let (lde, _) = coset_lde(&column, Val::one());
+        let on_trace_domain = lde.iter().step_by(1 << LOG_BLOWUP).copied().collect::<Vec<_>>();
+        assert_eq!(on_trace_domain, column);
+    }
+
+    #[test]
+    fn test_tampered_oracle_fails() {
+        let air = CosetHash { shift: Val::generator() };
+        let (mut trace, public_values) = generate_trace(&random_column(), air.shift);
+        trace.row_mut(9)[LDE_COL] += Val::one();
+        assert_constraints_fail!(&air, &trace, &public_values, 9);
+    }
+
+    #[test]
+    fn test_truncated_trace_fails() {
+        // the first rows alone satisfy every other constraint, but stop short of the last coset position
+        let air = CosetHash { shift: Val::generator() };
+        let (trace, public_values) = generate_trace(&random_column(), air.shift);
+        for height in [2, 16] {
+            let truncated = RowMajorMatrix::new(trace.values[..height * CH_ROW_WIDTH].to_vec(), CH_ROW_WIDTH);
+            assert_constraints_fail!(&air, &truncated, &public_values, height - 1);
+        }
+    }
+
+    #[test]
+    fn test_unshifted_lde_fails() {
+        // the LDE on the subgroup itself is a different oracle
+        let (trace, public_values) = generate_trace(&random_column(), Val::one());
+        assert_constraints_fail!(&CosetHash { shift: Val::generator() }, &trace, &public_values, 0);
+    }
+
+    #[test]
+    fn test_other_polynomial_fails() {
+        let air = CosetHash { shift: Val::generator() };
+        let (trace, mut public_values) = generate_trace(&random_column(), air.shift);
+        public_values[3] += Val::one();
+        assert_constraints_fail!(&air, &trace, &public_values, 0);
+    }
+
+    #[test]
+    fn test_coset_hash_round_trip() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let air = CosetHash { shift: Val::generator() };
+        let (trace, public_values) = generate_trace(&random_column(), air.shift);
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &air, &mut p_challenger, trace, &public_values);
+        let mut v_challenger = Challenger::new(perm);
+        verify(&config, &air, &mut v_challenger, &proof, &public_values).unwrap();
+    }
+}
@@ -0,0 +1,275 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_field::{AbstractField, Field, TwoAdicField};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::{prove, verify};
+use plonky3_cook::config::{default_config, random_perm, Challenger, Val};
+use plonky3_cook::gadgets::one_hot::{assert_one_hot, one_hot, select};
+use rand::{thread_rng, Rng};
+use tracing_forest::{util::LevelFilter, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+// Evaluating a polynomial over a coset: for coefficients `c_0..c_{N-1}` and a shift `g`,
+//   p(g * omega^i) = sum_j (c_j * g^j) * omega^(i*j)
+// so the coset evaluations are the plain NTT of the coefficients scaled by powers of the shift. That is how
+// the FRI commitment gets its LDE: the trace columns are interpolated to coefficients, padded to the blowup
+// size, and evaluated on the coset `Val::generator() * <omega>` (the shift keeps the LDE domain disjoint from
+// the trace domain), which `coset_dft` computes and the last test checks against.
+//
+// Each row of the trace holds the whole length-`N` vector after one stage of a radix-2 decimation-in-time
+// NTT. The first transition applies the shift, multiplying `c_j` by `g^j` and moving it to its bit-reversed
+// position; the next `log N` transitions are butterfly layers, `(u, t) -> (u + w * t, u - w * t)`; after that
+// rows repeat until the end of the trace, whose last row must be in the hold phase so a short trace can't
+// stop partway through the NTT. Which stage a row is in is a one-hot phase vector pinned to that
+// schedule, and each output is the phase-selected linear combination of the row, so the constraints are
+// degree 2. The coefficients (first row) and evaluations (last row) are the public values.
+
+const LOG_N: usize = 3;
+const N: usize = 1 << LOG_N;
+
+/// scale, one per butterfly layer, then hold
+const NUM_PHASES: usize = LOG_N + 2;
+const HOLD: usize = NUM_PHASES - 1;
+
+const CN_ROW_WIDTH: usize = N + NUM_PHASES;
+const CN_HEIGHT: usize = 8;
+
+/// A linear map on `N` values: output `p` is the sum of `coeff * input[src]` over `terms[p]`.
+type LinearMap = Vec<Vec<(usize, Val)>>;
+
+struct CosetNttAir {
+    /// the map applied by each phase's transition
+    phases: Vec<LinearMap>,
+}
+
+fn bit_reverse(i: usize) -> usize {
+    i.reverse_bits() >> (usize::BITS as usize - LOG_N)
+}
+
+impl CosetNttAir {
+    fn new(shift: Val) -> Self {
+        let mut phases = vec![];
+
+        let mut scale = vec![vec![]; N];
+        for (j, g_j) in shift.powers().take(N).enumerate() {
+            scale[bit_reverse(j)] = vec![(j, g_j)];
+        }
+        phases.push(scale);
+
+        for s in 1..=LOG_N {
+            let m = 1 << s;
+            let w_m = Val::two_adic_generator(s);
+            let mut layer = vec![vec![]; N];
+            for block in (0..N).step_by(m) {
+                for (j, w) in w_m.powers().take(m / 2).enumerate() {
+                    let (u, t) = (block + j, block + j + m / 2);
+                    layer[u] = vec![(u, Val::one()), (t, w)];
+                    layer[t] = vec![(u, Val::one()), (t, -w)];
+                }
+            }
+            phases.push(layer);
+        }
+
+        phases.push((0..N).map(|p| vec![(p, Val::one())]).collect());
+        CosetNttAir { phases }
+    }
+}
+
+fn apply(map: &LinearMap, values: &[Val; N]) -> [Val; N] {
+    core::array::from_fn(|p| map[p].iter().map(|&(src, coeff)| coeff * values[src]).sum())
+}
+
+impl<F> BaseAir<F> for CosetNttAir {
+    fn width(&self) -> usize {
+        CN_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues<F = Val>> Air<AB> for CosetNttAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &CosetNttRow<AB::Var> = (*local).borrow();
+        let next: &CosetNttRow<AB::Var> = (*next).borrow();
+
+        let pis = builder.public_values().iter().map(|&v| v.into()).collect::<Vec<AB::Expr>>();
+        let (coeffs, evals) = pis.split_at(N);
+
+        // the schedule: scale, the layers in order, then hold
+        assert_one_hot(builder, &local.phase);
+        builder.when_first_row().assert_one(local.phase[0]);
+        for p in 1..HOLD {
+            builder.when_transition().assert_eq(next.phase[p], local.phase[p - 1]);
+        }
+        builder.when_transition().assert_eq(next.phase[HOLD], local.phase[HOLD - 1] + local.phase[HOLD]);
+        builder.when_last_row().assert_one(local.phase[HOLD]);
+
+        for p in 0..N {
+            let candidates = self.phases.iter().map(|map| {
+                map[p].iter().map(|&(src, coeff)| AB::Expr::from(local.values[src]) * coeff).sum::<AB::Expr>()
+            });
+            builder.when_transition().assert_eq(next.values[p], select::<_, AB::Expr>(&local.phase, candidates));
+        }
+
+        for p in 0..N {
+            builder.when_first_row().assert_eq(local.values[p], coeffs[p].clone());
+            builder.when_last_row().assert_eq(local.values[p], evals[p].clone());
+        }
+    }
+}
+
+struct CosetNttRow<F> {
+    pub values: [F; N],
+    pub phase: [F; NUM_PHASES],
+}
+
+impl<F> Borrow<CosetNttRow<F>> for [F] {
+    fn borrow(&self) -> &CosetNttRow<F> {
+        debug_assert_eq!(self.len(), CN_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<CosetNttRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+/// Returns the trace together with the public values, the coefficients followed by the evaluations.
+fn generate_trace(air: &CosetNttAir, coeffs: [Val; N]) -> (RowMajorMatrix<Val>, Vec<Val>) {
+    let mut trace = RowMajorMatrix::new(vec![Val::zero(); CN_HEIGHT * CN_ROW_WIDTH], CN_ROW_WIDTH);
+
+    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<CosetNttRow<Val>>() };
+    assert!(prefix.is_empty(), "Alignment should match");
+    assert!(suffix.is_empty(), "Alignment should match");
+    assert_eq!(rows.len(), CN_HEIGHT);
+
+    let mut values = coeffs;
+    for (i, row) in rows.iter_mut().enumerate() {
+        let phase = i.min(HOLD);
+        row.values = values;
+        row.phase.copy_from_slice(&one_hot(phase, NUM_PHASES));
+        values = apply(&air.phases[phase], &values);
+    }
+
+    let mut public_values = coeffs.to_vec();
+    public_values.extend_from_slice(&rows[CN_HEIGHT - 1].values);
+    (trace, public_values)
+}
+
+/// `p(shift * omega^i)` for every `i`, by direct evaluation.
+fn naive_coset_evals<F: TwoAdicField>(coeffs: &[F], shift: F) -> Vec<F> {
+    let omega = F::two_adic_generator(LOG_N);
+    (0..N)
+        .map(|i| {
+            let x = shift * omega.exp_u64(i as u64);
+            coeffs.iter().rev().fold(F::zero(), |acc, &c| acc * x + c)
+        })
+        .collect()
+}
+
+fn random_coeffs() -> [Val; N] {
+    let mut rng = thread_rng();
+    core::array::from_fn(|_| rng.gen())
+}
+
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+
+    // the shift the PCS uses for its LDE coset
+    let air = CosetNttAir::new(Val::generator());
+    let (trace, public_values) = generate_trace(&air, random_coeffs());
+
+    let mut p_challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &air, &mut p_challenger, trace, &public_values);
+    let mut v_challenger = Challenger::new(perm);
+    verify(&config, &air, &mut v_challenger, &proof, &public_values).unwrap();
+
+    println!("proven: {} coset evaluations {:?}", N, &public_values[N..]);
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_dft::TwoAdicSubgroupDft;
+    use plonky3_cook::config::Dft;
+    use plonky3_cook::{assert_constraints_fail, assert_constraints_ok};
+
+    use super::*;
+
+    #[test]
+    fn test_last_row_is_coset_evaluation() {
+        let shift = Val::generator();
+        let coeffs = random_coeffs();
+        let (_, public_values) = generate_trace(&CosetNttAir::new(shift), coeffs);
+
+        let evals = &public_values[N..];
+        assert_eq!(evals, naive_coset_evals(&coeffs, shift));
+        assert_eq!(evals, Dft {}.coset_dft(coeffs.to_vec(), shift));
+    }
+
+    #[test]
+    fn test_coset_ntt_constraints() {
+        let air = CosetNttAir::new(Val::generator());
+        let (trace, public_values) = generate_trace(&air, random_coeffs());
+        assert_constraints_ok!(&air, &trace, &public_values);
+    }
+
+    #[test]
+    fn test_unshifted_ntt_fails() {
+        // evaluations over the subgroup itself don't satisfy the coset AIR
+        let coeffs = random_coeffs();
+        let (trace, public_values) = generate_trace(&CosetNttAir::new(Val::one()), coeffs);
+        assert_constraints_fail!(&CosetNttAir::new(Val::generator()), &trace, &public_values, 0);
+    }
+
+    #[test]
+    fn test_tampered_layer_fails() {
+        let air = CosetNttAir::new(Val::generator());
+        let (mut trace, public_values) = generate_trace(&air, random_coeffs());
+        trace.row_mut(2)[5] += Val::one();
+        assert_constraints_fail!(&air, &trace, &public_values, 1);
+    }
+
+    #[test]
+    fn test_partial_ntt_fails() {
+        // two rows stop after the shift, claiming its output as the evaluations
+        let air = CosetNttAir::new(Val::generator());
+        let coeffs = random_coeffs();
+        let (trace, _) = generate_trace(&air, coeffs);
+        let short = RowMajorMatrix::new(trace.values[..2 * CN_ROW_WIDTH].to_vec(), CN_ROW_WIDTH);
+        let public_values = [&coeffs[..], &short.row_slice(1)[..N]].concat();
+        assert_constraints_fail!(&air, &short, &public_values, 1);
+    }
+
+    #[test]
+    fn test_wrong_evaluation_fails() {
+        let air = CosetNttAir::new(Val::generator());
+        let (trace, mut public_values) = generate_trace(&air, random_coeffs());
+        public_values[N + 3] += Val::one();
+        assert_constraints_fail!(&air, &trace, &public_values, CN_HEIGHT - 1);
+    }
+
+    #[test]
+    fn test_coset_ntt_round_trip() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let air = CosetNttAir::new(Val::generator());
+        let (trace, public_values) = generate_trace(&air, random_coeffs());
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &air, &mut p_challenger, trace, &public_values);
+        let mut v_challenger = Challenger::new(perm);
+        verify(&config, &air, &mut v_challenger, &proof, &public_values).unwrap();
+    }
+}
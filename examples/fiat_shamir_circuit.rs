@@ -0,0 +1,237 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_challenger::{CanObserve, CanSample};
+use p3_field::{AbstractField, PrimeField32};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::{prove, verify};
+use plonky3_cook::config::{default_config, random_perm, Challenger, Val};
+use plonky3_cook::poseidon2_air::{
+    eval_permutation, generate_permutation, PermutationCols, Poseidon2Constants, PERMUTATION_WIDTH, WIDTH,
+};
+use rand::{thread_rng, Rng};
+use tracing_forest::{util::LevelFilter, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+// The Fiat-Shamir transform as a circuit: given the prover's messages as witness, the `ChallengeDerivation`
+// AIR proves the challenges a verifier's `Challenger` (a `DuplexChallenger` over Poseidon2, rate 8) derives
+// from them, which are the public values. Checking this is what a recursive verifier has to do before it can
+// trust any challenge it then uses.
+//
+// The transcript is `ROUNDS` rounds of: observe one 8-element message (a commitment digest), then sample
+// `SAMPLES` challenges. With the rate at 8, the challenger duplexes exactly when the eighth element comes in:
+// the message overwrites the rate part of the sponge state, the capacity part carries over, and the state is
+// permuted. Sampling then pops elements off the end of the permuted rate, `RATE - 1` first, without
+// permuting again. So each round is one permutation per row, as in `var_arg_hash.rs`' sponge, with
+//   inputs  =  [message, previous row's output capacity]      (zero capacity on the first row)
+// and its challenges at `out[RATE - 1], out[RATE - 2], ...`.
+//
+// The public challenges, in transcript order, are matched to rows through a shift register: the first row
+// holds them all in `pending`, each row checks its own against the front, and the next row's `pending` is
+// this row's shifted by `SAMPLES`. On the last row only its own challenges may be left, which pins the trace
+// to `ROUNDS` rows: a shorter one would leave the later public challenges unchecked.
+
+const RATE: usize = 8;
+const ROUNDS: usize = 8;
+const SAMPLES: usize = 2;
+const NUM_CHALLENGES: usize = ROUNDS * SAMPLES;
+
+const FS_ROW_WIDTH: usize = NUM_CHALLENGES + PERMUTATION_WIDTH;
+
+struct ChallengeDerivation {
+    constants: Poseidon2Constants,
+}
+
+impl<F> BaseAir<F> for ChallengeDerivation {
+    fn width(&self) -> usize {
+        FS_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues<F = Val>> Air<AB> for ChallengeDerivation {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &ChallengeRow<AB::Var> = (*local).borrow();
+        let next: &ChallengeRow<AB::Var> = (*next).borrow();
+
+        let challenges: Vec<AB::Expr> = builder.public_values().iter().map(|&v| v.into()).collect();
+
+        let out = eval_permutation(builder, &self.constants, &local.perm);
+
+        // the sponge: the message in `inputs[..RATE]` is free, the capacity carries over
+        for i in RATE..WIDTH {
+            builder.when_first_row().assert_zero(local.perm.inputs[i]);
+            builder.when_transition().assert_eq(next.perm.inputs[i], out[i].clone());
+        }
+
+        // this round's challenges, popped off the end of the rate
+        for s in 0..SAMPLES {
+            builder.assert_eq(local.pending[s], out[RATE - 1 - s].clone());
+        }
+
+        // the shift register of challenges still to come
+        for j in 0..NUM_CHALLENGES {
+            builder.when_first_row().assert_eq(local.pending[j], challenges[j].clone());
+            if j + SAMPLES < NUM_CHALLENGES {
+                builder.when_transition().assert_eq(next.pending[j], local.pending[j + SAMPLES]);
+            } else {
+                builder.when_transition().assert_zero(next.pending[j]);
+            }
+            if j >= SAMPLES {
+                builder.when_last_row().assert_zero(local.pending[j]);
+            }
+        }
+    }
+}
+
+struct ChallengeRow<F> {
+    /// the challenges of this round and the ones after it, zero-filled
+    pub pending: [F; NUM_CHALLENGES],
+    /// the message is `perm.inputs[..RATE]`
+    pub perm: PermutationCols<F>,
+}
+
+impl<F> Borrow<ChallengeRow<F>> for [F] {
+    fn borrow(&self) -> &ChallengeRow<F> {
+        debug_assert_eq!(self.len(), FS_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<ChallengeRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+/// Returns the trace of the transcript over `messages` together with its challenges.
+fn generate_trace(c: &Poseidon2Constants, messages: &[[Val; RATE]; ROUNDS]) -> (RowMajorMatrix<Val>, Vec<Val>) {
+    let mut trace = RowMajorMatrix::new(vec![Val::zero(); ROUNDS * FS_ROW_WIDTH], FS_ROW_WIDTH);
+    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<ChallengeRow<Val>>() };
+    assert!(prefix.is_empty(), "Alignment should match");
+    assert!(suffix.is_empty(), "Alignment should match");
+
+    let mut state = [Val::zero(); WIDTH];
+    let mut challenges = Vec::with_capacity(NUM_CHALLENGES);
+    for (row, message) in rows.iter_mut().zip(messages) {
+        state[..RATE].copy_from_slice(message);
+        state = generate_permutation(c, state, &mut row.perm);
+        challenges.extend((0..SAMPLES).map(|s| state[RATE - 1 - s]));
+    }
+
+    for (r, row) in rows.iter_mut().enumerate() {
+        let rest = &challenges[r * SAMPLES..];
+        row.pending[..rest.len()].copy_from_slice(rest);
+    }
+    (trace, challenges)
+}
+
+/// The challenges a `Challenger` over `c`'s permutation samples from `messages`.
+fn native_challenges(c: &Poseidon2Constants, messages: &[[Val; RATE]; ROUNDS]) -> Vec<Val> {
+    let mut challenger = Challenger::new(c.perm());
+    let mut challenges = vec![];
+    for message in messages {
+        challenger.observe_slice(message);
+        for _ in 0..SAMPLES {
+            challenges.push(challenger.sample());
+        }
+    }
+    challenges
+}
+
+fn random_messages() -> [[Val; RATE]; ROUNDS] {
+    let mut rng = thread_rng();
+    core::array::from_fn(|_| rng.gen())
+}
+
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+    let air = ChallengeDerivation { constants: Poseidon2Constants::from_seed(0x6673) };
+
+    let messages = random_messages();
+    let (trace, public_values) = generate_trace(&air.constants, &messages);
+    assert_eq!(public_values, native_challenges(&air.constants, &messages));
+
+    let mut p_challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &air, &mut p_challenger, trace, &public_values);
+    let mut v_challenger = Challenger::new(perm);
+    verify(&config, &air, &mut v_challenger, &proof, &public_values).unwrap();
+
+    println!(
+        "proven: {} challenges derived from {} messages, the first {}",
+        NUM_CHALLENGES,
+        ROUNDS,
+        public_values[0].as_canonical_u32()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky3_cook::{assert_constraints_fail, assert_constraints_ok};
+
+    use super::*;
+
+    fn air() -> ChallengeDerivation {
+        ChallengeDerivation { constants: Poseidon2Constants::from_seed(1) }
+    }
+
+    #[test]
+    fn test_matches_native_challenger() {
+        let air = air();
+        let messages = random_messages();
+        let (trace, public_values) = generate_trace(&air.constants, &messages);
+        assert_constraints_ok!(&air, &trace, &public_values);
+        assert_eq!(public_values, native_challenges(&air.constants, &messages));
+    }
+
+    #[test]
+    fn test_wrong_challenge_fails() {
+        let air = air();
+        let (trace, mut public_values) = generate_trace(&air.constants, &random_messages());
+        public_values[2 * SAMPLES + 1] += Val::one();
+        assert_constraints_fail!(&air, &trace, &public_values, 0);
+    }
+
+    #[test]
+    fn test_swapped_messages_fail() {
+        // the challenges of one transcript don't follow from the same messages in another order
+        let air = air();
+        let mut messages = random_messages();
+        let (_, public_values) = generate_trace(&air.constants, &messages);
+        messages.swap(3, 4);
+        let (trace, _) = generate_trace(&air.constants, &messages);
+        assert_constraints_fail!(&air, &trace, &public_values, 0);
+    }
+
+    #[test]
+    fn test_short_transcript_fails() {
+        // four rounds, with the public challenges after them left to the prover
+        let air = air();
+        let (trace, public_values) = generate_trace(&air.constants, &random_messages());
+        let short = RowMajorMatrix::new(trace.values[..4 * FS_ROW_WIDTH].to_vec(), FS_ROW_WIDTH);
+        assert_constraints_fail!(&air, &short, &public_values, 3);
+    }
+
+    #[test]
+    fn test_fiat_shamir_round_trip() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let air = air();
+        let (trace, public_values) = generate_trace(&air.constants, &random_messages());
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &air, &mut p_challenger, trace, &public_values);
+        let mut v_challenger = Challenger::new(perm);
+        verify(&config, &air, &mut v_challenger, &proof, &public_values).unwrap();
+    }
+}
@@ -0,0 +1,217 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_field::{AbstractField, PrimeField32};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::{prove, verify};
+use plonky3_cook::config::{default_config, random_perm, Challenger, Val};
+use plonky3_cook::gadgets::assert_latch;
+use plonky3_cook::gadgets::uint256::{add_witness, assert_add, Uint256, LIMBS, LIMB_BITS};
+use tracing_forest::{util::LevelFilter, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+// Fibonacci past the 32-bit range: `F(47)` no longer fits in a `u32`, and `F(100)` is a 69-bit number. Each
+// value is a `Uint256` (`gadgets::uint256`, 16 limbs of 16 bits) and each step is a range-checked 256-bit
+// addition, with the carry out of the top limb asserted zero so the sequence can't wrap.
+//
+// Row `i` holds `(a, b) = (F(i), F(i + 1))` and steps to `(b, a + b)`. `STEPS` isn't a power of two, so the
+// trace runs past it: a `done` latch (`gadgets::latch`) switches the rows from stepping to holding their
+// value, and `count` counts the stepping rows,
+//   next.count == count + 1 - done,   count == STEPS on the last row
+// which with the latch's shape puts the switch exactly at row `STEPS`. The last row's `(a, b)`,
+// `(F(STEPS), F(STEPS + 1))`, are the public values.
+
+const STEPS: usize = 100;
+const FIB_HEIGHT: usize = 128;
+
+const FIB_ROW_WIDTH: usize = 3 * LIMBS + LIMBS * LIMB_BITS + 2;
+
+struct FibonacciBigInt {}
+
+impl<F> BaseAir<F> for FibonacciBigInt {
+    fn width(&self) -> usize {
+        FIB_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues> Air<AB> for FibonacciBigInt {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &FibRow<AB::Var> = (*local).borrow();
+        let next: &FibRow<AB::Var> = (*next).borrow();
+
+        let pis = builder.public_values().to_vec();
+
+        // F(0) = 0, F(1) = 1
+        for i in 0..LIMBS {
+            builder.when_first_row().assert_zero(local.a[i]);
+            builder.when_first_row().assert_eq(local.b[i], AB::Expr::from_bool(i == 0));
+        }
+
+        // stepping for exactly STEPS rows, then holding
+        assert_latch(builder, local.done, next.done);
+        builder.when_first_row().assert_zero(local.count);
+        builder
+            .when_transition()
+            .assert_eq(next.count, local.count + AB::Expr::one() - local.done);
+        builder
+            .when_last_row()
+            .assert_eq(local.count, AB::Expr::from_canonical_usize(STEPS));
+
+        let stepping = builder.is_transition() * (AB::Expr::one() - local.done);
+        let mut step = builder.when(stepping);
+        assert_add(&mut step, &local.a, &local.b, &next.b, &next.b_bits, &local.carries);
+        step.assert_zero(local.carries[LIMBS - 1]);
+        for i in 0..LIMBS {
+            step.assert_eq(next.a[i], local.b[i]);
+        }
+
+        let holding = builder.is_transition() * local.done;
+        let mut hold = builder.when(holding);
+        for i in 0..LIMBS {
+            hold.assert_eq(next.a[i], local.a[i]);
+            hold.assert_eq(next.b[i], local.b[i]);
+        }
+
+        for i in 0..LIMBS {
+            builder.when_last_row().assert_eq(local.a[i], pis[i]);
+            builder.when_last_row().assert_eq(local.b[i], pis[LIMBS + i]);
+        }
+    }
+}
+
+struct FibRow<F> {
+    pub a: [F; LIMBS],
+    pub b: [F; LIMBS],
+    /// the bits of `b`'s limbs, range checking it as the sum of the step into this row
+    pub b_bits: [[F; LIMB_BITS]; LIMBS],
+    /// the carries of `a + b`
+    pub carries: [F; LIMBS],
+    /// 1 once the `STEPS` steps are done
+    pub done: F,
+    /// the stepping rows before this one
+    pub count: F,
+}
+
+impl<F> Borrow<FibRow<F>> for [F] {
+    fn borrow(&self) -> &FibRow<F> {
+        debug_assert_eq!(self.len(), FIB_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<FibRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+/// The trace of `STEPS` Fibonacci steps, and the limbs of `F(STEPS)` and `F(STEPS + 1)`.
+fn generate_trace() -> (RowMajorMatrix<Val>, Vec<Val>) {
+    let mut trace = RowMajorMatrix::new(vec![Val::zero(); FIB_HEIGHT * FIB_ROW_WIDTH], FIB_ROW_WIDTH);
+    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<FibRow<Val>>() };
+    assert!(prefix.is_empty(), "Alignment should match");
+    assert!(suffix.is_empty(), "Alignment should match");
+
+    let (mut a, mut b) = (Uint256::default(), Uint256::from_u128(1));
+    for (i, row) in rows.iter_mut().enumerate() {
+        row.a = a.limbs();
+        row.b = b.limbs();
+        row.b_bits = b.limb_bits();
+        row.count = Val::from_canonical_usize(i.min(STEPS));
+        if i < STEPS {
+            let (sum, carries) = add_witness(&a, &b);
+            row.carries = carries.map(Val::from_canonical_u32);
+            (a, b) = (b, sum);
+        } else {
+            row.done = Val::one();
+        }
+    }
+
+    let public_values = [a.limbs::<Val>(), b.limbs()].concat();
+    (trace, public_values)
+}
+
+fn to_uint256(limbs: &[Val]) -> Uint256 {
+    Uint256(core::array::from_fn(|i| limbs[i].as_canonical_u32()))
+}
+
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+    let (trace, public_values) = generate_trace();
+
+    let mut p_challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &FibonacciBigInt {}, &mut p_challenger, trace, &public_values);
+    let mut v_challenger = Challenger::new(perm);
+    verify(&config, &FibonacciBigInt {}, &mut v_challenger, &proof, &public_values).unwrap();
+
+    println!(
+        "proven: F({}) = {}, F({}) = {}",
+        STEPS,
+        to_uint256(&public_values[..LIMBS]),
+        STEPS + 1,
+        to_uint256(&public_values[LIMBS..])
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky3_cook::{assert_constraints_fail, assert_constraints_ok};
+
+    use super::*;
+
+    const A_COL: usize = 0;
+    const DONE_COL: usize = FIB_ROW_WIDTH - 2;
+
+    #[test]
+    fn test_fibonacci_bigint() {
+        let (trace, public_values) = generate_trace();
+        assert_constraints_ok!(&FibonacciBigInt {}, &trace, &public_values);
+
+        // F(100) = 354224848179261915075, F(101) = 573147844013817084101
+        assert_eq!(to_uint256(&public_values[..LIMBS]), Uint256::from_u128(354_224_848_179_261_915_075));
+        assert_eq!(to_uint256(&public_values[LIMBS..]), Uint256::from_u128(573_147_844_013_817_084_101));
+    }
+
+    #[test]
+    fn test_wrong_step_fails() {
+        let (mut trace, public_values) = generate_trace();
+        trace.row_mut(50)[A_COL + 1] += Val::one();
+        assert_constraints_fail!(&FibonacciBigInt {}, &trace, &public_values, 49);
+    }
+
+    #[test]
+    fn test_stopping_early_fails() {
+        // holding from row 99 leaves 99 steps on the count, and F(99), F(100) as the result
+        let (mut trace, public_values) = generate_trace();
+        let row = trace.row_slice(99).to_vec();
+        for r in 99..FIB_HEIGHT {
+            trace.row_mut(r).copy_from_slice(&row);
+            trace.row_mut(r)[DONE_COL] = Val::one();
+            trace.row_mut(r)[DONE_COL + 1] = Val::from_canonical_usize(STEPS - 1);
+        }
+        assert_constraints_fail!(&FibonacciBigInt {}, &trace, &public_values, FIB_HEIGHT - 1);
+    }
+
+    #[test]
+    fn test_fibonacci_bigint_round_trip() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let (trace, public_values) = generate_trace();
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &FibonacciBigInt {}, &mut p_challenger, trace, &public_values);
+        let mut v_challenger = Challenger::new(perm);
+        verify(&config, &FibonacciBigInt {}, &mut v_challenger, &proof, &public_values).unwrap();
+    }
+}
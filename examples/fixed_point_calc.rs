@@ -0,0 +1,204 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_field::{AbstractField, PrimeField32};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::{prove, verify};
+use plonky3_cook::config::{default_config, random_perm, Challenger, Val};
+use plonky3_cook::gadgets::fixed_point::{
+    assert_fixed_add, assert_fixed_mul, fixed_mul_witness, from_fixed, to_fixed, FixedMulOverflow, SCALE,
+};
+use plonky3_cook::gadgets::less_than::{assert_bit_decomposition, bit_decompose};
+use rand::{thread_rng, Rng};
+use tracing_forest::{util::LevelFilter, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+// A moving average over fixed-point readings (`gadgets::fixed_point`, 16 fractional bits): row `r` holds
+// the window `readings[r..r + WINDOW]` and its average, computed as
+//   sum over the window of  reading * (1 / WINDOW)
+// with one `assert_fixed_mul` per term and a chain of `assert_fixed_add`s. The window slides by one
+// reading per row. The public value is the last row's average.
+//
+// Readings are in `[0, 1)`, i.e. below `2^16` as fixed-point values, and each one is range checked to 16
+// bits in every window it is part of: `assert_fixed_mul` is only an integer identity while the product
+// stays below `2^30`, which a reading of 1.0 or more times the weight 0.25 could break. Each product is
+// truncated, so the average can be below the exact one by up to `WINDOW` units of `2^-16`.
+
+const WINDOW: usize = 4;
+
+const FP_ROW_WIDTH: usize = WINDOW * (1 + SCALE + 1 + FixedMulOverflow::<u8>::WIDTH) + WINDOW - 1;
+
+fn weight<F: AbstractField>() -> F {
+    F::from_canonical_u32(to_fixed(1.0 / WINDOW as f64))
+}
+
+struct MovingAverageAir {}
+
+impl<F> BaseAir<F> for MovingAverageAir {
+    fn width(&self) -> usize {
+        FP_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues> Air<AB> for MovingAverageAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &MovingAverageRow<AB::Var> = (*local).borrow();
+        let next: &MovingAverageRow<AB::Var> = (*next).borrow();
+
+        let average: AB::Expr = builder.public_values()[0].into();
+
+        // the weighted terms, from readings in [0, 1)
+        for i in 0..WINDOW {
+            assert_bit_decomposition(builder, local.window[i], &local.window_bits[i]);
+            assert_fixed_mul(builder, local.window[i], weight::<AB::Expr>(), local.terms[i], &local.overflow[i]);
+        }
+
+        // their sum
+        assert_fixed_add(builder, local.terms[0], local.terms[1], local.partial[0]);
+        for i in 1..WINDOW - 1 {
+            assert_fixed_add(builder, local.partial[i - 1], local.terms[i + 1], local.partial[i]);
+        }
+
+        // the window slides by one reading
+        for i in 0..WINDOW - 1 {
+            builder.when_transition().assert_eq(next.window[i], local.window[i + 1]);
+        }
+
+        builder.when_last_row().assert_eq(local.partial[WINDOW - 2], average);
+    }
+}
+
+struct MovingAverageRow<F> {
+    pub window: [F; WINDOW],
+    pub window_bits: [[F; SCALE]; WINDOW],
+    /// `window[i] * (1 / WINDOW)`
+    pub terms: [F; WINDOW],
+    pub overflow: [FixedMulOverflow<F>; WINDOW],
+    /// running sums of the terms; the last one is the average
+    pub partial: [F; WINDOW - 1],
+}
+
+impl<F> Borrow<MovingAverageRow<F>> for [F] {
+    fn borrow(&self) -> &MovingAverageRow<F> {
+        debug_assert_eq!(self.len(), FP_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<MovingAverageRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+/// The moving averages of `readings` (fixed-point values below 1.0), one window per row, and the last
+/// average as the public value.
+fn generate_trace(readings: &[u32]) -> (RowMajorMatrix<Val>, Vec<Val>) {
+    let height = readings.len() + 1 - WINDOW;
+    assert!(height.is_power_of_two(), "trace height must be a power of two");
+
+    let mut trace = RowMajorMatrix::new(vec![Val::zero(); height * FP_ROW_WIDTH], FP_ROW_WIDTH);
+    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<MovingAverageRow<Val>>() };
+    assert!(prefix.is_empty(), "Alignment should match");
+    assert!(suffix.is_empty(), "Alignment should match");
+
+    for (row, window) in rows.iter_mut().zip(readings.windows(WINDOW)) {
+        for i in 0..WINDOW {
+            row.window[i] = Val::from_canonical_u32(window[i]);
+            row.window_bits[i].copy_from_slice(&bit_decompose(row.window[i], SCALE));
+            (row.terms[i], row.overflow[i]) = fixed_mul_witness(row.window[i], weight());
+        }
+        row.partial[0] = row.terms[0] + row.terms[1];
+        for i in 1..WINDOW - 1 {
+            row.partial[i] = row.partial[i - 1] + row.terms[i + 1];
+        }
+    }
+
+    let average = rows[height - 1].partial[WINDOW - 2];
+    (trace, vec![average])
+}
+
+fn random_readings(n: usize) -> Vec<u32> {
+    let mut rng = thread_rng();
+    (0..n).map(|_| to_fixed(rng.gen_range(0.0..0.999))).collect()
+}
+
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+
+    let readings = random_readings((1 << 10) + WINDOW - 1);
+    let (trace, public_values) = generate_trace(&readings);
+
+    let mut p_challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &MovingAverageAir {}, &mut p_challenger, trace, &public_values);
+    let mut v_challenger = Challenger::new(perm);
+    verify(&config, &MovingAverageAir {}, &mut v_challenger, &proof, &public_values).unwrap();
+
+    println!(
+        "proven: the average of the last {} readings is {:.5}",
+        WINDOW,
+        from_fixed(public_values[0].as_canonical_u32())
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky3_cook::{assert_constraints_fail, assert_constraints_ok};
+
+    use super::*;
+
+    const TERMS_COL: usize = WINDOW * (1 + SCALE);
+
+    #[test]
+    fn test_moving_average() {
+        let readings = [0.5, 0.25, 0.75, 0.1, 0.9, 0.3, 0.6, 0.2, 0.45, 0.05, 0.8].map(to_fixed);
+        let (trace, public_values) = generate_trace(&readings);
+        assert_constraints_ok!(&MovingAverageAir {}, &trace, &public_values);
+
+        // the exact average of the encoded readings in the last window
+        let exact = readings[7..].iter().map(|&r| from_fixed(r)).sum::<f64>() / WINDOW as f64;
+        let proven = from_fixed(public_values[0].as_canonical_u32());
+        assert!(proven <= exact && exact - proven <= WINDOW as f64 / (1 << SCALE) as f64, "{}", proven);
+    }
+
+    #[test]
+    fn test_wrong_average_fails() {
+        let (trace, mut public_values) = generate_trace(&random_readings(16 + WINDOW - 1));
+        public_values[0] += Val::one();
+        assert_constraints_fail!(&MovingAverageAir {}, &trace, &public_values, 15);
+    }
+
+    #[test]
+    fn test_rounded_up_term_fails() {
+        // a term one unit too large, with the sums following it, has no valid remainder
+        let (mut trace, public_values) = generate_trace(&random_readings(8 + WINDOW - 1));
+        trace.row_mut(2)[TERMS_COL] += Val::one();
+        for col in FP_ROW_WIDTH - (WINDOW - 1)..FP_ROW_WIDTH {
+            trace.row_mut(2)[col] += Val::one();
+        }
+        assert_constraints_fail!(&MovingAverageAir {}, &trace, &public_values, 2);
+    }
+
+    #[test]
+    fn test_fixed_point_round_trip() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let (trace, public_values) = generate_trace(&random_readings(64 + WINDOW - 1));
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &MovingAverageAir {}, &mut p_challenger, trace, &public_values);
+        let mut v_challenger = Challenger::new(perm);
+        verify(&config, &MovingAverageAir {}, &mut v_challenger, &proof, &public_values).unwrap();
+    }
+}
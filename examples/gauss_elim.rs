@@ -0,0 +1,265 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_field::{AbstractField, Field};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::{prove, verify};
+use plonky3_cook::config::{default_config, random_perm, Challenger, Val};
+use plonky3_cook::gadgets::one_hot::{assert_one_hot, select};
+use rand::{thread_rng, Rng};
+use tracing_forest::{util::LevelFilter, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+// Full rank of an `N x N` matrix `A` (the public values), shown by Gaussian elimination: row operations
+// `target -= multiplier * pivot` don't change the rank, and they take `A` to an upper triangular matrix
+// whose diagonal has no zeros, which has rank `N`.
+//
+// Each row of the trace holds the whole matrix before one elimination step, plus the step itself:
+// `pivot_row`, `target_row`, `multiplier` and `row_after = target_row - multiplier * pivot_row`. The steps
+// follow the fixed schedule `(k, i)` for `k < i`, clearing column `k` of row `i` with row `k`, and after the
+// last one rows repeat until the end of the trace. Which step a row is in is a one-hot phase vector pinned
+// to that schedule, and `pivot_row`/`target_row` are the phase-selected rows of the matrix, so a step can't
+// use rows other than the schedule's. The last row must be in the hold phase, so the trace is tall enough to
+// run the whole schedule; the verifier takes the height from the proof, so nothing else would. Each step
+// proves its pivot nonzero with an inverse; the last pivot, `M[N-1][N-1]`, is only reached after the
+// schedule, and its inverse sits in the `pivot_inv` column of the last row, which the hold phase otherwise
+// leaves free.
+//
+// There is no row swapping, so a full-rank matrix that meets a zero pivot (e.g. `A[0][0] == 0`) can't be
+// proven as is; permuting its rows first, which the verifier can check, gets around that.
+
+const N: usize = 4;
+/// `(pivot, target)` for each elimination step
+const STEPS: [(usize, usize); N * (N - 1) / 2] = [(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)];
+
+/// one per step, then hold
+const NUM_PHASES: usize = STEPS.len() + 1;
+const HOLD: usize = NUM_PHASES - 1;
+
+const GE_ROW_WIDTH: usize = NUM_PHASES + N * N + 3 * N + 2;
+const GE_HEIGHT: usize = 8;
+
+struct GaussElimAir {}
+
+impl<F> BaseAir<F> for GaussElimAir {
+    fn width(&self) -> usize {
+        GE_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues> Air<AB> for GaussElimAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &GaussElimRow<AB::Var> = (*local).borrow();
+        let next: &GaussElimRow<AB::Var> = (*next).borrow();
+
+        let a = builder.public_values().iter().map(|&v| v.into()).collect::<Vec<AB::Expr>>();
+
+        // the schedule: the steps in order, then hold
+        assert_one_hot(builder, &local.phase);
+        builder.when_first_row().assert_one(local.phase[0]);
+        for p in 1..HOLD {
+            builder.when_transition().assert_eq(next.phase[p], local.phase[p - 1]);
+        }
+        builder.when_transition().assert_eq(next.phase[HOLD], local.phase[HOLD - 1] + local.phase[HOLD]);
+        builder.when_last_row().assert_one(local.phase[HOLD]);
+        let steps = &local.phase[..HOLD];
+
+        // the step, on the rows the schedule picks; all zero on hold
+        for c in 0..N {
+            let pivot: AB::Expr = select(steps, STEPS.iter().map(|&(k, _)| local.matrix[k][c]));
+            let target: AB::Expr = select(steps, STEPS.iter().map(|&(_, i)| local.matrix[i][c]));
+            builder.assert_eq(local.pivot_row[c], pivot);
+            builder.assert_eq(local.target_row[c], target);
+            builder.assert_eq(local.row_after[c], local.target_row[c] - local.multiplier * local.pivot_row[c]);
+        }
+        builder.when(local.phase[HOLD]).assert_zero(local.multiplier);
+
+        // the step clears column `k` of the target, and its pivot `M[k][k]` is nonzero
+        let cleared: AB::Expr = select(steps, STEPS.iter().map(|&(k, _)| local.row_after[k]));
+        builder.assert_zero(cleared);
+        let pivot: AB::Expr = select(steps, STEPS.iter().map(|&(k, _)| local.pivot_row[k]));
+        builder.assert_eq(pivot * local.pivot_inv, AB::Expr::one() - local.phase[HOLD]);
+
+        // the next matrix: the target row replaced, the others carried over
+        for r in 0..N {
+            let replaced: AB::Expr = select(
+                steps,
+                STEPS.iter().map(|&(_, i)| AB::Expr::from_bool(i == r)),
+            );
+            for c in 0..N {
+                let change = replaced.clone() * (local.row_after[c] - local.matrix[r][c]);
+                builder.when_transition().assert_eq(next.matrix[r][c], local.matrix[r][c] + change);
+            }
+        }
+
+        // `A`, and the upper triangular result with a nonzero last pivot
+        for r in 0..N {
+            for c in 0..N {
+                builder.when_first_row().assert_eq(local.matrix[r][c], a[r * N + c].clone());
+            }
+            for c in 0..r {
+                builder.when_last_row().assert_zero(local.matrix[r][c]);
+            }
+        }
+        builder.when_last_row().assert_one(local.matrix[N - 1][N - 1] * local.pivot_inv);
+    }
+}
+
+struct GaussElimRow<F> {
+    pub phase: [F; NUM_PHASES],
+    /// the matrix before this row's step
+    pub matrix: [[F; N]; N],
+    pub pivot_row: [F; N],
+    pub target_row: [F; N],
+    pub multiplier: F,
+    pub row_after: [F; N],
+    /// the inverse of this step's pivot; on the last row, of `M[N-1][N-1]`
+    pub pivot_inv: F,
+}
+
+impl<F> Borrow<GaussElimRow<F>> for [F] {
+    fn borrow(&self) -> &GaussElimRow<F> {
+        debug_assert_eq!(self.len(), GE_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<GaussElimRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+/// The trace eliminating `a`, and `a` flattened as the public values. A zero pivot gets a zero multiplier
+/// and inverse, which the constraints reject.
+fn generate_trace(a: [[Val; N]; N]) -> (RowMajorMatrix<Val>, Vec<Val>) {
+    let mut trace = RowMajorMatrix::new(vec![Val::zero(); GE_HEIGHT * GE_ROW_WIDTH], GE_ROW_WIDTH);
+
+    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<GaussElimRow<Val>>() };
+    assert!(prefix.is_empty(), "Alignment should match");
+    assert!(suffix.is_empty(), "Alignment should match");
+    assert_eq!(rows.len(), GE_HEIGHT);
+
+    let mut matrix = a;
+    for (r, row) in rows.iter_mut().enumerate() {
+        row.matrix = matrix;
+        let Some(&(k, i)) = STEPS.get(r) else {
+            row.phase[HOLD] = Val::one();
+            row.pivot_inv = matrix[N - 1][N - 1].try_inverse().unwrap_or(Val::zero());
+            continue;
+        };
+
+        row.phase[r] = Val::one();
+        row.pivot_row = matrix[k];
+        row.target_row = matrix[i];
+        row.pivot_inv = matrix[k][k].try_inverse().unwrap_or(Val::zero());
+        row.multiplier = matrix[i][k] * row.pivot_inv;
+        row.row_after = core::array::from_fn(|c| row.target_row[c] - row.multiplier * row.pivot_row[c]);
+        matrix[i] = row.row_after;
+    }
+
+    (trace, a.iter().flatten().copied().collect())
+}
+
+fn random_matrix() -> [[Val; N]; N] {
+    let mut rng = thread_rng();
+    core::array::from_fn(|_| core::array::from_fn(|_| rng.gen()))
+}
+
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+
+    let a = random_matrix();
+    let (trace, public_values) = generate_trace(a);
+
+    let mut p_challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &GaussElimAir {}, &mut p_challenger, trace, &public_values);
+    let mut v_challenger = Challenger::new(perm);
+    verify(&config, &GaussElimAir {}, &mut v_challenger, &proof, &public_values).unwrap();
+
+    println!("proven: {:?} has rank {}", a, N);
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky3_cook::{assert_constraints_fail, assert_constraints_ok};
+
+    use super::*;
+
+    const MULTIPLIER_COL: usize = NUM_PHASES + N * N + 2 * N;
+
+    fn matrix_of(rows: [[u32; N]; N]) -> [[Val; N]; N] {
+        rows.map(|row| row.map(Val::from_canonical_u32))
+    }
+
+    #[test]
+    fn test_full_rank() {
+        let (trace, public_values) = generate_trace(random_matrix());
+        assert_constraints_ok!(&GaussElimAir {}, &trace, &public_values);
+
+        let a = matrix_of([[2, 1, 0, 3], [4, 5, 1, 1], [0, 3, 7, 2], [1, 1, 1, 1]]);
+        let (trace, public_values) = generate_trace(a);
+        assert_constraints_ok!(&GaussElimAir {}, &trace, &public_values);
+    }
+
+    #[test]
+    fn test_singular_fails() {
+        // the last row is the sum of the first two, so the last pivot ends up zero
+        let a = matrix_of([[2, 1, 0, 3], [4, 5, 1, 1], [0, 3, 7, 2], [6, 6, 1, 4]]);
+        let (trace, public_values) = generate_trace(a);
+        assert_constraints_fail!(&GaussElimAir {}, &trace, &public_values, GE_HEIGHT - 1);
+    }
+
+    #[test]
+    fn test_zero_pivot_fails() {
+        let a = matrix_of([[0, 1, 0, 3], [4, 5, 1, 1], [0, 3, 7, 2], [1, 1, 1, 1]]);
+        let (trace, public_values) = generate_trace(a);
+        assert_constraints_fail!(&GaussElimAir {}, &trace, &public_values, 0);
+    }
+
+    #[test]
+    fn test_wrong_multiplier_fails() {
+        let (mut trace, public_values) = generate_trace(random_matrix());
+        trace.row_mut(3)[MULTIPLIER_COL] += Val::one();
+        assert_constraints_fail!(&GaussElimAir {}, &trace, &public_values, 3);
+    }
+
+    #[test]
+    fn test_other_matrix_fails() {
+        let (trace, mut public_values) = generate_trace(random_matrix());
+        public_values[5] += Val::one();
+        assert_constraints_fail!(&GaussElimAir {}, &trace, &public_values, 0);
+    }
+
+    #[test]
+    fn test_short_trace_fails() {
+        // four rows stop partway through the schedule
+        let a = matrix_of([[2, 1, 0, 3], [4, 5, 1, 1], [0, 3, 7, 2], [6, 6, 1, 4]]);
+        let (trace, public_values) = generate_trace(a);
+        let short = RowMajorMatrix::new(trace.values[..4 * GE_ROW_WIDTH].to_vec(), GE_ROW_WIDTH);
+        assert_constraints_fail!(&GaussElimAir {}, &short, &public_values, 3);
+    }
+
+    #[test]
+    fn test_gauss_elim_round_trip() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let (trace, public_values) = generate_trace(random_matrix());
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &GaussElimAir {}, &mut p_challenger, trace, &public_values);
+        let mut v_challenger = Challenger::new(perm);
+        verify(&config, &GaussElimAir {}, &mut v_challenger, &proof, &public_values).unwrap();
+    }
+}
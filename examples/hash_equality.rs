@@ -0,0 +1,251 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_field::{AbstractField, Field};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_symmetric::Permutation;
+use p3_uni_stark::{prove, verify};
+use plonky3_cook::config::{default_config, random_perm, Challenger, Val};
+use plonky3_cook::poseidon2_air::{
+    eval_permutation, generate_permutation, PermutationCols, Poseidon2Constants, PERMUTATION_WIDTH, WIDTH,
+};
+use rand::{thread_rng, Rng};
+use tracing_forest::{util::LevelFilter, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+// Proves whether two messages in the trace hash to the same digest, with only that bit public.
+//
+// The trace runs two independent hash chains side by side, each absorbing one input per row with a Poseidon2
+// permutation as in `checksum.rs`: `state' = Poseidon2([state, input, 0, ...])[..8]`. On the last row the
+// public bit `equal` is checked against the final digests, limb by limb with `d = digest_a - digest_b`:
+//   equal * d[i] == 0                          equal digests, if claimed
+//   (1 - equal) * (sum d[i] * w[i] - 1) == 0   some limb differs otherwise: `w` inverts one nonzero limb
+//
+// uni-stark proofs are not zero-knowledge: the openings at the out-of-domain point depend on the messages, so
+// this shows the two messages hash alike without revealing them directly, but it does not hide them.
+
+const DIGEST_LEN: usize = 8;
+
+const CHAIN_WIDTH: usize = 1 + PERMUTATION_WIDTH;
+const HE_ROW_WIDTH: usize = 2 * CHAIN_WIDTH + DIGEST_LEN;
+
+struct HashEqualityAir {
+    constants: Poseidon2Constants,
+}
+
+impl<F> BaseAir<F> for HashEqualityAir {
+    fn width(&self) -> usize {
+        HE_ROW_WIDTH
+    }
+}
+
+/// Constrains one chain and returns this row's digest.
+fn eval_chain<AB: AirBuilder<F = Val>>(
+    builder: &mut AB,
+    c: &Poseidon2Constants,
+    local: &ChainRow<AB::Var>,
+    next: &ChainRow<AB::Var>,
+) -> Vec<AB::Expr> {
+    let out = eval_permutation(builder, c, &local.perm);
+    let inputs = local.perm.inputs;
+    builder.assert_eq(inputs[DIGEST_LEN], local.input);
+    for &input in &inputs[DIGEST_LEN + 1..] {
+        builder.assert_zero(input);
+    }
+    for i in 0..DIGEST_LEN {
+        builder.when_first_row().assert_zero(inputs[i]);
+        builder.when_transition().assert_eq(next.perm.inputs[i], out[i].clone());
+    }
+    out[..DIGEST_LEN].to_vec()
+}
+
+impl<AB: AirBuilderWithPublicValues<F = Val>> Air<AB> for HashEqualityAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &HashEqualityRow<AB::Var> = (*local).borrow();
+        let next: &HashEqualityRow<AB::Var> = (*next).borrow();
+
+        let equal: AB::Expr = builder.public_values()[0].into();
+
+        let digest_a = eval_chain(builder, &self.constants, &local.a, &next.a);
+        let digest_b = eval_chain(builder, &self.constants, &local.b, &next.b);
+
+        let diffs = digest_a.into_iter().zip(digest_b).map(|(a, b)| a - b).collect::<Vec<_>>();
+        for diff in &diffs {
+            builder.when_last_row().assert_zero(equal.clone() * diff.clone());
+        }
+        let witnessed = diffs.into_iter().zip(local.unequal_witness).map(|(d, w)| d * w).sum::<AB::Expr>();
+        builder.when_last_row().assert_zero((AB::Expr::one() - equal) * (witnessed - AB::Expr::one()));
+    }
+}
+
+struct ChainRow<F> {
+    pub input: F,
+    /// `[state, input, 0, ..., 0]`
+    pub perm: PermutationCols<F>,
+}
+
+struct HashEqualityRow<F> {
+    pub a: ChainRow<F>,
+    pub b: ChainRow<F>,
+    /// on the last row, the inverse of the first limb where the digests differ, zero elsewhere
+    pub unequal_witness: [F; DIGEST_LEN],
+}
+
+impl<F> Borrow<HashEqualityRow<F>> for [F] {
+    fn borrow(&self) -> &HashEqualityRow<F> {
+        debug_assert_eq!(self.len(), HE_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<HashEqualityRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+fn absorb_inputs(state: [Val; DIGEST_LEN], input: Val) -> [Val; WIDTH] {
+    let mut inputs = [Val::zero(); WIDTH];
+    inputs[..DIGEST_LEN].copy_from_slice(&state);
+    inputs[DIGEST_LEN] = input;
+    inputs
+}
+
+/// Hashes `inputs` directly, without building a trace.
+fn hash_of(c: &Poseidon2Constants, inputs: &[Val]) -> [Val; DIGEST_LEN] {
+    let perm = c.perm();
+    inputs.iter().fold([Val::zero(); DIGEST_LEN], |state, &input| {
+        perm.permute(absorb_inputs(state, input))[..DIGEST_LEN].try_into().unwrap()
+    })
+}
+
+/// Fills one chain's columns; returns the state after absorbing `input`.
+fn chain_row(
+    c: &Poseidon2Constants,
+    state: [Val; DIGEST_LEN],
+    input: Val,
+    row: &mut ChainRow<Val>,
+) -> [Val; DIGEST_LEN] {
+    row.input = input;
+    generate_permutation(c, absorb_inputs(state, input), &mut row.perm)[..DIGEST_LEN].try_into().unwrap()
+}
+
+/// Returns the trace together with the equality bit, which is the public value.
+fn generate_trace(c: &Poseidon2Constants, a_inputs: &[Val], b_inputs: &[Val]) -> (RowMajorMatrix<Val>, Val) {
+    let n = a_inputs.len();
+    assert_eq!(n, b_inputs.len(), "both messages must have the same length");
+    assert!(n.is_power_of_two(), "trace height must be a power of two");
+
+    let mut trace = RowMajorMatrix::new(vec![Val::zero(); n * HE_ROW_WIDTH], HE_ROW_WIDTH);
+
+    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<HashEqualityRow<Val>>() };
+    assert!(prefix.is_empty(), "Alignment should match");
+    assert!(suffix.is_empty(), "Alignment should match");
+    assert_eq!(rows.len(), n);
+
+    let (mut state_a, mut state_b) = ([Val::zero(); DIGEST_LEN], [Val::zero(); DIGEST_LEN]);
+    for (row, (&a, &b)) in rows.iter_mut().zip(a_inputs.iter().zip(b_inputs)) {
+        state_a = chain_row(c, state_a, a, &mut row.a);
+        state_b = chain_row(c, state_b, b, &mut row.b);
+    }
+
+    if let Some(i) = (0..DIGEST_LEN).find(|&i| state_a[i] != state_b[i]) {
+        rows[n - 1].unequal_witness[i] = (state_a[i] - state_b[i]).inverse();
+    }
+    (trace, Val::from_bool(state_a == state_b))
+}
+
+fn random_inputs(n: usize) -> Vec<Val> {
+    let mut rng = thread_rng();
+    (0..n).map(|_| rng.gen()).collect()
+}
+
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+    let air = HashEqualityAir { constants: Poseidon2Constants::from_seed(0x68736571) };
+
+    // the two parties hold the same message, computed independently
+    let message = random_inputs(1024);
+    let (trace, equal) = generate_trace(&air.constants, &message, &message.clone());
+
+    let mut p_challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &air, &mut p_challenger, trace, &vec![equal]);
+    let mut v_challenger = Challenger::new(perm);
+    verify(&config, &air, &mut v_challenger, &proof, &vec![equal]).unwrap();
+
+    println!("proven digests equal: {}", equal.is_one());
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky3_cook::{assert_constraints_fail, assert_constraints_ok};
+
+    use super::*;
+
+    const WITNESS_COL: usize = 2 * CHAIN_WIDTH;
+
+    fn air() -> HashEqualityAir {
+        HashEqualityAir { constants: Poseidon2Constants::from_seed(0x68736571) }
+    }
+
+    #[test]
+    fn test_equal_digests() {
+        let air = air();
+        let message = random_inputs(1 << 6);
+        let (trace, equal) = generate_trace(&air.constants, &message, &message);
+        assert_eq!(equal, Val::one());
+        assert_constraints_ok!(&air, &trace, &[Val::one()]);
+
+        // the prover can't deny the equality
+        assert_constraints_fail!(&air, &trace, &[Val::zero()], (1 << 6) - 1);
+    }
+
+    #[test]
+    fn test_different_digests() {
+        let air = air();
+        let a = random_inputs(1 << 6);
+        let mut b = a.clone();
+        b[3] += Val::one();
+        assert_ne!(hash_of(&air.constants, &a), hash_of(&air.constants, &b));
+
+        let (mut trace, equal) = generate_trace(&air.constants, &a, &b);
+        assert_eq!(equal, Val::zero());
+        assert_constraints_ok!(&air, &trace, &[Val::zero()]);
+        assert_constraints_fail!(&air, &trace, &[Val::one()], (1 << 6) - 1);
+
+        // a witness that doesn't invert a differing limb doesn't show inequality
+        let last = trace.height() - 1;
+        trace.row_mut(last)[WITNESS_COL..].fill(Val::zero());
+        assert_constraints_fail!(&air, &trace, &[Val::zero()], last);
+    }
+
+    #[test]
+    fn test_equality_proof_round_trip() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let air = air();
+
+        let message = random_inputs(64);
+        let (trace, equal) = generate_trace(&air.constants, &message, &message);
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &air, &mut p_challenger, trace, &vec![equal]);
+
+        let mut v_challenger = Challenger::new(perm.clone());
+        verify(&config, &air, &mut v_challenger, &proof, &vec![Val::one()]).unwrap();
+
+        let mut v_challenger = Challenger::new(perm);
+        assert!(verify(&config, &air, &mut v_challenger, &proof, &vec![Val::zero()]).is_err());
+    }
+}
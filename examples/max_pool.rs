@@ -0,0 +1,204 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, BaseAir};
+use p3_field::{AbstractField, PrimeField32};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::{prove, verify};
+use plonky3_cook::config::{default_config, random_perm, Challenger, Val};
+use plonky3_cook::gadgets::less_than::{
+    assert_bit_decomposition, assert_lt_indicator, bit_decompose, lt_indicator_witness,
+};
+use plonky3_cook::gadgets::one_hot::select_if;
+use rand::{thread_rng, Rng};
+use tracing_forest::{util::LevelFilter, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+// 1-D max pooling with window 2: `result[i] = max(input[2i], input[2i + 1])`, one window per row.
+//
+// Activations are quantized to `VALUE_BITS`-bit unsigned integers, and each row range checks `a` and `b` with
+// their bits. `cond` is the less-than indicator for `a < b` from the `less_than` gadget, which range checks
+// whichever difference `cond` selects (`diff`, with its bits); with both inputs in range only the true answer
+// has a difference that fits, so no inverse witness is needed. Without the input range checks it could be
+// forged: `a = p - 1, b = 0` gives `b - a - 1 = 0`, which fits. The maximum is then `select_if(cond, b, a)`, a
+// degree-2 constraint.
+//
+// This proves one layer in isolation. In a network the pooled column feeds the next layer, which would tie
+// `max_val` to that layer's inputs with a lookup or a permutation argument.
+
+const VALUE_BITS: usize = 16;
+
+const MP_ROW_WIDTH: usize = 5 + 3 * VALUE_BITS;
+
+struct MaxPoolAir {}
+
+impl<F> BaseAir<F> for MaxPoolAir {
+    fn width(&self) -> usize {
+        MP_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilder> Air<AB> for MaxPoolAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = main.row_slice(0);
+        let local: &MaxPoolRow<AB::Var> = (*local).borrow();
+
+        assert_bit_decomposition(builder, local.a, &local.a_bits);
+        assert_bit_decomposition(builder, local.b, &local.b_bits);
+        assert_lt_indicator(builder, local.a, local.b, local.cond, local.diff, &local.diff_bits);
+        builder.assert_eq(local.max_val, select_if::<AB::Expr>(local.cond, local.b, local.a));
+    }
+}
+
+struct MaxPoolRow<F> {
+    pub a: F,
+    pub b: F,
+    pub max_val: F,
+    /// `a < b`
+    pub cond: F,
+    /// `b - a - 1` when `cond`, else `a - b`
+    pub diff: F,
+    pub diff_bits: [F; VALUE_BITS],
+    pub a_bits: [F; VALUE_BITS],
+    pub b_bits: [F; VALUE_BITS],
+}
+
+impl<F> Borrow<MaxPoolRow<F>> for [F] {
+    fn borrow(&self) -> &MaxPoolRow<F> {
+        debug_assert_eq!(self.len(), MP_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<MaxPoolRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+/// Pools `input` in windows of two; returns the trace and the pooled values.
+fn generate_trace<F: PrimeField32>(input: &[u32]) -> (RowMajorMatrix<F>, Vec<u32>) {
+    let n = input.len() / 2;
+    assert_eq!(input.len(), 2 * n, "input length must be even");
+    assert!(n.is_power_of_two(), "trace height must be a power of two");
+    assert!(input.iter().all(|&x| x >> VALUE_BITS == 0), "activations must fit in {} bits", VALUE_BITS);
+
+    let mut trace = RowMajorMatrix::new(vec![F::zero(); n * MP_ROW_WIDTH], MP_ROW_WIDTH);
+
+    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<MaxPoolRow<F>>() };
+    assert!(prefix.is_empty(), "Alignment should match");
+    assert!(suffix.is_empty(), "Alignment should match");
+    assert_eq!(rows.len(), n);
+
+    let mut pooled = Vec::with_capacity(n);
+    for (row, window) in rows.iter_mut().zip(input.chunks_exact(2)) {
+        let (a, b) = (F::from_canonical_u32(window[0]), F::from_canonical_u32(window[1]));
+        let (cond, diff, bits) = lt_indicator_witness(a, b, VALUE_BITS);
+        let max = window[0].max(window[1]);
+
+        row.a = a;
+        row.b = b;
+        row.max_val = F::from_canonical_u32(max);
+        row.cond = cond;
+        row.diff = diff;
+        row.diff_bits.copy_from_slice(&bits);
+        row.a_bits.copy_from_slice(&bit_decompose(a, VALUE_BITS));
+        row.b_bits.copy_from_slice(&bit_decompose(b, VALUE_BITS));
+        pooled.push(max);
+    }
+
+    (trace, pooled)
+}
+
+fn random_activations(n: usize) -> Vec<u32> {
+    let mut rng = thread_rng();
+    (0..n).map(|_| rng.gen_range(0..1 << VALUE_BITS)).collect()
+}
+
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+
+    let input = random_activations(1 << 11);
+    let (trace, pooled) = generate_trace::<Val>(&input);
+
+    let mut p_challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &MaxPoolAir {}, &mut p_challenger, trace, &vec![]);
+    let mut v_challenger = Challenger::new(perm);
+    verify(&config, &MaxPoolAir {}, &mut v_challenger, &proof, &vec![]).unwrap();
+
+    println!("proven: {} activations pooled to {}", input.len(), pooled.len());
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky3_cook::{assert_constraints_fail, assert_constraints_ok};
+
+    use super::*;
+
+    #[test]
+    fn test_max_pool_constraints() {
+        // includes ties and both orders
+        let mut input = random_activations(1 << 8);
+        input[..6].copy_from_slice(&[5, 5, 2, 9, 9, 2]);
+        let (trace, pooled) = generate_trace::<Val>(&input);
+        assert_eq!(pooled[..3], [5, 9, 9]);
+        assert_constraints_ok!(&MaxPoolAir {}, &trace, &[]);
+    }
+
+    #[test]
+    fn test_min_instead_of_max_fails() {
+        let mut input = random_activations(1 << 4);
+        input[6..8].copy_from_slice(&[2, 9]);
+        let (mut trace, _) = generate_trace::<Val>(&input);
+        trace.row_mut(3)[2] = Val::two();
+        assert_constraints_fail!(&MaxPoolAir {}, &trace, &[], 3);
+    }
+
+    #[test]
+    fn test_lying_indicator_fails() {
+        // claim 2 >= 9 and pick 2; no 16-bit difference supports it
+        let mut input = random_activations(1 << 4);
+        input[6..8].copy_from_slice(&[2, 9]);
+        let (mut trace, _) = generate_trace::<Val>(&input);
+        let row = trace.row_mut(3);
+        row[2] = Val::two();
+        row[3] = Val::zero();
+        row[4] = Val::two() - Val::from_canonical_u32(9);
+        assert_constraints_fail!(&MaxPoolAir {}, &trace, &[], 3);
+    }
+
+    #[test]
+    fn test_out_of_range_input_fails() {
+        // `a = p - 1, b = 0` with `cond = 1`: `diff = b - a - 1 = 0` fits, so only the range check on `a` stops it
+        let mut input = random_activations(1 << 4);
+        input[6..8].copy_from_slice(&[0, 0]);
+        let (mut trace, _) = generate_trace::<Val>(&input);
+        let row = trace.row_mut(3);
+        row[0] = Val::neg_one();
+        row[2] = Val::zero();
+        row[3] = Val::one();
+        row[4] = Val::zero();
+        assert_constraints_fail!(&MaxPoolAir {}, &trace, &[], 3);
+    }
+
+    #[test]
+    fn test_max_pool_round_trip() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let (trace, _) = generate_trace::<Val>(&random_activations(1 << 9));
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &MaxPoolAir {}, &mut p_challenger, trace, &vec![]);
+        let mut v_challenger = Challenger::new(perm);
+        verify(&config, &MaxPoolAir {}, &mut v_challenger, &proof, &vec![]).unwrap();
+    }
+}
@@ -0,0 +1,258 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_field::{AbstractField, PrimeField32};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::{prove, verify};
+use plonky3_cook::config::{default_config, random_perm, Challenger, Val};
+use plonky3_cook::gadgets::comparison::{assert_le, le_witness};
+use plonky3_cook::peek_ahead::{expand_trace, PeekAhead, PeekAheadAirBuilder};
+use rand::{thread_rng, Rng};
+use tracing_forest::{util::LevelFilter, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+// One merge step of Batcher's odd-even merge sort: sorted runs of two become sorted runs of four. A block
+// of four rows holds two sorted runs `[a0, a1 | b0, b1]`, and the merge network is two layers of
+// compare-exchanges,
+//   distance 2:   (x0, x2), (x1, x3)   input -> mid
+//   distance 1:   (x1, x2)             mid -> output
+// after which the block is sorted. A compare-exchange between rows `i` and `i + 2` is a constraint reading
+// two rows ahead, so the AIR runs under `PeekAhead<_, 2>`: row `i` sees rows `i + 1` and `i + 2` through
+// `peek`. A one-hot `pos` says where a row sits in its block; rows 0 and 1 of a block own the distance-2
+// exchanges, row 1 the distance-1 exchange, and rows 0 and 2 check that their run is sorted.
+//
+// Each exchange `(x, y) -> (lo, hi)` is a boolean `swap` with `lo = x + swap * (y - x)` and
+// `hi = y + swap * (x - y)`, plus the bits of `hi - lo` to show `lo <= hi`, so the values must stay below
+// `2^VALUE_BITS`.
+
+const VALUE_BITS: usize = 16;
+const BLOCK: usize = 4;
+
+const MS_ROW_WIDTH: usize = BLOCK + 3 + 3 * VALUE_BITS + 2;
+const MS_HEIGHT: usize = 64;
+
+struct OddEvenMerge {}
+
+impl<F> BaseAir<F> for OddEvenMerge {
+    fn width(&self) -> usize {
+        MS_ROW_WIDTH
+    }
+}
+
+/// `(lo, hi)` is `(x, y)` in order, swapped if `swap`.
+fn assert_compare_exchange<AB: AirBuilder>(
+    builder: &mut AB,
+    (x, y): (AB::Var, AB::Var),
+    (lo, hi): (AB::Var, AB::Var),
+    swap: AB::Var,
+    diff_bits: &[AB::Var],
+) {
+    builder.assert_bool(swap);
+    builder.assert_eq(lo, x + (y - x) * swap);
+    builder.assert_eq(hi, y + (x - y) * swap);
+    assert_le(builder, lo, hi, diff_bits);
+}
+
+impl<'a, AB: AirBuilderWithPublicValues> Air<PeekAheadAirBuilder<'a, AB, 2>> for OddEvenMerge {
+    fn eval(&self, builder: &mut PeekAheadAirBuilder<'a, AB, 2>) {
+        let rows = (0..=2).map(|k| builder.peek(k).to_vec()).collect::<Vec<_>>();
+        let (local, next, next2): (&MergeRow<AB::Var>, &MergeRow<AB::Var>, &MergeRow<AB::Var>) =
+            ((*rows[0]).borrow(), (*rows[1]).borrow(), (*rows[2]).borrow());
+
+        // the position in the block
+        builder.when_first_row().assert_one(local.pos[0]);
+        for j in 0..BLOCK {
+            builder.assert_bool(local.pos[j]);
+            builder.when_transition().assert_eq(next.pos[(j + 1) % BLOCK], local.pos[j]);
+        }
+        builder.assert_one(local.pos.iter().map(|&p| p.into()).sum::<AB::Expr>());
+
+        // the runs are sorted
+        assert_le(&mut builder.when(local.pos[0] + local.pos[2]), local.input, next.input, &local.run_bits);
+
+        // distance 2, from rows 0 and 1 of the block
+        assert_compare_exchange(
+            &mut builder.when(local.pos[0] + local.pos[1]),
+            (local.input, next2.input),
+            (local.mid, next2.mid),
+            local.far_swap,
+            &local.far_bits,
+        );
+
+        // distance 1, from row 1; rows 0 and 3 pass through
+        assert_compare_exchange(
+            &mut builder.when(local.pos[1]),
+            (local.mid, next.mid),
+            (local.output, next.output),
+            local.near_swap,
+            &local.near_bits,
+        );
+        builder.when(local.pos[0] + local.pos[3]).assert_eq(local.output, local.mid);
+    }
+}
+
+struct MergeRow<F> {
+    /// one-hot, the row's position in its block
+    pub pos: [F; BLOCK],
+    pub input: F,
+    /// after the distance-2 layer
+    pub mid: F,
+    /// after the distance-1 layer
+    pub output: F,
+    /// `next.input - input`, on the first row of each run
+    pub run_bits: [F; VALUE_BITS],
+    pub far_swap: F,
+    pub far_bits: [F; VALUE_BITS],
+    pub near_swap: F,
+    pub near_bits: [F; VALUE_BITS],
+}
+
+impl<F> Borrow<MergeRow<F>> for [F] {
+    fn borrow(&self) -> &MergeRow<F> {
+        debug_assert_eq!(self.len(), MS_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<MergeRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+/// `(min, max, swapped)` of one compare-exchange.
+fn exchange(x: u32, y: u32) -> (u32, u32, bool) {
+    (x.min(y), x.max(y), x > y)
+}
+
+/// The trace merging each block of `values`, which must be sorted pairs, before expansion.
+fn generate_trace(values: &[u32]) -> RowMajorMatrix<Val> {
+    let n = values.len();
+    assert!(n.is_power_of_two() && n >= BLOCK, "the trace height must be a power of two of whole blocks");
+    let mut trace = RowMajorMatrix::new(vec![Val::zero(); n * MS_ROW_WIDTH], MS_ROW_WIDTH);
+    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<MergeRow<Val>>() };
+    assert!(prefix.is_empty(), "Alignment should match");
+    assert!(suffix.is_empty(), "Alignment should match");
+
+    let f = Val::from_canonical_u32;
+    for (block, x) in rows.chunks_exact_mut(BLOCK).zip(values.chunks_exact(BLOCK)) {
+        assert!(x[0] <= x[1] && x[2] <= x[3], "the runs must be sorted");
+        let mut mid = [0; BLOCK];
+        let mut out = [0; BLOCK];
+        for i in 0..2 {
+            let (lo, hi, swap) = exchange(x[i], x[i + 2]);
+            (mid[i], mid[i + 2]) = (lo, hi);
+            block[i].far_swap = Val::from_bool(swap);
+            block[i].far_bits.copy_from_slice(&le_witness(f(lo), f(hi), VALUE_BITS));
+        }
+        let (lo, hi, swap) = exchange(mid[1], mid[2]);
+        (out[0], out[1], out[2], out[3]) = (mid[0], lo, hi, mid[3]);
+        block[1].near_swap = Val::from_bool(swap);
+        block[1].near_bits.copy_from_slice(&le_witness(f(lo), f(hi), VALUE_BITS));
+        for i in [0, 2] {
+            block[i].run_bits.copy_from_slice(&le_witness(f(x[i]), f(x[i + 1]), VALUE_BITS));
+        }
+
+        for (i, row) in block.iter_mut().enumerate() {
+            row.pos[i] = Val::one();
+            (row.input, row.mid, row.output) = (f(x[i]), f(mid[i]), f(out[i]));
+        }
+    }
+
+    trace
+}
+
+/// `n` values in sorted pairs.
+fn random_runs(n: usize) -> Vec<u32> {
+    let mut rng = thread_rng();
+    let mut values = (0..n).map(|_| rng.gen_range(0..1 << VALUE_BITS)).collect::<Vec<u32>>();
+    values.chunks_exact_mut(2).for_each(|run| run.sort());
+    values
+}
+
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+    let air = PeekAhead::<_, 2> { inner: OddEvenMerge {} };
+
+    let values = random_runs(MS_HEIGHT);
+    let trace = generate_trace(&values);
+    let merged = (0..BLOCK).map(|r| trace.get(r, BLOCK + 2).as_canonical_u32()).collect::<Vec<_>>();
+
+    let mut p_challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &air, &mut p_challenger, expand_trace::<_, 2>(&trace), &vec![]);
+    let mut v_challenger = Challenger::new(perm);
+    verify(&config, &air, &mut v_challenger, &proof, &vec![]).unwrap();
+
+    println!(
+        "proven: {} runs of two merged into runs of four, the first {:?} -> {:?}",
+        MS_HEIGHT / 2,
+        &values[..BLOCK],
+        merged
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky3_cook::{assert_constraints_fail, assert_constraints_ok};
+
+    use super::*;
+
+    const INPUT_COL: usize = BLOCK;
+    const OUTPUT_COL: usize = BLOCK + 2;
+
+    const AIR: PeekAhead<OddEvenMerge, 2> = PeekAhead { inner: OddEvenMerge {} };
+
+    #[test]
+    fn test_blocks_come_out_sorted() {
+        let values = random_runs(16);
+        let trace = generate_trace(&values);
+        assert_constraints_ok!(&AIR, &expand_trace::<_, 2>(&trace), &[]);
+
+        for (b, block) in values.chunks_exact(BLOCK).enumerate() {
+            let mut sorted = block.to_vec();
+            sorted.sort();
+            let output = (0..BLOCK).map(|i| trace.get(b * BLOCK + i, OUTPUT_COL).as_canonical_u32());
+            assert_eq!(output.collect::<Vec<_>>(), sorted);
+        }
+    }
+
+    #[test]
+    fn test_unsorted_run_fails() {
+        let mut trace = generate_trace(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        // `[6, 5]` isn't a run
+        trace.row_mut(4)[INPUT_COL] = Val::from_canonical_u32(6);
+        trace.row_mut(5)[INPUT_COL] = Val::from_canonical_u32(5);
+        assert_constraints_fail!(&AIR, &expand_trace::<_, 2>(&trace), &[], 4);
+    }
+
+    #[test]
+    fn test_unmerged_output_fails() {
+        let mut trace = generate_trace(&[1, 3, 2, 4, 5, 6, 7, 8]);
+        // the middle pair of the first block left in the order the distance-2 layer put it in
+        let (o1, o2) = (trace.get(1, OUTPUT_COL), trace.get(2, OUTPUT_COL));
+        trace.row_mut(1)[OUTPUT_COL] = o2;
+        trace.row_mut(2)[OUTPUT_COL] = o1;
+        assert_constraints_fail!(&AIR, &expand_trace::<_, 2>(&trace), &[], 1);
+    }
+
+    #[test]
+    fn test_merge_sort_step_round_trip() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let trace = expand_trace::<_, 2>(&generate_trace(&random_runs(MS_HEIGHT)));
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &AIR, &mut p_challenger, trace, &vec![]);
+        let mut v_challenger = Challenger::new(perm);
+        verify(&config, &AIR, &mut v_challenger, &proof, &vec![]).unwrap();
+    }
+}
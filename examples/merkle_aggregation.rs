@@ -0,0 +1,319 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_commit::Mmcs;
+use p3_field::{AbstractField, PrimeField32};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::{prove, verify};
+use plonky3_cook::config::{default_config, random_perm, Challenger, MyCompress, MyHash, Val, ValMmcs};
+use plonky3_cook::gadgets::one_hot::assert_one_hot;
+use plonky3_cook::poseidon2_air::{
+    eval_permutation, generate_permutation, PermutationCols, Poseidon2Constants, PERMUTATION_WIDTH, WIDTH,
+};
+use rand::{thread_rng, Rng};
+use tracing_forest::{util::LevelFilter, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+// Aggregating Merkle roots: `K` sub-trees, each committed on its own, and a super-root committing to their
+// roots. The public values are the `K` sub-roots and the super-root; the proof shows that the super-root is
+// the Poseidon2 Merkle root of a tree whose leaves are the sub-roots, in order.
+//
+// The AIR is `examples/merkle_root.rs`'s, with one change: the tree's leaves are digests already, so a leaf
+// row pushes a sub-root as it is instead of hashing it. Rows walk the outer tree in post-order with a stack
+// of digests, nodes join two digests of the same level with `MyCompress`, and the root must end up alone at
+// level `LOG_K`. A one-hot `cursor` picks the public sub-root a leaf row pushes, and moves on by one at every
+// leaf, so the leaves are the sub-roots in order.
+//
+// Since `MyCompress` is also what joins the nodes inside each sub-tree, the super-root of `K` sub-trees of
+// depth `SUB_DEPTH` is exactly the root `ValMmcs` gives one tree over all their leaves. That's what makes the
+// aggregation useful: a verifier holding only the super-root can check an opening into any sub-tree by
+// extending its path with the `LOG_K` outer siblings, and separate commitments (e.g. one per AIR of a
+// batch) fold into one without re-hashing anything below their roots.
+
+const DIGEST_LEN: usize = 8;
+const K: usize = 4;
+const LOG_K: usize = 2;
+const SUB_DEPTH: usize = 3;
+/// a post-order walk over `K` leaves never holds more than `LOG_K + 1` digests
+const SLOTS: usize = LOG_K + 1;
+
+const MA_ROW_WIDTH: usize = 3 + K + SLOTS * (DIGEST_LEN + 2) + PERMUTATION_WIDTH;
+
+struct MerkleAggregation {
+    constants: Poseidon2Constants,
+}
+
+impl<F> BaseAir<F> for MerkleAggregation {
+    fn width(&self) -> usize {
+        MA_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues<F = Val>> Air<AB> for MerkleAggregation {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &AggregationRow<AB::Var> = (*local).borrow();
+        let next: &AggregationRow<AB::Var> = (*next).borrow();
+
+        let pis: Vec<AB::Expr> = builder.public_values().iter().map(|&v| v.into()).collect();
+        let (sub_roots, super_root) = pis.split_at(K * DIGEST_LEN);
+
+        let out = eval_permutation(builder, &self.constants, &local.perm);
+        let inputs = local.perm.inputs;
+        let (top, second) = (&local.slots[0], &local.slots[1]);
+
+        assert_one_hot(builder, &[local.is_leaf, local.is_node, local.is_hold]);
+
+        // a leaf is the sub-root under the cursor; a node needs two digests of the same level
+        let leaf = |i: usize| {
+            (0..K).map(|j| sub_roots[j * DIGEST_LEN + i].clone() * local.cursor[j]).sum::<AB::Expr>()
+        };
+        for i in 0..DIGEST_LEN {
+            builder.when(local.is_node).assert_eq(inputs[i], second.digest[i]);
+            builder.when(local.is_node).assert_eq(inputs[DIGEST_LEN + i], top.digest[i]);
+        }
+        builder.when(local.is_leaf).assert_zero(local.slots[SLOTS - 1].present);
+        builder.when(local.is_node).assert_one(top.present);
+        builder.when(local.is_node).assert_one(second.present);
+        builder.when(local.is_node).assert_eq(top.level, second.level);
+
+        // the cursor starts at the first sub-root and moves on at every leaf
+        builder.when_first_row().assert_one(local.cursor[0]);
+        for j in 1..K {
+            builder.when_first_row().assert_zero(local.cursor[j]);
+        }
+        let not_leaf = AB::Expr::one() - local.is_leaf;
+        let mut transition = builder.when_transition();
+        transition.assert_eq(next.cursor[0], not_leaf.clone() * local.cursor[0]);
+        for j in 1..K {
+            let cursor_j = local.is_leaf * local.cursor[j - 1] + not_leaf.clone() * local.cursor[j];
+            transition.assert_eq(next.cursor[j], cursor_j);
+        }
+
+        // the stack after this row's operation
+        let pushed = local.is_leaf + local.is_node;
+        for i in 0..DIGEST_LEN {
+            let digest_i = local.is_leaf * leaf(i) + local.is_node * out[i].clone() + local.is_hold * top.digest[i];
+            transition.assert_eq(next.slots[0].digest[i], digest_i);
+        }
+        let level = local.is_node * (top.level + AB::Expr::one()) + local.is_hold * top.level;
+        transition.assert_eq(next.slots[0].level, level);
+        transition.assert_eq(next.slots[0].present, pushed + local.is_hold * top.present);
+        for d in 1..SLOTS {
+            let (below, above) = (&local.slots[d - 1], local.slots.get(d + 1));
+            let shifted = |column: &dyn Fn(&StackSlot<AB::Var>) -> AB::Var| {
+                local.is_leaf * column(below)
+                    + local.is_node * above.map_or(AB::Expr::zero(), |above| column(above).into())
+                    + local.is_hold * column(&local.slots[d])
+            };
+            for i in 0..DIGEST_LEN {
+                transition.assert_eq(next.slots[d].digest[i], shifted(&|slot| slot.digest[i]));
+            }
+            transition.assert_eq(next.slots[d].level, shifted(&|slot| slot.level));
+            transition.assert_eq(next.slots[d].present, shifted(&|slot| slot.present));
+        }
+
+        // an empty stack to start, the super-root alone to finish
+        for slot in &local.slots {
+            builder.when_first_row().assert_zero(slot.present);
+        }
+        builder.when_last_row().assert_one(local.is_hold);
+        builder.when_last_row().assert_one(top.present);
+        builder.when_last_row().assert_zero(second.present);
+        builder.when_last_row().assert_eq(top.level, AB::Expr::from_canonical_usize(LOG_K));
+        for i in 0..DIGEST_LEN {
+            builder.when_last_row().assert_eq(top.digest[i], super_root[i].clone());
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct StackSlot<F> {
+    pub digest: [F; DIGEST_LEN],
+    /// 0 for a sub-root, one more than its children's for a node
+    pub level: F,
+    /// 1 if the slot holds a digest
+    pub present: F,
+}
+
+struct AggregationRow<F> {
+    pub is_leaf: F,
+    pub is_node: F,
+    pub is_hold: F,
+    /// one-hot: the sub-root the next leaf row pushes; all zero once every sub-root is pushed
+    pub cursor: [F; K],
+    /// the stack before this row's operation, top first
+    pub slots: [StackSlot<F>; SLOTS],
+    pub perm: PermutationCols<F>,
+}
+
+impl<F> Borrow<AggregationRow<F>> for [F] {
+    fn borrow(&self) -> &AggregationRow<F> {
+        debug_assert_eq!(self.len(), MA_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<AggregationRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+/// The root `ValMmcs` commits `leaves` to, as a matrix of width 8.
+fn mmcs_root(c: &Poseidon2Constants, leaves: &[[Val; DIGEST_LEN]]) -> [Val; DIGEST_LEN] {
+    let perm = c.perm();
+    let mmcs = ValMmcs::new(MyHash::new(perm.clone()), MyCompress::new(perm));
+    let (commitment, _) = mmcs.commit_matrix(RowMajorMatrix::new(leaves.concat(), DIGEST_LEN));
+    commitment.into()
+}
+
+/// The roots of `K` sub-trees of `2^SUB_DEPTH` leaves each.
+fn sub_roots(c: &Poseidon2Constants, leaves: &[[Val; DIGEST_LEN]]) -> Vec<[Val; DIGEST_LEN]> {
+    assert_eq!(leaves.len(), K << SUB_DEPTH);
+    leaves.chunks_exact(1 << SUB_DEPTH).map(|sub_tree| mmcs_root(c, sub_tree)).collect()
+}
+
+/// Returns the trace aggregating `sub_roots`, and `[sub_roots.., super_root]`.
+fn generate_trace(c: &Poseidon2Constants, sub_roots: &[[Val; DIGEST_LEN]]) -> (RowMajorMatrix<Val>, Vec<Val>) {
+    assert_eq!(sub_roots.len(), K);
+    let height = 2 * K;
+
+    let mut trace = RowMajorMatrix::new(vec![Val::zero(); height * MA_ROW_WIDTH], MA_ROW_WIDTH);
+    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<AggregationRow<Val>>() };
+    assert!(prefix.is_empty(), "Alignment should match");
+    assert!(suffix.is_empty(), "Alignment should match");
+
+    // post-order: after the `k`-th sub-root, one node per trailing zero of `k`
+    let mut rows = rows.iter_mut();
+    let mut pushed = 0;
+    let mut step = |stack: &[StackSlot<Val>], is_leaf: bool, inputs: [Val; WIDTH]| -> [Val; DIGEST_LEN] {
+        let row = rows.next().unwrap();
+        row.is_leaf = Val::from_bool(is_leaf);
+        row.is_node = Val::from_bool(!is_leaf);
+        if pushed < K {
+            row.cursor[pushed] = Val::one();
+        }
+        pushed += is_leaf as usize;
+        for (slot, &entry) in row.slots.iter_mut().zip(stack.iter().rev()) {
+            *slot = entry;
+        }
+        generate_permutation(c, inputs, &mut row.perm)[..DIGEST_LEN].try_into().unwrap()
+    };
+    let mut stack: Vec<StackSlot<Val>> = vec![];
+    for (k, &sub_root) in sub_roots.iter().enumerate() {
+        // leaf rows don't hash; their permutation runs on zeros
+        step(&stack, true, [Val::zero(); WIDTH]);
+        stack.push(StackSlot { digest: sub_root, level: Val::zero(), present: Val::one() });
+
+        for _ in 0..(k + 1).trailing_zeros() {
+            let (left, right) = (stack[stack.len() - 2], stack[stack.len() - 1]);
+            let mut inputs = [Val::zero(); WIDTH];
+            inputs[..DIGEST_LEN].copy_from_slice(&left.digest);
+            inputs[DIGEST_LEN..].copy_from_slice(&right.digest);
+            let digest = step(&stack, false, inputs);
+            stack.truncate(stack.len() - 2);
+            stack.push(StackSlot { digest, level: left.level + Val::one(), present: Val::one() });
+        }
+    }
+
+    // hold rows: the super-root alone, hashing zeros
+    for row in rows {
+        row.is_hold = Val::one();
+        row.slots[0] = stack[0];
+        generate_permutation(c, [Val::zero(); WIDTH], &mut row.perm);
+    }
+
+    let mut public_values = sub_roots.concat();
+    public_values.extend(stack[0].digest);
+    (trace, public_values)
+}
+
+fn random_leaves(n: usize) -> Vec<[Val; DIGEST_LEN]> {
+    let mut rng = thread_rng();
+    (0..n).map(|_| rng.gen()).collect()
+}
+
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+
+    let constants = Poseidon2Constants::from_seed(0x6d61);
+    let leaves = random_leaves(K << SUB_DEPTH);
+    let (trace, public_values) = generate_trace(&constants, &sub_roots(&constants, &leaves));
+    let air = MerkleAggregation { constants };
+
+    let mut p_challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &air, &mut p_challenger, trace, &public_values);
+    let mut v_challenger = Challenger::new(perm);
+    verify(&config, &air, &mut v_challenger, &proof, &public_values).unwrap();
+
+    let super_root = public_values[K * DIGEST_LEN..].iter().map(|v| v.as_canonical_u32()).collect::<Vec<_>>();
+    println!("proven: {} sub-trees of depth {} aggregate to {:?}", K, SUB_DEPTH, super_root);
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky3_cook::{assert_constraints_fail, assert_constraints_ok};
+
+    use super::*;
+
+    fn air() -> MerkleAggregation {
+        MerkleAggregation { constants: Poseidon2Constants::from_seed(1) }
+    }
+
+    #[test]
+    fn test_super_root_is_root_over_all_leaves() {
+        let air = air();
+        let leaves = random_leaves(K << SUB_DEPTH);
+        let (trace, public_values) = generate_trace(&air.constants, &sub_roots(&air.constants, &leaves));
+        assert_constraints_ok!(&air, &trace, &public_values);
+        assert_eq!(public_values[K * DIGEST_LEN..], mmcs_root(&air.constants, &leaves));
+    }
+
+    #[test]
+    fn test_wrong_super_root_fails() {
+        let air = air();
+        let roots = sub_roots(&air.constants, &random_leaves(K << SUB_DEPTH));
+        let (trace, mut public_values) = generate_trace(&air.constants, &roots);
+        public_values[K * DIGEST_LEN + 5] += Val::one();
+        assert_constraints_fail!(&air, &trace, &public_values, 2 * K - 1);
+    }
+
+    #[test]
+    fn test_swapped_sub_roots_fail() {
+        // the trace aggregates the sub-roots in one order, the public values list them in another
+        let air = air();
+        let mut roots = sub_roots(&air.constants, &random_leaves(K << SUB_DEPTH));
+        let (trace, _) = generate_trace(&air.constants, &roots);
+        let super_root = trace.row_slice(2 * K - 1)[3 + K..3 + K + DIGEST_LEN].to_vec();
+        roots.swap(0, 1);
+        let mut public_values = roots.concat();
+        public_values.extend(super_root);
+        // the first leaf row pushes what the cursor points at
+        assert_constraints_fail!(&air, &trace, &public_values, 0);
+    }
+
+    #[test]
+    fn test_merkle_aggregation_round_trip() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let air = air();
+        let roots = sub_roots(&air.constants, &random_leaves(K << SUB_DEPTH));
+        let (trace, public_values) = generate_trace(&air.constants, &roots);
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &air, &mut p_challenger, trace, &public_values);
+        let mut v_challenger = Challenger::new(perm);
+        verify(&config, &air, &mut v_challenger, &proof, &public_values).unwrap();
+    }
+}
@@ -0,0 +1,313 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_field::{AbstractField, PrimeField32};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::{prove, verify};
+use plonky3_cook::config::{default_config, random_perm, Challenger, Val};
+use plonky3_cook::gadgets::one_hot::assert_one_hot;
+use plonky3_cook::poseidon2_air::{
+    eval_permutation, generate_permutation, PermutationCols, Poseidon2Constants, PERMUTATION_WIDTH, WIDTH,
+};
+use rand::{thread_rng, Rng};
+use tracing_forest::{util::LevelFilter, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+// The Poseidon2 Merkle root of a batch of leaves, every hash of the tree proven: one permutation per row,
+// `MyHash` for a leaf (an 8-element leaf is one permutation of `[leaf, 0, ..., 0]`) and `MyCompress` for a
+// node (`[left, right]`), so the root is the one `ValMmcs` commits the leaves to. The leaves are the
+// prover's witness and the root is the public value.
+//
+// A node's children are hashed on rows far above it, and constraints only see adjacent rows, so the rows
+// walk the tree in post-order and carry a stack of digests along: a leaf row pushes its hash, a node row
+// pops the top two digests and pushes their compression, and hold rows after the root leave the stack
+// alone. Each stack slot records its digest's level in the tree, and a node may only join two digests of the
+// same level. That pins the shape: a stack that ends as one digest of level `log_leaves` came from a
+// perfect tree over `2^log_leaves` leaves, in the order they were pushed. `SLOTS` bounds the stack, so trees
+// have at most `2^(SLOTS - 1)` leaves.
+//
+// A batch of `n` leaves is padded to `2^log_leaves` with all-zero leaves, as a matrix of `n` rows would be
+// padded before `ValMmcs` commits to it. A padding leaf is indistinguishable from a real zero leaf, so an
+// application that cares how many leaves there are has to bind `n` separately.
+
+const DIGEST_LEN: usize = 8;
+const SLOTS: usize = 6;
+
+const MR_ROW_WIDTH: usize = 3 + SLOTS * (DIGEST_LEN + 2) + PERMUTATION_WIDTH;
+
+struct MerkleRootAir {
+    constants: Poseidon2Constants,
+    log_leaves: usize,
+}
+
+impl<F> BaseAir<F> for MerkleRootAir {
+    fn width(&self) -> usize {
+        MR_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues<F = Val>> Air<AB> for MerkleRootAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &MerkleRootRow<AB::Var> = (*local).borrow();
+        let next: &MerkleRootRow<AB::Var> = (*next).borrow();
+
+        let root: Vec<AB::Expr> = builder.public_values().iter().map(|&v| v.into()).collect();
+
+        let out = eval_permutation(builder, &self.constants, &local.perm);
+        let inputs = local.perm.inputs;
+        let (top, second) = (&local.slots[0], &local.slots[1]);
+
+        assert_one_hot(builder, &[local.is_leaf, local.is_node, local.is_hold]);
+
+        // a leaf is hashed alone; a node needs two digests of the same level, and hashes them in push order
+        for i in 0..DIGEST_LEN {
+            builder.when(local.is_leaf).assert_zero(inputs[DIGEST_LEN + i]);
+            builder.when(local.is_node).assert_eq(inputs[i], second.digest[i]);
+            builder.when(local.is_node).assert_eq(inputs[DIGEST_LEN + i], top.digest[i]);
+        }
+        builder.when(local.is_leaf).assert_zero(local.slots[SLOTS - 1].present);
+        builder.when(local.is_node).assert_one(top.present);
+        builder.when(local.is_node).assert_one(second.present);
+        builder.when(local.is_node).assert_eq(top.level, second.level);
+
+        // the stack after this row's operation
+        let pushed = local.is_leaf + local.is_node;
+        let mut transition = builder.when_transition();
+        for i in 0..DIGEST_LEN {
+            let digest_i = pushed.clone() * out[i].clone() + local.is_hold * top.digest[i];
+            transition.assert_eq(next.slots[0].digest[i], digest_i);
+        }
+        let level = local.is_node * (top.level + AB::Expr::one()) + local.is_hold * top.level;
+        transition.assert_eq(next.slots[0].level, level);
+        transition.assert_eq(next.slots[0].present, pushed + local.is_hold * top.present);
+        for d in 1..SLOTS {
+            let (below, above) = (&local.slots[d - 1], local.slots.get(d + 1));
+            let shifted = |column: &dyn Fn(&StackSlot<AB::Var>) -> AB::Var| {
+                local.is_leaf * column(below)
+                    + local.is_node * above.map_or(AB::Expr::zero(), |above| column(above).into())
+                    + local.is_hold * column(&local.slots[d])
+            };
+            for i in 0..DIGEST_LEN {
+                transition.assert_eq(next.slots[d].digest[i], shifted(&|slot| slot.digest[i]));
+            }
+            transition.assert_eq(next.slots[d].level, shifted(&|slot| slot.level));
+            transition.assert_eq(next.slots[d].present, shifted(&|slot| slot.present));
+        }
+
+        // an empty stack to start, the root alone to finish
+        for slot in &local.slots {
+            builder.when_first_row().assert_zero(slot.present);
+        }
+        builder.when_last_row().assert_one(local.is_hold);
+        builder.when_last_row().assert_one(top.present);
+        builder.when_last_row().assert_zero(second.present);
+        builder
+            .when_last_row()
+            .assert_eq(top.level, AB::Expr::from_canonical_usize(self.log_leaves));
+        for i in 0..DIGEST_LEN {
+            builder.when_last_row().assert_eq(top.digest[i], root[i].clone());
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct StackSlot<F> {
+    pub digest: [F; DIGEST_LEN],
+    /// 0 for a leaf's hash, one more than its children's for a node's
+    pub level: F,
+    /// 1 if the slot holds a digest
+    pub present: F,
+}
+
+struct MerkleRootRow<F> {
+    pub is_leaf: F,
+    pub is_node: F,
+    pub is_hold: F,
+    /// the stack before this row's operation, top first
+    pub slots: [StackSlot<F>; SLOTS],
+    pub perm: PermutationCols<F>,
+}
+
+impl<F> Borrow<MerkleRootRow<F>> for [F] {
+    fn borrow(&self) -> &MerkleRootRow<F> {
+        debug_assert_eq!(self.len(), MR_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<MerkleRootRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+/// The leaves zero-padded to a power of two, and its log.
+fn padded_leaves(leaves: &[[Val; DIGEST_LEN]]) -> (Vec<[Val; DIGEST_LEN]>, usize) {
+    let n = leaves.len().next_power_of_two();
+    let log_leaves = n.trailing_zeros() as usize;
+    assert!(log_leaves < SLOTS, "at most 2^{} leaves", SLOTS - 1);
+    let mut padded = leaves.to_vec();
+    padded.resize(n, [Val::zero(); DIGEST_LEN]);
+    (padded, log_leaves)
+}
+
+/// Returns the trace of the tree over `leaves` together with its root.
+fn generate_trace(c: &Poseidon2Constants, leaves: &[[Val; DIGEST_LEN]]) -> (RowMajorMatrix<Val>, Vec<Val>) {
+    let (leaves, _) = padded_leaves(leaves);
+    let height = 2 * leaves.len();
+
+    let mut trace = RowMajorMatrix::new(vec![Val::zero(); height * MR_ROW_WIDTH], MR_ROW_WIDTH);
+    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<MerkleRootRow<Val>>() };
+    assert!(prefix.is_empty(), "Alignment should match");
+    assert!(suffix.is_empty(), "Alignment should match");
+
+    // post-order: after the `k`-th leaf, one node per trailing zero of `k`
+    let mut rows = rows.iter_mut();
+    let mut step = |stack: &[StackSlot<Val>], is_leaf: bool, inputs: [Val; WIDTH]| -> [Val; DIGEST_LEN] {
+        let row = rows.next().unwrap();
+        row.is_leaf = Val::from_bool(is_leaf);
+        row.is_node = Val::from_bool(!is_leaf);
+        for (slot, &entry) in row.slots.iter_mut().zip(stack.iter().rev()) {
+            *slot = entry;
+        }
+        generate_permutation(c, inputs, &mut row.perm)[..DIGEST_LEN].try_into().unwrap()
+    };
+    let mut stack: Vec<StackSlot<Val>> = vec![];
+    for (k, leaf) in leaves.iter().enumerate() {
+        let mut inputs = [Val::zero(); WIDTH];
+        inputs[..DIGEST_LEN].copy_from_slice(leaf);
+        let digest = step(&stack, true, inputs);
+        stack.push(StackSlot { digest, level: Val::zero(), present: Val::one() });
+
+        for _ in 0..(k + 1).trailing_zeros() {
+            let (left, right) = (stack[stack.len() - 2], stack[stack.len() - 1]);
+            inputs[..DIGEST_LEN].copy_from_slice(&left.digest);
+            inputs[DIGEST_LEN..].copy_from_slice(&right.digest);
+            let digest = step(&stack, false, inputs);
+            stack.truncate(stack.len() - 2);
+            stack.push(StackSlot { digest, level: left.level + Val::one(), present: Val::one() });
+        }
+    }
+
+    // hold rows: the root alone, hashing zeros
+    for row in rows {
+        row.is_hold = Val::one();
+        row.slots[0] = stack[0];
+        generate_permutation(c, [Val::zero(); WIDTH], &mut row.perm);
+    }
+
+    let root = stack[0].digest.to_vec();
+    (trace, root)
+}
+
+fn random_leaves(n: usize) -> Vec<[Val; DIGEST_LEN]> {
+    let mut rng = thread_rng();
+    (0..n).map(|_| rng.gen()).collect()
+}
+
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+
+    let leaves = random_leaves(21);
+    let constants = Poseidon2Constants::from_seed(0x6d72);
+    let (trace, public_values) = generate_trace(&constants, &leaves);
+    let air = MerkleRootAir { constants, log_leaves: padded_leaves(&leaves).1 };
+
+    let mut p_challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &air, &mut p_challenger, trace, &public_values);
+    let mut v_challenger = Challenger::new(perm);
+    verify(&config, &air, &mut v_challenger, &proof, &public_values).unwrap();
+
+    let root = public_values.iter().map(|v| v.as_canonical_u32()).collect::<Vec<_>>();
+    println!("proven: {} leaves hash to the root {:?}", leaves.len(), root);
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_commit::Mmcs;
+    use plonky3_cook::config::{MyCompress, MyHash, ValMmcs};
+    use plonky3_cook::{assert_constraints_fail, assert_constraints_ok};
+
+    use super::*;
+
+    fn constants() -> Poseidon2Constants {
+        Poseidon2Constants::from_seed(1)
+    }
+
+    /// The root `ValMmcs` commits the padded leaves to, as a matrix of width 8.
+    fn mmcs_root(c: &Poseidon2Constants, leaves: &[[Val; DIGEST_LEN]]) -> Vec<Val> {
+        let perm = c.perm();
+        let mmcs = ValMmcs::new(MyHash::new(perm.clone()), MyCompress::new(perm));
+        let (padded, _) = padded_leaves(leaves);
+        let (commitment, _) = mmcs.commit_matrix(RowMajorMatrix::new(padded.concat(), DIGEST_LEN));
+        let root: [Val; DIGEST_LEN] = commitment.into();
+        root.to_vec()
+    }
+
+    fn air_for(leaves: &[[Val; DIGEST_LEN]]) -> MerkleRootAir {
+        MerkleRootAir { constants: constants(), log_leaves: padded_leaves(leaves).1 }
+    }
+
+    #[test]
+    fn test_root_matches_mmcs() {
+        for n in [1, 2, 5, 8, 13] {
+            let leaves = random_leaves(n);
+            let (trace, public_values) = generate_trace(&constants(), &leaves);
+            assert_constraints_ok!(&air_for(&leaves), &trace, &public_values);
+            assert_eq!(public_values, mmcs_root(&constants(), &leaves), "{} leaves", n);
+        }
+    }
+
+    #[test]
+    fn test_wrong_root_fails() {
+        let leaves = random_leaves(8);
+        let (trace, mut public_values) = generate_trace(&constants(), &leaves);
+        public_values[3] += Val::one();
+        assert_constraints_fail!(&air_for(&leaves), &trace, &public_values, 15);
+    }
+
+    #[test]
+    fn test_smaller_tree_fails() {
+        // the root of 4 leaves doesn't pass for a tree of 8
+        let leaves = random_leaves(4);
+        let (trace, public_values) = generate_trace(&constants(), &leaves);
+        let air = MerkleRootAir { constants: constants(), log_leaves: 3 };
+        assert_constraints_fail!(&air, &trace, &public_values, 7);
+    }
+
+    #[test]
+    fn test_uneven_merge_fails() {
+        // a leaf row turned into a node row: it joins a node's digest with a leaf's
+        let leaves = random_leaves(4);
+        let (mut trace, public_values) = generate_trace(&constants(), &leaves);
+        // rows: L L N L L N N H; row 3 pushes the third leaf onto [n01]
+        trace.row_mut(3)[0] = Val::zero();
+        trace.row_mut(3)[1] = Val::one();
+        assert_constraints_fail!(&air_for(&leaves), &trace, &public_values, 3);
+    }
+
+    #[test]
+    fn test_merkle_root_round_trip() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let leaves = random_leaves(6);
+        let air = air_for(&leaves);
+        let (trace, public_values) = generate_trace(&air.constants, &leaves);
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &air, &mut p_challenger, trace, &public_values);
+        let mut v_challenger = Challenger::new(perm);
+        verify(&config, &air, &mut v_challenger, &proof, &public_values).unwrap();
+    }
+}
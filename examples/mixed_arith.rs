@@ -0,0 +1,250 @@
+use std::borrow::Borrow;
+use std::time::Instant;
+
+use p3_air::{Air, AirBuilder, BaseAir};
+use p3_field::{AbstractField, Field};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::{prove, verify};
+use plonky3_cook::config::{default_config, random_perm, Challenger, Val};
+use plonky3_cook::gadgets::one_hot::{assert_one_hot, select};
+use rand::{distributions::{Distribution, Standard}, thread_rng, Rng};
+use tracing_forest::{util::LevelFilter, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+// One trace mixing three operations, each row selecting one:
+//   op 0: result = a + b
+//   op 1: result = a * b
+//   op 2: result = a^-1, with `c` holding the inverse (`a * c == 1`) so the check stays low degree
+//
+// `op` is decoded into one-hot selector columns (`is_add`, `is_mul`, `is_inv`) and `result` is the selected
+// operation's value, which adds one to each operation's degree: add is degree 2, mul and inv are degree 3.
+// uni-stark sizes the quotient by the highest-degree constraint in the AIR, not per row, so every row pays
+// for degree 3; `main` compares against a uniform multiply-only circuit of the same width whose constraints
+// are degree 2.
+//
+// `op` is meant to be a public preprocessed column. uni-stark at this revision has no preprocessed trace,
+// so, as in `weighted_sum.rs`, the schedule is pinned by constraints: it cycles add, mul, inv from the
+// first row, so the only satisfying `op` column is `i mod 3`.
+
+const MA_ROW_WIDTH: usize = 8;
+
+const OP_ADD: u32 = 0;
+const OP_MUL: u32 = 1;
+const OP_INV: u32 = 2;
+
+struct MixedArithAir {}
+
+impl<F> BaseAir<F> for MixedArithAir {
+    fn width(&self) -> usize {
+        MA_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilder> Air<AB> for MixedArithAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &MixedArithRow<AB::Var> = (*local).borrow();
+        let next: &MixedArithRow<AB::Var> = (*next).borrow();
+
+        // selectors decode `op`
+        let selectors = [local.is_add, local.is_mul, local.is_inv];
+        assert_one_hot(builder, &selectors);
+        let ops = [OP_ADD, OP_MUL, OP_INV].map(AB::Expr::from_canonical_u32);
+        builder.assert_eq(local.op, select::<_, AB::Expr>(&selectors, ops));
+
+        // the fixed schedule
+        builder.when_first_row().assert_one(local.is_add);
+        builder.when_transition().assert_eq(next.is_mul, local.is_add);
+        builder.when_transition().assert_eq(next.is_inv, local.is_mul);
+        builder.when_transition().assert_eq(next.is_add, local.is_inv);
+
+        let results: [AB::Expr; 3] = [local.a + local.b, local.a * local.b, local.c.into()];
+        builder.assert_eq(local.result, select::<_, AB::Expr>(&selectors, results));
+        builder.when(local.is_inv).assert_one(local.a * local.c);
+    }
+}
+
+/// The same rows with every operation a multiplication and no selectors: degree 2 throughout.
+struct UniformMulAir {}
+
+impl<F> BaseAir<F> for UniformMulAir {
+    fn width(&self) -> usize {
+        MA_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilder> Air<AB> for UniformMulAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = main.row_slice(0);
+        let local: &MixedArithRow<AB::Var> = (*local).borrow();
+
+        builder.assert_eq(local.result, local.a * local.b);
+    }
+}
+
+struct MixedArithRow<F> {
+    pub a: F,
+    pub b: F,
+    pub c: F,
+    pub op: F,
+    pub result: F,
+    pub is_add: F,
+    pub is_mul: F,
+    pub is_inv: F,
+}
+
+impl<F> Borrow<MixedArithRow<F>> for [F] {
+    fn borrow(&self) -> &MixedArithRow<F> {
+        debug_assert_eq!(self.len(), MA_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<MixedArithRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+/// The fixed operation on row `i`.
+fn scheduled_op(i: usize) -> u32 {
+    [OP_ADD, OP_MUL, OP_INV][i % 3]
+}
+
+/// Builds the trace for the operand pairs, with `op_for_row` choosing each row's operation.
+fn generate_trace<F: Field>(operands: &[(F, F)], op_for_row: impl Fn(usize) -> u32) -> RowMajorMatrix<F> {
+    let n = operands.len();
+    assert!(n.is_power_of_two(), "trace height must be a power of two");
+
+    let mut trace = RowMajorMatrix::new(vec![F::zero(); n * MA_ROW_WIDTH], MA_ROW_WIDTH);
+
+    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<MixedArithRow<F>>() };
+    assert!(prefix.is_empty(), "Alignment should match");
+    assert!(suffix.is_empty(), "Alignment should match");
+    assert_eq!(rows.len(), n);
+
+    for (i, (row, &(a, b))) in rows.iter_mut().zip(operands).enumerate() {
+        let op = op_for_row(i);
+        let (c, result) = match op {
+            OP_ADD => (F::zero(), a + b),
+            OP_MUL => (F::zero(), a * b),
+            OP_INV => {
+                let inv = a.try_inverse().expect("the inverted operand must be nonzero");
+                (inv, inv)
+            }
+            _ => unreachable!("unknown op {}", op),
+        };
+        *row = MixedArithRow {
+            a,
+            b,
+            c,
+            op: F::from_canonical_u32(op),
+            result,
+            is_add: F::from_bool(op == OP_ADD),
+            is_mul: F::from_bool(op == OP_MUL),
+            is_inv: F::from_bool(op == OP_INV),
+        };
+    }
+
+    trace
+}
+
+/// Random operand pairs; `a` is never zero so every row can be an inversion.
+fn random_operands<F: Field>(n: usize) -> Vec<(F, F)> where Standard: Distribution<F> {
+    let mut rng = thread_rng();
+    (0..n)
+        .map(|_| {
+            let a: F = rng.gen();
+            (if a.is_zero() { F::one() } else { a }, rng.gen())
+        })
+        .collect()
+}
+
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+
+    println!("{:>8} {:>14} {:>14}", "log_n", "mixed (deg 3)", "uniform (deg 2)");
+    for log_n in [12, 14, 16] {
+        let operands = random_operands::<Val>(1 << log_n);
+
+        let trace = generate_trace(&operands, scheduled_op);
+        let start = Instant::now();
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &MixedArithAir {}, &mut p_challenger, trace, &vec![]);
+        let mixed_time = start.elapsed();
+        let mut v_challenger = Challenger::new(perm.clone());
+        verify(&config, &MixedArithAir {}, &mut v_challenger, &proof, &vec![]).unwrap();
+
+        let trace = generate_trace(&operands, |_| OP_MUL);
+        let start = Instant::now();
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &UniformMulAir {}, &mut p_challenger, trace, &vec![]);
+        let uniform_time = start.elapsed();
+        let mut v_challenger = Challenger::new(perm.clone());
+        verify(&config, &UniformMulAir {}, &mut v_challenger, &proof, &vec![]).unwrap();
+
+        println!("{:>8} {:>14?} {:>14?}", log_n, mixed_time, uniform_time);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky3_cook::{assert_constraints_fail, assert_constraints_ok};
+
+    use super::*;
+
+    #[test]
+    fn test_mixed_arith_constraints() {
+        let trace = generate_trace(&random_operands::<Val>(1 << 8), scheduled_op);
+        assert_constraints_ok!(&MixedArithAir {}, &trace, &[]);
+    }
+
+    #[test]
+    fn test_each_op_is_checked() {
+        // rows 3, 4 and 5 are add, mul and inv
+        for row in [3, 4, 5] {
+            let mut trace = generate_trace(&random_operands::<Val>(1 << 6), scheduled_op);
+            trace.row_mut(row)[4] += Val::one();
+            assert_constraints_fail!(&MixedArithAir {}, &trace, &[], row);
+        }
+    }
+
+    #[test]
+    fn test_inverse_needs_the_auxiliary_column() {
+        let mut trace = generate_trace(&random_operands::<Val>(1 << 6), scheduled_op);
+        // a consistent but wrong result and auxiliary value
+        trace.row_mut(5)[2] += Val::one();
+        trace.row_mut(5)[4] += Val::one();
+        assert_constraints_fail!(&MixedArithAir {}, &trace, &[], 5);
+    }
+
+    #[test]
+    fn test_prover_cannot_choose_op() {
+        // a valid multiplication on a row scheduled for addition
+        let trace = generate_trace(&random_operands::<Val>(1 << 6), |i| if i == 9 { OP_MUL } else { scheduled_op(i) });
+        assert_constraints_fail!(&MixedArithAir {}, &trace, &[], 8);
+    }
+
+    #[test]
+    fn test_mixed_arith_round_trip() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let trace = generate_trace(&random_operands::<Val>(1 << 8), scheduled_op);
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &MixedArithAir {}, &mut p_challenger, trace, &vec![]);
+        let mut v_challenger = Challenger::new(perm);
+        verify(&config, &MixedArithAir {}, &mut v_challenger, &proof, &vec![]).unwrap();
+    }
+}
@@ -0,0 +1,293 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_field::{AbstractExtensionField, AbstractField, Field};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use plonky3_cook::config::{default_config, random_perm, Challenge, MyConfig, Perm, Val};
+use plonky3_cook::error::CookError;
+use plonky3_cook::gadgets::sentinel::{assert_sentinel, when_active};
+use plonky3_cook::lookups::{
+    assert_ext_eq, ext, ext_add, ext_scale, ext_sub, ext_times, ext_values, lift, prove_two_round, verify_two_round,
+    TwoRoundAir, TwoRoundProof, EXT,
+};
+use rand::{thread_rng, Rng};
+use tracing_forest::{util::LevelFilter, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+// A tall trace checked against a short table: every `value` must be in `[0, TABLE_SIZE)`.
+//
+// The PCS is fine with mixed heights: one commitment can hold matrices of different heights (the Merkle tree
+// injects each shorter matrix at the layer matching its height) and each is opened on its own domain, see
+// `test_pcs_commits_mixed_heights`. uni-stark is not: it proves a single main trace, and an AIR only ever sees
+// rows of that one matrix. So the workaround is to give the table the main trace's height: its column holds
+// the `TABLE_SIZE` entries followed by padding rows, marked by a sentinel column, whose lookup multiplicity is
+// forced to zero. The cost is `TABLE_SIZE`-sized columns stretched to the trace height, which is why it only
+// pays off when the table is much shorter than the trace, never taller.
+//
+// The table is pinned by constraints (0, then +1 on each active row, ending at `TABLE_SIZE - 1`), and the
+// lookup is a LogUp sum with the challenge `z` drawn as in `causal_stream.rs`:
+//   sum over rows of multiplicity / (z - table) == sum over rows of 1 / (z - value)
+// The four columns before `z` are the first round of a `lookups::TwoRoundAir`, opened at the proof's
+// out-of-domain point against their commitment; `z`, the inverses and the accumulator are extension elements.
+
+const TABLE_SIZE: u32 = 256;
+
+// the columns committed before `z` is drawn
+const VALUE_WIDTH: usize = 4;
+const MH_ROW_WIDTH: usize = VALUE_WIDTH + 3 * EXT;
+
+struct RangeLookupAir {}
+
+impl<F> BaseAir<F> for RangeLookupAir {
+    fn width(&self) -> usize {
+        MH_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues<F = Val>> Air<AB> for RangeLookupAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &RangeLookupRow<AB::Var> = (*local).borrow();
+        let next: &RangeLookupRow<AB::Var> = (*next).borrow();
+
+        let z: [AB::Expr; EXT] = ext(&builder.public_values()[..EXT]);
+
+        // the table, padded to the trace height
+        assert_sentinel(builder, local.sentinel, next.sentinel);
+        builder.when_first_row().assert_zero(local.sentinel);
+        builder.when_first_row().assert_zero(local.table);
+        when_active(builder, next.sentinel, |builder| {
+            builder.when_transition().assert_eq(next.table, local.table + AB::Expr::one());
+        });
+        builder
+            .when_transition()
+            .when(next.sentinel - local.sentinel)
+            .assert_eq(local.table, AB::Expr::from_canonical_u32(TABLE_SIZE - 1));
+        builder.when_last_row().assert_one(local.sentinel);
+        builder.assert_zero(local.sentinel * local.multiplicity);
+
+        // the lookup
+        let one = lift(AB::Expr::one());
+        let table = ext_sub(z.clone(), lift(local.table.into()));
+        assert_ext_eq(builder, ext_times(&ext(&local.table_inv), &table), one.clone());
+        let value = ext_sub(z, lift(local.value.into()));
+        assert_ext_eq(builder, ext_times(&ext(&local.value_inv), &value), one);
+
+        let delta = |row: &RangeLookupRow<AB::Var>| {
+            ext_sub(ext_scale(ext::<AB::Expr, _>(&row.table_inv), row.multiplicity.into()), ext(&row.value_inv))
+        };
+        let acc = ext::<AB::Expr, _>(&local.acc);
+        assert_ext_eq(&mut builder.when_first_row(), acc.clone(), delta(local));
+        assert_ext_eq(&mut builder.when_transition(), ext(&next.acc), ext_add(acc.clone(), delta(next)));
+        assert_ext_eq(&mut builder.when_last_row(), acc, lift(AB::Expr::zero()));
+    }
+}
+
+struct RangeLookupRow<F> {
+    pub value: F,
+    pub table: F,
+    pub sentinel: F,
+    pub multiplicity: F,
+    // filled after `z` is drawn
+    pub table_inv: [F; EXT],
+    pub value_inv: [F; EXT],
+    pub acc: [F; EXT],
+}
+
+impl<F> Borrow<RangeLookupRow<F>> for [F] {
+    fn borrow(&self) -> &RangeLookupRow<F> {
+        debug_assert_eq!(self.len(), MH_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<RangeLookupRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+fn rows_mut<F>(trace: &mut RowMajorMatrix<F>) -> &mut [RangeLookupRow<F>] {
+    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<RangeLookupRow<F>>() };
+    assert!(prefix.is_empty(), "Alignment should match");
+    assert!(suffix.is_empty(), "Alignment should match");
+    rows
+}
+
+/// The trace with the value and table columns filled in and the lookup columns left zero.
+fn value_trace<F: Field>(values: &[u32]) -> RowMajorMatrix<F> {
+    let n = values.len();
+    assert!(n.is_power_of_two(), "trace height must be a power of two");
+    assert!(n > TABLE_SIZE as usize, "the trace must be taller than the table");
+
+    let mut multiplicities = vec![0u32; TABLE_SIZE as usize];
+    for &v in values {
+        // an out-of-range value has no table entry to count against, and the lookup won't balance
+        if let Some(m) = multiplicities.get_mut(v as usize) {
+            *m += 1;
+        }
+    }
+
+    let mut trace = RowMajorMatrix::new(vec![F::zero(); n * MH_ROW_WIDTH], MH_ROW_WIDTH);
+    for (i, row) in rows_mut(&mut trace).iter_mut().enumerate() {
+        row.value = F::from_canonical_u32(values[i]);
+        match multiplicities.get(i) {
+            Some(&m) => {
+                row.table = F::from_canonical_usize(i);
+                row.multiplicity = F::from_canonical_u32(m);
+            }
+            None => row.sentinel = F::one(),
+        }
+    }
+    trace
+}
+
+/// Fills the lookup columns for the challenge `z`.
+fn fill_lookup(trace: &mut RowMajorMatrix<Val>, z: Challenge) {
+    let mut acc = Challenge::zero();
+    for row in rows_mut(trace) {
+        let table_inv = (z - row.table).inverse();
+        let value_inv = (z - row.value).inverse();
+        acc += table_inv * row.multiplicity - value_inv;
+        row.table_inv.copy_from_slice(table_inv.as_base_slice());
+        row.value_inv.copy_from_slice(value_inv.as_base_slice());
+        row.acc.copy_from_slice(acc.as_base_slice());
+    }
+}
+
+impl TwoRoundAir for RangeLookupAir {
+    fn committed_width(&self) -> usize {
+        VALUE_WIDTH
+    }
+
+    fn num_challenges(&self) -> usize {
+        1
+    }
+
+    fn num_public_values(&self) -> usize {
+        0
+    }
+
+    fn complete_trace(&self, trace: &mut RowMajorMatrix<Val>, challenges: &[Challenge]) -> Vec<Val> {
+        fill_lookup(trace, challenges[0]);
+        vec![]
+    }
+}
+
+fn prove_in_range(config: &MyConfig, perm: &Perm, values: &[u32]) -> TwoRoundProof {
+    prove_two_round(config, perm, &RangeLookupAir {}, value_trace::<Val>(values), &[])
+}
+
+fn verify_in_range(config: &MyConfig, perm: &Perm, proof: &TwoRoundProof) -> Result<(), CookError> {
+    verify_two_round(config, perm, &RangeLookupAir {}, &[], proof).map(|_| ())
+}
+
+fn random_values(n: usize) -> Vec<u32> {
+    let mut rng = thread_rng();
+    (0..n).map(|_| rng.gen_range(0..TABLE_SIZE)).collect()
+}
+
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+
+    let values = random_values(1 << 12);
+    let proof = prove_in_range(&config, &perm, &values);
+    verify_in_range(&config, &perm, &proof).unwrap();
+
+    println!("proven: {} values against a {}-entry table", values.len(), TABLE_SIZE);
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_challenger::{CanObserve, FieldChallenger};
+    use p3_commit::Pcs as _;
+    use p3_uni_stark::StarkGenericConfig;
+    use plonky3_cook::config::Challenger;
+    use plonky3_cook::{assert_constraints_fail, assert_constraints_ok};
+
+    use super::*;
+
+    fn z() -> Challenge {
+        Challenge::from_base_fn(|i| Val::from_canonical_usize(1_000_003 + i))
+    }
+
+    #[test]
+    fn test_pcs_commits_mixed_heights() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let pcs = config.pcs();
+
+        let mut rng = thread_rng();
+        let tall = RowMajorMatrix::<Val>::new((0..(1 << 10) * 2).map(|_| rng.gen()).collect(), 2);
+        let short = RowMajorMatrix::<Val>::new((0..(1 << 4) * 3).map(|_| rng.gen()).collect(), 3);
+        let tall_domain = pcs.natural_domain_for_degree(tall.height());
+        let short_domain = pcs.natural_domain_for_degree(short.height());
+
+        // one commitment for both
+        let (commitment, data) = pcs.commit(vec![(tall_domain, tall), (short_domain, short)]);
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        p_challenger.observe(commitment.clone());
+        let zeta: Challenge = p_challenger.sample_ext_element();
+        let (opened, opening_proof) = pcs.open(vec![(&data, vec![vec![zeta], vec![zeta]])], &mut p_challenger);
+
+        let mut v_challenger = Challenger::new(perm);
+        v_challenger.observe(commitment.clone());
+        let zeta: Challenge = v_challenger.sample_ext_element();
+        let claims = vec![
+            (tall_domain, vec![(zeta, opened[0][0][0].clone())]),
+            (short_domain, vec![(zeta, opened[0][1][0].clone())]),
+        ];
+        pcs.verify(vec![(commitment, claims)], &opening_proof, &mut v_challenger).unwrap();
+    }
+
+    #[test]
+    fn test_range_lookup_constraints() {
+        let mut trace = value_trace::<Val>(&random_values(1 << 10));
+        fill_lookup(&mut trace, z());
+        assert_constraints_ok!(&RangeLookupAir {}, &trace, &ext_values(&[z()]));
+    }
+
+    #[test]
+    fn test_out_of_range_value_fails_lookup() {
+        let mut values = random_values(1 << 10);
+        values[17] = TABLE_SIZE;
+        let mut trace = value_trace::<Val>(&values);
+        fill_lookup(&mut trace, z());
+        assert_constraints_fail!(&RangeLookupAir {}, &trace, &ext_values(&[z()]), (1 << 10) - 1);
+    }
+
+    #[test]
+    fn test_table_cannot_be_extended() {
+        // pretending the table has one more entry breaks where the sentinel flips
+        let mut trace = value_trace::<Val>(&random_values(1 << 10));
+        let extra = TABLE_SIZE as usize;
+        trace.row_mut(extra)[1] = Val::from_canonical_u32(TABLE_SIZE);
+        trace.row_mut(extra)[2] = Val::zero();
+        fill_lookup(&mut trace, z());
+        assert_constraints_fail!(&RangeLookupAir {}, &trace, &ext_values(&[z()]), extra - 1);
+    }
+
+    #[test]
+    fn test_range_round_trip() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+
+        let mut proof = prove_in_range(&config, &perm, &random_values(1 << 9));
+        verify_in_range(&config, &perm, &proof).unwrap();
+
+        // values other than the committed ones show up at `zeta`
+        proof.committed_opening[0] += Challenge::one();
+        assert!(matches!(verify_in_range(&config, &perm, &proof), Err(CookError::PublicValues(_))));
+    }
+}
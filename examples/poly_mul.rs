@@ -0,0 +1,304 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_field::{AbstractField, Field, TwoAdicField};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::{prove, verify};
+use plonky3_cook::config::{default_config, random_perm, Challenger, Val};
+use plonky3_cook::gadgets::one_hot::{assert_one_hot, one_hot, select};
+use rand::{thread_rng, Rng};
+use tracing_forest::{util::LevelFilter, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+// Multiplying polynomials through the NTT: `r = p * q` is
+//   r = INTT(NTT(p) . NTT(q))
+// with `.` the pointwise product, as long as the NTT is longer than `deg r`. Two degree-8 polynomials have a
+// degree-16 product, so `N = 32`.
+//
+// The layout is the one from `coset_ntt.rs`: each row holds whole vectors after one stage, the stage is a
+// one-hot phase vector pinned to the schedule, and each output is the phase-selected map of the row. Here a
+// row holds two vectors, `a` and `b`, starting as `p` and `q` zero-padded to `N`:
+//   - forward NTT, both vectors side by side: bit-reversal, then one row per butterfly layer;
+//   - pointwise: `a <- a . b`;
+//   - inverse NTT of `a`: bit-reversal scaled by `1 / N`, then the butterfly layers with inverse roots;
+//   - hold until the end of the trace, whose last row must be holding: the verifier takes the height from the
+//     proof, so without that a short trace would end partway through.
+// Every stage but the pointwise one is linear, so the constraints are degree 4: the pointwise product,
+// times its phase, on transition rows. The public values are the coefficients of `p`, `q` and `r`.
+
+const DEGREE: usize = 8;
+const NUM_COEFFS: usize = DEGREE + 1;
+const PRODUCT_COEFFS: usize = 2 * DEGREE + 1;
+
+const LOG_N: usize = 5;
+const N: usize = 1 << LOG_N;
+
+/// bit-reversal and layers for the forward NTT, pointwise, bit-reversal and layers for the inverse, hold
+const NUM_PHASES: usize = 2 * (LOG_N + 1) + 2;
+const POINTWISE: usize = LOG_N + 1;
+const HOLD: usize = NUM_PHASES - 1;
+
+const PM_ROW_WIDTH: usize = 2 * N + NUM_PHASES;
+const PM_HEIGHT: usize = 16;
+
+/// A linear map on the `2N` values of a row: output `i` is the sum of `coeff * input[src]` over `terms[i]`.
+type LinearMap = Vec<Vec<(usize, Val)>>;
+
+enum Phase {
+    Linear(LinearMap),
+    Pointwise,
+}
+
+struct PolynomialMultiplication {
+    phases: Vec<Phase>,
+}
+
+fn bit_reverse(i: usize) -> usize {
+    i.reverse_bits() >> (usize::BITS as usize - LOG_N)
+}
+
+fn identity(offset: usize) -> LinearMap {
+    (offset..offset + N).map(|i| vec![(i, Val::one())]).collect()
+}
+
+fn bit_reversal(offset: usize, scale: Val) -> LinearMap {
+    (0..N).map(|i| vec![(offset + bit_reverse(i), scale)]).collect()
+}
+
+/// Butterfly layer `s` of the NTT of the vector at `offset`, with roots of unity from `w` of order `2^s`.
+fn butterfly_layer(offset: usize, s: usize, w_m: Val) -> LinearMap {
+    let m = 1 << s;
+    let mut layer = vec![vec![]; N];
+    for block in (0..N).step_by(m) {
+        for (j, w) in w_m.powers().take(m / 2).enumerate() {
+            let (u, t) = (block + j, block + j + m / 2);
+            layer[u] = vec![(offset + u, Val::one()), (offset + t, w)];
+            layer[t] = vec![(offset + u, Val::one()), (offset + t, -w)];
+        }
+    }
+    layer
+}
+
+impl PolynomialMultiplication {
+    fn new() -> Self {
+        let mut phases = vec![Phase::Linear([bit_reversal(0, Val::one()), bit_reversal(N, Val::one())].concat())];
+        for s in 1..=LOG_N {
+            let w_m = Val::two_adic_generator(s);
+            phases.push(Phase::Linear([butterfly_layer(0, s, w_m), butterfly_layer(N, s, w_m)].concat()));
+        }
+
+        phases.push(Phase::Pointwise);
+
+        let n_inv = Val::from_canonical_usize(N).inverse();
+        phases.push(Phase::Linear([bit_reversal(0, n_inv), identity(N)].concat()));
+        for s in 1..=LOG_N {
+            let w_m = Val::two_adic_generator(s).inverse();
+            phases.push(Phase::Linear([butterfly_layer(0, s, w_m), identity(N)].concat()));
+        }
+
+        phases.push(Phase::Linear([identity(0), identity(N)].concat()));
+        assert_eq!(phases.len(), NUM_PHASES);
+        PolynomialMultiplication { phases }
+    }
+}
+
+fn apply(phase: &Phase, values: &[Val; 2 * N]) -> [Val; 2 * N] {
+    match phase {
+        Phase::Linear(map) => core::array::from_fn(|i| map[i].iter().map(|&(src, coeff)| coeff * values[src]).sum()),
+        Phase::Pointwise => core::array::from_fn(|i| if i < N { values[i] * values[N + i] } else { values[i] }),
+    }
+}
+
+impl<F> BaseAir<F> for PolynomialMultiplication {
+    fn width(&self) -> usize {
+        PM_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues<F = Val>> Air<AB> for PolynomialMultiplication {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &PolyMulRow<AB::Var> = (*local).borrow();
+        let next: &PolyMulRow<AB::Var> = (*next).borrow();
+
+        let pis = builder.public_values().iter().map(|&v| v.into()).collect::<Vec<AB::Expr>>();
+        let (p, rest) = pis.split_at(NUM_COEFFS);
+        let (q, r) = rest.split_at(NUM_COEFFS);
+
+        // the schedule: each phase once, in order, then hold
+        assert_one_hot(builder, &local.phase);
+        builder.when_first_row().assert_one(local.phase[0]);
+        for i in 1..HOLD {
+            builder.when_transition().assert_eq(next.phase[i], local.phase[i - 1]);
+        }
+        builder.when_transition().assert_eq(next.phase[HOLD], local.phase[HOLD - 1] + local.phase[HOLD]);
+        builder.when_last_row().assert_one(local.phase[HOLD]);
+
+        for i in 0..2 * N {
+            let candidates = self.phases.iter().map(|phase| match phase {
+                Phase::Linear(map) => {
+                    map[i].iter().map(|&(src, coeff)| AB::Expr::from(local.values[src]) * coeff).sum::<AB::Expr>()
+                }
+                Phase::Pointwise if i < N => local.values[i] * local.values[N + i],
+                Phase::Pointwise => local.values[i].into(),
+            });
+            builder.when_transition().assert_eq(next.values[i], select::<_, AB::Expr>(&local.phase, candidates));
+        }
+
+        // `p` and `q` padded with zeros in, `r` out
+        for i in 0..N {
+            let padded = |coeffs: &[AB::Expr]| coeffs.get(i).cloned().unwrap_or_else(AB::Expr::zero);
+            builder.when_first_row().assert_eq(local.values[i], padded(p));
+            builder.when_first_row().assert_eq(local.values[N + i], padded(q));
+            builder.when_last_row().assert_eq(local.values[i], padded(r));
+        }
+    }
+}
+
+struct PolyMulRow<F> {
+    /// `a` then `b`
+    pub values: [F; 2 * N],
+    pub phase: [F; NUM_PHASES],
+}
+
+impl<F> Borrow<PolyMulRow<F>> for [F] {
+    fn borrow(&self) -> &PolyMulRow<F> {
+        debug_assert_eq!(self.len(), PM_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<PolyMulRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+/// Returns the trace together with the public values, the coefficients of `p`, `q` and `p * q`.
+fn generate_trace(
+    air: &PolynomialMultiplication,
+    p: [Val; NUM_COEFFS],
+    q: [Val; NUM_COEFFS],
+) -> (RowMajorMatrix<Val>, Vec<Val>) {
+    let mut trace = RowMajorMatrix::new(vec![Val::zero(); PM_HEIGHT * PM_ROW_WIDTH], PM_ROW_WIDTH);
+
+    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<PolyMulRow<Val>>() };
+    assert!(prefix.is_empty(), "Alignment should match");
+    assert!(suffix.is_empty(), "Alignment should match");
+    assert_eq!(rows.len(), PM_HEIGHT);
+
+    let mut values = [Val::zero(); 2 * N];
+    values[..NUM_COEFFS].copy_from_slice(&p);
+    values[N..N + NUM_COEFFS].copy_from_slice(&q);
+    for (i, row) in rows.iter_mut().enumerate() {
+        let phase = i.min(HOLD);
+        row.values = values;
+        row.phase.copy_from_slice(&one_hot(phase, NUM_PHASES));
+        values = apply(&air.phases[phase], &values);
+    }
+
+    let public_values = [&p[..], &q[..], &rows[PM_HEIGHT - 1].values[..PRODUCT_COEFFS]].concat();
+    (trace, public_values)
+}
+
+/// The coefficients of `p * q`, by schoolbook multiplication.
+fn naive_product<F: Field>(p: &[F], q: &[F]) -> Vec<F> {
+    let mut r = vec![F::zero(); p.len() + q.len() - 1];
+    for (i, &p_i) in p.iter().enumerate() {
+        for (j, &q_j) in q.iter().enumerate() {
+            r[i + j] += p_i * q_j;
+        }
+    }
+    r
+}
+
+fn random_poly() -> [Val; NUM_COEFFS] {
+    let mut rng = thread_rng();
+    core::array::from_fn(|_| rng.gen())
+}
+
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+
+    let air = PolynomialMultiplication::new();
+    let (p, q) = (random_poly(), random_poly());
+    let (trace, public_values) = generate_trace(&air, p, q);
+    assert_eq!(public_values[2 * NUM_COEFFS..], naive_product(&p, &q));
+
+    let mut p_challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &air, &mut p_challenger, trace, &public_values);
+    let mut v_challenger = Challenger::new(perm);
+    verify(&config, &air, &mut v_challenger, &proof, &public_values).unwrap();
+
+    println!("proven: p * q = {:?}", &public_values[2 * NUM_COEFFS..]);
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky3_cook::{assert_constraints_fail, assert_constraints_ok};
+
+    use super::*;
+
+    #[test]
+    fn test_product_matches_schoolbook() {
+        let air = PolynomialMultiplication::new();
+        let (p, q) = (random_poly(), random_poly());
+        let (trace, public_values) = generate_trace(&air, p, q);
+        assert_eq!(public_values[2 * NUM_COEFFS..], naive_product(&p, &q));
+
+        // nothing spills past the product's degree
+        let last = trace.row_slice(PM_HEIGHT - 1);
+        assert!(last[PRODUCT_COEFFS..N].iter().all(|v| v.is_zero()));
+        assert_constraints_ok!(&air, &trace, &public_values);
+    }
+
+    #[test]
+    fn test_wrong_product_fails() {
+        let air = PolynomialMultiplication::new();
+        let (trace, mut public_values) = generate_trace(&air, random_poly(), random_poly());
+        public_values[2 * NUM_COEFFS + DEGREE] += Val::one();
+        assert_constraints_fail!(&air, &trace, &public_values, PM_HEIGHT - 1);
+    }
+
+    #[test]
+    fn test_tampered_pointwise_product_fails() {
+        let air = PolynomialMultiplication::new();
+        let (mut trace, public_values) = generate_trace(&air, random_poly(), random_poly());
+        trace.row_mut(POINTWISE + 1)[7] += Val::one();
+        assert_constraints_fail!(&air, &trace, &public_values, POINTWISE);
+    }
+
+    #[test]
+    fn test_short_trace_fails() {
+        // two rows end after the bit-reversal, with `r` claimed to be whatever is there
+        let air = PolynomialMultiplication::new();
+        let (p, q) = (random_poly(), random_poly());
+        let (trace, _) = generate_trace(&air, p, q);
+        let short = RowMajorMatrix::new(trace.values[..2 * PM_ROW_WIDTH].to_vec(), PM_ROW_WIDTH);
+        let public_values = [&p[..], &q[..], &short.row_slice(1)[..PRODUCT_COEFFS]].concat();
+        assert_constraints_fail!(&air, &short, &public_values, 1);
+    }
+
+    #[test]
+    fn test_poly_mul_round_trip() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let air = PolynomialMultiplication::new();
+        let (trace, public_values) = generate_trace(&air, random_poly(), random_poly());
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &air, &mut p_challenger, trace, &public_values);
+        let mut v_challenger = Challenger::new(perm);
+        verify(&config, &air, &mut v_challenger, &proof, &public_values).unwrap();
+    }
+}
@@ -0,0 +1,300 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_field::{AbstractField, PrimeField32};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::{prove, verify};
+use plonky3_cook::config::{default_config, random_perm, Challenger, Val};
+use plonky3_cook::poseidon2_air::{
+    eval_permutation, generate_permutation, PermutationCols, Poseidon2Constants, PERMUTATION_WIDTH, WIDTH,
+};
+use rand::{thread_rng, Rng};
+use tracing_forest::{util::LevelFilter, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+// A statistical test over a proven pseudo-random sequence, the kind of check a randomness beacon might
+// publish with its output. Sample `i` is the first element of `Poseidon2([seed, i, 0, ..., 0])`, one
+// permutation per row, and the AIR counts how many samples fall into each quarter of the 31-bit range by
+// their top two bits. The counts are the statement; whether they look random is judged outside the proof,
+// by a chi-squared test on the public values against the distribution a uniform BabyBear element has.
+//
+// That distribution is not uniform over the bits: `p = 15 * 2^27 + 1`, so of the four buckets by top two
+// bits the first three each hold `4/15` of the field and the last only `3/15`, and the top bit is set with
+// probability `7/15` rather than `1/2`. Reading the buckets off the bits needs the canonical decomposition,
+// since `v + p` also fits in 31 bits for `v < 2^27 - 1`; the representation is canonical exactly when the
+// top four bits being all ones forces the low 27 to zero:
+//   every row:          bits recompose to the sample, and all_ones * (low 27 bits) == 0
+//   first row:          index == 0,  count_b == bucket_b
+//   every transition:   next.index == local.index + 1,  next.count_b == local.count_b + next.bucket_b
+//   last row:           count_b == pis[1 + b],  index == SAMPLES - 1
+// with `pis = [seed, count_0, ..., count_3]` and `bucket_b` the degree-2 indicator of the top two bits. The
+// verifier takes the height from the proof, so the last-row index pins the number of samples to `SAMPLES`.
+
+const LOG_SAMPLES: usize = 8;
+const SAMPLES: usize = 1 << LOG_SAMPLES;
+const BUCKETS: usize = 4;
+const SAMPLE_BITS: usize = 31;
+/// the bits below the top four
+const LOW_BITS: usize = 27;
+
+/// Each bucket's share of the field, in fifteenths.
+const BUCKET_WEIGHTS: [f64; BUCKETS] = [4.0, 4.0, 4.0, 3.0];
+/// The chi-squared value with 3 degrees of freedom exceeded with probability 0.001.
+const CHI_SQUARED_CRITICAL: f64 = 16.27;
+
+const PT_ROW_WIDTH: usize = 1 + SAMPLE_BITS + 2 + BUCKETS + PERMUTATION_WIDTH;
+
+struct PrngTest {
+    constants: Poseidon2Constants,
+}
+
+impl<F> BaseAir<F> for PrngTest {
+    fn width(&self) -> usize {
+        PT_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues<F = Val>> Air<AB> for PrngTest {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &SampleRow<AB::Var> = (*local).borrow();
+        let next: &SampleRow<AB::Var> = (*next).borrow();
+
+        let pis: Vec<AB::Expr> = builder.public_values().iter().map(|&v| v.into()).collect();
+        let (seed, counts) = (pis[0].clone(), &pis[1..]);
+
+        // sample `index` of the sequence seeded with `seed`
+        let out = eval_permutation(builder, &self.constants, &local.perm);
+        let inputs = local.perm.inputs;
+        builder.assert_eq(inputs[0], seed);
+        builder.assert_eq(inputs[1], local.index);
+        for &input in &inputs[2..] {
+            builder.assert_zero(input);
+        }
+        builder.when_first_row().assert_zero(local.index);
+        builder.when_transition().assert_eq(next.index, local.index + AB::Expr::one());
+        builder.when_last_row().assert_eq(local.index, AB::Expr::from_canonical_usize(SAMPLES - 1));
+
+        // its canonical bits
+        for &bit in &local.bits {
+            builder.assert_bool(bit);
+        }
+        let recomposed = local.bits.iter().rev().fold(AB::Expr::zero(), |acc, &bit| acc.double() + bit);
+        builder.assert_eq(recomposed, out[0].clone());
+        let low = local.bits[..LOW_BITS].iter().rev().fold(AB::Expr::zero(), |acc, &bit| acc.double() + bit);
+        let (b27, b28, b29, b30) = (local.bits[27], local.bits[28], local.bits[29], local.bits[30]);
+        builder.assert_eq(local.top_pair, b29 * b30);
+        builder.assert_eq(local.all_ones, local.top_pair * b28 * b27);
+        builder.assert_zero(local.all_ones * low);
+
+        // and its bucket, counted down the rows
+        let buckets = |row: &SampleRow<AB::Var>| -> [AB::Expr; BUCKETS] {
+            let (b29, b30) = (row.bits[29], row.bits[30]);
+            let (not29, not30) = (AB::Expr::one() - b29, AB::Expr::one() - b30);
+            [not30.clone() * not29.clone(), not30 * b29, not29 * b30, row.top_pair.into()]
+        };
+        let (local_buckets, next_buckets) = (buckets(local), buckets(next));
+        for b in 0..BUCKETS {
+            builder.when_first_row().assert_eq(local.counts[b], local_buckets[b].clone());
+            builder.when_transition().assert_eq(next.counts[b], local.counts[b] + next_buckets[b].clone());
+            builder.when_last_row().assert_eq(local.counts[b], counts[b].clone());
+        }
+    }
+}
+
+struct SampleRow<F> {
+    pub index: F,
+    /// the canonical bits of the sample, least significant first
+    pub bits: [F; SAMPLE_BITS],
+    /// `bits[29] * bits[30]`
+    pub top_pair: F,
+    /// the top four bits are all ones
+    pub all_ones: F,
+    /// samples per bucket up to this row
+    pub counts: [F; BUCKETS],
+    /// `[seed, index, 0, ..., 0]`
+    pub perm: PermutationCols<F>,
+}
+
+impl<F> Borrow<SampleRow<F>> for [F] {
+    fn borrow(&self) -> &SampleRow<F> {
+        debug_assert_eq!(self.len(), PT_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<SampleRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+/// The trace of `SAMPLES` samples from `seed`, and `[seed, count_0, ..., count_3]`.
+fn generate_trace(c: &Poseidon2Constants, seed: Val) -> (RowMajorMatrix<Val>, Vec<Val>) {
+    let mut trace = RowMajorMatrix::new(vec![Val::zero(); SAMPLES * PT_ROW_WIDTH], PT_ROW_WIDTH);
+    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<SampleRow<Val>>() };
+    assert!(prefix.is_empty(), "Alignment should match");
+    assert!(suffix.is_empty(), "Alignment should match");
+
+    let mut counts = [0u32; BUCKETS];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row.index = Val::from_canonical_usize(i);
+        let mut inputs = [Val::zero(); WIDTH];
+        (inputs[0], inputs[1]) = (seed, row.index);
+        let sample = generate_permutation(c, inputs, &mut row.perm)[0].as_canonical_u32();
+
+        for k in 0..SAMPLE_BITS {
+            row.bits[k] = Val::from_canonical_u32((sample >> k) & 1);
+        }
+        row.top_pair = row.bits[29] * row.bits[30];
+        row.all_ones = row.top_pair * row.bits[28] * row.bits[27];
+
+        counts[(sample >> 29) as usize] += 1;
+        row.counts = counts.map(Val::from_canonical_u32);
+    }
+
+    (trace, [vec![seed], counts.map(Val::from_canonical_u32).to_vec()].concat())
+}
+
+/// Pearson's chi-squared statistic of the bucket counts against a uniform field element.
+fn chi_squared(counts: &[u32]) -> f64 {
+    let total = counts.iter().sum::<u32>() as f64;
+    counts
+        .iter()
+        .zip(BUCKET_WEIGHTS)
+        .map(|(&observed, weight)| {
+            let expected = total * weight / 15.0;
+            (observed as f64 - expected).powi(2) / expected
+        })
+        .sum()
+}
+
+fn passes(counts: &[u32]) -> bool {
+    chi_squared(counts) < CHI_SQUARED_CRITICAL
+}
+
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+    let air = PrngTest { constants: Poseidon2Constants::from_seed(0x70726e67) };
+
+    let (trace, public_values) = generate_trace(&air.constants, thread_rng().gen());
+
+    let mut p_challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &air, &mut p_challenger, trace, &public_values);
+    let mut v_challenger = Challenger::new(perm);
+    verify(&config, &air, &mut v_challenger, &proof, &public_values).unwrap();
+
+    let counts = public_values[1..].iter().map(|c| c.as_canonical_u32()).collect::<Vec<_>>();
+    println!(
+        "proven: {} samples fall into the buckets {:?}; chi-squared {:.2}, {}",
+        SAMPLES,
+        counts,
+        chi_squared(&counts),
+        if passes(&counts) { "consistent with uniform" } else { "rejected at p = 0.001" }
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky3_cook::{assert_constraints_fail, assert_constraints_ok};
+
+    use super::*;
+
+    const BITS_COL: usize = 1;
+    const COUNTS_COL: usize = BITS_COL + SAMPLE_BITS + 2;
+
+    fn air() -> PrngTest {
+        PrngTest { constants: Poseidon2Constants::from_seed(1) }
+    }
+
+    #[test]
+    fn test_counts_are_proven() {
+        let (trace, public_values) = generate_trace(&air().constants, Val::from_canonical_u32(7));
+        assert_constraints_ok!(&air(), &trace, &public_values);
+
+        let counts = public_values[1..].iter().map(|c| c.as_canonical_u32()).collect::<Vec<_>>();
+        assert_eq!(counts.iter().sum::<u32>(), SAMPLES as u32);
+        assert!(passes(&counts), "chi-squared {} for {:?}", chi_squared(&counts), counts);
+    }
+
+    #[test]
+    fn test_chi_squared_rejects_bias() {
+        // a generator that never sets the top bit
+        assert!(!passes(&[128, 128, 0, 0]));
+        // uniform over the top two bits isn't uniform over the field
+        assert!(!passes(&[512; BUCKETS]));
+        assert!(passes(&[68, 68, 68, 52]));
+    }
+
+    #[test]
+    fn test_wrong_count_fails() {
+        let (trace, mut public_values) = generate_trace(&air().constants, Val::from_canonical_u32(7));
+        public_values[4] += Val::one();
+        assert_constraints_fail!(&air(), &trace, &public_values, SAMPLES - 1);
+    }
+
+    #[test]
+    fn test_other_seed_fails() {
+        let (trace, mut public_values) = generate_trace(&air().constants, Val::from_canonical_u32(7));
+        public_values[0] = Val::from_canonical_u32(8);
+        assert_constraints_fail!(&air(), &trace, &public_values, 0);
+    }
+
+    #[test]
+    fn test_non_canonical_bits_fail() {
+        let (mut trace, mut public_values) = generate_trace(&air().constants, Val::from_canonical_u32(7));
+        let sample = |trace: &RowMajorMatrix<Val>, r: usize| {
+            (0..SAMPLE_BITS).map(|k| trace.get(r, BITS_COL + k).as_canonical_u32() << k).sum::<u32>()
+        };
+        let r = (0..SAMPLES).find(|&r| sample(&trace, r) < (1 << LOW_BITS) - 1).expect("1 in 15 samples is that small");
+
+        // `v + p` in place of `v`, moving the sample from the first bucket to the last, counts and all
+        let aliased = sample(&trace, r) + Val::ORDER_U32;
+        let row = trace.row_mut(r);
+        for k in 0..SAMPLE_BITS {
+            row[BITS_COL + k] = Val::from_canonical_u32((aliased >> k) & 1);
+        }
+        (row[BITS_COL + SAMPLE_BITS], row[BITS_COL + SAMPLE_BITS + 1]) = (Val::one(), Val::one());
+        for later in r..SAMPLES {
+            trace.row_mut(later)[COUNTS_COL] -= Val::one();
+            trace.row_mut(later)[COUNTS_COL + BUCKETS - 1] += Val::one();
+        }
+        public_values[1] -= Val::one();
+        public_values[BUCKETS] += Val::one();
+        assert_constraints_fail!(&air(), &trace, &public_values, r);
+    }
+
+    #[test]
+    fn test_short_sequence_fails() {
+        // the first half of the samples, with counts to match: a valid sequence, just not `SAMPLES` long
+        let (trace, _) = generate_trace(&air().constants, Val::from_canonical_u32(7));
+        let half = SAMPLES / 2;
+        let short = RowMajorMatrix::new(trace.values[..half * PT_ROW_WIDTH].to_vec(), PT_ROW_WIDTH);
+        let counts = (0..BUCKETS).map(|b| short.get(half - 1, COUNTS_COL + b));
+        let public_values = [vec![Val::from_canonical_u32(7)], counts.collect()].concat();
+        assert_constraints_fail!(&air(), &short, &public_values, half - 1);
+    }
+
+    #[test]
+    fn test_prng_test_round_trip() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let air = air();
+        let (trace, public_values) = generate_trace(&air.constants, thread_rng().gen());
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &air, &mut p_challenger, trace, &public_values);
+        let mut v_challenger = Challenger::new(perm);
+        verify(&config, &air, &mut v_challenger, &proof, &public_values).unwrap();
+    }
+}
@@ -0,0 +1,107 @@
+use p3_field::AbstractField;
+use p3_matrix::dense::RowMajorMatrix;
+use plonky3_cook::config::{default_config, random_perm, MyConfig, Perm, Val};
+use plonky3_cook::error::CookError;
+use plonky3_cook::lookups::logup::LogUpLookup;
+use plonky3_cook::lookups::{prove_lookup, verify_lookup, LookupArgument};
+use rand::{thread_rng, Rng};
+use tracing_forest::{util::LevelFilter, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+// Range checks against a table: every value is in `[0, TABLE_SIZE)` iff it is an entry of the table
+// `0, 1, ..., TABLE_SIZE - 1`. The check is written against `impl LookupArgument`, so the strategy is picked
+// in one place, `strategy()`: LogUp (`lookups::logup`), or Plookup (`lookups::plookup`) with
+// `PlookupLookup { queries_per_entry: NUM_VALUES / TABLE_SIZE }`, since Plookup wants the queries to be a
+// multiple of the table.
+
+const TABLE_SIZE: usize = 256;
+const NUM_VALUES: usize = 1 << 10;
+
+fn strategy() -> impl LookupArgument {
+    LogUpLookup
+}
+
+fn range_table() -> RowMajorMatrix<Val> {
+    RowMajorMatrix::new((0..TABLE_SIZE).map(Val::from_canonical_usize).collect(), 1)
+}
+
+fn check_in_range(
+    config: &MyConfig,
+    perm: &Perm,
+    lookup: &impl LookupArgument,
+    values: &[u32],
+) -> Result<(), CookError> {
+    let queries: Vec<Val> = values.iter().map(|&v| Val::from_canonical_u32(v)).collect();
+    let proof = prove_lookup(config, perm, lookup, range_table(), &queries);
+    verify_lookup(config, perm, lookup, range_table(), &proof)
+}
+
+fn random_values(n: usize) -> Vec<u32> {
+    let mut rng = thread_rng();
+    (0..n).map(|_| rng.gen_range(0..TABLE_SIZE as u32)).collect()
+}
+
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+
+    check_in_range(&config, &perm, &strategy(), &random_values(NUM_VALUES)).unwrap();
+
+    println!("proven: {} values are below {}", NUM_VALUES, TABLE_SIZE);
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky3_cook::lookups::plookup::PlookupLookup;
+
+    use super::*;
+
+    fn plookup() -> PlookupLookup {
+        PlookupLookup { queries_per_entry: NUM_VALUES / TABLE_SIZE }
+    }
+
+    #[test]
+    fn test_range_check_logup() {
+        let perm = random_perm();
+        check_in_range(&default_config(&perm), &perm, &LogUpLookup, &random_values(NUM_VALUES)).unwrap();
+    }
+
+    #[test]
+    fn test_range_check_plookup() {
+        let perm = random_perm();
+        check_in_range(&default_config(&perm), &perm, &plookup(), &random_values(NUM_VALUES)).unwrap();
+    }
+
+    #[test]
+    fn test_out_of_range_value_rejected() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let mut values = random_values(NUM_VALUES);
+        values[100] = TABLE_SIZE as u32;
+
+        assert!(matches!(check_in_range(&config, &perm, &LogUpLookup, &values), Err(CookError::PublicValues(_))));
+        assert!(matches!(check_in_range(&config, &perm, &plookup(), &values), Err(CookError::PublicValues(_))));
+    }
+
+    #[test]
+    fn test_proof_is_for_its_table() {
+        // a proof against a shifted table doesn't verify against the range table
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let shifted = RowMajorMatrix::new((1..=TABLE_SIZE).map(Val::from_canonical_usize).collect(), 1);
+        let queries: Vec<Val> = random_values(NUM_VALUES).iter().map(|&v| Val::from_canonical_u32(v + 1)).collect();
+
+        let proof = prove_lookup(&config, &perm, &LogUpLookup, shifted.clone(), &queries);
+        verify_lookup(&config, &perm, &LogUpLookup, shifted, &proof).unwrap();
+        assert!(verify_lookup(&config, &perm, &LogUpLookup, range_table(), &proof).is_err());
+    }
+}
@@ -0,0 +1,353 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_challenger::{CanObserve, CanSample};
+use p3_commit::Mmcs;
+use p3_field::{AbstractField, PrimeField32};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::{prove, verify};
+use plonky3_cook::config::{default_config, random_perm, Challenger, MyCompress, MyHash, Val, ValMmcs};
+use plonky3_cook::gadgets::one_hot::{assert_one_hot, select_if};
+use plonky3_cook::poseidon2_air::{
+    eval_permutation, generate_permutation, PermutationCols, Poseidon2Constants, PERMUTATION_WIDTH, WIDTH,
+};
+use rand::{thread_rng, Rng};
+use tracing_forest::{util::LevelFilter, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+// A first step toward recursion: an outer proof that checks the start of an inner proof's transcript.
+//
+// The inner side is a Poseidon2 MMCS commitment, as the inner prover makes for its trace: a matrix of
+// `2^DEPTH` rows of width 8 is committed to a Merkle root, which is the first thing the inner prover's
+// `DuplexChallenger` observes. The `RecursiveHashVerifier` AIR proves, one Poseidon2 permutation per row:
+//   row 0           the leaf digest: the opened row, absorbed into a fresh sponge (`MyHash`)
+//   rows 1..=DEPTH  one compression per level (`MyCompress`): `[left, right]`, ordered by the index bit
+//   last row        the challenger duplexing after observing the root: `[root, 0, ..., 0]`, whose output
+//                   element `RATE - 1` is the first challenge the inner transcript samples
+// Public values are the inner transcript as far as it goes: the opened row and its index, the root, and
+// the challenge. The permutation constraints come from `poseidon2_air`, with constants shared with the
+// native `Perm` the inner MMCS and challenger use.
+//
+// A full recursive verifier would go on to replay the whole transcript (more duplexings, one per observed
+// commitment and sampled challenge) and check every FRI query path; each of those is the same row shape.
+
+const DIGEST_LEN: usize = 8;
+/// the challenger's sponge rate; `sample` pops from the end of the output buffer
+const RATE: usize = 8;
+const DEPTH: usize = 6;
+
+const RM_HEIGHT: usize = DEPTH + 2;
+const RM_ROW_WIDTH: usize = 3 + 2 * DIGEST_LEN + 3 + PERMUTATION_WIDTH;
+
+/// `[opened row (8), index, root (8), challenge]`
+const NUM_PUBLIC_VALUES: usize = 2 * DIGEST_LEN + 2;
+
+struct RecursiveHashVerifier {
+    constants: Poseidon2Constants,
+}
+
+impl<F> BaseAir<F> for RecursiveHashVerifier {
+    fn width(&self) -> usize {
+        RM_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues<F = Val>> Air<AB> for RecursiveHashVerifier {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &RecursiveMerkleRow<AB::Var> = (*local).borrow();
+        let next: &RecursiveMerkleRow<AB::Var> = (*next).borrow();
+
+        let pis = builder.public_values();
+        let opened: Vec<AB::Expr> = pis[..DIGEST_LEN].iter().map(|&v| v.into()).collect();
+        let index: AB::Expr = pis[DIGEST_LEN].into();
+        let root: Vec<AB::Expr> = pis[DIGEST_LEN + 1..2 * DIGEST_LEN + 1].iter().map(|&v| v.into()).collect();
+        let challenge: AB::Expr = pis[2 * DIGEST_LEN + 1].into();
+
+        let out = eval_permutation(builder, &self.constants, &local.perm);
+        let inputs = local.perm.inputs;
+
+        // the schedule: leaf, DEPTH nodes, challenge
+        assert_one_hot(builder, &[local.is_leaf, local.is_node, local.is_challenge]);
+        builder.when_first_row().assert_one(local.is_leaf);
+        builder.when_last_row().assert_one(local.is_challenge);
+        builder.when_transition().assert_zero(next.is_leaf);
+        builder.when_transition().assert_zero(local.is_challenge);
+
+        // each digest feeds the next row
+        for i in 0..DIGEST_LEN {
+            builder.when_transition().assert_eq(next.node[i], out[i].clone());
+        }
+
+        // the leaf: `MyHash` of an 8-element row is one permutation of `[row, 0, ..., 0]`
+        for i in 0..DIGEST_LEN {
+            builder.when_first_row().assert_eq(inputs[i], opened[i].clone());
+            builder.when_first_row().assert_zero(inputs[DIGEST_LEN + i]);
+        }
+
+        // a level: the node goes right when its index bit is set
+        builder.when(local.is_node).assert_bool(local.bit);
+        for i in 0..DIGEST_LEN {
+            let left: AB::Expr = select_if(local.bit, local.sibling[i], local.node[i]);
+            let right: AB::Expr = select_if(local.bit, local.node[i], local.sibling[i]);
+            builder.when(local.is_node).assert_eq(inputs[i], left);
+            builder.when(local.is_node).assert_eq(inputs[DIGEST_LEN + i], right);
+        }
+
+        // the index bits, least significant first
+        builder.when_first_row().assert_one(local.pow);
+        builder.when_first_row().assert_zero(local.index_acc);
+        builder.when_transition().assert_eq(next.pow, local.pow + local.is_node * local.pow);
+        builder
+            .when_transition()
+            .assert_eq(next.index_acc, local.index_acc + next.is_node * next.bit * next.pow);
+        builder.when_last_row().assert_eq(local.index_acc, index);
+
+        // the challenger: a fresh sponge state overwritten with the observed root
+        for i in 0..DIGEST_LEN {
+            builder.when(local.is_challenge).assert_eq(inputs[i], local.node[i]);
+            builder.when(local.is_challenge).assert_zero(inputs[DIGEST_LEN + i]);
+            builder.when_last_row().assert_eq(local.node[i], root[i].clone());
+        }
+        builder.when_last_row().assert_eq(out[RATE - 1].clone(), challenge);
+    }
+}
+
+struct RecursiveMerkleRow<F> {
+    pub is_leaf: F,
+    pub is_node: F,
+    pub is_challenge: F,
+    /// the digest entering this row
+    pub node: [F; DIGEST_LEN],
+    pub sibling: [F; DIGEST_LEN],
+    pub bit: F,
+    /// `2^level` on node rows
+    pub pow: F,
+    pub index_acc: F,
+    pub perm: PermutationCols<F>,
+}
+
+impl<F> Borrow<RecursiveMerkleRow<F>> for [F] {
+    fn borrow(&self) -> &RecursiveMerkleRow<F> {
+        debug_assert_eq!(self.len(), RM_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<RecursiveMerkleRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+/// Returns the trace together with `[opened row, index, root, challenge]`; `siblings` are the Merkle path
+/// from the leaf level up, as `Mmcs::open_batch` returns it.
+fn generate_trace(
+    c: &Poseidon2Constants,
+    opened: [Val; DIGEST_LEN],
+    index: usize,
+    siblings: &[[Val; DIGEST_LEN]],
+) -> (RowMajorMatrix<Val>, Vec<Val>) {
+    assert_eq!(siblings.len(), DEPTH);
+    assert!(index < 1 << DEPTH);
+
+    let mut trace = RowMajorMatrix::new(vec![Val::zero(); RM_HEIGHT * RM_ROW_WIDTH], RM_ROW_WIDTH);
+
+    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<RecursiveMerkleRow<Val>>() };
+    assert!(prefix.is_empty(), "Alignment should match");
+    assert!(suffix.is_empty(), "Alignment should match");
+    assert_eq!(rows.len(), RM_HEIGHT);
+
+    let mut inputs = [Val::zero(); WIDTH];
+    inputs[..DIGEST_LEN].copy_from_slice(&opened);
+    rows[0].is_leaf = Val::one();
+    rows[0].pow = Val::one();
+    let mut node = generate_permutation(c, inputs, &mut rows[0].perm);
+    let mut index_acc = Val::zero();
+
+    for (level, sibling) in siblings.iter().enumerate() {
+        let row = &mut rows[1 + level];
+        let bit = (index >> level) & 1 == 1;
+        row.is_node = Val::one();
+        row.node.copy_from_slice(&node[..DIGEST_LEN]);
+        row.sibling = *sibling;
+        row.bit = Val::from_bool(bit);
+        row.pow = Val::from_canonical_usize(1 << level);
+        index_acc += row.bit * row.pow;
+        row.index_acc = index_acc;
+
+        let (left, right) = if bit { (sibling, &row.node) } else { (&row.node, sibling) };
+        inputs[..DIGEST_LEN].copy_from_slice(left);
+        inputs[DIGEST_LEN..].copy_from_slice(right);
+        node = generate_permutation(c, inputs, &mut row.perm);
+    }
+
+    let row = &mut rows[RM_HEIGHT - 1];
+    row.is_challenge = Val::one();
+    row.node.copy_from_slice(&node[..DIGEST_LEN]);
+    row.pow = Val::from_canonical_usize(1 << DEPTH);
+    row.index_acc = index_acc;
+    let mut inputs = [Val::zero(); WIDTH];
+    inputs[..DIGEST_LEN].copy_from_slice(&row.node);
+    let sponge = generate_permutation(c, inputs, &mut row.perm);
+
+    let mut public_values = Vec::with_capacity(NUM_PUBLIC_VALUES);
+    public_values.extend_from_slice(&opened);
+    public_values.push(Val::from_canonical_usize(index));
+    public_values.extend_from_slice(&row.node);
+    public_values.push(sponge[RATE - 1]);
+    (trace, public_values)
+}
+
+/// The inner side: an MMCS over the permutation `c` describes, and a committed `2^DEPTH x 8` matrix.
+fn inner_commitment(c: &Poseidon2Constants) -> (ValMmcs, RowMajorMatrix<Val>) {
+    let perm = c.perm();
+    let mmcs = ValMmcs::new(MyHash::new(perm.clone()), MyCompress::new(perm));
+    let mut rng = thread_rng();
+    let values = (0..(1 << DEPTH) * DIGEST_LEN).map(|_| rng.gen()).collect();
+    (mmcs, RowMajorMatrix::new(values, DIGEST_LEN))
+}
+
+/// Commits `matrix`, opens row `index` and returns the outer trace and public values.
+fn open_and_generate(
+    c: &Poseidon2Constants,
+    mmcs: &ValMmcs,
+    matrix: RowMajorMatrix<Val>,
+    index: usize,
+) -> (RowMajorMatrix<Val>, Vec<Val>) {
+    let (_, prover_data) = mmcs.commit_matrix(matrix);
+    let (opened_values, siblings) = mmcs.open_batch(index, &prover_data);
+    let opened: [Val; DIGEST_LEN] = opened_values[0].clone().try_into().unwrap();
+    generate_trace(c, opened, index, &siblings)
+}
+
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let air = RecursiveHashVerifier { constants: Poseidon2Constants::from_seed(0x726d) };
+    let (mmcs, matrix) = inner_commitment(&air.constants);
+    let index = thread_rng().gen_range(0..1 << DEPTH);
+    let (trace, public_values) = open_and_generate(&air.constants, &mmcs, matrix, index);
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+    let mut p_challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &air, &mut p_challenger, trace, &public_values);
+    let mut v_challenger = Challenger::new(perm);
+    verify(&config, &air, &mut v_challenger, &proof, &public_values).unwrap();
+
+    let root = public_values[DIGEST_LEN + 1..2 * DIGEST_LEN + 1]
+        .iter()
+        .map(|v| v.as_canonical_u32())
+        .collect::<Vec<_>>();
+    println!(
+        "proven: row {} opens under root {:?}, and the inner transcript's first challenge is {}",
+        index,
+        root,
+        public_values[2 * DIGEST_LEN + 1]
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky3_cook::{assert_constraints_fail, assert_constraints_ok};
+
+    use super::*;
+
+    fn air() -> RecursiveHashVerifier {
+        RecursiveHashVerifier { constants: Poseidon2Constants::from_seed(1) }
+    }
+
+    #[test]
+    fn test_publics_match_inner_transcript() {
+        let air = air();
+        let (mmcs, matrix) = inner_commitment(&air.constants);
+        let index = 37;
+        let row = matrix.row_slice(index).to_vec();
+        let (commitment, _) = mmcs.commit_matrix(matrix.clone());
+        let (trace, public_values) = open_and_generate(&air.constants, &mmcs, matrix, index);
+        assert_constraints_ok!(&air, &trace, &public_values);
+
+        let root: [Val; DIGEST_LEN] = commitment.into();
+        assert_eq!(public_values[..DIGEST_LEN], row[..]);
+        assert_eq!(public_values[DIGEST_LEN], Val::from_canonical_usize(index));
+        assert_eq!(public_values[DIGEST_LEN + 1..2 * DIGEST_LEN + 1], root);
+
+        // what the inner prover's challenger samples first after observing the commitment
+        let mut challenger = Challenger::new(air.constants.perm());
+        challenger.observe(commitment);
+        let first: Val = challenger.sample();
+        assert_eq!(public_values[2 * DIGEST_LEN + 1], first);
+    }
+
+    #[test]
+    fn test_every_index() {
+        let air = air();
+        let (mmcs, matrix) = inner_commitment(&air.constants);
+        for index in [0, 1, (1 << DEPTH) - 1] {
+            let (trace, public_values) = open_and_generate(&air.constants, &mmcs, matrix.clone(), index);
+            assert_constraints_ok!(&air, &trace, &public_values);
+        }
+    }
+
+    #[test]
+    fn test_wrong_sibling_fails() {
+        let air = air();
+        let (mmcs, matrix) = inner_commitment(&air.constants);
+        let (mut trace, public_values) = open_and_generate(&air.constants, &mmcs, matrix, 5);
+        // `sibling[0]` of the third level
+        trace.row_mut(3)[3 + DIGEST_LEN] += Val::one();
+        assert_constraints_fail!(&air, &trace, &public_values, 3);
+    }
+
+    #[test]
+    fn test_wrong_opening_fails() {
+        let air = air();
+        let (mmcs, matrix) = inner_commitment(&air.constants);
+        let (trace, mut public_values) = open_and_generate(&air.constants, &mmcs, matrix, 5);
+        public_values[0] += Val::one();
+        assert_constraints_fail!(&air, &trace, &public_values, 0);
+    }
+
+    #[test]
+    fn test_wrong_index_or_challenge_fails() {
+        let air = air();
+        let (mmcs, matrix) = inner_commitment(&air.constants);
+        let (trace, public_values) = open_and_generate(&air.constants, &mmcs, matrix, 5);
+
+        let mut wrong_index = public_values.clone();
+        wrong_index[DIGEST_LEN] = Val::from_canonical_usize(6);
+        assert_constraints_fail!(&air, &trace, &wrong_index, RM_HEIGHT - 1);
+
+        let mut wrong_challenge = public_values;
+        wrong_challenge[2 * DIGEST_LEN + 1] += Val::one();
+        assert_constraints_fail!(&air, &trace, &wrong_challenge, RM_HEIGHT - 1);
+    }
+
+    #[test]
+    fn test_recursive_merkle_round_trip() {
+        let air = air();
+        let (mmcs, matrix) = inner_commitment(&air.constants);
+        let (trace, public_values) = open_and_generate(&air.constants, &mmcs, matrix, 9);
+
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &air, &mut p_challenger, trace, &public_values);
+
+        let mut v_challenger = Challenger::new(perm.clone());
+        verify(&config, &air, &mut v_challenger, &proof, &public_values).unwrap();
+
+        let mut other_root = public_values.clone();
+        other_root[DIGEST_LEN + 1] += Val::one();
+        let mut v_challenger = Challenger::new(perm);
+        assert!(verify(&config, &air, &mut v_challenger, &proof, &other_root).is_err());
+    }
+}
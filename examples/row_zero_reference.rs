@@ -0,0 +1,206 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_field::{AbstractField, PrimeField32};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::{prove, verify};
+use plonky3_cook::config::{default_config, random_perm, Challenger, Val};
+use plonky3_cook::gadgets::less_than::{assert_bit_decomposition, bit_decompose};
+use rand::{thread_rng, Rng};
+use tracing_forest::{util::LevelFilter, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+// Comparing every row with row 0. A constraint only sees the window `(local, next)`: there is no way to
+// name row 0, or any other fixed row, from row 100. What a constraint can name on every row is a public
+// value, so the idiom is to capture the row-0 values as public values, pin them to row 0 with
+// `when_first_row`, and refer to the public values everywhere else.
+//
+// Here an account's rows must all carry the account id of row 0, and its balance may never drop below the
+// opening balance of row 0:
+//   when_first_row:    local.account == pis[1]   and   local.balance == pis[0]
+//   when_transition:   next.account == pis[1]    and   next.balance == local.balance + local.delta
+//   every row:         local.balance - pis[0] fits in `HEADROOM_BITS` bits
+// with `pis = [opening_balance, account]`.
+//
+// Without public values, the same effect takes a carry column: `when_first_row` sets it to the row-0
+// value and `when_transition` copies it down (`next.carry == local.carry`), one column per captured value.
+// Public values cost no columns but put the captured values in the statement, which is often the point:
+// the verifier learns which account and opening balance the proof is about.
+
+const HEADROOM_BITS: usize = 20;
+
+const RZ_ROW_WIDTH: usize = 3 + HEADROOM_BITS;
+
+struct OpeningBalanceFloor {}
+
+impl<F> BaseAir<F> for OpeningBalanceFloor {
+    fn width(&self) -> usize {
+        RZ_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues> Air<AB> for OpeningBalanceFloor {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &AccountRow<AB::Var> = (*local).borrow();
+        let next: &AccountRow<AB::Var> = (*next).borrow();
+
+        let pis = builder.public_values();
+        let (opening, account): (AB::Expr, AB::Expr) = (pis[0].into(), pis[1].into());
+
+        // the public values are row 0's
+        builder.when_first_row().assert_eq(local.account, account.clone());
+        builder.when_first_row().assert_eq(local.balance, opening.clone());
+
+        // and stand in for it on every other row
+        builder.when_transition().assert_eq(next.account, account);
+        builder.when_transition().assert_eq(next.balance, local.balance + local.delta);
+        assert_bit_decomposition(builder, local.balance - opening, &local.headroom_bits);
+    }
+}
+
+struct AccountRow<F> {
+    pub account: F,
+    pub balance: F,
+    /// the change from this row's balance to the next one's
+    pub delta: F,
+    /// `balance - opening_balance`
+    pub headroom_bits: [F; HEADROOM_BITS],
+}
+
+impl<F> Borrow<AccountRow<F>> for [F] {
+    fn borrow(&self) -> &AccountRow<F> {
+        debug_assert_eq!(self.len(), RZ_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<AccountRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+/// Balances starting at `opening` and never dropping below it.
+fn random_balances(opening: u32, n: usize) -> Vec<u32> {
+    let mut rng = thread_rng();
+    let mut balance = opening;
+    (0..n)
+        .map(|_| {
+            let current = balance;
+            balance = (balance + rng.gen_range(0..100)).saturating_sub(rng.gen_range(0..100)).max(opening);
+            current
+        })
+        .collect()
+}
+
+/// The trace of `account`'s `balances`, and `[opening_balance, account]`.
+fn generate_trace(account: u32, balances: &[u32]) -> (RowMajorMatrix<Val>, Vec<Val>) {
+    let n = balances.len();
+    assert!(n.is_power_of_two(), "trace height must be a power of two");
+
+    let mut trace = RowMajorMatrix::new(vec![Val::zero(); n * RZ_ROW_WIDTH], RZ_ROW_WIDTH);
+    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<AccountRow<Val>>() };
+    assert!(prefix.is_empty(), "Alignment should match");
+    assert!(suffix.is_empty(), "Alignment should match");
+
+    let opening = Val::from_canonical_u32(balances[0]);
+    for (i, row) in rows.iter_mut().enumerate() {
+        row.account = Val::from_canonical_u32(account);
+        row.balance = Val::from_canonical_u32(balances[i]);
+        if i + 1 < n {
+            row.delta = Val::from_canonical_u32(balances[i + 1]) - row.balance;
+        }
+        row.headroom_bits.copy_from_slice(&bit_decompose(row.balance - opening, HEADROOM_BITS));
+    }
+
+    (trace, vec![opening, Val::from_canonical_u32(account)])
+}
+
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+
+    let balances = random_balances(5000, 1 << 10);
+    let (trace, public_values) = generate_trace(42, &balances);
+
+    let mut p_challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &OpeningBalanceFloor {}, &mut p_challenger, trace, &public_values);
+    let mut v_challenger = Challenger::new(perm);
+    verify(&config, &OpeningBalanceFloor {}, &mut v_challenger, &proof, &public_values).unwrap();
+
+    println!(
+        "proven: account {} never dropped below its opening balance of {} over {} rows",
+        public_values[1].as_canonical_u32(),
+        public_values[0].as_canonical_u32(),
+        balances.len()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky3_cook::{assert_constraints_fail, assert_constraints_ok};
+
+    use super::*;
+
+    const ACCOUNT_COL: usize = 0;
+    const BALANCE_COL: usize = 1;
+    const DELTA_COL: usize = 2;
+
+    #[test]
+    fn test_rows_match_row_zero() {
+        let (trace, public_values) = generate_trace(7, &random_balances(1000, 16));
+        assert_constraints_ok!(&OpeningBalanceFloor {}, &trace, &public_values);
+    }
+
+    #[test]
+    fn test_other_account_fails() {
+        let (mut trace, public_values) = generate_trace(7, &random_balances(1000, 16));
+        // the transition into row 9 compares it with row 0's account
+        trace.row_mut(9)[ACCOUNT_COL] = Val::from_canonical_u32(8);
+        assert_constraints_fail!(&OpeningBalanceFloor {}, &trace, &public_values, 8);
+    }
+
+    #[test]
+    fn test_balance_below_opening_fails() {
+        let mut balances = vec![1000; 8];
+        balances[5] = 999;
+        // the balances are consistent with their deltas; only the floor is broken
+        let (mut trace, public_values) = generate_trace(7, &vec![1000; 8]);
+        for (r, &b) in balances.iter().enumerate() {
+            trace.row_mut(r)[BALANCE_COL] = Val::from_canonical_u32(b);
+        }
+        trace.row_mut(4)[DELTA_COL] = -Val::one();
+        trace.row_mut(5)[DELTA_COL] = Val::one();
+        assert_constraints_fail!(&OpeningBalanceFloor {}, &trace, &public_values, 5);
+    }
+
+    #[test]
+    fn test_public_values_pinned_to_row_zero() {
+        let (trace, mut public_values) = generate_trace(7, &random_balances(1000, 16));
+        // a lower claimed opening balance would loosen the floor on every row
+        public_values[0] -= Val::one();
+        assert_constraints_fail!(&OpeningBalanceFloor {}, &trace, &public_values, 0);
+    }
+
+    #[test]
+    fn test_row_zero_reference_round_trip() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let (trace, public_values) = generate_trace(7, &random_balances(1000, 64));
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &OpeningBalanceFloor {}, &mut p_challenger, trace, &public_values);
+        let mut v_challenger = Challenger::new(perm);
+        verify(&config, &OpeningBalanceFloor {}, &mut v_challenger, &proof, &public_values).unwrap();
+    }
+}
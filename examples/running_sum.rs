@@ -0,0 +1,164 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_field::{AbstractField, Field, PrimeField32};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::{prove, verify};
+use plonky3_cook::config::{default_config, random_perm, Challenger, Val};
+use plonky3_cook::gadgets::sentinel::{assert_sentinel, when_active};
+use rand::{thread_rng, Rng};
+use tracing_forest::{util::LevelFilter, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+// A running sum over a variable number of values.
+//
+// The trace height must be a power of two, so `k` values are followed by padding rows marked with the
+// sentinel column. The sum only advances into active rows, and the total is read off the last active row:
+// either the row just before the sentinel flips, or the last row if there is no padding.
+
+const RS_ROW_WIDTH: usize = 3;
+
+struct RunningSumAir {}
+
+impl<F> BaseAir<F> for RunningSumAir {
+    fn width(&self) -> usize {
+        RS_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues> Air<AB> for RunningSumAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &RunningSumRow<AB::Var> = (*local).borrow();
+        let next: &RunningSumRow<AB::Var> = (*next).borrow();
+
+        let total: AB::Expr = builder.public_values()[0].into();
+
+        assert_sentinel(builder, local.sentinel, next.sentinel);
+
+        // at least one value, and the sum starts with it
+        builder.when_first_row().assert_zero(local.sentinel);
+        builder.when_first_row().assert_eq(local.sum, local.value);
+
+        // the sum advances into every active row
+        when_active(builder, next.sentinel, |builder| {
+            builder.when_transition().assert_eq(next.sum, local.sum + next.value);
+        });
+
+        // the last active row carries the total
+        builder.when_transition().when(next.sentinel - local.sentinel).assert_eq(local.sum, total.clone());
+        when_active(builder, local.sentinel, |builder| {
+            builder.when_last_row().assert_eq(local.sum, total);
+        });
+    }
+}
+
+struct RunningSumRow<F> {
+    pub value: F,
+    pub sum: F,
+    pub sentinel: F,
+}
+
+impl<F> Borrow<RunningSumRow<F>> for [F] {
+    fn borrow(&self) -> &RunningSumRow<F> {
+        debug_assert_eq!(self.len(), RS_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<RunningSumRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+/// Lays out `values` followed by zeroed padding rows, returning the trace and the total.
+fn generate_trace<F: Field>(values: &[F]) -> (RowMajorMatrix<F>, F) {
+    assert!(!values.is_empty(), "at least one value is needed");
+    let n = values.len().next_power_of_two().max(2);
+
+    let mut trace = RowMajorMatrix::new(vec![F::zero(); n * RS_ROW_WIDTH], RS_ROW_WIDTH);
+    let mut sum = F::zero();
+    for (i, row) in trace.values.chunks_exact_mut(RS_ROW_WIDTH).enumerate() {
+        match values.get(i) {
+            Some(&value) => {
+                sum += value;
+                row.copy_from_slice(&[value, sum, F::zero()]);
+            }
+            None => row.copy_from_slice(&[F::zero(), F::zero(), F::one()]),
+        }
+    }
+
+    (trace, sum)
+}
+
+fn random_values<F: PrimeField32>(k: usize) -> Vec<F> {
+    let mut rng = thread_rng();
+    (0..k).map(|_| F::from_canonical_u32(rng.gen_range(0..1 << 20))).collect()
+}
+
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+
+    let values = random_values::<Val>(1000);
+    let (trace, total) = generate_trace(&values);
+
+    let mut p_challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &RunningSumAir {}, &mut p_challenger, trace, &vec![total]);
+    let mut v_challenger = Challenger::new(perm);
+    verify(&config, &RunningSumAir {}, &mut v_challenger, &proof, &vec![total]).unwrap();
+
+    println!("sum of {} values: {}", values.len(), total);
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky3_cook::{assert_constraints_fail, assert_constraints_ok};
+
+    use super::*;
+
+    #[test]
+    fn test_variable_lengths() {
+        for k in [1, 5, 8, 1000] {
+            let (trace, total) = generate_trace(&random_values::<Val>(k));
+            assert_constraints_ok!(&RunningSumAir {}, &trace, &[total]);
+        }
+    }
+
+    #[test]
+    fn test_wrong_total_fails() {
+        let (trace, total) = generate_trace(&random_values::<Val>(5));
+        // the boundary is between rows 4 and 5
+        assert_constraints_fail!(&RunningSumAir {}, &trace, &[total + Val::one()], 4);
+    }
+
+    #[test]
+    fn test_sentinel_cannot_reset() {
+        let (mut trace, total) = generate_trace(&random_values::<Val>(5));
+        // going back to active after padding started
+        trace.row_mut(6)[2] = Val::zero();
+        assert_constraints_fail!(&RunningSumAir {}, &trace, &[total], 5);
+    }
+
+    #[test]
+    fn test_prove_variable_length() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let (trace, total) = generate_trace(&random_values::<Val>(37));
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &RunningSumAir {}, &mut p_challenger, trace, &vec![total]);
+        let mut v_challenger = Challenger::new(perm);
+        verify(&config, &RunningSumAir {}, &mut v_challenger, &proof, &vec![total]).unwrap();
+    }
+}
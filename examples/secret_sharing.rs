@@ -0,0 +1,293 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_field::{AbstractField, Field, PrimeField32};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::{prove, verify};
+use plonky3_cook::config::{default_config, random_perm, Challenger, Val};
+use plonky3_cook::poseidon2_air::{
+    eval_permutation, generate_permutation, PermutationCols, Poseidon2Constants, PERMUTATION_WIDTH, WIDTH,
+};
+use rand::{thread_rng, Rng};
+use tracing_forest::{util::LevelFilter, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+// Recombining `K` shares of a `(n, K)` Shamir secret sharing. The dealer hides the secret as the constant
+// term of a random polynomial `f` of degree `K - 1` and hands out the points `(x, f(x))`; any `K` of them
+// determine `f`, and with it the secret, by Lagrange interpolation at zero:
+//   secret = f(0) = sum over j of y_j * lambda_j,   lambda_j = prod over m != j of x_m / (x_m - x_j)
+// The x-coordinates (who holds the shares) are public, the y-coordinates private, and the public output
+// is a commitment to the secret, `Poseidon2([secret, blinding, 0, ..., 0])[..8]`. A field element is
+// only 31 bits, so its bare hash could be inverted by trying every value; the private `blinding` prevents
+// that.
+//
+// The trace has one row per interpolation step, exactly `K` rows. A one-hot `sel`, `e_0` on row 0 and
+// shifted by one per row, picks share `j` on row `j`; `num` and `den` are running products over `m` of
+// the factors of `lambda_j`, with the factor for `m == j` replaced by 1:
+//   num_m = x_m + sel_m * (1 - x_m)     den_m = (x_m - x_j) + sel_m * (1 - (x_m - x_j))
+// `den_inv * den == 1` also proves the x-coordinates distinct, and `acc` sums `lambda_j * y_j` down the
+// rows. The last row hashes `acc` with the blinding, its own permutation constrained by `poseidon2_air`;
+// the other rows permute zeros.
+
+const K: usize = 4;
+const N: usize = 7;
+const DIGEST_LEN: usize = 8;
+
+const SS_ROW_WIDTH: usize = 3 * K + 4 + PERMUTATION_WIDTH;
+
+struct ThresholdProof {
+    constants: Poseidon2Constants,
+}
+
+impl<F> BaseAir<F> for ThresholdProof {
+    fn width(&self) -> usize {
+        SS_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues<F = Val>> Air<AB> for ThresholdProof {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &ShareRow<AB::Var> = (*local).borrow();
+        let next: &ShareRow<AB::Var> = (*next).borrow();
+
+        let pis: Vec<AB::Expr> = builder.public_values().iter().map(|&v| v.into()).collect();
+        let (xs, commitment) = pis.split_at(K);
+
+        let out = eval_permutation(builder, &self.constants, &local.perm);
+
+        // row j interpolates share j, and there are exactly K rows
+        builder.when_first_row().assert_one(local.sel[0]);
+        for i in 1..K {
+            builder.when_first_row().assert_zero(local.sel[i]);
+            builder.when_transition().assert_eq(next.sel[i], local.sel[i - 1]);
+        }
+        builder.when_transition().assert_zero(next.sel[0]);
+        builder.when_last_row().assert_one(local.sel[K - 1]);
+        let x_j = (0..K).map(|i| xs[i].clone() * local.sel[i]).sum::<AB::Expr>();
+
+        // lambda_j
+        for m in 0..K {
+            let num_factor = xs[m].clone() + local.sel[m] * (AB::Expr::one() - xs[m].clone());
+            let diff = xs[m].clone() - x_j.clone();
+            let den_factor = diff.clone() + local.sel[m] * (AB::Expr::one() - diff);
+            if m == 0 {
+                builder.assert_eq(local.num[0], num_factor);
+                builder.assert_eq(local.den[0], den_factor);
+            } else {
+                builder.assert_eq(local.num[m], local.num[m - 1] * num_factor);
+                builder.assert_eq(local.den[m], local.den[m - 1] * den_factor);
+            }
+        }
+        builder.assert_one(local.den_inv * local.den[K - 1]);
+        builder.assert_eq(local.lambda, local.num[K - 1] * local.den_inv);
+
+        builder.when_first_row().assert_eq(local.acc, local.lambda * local.y);
+        builder.when_transition().assert_eq(next.acc, local.acc + next.lambda * next.y);
+
+        // the commitment to the secret
+        let inputs = local.perm.inputs;
+        builder.when_last_row().assert_eq(inputs[0], local.acc);
+        for &input in &inputs[2..] {
+            builder.when_last_row().assert_zero(input);
+        }
+        for i in 0..DIGEST_LEN {
+            builder.when_last_row().assert_eq(out[i].clone(), commitment[i].clone());
+        }
+    }
+}
+
+struct ShareRow<F> {
+    /// one-hot, the share this row interpolates
+    pub sel: [F; K],
+    pub y: F,
+    /// running products of the numerator and denominator factors of `lambda`
+    pub num: [F; K],
+    pub den: [F; K],
+    pub den_inv: F,
+    pub lambda: F,
+    /// the sum of `lambda * y` up to this row; the secret on the last one
+    pub acc: F,
+    /// `[secret, blinding, 0, ..., 0]` on the last row
+    pub perm: PermutationCols<F>,
+}
+
+impl<F> Borrow<ShareRow<F>> for [F] {
+    fn borrow(&self) -> &ShareRow<F> {
+        debug_assert_eq!(self.len(), SS_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<ShareRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+/// `n` shares of `secret` at `x = 1, ..., n`, any `K` of which recover it.
+fn split(secret: Val, n: usize) -> Vec<(Val, Val)> {
+    let mut rng = thread_rng();
+    let coeffs = [vec![secret], (1..K).map(|_| rng.gen()).collect()].concat();
+    (1..=n as u32)
+        .map(|x| {
+            let x = Val::from_canonical_u32(x);
+            (x, coeffs.iter().rev().fold(Val::zero(), |acc, &c| acc * x + c))
+        })
+        .collect()
+}
+
+/// `f(0)` for the polynomial through `shares`.
+fn interpolate_at_zero(shares: &[(Val, Val)]) -> Val {
+    shares
+        .iter()
+        .enumerate()
+        .map(|(j, &(x_j, y_j))| {
+            let others = shares.iter().enumerate().filter(|&(m, _)| m != j);
+            y_j * others.map(|(_, &(x_m, _))| x_m * (x_m - x_j).inverse()).product::<Val>()
+        })
+        .sum()
+}
+
+/// The trace recombining `shares`, and `[x_0, ..., x_{K-1}, commitment..]`.
+fn generate_trace(c: &Poseidon2Constants, shares: &[(Val, Val)], blinding: Val) -> (RowMajorMatrix<Val>, Vec<Val>) {
+    assert_eq!(shares.len(), K, "recombining takes exactly K shares");
+
+    let mut trace = RowMajorMatrix::new(vec![Val::zero(); K * SS_ROW_WIDTH], SS_ROW_WIDTH);
+    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<ShareRow<Val>>() };
+    assert!(prefix.is_empty(), "Alignment should match");
+    assert!(suffix.is_empty(), "Alignment should match");
+
+    let mut acc = Val::zero();
+    let mut commitment = [Val::zero(); WIDTH];
+    for (j, row) in rows.iter_mut().enumerate() {
+        let (x_j, y_j) = shares[j];
+        row.sel[j] = Val::one();
+        row.y = y_j;
+
+        let (mut num, mut den) = (Val::one(), Val::one());
+        for (m, &(x_m, _)) in shares.iter().enumerate() {
+            if m != j {
+                num *= x_m;
+                den *= x_m - x_j;
+            }
+            (row.num[m], row.den[m]) = (num, den);
+        }
+        // zero, and unsatisfiable, if two shares have the same x
+        row.den_inv = den.try_inverse().unwrap_or(Val::zero());
+        row.lambda = num * row.den_inv;
+        acc += row.lambda * y_j;
+        row.acc = acc;
+
+        let mut inputs = [Val::zero(); WIDTH];
+        if j == K - 1 {
+            (inputs[0], inputs[1]) = (acc, blinding);
+        }
+        commitment = generate_permutation(c, inputs, &mut row.perm);
+    }
+
+    let mut public_values = shares.iter().map(|&(x, _)| x).collect::<Vec<_>>();
+    public_values.extend_from_slice(&commitment[..DIGEST_LEN]);
+    (trace, public_values)
+}
+
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+    let air = ThresholdProof { constants: Poseidon2Constants::from_seed(0x736873) };
+
+    let mut rng = thread_rng();
+    let (secret, blinding): (Val, Val) = (rng.gen(), rng.gen());
+    let shares = split(secret, N);
+    let chosen = [shares[1], shares[2], shares[4], shares[6]];
+    assert_eq!(interpolate_at_zero(&chosen), secret);
+    let (trace, public_values) = generate_trace(&air.constants, &chosen, blinding);
+
+    let mut p_challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &air, &mut p_challenger, trace, &public_values);
+    let mut v_challenger = Challenger::new(perm);
+    verify(&config, &air, &mut v_challenger, &proof, &public_values).unwrap();
+
+    let xs = public_values[..K].iter().map(|x| x.as_canonical_u32()).collect::<Vec<_>>();
+    println!("proven: the shares at x = {:?} of a ({}, {}) sharing recombine to the committed secret", xs, N, K);
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_symmetric::Permutation;
+    use plonky3_cook::{assert_constraints_fail, assert_constraints_ok};
+
+    use super::*;
+
+    fn air() -> ThresholdProof {
+        ThresholdProof { constants: Poseidon2Constants::from_seed(1) }
+    }
+
+    #[test]
+    fn test_any_k_shares_recover_the_secret() {
+        let secret = Val::from_canonical_u32(1234);
+        let shares = split(secret, N);
+        assert_eq!(interpolate_at_zero(&shares[..K]), secret);
+        assert_eq!(interpolate_at_zero(&[shares[6], shares[0], shares[3], shares[5]]), secret);
+        // K - 1 shares fit a polynomial of lower degree, with another constant term
+        assert_ne!(interpolate_at_zero(&shares[..K - 1]), secret);
+    }
+
+    #[test]
+    fn test_recombination() {
+        let air = air();
+        let (secret, blinding) = (Val::from_canonical_u32(1234), Val::from_canonical_u32(99));
+        let shares = split(secret, N);
+        let (trace, public_values) = generate_trace(&air.constants, &shares[2..2 + K], blinding);
+        assert_constraints_ok!(&air, &trace, &public_values);
+
+        let mut inputs = [Val::zero(); WIDTH];
+        (inputs[0], inputs[1]) = (secret, blinding);
+        assert_eq!(public_values[K..], air.constants.perm().permute(inputs)[..DIGEST_LEN]);
+    }
+
+    #[test]
+    fn test_wrong_share_fails() {
+        let air = air();
+        let blinding = Val::from_canonical_u32(99);
+        let mut shares = split(Val::from_canonical_u32(1234), N)[..K].to_vec();
+        let (_, public_values) = generate_trace(&air.constants, &shares, blinding);
+
+        // a consistent trace of the wrong share recombines to another secret
+        shares[1].1 += Val::one();
+        let (trace, _) = generate_trace(&air.constants, &shares, blinding);
+        assert_constraints_fail!(&air, &trace, &public_values, K - 1);
+    }
+
+    #[test]
+    fn test_repeated_share_fails() {
+        let air = air();
+        let shares = split(Val::from_canonical_u32(1234), N);
+        let repeated = [shares[0], shares[1], shares[1], shares[3]];
+        let (trace, public_values) = generate_trace(&air.constants, &repeated, Val::zero());
+        assert_constraints_fail!(&air, &trace, &public_values, 1);
+    }
+
+    #[test]
+    fn test_secret_sharing_round_trip() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let air = air();
+        let shares = split(thread_rng().gen(), N);
+        let (trace, public_values) = generate_trace(&air.constants, &shares[3..], thread_rng().gen());
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &air, &mut p_challenger, trace, &public_values);
+        let mut v_challenger = Challenger::new(perm);
+        verify(&config, &air, &mut v_challenger, &proof, &public_values).unwrap();
+    }
+}
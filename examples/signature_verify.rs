@@ -0,0 +1,310 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_field::{AbstractField, PrimeField32};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::{prove, verify};
+use plonky3_cook::config::{default_config, random_perm, Challenger, Val};
+use plonky3_cook::crypto::sig::{PoseidonLamport, SignatureScheme, CHUNK};
+use plonky3_cook::gadgets::inverse_or_zero::{assert_is_zero, is_zero_witness};
+use plonky3_cook::gadgets::less_than::bit_decompose;
+use plonky3_cook::poseidon2_air::{
+    eval_permutation, generate_permutation, PermutationCols, Poseidon2Constants, PERMUTATION_WIDTH, WIDTH,
+};
+use rand::{thread_rng, Rng};
+use tracing_forest::{util::LevelFilter, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+// Verifying a `PoseidonLamport` signature (`crypto::sig`) in an AIR. The public values are the message,
+// the public key and the outcome, 1 if the signature verifies and 0 if it doesn't; the signature itself is
+// the prover's witness. One permutation per row:
+//   row 0        hashes the message, and decomposes the first digest element into its 31 bits
+//   row i >= 1   hashes the revealed chunk `sig[i - 1]` and compares it with the half of key pair `i - 1`
+//                picked by message bit `i - 1`
+// Rather than index the key and the bits by row, both sit in every row and shift by one pair and one bit
+// per row after the first, so each row compares against `pk[0..2 * CHUNK]` and `bits[0]`.
+//
+// Each comparison is an is-zero flag per digest element, and `acc` multiplies the rows' matches, so the
+// last row's `acc` is the outcome. The outcome is proven either way: a bad signature doesn't break the
+// trace, it makes the outcome 0. A row counter pins the height to `MESSAGE_BITS + 1`: the verifier takes
+// the height from the proof, and a shorter trace would check fewer bits, a taller one compare the later rows
+// against a key shifted down to zeros.
+//
+// The digest element is below `p = 2^31 - 2^27 + 1`, but 31 bits also encode the values in `[p, 2^31)`,
+// which would give the small elements a second decomposition with other low bits. Such a value has its top
+// four bits set and something below them, so `top_ones * low_27_bits == 0` rules it out.
+
+const MESSAGE_BITS: usize = 15;
+const DIGEST_BITS: usize = 31;
+const PK_LEN: usize = 2 * MESSAGE_BITS * CHUNK;
+
+const SV_ROW_WIDTH: usize = PK_LEN + DIGEST_BITS + 1 + 3 * CHUNK - 1 + 2 + PERMUTATION_WIDTH;
+
+struct SignatureVerification {
+    constants: Poseidon2Constants,
+}
+
+impl<F> BaseAir<F> for SignatureVerification {
+    fn width(&self) -> usize {
+        SV_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues<F = Val>> Air<AB> for SignatureVerification {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &SignatureRow<AB::Var> = (*local).borrow();
+        let next: &SignatureRow<AB::Var> = (*next).borrow();
+
+        let pis: Vec<AB::Expr> = builder.public_values().iter().map(|&v| v.into()).collect();
+        let (msg, rest) = pis.split_at(CHUNK);
+        let (pk, outcome) = (&rest[..PK_LEN], rest[PK_LEN].clone());
+
+        let out = eval_permutation(builder, &self.constants, &local.perm);
+        let inputs = local.perm.inputs;
+        let is_first_row = builder.is_first_row();
+
+        // every hash is of one chunk
+        for i in 0..CHUNK {
+            builder.assert_zero(inputs[CHUNK + i]);
+        }
+
+        // row 0: the message, the key, and the message bits
+        for i in 0..CHUNK {
+            builder.when_first_row().assert_eq(inputs[i], msg[i].clone());
+        }
+        for k in 0..PK_LEN {
+            builder.when_first_row().assert_eq(local.pk[k], pk[k].clone());
+        }
+        // `assert_bit_decomposition` stops short of 31 bits, which can wrap; the canonical check covers that
+        let recomposed = |bits: &[AB::Var]| {
+            bits.iter().enumerate().map(|(j, &b)| b * AB::Expr::from_canonical_u32(1 << j)).sum::<AB::Expr>()
+        };
+        let mut first = builder.when_first_row();
+        for &b in &local.bits {
+            first.assert_bool(b);
+        }
+        first.assert_eq(recomposed(&local.bits), out[0].clone());
+        let top = &local.bits[DIGEST_BITS - 4..];
+        first.assert_eq(local.top_ones, top.iter().map(|&b| b.into()).product::<AB::Expr>());
+        first.assert_zero(local.top_ones * recomposed(&local.bits[..DIGEST_BITS - 4]));
+
+        // the hash against the half of the key the bit picks
+        let bit = local.bits[0];
+        for i in 0..CHUNK {
+            let expected = local.pk[i] + bit * (local.pk[CHUNK + i] - local.pk[i]);
+            assert_is_zero(builder, out[i].clone() - expected, local.inv[i], local.is_zero[i]);
+        }
+        builder.assert_eq(local.matched[0], local.is_zero[0] * local.is_zero[1]);
+        for k in 1..CHUNK - 1 {
+            builder.assert_eq(local.matched[k], local.matched[k - 1] * local.is_zero[k + 1]);
+        }
+
+        // the key and the bits shift after every row but the first, and the matches multiply up
+        let shifts = AB::Expr::one() - is_first_row.clone();
+        let mut transition = builder.when_transition();
+        for k in 0..PK_LEN {
+            let shifted = local.pk.get(k + 2 * CHUNK).map_or(AB::Expr::zero(), |&v| v.into());
+            transition.assert_eq(next.pk[k], is_first_row.clone() * local.pk[k] + shifts.clone() * shifted);
+        }
+        for j in 0..DIGEST_BITS {
+            let shifted = local.bits.get(j + 1).map_or(AB::Expr::zero(), |&v| v.into());
+            transition.assert_eq(next.bits[j], is_first_row.clone() * local.bits[j] + shifts.clone() * shifted);
+        }
+        transition.assert_eq(next.acc, local.acc * next.matched[CHUNK - 2]);
+        transition.assert_eq(next.row, local.row + AB::Expr::one());
+
+        builder.when_first_row().assert_one(local.acc);
+        builder.when_first_row().assert_zero(local.row);
+        builder.when_last_row().assert_eq(local.row, AB::Expr::from_canonical_usize(MESSAGE_BITS));
+        builder.when_last_row().assert_eq(local.acc, outcome);
+    }
+}
+
+struct SignatureRow<F> {
+    /// the key pairs not yet compared, front first
+    pub pk: [F; PK_LEN],
+    /// the message bits not yet used, least significant first
+    pub bits: [F; DIGEST_BITS],
+    /// the product of the top four bits, on row 0
+    pub top_ones: F,
+    pub inv: [F; CHUNK],
+    /// per digest element, 1 if it matches the key
+    pub is_zero: [F; CHUNK],
+    /// running products of `is_zero`; the last one is 1 if the whole digest matches
+    pub matched: [F; CHUNK - 1],
+    /// 1 while every row so far matched
+    pub acc: F,
+    /// the row's index
+    pub row: F,
+    pub perm: PermutationCols<F>,
+}
+
+impl<F> Borrow<SignatureRow<F>> for [F] {
+    fn borrow(&self) -> &SignatureRow<F> {
+        debug_assert_eq!(self.len(), SV_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<SignatureRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+/// The trace verifying `sig` on `msg` under `pk`, and `[msg.., pk.., outcome]`.
+fn generate_trace(c: &Poseidon2Constants, msg: &[Val], pk: &[Val], sig: &[Val]) -> (RowMajorMatrix<Val>, Vec<Val>) {
+    assert_eq!((msg.len(), pk.len(), sig.len()), (CHUNK, PK_LEN, MESSAGE_BITS * CHUNK));
+    let height = MESSAGE_BITS + 1;
+
+    let mut trace = RowMajorMatrix::new(vec![Val::zero(); height * SV_ROW_WIDTH], SV_ROW_WIDTH);
+    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<SignatureRow<Val>>() };
+    assert!(prefix.is_empty(), "Alignment should match");
+    assert!(suffix.is_empty(), "Alignment should match");
+
+    let mut bits = vec![];
+    let mut acc = Val::one();
+    for (r, row) in rows.iter_mut().enumerate() {
+        let mut inputs = [Val::zero(); WIDTH];
+        inputs[..CHUNK].copy_from_slice(if r == 0 { msg } else { &sig[(r - 1) * CHUNK..r * CHUNK] });
+        let out = generate_permutation(c, inputs, &mut row.perm);
+
+        let shift = r.saturating_sub(1);
+        if r == 0 {
+            bits = bit_decompose(out[0], DIGEST_BITS);
+            row.top_ones = bits[DIGEST_BITS - 4..].iter().copied().product();
+        }
+        for (j, bit) in row.bits.iter_mut().enumerate() {
+            *bit = bits.get(j + shift).copied().unwrap_or(Val::zero());
+        }
+        for (k, v) in row.pk.iter_mut().enumerate() {
+            *v = pk.get(k + 2 * CHUNK * shift).copied().unwrap_or(Val::zero());
+        }
+
+        for i in 0..CHUNK {
+            let expected = row.pk[i] + row.bits[0] * (row.pk[CHUNK + i] - row.pk[i]);
+            (row.inv[i], row.is_zero[i]) = is_zero_witness(out[i] - expected);
+        }
+        row.matched[0] = row.is_zero[0] * row.is_zero[1];
+        for k in 1..CHUNK - 1 {
+            row.matched[k] = row.matched[k - 1] * row.is_zero[k + 1];
+        }
+        if r > 0 {
+            acc *= row.matched[CHUNK - 2];
+        }
+        row.acc = acc;
+        row.row = Val::from_canonical_usize(r);
+    }
+
+    let mut public_values = [msg, pk].concat();
+    public_values.push(acc);
+    (trace, public_values)
+}
+
+fn random_message() -> Vec<Val> {
+    let mut rng = thread_rng();
+    (0..CHUNK).map(|_| rng.gen()).collect()
+}
+
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+
+    let constants = Poseidon2Constants::from_seed(0x7369);
+    let scheme = PoseidonLamport::new(constants.perm(), MESSAGE_BITS);
+    let (sk, pk) = scheme.keygen(&mut thread_rng());
+    let msg = random_message();
+    let sig = scheme.sign(&sk, &msg);
+
+    let (trace, public_values) = generate_trace(&constants, &msg, &pk, &sig);
+    let air = SignatureVerification { constants };
+
+    let mut p_challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &air, &mut p_challenger, trace, &public_values);
+    let mut v_challenger = Challenger::new(perm);
+    verify(&config, &air, &mut v_challenger, &proof, &public_values).unwrap();
+
+    println!("proven: the signature verification outcome is {}", public_values[CHUNK + PK_LEN].as_canonical_u32());
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky3_cook::{assert_constraints_fail, assert_constraints_ok};
+
+    use super::*;
+
+    const OUTCOME: usize = CHUNK + PK_LEN;
+
+    fn setup() -> (SignatureVerification, PoseidonLamport, Vec<Val>, Vec<Val>, Vec<Val>) {
+        let constants = Poseidon2Constants::from_seed(1);
+        let scheme = PoseidonLamport::new(constants.perm(), MESSAGE_BITS);
+        let (sk, pk) = scheme.keygen(&mut thread_rng());
+        let msg = random_message();
+        let sig = scheme.sign(&sk, &msg);
+        (SignatureVerification { constants }, scheme, msg, pk, sig)
+    }
+
+    #[test]
+    fn test_valid_signature() {
+        let (air, scheme, msg, pk, sig) = setup();
+        let (trace, public_values) = generate_trace(&air.constants, &msg, &pk, &sig);
+        assert_constraints_ok!(&air, &trace, &public_values);
+        assert!(scheme.verify(&pk, &msg, &sig));
+        assert_eq!(public_values[OUTCOME], Val::one());
+    }
+
+    #[test]
+    fn test_forged_signature_proves_outcome_zero() {
+        let (air, scheme, msg, pk, mut sig) = setup();
+        sig[5 * CHUNK + 3] += Val::one();
+        let (trace, mut public_values) = generate_trace(&air.constants, &msg, &pk, &sig);
+        assert_constraints_ok!(&air, &trace, &public_values);
+        assert!(!scheme.verify(&pk, &msg, &sig));
+        assert_eq!(public_values[OUTCOME], Val::zero());
+
+        // and claiming it verified fails on the last row
+        public_values[OUTCOME] = Val::one();
+        assert_constraints_fail!(&air, &trace, &public_values, MESSAGE_BITS);
+    }
+
+    #[test]
+    fn test_short_trace_fails() {
+        // four rows check three bits, and a signature revealing three valid chunks would pass them
+        let (air, _, msg, pk, sig) = setup();
+        let (trace, mut public_values) = generate_trace(&air.constants, &msg, &pk, &sig);
+        let short = RowMajorMatrix::new(trace.values[..4 * SV_ROW_WIDTH].to_vec(), SV_ROW_WIDTH);
+        public_values[OUTCOME] = Val::one();
+        assert_constraints_fail!(&air, &short, &public_values, 3);
+    }
+
+    #[test]
+    fn test_other_message_fails() {
+        let (air, _, msg, pk, sig) = setup();
+        let (trace, mut public_values) = generate_trace(&air.constants, &msg, &pk, &sig);
+        public_values[2] += Val::one();
+        assert_constraints_fail!(&air, &trace, &public_values, 0);
+    }
+
+    #[test]
+    fn test_signature_verify_round_trip() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let (air, _, msg, pk, sig) = setup();
+        let (trace, public_values) = generate_trace(&air.constants, &msg, &pk, &sig);
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &air, &mut p_challenger, trace, &public_values);
+        let mut v_challenger = Challenger::new(perm);
+        verify(&config, &air, &mut v_challenger, &proof, &public_values).unwrap();
+    }
+}
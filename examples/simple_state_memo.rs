@@ -0,0 +1,152 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, BaseAir};
+use p3_field::{AbstractField, Field, PrimeField32};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::{prove, verify};
+use plonky3_cook::config::{default_config, random_perm, Challenger, Val};
+use plonky3_cook::gadgets::optional::{assert_optional_columns, zero_fill_unused};
+use plonky3_cook::simple_state::{random_trace, SimStateRow, SS_ROW_WIDTH};
+use rand::{distributions::{Distribution, Standard}, thread_rng, Rng};
+use tracing_forest::{util::LevelFilter, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+// SimpleState where some transactions carry a memo.
+//
+// `memo` and `memo_inv` are optional columns: they are only used on rows with `has_memo == 1` and are
+// zero-filled everywhere else. A memo must be nonzero, which is shown with `memo * memo_inv == 1`. On a row
+// without a memo that constraint would fail on the zero fill, so it is gated on `has_memo`.
+
+const MEMO_ROW_WIDTH: usize = SS_ROW_WIDTH + 3;
+
+const HAS_MEMO_COL: usize = SS_ROW_WIDTH;
+const MEMO_COL: usize = SS_ROW_WIDTH + 1;
+const MEMO_INV_COL: usize = SS_ROW_WIDTH + 2;
+
+struct SimpleStateMemo {}
+
+impl<F> BaseAir<F> for SimpleStateMemo {
+    fn width(&self) -> usize {
+        MEMO_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilder> Air<AB> for SimpleStateMemo {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &MemoRow<AB::Var> = (*local).borrow();
+        let next: &MemoRow<AB::Var> = (*next).borrow();
+
+        builder.when_transition().assert_eq(
+            local.state.balance + local.state.input - local.state.output,
+            next.state.balance,
+        );
+
+        assert_optional_columns(builder, local.has_memo, &[local.memo, local.memo_inv]);
+        builder.when(local.has_memo).assert_one(local.memo * local.memo_inv);
+    }
+}
+
+struct MemoRow<F> {
+    pub state: SimStateRow<F>,
+    pub has_memo: F,
+    pub memo: F,
+    pub memo_inv: F,
+}
+
+impl<F> Borrow<MemoRow<F>> for [F] {
+    fn borrow(&self) -> &MemoRow<F> {
+        debug_assert_eq!(self.len(), MEMO_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<MemoRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+/// A SimpleState trace where roughly one row in `memo_every` carries a random memo.
+fn memo_trace<F: PrimeField32>(log_n: usize, memo_every: u32) -> RowMajorMatrix<F> where Standard: Distribution<F> {
+    let state_trace = random_trace::<F>(log_n);
+
+    let mut rng = thread_rng();
+    let mut values = Vec::with_capacity(state_trace.height() * MEMO_ROW_WIDTH);
+    for state in state_trace.values.chunks_exact(SS_ROW_WIDTH) {
+        values.extend_from_slice(state);
+
+        // the optional columns are filled with garbage here, `zero_fill_unused` cleans them up below
+        let has_memo = rng.gen_ratio(1, memo_every);
+        let memo: F = rng.gen();
+        let memo = if memo.is_zero() { F::one() } else { memo };
+        values.extend([F::from_bool(has_memo), memo, memo.inverse()]);
+    }
+
+    let mut trace = RowMajorMatrix::new(values, MEMO_ROW_WIDTH);
+    zero_fill_unused(&mut trace, HAS_MEMO_COL, &[MEMO_COL, MEMO_INV_COL]);
+    trace
+}
+
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+
+    let trace = memo_trace::<Val>(10, 4);
+
+    let mut p_challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &SimpleStateMemo {}, &mut p_challenger, trace, &vec![]);
+    let mut v_challenger = Challenger::new(perm);
+    verify(&config, &SimpleStateMemo {}, &mut v_challenger, &proof, &vec![]).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky3_cook::{assert_constraints_fail, assert_constraints_ok};
+
+    use super::*;
+
+    #[test]
+    fn test_memo_constraints() {
+        let mut trace = memo_trace::<Val>(12, 4);
+        assert_constraints_ok!(&SimpleStateMemo {}, &trace, &[]);
+
+        // data in the memo column of a row without a memo is rejected
+        let plain_row = (0..trace.height()).find(|&i| trace.row_slice(i)[HAS_MEMO_COL].is_zero()).unwrap();
+        trace.row_mut(plain_row)[MEMO_COL] = Val::one();
+        assert_constraints_fail!(&SimpleStateMemo {}, &trace, &[], plain_row);
+    }
+
+    #[test]
+    fn test_mixed_memo_rows_prove() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+
+        let trace = memo_trace::<Val>(8, 3);
+        let memo_rows = trace.values.chunks_exact(MEMO_ROW_WIDTH).filter(|row| row[HAS_MEMO_COL].is_one()).count();
+        assert!(memo_rows > 0 && memo_rows < trace.height(), "trace should mix memo and plain rows");
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &SimpleStateMemo {}, &mut p_challenger, trace, &vec![]);
+        let mut v_challenger = Challenger::new(perm);
+        verify(&config, &SimpleStateMemo {}, &mut v_challenger, &proof, &vec![]).unwrap();
+    }
+
+    #[test]
+    fn test_unused_columns_are_zero_filled() {
+        let trace = memo_trace::<Val>(6, 2);
+        for row in trace.values.chunks_exact(MEMO_ROW_WIDTH).filter(|row| row[HAS_MEMO_COL].is_zero()) {
+            assert!(row[MEMO_COL].is_zero());
+            assert!(row[MEMO_INV_COL].is_zero());
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_field::{AbstractField, PrimeField32};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::{prove, verify, Proof};
+use plonky3_cook::config::{default_config, random_perm, Challenger, MyConfig, Val};
+use plonky3_cook::gadgets::comparison::{assert_le, le_witness};
+use rand::{thread_rng, Rng};
+use tracing_forest::{util::LevelFilter, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+// Proves a column is non-decreasing: on every transition `value <= next_value`, via `assert_le`, which range
+// checks the difference `next_value - value` into `DIFF_BITS` bits held on the row.
+//
+// A range-checked difference on its own only says `next = value + d mod p` with small `d`; it's the absence
+// of wrap-around that makes that an ordering. The first value is public and the verifier checks it is below
+// `2^VALUE_BITS`, and every step adds less than `2^DIFF_BITS`, so for traces up to `2^MAX_LOG_N` rows the
+// running value stays far below the BabyBear modulus and never wraps. The height comes from the proof, so the
+// verifier checks that bound too. The public values are the first and
+// last entries, the minimum and maximum of the sorted column.
+
+const VALUE_BITS: usize = 16;
+const DIFF_BITS: usize = 16;
+const MAX_LOG_N: usize = 12;
+
+const SO_ROW_WIDTH: usize = 1 + DIFF_BITS;
+
+struct SortedAir {}
+
+impl<F> BaseAir<F> for SortedAir {
+    fn width(&self) -> usize {
+        SO_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues> Air<AB> for SortedAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &SortedRow<AB::Var> = (*local).borrow();
+        let next: &SortedRow<AB::Var> = (*next).borrow();
+
+        let pis = builder.public_values();
+        let (min, max): (AB::Expr, AB::Expr) = (pis[0].into(), pis[1].into());
+
+        assert_le(&mut builder.when_transition(), local.value, next.value, &local.diff_bits);
+
+        builder.when_first_row().assert_eq(local.value, min);
+        builder.when_last_row().assert_eq(local.value, max);
+    }
+}
+
+struct SortedRow<F> {
+    pub value: F,
+    /// bits of `next_value - value`, zero on the last row
+    pub diff_bits: [F; DIFF_BITS],
+}
+
+impl<F> Borrow<SortedRow<F>> for [F] {
+    fn borrow(&self) -> &SortedRow<F> {
+        debug_assert_eq!(self.len(), SO_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<SortedRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+/// Returns the trace for `values` in the given order, with `[first, last]` as public values.
+///
+/// A decreasing step has no valid difference bits; its bits are left zero so the trace can still be built,
+/// and the constraints reject it.
+fn generate_trace<F: PrimeField32>(values: &[u32]) -> (RowMajorMatrix<F>, Vec<F>) {
+    let n = values.len();
+    assert!(n.is_power_of_two(), "trace height must be a power of two");
+    assert!(n <= 1 << MAX_LOG_N, "trace height must be at most 2^{}", MAX_LOG_N);
+
+    let mut trace = RowMajorMatrix::new(vec![F::zero(); n * SO_ROW_WIDTH], SO_ROW_WIDTH);
+
+    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<SortedRow<F>>() };
+    assert!(prefix.is_empty(), "Alignment should match");
+    assert!(suffix.is_empty(), "Alignment should match");
+    assert_eq!(rows.len(), n);
+
+    for (i, row) in rows.iter_mut().enumerate() {
+        row.value = F::from_canonical_u32(values[i]);
+        if let Some(&next) = values.get(i + 1).filter(|&&next| next >= values[i]) {
+            let bits = le_witness(row.value, F::from_canonical_u32(next), DIFF_BITS);
+            row.diff_bits.copy_from_slice(&bits);
+        }
+    }
+
+    (trace, vec![F::from_canonical_u32(values[0]), F::from_canonical_u32(values[n - 1])])
+}
+
+/// Sorts `values` and returns the sorted column with its trace and public values.
+fn sorted_trace<F: PrimeField32>(values: &[u32]) -> (Vec<u32>, RowMajorMatrix<F>, Vec<F>) {
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    let (trace, public_values) = generate_trace(&sorted);
+    (sorted, trace, public_values)
+}
+
+/// The verifier's side of the no-wrap argument: the proof covers at most `2^MAX_LOG_N` rows and the public
+/// minimum fits in `VALUE_BITS` bits. Run before `verify`.
+fn check_statement(proof: &Proof<MyConfig>, public_values: &[Val]) -> Result<(), String> {
+    if proof.degree_bits > MAX_LOG_N {
+        return Err(format!("trace height 2^{} exceeds 2^{}", proof.degree_bits, MAX_LOG_N));
+    }
+    check_public_values(public_values)
+}
+
+fn check_public_values(public_values: &[Val]) -> Result<(), String> {
+    match public_values {
+        [min, _] if min.as_canonical_u32() >> VALUE_BITS == 0 => Ok(()),
+        [min, _] => Err(format!("minimum {} does not fit in {} bits", min, VALUE_BITS)),
+        _ => Err(format!("expected [min, max], got {} public values", public_values.len())),
+    }
+}
+
+/// Values spread over `0..2^VALUE_BITS`, close enough that consecutive sorted values differ by well under
+/// `2^DIFF_BITS`.
+fn random_values(n: usize) -> Vec<u32> {
+    let mut rng = thread_rng();
+    (0..n).map(|_| rng.gen_range(0..1 << VALUE_BITS)).collect()
+}
+
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+
+    let (sorted, trace, public_values) = sorted_trace::<Val>(&random_values(1 << 10));
+
+    let mut p_challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &SortedAir {}, &mut p_challenger, trace, &public_values);
+    check_statement(&proof, &public_values).unwrap();
+    let mut v_challenger = Challenger::new(perm);
+    verify(&config, &SortedAir {}, &mut v_challenger, &proof, &public_values).unwrap();
+
+    println!("proven sorted: {} values from {} to {}", sorted.len(), sorted[0], sorted[sorted.len() - 1]);
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky3_cook::{assert_constraints_fail, assert_constraints_ok};
+
+    use super::*;
+
+    #[test]
+    fn test_sorted_column_passes() {
+        let (sorted, trace, public_values) = sorted_trace::<Val>(&random_values(1 << 8));
+        assert!(sorted.windows(2).all(|w| w[0] <= w[1]));
+        assert_constraints_ok!(&SortedAir {}, &trace, &public_values);
+    }
+
+    #[test]
+    fn test_repeated_values_pass() {
+        let (trace, public_values) = generate_trace::<Val>(&[3, 3, 3, 5, 5, 9, 9, 9]);
+        assert_constraints_ok!(&SortedAir {}, &trace, &public_values);
+    }
+
+    #[test]
+    fn test_unsorted_column_fails() {
+        let (trace, public_values) = generate_trace::<Val>(&[1, 2, 4, 3, 5, 6, 7, 8]);
+        assert_constraints_fail!(&SortedAir {}, &trace, &public_values, 2);
+    }
+
+    #[test]
+    fn test_wrapping_step_needs_public_check() {
+        // p - 1 -> 0 is a step of +1 mod p, which the constraints alone accept
+        let (mut trace, public_values) = generate_trace::<Val>(&[Val::ORDER_U32 - 1, 0, 1, 2, 3, 4, 5, 6]);
+        trace.row_mut(0)[1] = Val::one();
+        assert_constraints_ok!(&SortedAir {}, &trace, &public_values);
+        assert!(check_public_values(&public_values).is_err());
+    }
+
+    #[test]
+    fn test_sorted_round_trip() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let (_, trace, public_values) = sorted_trace::<Val>(&random_values(1 << 8));
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let mut proof = prove(&config, &SortedAir {}, &mut p_challenger, trace, &public_values);
+        check_statement(&proof, &public_values).unwrap();
+        let mut v_challenger = Challenger::new(perm);
+        verify(&config, &SortedAir {}, &mut v_challenger, &proof, &public_values).unwrap();
+
+        // a proof claiming more rows than the no-wrap bound allows
+        proof.degree_bits = MAX_LOG_N + 1;
+        assert!(check_statement(&proof, &public_values).is_err());
+    }
+}
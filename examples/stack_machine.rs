@@ -0,0 +1,381 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_field::{AbstractExtensionField, AbstractField, Field, PrimeField32};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use plonky3_cook::config::{default_config, random_perm, Challenge, MyConfig, Perm, Val};
+use plonky3_cook::error::CookError;
+use plonky3_cook::gadgets::less_than::{assert_lt, lt_witness};
+use plonky3_cook::gadgets::one_hot::assert_one_hot;
+use plonky3_cook::gadgets::optional::assert_optional_columns;
+use plonky3_cook::lookups::{
+    assert_ext_eq, ext, ext_add, ext_scale, ext_sub, ext_times, ext_values, lift, prove_two_round, verify_two_round,
+    TwoRoundAir, TwoRoundProof, EXT,
+};
+use rand::{thread_rng, Rng};
+use tracing_forest::{util::LevelFilter, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+// A stack machine: each row runs one op, push, pop or nop, against a stack whose pointer `sp` starts at 0
+// and moves by `is_push - is_pop`. A push writes `value` at address `sp`; a pop reads address `sp - 1`.
+//
+// Memory consistency, that every pop returns what the matching push wrote, is a LogUp lookup of pops into
+// pushes over the fingerprint of `(address, value, clk)`:
+//   sum over push rows of  multiplicity / (z - (sp + alpha * value + alpha^2 * clk))
+//   == sum over pop rows of  1 / (z - (sp - 1 + alpha * value + alpha^2 * push_clk))
+// where a pop names the clock of the push it consumes and `push_clk < clk` is range checked. With boolean
+// multiplicities each push is consumed at most once, and since `sp` moves by one per op, pushes and pops at
+// one address alternate; so the only assignment that balances is the one pairing each pop with the latest
+// unconsumed push at its address, which is the stack discipline. Popping an empty stack addresses
+// `sp - 1 = -1`, where nothing was ever pushed, and fails the lookup.
+//
+// The op columns are the first round of a `lookups::TwoRoundAir`: they are committed, `alpha` and `z` are
+// drawn from `Challenge` after them, and the commitment is opened at the proof's out-of-domain point and
+// compared with the proven trace, so the pops can't be re-chosen once the challenges are known. The inverses
+// and the accumulator are extension elements, `EXT` columns each. The public values are
+// `[alpha, z, final_sp]`, `alpha` and `z` taking `EXT` values each.
+
+const CLK_BITS: usize = 16;
+
+// the op columns, committed before the challenges are drawn
+const OP_WIDTH: usize = 8 + CLK_BITS;
+const SM_ROW_WIDTH: usize = OP_WIDTH + 3 * EXT;
+
+struct StackMachineAir {}
+
+impl<F> BaseAir<F> for StackMachineAir {
+    fn width(&self) -> usize {
+        SM_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues<F = Val>> Air<AB> for StackMachineAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &StackRow<AB::Var> = (*local).borrow();
+        let next: &StackRow<AB::Var> = (*next).borrow();
+
+        let pis = builder.public_values();
+        let (alpha, z): ([AB::Expr; EXT], [AB::Expr; EXT]) = (ext(&pis[..EXT]), ext(&pis[EXT..2 * EXT]));
+        let final_sp: AB::Expr = pis[2 * EXT].into();
+
+        assert_one_hot(builder, &[local.is_push, local.is_pop, local.is_nop]);
+        builder.when(local.is_nop).assert_zero(local.value);
+
+        builder.when_first_row().assert_zero(local.clk);
+        builder.when_transition().assert_eq(next.clk, local.clk + AB::Expr::one());
+
+        // the stack pointer
+        let sp_after = local.sp + local.is_push - local.is_pop;
+        builder.when_first_row().assert_zero(local.sp);
+        builder.when_transition().assert_eq(next.sp, sp_after.clone());
+        builder.when_last_row().assert_eq(sp_after, final_sp);
+
+        // a pop consumes an earlier push
+        let mut pop_columns = vec![local.push_clk];
+        pop_columns.extend(local.clk_lt_bits);
+        assert_optional_columns(builder, local.is_pop, &pop_columns);
+        assert_lt(&mut builder.when(local.is_pop), local.push_clk, local.clk, &local.clk_lt_bits);
+        assert_optional_columns(builder, local.is_push, &[local.multiplicity]);
+        builder.assert_bool(local.multiplicity);
+
+        // the lookup
+        let alpha_sq = ext_times(&alpha, &alpha);
+        let fingerprint = |address: AB::Expr, value: AB::Var, clk: AB::Var| {
+            let terms = ext_add(ext_scale(alpha.clone(), value.into()), ext_scale(alpha_sq.clone(), clk.into()));
+            ext_sub(z.clone(), ext_add(lift(address), terms))
+        };
+        let table = fingerprint(local.sp.into(), local.value, local.clk);
+        let query = fingerprint(local.sp - AB::Expr::one(), local.value, local.push_clk);
+        assert_ext_eq(builder, ext_times(&ext(&local.table_inv), &table), lift(AB::Expr::one()));
+        assert_ext_eq(builder, ext_times(&ext(&local.query_inv), &query), lift(AB::Expr::one()));
+
+        let delta = |row: &StackRow<AB::Var>| {
+            let pushed = ext_scale(ext::<AB::Expr, _>(&row.table_inv), row.multiplicity.into());
+            ext_sub(pushed, ext_scale(ext(&row.query_inv), row.is_pop.into()))
+        };
+        assert_ext_eq(&mut builder.when_first_row(), ext(&local.acc), delta(local));
+        assert_ext_eq(&mut builder.when_transition(), ext(&next.acc), ext_add(ext(&local.acc), delta(next)));
+        assert_ext_eq(&mut builder.when_last_row(), ext(&local.acc), lift(AB::Expr::zero()));
+    }
+}
+
+struct StackRow<F> {
+    pub clk: F,
+    pub is_push: F,
+    pub is_pop: F,
+    pub is_nop: F,
+    /// the stack pointer before the op
+    pub sp: F,
+    /// pushed or popped
+    pub value: F,
+    /// on a pop, the clock of the push it consumes
+    pub push_clk: F,
+    /// bits of `clk - push_clk - 1`
+    pub clk_lt_bits: [F; CLK_BITS],
+    /// on a push, whether a later pop consumes it
+    pub multiplicity: F,
+    // filled after the challenges are drawn
+    pub table_inv: [F; EXT],
+    pub query_inv: [F; EXT],
+    pub acc: [F; EXT],
+}
+
+impl<F> Borrow<StackRow<F>> for [F] {
+    fn borrow(&self) -> &StackRow<F> {
+        debug_assert_eq!(self.len(), SM_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<StackRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+    Push(u32),
+    Pop,
+    Nop,
+}
+
+/// A random program of `n` ops that never pops an empty stack.
+fn random_program(n: usize) -> Vec<Op> {
+    let mut rng = thread_rng();
+    let mut depth = 0;
+    (0..n)
+        .map(|_| match rng.gen_range(0..3) {
+            0 => {
+                depth += 1;
+                Op::Push(rng.gen_range(0..1 << 20))
+            }
+            1 if depth > 0 => {
+                depth -= 1;
+                Op::Pop
+            }
+            _ => Op::Nop,
+        })
+        .collect()
+}
+
+fn rows_mut<F>(trace: &mut RowMajorMatrix<F>) -> &mut [StackRow<F>] {
+    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<StackRow<F>>() };
+    assert!(prefix.is_empty(), "Alignment should match");
+    assert!(suffix.is_empty(), "Alignment should match");
+    rows
+}
+
+/// The trace with the op columns filled in and the lookup columns left zero, and the final stack pointer.
+///
+/// A pop of an empty stack is still written, reading 0 from a push at clock 0 that doesn't exist; the
+/// constraints reject it.
+fn op_trace<F: PrimeField32>(program: &[Op]) -> (RowMajorMatrix<F>, F) {
+    let n = program.len();
+    assert!(n.is_power_of_two(), "trace height must be a power of two");
+    assert!(n <= 1 << CLK_BITS, "clock doesn't fit in {} bits", CLK_BITS);
+
+    let mut trace = RowMajorMatrix::new(vec![F::zero(); n * SM_ROW_WIDTH], SM_ROW_WIDTH);
+    let rows = rows_mut(&mut trace);
+
+    // `(value, clk)` of each push still on the stack
+    let mut stack: Vec<(u32, usize)> = vec![];
+    let mut sp = F::zero();
+    for (clk, op) in program.iter().enumerate() {
+        let row = &mut rows[clk];
+        row.clk = F::from_canonical_usize(clk);
+        row.sp = sp;
+        match *op {
+            Op::Push(value) => {
+                row.is_push = F::one();
+                row.value = F::from_canonical_u32(value);
+                stack.push((value, clk));
+                sp += F::one();
+            }
+            Op::Pop => {
+                row.is_pop = F::one();
+                if let Some((value, push_clk)) = stack.pop() {
+                    row.value = F::from_canonical_u32(value);
+                    row.push_clk = F::from_canonical_usize(push_clk);
+                    rows[push_clk].multiplicity = F::one();
+                    let row = &mut rows[clk];
+                    let bits = lt_witness(row.push_clk, row.clk, CLK_BITS);
+                    row.clk_lt_bits.copy_from_slice(&bits);
+                }
+                sp -= F::one();
+            }
+            Op::Nop => row.is_nop = F::one(),
+        }
+    }
+
+    (trace, sp)
+}
+
+fn fingerprint(alpha: Challenge, address: Val, value: Val, clk: Val) -> Challenge {
+    alpha * value + alpha.square() * clk + address
+}
+
+/// Fills the lookup columns for the challenges `alpha` and `z`.
+fn fill_lookup(trace: &mut RowMajorMatrix<Val>, alpha: Challenge, z: Challenge) {
+    let mut acc = Challenge::zero();
+    for row in rows_mut(trace) {
+        let table_inv = (z - fingerprint(alpha, row.sp, row.value, row.clk)).inverse();
+        let query_inv = (z - fingerprint(alpha, row.sp - Val::one(), row.value, row.push_clk)).inverse();
+        acc += table_inv * row.multiplicity - query_inv * row.is_pop;
+        row.table_inv.copy_from_slice(table_inv.as_base_slice());
+        row.query_inv.copy_from_slice(query_inv.as_base_slice());
+        row.acc.copy_from_slice(acc.as_base_slice());
+    }
+}
+
+impl TwoRoundAir for StackMachineAir {
+    fn committed_width(&self) -> usize {
+        OP_WIDTH
+    }
+
+    fn num_challenges(&self) -> usize {
+        2
+    }
+
+    fn num_public_values(&self) -> usize {
+        1
+    }
+
+    fn complete_trace(&self, trace: &mut RowMajorMatrix<Val>, challenges: &[Challenge]) -> Vec<Val> {
+        fill_lookup(trace, challenges[0], challenges[1]);
+        let last = rows_mut(trace).last().expect("the trace is not empty");
+        vec![last.sp + last.is_push - last.is_pop]
+    }
+}
+
+fn prove_stack(config: &MyConfig, perm: &Perm, program: &[Op]) -> TwoRoundProof {
+    let (trace, _) = op_trace::<Val>(program);
+    prove_two_round(config, perm, &StackMachineAir {}, trace, &[])
+}
+
+fn verify_stack(config: &MyConfig, perm: &Perm, proof: &TwoRoundProof) -> Result<(), CookError> {
+    verify_two_round(config, perm, &StackMachineAir {}, &[], proof).map(|_| ())
+}
+
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+
+    let program = random_program(1 << 10);
+    let proof = prove_stack(&config, &perm, &program);
+    verify_stack(&config, &perm, &proof).unwrap();
+
+    let pushes = program.iter().filter(|op| matches!(op, Op::Push(_))).count();
+    let pops = program.iter().filter(|op| **op == Op::Pop).count();
+    println!(
+        "proven: {} ops, {} pushes, {} pops, final depth {}",
+        program.len(),
+        pushes,
+        pops,
+        proof.public_values[2 * EXT]
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky3_cook::{assert_constraints_fail, assert_constraints_ok};
+
+    use super::*;
+
+    const FINAL_SP: usize = 2 * EXT;
+
+    fn challenges() -> (Challenge, Challenge) {
+        let challenge = |seed: usize| Challenge::from_base_fn(|i| Val::from_canonical_usize(seed + i));
+        (challenge(123_456), challenge(987_654_321))
+    }
+
+    fn public_values(alpha: Challenge, z: Challenge, final_sp: Val) -> Vec<Val> {
+        [ext_values(&[alpha, z]), vec![final_sp]].concat()
+    }
+
+    fn trace_with_challenges(program: &[Op]) -> (RowMajorMatrix<Val>, Vec<Val>) {
+        let (alpha, z) = challenges();
+        let (mut trace, final_sp) = op_trace(program);
+        fill_lookup(&mut trace, alpha, z);
+        (trace, public_values(alpha, z, final_sp))
+    }
+
+    fn padded(mut program: Vec<Op>, n: usize) -> Vec<Op> {
+        program.resize(n, Op::Nop);
+        program
+    }
+
+    #[test]
+    fn test_balanced_program() {
+        let program = padded(vec![Op::Push(5), Op::Push(7), Op::Pop, Op::Push(9), Op::Pop, Op::Pop], 8);
+        let (trace, pis) = trace_with_challenges(&program);
+        assert_eq!(pis[FINAL_SP], Val::zero());
+        assert_constraints_ok!(&StackMachineAir {}, &trace, &pis);
+    }
+
+    #[test]
+    fn test_random_program() {
+        let (trace, pis) = trace_with_challenges(&random_program(1 << 6));
+        assert_constraints_ok!(&StackMachineAir {}, &trace, &pis);
+    }
+
+    #[test]
+    fn test_leftover_pushes_fail_balanced_claim() {
+        let program = padded(vec![Op::Push(5), Op::Push(7), Op::Pop], 4);
+        let (trace, mut pis) = trace_with_challenges(&program);
+        assert_eq!(pis[FINAL_SP], Val::one());
+        assert_constraints_ok!(&StackMachineAir {}, &trace, &pis);
+
+        pis[FINAL_SP] = Val::zero();
+        assert_constraints_fail!(&StackMachineAir {}, &trace, &pis, 3);
+    }
+
+    #[test]
+    fn test_pop_of_empty_stack_fails() {
+        let program = padded(vec![Op::Push(5), Op::Pop, Op::Pop], 4);
+        let (trace, pis) = trace_with_challenges(&program);
+        assert_constraints_fail!(&StackMachineAir {}, &trace, &pis, 2);
+    }
+
+    #[test]
+    fn test_swapped_pops_fail_lookup() {
+        let program = vec![Op::Push(5), Op::Push(7), Op::Pop, Op::Pop];
+        let (mut trace, _) = op_trace::<Val>(&program);
+        // the pops claim each other's pushes
+        {
+            let rows = rows_mut(&mut trace);
+            (rows[2].value, rows[3].value) = (rows[3].value, rows[2].value);
+            (rows[2].push_clk, rows[3].push_clk) = (rows[3].push_clk, rows[2].push_clk);
+            for clk in [2, 3] {
+                let bits = lt_witness(rows[clk].push_clk, rows[clk].clk, CLK_BITS);
+                rows[clk].clk_lt_bits.copy_from_slice(&bits);
+            }
+        }
+        let (alpha, z) = challenges();
+        fill_lookup(&mut trace, alpha, z);
+        assert_constraints_fail!(&StackMachineAir {}, &trace, &public_values(alpha, z, Val::zero()), 3);
+    }
+
+    #[test]
+    fn test_stack_round_trip() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+
+        let mut proof = prove_stack(&config, &perm, &random_program(64));
+        verify_stack(&config, &perm, &proof).unwrap();
+
+        // ops other than the committed ones show up at `zeta`
+        proof.committed_opening[0] += Challenge::one();
+        assert!(matches!(verify_stack(&config, &perm, &proof), Err(CookError::PublicValues(_))));
+    }
+}
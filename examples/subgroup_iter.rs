@@ -0,0 +1,165 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_field::{AbstractField, Field, TwoAdicField};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::{prove, verify};
+use plonky3_cook::config::{default_config, random_perm, Challenger, Val};
+use plonky3_cook::gadgets::inverse_or_zero::{assert_inverse_or_zero, inverse_or_zero};
+use tracing_forest::{util::LevelFilter, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+// Walks a multiplicative subgroup: row i holds `g^(i+1)`, with the generator `g` as the public value.
+//
+// `element` starts at `g` and is multiplied by `g` on every step, and must be 1 on the last row. That alone
+// only shows `g^n == 1`, i.e. that the order of `g` divides `n`. To show the order is exactly `n` (so the
+// column really lists every element of the subgroup of size `n`) `is_one` must be an exact indicator of
+// `element == 1`, which takes one more column: `inv`, the inverse-or-zero of `element - 1`, with
+// `is_one = 1 - (element - 1) * inv`. `is_one` is then required to be 0 on every row but the last.
+
+const SI_ROW_WIDTH: usize = 3;
+
+struct SubgroupIterAir {}
+
+impl<F> BaseAir<F> for SubgroupIterAir {
+    fn width(&self) -> usize {
+        SI_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues> Air<AB> for SubgroupIterAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &SubgroupIterRow<AB::Var> = (*local).borrow();
+        let next: &SubgroupIterRow<AB::Var> = (*next).borrow();
+
+        let generator: AB::Expr = builder.public_values()[0].into();
+
+        builder.when_first_row().assert_eq(local.element, generator.clone());
+        builder.when_transition().assert_eq(next.element, local.element * generator);
+        builder.when_last_row().assert_one(local.element);
+
+        let element_minus_one = local.element - AB::Expr::one();
+        assert_inverse_or_zero(builder, element_minus_one.clone(), local.inv);
+        builder.assert_eq(local.is_one, AB::Expr::one() - element_minus_one * local.inv);
+        builder.when_transition().assert_zero(local.is_one);
+        builder.when_last_row().assert_one(local.is_one);
+    }
+}
+
+struct SubgroupIterRow<F> {
+    pub element: F,
+    pub is_one: F,
+    pub inv: F,
+}
+
+impl<F> Borrow<SubgroupIterRow<F>> for [F] {
+    fn borrow(&self) -> &SubgroupIterRow<F> {
+        debug_assert_eq!(self.len(), SI_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<SubgroupIterRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+/// Returns the `2^log_n` rows walking the powers of `generator`; the generator is the public value.
+fn generate_trace<F: Field>(log_n: usize, generator: F) -> RowMajorMatrix<F> {
+    let n = 1 << log_n;
+    let mut trace = RowMajorMatrix::new(vec![F::zero(); n * SI_ROW_WIDTH], SI_ROW_WIDTH);
+
+    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<SubgroupIterRow<F>>() };
+    assert!(prefix.is_empty(), "Alignment should match");
+    assert!(suffix.is_empty(), "Alignment should match");
+    assert_eq!(rows.len(), n);
+
+    let mut element = generator;
+    for row in rows.iter_mut() {
+        *row = SubgroupIterRow {
+            element,
+            is_one: F::from_bool(element.is_one()),
+            inv: inverse_or_zero(element - F::one()),
+        };
+        element *= generator;
+    }
+
+    trace
+}
+
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+
+    let log_n = 10;
+    let generator = Val::two_adic_generator(log_n);
+    let trace = generate_trace(log_n, generator);
+
+    let mut p_challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &SubgroupIterAir {}, &mut p_challenger, trace, &vec![generator]);
+    let mut v_challenger = Challenger::new(perm);
+    verify(&config, &SubgroupIterAir {}, &mut v_challenger, &proof, &vec![generator]).unwrap();
+
+    println!("proven: {} generates the subgroup of order 2^{}", generator, log_n);
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky3_cook::{assert_constraints_fail, assert_constraints_ok};
+
+    use super::*;
+
+    #[test]
+    fn test_subgroup_iter_constraints() {
+        let log_n = 8;
+        let generator = Val::two_adic_generator(log_n);
+        let trace = generate_trace(log_n, generator);
+        assert_constraints_ok!(&SubgroupIterAir {}, &trace, &[generator]);
+
+        // every element of the subgroup appears exactly once
+        let mut elements = trace.values.chunks_exact(SI_ROW_WIDTH).map(|row| row[0]).collect::<Vec<_>>();
+        elements.sort_by_key(|x| x.to_string());
+        elements.dedup();
+        assert_eq!(elements.len(), 1 << log_n);
+    }
+
+    #[test]
+    fn test_generator_of_smaller_order_is_rejected() {
+        // g^n is still 1, but the walk returns to 1 halfway through
+        let log_n = 8;
+        let generator = Val::two_adic_generator(log_n - 1);
+        let trace = generate_trace(log_n, generator);
+        assert_constraints_fail!(&SubgroupIterAir {}, &trace, &[generator], (1 << (log_n - 1)) - 1);
+    }
+
+    #[test]
+    fn test_subgroup_iter_proves() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+
+        let log_n = 6;
+        let generator = Val::two_adic_generator(log_n);
+        let trace = generate_trace(log_n, generator);
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &SubgroupIterAir {}, &mut p_challenger, trace, &vec![generator]);
+
+        let mut v_challenger = Challenger::new(perm.clone());
+        verify(&config, &SubgroupIterAir {}, &mut v_challenger, &proof, &vec![generator]).unwrap();
+
+        let other = Val::two_adic_generator(log_n).square();
+        let mut v_challenger = Challenger::new(perm);
+        assert!(verify(&config, &SubgroupIterAir {}, &mut v_challenger, &proof, &vec![other]).is_err());
+    }
+}
@@ -0,0 +1,229 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_field::{AbstractField, PrimeField32};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::{prove, verify};
+use plonky3_cook::config::{default_config, random_perm, Challenger, Val};
+use plonky3_cook::gadgets::less_than::{assert_bit_decomposition, bit_decompose};
+use rand::{thread_rng, Rng};
+use tracing_forest::{util::LevelFilter, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+// A token ledger over `ACCOUNTS` accounts whose total supply never changes. Row `i` holds every balance
+// before entry `i` and the entry's per-account `deltas`; the next row's balances are this row's plus the
+// deltas. Nothing constrains an entry's shape, so a transfer, a split payment and an illicit mint are all
+// just deltas. What rules out the mint is a global running sum of every delta,
+//   net == 0 on the first row,   next.net == net + sum(deltas),   net == 0 on the last row
+// so the deltas of the whole ledger cancel: total in == total out. With the first row's balances summing to
+// the public `total_supply`, every later row's balances sum to `total_supply + net`, and the last row's to
+// `total_supply` again.
+//
+// The check is over the field, so it also needs the balances range checked: without it, an entry could
+// mint `x` into one account and "burn" it from an empty one, leaving that account at `-x`. With every
+// balance below `2^BALANCE_BITS`, the true supply is far below the modulus and can't wrap around.
+// The last row is the closing state: its deltas would apply to a row that doesn't exist, so they are zero.
+
+const ACCOUNTS: usize = 4;
+const BALANCE_BITS: usize = 20;
+
+const SC_ROW_WIDTH: usize = ACCOUNTS * (2 + BALANCE_BITS) + 1;
+
+struct SupplyConservation {}
+
+impl<F> BaseAir<F> for SupplyConservation {
+    fn width(&self) -> usize {
+        SC_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues> Air<AB> for SupplyConservation {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &LedgerRow<AB::Var> = (*local).borrow();
+        let next: &LedgerRow<AB::Var> = (*next).borrow();
+
+        let total_supply: AB::Expr = builder.public_values()[0].into();
+
+        let supply = local.balances.iter().map(|&b| b.into()).sum::<AB::Expr>();
+        builder.when_first_row().assert_eq(supply, total_supply);
+
+        for k in 0..ACCOUNTS {
+            assert_bit_decomposition(builder, local.balances[k], &local.balance_bits[k]);
+            builder.when_transition().assert_eq(next.balances[k], local.balances[k] + local.deltas[k]);
+            builder.when_last_row().assert_zero(local.deltas[k]);
+        }
+
+        // the deltas of the whole ledger cancel
+        let entry_net = local.deltas.iter().map(|&d| d.into()).sum::<AB::Expr>();
+        builder.when_first_row().assert_zero(local.net);
+        builder.when_transition().assert_eq(next.net, local.net + entry_net);
+        builder.when_last_row().assert_zero(local.net);
+    }
+}
+
+struct LedgerRow<F> {
+    /// before this row's entry
+    pub balances: [F; ACCOUNTS],
+    pub balance_bits: [[F; BALANCE_BITS]; ACCOUNTS],
+    /// the entry: what it adds to each balance
+    pub deltas: [F; ACCOUNTS],
+    /// the sum of every delta before this row's
+    pub net: F,
+}
+
+impl<F> Borrow<LedgerRow<F>> for [F] {
+    fn borrow(&self) -> &LedgerRow<F> {
+        debug_assert_eq!(self.len(), SC_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<LedgerRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+fn signed(x: i64) -> Val {
+    if x < 0 {
+        -Val::from_canonical_u64(x.unsigned_abs())
+    } else {
+        Val::from_canonical_u64(x as u64)
+    }
+}
+
+/// Random transfers between the accounts, one per row but the last, as per-account deltas.
+fn random_transfers(initial: [u32; ACCOUNTS], n: usize) -> Vec<[i64; ACCOUNTS]> {
+    let mut rng = thread_rng();
+    let mut balances = initial.map(|b| b as i64);
+    (0..n - 1)
+        .map(|_| {
+            let (from, to) = (rng.gen_range(0..ACCOUNTS), rng.gen_range(0..ACCOUNTS));
+            let amount = rng.gen_range(0..=balances[from]);
+            let mut deltas = [0; ACCOUNTS];
+            deltas[from] -= amount;
+            deltas[to] += amount;
+            for k in 0..ACCOUNTS {
+                balances[k] += deltas[k];
+            }
+            deltas
+        })
+        .collect()
+}
+
+/// The ledger applying `entries` to `initial`, and `[total_supply]`.
+fn generate_trace(initial: [u32; ACCOUNTS], entries: &[[i64; ACCOUNTS]]) -> (RowMajorMatrix<Val>, Vec<Val>) {
+    let n = entries.len() + 1;
+    assert!(n.is_power_of_two(), "trace height must be a power of two");
+
+    let mut trace = RowMajorMatrix::new(vec![Val::zero(); n * SC_ROW_WIDTH], SC_ROW_WIDTH);
+    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<LedgerRow<Val>>() };
+    assert!(prefix.is_empty(), "Alignment should match");
+    assert!(suffix.is_empty(), "Alignment should match");
+
+    let mut balances = initial.map(|b| b as i64);
+    let mut net = 0;
+    for (i, row) in rows.iter_mut().enumerate() {
+        let deltas = entries.get(i).copied().unwrap_or([0; ACCOUNTS]);
+        row.balances = balances.map(signed);
+        for k in 0..ACCOUNTS {
+            // an overdrawn balance has no decomposition; its bits are left zero
+            if (0..1 << BALANCE_BITS).contains(&balances[k]) {
+                row.balance_bits[k].copy_from_slice(&bit_decompose(row.balances[k], BALANCE_BITS));
+            }
+            balances[k] += deltas[k];
+        }
+        row.deltas = deltas.map(signed);
+        row.net = signed(net);
+        net += deltas.iter().sum::<i64>();
+    }
+
+    let total_supply = initial.iter().map(|&b| b as u64).sum();
+    (trace, vec![Val::from_canonical_u64(total_supply)])
+}
+
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+
+    let initial = [50_000, 20_000, 7_000, 0];
+    let entries = random_transfers(initial, 1 << 10);
+    let (trace, public_values) = generate_trace(initial, &entries);
+
+    let mut p_challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &SupplyConservation {}, &mut p_challenger, trace, &public_values);
+    let mut v_challenger = Challenger::new(perm);
+    verify(&config, &SupplyConservation {}, &mut v_challenger, &proof, &public_values).unwrap();
+
+    println!(
+        "proven: {} transfers between {} accounts conserve the total supply of {}",
+        entries.len(),
+        ACCOUNTS,
+        public_values[0].as_canonical_u32()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky3_cook::{assert_constraints_fail, assert_constraints_ok};
+
+    use super::*;
+
+    const INITIAL: [u32; ACCOUNTS] = [1000, 500, 250, 0];
+
+    #[test]
+    fn test_balanced_ledger() {
+        let (trace, public_values) = generate_trace(INITIAL, &random_transfers(INITIAL, 16));
+        assert_constraints_ok!(&SupplyConservation {}, &trace, &public_values);
+        assert_eq!(public_values[0], Val::from_canonical_u32(1750));
+    }
+
+    #[test]
+    fn test_split_payment_is_balanced() {
+        // one entry may move tokens between several accounts, as long as it nets to zero
+        let mut entries = vec![[0; ACCOUNTS]; 7];
+        entries[2] = [-300, 100, 150, 50];
+        let (trace, public_values) = generate_trace(INITIAL, &entries);
+        assert_constraints_ok!(&SupplyConservation {}, &trace, &public_values);
+    }
+
+    #[test]
+    fn test_illicit_mint_fails() {
+        // the trace is consistent row by row, but the ledger ends with 500 more tokens than it started with
+        let mut entries = random_transfers(INITIAL, 16);
+        entries[9][3] += 500;
+        let (trace, public_values) = generate_trace(INITIAL, &entries);
+        assert_constraints_fail!(&SupplyConservation {}, &trace, &public_values, 15);
+    }
+
+    #[test]
+    fn test_mint_against_a_negative_balance_fails() {
+        // +500 to account 0 and -500 from the empty account 3 nets to zero, but leaves account 3 at -500
+        let mut entries = vec![[0; ACCOUNTS]; 7];
+        entries[4] = [500, 0, 0, -500];
+        let (trace, public_values) = generate_trace(INITIAL, &entries);
+        assert_constraints_fail!(&SupplyConservation {}, &trace, &public_values, 5);
+    }
+
+    #[test]
+    fn test_supply_conservation_round_trip() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let (trace, public_values) = generate_trace(INITIAL, &random_transfers(INITIAL, 64));
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &SupplyConservation {}, &mut p_challenger, trace, &public_values);
+        let mut v_challenger = Challenger::new(perm);
+        verify(&config, &SupplyConservation {}, &mut v_challenger, &proof, &public_values).unwrap();
+    }
+}
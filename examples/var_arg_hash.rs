@@ -0,0 +1,228 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_field::{AbstractField, PrimeField32};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::{prove, verify};
+use plonky3_cook::config::{default_config, random_perm, Challenger, Val};
+use plonky3_cook::gadgets::sentinel::assert_sentinel;
+use plonky3_cook::poseidon2_air::{
+    eval_permutation, generate_permutation, PermutationCols, Poseidon2Constants, PERMUTATION_WIDTH, WIDTH,
+};
+use rand::{thread_rng, Rng};
+use tracing_forest::{util::LevelFilter, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+// `H(x_1, ..., x_k) == output` for a `k` that is a public value rather than fixed by the AIR, with a rate-1
+// Poseidon2 sponge: one permutation per row, as in `PaddingFreeSponge<Perm, 16, 1, 1>`.
+//
+// Absorb rows (`phase == 0`) overwrite `state[0]` with their `value`, the next input, and permute. Squeeze
+// rows (`phase == 1`) read their `value` off `state[0]` and permute; the first squeezed value is the hash.
+// Either way `value` is element 0 of the permutation's input, and every other input element is the
+// previous row's output (zero on the first row), so the two kinds of row differ only in where `state[0]`
+// comes from.
+//
+// `phase` is a sentinel (`gadgets::sentinel`): boolean and never going back from 1 to 0, so the absorb rows
+// are a prefix of the trace and the rest squeeze. `absorbed` counts absorb rows and ends at `k`, and the
+// last row must squeeze, so there is always an output. The public values are `[k, output]`.
+//
+// The sponge doesn't pad its input, so `H(x)` and `H(x, 0)`-style inputs of different lengths aren't
+// separated by the hash alone; here the public `k` pins the length.
+
+const VH_ROW_WIDTH: usize = 3 + PERMUTATION_WIDTH;
+
+struct VarArgHashAir {
+    constants: Poseidon2Constants,
+}
+
+impl<F> BaseAir<F> for VarArgHashAir {
+    fn width(&self) -> usize {
+        VH_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues<F = Val>> Air<AB> for VarArgHashAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &VarArgHashRow<AB::Var> = (*local).borrow();
+        let next: &VarArgHashRow<AB::Var> = (*next).borrow();
+
+        let pis = builder.public_values();
+        let (k, output): (AB::Expr, AB::Expr) = (pis[0].into(), pis[1].into());
+
+        let out = eval_permutation(builder, &self.constants, &local.perm);
+        let inputs = local.perm.inputs;
+
+        // absorb rows, then squeeze rows
+        assert_sentinel(builder, local.phase, next.phase);
+        builder.when_last_row().assert_one(local.phase);
+        builder.when_first_row().assert_eq(local.absorbed, AB::Expr::one() - local.phase);
+        builder
+            .when_transition()
+            .assert_eq(next.absorbed, local.absorbed + AB::Expr::one() - next.phase);
+        builder.when_last_row().assert_eq(local.absorbed, k);
+
+        // the sponge
+        builder.assert_eq(local.value, inputs[0]);
+        builder.when_first_row().when(local.phase).assert_zero(inputs[0]);
+        builder.when_transition().when(next.phase).assert_eq(next.perm.inputs[0], out[0].clone());
+        for c in 1..WIDTH {
+            builder.when_first_row().assert_zero(inputs[c]);
+            builder.when_transition().assert_eq(next.perm.inputs[c], out[c].clone());
+        }
+
+        // the first squeezed value is the hash
+        builder.when_first_row().when(local.phase).assert_eq(local.value, output.clone());
+        builder
+            .when_transition()
+            .when(next.phase - local.phase)
+            .assert_eq(next.value, output);
+    }
+}
+
+struct VarArgHashRow<F> {
+    /// 0 while absorbing, 1 while squeezing
+    pub phase: F,
+    /// the input absorbed, or the output squeezed
+    pub value: F,
+    /// absorb rows so far, this one included
+    pub absorbed: F,
+    pub perm: PermutationCols<F>,
+}
+
+impl<F> Borrow<VarArgHashRow<F>> for [F] {
+    fn borrow(&self) -> &VarArgHashRow<F> {
+        debug_assert_eq!(self.len(), VH_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<VarArgHashRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+/// Hashes `inputs` over a trace of `height` rows; returns the trace together with `[k, output]`.
+fn generate_trace(c: &Poseidon2Constants, inputs: &[Val], height: usize) -> (RowMajorMatrix<Val>, Vec<Val>) {
+    assert!(height.is_power_of_two(), "trace height must be a power of two");
+    assert!(inputs.len() < height, "no row left to squeeze");
+
+    let mut trace = RowMajorMatrix::new(vec![Val::zero(); height * VH_ROW_WIDTH], VH_ROW_WIDTH);
+
+    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<VarArgHashRow<Val>>() };
+    assert!(prefix.is_empty(), "Alignment should match");
+    assert!(suffix.is_empty(), "Alignment should match");
+
+    let mut state = [Val::zero(); WIDTH];
+    for (i, row) in rows.iter_mut().enumerate() {
+        match inputs.get(i) {
+            Some(&x) => {
+                state[0] = x;
+                row.absorbed = Val::from_canonical_usize(i + 1);
+            }
+            None => {
+                row.phase = Val::one();
+                row.absorbed = Val::from_canonical_usize(inputs.len());
+            }
+        }
+        row.value = state[0];
+        state = generate_permutation(c, state, &mut row.perm);
+    }
+
+    let output = rows[inputs.len()].value;
+    (trace, vec![Val::from_canonical_usize(inputs.len()), output])
+}
+
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+    let air = VarArgHashAir { constants: Poseidon2Constants::from_seed(0x7661) };
+
+    let mut rng = thread_rng();
+    let k = rng.gen_range(1..16);
+    let inputs = (0..k).map(|_| rng.gen()).collect::<Vec<Val>>();
+    let (trace, public_values) = generate_trace(&air.constants, &inputs, 16);
+
+    let mut p_challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &air, &mut p_challenger, trace, &public_values);
+    let mut v_challenger = Challenger::new(perm);
+    verify(&config, &air, &mut v_challenger, &proof, &public_values).unwrap();
+
+    println!("proven: a hash of {} inputs is {}", k, public_values[1].as_canonical_u32());
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_symmetric::{CryptographicHasher, PaddingFreeSponge};
+    use plonky3_cook::config::Perm;
+    use plonky3_cook::{assert_constraints_fail, assert_constraints_ok};
+
+    use super::*;
+
+    fn air() -> VarArgHashAir {
+        VarArgHashAir { constants: Poseidon2Constants::from_seed(1) }
+    }
+
+    fn inputs(k: usize) -> Vec<Val> {
+        (1..=k as u32).map(Val::from_canonical_u32).collect()
+    }
+
+    #[test]
+    fn test_matches_native_sponge() {
+        let air = air();
+        let sponge = PaddingFreeSponge::<Perm, WIDTH, 1, 1>::new(air.constants.perm());
+        for k in [1, 3, 7] {
+            let (trace, public_values) = generate_trace(&air.constants, &inputs(k), 8);
+            assert_constraints_ok!(&air, &trace, &public_values);
+            assert_eq!(public_values, vec![Val::from_canonical_usize(k), sponge.hash_iter(inputs(k))[0]]);
+        }
+    }
+
+    #[test]
+    fn test_wrong_arity_fails() {
+        let air = air();
+        let (trace, mut public_values) = generate_trace(&air.constants, &inputs(3), 8);
+        public_values[0] = Val::from_canonical_u32(4);
+        assert_constraints_fail!(&air, &trace, &public_values, 7);
+    }
+
+    #[test]
+    fn test_wrong_output_fails() {
+        let air = air();
+        let (trace, mut public_values) = generate_trace(&air.constants, &inputs(3), 8);
+        public_values[1] += Val::one();
+        // the transition into the first squeeze row
+        assert_constraints_fail!(&air, &trace, &public_values, 2);
+    }
+
+    #[test]
+    fn test_phase_cannot_go_back() {
+        let air = air();
+        let (mut trace, public_values) = generate_trace(&air.constants, &inputs(3), 8);
+        trace.row_mut(5)[0] = Val::zero();
+        assert_constraints_fail!(&air, &trace, &public_values, 4);
+    }
+
+    #[test]
+    fn test_var_arg_hash_round_trip() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let air = air();
+        let (trace, public_values) = generate_trace(&air.constants, &inputs(5), 8);
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &air, &mut p_challenger, trace, &public_values);
+        let mut v_challenger = Challenger::new(perm);
+        verify(&config, &air, &mut v_challenger, &proof, &public_values).unwrap();
+    }
+}
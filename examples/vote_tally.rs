@@ -0,0 +1,312 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_field::{AbstractField, PrimeField32};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_symmetric::Permutation;
+use p3_uni_stark::{prove, verify};
+use plonky3_cook::config::{default_config, random_perm, Challenger, Val};
+use plonky3_cook::poseidon2_air::{
+    eval_permutation, generate_permutation, PermutationCols, Poseidon2Constants, PERMUTATION_WIDTH, WIDTH,
+};
+use rand::{thread_rng, Rng};
+use tracing_forest::{util::LevelFilter, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+// A tally of masked yes/no votes: the sum of the published encrypted ballots decrypts to the claimed count of
+// yes votes, while the individual votes stay in the trace.
+//
+// A ballot encrypts a vote `v` as `Enc(v) = v + r * G` for a random mask `r` and a fixed `G`. The scheme is
+// additively homomorphic: `sum Enc(v_i) = sum v_i + (sum r_i) * G`, so the encrypted total opens to the
+// tally with the sum of the masks. Each row is one ballot; `accumulated_sum` adds up the encryptions and
+// `accumulated_mask` the masks, and on the last row
+//   accumulated_sum == encrypted_total   and   accumulated_sum - accumulated_mask * G == tally
+// Each vote `enc_vote - mask * G` must be 0 or 1, so no ballot counts twice or negatively. A ballot with
+// `reveal_bit == 1` is cast in the clear: its mask is zero and its encryption is the vote itself.
+//
+// The ballots are published, and the proof is bound to them through a Poseidon2 chain over the `enc_vote`
+// column, one permutation per row,
+//   digest' = Poseidon2([digest, enc_vote, 0, ...])[..8]
+// starting from zero, as `continuation.rs` folds transactions into its root. The public values are
+//   [encrypted_total, tally, ballots_digest..]
+// and the verifier computes the total and the digest from the published ballots itself, so the proof can't
+// tally some other set of ballots that happens to add up to the same total.
+//
+// This is a pedagogical model, not a voting scheme. In a field, `r * G` can be undone by anyone who knows
+// `Enc(v)` and `v` (and the prover picks the masks), so the ballots hide but don't bind: the proof shows
+// that some boolean votes and masks explain the ballots and the tally, not that they are the voters' own.
+// Binding needs a group where `r * G` can't be inverted, e.g. Pedersen commitments on an elliptic curve.
+// uni-stark proofs aren't zero-knowledge either, so the masks only hide the votes from whoever sees the
+// ballots, not from the proof.
+
+const MASK_BASE: u32 = 31;
+
+const DIGEST_LEN: usize = 8;
+
+const VT_ROW_WIDTH: usize = 5 + PERMUTATION_WIDTH;
+
+struct HomomorphicSumProof {
+    constants: Poseidon2Constants,
+}
+
+impl<F> BaseAir<F> for HomomorphicSumProof {
+    fn width(&self) -> usize {
+        VT_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues<F = Val>> Air<AB> for HomomorphicSumProof {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &VoteRow<AB::Var> = (*local).borrow();
+        let next: &VoteRow<AB::Var> = (*next).borrow();
+
+        let pis: Vec<AB::Expr> = builder.public_values().iter().map(|&v| v.into()).collect();
+        let (encrypted_total, tally, digest) = (pis[0].clone(), pis[1].clone(), &pis[2..2 + DIGEST_LEN]);
+        let g = AB::Expr::from_canonical_u32(MASK_BASE);
+
+        // a ballot holds a 0 or a 1, in the clear if revealed
+        let vote = local.enc_vote - local.mask * g.clone();
+        builder.assert_bool(vote);
+        builder.assert_bool(local.reveal_bit);
+        builder.when(local.reveal_bit).assert_zero(local.mask);
+
+        // the running sums
+        builder.when_first_row().assert_eq(local.accumulated_sum, local.enc_vote);
+        builder.when_first_row().assert_eq(local.accumulated_mask, local.mask);
+        builder
+            .when_transition()
+            .assert_eq(next.accumulated_sum, local.accumulated_sum + next.enc_vote);
+        builder
+            .when_transition()
+            .assert_eq(next.accumulated_mask, local.accumulated_mask + next.mask);
+
+        // the encrypted total opens to the tally
+        builder.when_last_row().assert_eq(local.accumulated_sum, encrypted_total);
+        builder
+            .when_last_row()
+            .assert_eq(local.accumulated_sum - local.accumulated_mask * g, tally);
+
+        // the chain over the ballots ends in the public digest
+        let out = eval_permutation(builder, &self.constants, &local.perm);
+        let inputs = local.perm.inputs;
+        builder.assert_eq(inputs[DIGEST_LEN], local.enc_vote);
+        for &input in &inputs[DIGEST_LEN + 1..] {
+            builder.assert_zero(input);
+        }
+        for i in 0..DIGEST_LEN {
+            builder.when_first_row().assert_zero(inputs[i]);
+            builder.when_transition().assert_eq(next.perm.inputs[i], out[i].clone());
+            builder.when_last_row().assert_eq(out[i].clone(), digest[i].clone());
+        }
+    }
+}
+
+struct VoteRow<F> {
+    /// `vote + mask * G`
+    pub enc_vote: F,
+    pub mask: F,
+    /// 1 if the ballot was cast in the clear
+    pub reveal_bit: F,
+    pub accumulated_sum: F,
+    pub accumulated_mask: F,
+    /// `[digest, enc_vote, 0, ..., 0]`
+    pub perm: PermutationCols<F>,
+}
+
+impl<F> Borrow<VoteRow<F>> for [F] {
+    fn borrow(&self) -> &VoteRow<F> {
+        debug_assert_eq!(self.len(), VT_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<VoteRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Ballot {
+    vote: bool,
+    revealed: bool,
+}
+
+fn random_ballots(n: usize) -> Vec<Ballot> {
+    let mut rng = thread_rng();
+    (0..n).map(|_| Ballot { vote: rng.gen(), revealed: rng.gen_bool(0.25) }).collect()
+}
+
+/// Encrypts `ballots` with fresh masks, as `(enc_vote, mask, revealed)`.
+fn encrypt(ballots: &[Ballot]) -> Vec<(Val, Val, bool)> {
+    let mut rng = thread_rng();
+    let g = Val::from_canonical_u32(MASK_BASE);
+    ballots
+        .iter()
+        .map(|ballot| {
+            let mask = if ballot.revealed { Val::zero() } else { rng.gen() };
+            (Val::from_bool(ballot.vote) + mask * g, mask, ballot.revealed)
+        })
+        .collect()
+}
+
+/// The digest of the published ballots, computed natively as the AIR chains it.
+fn ballots_digest(c: &Poseidon2Constants, enc_votes: &[Val]) -> [Val; DIGEST_LEN] {
+    let perm = c.perm();
+    enc_votes.iter().fold([Val::zero(); DIGEST_LEN], |digest, &enc_vote| {
+        let mut inputs = [Val::zero(); WIDTH];
+        inputs[..DIGEST_LEN].copy_from_slice(&digest);
+        inputs[DIGEST_LEN] = enc_vote;
+        perm.permute(inputs)[..DIGEST_LEN].try_into().unwrap()
+    })
+}
+
+/// What the verifier checks the proof against: the total and digest of the published ballots, and the tally.
+fn public_values(c: &Poseidon2Constants, enc_votes: &[Val], tally: Val) -> Vec<Val> {
+    let total = enc_votes.iter().copied().sum();
+    [vec![total, tally], ballots_digest(c, enc_votes).to_vec()].concat()
+}
+
+/// The trace tallying the encrypted ballots, and its public values.
+fn generate_trace(c: &Poseidon2Constants, ballots: &[(Val, Val, bool)]) -> (RowMajorMatrix<Val>, Vec<Val>) {
+    let n = ballots.len();
+    assert!(n.is_power_of_two(), "trace height must be a power of two");
+
+    let mut trace = RowMajorMatrix::new(vec![Val::zero(); n * VT_ROW_WIDTH], VT_ROW_WIDTH);
+    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<VoteRow<Val>>() };
+    assert!(prefix.is_empty(), "Alignment should match");
+    assert!(suffix.is_empty(), "Alignment should match");
+
+    let g = Val::from_canonical_u32(MASK_BASE);
+    let (mut sum, mut masks) = (Val::zero(), Val::zero());
+    let mut digest = [Val::zero(); DIGEST_LEN];
+    for (row, &(enc_vote, mask, revealed)) in rows.iter_mut().zip(ballots) {
+        row.enc_vote = enc_vote;
+        row.mask = mask;
+        row.reveal_bit = Val::from_bool(revealed);
+        sum += enc_vote;
+        masks += mask;
+        row.accumulated_sum = sum;
+        row.accumulated_mask = masks;
+
+        let mut inputs = [Val::zero(); WIDTH];
+        inputs[..DIGEST_LEN].copy_from_slice(&digest);
+        inputs[DIGEST_LEN] = enc_vote;
+        let out = generate_permutation(c, inputs, &mut row.perm);
+        digest.copy_from_slice(&out[..DIGEST_LEN]);
+    }
+
+    let enc_votes = ballots.iter().map(|&(enc_vote, _, _)| enc_vote).collect::<Vec<_>>();
+    (trace, public_values(c, &enc_votes, sum - masks * g))
+}
+
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+    let air = HomomorphicSumProof { constants: Poseidon2Constants::from_seed(0x766f7465) };
+
+    let ballots = encrypt(&random_ballots(1 << 10));
+    let (trace, prover_public_values) = generate_trace(&air.constants, &ballots);
+
+    let mut p_challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &air, &mut p_challenger, trace, &prover_public_values);
+
+    // the verifier has the published ballots and the claimed tally
+    let enc_votes = ballots.iter().map(|&(enc_vote, _, _)| enc_vote).collect::<Vec<_>>();
+    let tally = prover_public_values[1];
+    let public_values = public_values(&air.constants, &enc_votes, tally);
+    let mut v_challenger = Challenger::new(perm);
+    verify(&config, &air, &mut v_challenger, &proof, &public_values).unwrap();
+
+    println!("proven: {} of {} ballots are yes votes", tally.as_canonical_u32(), ballots.len());
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky3_cook::{assert_constraints_fail, assert_constraints_ok};
+
+    use super::*;
+
+    fn air() -> HomomorphicSumProof {
+        HomomorphicSumProof { constants: Poseidon2Constants::from_seed(0x766f7465) }
+    }
+
+    #[test]
+    fn test_tally() {
+        let air = air();
+        let ballots = random_ballots(16);
+        let (trace, public_values) = generate_trace(&air.constants, &encrypt(&ballots));
+        assert_constraints_ok!(&air, &trace, &public_values);
+
+        let tally = ballots.iter().filter(|b| b.vote).count();
+        assert_eq!(public_values[1], Val::from_canonical_usize(tally));
+    }
+
+    #[test]
+    fn test_wrong_tally_fails() {
+        let air = air();
+        let (trace, mut public_values) = generate_trace(&air.constants, &encrypt(&random_ballots(16)));
+        public_values[1] += Val::one();
+        assert_constraints_fail!(&air, &trace, &public_values, 15);
+    }
+
+    #[test]
+    fn test_other_ballots_fail() {
+        // the same total and tally over ballots other than the published ones
+        let air = air();
+        let ballots = encrypt(&random_ballots(16));
+        let (trace, honest_public_values) = generate_trace(&air.constants, &ballots);
+        let mut published = ballots.iter().map(|&(enc_vote, _, _)| enc_vote).collect::<Vec<_>>();
+        published.swap(0, 1);
+        if published[0] == published[1] {
+            published[0] += Val::one();
+            published[1] -= Val::one();
+        }
+        let other_public_values = public_values(&air.constants, &published, honest_public_values[1]);
+        assert_eq!(other_public_values[..2], honest_public_values[..2]);
+        assert_constraints_fail!(&air, &trace, &other_public_values, 15);
+    }
+
+    #[test]
+    fn test_double_vote_fails() {
+        // a ballot of 2, with the running sums, digest and tally consistent with it
+        let mut ballots = encrypt(&vec![Ballot { vote: true, revealed: false }; 8]);
+        ballots[3].0 += Val::one();
+        let air = air();
+        let (trace, public_values) = generate_trace(&air.constants, &ballots);
+        assert_constraints_fail!(&air, &trace, &public_values, 3);
+    }
+
+    #[test]
+    fn test_revealed_ballot_has_no_mask() {
+        // the same vote under a mask, with everything else consistent with it
+        let mut ballots = encrypt(&vec![Ballot { vote: true, revealed: true }; 8]);
+        ballots[5] = (ballots[5].0 + Val::from_canonical_u32(MASK_BASE), Val::one(), true);
+        let air = air();
+        let (trace, public_values) = generate_trace(&air.constants, &ballots);
+        assert_constraints_fail!(&air, &trace, &public_values, 5);
+    }
+
+    #[test]
+    fn test_vote_tally_round_trip() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let air = air();
+        let (trace, public_values) = generate_trace(&air.constants, &encrypt(&random_ballots(64)));
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &air, &mut p_challenger, trace, &public_values);
+        let mut v_challenger = Challenger::new(perm);
+        verify(&config, &air, &mut v_challenger, &proof, &public_values).unwrap();
+    }
+}
@@ -0,0 +1,93 @@
+use p3_uni_stark::{prove, verify};
+use plonky3_cook::config::{default_config, random_perm, Challenger, Val};
+use plonky3_cook::simple_state::{random_trace, SimpleState};
+#[cfg(not(target_arch = "wasm32"))]
+use tracing_forest::{util::LevelFilter, ForestLayer};
+#[cfg(not(target_arch = "wasm32"))]
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+// The SimpleState proof generated in the browser. Natively this runs like any other example; built for
+// `wasm32-unknown-unknown` it exports `prove_simple_state` to JavaScript, and `examples/wasm_prove/index.html`
+// calls it:
+//   cargo build -r --example wasm_prove --target wasm32-unknown-unknown
+//   wasm-bindgen --target web --out-dir examples/wasm_prove/pkg \
+//       target/wasm32-unknown-unknown/release/examples/wasm_prove.wasm
+//   python3 -m http.server -d examples/wasm_prove
+// `wasm-pack` only packages library crates, so the example goes through `wasm-bindgen` directly; that is the
+// step `wasm-pack` would run.
+//
+// What changes on wasm32:
+//   - entropy: `thread_rng`, behind `random_perm` and `random_trace`, seeds from `getrandom`, which has no
+//     source on wasm32-unknown-unknown until its `js` feature routes it to `crypto.getRandomValues`. The
+//     feature is switched on by the wasm32-only dev-dependency in Cargo.toml; no code changes.
+//   - threads: leave the `parallel` feature off. Without it `p3-maybe-rayon` runs its iterators sequentially,
+//     and the browser has no threads for rayon to spawn anyway.
+//   - logging: `tracing_forest` prints to stdout, which the browser drops, so `main` installs `console_log`
+//     instead. It forwards the `log` records below; Plonky3's tracing spans are not shown.
+//   - time: `std::time::Instant` panics on wasm32-unknown-unknown, so nothing here times itself; the page
+//     measures the call with `performance.now()`.
+// The `#[cfg(target_arch = "wasm32")]` items in this file are those switches; the library needs none.
+
+const LOG_N: usize = 10;
+
+/// Proves and verifies a random SimpleState trace of `2^log_n` rows, returning the serialized proof.
+fn prove_and_verify(log_n: usize) -> Vec<u8> {
+    let perm = random_perm();
+    let config = default_config(&perm);
+    let trace = random_trace::<Val>(log_n);
+
+    let mut p_challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &SimpleState {}, &mut p_challenger, trace, &vec![]);
+    let mut v_challenger = Challenger::new(perm);
+    verify(&config, &SimpleState {}, &mut v_challenger, &proof, &vec![]).unwrap();
+
+    bincode::serialize(&proof).unwrap()
+}
+
+/// The browser entry point: the serialized proof of `2^log_n` rows, as a `Uint8Array`.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn prove_simple_state(log_n: u32) -> Vec<u8> {
+    log::info!("proving {} rows of SimpleState", 1 << log_n);
+    let bytes = prove_and_verify(log_n as usize);
+    log::info!("proof verified, {} bytes", bytes.len());
+    bytes
+}
+
+/// Run by `wasm-bindgen` when the module is instantiated.
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    console_log::init_with_level(log::Level::Info).expect("the logger is installed once");
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let bytes = prove_and_verify(LOG_N);
+    println!("proven and verified: {} rows, {} proof bytes", 1 << LOG_N, bytes.len());
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_uni_stark::Proof;
+    use plonky3_cook::config::MyConfig;
+
+    use super::*;
+
+    #[test]
+    fn test_proof_bytes_decode() {
+        let bytes = prove_and_verify(4);
+        let proof: Proof<MyConfig> = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(bincode::serialize(&proof).unwrap(), bytes);
+    }
+}
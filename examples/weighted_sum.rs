@@ -0,0 +1,166 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_field::{AbstractField, Field};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::{prove, verify};
+use plonky3_cook::config::{default_config, random_perm, Challenger, Val};
+use rand::{distributions::{Distribution, Standard}, thread_rng, Rng};
+use tracing_forest::{util::LevelFilter, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+// A weighted running sum: `acc += coeff * value` on every row, with the total as the public value.
+//
+// `coeff` is meant to be a preprocessed (fixed) column: the verifier knows it, the prover must not choose it.
+// uni-stark at this revision has no preprocessed trace, so the column lives in the main trace and is pinned
+// by constraints instead: `coeff` is 1 on the first row and is multiplied by `COEFF_RATIO` on every step,
+// so the only satisfying column is `coeff_i = COEFF_RATIO^i`.
+
+const WS_ROW_WIDTH: usize = 3;
+
+const COEFF_RATIO: u32 = 3;
+
+struct WeightedSumAir {}
+
+impl<F> BaseAir<F> for WeightedSumAir {
+    fn width(&self) -> usize {
+        WS_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues> Air<AB> for WeightedSumAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &WeightedSumRow<AB::Var> = (*local).borrow();
+        let next: &WeightedSumRow<AB::Var> = (*next).borrow();
+
+        let total: AB::Expr = builder.public_values()[0].into();
+
+        // the fixed column
+        builder.when_first_row().assert_one(local.coeff);
+        builder
+            .when_transition()
+            .assert_eq(next.coeff, local.coeff * AB::Expr::from_canonical_u32(COEFF_RATIO));
+
+        builder.when_first_row().assert_eq(local.acc, local.coeff * local.value);
+        builder.when_transition().assert_eq(next.acc, local.acc + next.coeff * next.value);
+        builder.when_last_row().assert_eq(local.acc, total);
+    }
+}
+
+struct WeightedSumRow<F> {
+    pub value: F,
+    pub coeff: F,
+    pub acc: F,
+}
+
+impl<F> Borrow<WeightedSumRow<F>> for [F] {
+    fn borrow(&self) -> &WeightedSumRow<F> {
+        debug_assert_eq!(self.len(), WS_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<WeightedSumRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+/// The fixed coefficients for a trace of height `n`.
+fn fixed_coeffs<F: Field>(n: usize) -> Vec<F> {
+    let ratio = F::from_canonical_u32(COEFF_RATIO);
+    (0..n).scan(F::one(), |c, _| Some(std::mem::replace(c, *c * ratio))).collect()
+}
+
+/// Computes the weighted sum directly, without building a trace.
+fn weighted_sum<F: Field>(values: &[F]) -> F {
+    values.iter().zip(fixed_coeffs::<F>(values.len())).map(|(&v, c)| c * v).sum()
+}
+
+/// Returns the trace together with the total, which is the public value.
+fn generate_trace<F: Field>(values: &[F]) -> (RowMajorMatrix<F>, F) {
+    let n = values.len();
+    assert!(n.is_power_of_two(), "trace height must be a power of two");
+
+    let mut trace = RowMajorMatrix::new(vec![F::zero(); n * WS_ROW_WIDTH], WS_ROW_WIDTH);
+
+    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<WeightedSumRow<F>>() };
+    assert!(prefix.is_empty(), "Alignment should match");
+    assert!(suffix.is_empty(), "Alignment should match");
+    assert_eq!(rows.len(), n);
+
+    let mut acc = F::zero();
+    for ((row, &value), coeff) in rows.iter_mut().zip(values).zip(fixed_coeffs(n)) {
+        acc += coeff * value;
+        *row = WeightedSumRow { value, coeff, acc };
+    }
+
+    (trace, acc)
+}
+
+fn random_values<F>(n: usize) -> Vec<F> where Standard: Distribution<F> {
+    let mut rng = thread_rng();
+    (0..n).map(|_| rng.gen()).collect()
+}
+
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+
+    let values = random_values::<Val>(1024);
+    let (trace, total) = generate_trace(&values);
+
+    let mut p_challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &WeightedSumAir {}, &mut p_challenger, trace, &vec![total]);
+    let mut v_challenger = Challenger::new(perm);
+    verify(&config, &WeightedSumAir {}, &mut v_challenger, &proof, &vec![total]).unwrap();
+
+    println!("proven weighted sum: {}", total);
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky3_cook::{assert_constraints_fail, assert_constraints_ok};
+
+    use super::*;
+
+    #[test]
+    fn test_weighted_sum_constraints() {
+        let values = random_values::<Val>(1 << 8);
+        let (mut trace, total) = generate_trace(&values);
+        assert_constraints_ok!(&WeightedSumAir {}, &trace, &[total]);
+
+        // the prover can't pick its own coefficient
+        trace.row_mut(5)[1] += Val::one();
+        assert_constraints_fail!(&WeightedSumAir {}, &trace, &[total], 4);
+    }
+
+    #[test]
+    fn test_proven_total_matches_direct_computation() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+
+        let values = random_values::<Val>(256);
+        let (trace, total) = generate_trace(&values);
+        assert_eq!(total, weighted_sum(&values));
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &WeightedSumAir {}, &mut p_challenger, trace, &vec![total]);
+
+        let mut v_challenger = Challenger::new(perm.clone());
+        verify(&config, &WeightedSumAir {}, &mut v_challenger, &proof, &vec![weighted_sum(&values)]).unwrap();
+
+        let mut v_challenger = Challenger::new(perm);
+        assert!(verify(&config, &WeightedSumAir {}, &mut v_challenger, &proof, &vec![total + Val::one()]).is_err());
+    }
+}
@@ -0,0 +1,454 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_field::{AbstractExtensionField, AbstractField, Field};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::{get_max_constraint_degree, get_symbolic_constraints, prove, verify, SymbolicAirBuilder};
+use plonky3_cook::config::{default_config, random_perm, Challenge, Challenger, MyConfig, Perm, Val};
+use plonky3_cook::error::CookError;
+use plonky3_cook::gadgets::xor::{assert_xor, xor_witness};
+use plonky3_cook::lookups::{
+    assert_ext_eq, ext, ext_add, ext_scale, ext_sub, ext_times, ext_values, lift, prove_two_round, verify_two_round,
+    TwoRoundAir, TwoRoundProof, EXT,
+};
+use rand::{thread_rng, Rng};
+use tracing_forest::{util::LevelFilter, ForestLayer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry};
+
+// `out == a XOR b` for byte operands, one XOR per row, two ways.
+//
+// Bit decomposition (`XorBitsAir`, with `gadgets::xor`): both operands are decomposed into 8 boolean
+// columns each, and `out` is the recomposition of `a_i + b_i - 2 * a_i * b_i`.
+//
+// Lookup (`XorLookupAir`): each operand and the output are split into two nibbles, and each nibble triple
+// `(a, b, out)` is looked up in the 256-entry table of all 4-bit XORs. The lookup is a LogUp sum over the
+// fingerprint `a + alpha * b + alpha^2 * out`:
+//   sum over rows of  1 / (z - fp(lo nibbles)) + 1 / (z - fp(hi nibbles))
+//   == sum over table entries t of  m_t / (z - fp(t))
+// The trace only holds the query side, accumulated in `acc`. uni-stark has no preprocessed columns, so a
+// table in the trace would need constraints of its own to pin its entries, bits included; instead the
+// verifier computes the table side itself from the multiplicities `m_t`, which come with the proof.
+//
+// The query columns are the first round of a `lookups::TwoRoundAir`: they are committed, `alpha` and `z`
+// are drawn from `Challenge` after that commitment and the multiplicities, and the commitment is opened at
+// the proof's out-of-domain point and compared with the proven trace. So the nibbles are fixed before the
+// challenges are known, and they need no range check: a nibble of 16 or more isn't in the table. The
+// inverses, the accumulator and the table sum are extension elements, `EXT` columns or values each.
+//
+// `main` prints the two AIRs' width, constraint count and maximum constraint degree, counted from the
+// symbolic constraints uni-stark derives from each AIR.
+
+const BITS: usize = 8;
+const NIBBLE_BITS: usize = 4;
+const TABLE_SIZE: usize = 1 << (2 * NIBBLE_BITS);
+
+const XB_ROW_WIDTH: usize = 3 + 2 * BITS;
+
+struct XorBitsAir {}
+
+impl<F> BaseAir<F> for XorBitsAir {
+    fn width(&self) -> usize {
+        XB_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilder> Air<AB> for XorBitsAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = main.row_slice(0);
+        let local: &XorBitsRow<AB::Var> = (*local).borrow();
+
+        assert_xor(builder, local.a, local.b, local.out, &local.a_bits, &local.b_bits);
+    }
+}
+
+struct XorBitsRow<F> {
+    pub a: F,
+    pub b: F,
+    pub out: F,
+    pub a_bits: [F; BITS],
+    pub b_bits: [F; BITS],
+}
+
+impl<F> Borrow<XorBitsRow<F>> for [F] {
+    fn borrow(&self) -> &XorBitsRow<F> {
+        debug_assert_eq!(self.len(), XB_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<XorBitsRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+fn bits_trace(pairs: &[(u32, u32)]) -> RowMajorMatrix<Val> {
+    let n = pairs.len();
+    assert!(n.is_power_of_two(), "trace height must be a power of two");
+
+    let mut trace = RowMajorMatrix::new(vec![Val::zero(); n * XB_ROW_WIDTH], XB_ROW_WIDTH);
+    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<XorBitsRow<Val>>() };
+    assert!(prefix.is_empty(), "Alignment should match");
+    assert!(suffix.is_empty(), "Alignment should match");
+
+    for (row, &(a, b)) in rows.iter_mut().zip(pairs) {
+        row.a = Val::from_canonical_u32(a);
+        row.b = Val::from_canonical_u32(b);
+        let (a_bits, b_bits, out) = xor_witness(row.a, row.b, BITS);
+        row.a_bits.copy_from_slice(&a_bits);
+        row.b_bits.copy_from_slice(&b_bits);
+        row.out = out;
+    }
+    trace
+}
+
+// the query columns, committed before the challenges are drawn
+const QUERY_WIDTH: usize = 9;
+const XL_ROW_WIDTH: usize = QUERY_WIDTH + 3 * EXT;
+/// `[alpha, z, table_sum]`, `EXT` values each
+const XL_NUM_PUBLIC_VALUES: usize = 3 * EXT;
+
+struct XorLookupAir {}
+
+impl<F> BaseAir<F> for XorLookupAir {
+    fn width(&self) -> usize {
+        XL_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues<F = Val>> Air<AB> for XorLookupAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &XorLookupRow<AB::Var> = (*local).borrow();
+        let next: &XorLookupRow<AB::Var> = (*next).borrow();
+
+        let pis = builder.public_values();
+        let (alpha, z, table_sum): ([AB::Expr; EXT], [AB::Expr; EXT], [AB::Expr; EXT]) =
+            (ext(&pis[..EXT]), ext(&pis[EXT..2 * EXT]), ext(&pis[2 * EXT..]));
+
+        let nibble = AB::Expr::from_canonical_u32(1 << NIBBLE_BITS);
+        builder.assert_eq(local.a, local.a_nibbles[0] + nibble.clone() * local.a_nibbles[1]);
+        builder.assert_eq(local.b, local.b_nibbles[0] + nibble.clone() * local.b_nibbles[1]);
+        builder.assert_eq(local.out, local.out_nibbles[0] + nibble * local.out_nibbles[1]);
+
+        // the queries
+        let alpha_sq = ext_times(&alpha, &alpha);
+        for i in 0..2 {
+            let terms = ext_add(
+                ext_scale(alpha.clone(), local.b_nibbles[i].into()),
+                ext_scale(alpha_sq.clone(), local.out_nibbles[i].into()),
+            );
+            let fp = ext_add(lift(local.a_nibbles[i].into()), terms);
+            let inv = ext(&local.query_inv[i]);
+            assert_ext_eq(builder, ext_times(&inv, &ext_sub(z.clone(), fp)), lift(AB::Expr::one()));
+        }
+
+        let delta =
+            |row: &XorLookupRow<AB::Var>| ext_add(ext::<AB::Expr, _>(&row.query_inv[0]), ext(&row.query_inv[1]));
+        assert_ext_eq(&mut builder.when_first_row(), ext(&local.acc), delta(local));
+        assert_ext_eq(&mut builder.when_transition(), ext(&next.acc), ext_add(ext(&local.acc), delta(next)));
+        assert_ext_eq(&mut builder.when_last_row(), ext(&local.acc), table_sum);
+    }
+}
+
+struct XorLookupRow<F> {
+    pub a: F,
+    pub b: F,
+    pub out: F,
+    /// low nibble first
+    pub a_nibbles: [F; 2],
+    pub b_nibbles: [F; 2],
+    pub out_nibbles: [F; 2],
+    // filled after the challenges are drawn
+    pub query_inv: [[F; EXT]; 2],
+    pub acc: [F; EXT],
+}
+
+impl<F> Borrow<XorLookupRow<F>> for [F] {
+    fn borrow(&self) -> &XorLookupRow<F> {
+        debug_assert_eq!(self.len(), XL_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<XorLookupRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+fn lookup_rows_mut<F>(trace: &mut RowMajorMatrix<F>) -> &mut [XorLookupRow<F>] {
+    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<XorLookupRow<F>>() };
+    assert!(prefix.is_empty(), "Alignment should match");
+    assert!(suffix.is_empty(), "Alignment should match");
+    rows
+}
+
+fn table_index(a: u32, b: u32) -> usize {
+    ((a << NIBBLE_BITS) | b) as usize
+}
+
+fn nibbles<F: AbstractField>(x: u32) -> [F; 2] {
+    [F::from_canonical_u32(x & 0xf), F::from_canonical_u32(x >> NIBBLE_BITS)]
+}
+
+/// The trace with the query columns filled in and the lookup columns left zero, and the multiplicity of
+/// each table entry, indexed by `16 * a + b`.
+fn lookup_trace(pairs: &[(u32, u32)]) -> (RowMajorMatrix<Val>, Vec<u32>) {
+    let n = pairs.len();
+    assert!(n.is_power_of_two(), "trace height must be a power of two");
+
+    let mut trace = RowMajorMatrix::new(vec![Val::zero(); n * XL_ROW_WIDTH], XL_ROW_WIDTH);
+    let mut multiplicities = vec![0; TABLE_SIZE];
+    for (row, &(a, b)) in lookup_rows_mut(&mut trace).iter_mut().zip(pairs) {
+        let out = a ^ b;
+        row.a = Val::from_canonical_u32(a);
+        row.b = Val::from_canonical_u32(b);
+        row.out = Val::from_canonical_u32(out);
+        row.a_nibbles = nibbles(a);
+        row.b_nibbles = nibbles(b);
+        row.out_nibbles = nibbles(out);
+        multiplicities[table_index(a & 0xf, b & 0xf)] += 1;
+        multiplicities[table_index(a >> NIBBLE_BITS, b >> NIBBLE_BITS)] += 1;
+    }
+    (trace, multiplicities)
+}
+
+fn fingerprint(alpha: Challenge, a: Val, b: Val, out: Val) -> Challenge {
+    alpha * b + alpha.square() * out + a
+}
+
+/// Fills the lookup columns for the challenges `alpha` and `z`.
+fn fill_lookup(trace: &mut RowMajorMatrix<Val>, alpha: Challenge, z: Challenge) {
+    let mut acc = Challenge::zero();
+    for row in lookup_rows_mut(trace) {
+        for i in 0..2 {
+            let fp = fingerprint(alpha, row.a_nibbles[i], row.b_nibbles[i], row.out_nibbles[i]);
+            let inv = (z - fp).inverse();
+            row.query_inv[i].copy_from_slice(inv.as_base_slice());
+            acc += inv;
+        }
+        row.acc.copy_from_slice(acc.as_base_slice());
+    }
+}
+
+/// The table side of the LogUp sum, as the verifier computes it.
+fn table_sum(multiplicities: &[u32], alpha: Challenge, z: Challenge) -> Challenge {
+    (0..TABLE_SIZE as u32)
+        .map(|i| {
+            let (a, b) = (i >> NIBBLE_BITS, i & 0xf);
+            let [a_f, b_f, out_f] = [a, b, a ^ b].map(Val::from_canonical_u32);
+            let fp = fingerprint(alpha, a_f, b_f, out_f);
+            (z - fp).inverse() * Val::from_canonical_u32(multiplicities[i as usize])
+        })
+        .sum()
+}
+
+fn multiplicity_values(multiplicities: &[u32]) -> Vec<Val> {
+    multiplicities.iter().map(|&m| Val::from_canonical_u32(m)).collect()
+}
+
+/// `XorLookupAir` with the multiplicities the table sum is computed from.
+struct XorLookup {
+    multiplicities: Vec<u32>,
+}
+
+impl<F> BaseAir<F> for XorLookup {
+    fn width(&self) -> usize {
+        XL_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues<F = Val>> Air<AB> for XorLookup {
+    fn eval(&self, builder: &mut AB) {
+        XorLookupAir {}.eval(builder)
+    }
+}
+
+impl TwoRoundAir for XorLookup {
+    fn committed_width(&self) -> usize {
+        QUERY_WIDTH
+    }
+
+    fn num_challenges(&self) -> usize {
+        2
+    }
+
+    fn num_public_values(&self) -> usize {
+        EXT
+    }
+
+    fn complete_trace(&self, trace: &mut RowMajorMatrix<Val>, challenges: &[Challenge]) -> Vec<Val> {
+        let (alpha, z) = (challenges[0], challenges[1]);
+        fill_lookup(trace, alpha, z);
+        ext_values(&[table_sum(&self.multiplicities, alpha, z)])
+    }
+}
+
+struct XorLookupProof {
+    multiplicities: Vec<u32>,
+    proof: TwoRoundProof,
+}
+
+fn prove_lookup(config: &MyConfig, perm: &Perm, pairs: &[(u32, u32)]) -> XorLookupProof {
+    let (trace, multiplicities) = lookup_trace(pairs);
+    let air = XorLookup { multiplicities };
+    let proof = prove_two_round(config, perm, &air, trace, &multiplicity_values(&air.multiplicities));
+    XorLookupProof { multiplicities: air.multiplicities, proof }
+}
+
+fn verify_lookup(config: &MyConfig, perm: &Perm, proof: &XorLookupProof) -> Result<(), CookError> {
+    if proof.multiplicities.len() != TABLE_SIZE {
+        let reason = format!("expected {} multiplicities, got {}", TABLE_SIZE, proof.multiplicities.len());
+        return Err(CookError::PublicValues(reason));
+    }
+    let air = XorLookup { multiplicities: proof.multiplicities.clone() };
+    let challenges = verify_two_round(config, perm, &air, &multiplicity_values(&proof.multiplicities), &proof.proof)?;
+    let table_sum = ext_values(&[table_sum(&proof.multiplicities, challenges[0], challenges[1])]);
+    if proof.proof.public_values[2 * EXT..] != table_sum {
+        return Err(CookError::PublicValues("the table sum is not the multiplicities' one".to_string()));
+    }
+    Ok(())
+}
+
+/// `(width, constraints, max degree)` of `air`.
+fn stats<A: BaseAir<Val> + Air<SymbolicAirBuilder<Val>>>(air: &A, num_public_values: usize) -> (usize, usize, usize) {
+    (
+        air.width(),
+        get_symbolic_constraints::<Val, A>(air, 0, num_public_values).len(),
+        get_max_constraint_degree::<Val, A>(air, 0, num_public_values),
+    )
+}
+
+fn random_pairs(n: usize) -> Vec<(u32, u32)> {
+    let mut rng = thread_rng();
+    (0..n).map(|_| (rng.gen_range(0..1 << BITS), rng.gen_range(0..1 << BITS))).collect()
+}
+
+fn main() {
+    let env_filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+
+    Registry::default()
+        .with(env_filter)
+        .with(ForestLayer::default())
+        .init();
+
+    let perm = random_perm();
+    let config = default_config(&perm);
+    let pairs = random_pairs(1 << 10);
+
+    let mut p_challenger = Challenger::new(perm.clone());
+    let proof = prove(&config, &XorBitsAir {}, &mut p_challenger, bits_trace(&pairs), &vec![]);
+    let mut v_challenger = Challenger::new(perm.clone());
+    verify(&config, &XorBitsAir {}, &mut v_challenger, &proof, &vec![]).unwrap();
+
+    let proof = prove_lookup(&config, &perm, &pairs);
+    verify_lookup(&config, &perm, &proof).unwrap();
+
+    println!("proven: {} byte XORs, each way", pairs.len());
+    println!("{:<20} {:>6} {:>12} {:>11}", "", "width", "constraints", "max degree");
+    let rows = [
+        ("bit decomposition", stats(&XorBitsAir {}, 0)),
+        ("lookup", stats(&XorLookupAir {}, XL_NUM_PUBLIC_VALUES)),
+    ];
+    for (name, (width, constraints, degree)) in rows {
+        println!("{:<20} {:>6} {:>12} {:>11}", name, width, constraints, degree);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky3_cook::{assert_constraints_fail, assert_constraints_ok};
+
+    use super::*;
+
+    const PAIRS: [(u32, u32); 8] =
+        [(0, 0), (0xff, 0), (0xff, 0xff), (0xaa, 0x55), (0x0f, 0xf0), (1, 3), (200, 100), (0x80, 0x7f)];
+
+    fn challenges() -> (Challenge, Challenge) {
+        let challenge = |seed: usize| Challenge::from_base_fn(|i| Val::from_canonical_usize(seed + i));
+        (challenge(123_456), challenge(987_654_321))
+    }
+
+    fn lookup_trace_with_challenges(pairs: &[(u32, u32)]) -> (RowMajorMatrix<Val>, Vec<Val>) {
+        let (alpha, z) = challenges();
+        let (mut trace, multiplicities) = lookup_trace(pairs);
+        fill_lookup(&mut trace, alpha, z);
+        (trace, ext_values(&[alpha, z, table_sum(&multiplicities, alpha, z)]))
+    }
+
+    #[test]
+    fn test_xor_bits() {
+        let trace = bits_trace(&PAIRS);
+        assert_constraints_ok!(&XorBitsAir {}, &trace, &vec![]);
+        for (i, &(a, b)) in PAIRS.iter().enumerate() {
+            assert_eq!(trace.row_slice(i)[2], Val::from_canonical_u32(a ^ b));
+        }
+    }
+
+    #[test]
+    fn test_wrong_xor_fails() {
+        let mut trace = bits_trace(&PAIRS);
+        // OR instead of XOR
+        trace.row_mut(5)[2] = Val::from_canonical_u32(1 | 3);
+        assert_constraints_fail!(&XorBitsAir {}, &trace, &vec![], 5);
+
+        let (mut trace, pis) = lookup_trace_with_challenges(&PAIRS);
+        trace.row_mut(1)[2] = Val::zero();
+        assert_constraints_fail!(&XorLookupAir {}, &trace, &pis, 1);
+    }
+
+    #[test]
+    fn test_xor_lookup() {
+        let (trace, pis) = lookup_trace_with_challenges(&PAIRS);
+        assert_constraints_ok!(&XorLookupAir {}, &trace, &pis);
+
+        let (trace, pis) = lookup_trace_with_challenges(&random_pairs(64));
+        assert_constraints_ok!(&XorLookupAir {}, &trace, &pis);
+    }
+
+    #[test]
+    fn test_nibbles_outside_the_table_fail() {
+        // 0x12 split as nibbles (0x12, 0) recomposes fine, but (0x12, b, out) is no table entry
+        let (alpha, z) = challenges();
+        let (mut trace, multiplicities) = lookup_trace(&PAIRS);
+        let row = &mut lookup_rows_mut(&mut trace)[0];
+        row.a = Val::from_canonical_u32(0x12);
+        row.a_nibbles = [Val::from_canonical_u32(0x12), Val::zero()];
+        fill_lookup(&mut trace, alpha, z);
+        let pis = ext_values(&[alpha, z, table_sum(&multiplicities, alpha, z)]);
+        assert_constraints_fail!(&XorLookupAir {}, &trace, &pis, PAIRS.len() - 1);
+    }
+
+    #[test]
+    fn test_constraint_counts() {
+        // 16 booleans, 2 decompositions and the output
+        assert_eq!(stats(&XorBitsAir {}, 0), (XB_ROW_WIDTH, 19, 2));
+        // 3 recompositions, then `EXT` each for 2 queries and the accumulator's first, transition and last row
+        assert_eq!(stats(&XorLookupAir {}, XL_NUM_PUBLIC_VALUES), (XL_ROW_WIDTH, 3 + 5 * EXT, 2));
+    }
+
+    #[test]
+    fn test_xor_round_trip() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let pairs = random_pairs(64);
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &XorBitsAir {}, &mut p_challenger, bits_trace(&pairs), &vec![]);
+        let mut v_challenger = Challenger::new(perm.clone());
+        verify(&config, &XorBitsAir {}, &mut v_challenger, &proof, &vec![]).unwrap();
+
+        let mut proof = prove_lookup(&config, &perm, &pairs);
+        verify_lookup(&config, &perm, &proof).unwrap();
+
+        proof.multiplicities[0] += 1;
+        assert!(verify_lookup(&config, &perm, &proof).is_err());
+        proof.multiplicities[0] -= 1;
+
+        // queries other than the committed ones show up at `zeta`
+        proof.proof.committed_opening[3] += Challenge::one();
+        assert!(matches!(verify_lookup(&config, &perm, &proof), Err(CookError::PublicValues(_))));
+    }
+}
@@ -0,0 +1,156 @@
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+
+use p3_field::{AbstractField, PrimeField32};
+use p3_uni_stark::{prove, verify, Proof};
+use plonky3_cook::config::{
+    make_config, perm_from_seed, Challenger, MyConfig, Val, DEFAULT_LOG_BLOWUP, DEFAULT_NUM_QUERIES, DEFAULT_POW_BITS,
+};
+use plonky3_cook::error::CookError as CoreError;
+use plonky3_cook::simple_state::SimpleStateChecked;
+use plonky3_cook::transaction::{trace_from_transactions, Transaction};
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict};
+
+// Python bindings for proving and verifying `SimpleStateChecked` ledgers. Only the library's public API is
+// used here, so the core crate stays free of pyo3. Proofs are bincode-encoded `Proof<MyConfig>`; prover and
+// verifier must be given the same `params`. Proving and verifying run under `catch_unwind`, so a panic in
+// either surfaces as a `CookError` rather than pyo3's `PanicException`.
+
+create_exception!(cook, CookError, PyException);
+create_exception!(cook, LedgerError, CookError);
+create_exception!(cook, ProofDecodeError, CookError);
+
+/// Same as cook-ffi's `COOK_PERM_SEED`, so proofs made here also verify through the C API.
+const DEFAULT_SEED: u64 = 0x636f6f6b;
+
+struct Params {
+    log_blowup: usize,
+    num_queries: usize,
+    pow_bits: usize,
+    seed: u64,
+}
+
+impl Params {
+    fn from_dict(dict: Option<&Bound<'_, PyDict>>) -> PyResult<Self> {
+        let mut params = Params {
+            log_blowup: DEFAULT_LOG_BLOWUP,
+            num_queries: DEFAULT_NUM_QUERIES,
+            pow_bits: DEFAULT_POW_BITS,
+            seed: DEFAULT_SEED,
+        };
+        let Some(dict) = dict else { return Ok(params) };
+
+        for (key, value) in dict.iter() {
+            match key.extract::<String>()?.as_str() {
+                "log_blowup" => params.log_blowup = value.extract()?,
+                "num_queries" => params.num_queries = value.extract()?,
+                "pow_bits" => params.pow_bits = value.extract()?,
+                "seed" => params.seed = value.extract()?,
+                other => {
+                    return Err(PyValueError::new_err(format!(
+                        "unknown param {:?}, expected log_blowup, num_queries, pow_bits or seed",
+                        other
+                    )))
+                }
+            }
+        }
+        Ok(params)
+    }
+
+    fn setup(&self) -> (MyConfig, Challenger) {
+        let perm = perm_from_seed(self.seed);
+        let config = make_config(&perm, self.log_blowup, self.num_queries, self.pow_bits);
+        (config, Challenger::new(perm))
+    }
+}
+
+fn to_py_err(e: CoreError) -> PyErr {
+    match e {
+        CoreError::Ledger(e) => LedgerError::new_err(e.to_string()),
+        e => CookError::new_err(e.to_string()),
+    }
+}
+
+fn panic_to_py_err(what: &str, payload: Box<dyn Any + Send>) -> PyErr {
+    let message = payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string());
+    CookError::new_err(format!("the {} panicked: {}", what, message))
+}
+
+/// Proves a ledger of `(input, output)` transactions starting at `initial_balance`; returns the proof bytes.
+/// The public values to verify against are `[initial_balance, final_balance]`.
+#[pyfunction]
+#[pyo3(signature = (transactions, initial_balance, params=None))]
+fn prove_simple_state<'py>(
+    py: Python<'py>,
+    transactions: Vec<(u32, u32)>,
+    initial_balance: u32,
+    params: Option<&Bound<'py, PyDict>>,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let params = Params::from_dict(params)?;
+    let transactions = transactions.into_iter().map(|(i, o)| Transaction::new(i, o)).collect::<Vec<_>>();
+
+    let proof = py
+        .allow_threads(|| {
+            panic::catch_unwind(AssertUnwindSafe(|| -> Result<Vec<u8>, CoreError> {
+                let (trace, public_values) = trace_from_transactions::<Val>(initial_balance, &transactions)?;
+                let (config, mut challenger) = params.setup();
+                let proof = prove(&config, &SimpleStateChecked {}, &mut challenger, trace, &public_values);
+                Ok(bincode::serialize(&proof).expect("proofs always serialize"))
+            }))
+        })
+        .map_err(|payload| panic_to_py_err("prover", payload))?
+        .map_err(to_py_err)?;
+
+    Ok(PyBytes::new_bound(py, &proof))
+}
+
+/// Verifies proof bytes against `[initial_balance, final_balance]`. Returns `False` for a proof that does
+/// not verify, and raises for input that isn't a proof or public values at all, or if the verifier panics.
+#[pyfunction]
+#[pyo3(signature = (proof, publics, params=None))]
+fn verify_simple_state(
+    py: Python<'_>,
+    proof: &[u8],
+    publics: Vec<u64>,
+    params: Option<&Bound<'_, PyDict>>,
+) -> PyResult<bool> {
+    let params = Params::from_dict(params)?;
+
+    let proof: Proof<MyConfig> =
+        bincode::deserialize(proof).map_err(|e| ProofDecodeError::new_err(format!("not a proof: {}", e)))?;
+    if publics.len() != 2 {
+        return Err(to_py_err(CoreError::PublicValues(format!(
+            "expected [initial, final], got {} values",
+            publics.len()
+        ))));
+    }
+    if let Some(v) = publics.iter().find(|&&v| v >= Val::ORDER_U32 as u64) {
+        return Err(to_py_err(CoreError::PublicValues(format!("{} is not a field element", v))));
+    }
+    let public_values = publics.iter().map(|&v| Val::from_canonical_u64(v)).collect::<Vec<_>>();
+
+    py.allow_threads(|| {
+        panic::catch_unwind(AssertUnwindSafe(|| {
+            let (config, mut challenger) = params.setup();
+            verify(&config, &SimpleStateChecked {}, &mut challenger, &proof, &public_values).is_ok()
+        }))
+    })
+    .map_err(|payload| panic_to_py_err("verifier", payload))
+}
+
+#[pymodule]
+fn cook(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(prove_simple_state, m)?)?;
+    m.add_function(wrap_pyfunction!(verify_simple_state, m)?)?;
+    m.add("CookError", m.py().get_type_bound::<CookError>())?;
+    m.add("LedgerError", m.py().get_type_bound::<LedgerError>())?;
+    m.add("ProofDecodeError", m.py().get_type_bound::<ProofDecodeError>())?;
+    Ok(())
+}
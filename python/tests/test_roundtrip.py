@@ -0,0 +1,58 @@
+import pytest
+
+import cook
+
+PARAMS = {"num_queries": 20, "seed": 7}
+
+
+def ledger():
+    transactions = [(100, 30), (0, 50), (25, 0), (7, 7), (1, 2)]
+    initial = 1000
+    final = initial + sum(i - o for i, o in transactions)
+    return transactions, initial, final
+
+
+def test_round_trip():
+    transactions, initial, final = ledger()
+    proof = cook.prove_simple_state(transactions, initial, PARAMS)
+
+    assert isinstance(proof, bytes)
+    assert cook.verify_simple_state(proof, [initial, final], PARAMS)
+
+
+def test_wrong_final_balance_is_rejected():
+    transactions, initial, final = ledger()
+    proof = cook.prove_simple_state(transactions, initial, PARAMS)
+
+    assert not cook.verify_simple_state(proof, [initial, final + 1], PARAMS)
+
+
+def test_tampered_proof_is_rejected():
+    transactions, initial, final = ledger()
+    proof = bytearray(cook.prove_simple_state(transactions, initial, PARAMS))
+    # the low bit of the first trace commitment limb: still a proof, but not of this trace
+    proof[0] ^= 1
+
+    assert cook.verify_simple_state(bytes(proof), [initial, final], PARAMS) is False
+
+
+def test_params_must_match():
+    transactions, initial, final = ledger()
+    proof = cook.prove_simple_state(transactions, initial, PARAMS)
+
+    assert not cook.verify_simple_state(proof, [initial, final], {**PARAMS, "seed": 8})
+
+
+def test_overdraft_raises_ledger_error():
+    with pytest.raises(cook.LedgerError, match="overdraws"):
+        cook.prove_simple_state([(0, 2000)], 1000, PARAMS)
+
+
+def test_garbage_is_not_a_proof():
+    with pytest.raises(cook.ProofDecodeError):
+        cook.verify_simple_state(b"not a proof", [1, 2], PARAMS)
+
+
+def test_unknown_param():
+    with pytest.raises(ValueError, match="unknown param"):
+        cook.prove_simple_state([(1, 0)], 1, {"blowup": 2})
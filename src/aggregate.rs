@@ -0,0 +1,214 @@
+use std::ops::Range;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+#[cfg(not(feature = "verifier-only"))]
+use p3_matrix::dense::RowMajorMatrix;
+#[cfg(not(feature = "verifier-only"))]
+use p3_matrix::Matrix;
+#[cfg(not(feature = "verifier-only"))]
+use p3_uni_stark::prove;
+use p3_uni_stark::{verify, Proof};
+
+use crate::config::{Challenger, MyConfig, Perm, Val};
+use crate::error::CookError;
+use crate::lookups::ProvableAir;
+use crate::optimization::column_reorder::PermutedColumns;
+
+// Many instances of one AIR in a single proof. Finished proofs can't be merged after the fact: a random
+// combination of their transcripts says nothing about whether each instance satisfied the AIR, and checking
+// that takes either every proof or a recursive verifier. What a STARK can do cheaply is prove the instances
+// together, laid side by side in one trace:
+//   row r = [instance_0 row r | instance_1 row r | ... | instance_(k-1) row r]
+// with the public values concatenated in the same order. `Replicated` runs the inner AIR once per instance,
+// through a builder showing it only that instance's columns and public values.
+//
+// The verifier then checks one FRI proof instead of `k`: the Merkle paths and folding rounds are shared, and
+// only the opened rows grow, by `k * width` values per query. All instances need the same height.
+
+/// `k` copies of `inner` side by side, each with `num_public_values` of its own.
+pub struct Replicated<'a, A> {
+    inner: &'a A,
+    num_public_values: usize,
+    /// the trace columns of each copy
+    positions: Vec<Vec<usize>>,
+}
+
+impl<'a, A: BaseAir<Val>> Replicated<'a, A> {
+    pub fn new(inner: &'a A, copies: usize, num_public_values: usize) -> Self {
+        let width = inner.width();
+        let positions = (0..copies).map(|i| (i * width..(i + 1) * width).collect()).collect();
+        Replicated { inner, num_public_values, positions }
+    }
+}
+
+impl<'a, F, A: BaseAir<F>> BaseAir<F> for Replicated<'a, A> {
+    fn width(&self) -> usize {
+        self.inner.width() * self.positions.len()
+    }
+}
+
+impl<'a, AB, A> Air<AB> for Replicated<'a, A>
+where
+    AB: AirBuilderWithPublicValues,
+    A: BaseAir<AB::F> + for<'b> Air<InstanceBuilder<'b, AB>>,
+{
+    fn eval(&self, builder: &mut AB) {
+        for (i, positions) in self.positions.iter().enumerate() {
+            let public_values = i * self.num_public_values..(i + 1) * self.num_public_values;
+            self.inner.eval(&mut InstanceBuilder { inner: builder, positions, public_values });
+        }
+    }
+}
+
+/// A builder that forwards everything to `inner` but shows one instance's columns and public values.
+pub struct InstanceBuilder<'a, AB> {
+    inner: &'a mut AB,
+    positions: &'a [usize],
+    public_values: Range<usize>,
+}
+
+impl<'a, AB: AirBuilder> AirBuilder for InstanceBuilder<'a, AB> {
+    type F = AB::F;
+    type Expr = AB::Expr;
+    type Var = AB::Var;
+    type M = PermutedColumns<'a, AB::M>;
+
+    fn main(&self) -> Self::M {
+        PermutedColumns::new(self.inner.main(), self.positions)
+    }
+
+    fn is_first_row(&self) -> Self::Expr {
+        self.inner.is_first_row()
+    }
+
+    fn is_last_row(&self) -> Self::Expr {
+        self.inner.is_last_row()
+    }
+
+    fn is_transition_window(&self, size: usize) -> Self::Expr {
+        self.inner.is_transition_window(size)
+    }
+
+    fn assert_zero<I: Into<Self::Expr>>(&mut self, x: I) {
+        self.inner.assert_zero(x);
+    }
+}
+
+impl<'a, AB: AirBuilderWithPublicValues> AirBuilderWithPublicValues for InstanceBuilder<'a, AB> {
+    type PublicVar = AB::PublicVar;
+
+    fn public_values(&self) -> &[Self::PublicVar] {
+        &self.inner.public_values()[self.public_values.clone()]
+    }
+}
+
+/// One proof of every instance, and each instance's public values.
+pub struct AggregateProof {
+    pub proof: Proof<MyConfig>,
+    pub public_values: Vec<Vec<Val>>,
+}
+
+/// Lays the traces side by side, row by row.
+#[cfg(not(feature = "verifier-only"))]
+pub fn stack_instances(traces: &[RowMajorMatrix<Val>]) -> RowMajorMatrix<Val> {
+    let height = traces[0].height();
+    assert!(traces.iter().all(|t| t.height() == height), "every instance needs the same height");
+    let values = (0..height).flat_map(|r| traces.iter().flat_map(move |t| t.row_slice(r).to_vec())).collect();
+    RowMajorMatrix::new(values, traces.iter().map(|t| t.width()).sum())
+}
+
+/// Proves every `(trace, public_values)` instance of `air` in one proof.
+#[cfg(not(feature = "verifier-only"))]
+pub fn prove_aggregate<A>(
+    config: &MyConfig,
+    perm: &Perm,
+    air: &A,
+    instances: Vec<(RowMajorMatrix<Val>, Vec<Val>)>,
+) -> AggregateProof
+where
+    A: BaseAir<Val>,
+    for<'a> Replicated<'a, A>: ProvableAir,
+{
+    assert!(!instances.is_empty(), "nothing to aggregate");
+    let (traces, public_values): (Vec<_>, Vec<_>) = instances.into_iter().unzip();
+    let num_public_values = public_values[0].len();
+    assert!(public_values.iter().all(|pis| pis.len() == num_public_values), "instances disagree on arity");
+
+    let replicated = Replicated::new(air, traces.len(), num_public_values);
+    let mut challenger = Challenger::new(perm.clone());
+    let proof = prove(config, &replicated, &mut challenger, stack_instances(&traces), &public_values.concat());
+    AggregateProof { proof, public_values }
+}
+
+/// Verifies a proof from `prove_aggregate`.
+pub fn verify_aggregate<A>(config: &MyConfig, perm: &Perm, air: &A, aggregate: &AggregateProof) -> Result<(), CookError>
+where
+    A: BaseAir<Val>,
+    for<'a> Replicated<'a, A>: ProvableAir,
+{
+    let Some(first) = aggregate.public_values.first() else {
+        return Err(CookError::PublicValues("an aggregate of no instances".to_string()));
+    };
+    if let Some(i) = aggregate.public_values.iter().position(|pis| pis.len() != first.len()) {
+        let got = aggregate.public_values[i].len();
+        return Err(CookError::PublicValues(format!("instance {} has {} public values, not {}", i, got, first.len())));
+    }
+
+    let replicated = Replicated::new(air, aggregate.public_values.len(), first.len());
+    let mut challenger = Challenger::new(perm.clone());
+    verify(config, &replicated, &mut challenger, &aggregate.proof, &aggregate.public_values.concat())
+        .map_err(|e| CookError::Verification(e.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_field::AbstractField;
+
+    use super::*;
+    use crate::config::{default_config, random_perm};
+    use crate::simple_state::{random_checked_trace, SimpleStateChecked, SSC_ROW_WIDTH};
+    use crate::{assert_constraints_fail, assert_constraints_ok};
+
+    const LOG_N: usize = 4;
+
+    fn instances(k: usize) -> Vec<(RowMajorMatrix<Val>, Vec<Val>)> {
+        (0..k).map(|_| random_checked_trace(LOG_N)).collect()
+    }
+
+    #[test]
+    fn test_each_instance_checked_on_its_own_columns() {
+        let (traces, public_values): (Vec<_>, Vec<_>) = instances(3).into_iter().unzip();
+        let inner = SimpleStateChecked {};
+        let air = Replicated::new(&inner, 3, 2);
+        let mut trace = stack_instances(&traces);
+        assert_constraints_ok!(&air, &trace, &public_values.concat());
+
+        // the second instance's final balance, checked on the last row
+        let mut wrong = public_values.clone();
+        wrong[1][1] += Val::one();
+        assert_constraints_fail!(&air, &trace, &wrong.concat(), (1 << LOG_N) - 1);
+
+        // the third instance's initial balance, in its own first column
+        trace.row_mut(0)[2 * SSC_ROW_WIDTH] += Val::one();
+        assert_constraints_fail!(&air, &trace, &public_values.concat(), 0);
+    }
+
+    #[test]
+    fn test_aggregate_round_trip() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let mut aggregate = prove_aggregate(&config, &perm, &SimpleStateChecked {}, instances(4));
+        verify_aggregate(&config, &perm, &SimpleStateChecked {}, &aggregate).unwrap();
+
+        aggregate.public_values.swap(0, 3);
+        assert!(matches!(
+            verify_aggregate(&config, &perm, &SimpleStateChecked {}, &aggregate),
+            Err(CookError::Verification(_))
+        ));
+        aggregate.public_values[2].pop();
+        assert!(matches!(
+            verify_aggregate(&config, &perm, &SimpleStateChecked {}, &aggregate),
+            Err(CookError::PublicValues(_))
+        ));
+    }
+}
@@ -0,0 +1,133 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::ptr;
+
+use p3_field::Field;
+use p3_matrix::dense::RowMajorMatrix;
+
+use crate::error::CookError;
+
+// `RowMajorMatrix` owns a plain `Vec<F>`, and a `Vec` frees its buffer with `F`'s own alignment. Handing it a
+// buffer allocated with a 64-byte `Layout` would make that free mismatch the allocation, so the alignment has
+// to come from the allocator itself: `CacheAlignedAlloc` rounds every large allocation's `Layout` up to a
+// cache line, and since the decision only depends on the size it makes the same choice on free.
+//
+// Binaries opt in with
+//
+//     #[global_allocator]
+//     static ALLOC: CacheAlignedAlloc = CacheAlignedAlloc;
+//
+// after which `alloc_aligned_trace` (and any other trace-sized `Vec`) starts on a cache line. Without it, or
+// for a trace below the threshold, the buffer may land anywhere, so `alloc_aligned_trace` checks and fails
+// rather than hand back a trace that only looks aligned.
+
+pub const CACHE_LINE: usize = 64;
+
+/// Allocations at least this large are cache-line aligned; smaller ones keep their natural alignment.
+pub const ALIGN_THRESHOLD: usize = 4096;
+
+pub struct CacheAlignedAlloc;
+
+impl CacheAlignedAlloc {
+    fn adjust(layout: Layout) -> Layout {
+        if layout.size() >= ALIGN_THRESHOLD && layout.align() < CACHE_LINE {
+            // a size that fit the original layout can't overflow with a 64-byte alignment
+            Layout::from_size_align(layout.size(), CACHE_LINE).unwrap()
+        } else {
+            layout
+        }
+    }
+}
+
+unsafe impl GlobalAlloc for CacheAlignedAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        System.alloc(Self::adjust(layout))
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        System.alloc_zeroed(Self::adjust(layout))
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, Self::adjust(layout))
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let old = Self::adjust(layout);
+        let new = Self::adjust(Layout::from_size_align_unchecked(new_size, layout.align()));
+        if old.align() == new.align() {
+            return System.realloc(ptr, old, new_size);
+        }
+
+        // crossing the threshold changes the alignment, which `realloc` can't do in place
+        let new_ptr = System.alloc(new);
+        if !new_ptr.is_null() {
+            ptr::copy_nonoverlapping(ptr, new_ptr, layout.size().min(new_size));
+            System.dealloc(ptr, old);
+        }
+        new_ptr
+    }
+}
+
+/// A zeroed `n_rows x n_cols` trace starting on a cache line.
+///
+/// That needs `CacheAlignedAlloc` as the global allocator and a trace of at least `ALIGN_THRESHOLD` bytes;
+/// otherwise, or if the trace is too large to allocate, this returns an error.
+pub fn alloc_aligned_trace<F: Field>(n_rows: usize, n_cols: usize) -> Result<RowMajorMatrix<F>, CookError> {
+    let len = n_rows
+        .checked_mul(n_cols)
+        .filter(|&len| Layout::array::<F>(len).is_ok())
+        .ok_or_else(|| CookError::Trace(format!("a {} x {} trace is too large to allocate", n_rows, n_cols)))?;
+
+    let mut values = Vec::with_capacity(len);
+    values.resize(len, F::zero());
+    let trace = RowMajorMatrix::new(values, n_cols);
+    if !is_cache_aligned(&trace) {
+        return Err(CookError::Trace(format!(
+            "the trace is not aligned to {} bytes; it needs `CacheAlignedAlloc` as the global allocator and at \
+             least {} bytes",
+            CACHE_LINE, ALIGN_THRESHOLD
+        )));
+    }
+    Ok(trace)
+}
+
+pub fn is_cache_aligned<F>(trace: &RowMajorMatrix<F>) -> bool {
+    trace.values.as_ptr() as usize % CACHE_LINE == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Val;
+
+    #[test]
+    fn test_adjust_only_large_layouts() {
+        let small = Layout::from_size_align(64, 4).unwrap();
+        assert_eq!(CacheAlignedAlloc::adjust(small), small);
+
+        let large = Layout::from_size_align(ALIGN_THRESHOLD, 4).unwrap();
+        assert_eq!(CacheAlignedAlloc::adjust(large).align(), CACHE_LINE);
+    }
+
+    #[test]
+    fn test_oversized_trace_is_an_error() {
+        assert!(matches!(alloc_aligned_trace::<Val>(usize::MAX, 2), Err(CookError::Trace(_))));
+        assert!(matches!(alloc_aligned_trace::<Val>(usize::MAX / 2, 2), Err(CookError::Trace(_))));
+    }
+
+    #[test]
+    fn test_realloc_across_threshold() {
+        let alloc = CacheAlignedAlloc;
+        unsafe {
+            let layout = Layout::from_size_align(16, 4).unwrap();
+            let ptr = alloc.alloc(layout);
+            ptr.write_bytes(7, 16);
+
+            let grown = alloc.realloc(ptr, layout, ALIGN_THRESHOLD * 2);
+            assert_eq!(grown as usize % CACHE_LINE, 0);
+            assert_eq!(*grown.add(15), 7);
+
+            alloc.dealloc(grown, Layout::from_size_align(ALIGN_THRESHOLD * 2, 4).unwrap());
+        }
+    }
+}
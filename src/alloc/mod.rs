@@ -0,0 +1,2 @@
+pub mod aligned_trace;
+pub mod peak;
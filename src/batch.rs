@@ -0,0 +1,185 @@
+use p3_challenger::CanObserve;
+use p3_commit::Pcs as _;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+use p3_maybe_rayon::prelude::*;
+use p3_symmetric::Hash;
+use p3_uni_stark::{prove, verify, Proof, StarkGenericConfig};
+
+use crate::config::{Challenger, MyConfig, Perm, Val};
+use crate::error::CookError;
+use crate::lookups::{interpolate_at, out_of_domain_point, ProvableAir};
+
+// Many instances of one circuit over one fixed table, proven in parallel with the `parallel` feature, each
+// proof bound to one commitment of the table.
+//
+// uni-stark at this revision has no preprocessed trace, so the table's columns live in each main trace (at
+// `SharedPreprocessed::columns`) and are tied to the shared table the way the lookups tie their tables:
+//   1. the prover commits the table once; every instance's transcript starts by observing that commitment,
+//      so each proof is bound to it;
+//   2. the verifier checks each proof's opening of the table columns at the out-of-domain point `zeta`
+//      against the table interpolated there, `O(height)` field operations per column and proof.
+// Instances don't share a transcript, so proofs can be produced, shipped and verified independently.
+//
+// This saves no prover work: every main trace carries the table's columns, so each proof commits them again
+// and pays for them in its LDE and FRI like any other column. The shared commitment only pins down which
+// table every proof in the batch was made over; proving the batch costs what proving each instance does.
+
+/// A fixed table shared by every instance of a batch, and its commitment.
+pub struct SharedPreprocessed {
+    pub table: RowMajorMatrix<Val>,
+    /// the main-trace column holding each table column
+    pub columns: Vec<usize>,
+    pub commitment: Hash<Val, Val, 8>,
+}
+
+impl SharedPreprocessed {
+    /// Commits `table` once, for instances carrying its columns at `columns`.
+    pub fn commit(config: &MyConfig, table: RowMajorMatrix<Val>, columns: Vec<usize>) -> Self {
+        assert_eq!(columns.len(), table.width(), "every table column needs a trace column");
+        let pcs = config.pcs();
+        let domain = pcs.natural_domain_for_degree(table.height());
+        let (commitment, _) = pcs.commit(vec![(domain, table.clone())]);
+        SharedPreprocessed { table, columns, commitment }
+    }
+
+    fn challenger(&self, perm: &Perm) -> Challenger {
+        let mut challenger = Challenger::new(perm.clone());
+        challenger.observe(self.commitment.clone());
+        challenger
+    }
+
+    fn matches(&self, trace: &RowMajorMatrix<Val>) -> bool {
+        trace.height() == self.table.height()
+            && (0..trace.height()).all(|r| {
+                let (row, fixed) = (trace.row_slice(r), self.table.row_slice(r));
+                self.columns.iter().zip(fixed.iter()).all(|(&c, v)| row[c] == *v)
+            })
+    }
+}
+
+/// Proves each `(trace, public_values)` instance of `air` against the one `shared` commitment. Fails before
+/// proving anything if an instance's trace doesn't carry the shared table.
+pub fn prove_batch<A: ProvableAir + Sync>(
+    config: &MyConfig,
+    perm: &Perm,
+    air: &A,
+    shared: &SharedPreprocessed,
+    instances: Vec<(RowMajorMatrix<Val>, Vec<Val>)>,
+) -> Result<Vec<Proof<MyConfig>>, CookError> {
+    if let Some(i) = instances.iter().position(|(trace, _)| !shared.matches(trace)) {
+        return Err(CookError::Trace(format!("instance {} does not carry the shared table", i)));
+    }
+    Ok(instances
+        .into_par_iter()
+        .map(|(trace, public_values)| prove(config, air, &mut shared.challenger(perm), trace, &public_values))
+        .collect())
+}
+
+/// Verifies one proof from `prove_batch`.
+pub fn verify_batch_member<A: ProvableAir>(
+    config: &MyConfig,
+    perm: &Perm,
+    air: &A,
+    shared: &SharedPreprocessed,
+    proof: &Proof<MyConfig>,
+    public_values: &Vec<Val>,
+) -> Result<(), CookError> {
+    let mut challenger = shared.challenger(perm);
+
+    let zeta = out_of_domain_point(&challenger, proof, public_values);
+    let opened = &proof.opened_values.trace_local;
+    for (i, &column) in shared.columns.iter().enumerate() {
+        let values = shared.table.values.iter().skip(i).step_by(shared.table.width()).copied().collect::<Vec<_>>();
+        if opened.get(column) != Some(&interpolate_at(&values, zeta)) {
+            return Err(CookError::PublicValues(format!("column {} is not the shared table", column)));
+        }
+    }
+
+    verify(config, air, &mut challenger, proof, public_values).map_err(|e| CookError::Verification(e.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_air::{Air, AirBuilderWithPublicValues, BaseAir};
+    use p3_field::AbstractField;
+    use rand::{thread_rng, Rng};
+
+    use super::*;
+    use crate::config::{default_config, random_perm};
+
+    const WEIGHT_COL: usize = 0;
+
+    /// `acc` sums `weight * value`, `weight` being the shared table; the total is the public value.
+    struct WeightedTotal {}
+
+    impl<F> BaseAir<F> for WeightedTotal {
+        fn width(&self) -> usize {
+            3
+        }
+    }
+
+    impl<AB: AirBuilderWithPublicValues> Air<AB> for WeightedTotal {
+        fn eval(&self, builder: &mut AB) {
+            let main = builder.main();
+            let (local, next) = (main.row_slice(0), main.row_slice(1));
+            let total: AB::Expr = builder.public_values()[0].into();
+
+            builder.when_first_row().assert_eq(local[2], local[0] * local[1]);
+            builder.when_transition().assert_eq(next[2], local[2] + next[0] * next[1]);
+            builder.when_last_row().assert_eq(local[2], total);
+        }
+    }
+
+    fn instance(weights: &[Val]) -> (RowMajorMatrix<Val>, Vec<Val>) {
+        let mut rng = thread_rng();
+        let mut acc = Val::zero();
+        let values = weights
+            .iter()
+            .flat_map(|&w| {
+                let value: Val = rng.gen();
+                acc += w * value;
+                [w, value, acc]
+            })
+            .collect();
+        (RowMajorMatrix::new(values, 3), vec![acc])
+    }
+
+    #[test]
+    fn test_batch_verifies_against_one_commitment() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let weights = (0..64).map(|_| thread_rng().gen()).collect::<Vec<Val>>();
+        let shared = SharedPreprocessed::commit(&config, RowMajorMatrix::new_col(weights.clone()), vec![WEIGHT_COL]);
+
+        let instances = (0..4).map(|_| instance(&weights)).collect::<Vec<_>>();
+        let public_values = instances.iter().map(|(_, pis)| pis.clone()).collect::<Vec<_>>();
+        let proofs = prove_batch(&config, &perm, &WeightedTotal {}, &shared, instances).unwrap();
+
+        assert_eq!(proofs.len(), 4);
+        for (proof, pis) in proofs.iter().zip(&public_values) {
+            verify_batch_member(&config, &perm, &WeightedTotal {}, &shared, proof, pis).unwrap();
+        }
+
+        // an instance proven over other weights is not a member of the batch
+        let other_weights = weights.iter().map(|&w| w + Val::one()).collect::<Vec<_>>();
+        let (trace, pis) = instance(&other_weights);
+        let proof = prove(&config, &WeightedTotal {}, &mut shared.challenger(&perm), trace, &pis);
+        assert!(verify_batch_member(&config, &perm, &WeightedTotal {}, &shared, &proof, &pis).is_err());
+    }
+
+    #[test]
+    fn test_instance_without_the_table_is_rejected() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let weights = (0..16).map(|_| thread_rng().gen()).collect::<Vec<Val>>();
+        let shared = SharedPreprocessed::commit(&config, RowMajorMatrix::new_col(weights.clone()), vec![WEIGHT_COL]);
+
+        let other_weights = weights.iter().map(|&w| w + Val::one()).collect::<Vec<_>>();
+        let instances = vec![instance(&weights), instance(&other_weights)];
+        assert!(matches!(
+            prove_batch(&config, &perm, &WeightedTotal {}, &shared, instances),
+            Err(CookError::Trace(_))
+        ));
+    }
+}
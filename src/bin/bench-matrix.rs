@@ -0,0 +1,196 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::time::Instant;
+use std::{env, fs, process};
+
+use p3_uni_stark::verify;
+use plonky3_cook::config::{
+    make_config, make_config_with, make_hybrid_config, random_perm, random_perm24, Challenger, Val, WideHash,
+    DEFAULT_NUM_QUERIES, DEFAULT_POW_BITS,
+};
+use plonky3_cook::simple_state::{random_trace, SimpleState};
+use plonky3_cook::timing::{prove_timed, RunStats};
+use serde::Deserialize;
+
+// Proves and verifies SimpleState for every combination of a parameter grid and writes one report.
+//
+//   bench-matrix <grid.toml> [--out <report.json|report.csv>]
+//
+// The grid lists `fields`, `hashes`, `log_blowups` and `log_heights`; see `benches/grids/default.toml`.
+// Combinations the crate has no config for (any field but BabyBear, since `TwoAdicFriPcs` needs a two-adic
+// field and the only other config would be the circle PCS) or that don't fit the field's two-adicity are
+// recorded as skipped with the reason. A proof that fails to verify is recorded as failed, the rest of the grid
+// still runs, and the exit status is non-zero. The report is rewritten after every run, and runs already in it
+// are not repeated, so an interrupted grid resumes where it stopped.
+
+const USAGE: &str = "usage: bench-matrix <grid.toml> [--out <report.json|report.csv>]";
+
+/// log2 of the largest BabyBear two-adic subgroup
+const BABYBEAR_TWO_ADICITY: usize = 27;
+
+#[derive(Deserialize)]
+struct Grid {
+    fields: Vec<String>,
+    hashes: Vec<String>,
+    log_blowups: Vec<usize>,
+    log_heights: Vec<usize>,
+}
+
+fn parse_args() -> Result<(String, String), String> {
+    let mut grid = None;
+    let mut out = "bench-matrix.json".to_string();
+
+    let mut it = env::args().skip(1);
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--out" => out = it.next().ok_or("missing value for --out")?,
+            _ if grid.is_none() => grid = Some(arg),
+            _ => return Err(format!("unexpected argument {}", arg)),
+        }
+    }
+    Ok((grid.ok_or("a grid file is required")?, out))
+}
+
+fn is_csv(path: &str) -> bool {
+    Path::new(path).extension().is_some_and(|ext| ext == "csv")
+}
+
+fn read_report(path: &str) -> Result<Vec<RunStats>, String> {
+    let Ok(text) = fs::read_to_string(path) else {
+        return Ok(vec![]);
+    };
+    if is_csv(path) {
+        text.lines().skip(1).filter(|line| !line.is_empty()).map(RunStats::from_csv_row).collect()
+    } else {
+        serde_json::from_str(&text).map_err(|e| format!("{}: {}", path, e))
+    }
+}
+
+fn write_report(path: &str, runs: &[RunStats]) -> Result<(), String> {
+    let text = if is_csv(path) {
+        let rows = runs.iter().map(RunStats::to_csv_row);
+        std::iter::once(RunStats::CSV_HEADER.to_string()).chain(rows).collect::<Vec<_>>().join("\n") + "\n"
+    } else {
+        serde_json::to_string_pretty(runs).map_err(|e| e.to_string())?
+    };
+    fs::write(path, text).map_err(|e| format!("{}: {}", path, e))
+}
+
+fn run(field: &str, hash: &str, log_blowup: usize, log_n: usize) -> RunStats {
+    if field != "babybear" {
+        return RunStats::skipped(
+            field,
+            hash,
+            log_blowup,
+            log_n,
+            format!("no TwoAdicFriPcs config for {}; only babybear is supported", field),
+        );
+    }
+    if log_n + log_blowup > BABYBEAR_TWO_ADICITY {
+        return RunStats::skipped(
+            field,
+            hash,
+            log_blowup,
+            log_n,
+            format!("LDE height 2^{} exceeds the two-adicity 2^{}", log_n + log_blowup, BABYBEAR_TWO_ADICITY),
+        );
+    }
+    if log_blowup == 0 {
+        return RunStats::skipped(field, hash, log_blowup, log_n, "the quotient needs a blowup of at least 2");
+    }
+
+    let perm = random_perm();
+    let trace = random_trace::<Val>(log_n);
+
+    macro_rules! measure {
+        ($config:expr) => {{
+            let config = $config;
+            let mut p_challenger = Challenger::new(perm.clone());
+            let (proof, phases) = prove_timed(&config, &SimpleState {}, &mut p_challenger, trace, &vec![]);
+
+            let start = Instant::now();
+            let mut v_challenger = Challenger::new(perm.clone());
+            if let Err(e) = verify(&config, &SimpleState {}, &mut v_challenger, &proof, &vec![]) {
+                return RunStats::failed(field, hash, log_blowup, log_n, format!("verification failed: {:?}", e));
+            }
+            let verify_time = start.elapsed();
+
+            let proof_bytes = bincode::serialize(&proof).expect("proofs always serialize").len();
+            RunStats::measured(field, hash, log_blowup, log_n, &phases, verify_time, proof_bytes)
+        }};
+    }
+
+    match hash {
+        "poseidon2" => measure!(make_config(&perm, log_blowup, DEFAULT_NUM_QUERIES, DEFAULT_POW_BITS)),
+        "poseidon2-wide" => measure!(make_config_with(
+            WideHash::new(random_perm24()),
+            &perm,
+            log_blowup,
+            DEFAULT_NUM_QUERIES,
+            DEFAULT_POW_BITS
+        )),
+        "hybrid" => measure!(make_hybrid_config(&perm, log_blowup, DEFAULT_NUM_QUERIES, DEFAULT_POW_BITS)),
+        other => RunStats::skipped(
+            field,
+            hash,
+            log_blowup,
+            log_n,
+            format!("unknown hash {}, expected poseidon2, poseidon2-wide or hybrid", other),
+        ),
+    }
+}
+
+fn run_grid(grid_path: &str, out: &str) -> Result<(), String> {
+    let grid = fs::read_to_string(grid_path).map_err(|e| format!("{}: {}", grid_path, e))?;
+    let grid: Grid = toml::from_str(&grid).map_err(|e| format!("{}: {}", grid_path, e))?;
+
+    let mut runs = read_report(out)?;
+    let done = runs.iter().map(RunStats::key).collect::<BTreeSet<_>>();
+    if !done.is_empty() {
+        println!("resuming: {} runs already in {}", done.len(), out);
+    }
+
+    for field in &grid.fields {
+        for hash in &grid.hashes {
+            for &log_blowup in &grid.log_blowups {
+                for &log_n in &grid.log_heights {
+                    if done.contains(&(field.clone(), hash.clone(), log_blowup, log_n)) {
+                        continue;
+                    }
+
+                    let stats = run(field, hash, log_blowup, log_n);
+                    let label = format!("{} {} blowup 2^{} n 2^{}", field, hash, log_blowup, log_n);
+                    match (&stats.skipped, &stats.failed, stats.prove_ms) {
+                        (Some(reason), _, _) => println!("{}: skipped, {}", label, reason),
+                        (None, Some(reason), _) => eprintln!("{}: FAILED, {}", label, reason),
+                        (None, None, Some(ms)) => println!("{}: prove {:.1} ms", label, ms),
+                        (None, None, None) => unreachable!("a measured run has a prove time"),
+                    }
+                    runs.push(stats);
+                    write_report(out, &runs)?;
+                }
+            }
+        }
+    }
+
+    let failed = runs.iter().filter(|stats| stats.failed.is_some()).count();
+    if failed > 0 {
+        return Err(format!("{} of {} runs failed to verify; see {}", failed, runs.len(), out));
+    }
+    Ok(())
+}
+
+fn main() {
+    let (grid, out) = parse_args().unwrap_or_else(|e| {
+        eprintln!("{}\n{}", e, USAGE);
+        process::exit(2);
+    });
+
+    match run_grid(&grid, &out) {
+        Ok(()) => println!("wrote {}", out),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
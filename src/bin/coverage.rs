@@ -0,0 +1,30 @@
+use plonky3_cook::config::Val;
+use plonky3_cook::coverage::{mutate_and_check, Coverage};
+use plonky3_cook::simple_state::{random_checked_trace, random_trace, SimpleState, SimpleStateChecked};
+
+// Prints per-column mutation coverage for SimpleState and its range-checked variant.
+
+fn print_summary(name: &str, columns: &[&str], coverage: &Coverage) {
+    println!("{}: {} mutations, {} undetected", name, coverage.mutated, coverage.undetected.len());
+    for (col, missed) in coverage.undetected_per_column().into_iter().enumerate() {
+        let label = columns.get(col).copied().unwrap_or("bit");
+        let status = if missed == 0 { "ok" } else { "MISSING CONSTRAINT?" };
+        println!("  {:>3} {:<10} {:>6} undetected  {}", col, label, missed, status);
+    }
+    println!();
+}
+
+fn main() {
+    let log_n = 8;
+    let columns = ["balance", "input", "output"];
+
+    let trace = random_trace::<Val>(log_n);
+    print_summary("SimpleState", &columns, &mutate_and_check(&SimpleState {}, &trace, &[]));
+
+    let (trace, public_values) = random_checked_trace::<Val>(log_n);
+    print_summary(
+        "SimpleStateChecked",
+        &columns,
+        &mutate_and_check(&SimpleStateChecked {}, &trace, &public_values),
+    );
+}
@@ -0,0 +1,139 @@
+use std::io::{self, BufRead, Write};
+
+use p3_field::PrimeField32;
+use p3_uni_stark::{prove, verify};
+use plonky3_cook::config::{default_config, random_perm, Challenger, Val};
+use plonky3_cook::simple_state::SimpleStateChecked;
+use plonky3_cook::transaction::{trace_from_transactions, validate_all, Transaction};
+
+// An interactive ledger: each command appends a transaction to an in-memory `SimpleStateChecked` session,
+// and `prove` proves and verifies the whole session so far. Invalid transactions are rejected with the
+// ledger's message and leave the session as it was.
+//
+//   deposit <n>     add a transaction with input n
+//   withdraw <n>    add a transaction with output n
+//   balance         print the current balance
+//   history         list the transactions so far
+//   prove           prove and verify the session
+//   reset [<n>]     start over, with initial balance n (default 0)
+//   help, quit
+//
+// Lines starting with `#` are comments, so a session can be scripted: `repl < session.txt`.
+
+const HELP: &str = "commands: deposit <n>, withdraw <n>, balance, history, prove, reset [<n>], help, quit";
+
+struct Session {
+    initial_balance: u32,
+    transactions: Vec<Transaction>,
+    balance: u32,
+}
+
+impl Session {
+    fn new(initial_balance: u32) -> Self {
+        Session { initial_balance, transactions: vec![], balance: initial_balance }
+    }
+
+    fn push(&mut self, tx: Transaction) -> Result<(), String> {
+        self.transactions.push(tx);
+        match validate_all(self.initial_balance, &self.transactions) {
+            Ok(balances) => {
+                self.balance = *balances.last().unwrap();
+                Ok(())
+            }
+            Err(e) => {
+                self.transactions.pop();
+                Err(e.to_string())
+            }
+        }
+    }
+
+    fn prove(&self) -> Result<String, String> {
+        let (trace, public_values) =
+            trace_from_transactions::<Val>(self.initial_balance, &self.transactions).map_err(|e| e.to_string())?;
+        let perm = random_perm();
+        let config = default_config(&perm);
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &SimpleStateChecked {}, &mut p_challenger, trace, &public_values);
+        let mut v_challenger = Challenger::new(perm);
+        verify(&config, &SimpleStateChecked {}, &mut v_challenger, &proof, &public_values)
+            .map_err(|e| format!("verification failed: {:?}", e))?;
+
+        let size = bincode::serialize(&proof).map(|bytes| bytes.len()).unwrap_or(0);
+        Ok(format!(
+            "proof verified: {} transaction(s), balance {} -> {}, {} bytes",
+            self.transactions.len(),
+            public_values[0].as_canonical_u32(),
+            public_values[1].as_canonical_u32(),
+            size
+        ))
+    }
+}
+
+fn parse_amount(arg: Option<&str>) -> Result<u32, String> {
+    let arg = arg.ok_or("missing amount")?;
+    arg.parse().map_err(|_| format!("not an amount: {}", arg))
+}
+
+/// Runs one command line; `None` ends the session.
+fn run_command(session: &mut Session, line: &str) -> Option<Result<String, String>> {
+    let mut words = line.split_whitespace();
+    let command = words.next()?;
+    let arg = words.next();
+    if words.next().is_some() {
+        return Some(Err(format!("too many arguments to {}", command)));
+    }
+
+    let result = match command {
+        "deposit" | "withdraw" => parse_amount(arg).and_then(|n| {
+            let tx = if command == "deposit" { Transaction::new(n, 0) } else { Transaction::new(0, n) };
+            session.push(tx)?;
+            Ok(format!("balance: {}", session.balance))
+        }),
+        "balance" => Ok(format!("balance: {}", session.balance)),
+        "history" => Ok(session
+            .transactions
+            .iter()
+            .enumerate()
+            .map(|(i, tx)| format!("{:>4}  in {:>10}  out {:>10}", i, tx.input, tx.output))
+            .collect::<Vec<_>>()
+            .join("\n")),
+        "prove" => session.prove(),
+        "reset" => {
+            let initial = match arg {
+                Some(_) => parse_amount(arg),
+                None => Ok(0),
+            };
+            initial.map(|n| {
+                *session = Session::new(n);
+                format!("balance: {}", n)
+            })
+        }
+        "help" => Ok(HELP.to_string()),
+        "quit" | "exit" => return None,
+        _ => Err(format!("unknown command: {} ({})", command, HELP)),
+    };
+    Some(result)
+}
+
+fn main() -> io::Result<()> {
+    let (stdin, stdout) = (io::stdin(), io::stdout());
+    let mut out = stdout.lock();
+    let mut session = Session::new(0);
+
+    writeln!(out, "{}", HELP)?;
+    for line in stdin.lock().lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match run_command(&mut session, line) {
+            None => break,
+            Some(Ok(message)) => writeln!(out, "{}", message)?,
+            Some(Err(message)) => writeln!(out, "error: {}", message)?,
+        }
+        out.flush()?;
+    }
+    Ok(())
+}
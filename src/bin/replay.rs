@@ -0,0 +1,58 @@
+use std::{env, process};
+
+use p3_field::{AbstractField, PrimeField32};
+use p3_uni_stark::prove;
+use plonky3_cook::config::{default_config, random_perm, Challenger, Val};
+use plonky3_cook::replay::{replay, verify_and_replay, ReplayedState};
+use plonky3_cook::simple_state::{random_checked_trace, SimpleStateChecked};
+
+// Prints the state certified by SimpleStateChecked public values.
+//
+//   replay <initial> <final>   decode the given public values
+//   replay                     prove a random ledger, verify it, and replay its public values
+
+fn print_state(state: &ReplayedState) {
+    println!("initial balance: {}", state.initial_balance);
+    println!("final balance:   {}", state.final_balance);
+    println!("net flow:        {:+}", state.net_flow);
+}
+
+/// A public value as a `u32` below the modulus; a larger one would be reduced and replay a different state.
+fn parse_public_value(arg: &str) -> Result<Val, String> {
+    let value = arg.parse::<u32>().map_err(|e| format!("{:?}: {}", arg, e))?;
+    if value >= Val::ORDER_U32 {
+        return Err(format!("{} is not below the modulus {}", value, Val::ORDER_U32));
+    }
+    Ok(Val::from_canonical_u32(value))
+}
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+
+    let state = if args.is_empty() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let (trace, public_values) = random_checked_trace::<Val>(10);
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &SimpleStateChecked {}, &mut p_challenger, trace, &public_values);
+        let mut v_challenger = Challenger::new(perm);
+        verify_and_replay(&config, &mut v_challenger, &proof, &public_values)
+    } else {
+        match args.iter().map(|a| parse_public_value(a)).collect::<Result<Vec<_>, _>>() {
+            Ok(public_values) => replay(&public_values),
+            Err(e) => {
+                eprintln!("public values must be canonical field elements: {}", e);
+                process::exit(2);
+            }
+        }
+    };
+
+    match state {
+        Ok(state) => print_state(&state),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use plonky3_cook::config::{challenge_field_bits, FriParams, DEFAULT_LOG_BLOWUP, DEFAULT_POW_BITS};
+
+// Prints the estimated security of the default config's FRI parameters as the number of queries grows, so
+// the cost of each query (proof size, verifier time) can be weighed against the bits it buys. See
+// `FriParams::conjectured_security_bits` for the formula and what it leaves out.
+
+fn main() {
+    println!(
+        "log_blowup = {}, proof_of_work_bits = {}, challenge field = {:.1} bits",
+        DEFAULT_LOG_BLOWUP,
+        DEFAULT_POW_BITS,
+        challenge_field_bits()
+    );
+    println!("{:>11} {:>16} {:>12}", "num_queries", "conjectured bits", "proven bits");
+    for num_queries in (10..=100).step_by(10) {
+        let params = FriParams { num_queries, ..FriParams::default() };
+        println!(
+            "{:>11} {:>16.1} {:>12.1}",
+            num_queries,
+            params.conjectured_security_bits(),
+            params.proven_security_bits()
+        );
+    }
+}
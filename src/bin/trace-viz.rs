@@ -0,0 +1,149 @@
+use std::{env, fs, process};
+
+use p3_air::{Air, BaseAir};
+use p3_field::AbstractField;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::SymbolicAirBuilder;
+use plonky3_cook::columns::NamedColumns;
+use plonky3_cook::config::Val;
+use plonky3_cook::debug::EvalBuilder;
+use plonky3_cook::simple_state::{
+    random_checked_trace_with_rng, random_trace_with_rng, SimpleState, SimpleStateChecked,
+};
+use plonky3_cook::viz::{publics_from_csv, render_html, trace_from_csv};
+use rand::{rngs::StdRng, SeedableRng};
+
+// Renders a trace as a static HTML table, highlighting cells implicated in failed constraints.
+//
+//   trace-viz --air <simple_state|simple_state_checked>
+//             (--csv <trace.csv> [--publics a,b,...] | --seed <u64> [--log-n <n>])
+//             [--corrupt <row>,<col>] [--rows <start>..<end>] [--out <trace.html>]
+
+const USAGE: &str = "usage: trace-viz --air <simple_state|simple_state_checked> \
+(--csv <trace.csv> [--publics a,b,...] | --seed <u64> [--log-n <n>]) \
+[--corrupt <row>,<col>] [--rows <start>..<end>] [--out <trace.html>]";
+
+struct Args {
+    air: String,
+    csv: Option<String>,
+    publics: Option<Vec<Val>>,
+    seed: Option<u64>,
+    log_n: usize,
+    corrupt: Option<(usize, usize)>,
+    rows: Option<(usize, usize)>,
+    out: String,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        air: String::new(),
+        csv: None,
+        publics: None,
+        seed: None,
+        log_n: 4,
+        corrupt: None,
+        rows: None,
+        out: "trace.html".to_string(),
+    };
+
+    let mut it = env::args().skip(1);
+    while let Some(flag) = it.next() {
+        let value = it.next().ok_or(format!("missing value for {}", flag))?;
+        match flag.as_str() {
+            "--air" => args.air = value,
+            "--csv" => args.csv = Some(value),
+            "--publics" => args.publics = Some(publics_from_csv(&value).map_err(|e| format!("--publics: {}", e))?),
+            "--seed" => args.seed = Some(value.parse().map_err(|e| format!("--seed: {}", e))?),
+            "--log-n" => args.log_n = value.parse().map_err(|e| format!("--log-n: {}", e))?,
+            "--corrupt" => {
+                let (r, c) = value.split_once(',').ok_or("--corrupt expects <row>,<col>")?;
+                args.corrupt = Some((r.parse().map_err(|e| format!("--corrupt: {}", e))?, c.parse().map_err(|e| format!("--corrupt: {}", e))?));
+            }
+            "--rows" => {
+                let (a, b) = value.split_once("..").ok_or("--rows expects <start>..<end>")?;
+                args.rows = Some((a.parse().map_err(|e| format!("--rows: {}", e))?, b.parse().map_err(|e| format!("--rows: {}", e))?));
+            }
+            "--out" => args.out = value,
+            _ => return Err(format!("unknown flag {}", flag)),
+        }
+    }
+
+    if args.air.is_empty() {
+        return Err("--air is required".to_string());
+    }
+    if args.csv.is_none() == args.seed.is_none() {
+        return Err("exactly one of --csv and --seed is required".to_string());
+    }
+    Ok(args)
+}
+
+/// Renders `trace` for `air`, which reads `num_public_values` public values.
+fn render<A>(
+    air: &A,
+    num_public_values: usize,
+    mut trace: RowMajorMatrix<Val>,
+    public_values: Vec<Val>,
+    args: &Args,
+) -> Result<String, String>
+where
+    A: NamedColumns + Air<SymbolicAirBuilder<Val>> + for<'a> Air<EvalBuilder<'a, Val>>,
+{
+    let width = BaseAir::<Val>::width(air);
+    if trace.width != width {
+        return Err(format!("the trace has {} columns, but the AIR has {}", trace.width, width));
+    }
+    if public_values.len() != num_public_values {
+        return Err(format!("{} takes {} public values, got {}", args.air, num_public_values, public_values.len()));
+    }
+    if let Some((r, c)) = args.corrupt {
+        if r >= trace.values.len() / trace.width || c >= trace.width {
+            return Err(format!("--corrupt {},{} is outside the trace", r, c));
+        }
+        trace.row_mut(r)[c] += Val::one();
+    }
+
+    let height = trace.values.len() / trace.width;
+    let (start, end) = args.rows.unwrap_or((0, height));
+    Ok(render_html(air, &air.column_names(), &trace, &public_values, start..end))
+}
+
+fn run(args: &Args) -> Result<String, String> {
+    let csv_trace = match &args.csv {
+        Some(path) => {
+            let csv = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+            Some(trace_from_csv::<Val>(&csv)?)
+        }
+        None => None,
+    };
+    let mut rng = StdRng::seed_from_u64(args.seed.unwrap_or_default());
+
+    match args.air.as_str() {
+        "simple_state" => {
+            let trace = csv_trace.unwrap_or_else(|| random_trace_with_rng(args.log_n, &mut rng));
+            render(&SimpleState {}, 0, trace, args.publics.clone().unwrap_or_default(), args)
+        }
+        "simple_state_checked" => {
+            let (trace, publics) = match csv_trace {
+                Some(trace) => (trace, args.publics.clone().ok_or("--publics is required with --csv")?),
+                None => random_checked_trace_with_rng(args.log_n, &mut rng),
+            };
+            render(&SimpleStateChecked {}, 2, trace, publics, args)
+        }
+        other => Err(format!("unknown air {}, expected simple_state or simple_state_checked", other)),
+    }
+}
+
+fn main() {
+    let args = parse_args().unwrap_or_else(|e| {
+        eprintln!("{}\n{}", e, USAGE);
+        process::exit(2);
+    });
+
+    match run(&args).and_then(|html| fs::write(&args.out, html).map_err(|e| format!("{}: {}", args.out, e))) {
+        Ok(()) => println!("wrote {}", args.out),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use std::collections::BTreeMap;
+
+use p3_air::Air;
+use p3_challenger::CanObserve;
+use p3_commit::Pcs;
+use p3_field::PrimeField32;
+use p3_keccak::Keccak256Hash;
+#[cfg(not(feature = "verifier-only"))]
+use p3_matrix::dense::RowMajorMatrix;
+use p3_symmetric::CryptographicHasher;
+use p3_uni_stark::{
+    verify, Proof, StarkGenericConfig, SymbolicAirBuilder, Val, VerificationError, VerifierConstraintFolder,
+};
+#[cfg(not(feature = "verifier-only"))]
+use p3_uni_stark::{prove, ProverConstraintFolder};
+#[cfg(all(debug_assertions, not(feature = "verifier-only")))]
+use p3_uni_stark::DebugConstraintBuilder;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{CookError, VerifyFailure};
+use crate::hash::bytes_to_field_digest;
+
+// A proof bundle is what gets stored or sent: the proof, its public values, and free-form metadata (block
+// number, timestamp, software version, ...) for whoever consumes it. The metadata is serialized with the
+// bundle but is not part of the proof, so by default anyone can edit it without the proof noticing.
+//
+// An application that needs the metadata to be tamper-evident opts in with `bind_metadata`: the prover and
+// the verifier both observe a digest of it before `prove`/`verify`, so changing any entry changes every
+// challenge and the proof no longer verifies. Both sides have to agree on the flag; it isn't stored in the
+// bundle, where it could be flipped along with the metadata.
+
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct ProofBundle<SC: StarkGenericConfig> {
+    pub proof: Proof<SC>,
+    pub public_values: Vec<Val<SC>>,
+    pub metadata: BTreeMap<String, String>,
+}
+
+impl<SC: StarkGenericConfig> ProofBundle<SC> {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        bincode::serialize(self).expect("bundles always serialize")
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, VerifyFailure> {
+        bincode::deserialize(bytes).map_err(|_| VerifyFailure::ProofShape)
+    }
+}
+
+/// Observes the number of metadata entries and a keccak digest of the entries, each key and value prefixed
+/// with its length as a little-endian u64, in key order.
+pub fn observe_metadata<F, C>(challenger: &mut C, metadata: &BTreeMap<String, String>)
+where
+    F: PrimeField32,
+    C: CanObserve<F>,
+{
+    let prefixed = |s: &String| (s.len() as u64).to_le_bytes().into_iter().chain(s.bytes()).collect::<Vec<_>>();
+    let bytes = metadata.iter().flat_map(|(key, value)| [prefixed(key), prefixed(value)].concat());
+    challenger.observe(F::from_canonical_usize(metadata.len()));
+    challenger.observe_slice(&bytes_to_field_digest::<F>(Keccak256Hash {}.hash_iter(bytes)));
+}
+
+/// Proves `trace` into a bundle carrying `metadata`, bound into the transcript if `bind_metadata`.
+#[cfg(not(feature = "verifier-only"))]
+pub fn prove_bundle<
+    SC,
+    #[cfg(debug_assertions)] A: for<'a> Air<DebugConstraintBuilder<'a, Val<SC>>>,
+    #[cfg(not(debug_assertions))] A,
+>(
+    config: &SC,
+    air: &A,
+    challenger: &mut SC::Challenger,
+    trace: RowMajorMatrix<Val<SC>>,
+    public_values: Vec<Val<SC>>,
+    metadata: BTreeMap<String, String>,
+    bind_metadata: bool,
+) -> ProofBundle<SC>
+where
+    SC: StarkGenericConfig,
+    Val<SC>: PrimeField32,
+    A: Air<SymbolicAirBuilder<Val<SC>>> + for<'a> Air<ProverConstraintFolder<'a, SC>>,
+{
+    if bind_metadata {
+        observe_metadata(challenger, &metadata);
+    }
+    let proof = prove(config, air, challenger, trace, &public_values);
+    ProofBundle { proof, public_values, metadata }
+}
+
+/// Verifies a bundle, with its metadata bound if `bind_metadata`, mirroring `prove_bundle`.
+pub fn verify_bundle<SC, A>(
+    config: &SC,
+    air: &A,
+    challenger: &mut SC::Challenger,
+    bundle: &ProofBundle<SC>,
+    bind_metadata: bool,
+) -> Result<(), CookError>
+where
+    SC: StarkGenericConfig,
+    Val<SC>: PrimeField32,
+    A: Air<SymbolicAirBuilder<Val<SC>>> + for<'a> Air<VerifierConstraintFolder<'a, SC>>,
+    VerifyFailure: From<VerificationError<<SC::Pcs as Pcs<SC::Challenge, SC::Challenger>>::Error>>,
+{
+    if bind_metadata {
+        observe_metadata(challenger, &bundle.metadata);
+    }
+    verify(config, air, challenger, &bundle.proof, &bundle.public_values)
+        .map_err(|e| CookError::Verification(e.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_challenger::CanSample;
+
+    use super::*;
+    use crate::config::{default_config, random_perm, Challenger, MyConfig, Perm, Val};
+    use crate::simple_state::{random_checked_trace, SimpleStateChecked};
+
+    fn metadata() -> BTreeMap<String, String> {
+        [("block", "19000000"), ("timestamp", "2024-06-01T12:00:00Z"), ("version", env!("CARGO_PKG_VERSION"))]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn bundle(perm: &Perm, bind_metadata: bool) -> ProofBundle<MyConfig> {
+        let (trace, public_values) = random_checked_trace::<Val>(6);
+        let mut challenger = Challenger::new(perm.clone());
+        let config = default_config(perm);
+        let air = SimpleStateChecked {};
+        prove_bundle(&config, &air, &mut challenger, trace, public_values, metadata(), bind_metadata)
+    }
+
+    fn verifies(perm: &Perm, bundle: &ProofBundle<MyConfig>, bind_metadata: bool) -> bool {
+        let mut challenger = Challenger::new(perm.clone());
+        verify_bundle(&default_config(perm), &SimpleStateChecked {}, &mut challenger, bundle, bind_metadata).is_ok()
+    }
+
+    #[test]
+    fn test_metadata_round_trip() {
+        let perm = random_perm();
+        let bundle = bundle(&perm, false);
+
+        let decoded = ProofBundle::<MyConfig>::from_bytes(&bundle.to_bytes()).unwrap();
+        assert_eq!(decoded.metadata, metadata());
+        assert_eq!(decoded.public_values, bundle.public_values);
+        assert!(verifies(&perm, &decoded, false));
+
+        assert!(ProofBundle::<MyConfig>::from_bytes(&bundle.to_bytes()[..100]).is_err());
+    }
+
+    #[test]
+    fn test_metadata_binding_is_opt_in() {
+        let perm = random_perm();
+
+        let mut unbound = bundle(&perm, false);
+        unbound.metadata.insert("block".to_string(), "1".to_string());
+        assert!(verifies(&perm, &unbound, false));
+
+        let mut bound = bundle(&perm, true);
+        assert!(verifies(&perm, &bound, true));
+        // the verifier has to opt in too
+        assert!(!verifies(&perm, &bound, false));
+        bound.metadata.insert("block".to_string(), "1".to_string());
+        assert!(!verifies(&perm, &bound, true));
+    }
+
+    #[test]
+    fn test_metadata_entries_are_unambiguous() {
+        // the same bytes split differently into a key and a value, which NUL terminators could not tell apart
+        let perm = random_perm();
+        let observed = |key: &str, value: &str| {
+            let mut challenger = Challenger::new(perm.clone());
+            observe_metadata(&mut challenger, &BTreeMap::from([(key.to_string(), value.to_string())]));
+            CanSample::<Val>::sample(&mut challenger)
+        };
+        assert_ne!(observed("a\0b", "c"), observed("a", "b\0c"));
+    }
+}
@@ -0,0 +1,121 @@
+use crate::simple_state::{SimpleState, SimpleStateChecked, StateColumns, BALANCE_BITS};
+
+// Row structs borrowed from `[F]` (`SimStateRow` and the like) rely on `#[repr(Rust)]` laying fields out in
+// declaration order, which the compiler doesn't promise; the `debug_assert`s only catch a size mismatch.
+// `columns!` declares a view of a row instead: a transparent wrapper around the slice with a getter and a
+// setter per column, reading fixed offsets, and the names in trace order as `Columns::NAMES`:
+//   columns! { pub struct StateColumns { balance / set_balance, input / set_input, output / set_output } }
+//   let local = StateColumns::view(&local);     // in `eval`: `local.balance()`
+//   StateColumns::view_mut(row).set_input(x);   // in a generator
+// Each entry names the getter and the setter; `macro_rules!` can't build `set_balance` out of `balance`.
+
+/// Human-readable column names, in trace order, for tools that display traces.
+pub trait NamedColumns {
+    fn column_names(&self) -> Vec<String>;
+}
+
+/// A row view declared with `columns!`: one named getter and setter per column.
+pub trait Columns {
+    /// the column names, in trace order
+    const NAMES: &'static [&'static str];
+    const WIDTH: usize = Self::NAMES.len();
+}
+
+/// Declares a `Columns` view of a trace row; see the module comment.
+#[macro_export]
+macro_rules! columns {
+    ($(#[$meta:meta])* $vis:vis struct $name:ident { $($col:ident / $set:ident),+ $(,)? }) => {
+        $(#[$meta])*
+        #[repr(transparent)]
+        $vis struct $name<T>([T]);
+
+        impl<T> $name<T> {
+            pub fn view(row: &[T]) -> &Self {
+                debug_assert_eq!(row.len(), <Self as $crate::columns::Columns>::WIDTH);
+                // SAFETY: `Self` is a transparent wrapper around `[T]`
+                unsafe { &*(row as *const [T] as *const Self) }
+            }
+
+            pub fn view_mut(row: &mut [T]) -> &mut Self {
+                debug_assert_eq!(row.len(), <Self as $crate::columns::Columns>::WIDTH);
+                // SAFETY: as in `view`
+                unsafe { &mut *(row as *mut [T] as *mut Self) }
+            }
+        }
+
+        impl<T> $crate::columns::Columns for $name<T> {
+            const NAMES: &'static [&'static str] = &[$(stringify!($col)),+];
+        }
+
+        $crate::columns!(@accessors $name; 0usize; $($col / $set),+);
+    };
+    (@accessors $name:ident; $idx:expr; $col:ident / $set:ident $(, $rest:ident / $rest_set:ident)*) => {
+        impl<T: Copy> $name<T> {
+            pub fn $col(&self) -> T {
+                self.0[$idx]
+            }
+
+            pub fn $set(&mut self, value: T) {
+                self.0[$idx] = value;
+            }
+        }
+
+        $crate::columns!(@accessors $name; $idx + 1; $($rest / $rest_set),*);
+    };
+    (@accessors $name:ident; $idx:expr;) => {};
+}
+
+impl NamedColumns for SimpleState {
+    fn column_names(&self) -> Vec<String> {
+        <StateColumns<u8> as Columns>::NAMES.iter().map(|&name| name.to_string()).collect()
+    }
+}
+
+impl NamedColumns for SimpleStateChecked {
+    fn column_names(&self) -> Vec<String> {
+        let mut names = SimpleState {}.column_names();
+        names.extend((0..BALANCE_BITS).map(|i| format!("new_balance_bit{}", i)));
+        names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_field::AbstractField;
+    use p3_matrix::dense::RowMajorMatrix;
+    use p3_matrix::Matrix;
+
+    use super::*;
+    use crate::config::Val;
+    use crate::simple_state::{random_trace, random_trace_with_fault, SS_ROW_WIDTH};
+    use crate::{assert_constraints_fail, assert_constraints_ok};
+
+    #[test]
+    fn test_names_and_width() {
+        assert_eq!(SimpleState {}.column_names(), ["balance", "input", "output"]);
+        assert_eq!(<StateColumns<Val> as Columns>::WIDTH, SS_ROW_WIDTH);
+    }
+
+    #[test]
+    fn test_getters_read_simple_state_traces() {
+        // `SimpleState` reads its columns through `StateColumns`
+        assert_constraints_ok!(&SimpleState {}, &random_trace::<Val>(4), &[]);
+        assert_constraints_fail!(&SimpleState {}, &random_trace_with_fault::<Val>(4, 6), &[], 6);
+    }
+
+    #[test]
+    fn test_setters_write_their_column() {
+        let mut trace = RowMajorMatrix::new(vec![Val::zero(); 4 * SS_ROW_WIDTH], SS_ROW_WIDTH);
+        let mut balance = Val::from_canonical_u32(100);
+        for row in trace.values.chunks_exact_mut(SS_ROW_WIDTH) {
+            let row = StateColumns::view_mut(row);
+            row.set_balance(balance);
+            row.set_input(Val::from_canonical_u32(7));
+            row.set_output(Val::from_canonical_u32(2));
+            balance += Val::from_canonical_u32(5);
+        }
+
+        assert_eq!(trace.row_slice(1).to_vec(), [105, 7, 2].map(Val::from_canonical_u32));
+        assert_constraints_ok!(&SimpleState {}, &trace, &[]);
+    }
+}
@@ -0,0 +1,411 @@
+use p3_baby_bear::{BabyBear, DiffusionMatrixBabyBear};
+use p3_challenger::DuplexChallenger;
+use p3_commit::ExtensionMmcs;
+use p3_dft::Radix2DitParallel;
+use p3_field::{extension::BinomialExtensionField, AbstractExtensionField, Field, PrimeField32};
+use p3_fri::{FriConfig, TwoAdicFriPcs};
+use p3_keccak::Keccak256Hash;
+use p3_merkle_tree::FieldMerkleTreeMmcs;
+use p3_poseidon2::{Poseidon2, Poseidon2ExternalMatrixGeneral};
+use p3_symmetric::{CryptographicHasher, PaddingFreeSponge, SerializingHasher32, TruncatedPermutation};
+use p3_uni_stark::StarkConfig;
+use rand::{rngs::StdRng, thread_rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+
+use crate::hash::FieldDigestHasher;
+
+// the BabyBear + Poseidon2 setup used by `examples/simple_state.rs`, shared by all the examples
+//
+// The leaf hash is a type parameter of the MMCS, the PCS and the config (`*With<H>`). The default hashes
+// rows with the width-16 sponge (rate 8); `WideHash` uses the width-24 permutation (rate 16), absorbing twice
+// as many elements per call, which matters for wide traces. Both produce 8-element digests, so the width-16
+// compression function and challenger are shared.
+//
+// The hybrid config hashes rows with Keccak256 instead, packs the 32-byte digest into 8 field elements, and
+// compresses internal nodes with Poseidon2. Leaf hashing stays fast natively on wide traces, while the
+// Merkle paths (the part a recursive verifier walks) are still arithmetic-friendly. Its tree works on
+// unpacked values because the Keccak leaf hash has no packed implementation.
+
+pub type Val = BabyBear;
+pub type Challenge = BinomialExtensionField<Val, 4>;
+
+pub type Perm = Poseidon2<Val, Poseidon2ExternalMatrixGeneral, DiffusionMatrixBabyBear, 16, 7>;
+pub type MyHash = PaddingFreeSponge<Perm, 16, 8, 8>;
+pub type MyCompress = TruncatedPermutation<Perm, 2, 8, 16>;
+
+pub type Perm24 = Poseidon2<Val, Poseidon2ExternalMatrixGeneral, DiffusionMatrixBabyBear, 24, 7>;
+pub type WideHash = PaddingFreeSponge<Perm24, 24, 16, 8>;
+
+pub type ValMmcsWith<H> = FieldMerkleTreeMmcs<
+    <Val as Field>::Packing,
+    <Val as Field>::Packing,
+    H,
+    MyCompress,
+    8,
+>;
+pub type ChallengeMmcsWith<H> = ExtensionMmcs<Val, Challenge, ValMmcsWith<H>>;
+
+pub type Dft = Radix2DitParallel;
+pub type Challenger = DuplexChallenger<Val, Perm, 16, 8>;
+pub type PcsWith<H> = TwoAdicFriPcs<Val, Dft, ValMmcsWith<H>, ChallengeMmcsWith<H>>;
+pub type ConfigWith<H> = StarkConfig<PcsWith<H>, Challenge, Challenger>;
+
+pub type ValMmcs = ValMmcsWith<MyHash>;
+pub type ChallengeMmcs = ChallengeMmcsWith<MyHash>;
+pub type Pcs = PcsWith<MyHash>;
+pub type MyConfig = ConfigWith<MyHash>;
+
+pub type WideConfig = ConfigWith<WideHash>;
+
+pub type KeccakLeafHash = FieldDigestHasher<SerializingHasher32<Keccak256Hash>>;
+pub type HybridMmcs = FieldMerkleTreeMmcs<Val, Val, KeccakLeafHash, MyCompress, 8>;
+pub type HybridChallengeMmcs = ExtensionMmcs<Val, Challenge, HybridMmcs>;
+pub type HybridPcs = TwoAdicFriPcs<Val, Dft, HybridMmcs, HybridChallengeMmcs>;
+pub type HybridConfig = StarkConfig<HybridPcs, Challenge, Challenger>;
+
+pub const DEFAULT_LOG_BLOWUP: usize = 2;
+pub const DEFAULT_NUM_QUERIES: usize = 40;
+pub const DEFAULT_POW_BITS: usize = 8;
+
+/// The FRI parameters a config is built from; the built config keeps them private.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FriParams {
+    pub log_blowup: usize,
+    pub num_queries: usize,
+    pub proof_of_work_bits: usize,
+}
+
+impl Default for FriParams {
+    fn default() -> Self {
+        FriParams {
+            log_blowup: DEFAULT_LOG_BLOWUP,
+            num_queries: DEFAULT_NUM_QUERIES,
+            proof_of_work_bits: DEFAULT_POW_BITS,
+        }
+    }
+}
+
+impl FriParams {
+    /// The parameters with the fewest queries reaching `target_bits` of conjectured security over this
+    /// crate's challenge field; see `min_queries_for_bits`, whose panics this shares.
+    pub fn for_security_bits(target_bits: usize, log_blowup: usize, proof_of_work_bits: usize) -> Self {
+        let ext_degree = <Challenge as AbstractExtensionField<Val>>::D;
+        FriParams {
+            log_blowup,
+            num_queries: min_queries_for_bits(target_bits, log_blowup, proof_of_work_bits, ext_degree),
+            proof_of_work_bits,
+        }
+    }
+
+    pub fn config(&self, perm: &Perm) -> MyConfig {
+        make_config(perm, self.log_blowup, self.num_queries, self.proof_of_work_bits)
+    }
+
+    /// Estimated security in bits, `-log2` of the soundness error, under the conjecture the ethSTARK paper
+    /// and most deployments rely on: each query catches a cheating prover except with probability
+    /// `2^-log_blowup`, and the proof-of-work adds its bits on top.
+    ///
+    /// This ignores the hash's collision resistance and the Fiat-Shamir losses, and caps the result at the
+    /// size of the challenge field, which bounds the out-of-domain and folding challenges.
+    pub fn conjectured_security_bits(&self) -> f64 {
+        let bits = (self.num_queries * self.log_blowup + self.proof_of_work_bits) as f64;
+        bits.min(challenge_field_bits())
+    }
+
+    /// Like `conjectured_security_bits`, but in the regime the proven bounds cover (up to the Johnson
+    /// bound), where a query only catches a cheating prover except with probability `2^(-log_blowup / 2)`.
+    pub fn proven_security_bits(&self) -> f64 {
+        let bits = self.num_queries as f64 * self.log_blowup as f64 / 2.0 + self.proof_of_work_bits as f64;
+        bits.min(challenge_field_bits())
+    }
+}
+
+/// `log2` of the challenge field's size.
+pub fn challenge_field_bits() -> f64 {
+    (Val::ORDER_U32 as f64).log2() * <Challenge as AbstractExtensionField<Val>>::D as f64
+}
+
+/// The fewest FRI queries reaching `target_bits` of conjectured security (see
+/// `FriParams::conjectured_security_bits`) with a challenge field of degree `ext_degree` over BabyBear.
+///
+/// At least one query is always needed, even when the proof-of-work alone covers the target. Panics if
+/// the target is beyond the challenge field, which no number of queries can make up for.
+pub fn min_queries_for_bits(target_bits: usize, log_blowup: usize, pow_bits: usize, ext_degree: usize) -> usize {
+    assert!(log_blowup > 0, "a blowup of 1 gives queries no soundness");
+    let field_bits = (Val::ORDER_U32 as f64).log2() * ext_degree as f64;
+    assert!(
+        target_bits as f64 <= field_bits,
+        "{} bits is beyond a degree-{} challenge field ({:.1} bits)",
+        target_bits,
+        ext_degree,
+        field_bits
+    );
+    target_bits.saturating_sub(pow_bits).div_ceil(log_blowup).max(1)
+}
+
+/// One field/PCS combination the crate has a config for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BackendInfo {
+    pub field: &'static str,
+    /// degree of the challenge field over `field`
+    pub extension_degree: usize,
+    pub pcs: &'static str,
+    /// the function building the config
+    pub constructor: &'static str,
+    pub description: &'static str,
+}
+
+/// Every field/PCS combination with a config in this module, the default first.
+pub fn supported_backends() -> Vec<BackendInfo> {
+    let field = "BabyBear";
+    let extension_degree = <Challenge as AbstractExtensionField<Val>>::D;
+    vec![
+        BackendInfo {
+            field,
+            extension_degree,
+            pcs: "FRI",
+            constructor: "default_config",
+            description: "two-adic FRI over Poseidon2 Merkle trees, width-16 leaf hash",
+        },
+        BackendInfo {
+            field,
+            extension_degree,
+            pcs: "FRI",
+            constructor: "wide_config",
+            description: "two-adic FRI over Poseidon2 Merkle trees, width-24 leaf hash for wide traces",
+        },
+        BackendInfo {
+            field,
+            extension_degree,
+            pcs: "FRI",
+            constructor: "hybrid_babybear_config",
+            description: "two-adic FRI with Keccak256 leaf hashing and Poseidon2 compression",
+        },
+    ]
+}
+
+/// Poseidon2 permutation with random round constants.
+pub fn random_perm() -> Perm {
+    Perm::new_from_rng_128(
+        Poseidon2ExternalMatrixGeneral,
+        DiffusionMatrixBabyBear::default(),
+        &mut thread_rng(),
+    )
+}
+
+/// Poseidon2 permutation with round constants drawn from a seeded rng, so that separate processes (or a
+/// prover and a foreign verifier) can agree on it.
+pub fn perm_from_seed(seed: u64) -> Perm {
+    Perm::new_from_rng_128(
+        Poseidon2ExternalMatrixGeneral,
+        DiffusionMatrixBabyBear::default(),
+        &mut StdRng::seed_from_u64(seed),
+    )
+}
+
+/// Poseidon2 permutation with round constants drawn from ChaCha20 seeded with the Keccak-256 digest of
+/// `domain`, so a parameter set can be named (`"my-protocol/v1"`) instead of shipped as constants.
+///
+/// ChaCha20 is named rather than `StdRng`, whose algorithm `rand` may change in any release; the constants
+/// for a domain must never change.
+pub fn perm_from_domain(domain: &str) -> Perm {
+    let seed = Keccak256Hash {}.hash_iter(domain.bytes());
+    Perm::new_from_rng_128(
+        Poseidon2ExternalMatrixGeneral,
+        DiffusionMatrixBabyBear::default(),
+        &mut ChaCha20Rng::from_seed(seed),
+    )
+}
+
+/// Width-24 Poseidon2 permutation with random round constants.
+pub fn random_perm24() -> Perm24 {
+    Perm24::new_from_rng_128(
+        Poseidon2ExternalMatrixGeneral,
+        DiffusionMatrixBabyBear::default(),
+        &mut thread_rng(),
+    )
+}
+
+/// Builds the PCS hashing leaves with `hash` and compressing with `perm`.
+pub fn make_pcs_with<H: Clone>(
+    hash: H,
+    perm: &Perm,
+    log_blowup: usize,
+    num_queries: usize,
+    proof_of_work_bits: usize,
+) -> PcsWith<H> {
+    let compress = MyCompress::new(perm.clone());
+    let val_mmcs = ValMmcsWith::<H>::new(hash, compress);
+    let challenge_mmcs = ChallengeMmcsWith::<H>::new(val_mmcs.clone());
+
+    let fri_config = FriConfig {
+        log_blowup,
+        num_queries,
+        proof_of_work_bits,
+        mmcs: challenge_mmcs,
+    };
+    PcsWith::<H>::new(Dft {}, val_mmcs, fri_config)
+}
+
+/// Builds a stark config hashing leaves with `hash` and compressing with `perm`.
+pub fn make_config_with<H: Clone>(
+    hash: H,
+    perm: &Perm,
+    log_blowup: usize,
+    num_queries: usize,
+    proof_of_work_bits: usize,
+) -> ConfigWith<H> {
+    ConfigWith::<H>::new(make_pcs_with(hash, perm, log_blowup, num_queries, proof_of_work_bits))
+}
+
+/// Builds the stark config with explicit FRI parameters.
+pub fn make_config(perm: &Perm, log_blowup: usize, num_queries: usize, proof_of_work_bits: usize) -> MyConfig {
+    make_config_with(MyHash::new(perm.clone()), perm, log_blowup, num_queries, proof_of_work_bits)
+}
+
+/// Builds the stark config with the crate's default FRI parameters.
+pub fn default_config(perm: &Perm) -> MyConfig {
+    make_config(perm, DEFAULT_LOG_BLOWUP, DEFAULT_NUM_QUERIES, DEFAULT_POW_BITS)
+}
+
+/// Builds the width-24 leaf-hash config with the crate's default FRI parameters.
+pub fn wide_config(perm: &Perm, perm24: &Perm24) -> WideConfig {
+    make_config_with(
+        WideHash::new(perm24.clone()),
+        perm,
+        DEFAULT_LOG_BLOWUP,
+        DEFAULT_NUM_QUERIES,
+        DEFAULT_POW_BITS,
+    )
+}
+
+/// Builds the Keccak-leaf, Poseidon2-compression config with explicit FRI parameters.
+pub fn make_hybrid_config(
+    perm: &Perm,
+    log_blowup: usize,
+    num_queries: usize,
+    proof_of_work_bits: usize,
+) -> HybridConfig {
+    let hash = KeccakLeafHash::new(SerializingHasher32::new(Keccak256Hash {}));
+    let compress = MyCompress::new(perm.clone());
+    let val_mmcs = HybridMmcs::new(hash, compress);
+    let challenge_mmcs = HybridChallengeMmcs::new(val_mmcs.clone());
+
+    let fri_config = FriConfig {
+        log_blowup,
+        num_queries,
+        proof_of_work_bits,
+        mmcs: challenge_mmcs,
+    };
+    let pcs = HybridPcs::new(Dft {}, val_mmcs, fri_config);
+
+    HybridConfig::new(pcs)
+}
+
+/// Builds the Keccak-leaf, Poseidon2-compression config with the crate's default FRI parameters.
+pub fn hybrid_babybear_config(perm: &Perm) -> HybridConfig {
+    make_hybrid_config(perm, DEFAULT_LOG_BLOWUP, DEFAULT_NUM_QUERIES, DEFAULT_POW_BITS)
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_field::AbstractField;
+    use p3_symmetric::Permutation;
+    use p3_uni_stark::{prove, verify};
+
+    use super::*;
+    use crate::determinism::{assert_same_proof_bytes, proof_bytes, prove_deterministic};
+    use crate::simple_state::{random_trace, random_trace_with_rng, SimpleState};
+
+    #[test]
+    fn test_security_estimates() {
+        let params = |num_queries| FriParams { num_queries, ..FriParams::default() };
+        assert_eq!(params(10).conjectured_security_bits(), 28.0);
+        assert_eq!(params(DEFAULT_NUM_QUERIES).conjectured_security_bits(), 88.0);
+        assert_eq!(params(DEFAULT_NUM_QUERIES).proven_security_bits(), 48.0);
+        // capped by the ~124-bit challenge field
+        assert_eq!(params(100).conjectured_security_bits(), challenge_field_bits());
+        assert!((challenge_field_bits() - 123.6).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_min_queries_for_bits() {
+        assert_eq!(min_queries_for_bits(88, DEFAULT_LOG_BLOWUP, DEFAULT_POW_BITS, 4), DEFAULT_NUM_QUERIES);
+        assert_eq!(min_queries_for_bits(100, 2, 8, 4), 46);
+        assert_eq!(min_queries_for_bits(100, 3, 16, 4), 28);
+        assert_eq!(min_queries_for_bits(100, 1, 0, 4), 100);
+        assert_eq!(min_queries_for_bits(16, 2, 16, 4), 1);
+
+        // the smallest count: one query fewer falls short
+        for target in [60, 80, 100, 120] {
+            let num_queries = min_queries_for_bits(target, 2, 8, 4);
+            let params = |num_queries| FriParams { num_queries, log_blowup: 2, proof_of_work_bits: 8 };
+            assert!(params(num_queries).conjectured_security_bits() >= target as f64);
+            assert!(params(num_queries - 1).conjectured_security_bits() < target as f64);
+        }
+    }
+
+    #[test]
+    fn test_fri_params_for_security_bits() {
+        let params = FriParams::for_security_bits(88, DEFAULT_LOG_BLOWUP, DEFAULT_POW_BITS);
+        assert_eq!(params, FriParams::default());
+
+        let params = FriParams::for_security_bits(100, 3, 16);
+        assert_eq!(params, FriParams { log_blowup: 3, num_queries: 28, proof_of_work_bits: 16 });
+        assert!(params.conjectured_security_bits() >= 100.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "beyond a degree-2 challenge field")]
+    fn test_min_queries_beyond_challenge_field() {
+        min_queries_for_bits(100, 2, 8, 2);
+    }
+
+    #[test]
+    fn test_same_domain_same_constants_and_proofs() {
+        let input: [Val; 16] = core::array::from_fn(Val::from_canonical_usize);
+        let (a, b) = (perm_from_domain("plonky3-cook/test"), perm_from_domain("plonky3-cook/test"));
+        assert_eq!(a.permute(input), b.permute(input));
+        assert_ne!(a.permute(input), perm_from_domain("plonky3-cook/other").permute(input));
+
+        let proof = |perm: Perm| {
+            let config = default_config(&perm);
+            let trace = random_trace_with_rng::<Val, _>(8, &mut StdRng::seed_from_u64(3));
+            let mut challenger = Challenger::new(perm);
+            proof_bytes(&prove_deterministic(&config, &SimpleState {}, &mut challenger, trace, &vec![]))
+        };
+        assert_same_proof_bytes(&proof(a), &proof(b));
+    }
+
+    #[test]
+    fn test_babybear_fri_is_supported() {
+        let backends = supported_backends();
+        assert!(backends.iter().any(|b| b.field == "BabyBear" && b.pcs == "FRI"));
+        assert_eq!(backends[0].constructor, "default_config");
+        assert_eq!(backends[0].extension_degree, 4);
+    }
+
+    #[test]
+    fn test_wide_config_round_trip() {
+        let perm = random_perm();
+        let config = wide_config(&perm, &random_perm24());
+        let trace = random_trace::<Val>(8);
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &SimpleState {}, &mut p_challenger, trace, &vec![]);
+        let mut v_challenger = Challenger::new(perm);
+        verify(&config, &SimpleState {}, &mut v_challenger, &proof, &vec![]).unwrap();
+    }
+
+    #[test]
+    fn test_hybrid_config_round_trip() {
+        let perm = random_perm();
+        let config = hybrid_babybear_config(&perm);
+        let trace = random_trace::<Val>(8);
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &SimpleState {}, &mut p_challenger, trace, &vec![]);
+        let mut v_challenger = Challenger::new(perm);
+        verify(&config, &SimpleState {}, &mut v_challenger, &proof, &vec![]).unwrap();
+    }
+}
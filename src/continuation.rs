@@ -0,0 +1,292 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_field::{AbstractField, PrimeField32};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+#[cfg(not(feature = "verifier-only"))]
+use p3_uni_stark::prove;
+use p3_uni_stark::{verify, Proof};
+
+use crate::config::{Challenger, MyConfig, Perm, Val};
+use crate::error::CookError;
+use crate::gadgets::less_than::{assert_bit_decomposition, bit_decompose};
+use crate::poseidon2_air::{
+    eval_permutation, generate_permutation, PermutationCols, Poseidon2Constants, PERMUTATION_WIDTH, WIDTH,
+};
+use crate::simple_state::BALANCE_BITS;
+use crate::transaction::{validate_all, Transaction};
+
+// Continuations: a ledger proven one segment at a time, each proof starting from the state the previous one
+// ended in. The state is a balance and a root committing to the history that led to it; every transaction
+// folds itself into the root,
+//   root' = Poseidon2([root, input, output, new_balance, 0, ...])[..8]
+// so two histories ending at the same balance still end at different states. A segment's public values are
+//   [initial_balance, initial_root.., final_balance, final_root..]
+// and a chain of segments is sound when every proof verifies and each segment's initial state is the final
+// state of the one before it, which `verify_chain` checks from a trusted genesis state. Segments are padded
+// to a power of two with empty transactions, which are folded into the root like any other.
+//
+// The rows are `SimpleStateChecked`'s (the balance carried down, the new balance range checked) plus one
+// permutation per row, whose first eight inputs are the previous row's root.
+
+pub const ROOT_LEN: usize = 8;
+pub const NUM_PUBLIC_VALUES: usize = 2 * (1 + ROOT_LEN);
+
+const CONT_ROW_WIDTH: usize = 3 + BALANCE_BITS + PERMUTATION_WIDTH;
+
+/// The state a segment starts from or ends in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LedgerState {
+    pub balance: Val,
+    pub root: [Val; ROOT_LEN],
+}
+
+impl LedgerState {
+    /// The state before any transaction: `balance` and an all-zero root.
+    pub fn genesis(balance: u32) -> Self {
+        LedgerState { balance: Val::from_canonical_u32(balance), root: [Val::zero(); ROOT_LEN] }
+    }
+
+    fn from_public_values(values: &[Val]) -> Self {
+        LedgerState { balance: values[0], root: values[1..1 + ROOT_LEN].try_into().unwrap() }
+    }
+
+    fn to_public_values(self) -> Vec<Val> {
+        [vec![self.balance], self.root.to_vec()].concat()
+    }
+}
+
+pub struct ContinuationAir {
+    pub constants: Poseidon2Constants,
+}
+
+impl<F> BaseAir<F> for ContinuationAir {
+    fn width(&self) -> usize {
+        CONT_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues<F = Val>> Air<AB> for ContinuationAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &ContinuationRow<AB::Var> = (*local).borrow();
+        let next: &ContinuationRow<AB::Var> = (*next).borrow();
+
+        let pis: Vec<AB::Expr> = builder.public_values().iter().map(|&v| v.into()).collect();
+        let (initial, last) = pis.split_at(1 + ROOT_LEN);
+
+        // the balance, as in `SimpleStateChecked`
+        let new_balance = local.balance + local.input - local.output;
+        assert_bit_decomposition(builder, new_balance.clone(), &local.new_balance_bits);
+        builder.when_transition().assert_eq(next.balance, new_balance.clone());
+
+        // the root, folding in each transaction
+        let out = eval_permutation(builder, &self.constants, &local.perm);
+        let inputs = local.perm.inputs;
+        builder.assert_eq(inputs[ROOT_LEN], local.input);
+        builder.assert_eq(inputs[ROOT_LEN + 1], local.output);
+        builder.assert_eq(inputs[ROOT_LEN + 2], new_balance.clone());
+        for &input in &inputs[ROOT_LEN + 3..] {
+            builder.assert_zero(input);
+        }
+        for i in 0..ROOT_LEN {
+            builder.when_transition().assert_eq(next.perm.inputs[i], out[i].clone());
+        }
+
+        // the segment starts and ends in the public states
+        builder.when_first_row().assert_eq(local.balance, initial[0].clone());
+        builder.when_last_row().assert_eq(new_balance, last[0].clone());
+        for i in 0..ROOT_LEN {
+            builder.when_first_row().assert_eq(inputs[i], initial[1 + i].clone());
+            builder.when_last_row().assert_eq(out[i].clone(), last[1 + i].clone());
+        }
+    }
+}
+
+pub struct ContinuationRow<F> {
+    pub balance: F,
+    pub input: F,
+    pub output: F,
+    pub new_balance_bits: [F; BALANCE_BITS],
+    /// `[root, input, output, new_balance, 0, ..., 0]`
+    pub perm: PermutationCols<F>,
+}
+
+impl<F> Borrow<ContinuationRow<F>> for [F] {
+    fn borrow(&self) -> &ContinuationRow<F> {
+        debug_assert_eq!(self.len(), CONT_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<ContinuationRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+/// The trace of `transactions` from `start`, its public values, and the state it ends in.
+pub fn generate_segment(
+    c: &Poseidon2Constants,
+    start: &LedgerState,
+    transactions: &[Transaction],
+) -> Result<(RowMajorMatrix<Val>, Vec<Val>, LedgerState), CookError> {
+    let balances = validate_all(start.balance.as_canonical_u32(), transactions)?;
+    let n = transactions.len().next_power_of_two().max(2);
+    let padding = Transaction::new(0, 0);
+
+    let mut trace = RowMajorMatrix::new(vec![Val::zero(); n * CONT_ROW_WIDTH], CONT_ROW_WIDTH);
+    let (prefix, rows, suffix) = unsafe { trace.values.align_to_mut::<ContinuationRow<Val>>() };
+    assert!(prefix.is_empty(), "Alignment should match");
+    assert!(suffix.is_empty(), "Alignment should match");
+
+    let mut state = *start;
+    for (i, row) in rows.iter_mut().enumerate() {
+        let tx = transactions.get(i).unwrap_or(&padding);
+        let balance = balances[i.min(transactions.len())] as u64;
+        let new_balance = (balance + tx.input as u64 - tx.output as u64) as u32;
+        row.balance = state.balance;
+        row.input = Val::from_canonical_u32(tx.input);
+        row.output = Val::from_canonical_u32(tx.output);
+        row.new_balance_bits.copy_from_slice(&bit_decompose(Val::from_canonical_u32(new_balance), BALANCE_BITS));
+
+        let mut inputs = [Val::zero(); WIDTH];
+        inputs[..ROOT_LEN].copy_from_slice(&state.root);
+        let new_balance = Val::from_canonical_u32(new_balance);
+        inputs[ROOT_LEN..ROOT_LEN + 3].copy_from_slice(&[row.input, row.output, new_balance]);
+        let out = generate_permutation(c, inputs, &mut row.perm);
+        state = LedgerState { balance: new_balance, root: out[..ROOT_LEN].try_into().unwrap() };
+    }
+
+    let public_values = [start.to_public_values(), state.to_public_values()].concat();
+    Ok((trace, public_values, state))
+}
+
+/// One proven segment of a chain.
+pub struct Continuation {
+    pub public_values: Vec<Val>,
+    pub proof: Proof<MyConfig>,
+}
+
+impl Continuation {
+    pub fn initial_state(&self) -> LedgerState {
+        LedgerState::from_public_values(&self.public_values[..1 + ROOT_LEN])
+    }
+
+    pub fn final_state(&self) -> LedgerState {
+        LedgerState::from_public_values(&self.public_values[1 + ROOT_LEN..])
+    }
+}
+
+/// Proves `transactions` from `start`, returning the segment and the state the next one starts from.
+#[cfg(not(feature = "verifier-only"))]
+pub fn prove_segment(
+    config: &MyConfig,
+    perm: &Perm,
+    air: &ContinuationAir,
+    start: &LedgerState,
+    transactions: &[Transaction],
+) -> Result<(Continuation, LedgerState), CookError> {
+    let (trace, public_values, end) = generate_segment(&air.constants, start, transactions)?;
+    let proof = prove(config, air, &mut Challenger::new(perm.clone()), trace, &public_values);
+    Ok((Continuation { public_values, proof }, end))
+}
+
+/// Verifies `segments` in order from `genesis`, returning the state the chain ends in.
+pub fn verify_chain(
+    config: &MyConfig,
+    perm: &Perm,
+    air: &ContinuationAir,
+    genesis: &LedgerState,
+    segments: &[Continuation],
+) -> Result<LedgerState, CookError> {
+    let mut state = *genesis;
+    for (i, segment) in segments.iter().enumerate() {
+        if segment.public_values.len() != NUM_PUBLIC_VALUES {
+            return Err(CookError::PublicValues(format!("segment {} has the wrong number of public values", i)));
+        }
+        if segment.initial_state() != state {
+            return Err(CookError::PublicValues(format!("segment {} doesn't start where the chain left off", i)));
+        }
+        verify(config, air, &mut Challenger::new(perm.clone()), &segment.proof, &segment.public_values)
+            .map_err(|e| CookError::Verification(e.into()))?;
+        state = segment.final_state();
+    }
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{default_config, random_perm};
+    use crate::transaction::LedgerError;
+    use crate::{assert_constraints_fail, assert_constraints_ok};
+
+    const INPUT_COL: usize = 1;
+
+    fn air() -> ContinuationAir {
+        ContinuationAir { constants: Poseidon2Constants::from_seed(0x636f6e74) }
+    }
+
+    fn transactions(seed: u32, n: u32) -> Vec<Transaction> {
+        (0..n).map(|i| Transaction::new(seed + i, (seed + 2 * i) % 7)).collect()
+    }
+
+    #[test]
+    fn test_segment_constraints() {
+        let (mut trace, public_values, _) =
+            generate_segment(&air().constants, &LedgerState::genesis(100), &transactions(3, 6)).unwrap();
+        assert_constraints_ok!(&air(), &trace, &public_values);
+
+        // a transaction the root wasn't folded over
+        trace.row_mut(2)[INPUT_COL] += Val::one();
+        assert_constraints_fail!(&air(), &trace, &public_values, 2);
+    }
+
+    #[test]
+    fn test_amount_outside_the_field_is_rejected() {
+        let txs = [Transaction::new(1, 0), Transaction::new(Val::ORDER_U32, 0)];
+        assert!(matches!(
+            generate_segment(&air().constants, &LedgerState::genesis(100), &txs),
+            Err(CookError::Ledger(LedgerError::NotInField { index: 1, .. }))
+        ));
+    }
+
+    #[test]
+    fn test_chain_of_three() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let air = air();
+        let genesis = LedgerState::genesis(1000);
+
+        let mut state = genesis;
+        let segments = [4, 8, 3]
+            .iter()
+            .map(|&n| {
+                let (segment, end) = prove_segment(&config, &perm, &air, &state, &transactions(n, n)).unwrap();
+                state = end;
+                segment
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(verify_chain(&config, &perm, &air, &genesis, &segments).unwrap(), state);
+    }
+
+    #[test]
+    fn test_broken_link_fails() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let air = air();
+        let genesis = LedgerState::genesis(1000);
+
+        let (first, end) = prove_segment(&config, &perm, &air, &genesis, &transactions(1, 4)).unwrap();
+        // the same balance under another root: a valid proof, but not of what came after `first`
+        let forked = LedgerState { root: [Val::one(); ROOT_LEN], ..end };
+        let (second, _) = prove_segment(&config, &perm, &air, &forked, &transactions(2, 4)).unwrap();
+        verify_chain(&config, &perm, &air, &forked, std::slice::from_ref(&second)).unwrap();
+
+        assert!(matches!(
+            verify_chain(&config, &perm, &air, &genesis, &[first, second]),
+            Err(CookError::PublicValues(_))
+        ));
+    }
+}
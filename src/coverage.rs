@@ -0,0 +1,102 @@
+use p3_air::Air;
+use p3_field::Field;
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use rand::{thread_rng, Rng};
+
+use crate::debug::{failed_constraints, EvalBuilder};
+
+// Mutation coverage: bump a cell by one and see whether any constraint notices. A cell whose mutation goes
+// undetected is one the constraints don't pin down, which is usually a missing constraint (an unconstrained
+// last row, a column that is never range checked, ...).
+//
+// Changing row `r` can only affect the windows starting at `r - 1` and `r`, so only those are re-evaluated.
+
+/// Traces with at most this many cells are mutated exhaustively, larger ones are sampled.
+pub const EXHAUSTIVE_CELLS: usize = 1 << 14;
+pub const SAMPLED_CELLS: usize = 4096;
+
+#[derive(Clone, Debug)]
+pub struct Coverage {
+    pub width: usize,
+    pub mutated: usize,
+    /// `(row, column)` of every mutation no constraint caught
+    pub undetected: Vec<(usize, usize)>,
+}
+
+impl Coverage {
+    pub fn is_complete(&self) -> bool {
+        self.undetected.is_empty()
+    }
+
+    /// Number of undetected mutations in each column.
+    pub fn undetected_per_column(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.width];
+        for &(_, col) in &self.undetected {
+            counts[col] += 1;
+        }
+        counts
+    }
+}
+
+/// Mutates cells of a valid `trace` one at a time and records which mutations `air` fails to reject.
+pub fn mutate_and_check<F, A>(air: &A, trace: &RowMajorMatrix<F>, public_values: &[F]) -> Coverage
+where
+    F: Field,
+    A: for<'a> Air<EvalBuilder<'a, F>>,
+{
+    let (height, width) = (trace.height(), trace.width());
+
+    let cells: Vec<(usize, usize)> = if height * width <= EXHAUSTIVE_CELLS {
+        (0..height).flat_map(|r| (0..width).map(move |c| (r, c))).collect()
+    } else {
+        let mut rng = thread_rng();
+        (0..SAMPLED_CELLS).map(|_| (rng.gen_range(0..height), rng.gen_range(0..width))).collect()
+    };
+
+    let mut mutant = trace.clone();
+    let mut undetected = vec![];
+    for &(r, c) in &cells {
+        let original = mutant.row_mut(r)[c];
+        mutant.row_mut(r)[c] = original + F::one();
+
+        let prev = (r + height - 1) % height;
+        let detected = !failed_constraints(air, &mutant, public_values, prev).is_empty()
+            || !failed_constraints(air, &mutant, public_values, r).is_empty();
+        if !detected {
+            undetected.push((r, c));
+        }
+
+        mutant.row_mut(r)[c] = original;
+    }
+
+    Coverage { width, mutated: cells.len(), undetected }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_baby_bear::BabyBear;
+
+    use super::*;
+    use crate::simple_state::{random_checked_trace, random_trace, SimpleState, SimpleStateChecked};
+
+    #[test]
+    fn test_original_simple_state_has_gaps() {
+        let trace = random_trace::<BabyBear>(6);
+        let coverage = mutate_and_check(&SimpleState {}, &trace, &[]);
+
+        // nothing constrains the input and output of the last row
+        let last = trace.height() - 1;
+        assert!(coverage.undetected.contains(&(last, 1)));
+        assert!(coverage.undetected.contains(&(last, 2)));
+    }
+
+    #[test]
+    fn test_checked_simple_state_is_fully_covered() {
+        let (trace, public_values) = random_checked_trace::<BabyBear>(6);
+        let coverage = mutate_and_check(&SimpleStateChecked {}, &trace, &public_values);
+
+        assert_eq!(coverage.mutated, trace.height() * trace.width());
+        assert!(coverage.is_complete(), "undetected mutations: {:?}", coverage.undetected);
+    }
+}
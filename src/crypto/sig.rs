@@ -0,0 +1,128 @@
+use p3_field::PrimeField32;
+use p3_symmetric::CryptographicHasher;
+use rand::Rng;
+
+use crate::config::{MyHash, Perm, Val};
+
+// One-time signatures whose verification is only hashing, so it is cheap to prove in an AIR.
+//
+// `PoseidonLamport` is Lamport's scheme with the crate's width-16 Poseidon2 sponge (`MyHash`) as the one-way
+// function. The secret key is two random 8-element chunks per message bit, the public key their hashes:
+//   sk = [s(0, 0), s(0, 1), s(1, 0), s(1, 1), ...]    pk = [H(s(0, 0)), H(s(0, 1)), ...]
+// To sign, hash the message and reveal `s(i, b_i)` for each of the first `message_bits` bits `b_i` of the
+// digest (31 bits per digest element, least significant first); to verify, hash every revealed chunk and
+// compare with `H(s(i, b_i))` in the public key.
+//
+// A key signs one message: a second signature reveals the other half of the key wherever the two digests
+// differ. The scheme is as strong as its message bits, so toy parameters (`examples/signature_verify.rs`
+// signs 15 bits to keep its trace small) are forgeable by brute force.
+
+pub const CHUNK: usize = 8;
+
+/// Bits of one digest element, the canonical value being below `2^31`.
+const BITS_PER_ELEMENT: usize = 31;
+
+pub trait SignatureScheme<F> {
+    fn sign(&self, sk: &[F], msg: &[F]) -> Vec<F>;
+
+    fn verify(&self, pk: &[F], msg: &[F], sig: &[F]) -> bool;
+}
+
+/// Lamport signatures over BabyBear with Poseidon2 as the one-way function.
+#[derive(Clone)]
+pub struct PoseidonLamport {
+    hash: MyHash,
+    pub message_bits: usize,
+}
+
+impl PoseidonLamport {
+    pub fn new(perm: Perm, message_bits: usize) -> Self {
+        assert!(message_bits <= CHUNK * BITS_PER_ELEMENT, "a digest has {} bits", CHUNK * BITS_PER_ELEMENT);
+        PoseidonLamport { hash: MyHash::new(perm), message_bits }
+    }
+
+    /// A fresh `(sk, pk)` pair.
+    pub fn keygen<R: Rng>(&self, rng: &mut R) -> (Vec<Val>, Vec<Val>) {
+        let sk = (0..2 * self.message_bits * CHUNK).map(|_| rng.gen()).collect::<Vec<Val>>();
+        let pk = self.public_key(&sk);
+        (sk, pk)
+    }
+
+    pub fn public_key(&self, sk: &[Val]) -> Vec<Val> {
+        sk.chunks_exact(CHUNK).flat_map(|chunk| self.hash_chunk(chunk)).collect()
+    }
+
+    /// The `H` of the scheme: one permutation of `[chunk, 0, ..., 0]`.
+    pub fn hash_chunk(&self, chunk: &[Val]) -> [Val; CHUNK] {
+        self.hash.hash_slice(chunk)
+    }
+
+    /// The digest bits `msg` is signed under.
+    pub fn message_bits(&self, msg: &[Val]) -> Vec<bool> {
+        let digest = self.hash.hash_slice(msg);
+        (0..self.message_bits)
+            .map(|i| (digest[i / BITS_PER_ELEMENT].as_canonical_u32() >> (i % BITS_PER_ELEMENT)) & 1 == 1)
+            .collect()
+    }
+
+    fn key_len(&self) -> usize {
+        2 * self.message_bits * CHUNK
+    }
+}
+
+impl SignatureScheme<Val> for PoseidonLamport {
+    fn sign(&self, sk: &[Val], msg: &[Val]) -> Vec<Val> {
+        assert_eq!(sk.len(), self.key_len(), "wrong secret key length");
+        self.message_bits(msg)
+            .into_iter()
+            .enumerate()
+            .flat_map(|(i, bit)| {
+                let start = (2 * i + bit as usize) * CHUNK;
+                sk[start..start + CHUNK].to_vec()
+            })
+            .collect()
+    }
+
+    fn verify(&self, pk: &[Val], msg: &[Val], sig: &[Val]) -> bool {
+        if pk.len() != self.key_len() || sig.len() != self.message_bits * CHUNK {
+            return false;
+        }
+        self.message_bits(msg).into_iter().zip(sig.chunks_exact(CHUNK)).enumerate().all(|(i, (bit, revealed))| {
+            let start = (2 * i + bit as usize) * CHUNK;
+            self.hash_chunk(revealed)[..] == pk[start..start + CHUNK]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_field::AbstractField;
+    use rand::thread_rng;
+
+    use super::*;
+    use crate::config::random_perm;
+
+    fn message(seed: u32) -> Vec<Val> {
+        (0..CHUNK as u32).map(|i| Val::from_canonical_u32(seed + i)).collect()
+    }
+
+    #[test]
+    fn test_sign_verify() {
+        let scheme = PoseidonLamport::new(random_perm(), 64);
+        let (sk, pk) = scheme.keygen(&mut thread_rng());
+        let sig = scheme.sign(&sk, &message(1));
+        assert!(scheme.verify(&pk, &message(1), &sig));
+    }
+
+    #[test]
+    fn test_wrong_message_or_signature_fails() {
+        let scheme = PoseidonLamport::new(random_perm(), 64);
+        let (sk, pk) = scheme.keygen(&mut thread_rng());
+        let mut sig = scheme.sign(&sk, &message(1));
+        assert!(!scheme.verify(&pk, &message(2), &sig));
+
+        sig[CHUNK * 3 + 2] += Val::one();
+        assert!(!scheme.verify(&pk, &message(1), &sig));
+        assert!(!scheme.verify(&pk, &message(1), &sig[..CHUNK]));
+    }
+}
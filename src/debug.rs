@@ -0,0 +1,334 @@
+use std::fmt::{self, Display};
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues};
+use p3_field::Field;
+use p3_matrix::Matrix;
+use p3_matrix::dense::{RowMajorMatrix, RowMajorMatrixView};
+use p3_matrix::stack::VerticalPair;
+
+// A native constraint checker: evaluates the AIR on concrete rows instead of proving, so tests of a
+// constraint run in milliseconds and report which row broke rather than just "verification failed".
+//
+// `EvalBuilder` is the one concrete builder underneath: it evaluates every asserted expression on a
+// `(local, next)` window and keeps the values, the residuals, in the order `eval` asserts them. A residual
+// of zero is a constraint that holds; everything else here reads the residuals.
+
+/// The row selectors of a window.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Selectors<F> {
+    pub is_first_row: F,
+    pub is_last_row: F,
+    pub is_transition: F,
+}
+
+impl<F: Field> Selectors<F> {
+    /// The selectors of the window starting at row `i` of a trace of `height` rows.
+    pub fn for_row(i: usize, height: usize) -> Self {
+        Selectors {
+            is_first_row: F::from_bool(i == 0),
+            is_last_row: F::from_bool(i == height - 1),
+            is_transition: F::from_bool(i != height - 1),
+        }
+    }
+
+    /// A window in the middle of the trace: a transition, neither first nor last.
+    pub fn interior() -> Self {
+        Selectors { is_first_row: F::zero(), is_last_row: F::zero(), is_transition: F::one() }
+    }
+}
+
+/// Evaluates constraints on one `(local, next)` window, recording the value of each.
+pub struct EvalBuilder<'a, F: Field> {
+    main: VerticalPair<RowMajorMatrixView<'a, F>, RowMajorMatrixView<'a, F>>,
+    public_values: &'a [F],
+    selectors: Selectors<F>,
+    residuals: Vec<F>,
+}
+
+impl<'a, F: Field> EvalBuilder<'a, F> {
+    pub fn new(local: &'a [F], next: &'a [F], public_values: &'a [F], selectors: Selectors<F>) -> Self {
+        EvalBuilder {
+            main: VerticalPair::new(RowMajorMatrixView::new_row(local), RowMajorMatrixView::new_row(next)),
+            public_values,
+            selectors,
+            residuals: vec![],
+        }
+    }
+
+    /// The value of every constraint asserted so far.
+    pub fn residuals(&self) -> &[F] {
+        &self.residuals
+    }
+
+    pub fn into_residuals(self) -> Vec<F> {
+        self.residuals
+    }
+}
+
+impl<'a, F: Field> AirBuilder for EvalBuilder<'a, F> {
+    type F = F;
+    type Expr = F;
+    type Var = F;
+    type M = VerticalPair<RowMajorMatrixView<'a, F>, RowMajorMatrixView<'a, F>>;
+
+    fn main(&self) -> Self::M {
+        self.main
+    }
+
+    fn is_first_row(&self) -> Self::Expr {
+        self.selectors.is_first_row
+    }
+
+    fn is_last_row(&self) -> Self::Expr {
+        self.selectors.is_last_row
+    }
+
+    fn is_transition_window(&self, size: usize) -> Self::Expr {
+        if size == 2 {
+            self.selectors.is_transition
+        } else {
+            panic!("uni-stark only supports a window size of 2")
+        }
+    }
+
+    fn assert_zero<I: Into<Self::Expr>>(&mut self, x: I) {
+        self.residuals.push(x.into());
+    }
+}
+
+impl<'a, F: Field> AirBuilderWithPublicValues for EvalBuilder<'a, F> {
+    type PublicVar = F;
+
+    fn public_values(&self) -> &[Self::PublicVar] {
+        self.public_values
+    }
+}
+
+/// A row whose window violated at least one constraint.
+#[derive(Clone, Debug)]
+pub struct ConstraintFailure<F> {
+    pub row: usize,
+    /// indices of the failed constraints, in the order `eval` asserts them
+    pub constraints: Vec<usize>,
+    pub local: Vec<F>,
+    pub next: Vec<F>,
+}
+
+impl<F: Display> Display for ConstraintFailure<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "row {}: constraints {:?} failed", self.row, self.constraints)?;
+        writeln!(f, "  local: {}", display_row(&self.local))?;
+        write!(f, "  next:  {}", display_row(&self.next))
+    }
+}
+
+/// Renders a row as `[v0, v1, ...]`.
+pub fn display_row<F: Display>(row: &[F]) -> String {
+    let cells = row.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+    format!("[{}]", cells.join(", "))
+}
+
+/// The residual of every constraint of `air` on `(local, next)` with the given selectors; nonzero ones are
+/// violated.
+pub fn eval_constraints_with<F, A>(
+    air: &A,
+    local: &[F],
+    next: &[F],
+    public_values: &[F],
+    selectors: Selectors<F>,
+) -> Vec<F>
+where
+    F: Field,
+    A: for<'a> Air<EvalBuilder<'a, F>>,
+{
+    let mut builder = EvalBuilder::new(local, next, public_values, selectors);
+    air.eval(&mut builder);
+    builder.into_residuals()
+}
+
+/// The residual of every constraint of `air` on `(local, next)` as an interior window of the trace.
+pub fn eval_constraints<F, A>(air: &A, local: &[F], next: &[F], public_values: &[F]) -> Vec<F>
+where
+    F: Field,
+    A: for<'a> Air<EvalBuilder<'a, F>>,
+{
+    eval_constraints_with(air, local, next, public_values, Selectors::interior())
+}
+
+/// Evaluates `air` on the window starting at row `i` (wrapping around); returns the number of constraints and
+/// the failed ones.
+fn eval_window<F, A>(air: &A, trace: &RowMajorMatrix<F>, public_values: &[F], i: usize) -> (usize, Vec<usize>)
+where
+    F: Field,
+    A: for<'a> Air<EvalBuilder<'a, F>>,
+{
+    let height = trace.height();
+    let local = trace.row_slice(i);
+    let next = trace.row_slice((i + 1) % height);
+
+    let residuals = eval_constraints_with(air, &local, &next, public_values, Selectors::for_row(i, height));
+    let failed = residuals.iter().enumerate().filter(|(_, r)| !r.is_zero()).map(|(k, _)| k).collect();
+    (residuals.len(), failed)
+}
+
+/// Evaluates `air` on the window starting at row `i` (wrapping around), returning the failed constraints.
+pub fn failed_constraints<F, A>(air: &A, trace: &RowMajorMatrix<F>, public_values: &[F], i: usize) -> Vec<usize>
+where
+    F: Field,
+    A: for<'a> Air<EvalBuilder<'a, F>>,
+{
+    eval_window(air, trace, public_values, i).1
+}
+
+/// `[row][constraint]`: whether each constraint holds on each row's window, constraints in the order `eval`
+/// asserts them. Gated constraints count as holding on rows where their gate is off.
+pub fn constraint_satisfaction<F, A>(air: &A, trace: &RowMajorMatrix<F>, public_values: &[F]) -> Vec<Vec<bool>>
+where
+    F: Field,
+    A: for<'a> Air<EvalBuilder<'a, F>>,
+{
+    (0..trace.height())
+        .map(|i| {
+            let (n_constraints, failed) = eval_window(air, trace, public_values, i);
+            let mut holds = vec![true; n_constraints];
+            for k in failed {
+                holds[k] = false;
+            }
+            holds
+        })
+        .collect()
+}
+
+/// Renders a satisfaction matrix as text, one line per row: `.` where a constraint holds, `X` where it fails.
+pub fn satisfaction_heatmap(satisfaction: &[Vec<bool>]) -> String {
+    let digits = satisfaction.len().saturating_sub(1).to_string().len();
+    let mut out = String::new();
+    for (i, row) in satisfaction.iter().enumerate() {
+        let cells = row.iter().map(|&holds| if holds { '.' } else { 'X' }).collect::<String>();
+        out.push_str(&format!("{:>width$} {}\n", i, cells, width = digits));
+    }
+    out
+}
+
+/// Checks every row of `trace` against `air`, returning the failing rows in order.
+pub fn debug_check_constraints<F, A>(
+    air: &A,
+    trace: &RowMajorMatrix<F>,
+    public_values: &[F],
+) -> Result<(), Vec<ConstraintFailure<F>>>
+where
+    F: Field,
+    A: for<'a> Air<EvalBuilder<'a, F>>,
+{
+    let height = trace.height();
+    let mut failures = vec![];
+
+    for i in 0..height {
+        let failed = failed_constraints(air, trace, public_values, i);
+        if !failed.is_empty() {
+            failures.push(ConstraintFailure {
+                row: i,
+                constraints: failed,
+                local: trace.row_slice(i).to_vec(),
+                next: trace.row_slice((i + 1) % height).to_vec(),
+            });
+        }
+    }
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(failures)
+    }
+}
+
+/// Asserts that every constraint of `air` holds on `trace`.
+#[macro_export]
+macro_rules! assert_constraints_ok {
+    ($air:expr, $trace:expr, $publics:expr) => {
+        if let Err(failures) = $crate::debug::debug_check_constraints($air, $trace, $publics) {
+            panic!(
+                "expected all constraints to hold, but {} rows failed; first failure:\n{}",
+                failures.len(),
+                failures[0]
+            );
+        }
+    };
+}
+
+/// Asserts that `air` rejects `trace`, and that the first failing row is `row`.
+#[macro_export]
+macro_rules! assert_constraints_fail {
+    ($air:expr, $trace:expr, $publics:expr, $row:expr) => {
+        match $crate::debug::debug_check_constraints($air, $trace, $publics) {
+            Ok(()) => panic!("expected a constraint failure on row {}, but all constraints hold", $row),
+            Err(failures) => assert_eq!(
+                failures[0].row, $row,
+                "expected the first failure on row {}, got:\n{}",
+                $row, failures[0]
+            ),
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_field::AbstractField;
+
+    use super::*;
+    use crate::config::Val;
+    use crate::simple_state::{random_checked_trace, random_trace_with_fault, SimpleState, SimpleStateChecked};
+
+    fn vals(values: &[u32]) -> Vec<Val> {
+        values.iter().map(|&v| Val::from_canonical_u32(v)).collect()
+    }
+
+    #[test]
+    fn test_simple_state_residuals() {
+        // `balance + input - output == next.balance`
+        let local = vals(&[10, 5, 3]);
+        assert_eq!(eval_constraints(&SimpleState {}, &local, &vals(&[12, 0, 0]), &[]), vals(&[0]));
+        assert_eq!(eval_constraints(&SimpleState {}, &local, &vals(&[11, 0, 0]), &[]), vals(&[1]));
+        let residuals = eval_constraints(&SimpleState {}, &local, &vals(&[15, 0, 0]), &[]);
+        assert_eq!(residuals, vec![-Val::from_canonical_u32(3)]);
+
+        // the last window isn't a transition
+        let last = Selectors::for_row(7, 8);
+        assert_eq!(eval_constraints_with(&SimpleState {}, &local, &vals(&[15, 0, 0]), &[], last), vals(&[0]));
+    }
+
+    #[test]
+    fn test_residuals_match_failures() {
+        let (trace, mut public_values) = random_checked_trace::<Val>(3);
+        let (local, next) = (trace.row_slice(0), trace.row_slice(1));
+        let first = Selectors::for_row(0, 8);
+        let residuals = eval_constraints_with(&SimpleStateChecked {}, &local, &next, &public_values, first);
+        assert!(residuals.iter().all(|r| r.is_zero()));
+        assert_eq!(residuals.len(), eval_window(&SimpleStateChecked {}, &trace, &public_values, 0).0);
+
+        // a wrong initial balance shows up in exactly the constraints reported as failed
+        public_values[0] += Val::one();
+        let residuals = eval_constraints_with(&SimpleStateChecked {}, &local, &next, &public_values, first);
+        let nonzero = residuals.iter().enumerate().filter(|(_, r)| !r.is_zero()).map(|(k, _)| k).collect::<Vec<_>>();
+        assert!(!nonzero.is_empty());
+        assert_eq!(nonzero, failed_constraints(&SimpleStateChecked {}, &trace, &public_values, 0));
+    }
+
+    #[test]
+    fn test_constraint_satisfaction_single_bad_row() {
+        let trace = random_trace_with_fault::<Val>(3, 4);
+        let satisfaction = constraint_satisfaction(&SimpleState {}, &trace, &[]);
+        assert_eq!(satisfaction.len(), 8);
+
+        let violated = satisfaction
+            .iter()
+            .enumerate()
+            .flat_map(|(i, row)| row.iter().enumerate().filter(|(_, &holds)| !holds).map(move |(k, _)| (i, k)))
+            .collect::<Vec<_>>();
+        assert_eq!(violated, vec![(4, 0)]);
+
+        let heatmap = satisfaction_heatmap(&satisfaction);
+        assert_eq!(heatmap.lines().nth(4), Some("4 X"));
+        assert_eq!(heatmap.lines().nth(3), Some("3 ."));
+    }
+}
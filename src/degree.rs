@@ -0,0 +1,93 @@
+use p3_air::Air;
+use p3_field::Field;
+use p3_uni_stark::{get_log_quotient_degree, get_max_constraint_degree, SymbolicAirBuilder};
+
+use crate::error::CookError;
+use crate::simple_state::{SimpleState, SimpleStateChecked};
+
+// The quotient polynomial has degree `(constraint degree - 1) * (trace degree)`, and the prover splits it
+// into `2^log_quotient_degree` chunks, which the LDE must have room for: a `log_blowup` below that fails
+// deep inside the prover. uni-stark derives the degree from the symbolic constraints, so an AIR whose
+// constraints grew a degree by accident changes the required blowup without anyone noticing. Declaring the
+// intended degree turns that into an error at setup, and documents it next to the AIR.
+
+/// An AIR that states the maximum degree its constraints are meant to have.
+pub trait DeclaredDegree {
+    fn declared_max_degree(&self) -> usize;
+}
+
+impl DeclaredDegree for SimpleState {
+    fn declared_max_degree(&self) -> usize {
+        1
+    }
+}
+
+impl DeclaredDegree for SimpleStateChecked {
+    fn declared_max_degree(&self) -> usize {
+        // the range-check bits are boolean
+        2
+    }
+}
+
+/// Checks `air`'s declared degree against the symbolic one; returns the log of the quotient degree, the
+/// smallest `log_blowup` the config may use.
+pub fn check_declared_degree<F, A>(air: &A, num_public_values: usize) -> Result<usize, CookError>
+where
+    F: Field,
+    A: DeclaredDegree + Air<SymbolicAirBuilder<F>>,
+{
+    let declared = air.declared_max_degree();
+    let computed = get_max_constraint_degree::<F, A>(air, 0, num_public_values);
+    if declared != computed {
+        return Err(CookError::Degree { declared, computed });
+    }
+    Ok(get_log_quotient_degree::<F, A>(air, 0, num_public_values))
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_air::{AirBuilder, BaseAir};
+    use p3_matrix::Matrix;
+
+    use super::*;
+    use crate::config::{Val, DEFAULT_LOG_BLOWUP};
+
+    // declares degree 2, but its transition is a cube
+    struct UnderDeclared {}
+
+    impl<F> BaseAir<F> for UnderDeclared {
+        fn width(&self) -> usize {
+            1
+        }
+    }
+
+    impl<AB: AirBuilder> Air<AB> for UnderDeclared {
+        fn eval(&self, builder: &mut AB) {
+            let main = builder.main();
+            let (local, next) = (main.row_slice(0), main.row_slice(1));
+            builder.when_transition().assert_eq(next[0], local[0] * local[0] * local[0]);
+        }
+    }
+
+    impl DeclaredDegree for UnderDeclared {
+        fn declared_max_degree(&self) -> usize {
+            2
+        }
+    }
+
+    #[test]
+    fn test_declared_degrees_match() {
+        let log_quotient_degree = check_declared_degree::<Val, _>(&SimpleState {}, 0).unwrap();
+        assert!(log_quotient_degree <= DEFAULT_LOG_BLOWUP);
+        let log_quotient_degree = check_declared_degree::<Val, _>(&SimpleStateChecked {}, 2).unwrap();
+        assert!(log_quotient_degree <= DEFAULT_LOG_BLOWUP);
+    }
+
+    #[test]
+    fn test_under_declared_degree_is_caught() {
+        match check_declared_degree::<Val, _>(&UnderDeclared {}, 0) {
+            Err(CookError::Degree { declared: 2, computed: 3 }) => {}
+            other => panic!("expected a degree mismatch, got {:?}", other),
+        }
+    }
+}
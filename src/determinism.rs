@@ -0,0 +1,212 @@
+use p3_air::Air;
+use p3_fri::{BatchOpening, QueryProof};
+use p3_matrix::dense::RowMajorMatrix;
+#[cfg(not(feature = "parallel"))]
+use p3_uni_stark::prove;
+use p3_uni_stark::{Proof, ProverConstraintFolder, StarkGenericConfig, SymbolicAirBuilder, Val};
+#[cfg(debug_assertions)]
+use p3_uni_stark::DebugConstraintBuilder;
+
+use crate::config::{Challenge, ChallengeMmcs, MyConfig, Val as MyVal, ValMmcs};
+
+// A uni-stark proof is a function of the config, the challenger's initial state, the trace and the public
+// values; the prover draws no randomness of its own. The usual sources of differing bytes are the inputs:
+//
+// - `random_perm` draws Poseidon2 round constants from `thread_rng`, so two configs built from it never
+//   agree. Use `perm_from_seed`.
+// - `random_trace` draws from `thread_rng` too. Use `random_trace_with_rng` with a seeded rng.
+//
+// Inside the prover, parallel work is either per-element (DFTs, hashing rows, evaluating constraints) or an
+// exact field reduction, so thread scheduling can't change a value. The one exception is proof-of-work
+// grinding: with the `parallel` feature Plonky3 searches for the witness with `find_any`, which returns
+// whichever valid witness a worker hits first. `prove_deterministic` runs the prover in a one-worker pool,
+// where that search always walks the candidates in the same order. Without the feature grinding is a plain
+// sequential search and nothing needs doing.
+//
+// Byte offsets say little about what differs. `first_proof_difference` walks two proofs of the default
+// config component by component (commitments, opened values, then the FRI proof down to single Merkle
+// siblings) and names the first one that differs, e.g. `opening_proof.query_proofs[3].input_proof[1]`.
+//
+// bincode writes fixed-width little-endian integers and field elements as their internal `u32`, so the
+// serialized bytes don't depend on the platform's endianness or word size.
+
+/// `prove`, with proof-of-work grinding made reproducible under the `parallel` feature.
+pub fn prove_deterministic<
+    SC,
+    #[cfg(debug_assertions)] A: for<'a> Air<DebugConstraintBuilder<'a, Val<SC>>>,
+    #[cfg(not(debug_assertions))] A,
+>(
+    config: &SC,
+    air: &A,
+    challenger: &mut SC::Challenger,
+    trace: RowMajorMatrix<Val<SC>>,
+    public_values: &Vec<Val<SC>>,
+) -> Proof<SC>
+where
+    SC: StarkGenericConfig + Sync,
+    SC::Challenger: Send,
+    A: Air<SymbolicAirBuilder<Val<SC>>> + for<'a> Air<ProverConstraintFolder<'a, SC>> + Sync,
+    Proof<SC>: Send,
+{
+    #[cfg(feature = "parallel")]
+    {
+        let pool = crate::parallel::build_prover_pool(1).expect("a one-thread pool always builds");
+        crate::parallel::prove_in_pool(&pool, config, air, challenger, trace, public_values)
+    }
+    #[cfg(not(feature = "parallel"))]
+    prove(config, air, challenger, trace, public_values)
+}
+
+/// The canonical byte encoding of a proof, the one compared for determinism.
+pub fn proof_bytes<SC: StarkGenericConfig>(proof: &Proof<SC>) -> Vec<u8> {
+    bincode::serialize(proof).expect("proofs always serialize")
+}
+
+/// Panics unless the two encodings are identical, naming the first differing offset.
+pub fn assert_same_proof_bytes(left: &[u8], right: &[u8]) {
+    if let Some(offset) = left.iter().zip(right).position(|(l, r)| l != r) {
+        panic!(
+            "proof bytes differ at offset {} of {}: {:#04x} != {:#04x}",
+            offset,
+            left.len(),
+            left[offset],
+            right[offset]
+        );
+    }
+    assert_eq!(
+        left.len(),
+        right.len(),
+        "proof bytes agree on the first {} bytes but differ in length",
+        left.len().min(right.len())
+    );
+}
+
+fn first_difference<T>(
+    label: &str,
+    a: &[T],
+    b: &[T],
+    check: impl Fn(usize, &T, &T) -> Option<String>,
+) -> Option<String> {
+    if a.len() != b.len() {
+        return Some(format!("{}: {} entries != {}", label, a.len(), b.len()));
+    }
+    a.iter().zip(b).enumerate().find_map(|(i, (x, y))| check(i, x, y))
+}
+
+fn differs<T: PartialEq>(label: String, a: &T, b: &T) -> Option<String> {
+    (a != b).then_some(label)
+}
+
+type MyBatchOpening = BatchOpening<MyVal, ValMmcs>;
+type MyQueryProof = QueryProof<Challenge, ChallengeMmcs, Vec<MyBatchOpening>>;
+
+fn batch_difference(label: String, a: &MyBatchOpening, b: &MyBatchOpening) -> Option<String> {
+    differs(format!("{}.opened_values", label), &a.opened_values, &b.opened_values)
+        .or_else(|| differs(format!("{}.opening_proof", label), &a.opening_proof, &b.opening_proof))
+}
+
+fn query_difference(label: String, a: &MyQueryProof, b: &MyQueryProof) -> Option<String> {
+    let inputs = format!("{}.input_proof", label);
+    let steps = format!("{}.commit_phase_openings", label);
+    first_difference(&inputs, &a.input_proof, &b.input_proof, |i, x, y| {
+        batch_difference(format!("{}[{}]", inputs, i), x, y)
+    })
+    .or_else(|| {
+        first_difference(&steps, &a.commit_phase_openings, &b.commit_phase_openings, |i, x, y| {
+            differs(format!("{}[{}].sibling_value", steps, i), &x.sibling_value, &y.sibling_value)
+                .or_else(|| differs(format!("{}[{}].opening_proof", steps, i), &x.opening_proof, &y.opening_proof))
+        })
+    })
+}
+
+/// The first component in which two proofs differ, or `None` if they agree everywhere.
+pub fn first_proof_difference(a: &Proof<MyConfig>, b: &Proof<MyConfig>) -> Option<String> {
+    let (ca, cb) = (&a.commitments, &b.commitments);
+    let (oa, ob) = (&a.opened_values, &b.opened_values);
+    let (fa, fb) = (&a.opening_proof, &b.opening_proof);
+    differs("degree_bits".to_string(), &a.degree_bits, &b.degree_bits)
+        .or_else(|| differs("commitments.trace".to_string(), &ca.trace, &cb.trace))
+        .or_else(|| differs("commitments.quotient_chunks".to_string(), &ca.quotient_chunks, &cb.quotient_chunks))
+        .or_else(|| differs("opened_values.trace_local".to_string(), &oa.trace_local, &ob.trace_local))
+        .or_else(|| differs("opened_values.trace_next".to_string(), &oa.trace_next, &ob.trace_next))
+        .or_else(|| {
+            differs("opened_values.quotient_chunks".to_string(), &oa.quotient_chunks, &ob.quotient_chunks)
+        })
+        .or_else(|| {
+            let label = "opening_proof.commit_phase_commits";
+            first_difference(label, &fa.commit_phase_commits, &fb.commit_phase_commits, |i, x, y| {
+                differs(format!("{}[{}]", label, i), x, y)
+            })
+        })
+        .or_else(|| {
+            let label = "opening_proof.query_proofs";
+            first_difference(label, &fa.query_proofs, &fb.query_proofs, |i, x, y| {
+                query_difference(format!("{}[{}]", label, i), x, y)
+            })
+        })
+        .or_else(|| differs("opening_proof.final_poly".to_string(), &fa.final_poly, &fb.final_poly))
+        .or_else(|| differs("opening_proof.pow_witness".to_string(), &fa.pow_witness, &fb.pow_witness))
+}
+
+/// Whether two proofs agree component by component; `first_proof_difference` says where they don't.
+pub fn proofs_structurally_equal(a: &Proof<MyConfig>, b: &Proof<MyConfig>) -> bool {
+    first_proof_difference(a, b).is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use p3_field::AbstractField;
+
+    use super::*;
+    use crate::config::{default_config, perm_from_seed, Challenger, Val};
+    use crate::simple_state::{random_trace_with_rng, SimpleState};
+
+    fn seeded_proof(mutate: bool) -> Proof<MyConfig> {
+        let perm = perm_from_seed(42);
+        let config = default_config(&perm);
+        let mut trace = random_trace_with_rng::<Val, _>(10, &mut StdRng::seed_from_u64(42));
+        if mutate {
+            // row 1's input and output both up by one, which keeps the balances valid
+            trace.values[4] += Val::one();
+            trace.values[5] += Val::one();
+        }
+
+        let mut challenger = Challenger::new(perm);
+        prove_deterministic(&config, &SimpleState {}, &mut challenger, trace, &vec![])
+    }
+
+    fn seeded_proof_bytes() -> Vec<u8> {
+        proof_bytes(&seeded_proof(false))
+    }
+
+    #[test]
+    fn test_seeded_proofs_are_byte_identical() {
+        assert_same_proof_bytes(&seeded_proof_bytes(), &seeded_proof_bytes());
+    }
+
+    #[test]
+    #[should_panic(expected = "proof bytes differ at offset 3")]
+    fn test_assert_reports_first_difference() {
+        assert_same_proof_bytes(&[1, 2, 3, 4], &[1, 2, 3, 5]);
+    }
+
+    #[test]
+    fn test_structural_comparison() {
+        let proof = seeded_proof(false);
+        assert!(proofs_structurally_equal(&proof, &proof));
+        assert!(proofs_structurally_equal(&proof, &seeded_proof(false)));
+
+        let other = seeded_proof(true);
+        assert!(!proofs_structurally_equal(&proof, &other));
+        assert_eq!(first_proof_difference(&proof, &other).as_deref(), Some("commitments.trace"));
+
+        let mut tampered = seeded_proof(false);
+        tampered.opening_proof.query_proofs[3].commit_phase_openings[1].sibling_value += Challenge::one();
+        assert_eq!(
+            first_proof_difference(&proof, &tampered).as_deref(),
+            Some("opening_proof.query_proofs[3].commit_phase_openings[1].sibling_value")
+        );
+    }
+}
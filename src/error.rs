@@ -0,0 +1,240 @@
+use std::fmt::{self, Debug};
+use std::time::Duration;
+
+use crate::transaction::LedgerError;
+
+#[derive(Debug)]
+pub enum CookError {
+    Ledger(LedgerError),
+    PublicValues(String),
+    Verification(VerifyFailure),
+    /// the AIR's declared constraint degree differs from the symbolic one
+    Degree { declared: usize, computed: usize },
+    /// verification did not finish within the caller's time budget
+    Timeout { budget: Duration },
+    /// raw values that don't form a trace
+    Trace(String),
+}
+
+impl fmt::Display for CookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CookError::Ledger(e) => write!(f, "invalid ledger: {}", e),
+            CookError::PublicValues(reason) => write!(f, "invalid public values: {}", reason),
+            CookError::Verification(failure) => write!(f, "verification failed: {}", failure.reason()),
+            CookError::Degree { declared, computed } => {
+                write!(f, "the AIR declares constraint degree {}, but its constraints have degree {}", declared, computed)
+            }
+            CookError::Timeout { budget } => write!(f, "verification did not finish within {:?}", budget),
+            CookError::Trace(reason) => write!(f, "invalid trace: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for CookError {}
+
+impl From<LedgerError> for CookError {
+    fn from(e: LedgerError) -> Self {
+        CookError::Ledger(e)
+    }
+}
+
+impl From<VerifyFailure> for CookError {
+    fn from(failure: VerifyFailure) -> Self {
+        CookError::Verification(failure)
+    }
+}
+
+/// Why a proof was rejected.
+///
+/// The verifier checks, in order: the schema in an encoded proof's header, the proof's shape, the public
+/// values' arity, the PCS opening (FRI's proof-of-work, then its query consistency), and finally the
+/// constraints at the out-of-domain point. A wrong public value is bound into Fiat-Shamir, so it does not
+/// surface as a constraint failure: every later challenge changes and the first check to notice is the
+/// proof-of-work.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VerifyFailure {
+    Schema { expected: String, got: String },
+    ProofShape,
+    PublicValues { expected: usize, got: usize },
+    ProofOfWork,
+    LowDegree(String),
+    Constraints,
+}
+
+impl VerifyFailure {
+    pub fn reason(&self) -> String {
+        match self {
+            VerifyFailure::Schema { expected, got } => {
+                format!("the proof was produced for AIR schema {}, but this verifier's AIR is {}", got, expected)
+            }
+            VerifyFailure::ProofShape => "the proof does not have the shape this AIR and config expect".to_string(),
+            VerifyFailure::PublicValues { expected, got } => {
+                format!("expected {} public values, got {}", expected, got)
+            }
+            VerifyFailure::ProofOfWork => "the proof-of-work witness was rejected; unless the proof was tampered \
+                with, the transcript diverged from the prover's, most often because the public values differ"
+                .to_string(),
+            VerifyFailure::LowDegree(detail) => format!("the FRI low-degree test failed: {}", detail),
+            VerifyFailure::Constraints => {
+                "the AIR constraints do not hold at the out-of-domain point".to_string()
+            }
+        }
+    }
+}
+
+impl fmt::Display for VerifyFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.reason())
+    }
+}
+
+impl<CommitMmcsErr: Debug, InputErr: Debug>
+    From<p3_uni_stark::VerificationError<p3_fri::VerificationError<CommitMmcsErr, InputErr>>> for VerifyFailure
+{
+    fn from(e: p3_uni_stark::VerificationError<p3_fri::VerificationError<CommitMmcsErr, InputErr>>) -> Self {
+        use p3_fri::VerificationError as Fri;
+        use p3_uni_stark::VerificationError as Stark;
+
+        match e {
+            Stark::InvalidProofShape | Stark::InvalidOpeningArgument(Fri::InvalidProofShape) => {
+                VerifyFailure::ProofShape
+            }
+            Stark::InvalidOpeningArgument(Fri::InvalidPowWitness) => VerifyFailure::ProofOfWork,
+            Stark::InvalidOpeningArgument(Fri::CommitPhaseMmcsError(e)) => {
+                VerifyFailure::LowDegree(format!("a commit-phase Merkle opening is invalid ({:?})", e))
+            }
+            Stark::InvalidOpeningArgument(Fri::InputError(e)) => {
+                VerifyFailure::LowDegree(format!("a trace or quotient Merkle opening is invalid ({:?})", e))
+            }
+            Stark::InvalidOpeningArgument(Fri::FinalPolyMismatch) => {
+                VerifyFailure::LowDegree("the folded queries do not match the final polynomial".to_string())
+            }
+            Stark::OodEvaluationMismatch => VerifyFailure::Constraints,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_air::{Air, AirBuilder, BaseAir};
+    use p3_baby_bear::DiffusionMatrixBabyBear;
+    use p3_field::AbstractField;
+    use p3_matrix::Matrix;
+    use p3_poseidon2::Poseidon2ExternalMatrixGeneral;
+    use p3_uni_stark::{prove, verify, Proof, SymbolicAirBuilder, VerifierConstraintFolder};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::config::{default_config, Challenge, Challenger, MyConfig, Perm, Val};
+    use crate::replay::verify_and_replay;
+    use crate::simple_state::{random_checked_trace_with_rng, random_trace_with_rng, SimpleState, SimpleStateChecked};
+
+    // the same width and constraint degree as `SimpleState`, but a different transition
+    struct OtherState {}
+
+    impl<F> BaseAir<F> for OtherState {
+        fn width(&self) -> usize {
+            3
+        }
+    }
+
+    impl<AB: AirBuilder> Air<AB> for OtherState {
+        fn eval(&self, builder: &mut AB) {
+            let main = builder.main();
+            let (local, next) = (main.row_slice(0), main.row_slice(1));
+            builder.when_transition().assert_eq(next[0], local[0] + local[1] + local[2]);
+        }
+    }
+
+    // seeded so the outcome of a tampered transcript is reproducible
+    fn setup() -> (Perm, MyConfig, Proof<MyConfig>, Vec<Val>) {
+        let mut rng = StdRng::seed_from_u64(7);
+        let perm = Perm::new_from_rng_128(Poseidon2ExternalMatrixGeneral, DiffusionMatrixBabyBear::default(), &mut rng);
+        let config = default_config(&perm);
+        let (trace, public_values) = random_checked_trace_with_rng::<Val, _>(6, &mut rng);
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &SimpleStateChecked {}, &mut p_challenger, trace, &public_values);
+        (perm, config, proof, public_values)
+    }
+
+    fn failure<A>(perm: &Perm, config: &MyConfig, air: &A, proof: &Proof<MyConfig>, pis: &Vec<Val>) -> VerifyFailure
+    where
+        A: Air<SymbolicAirBuilder<Val>> + for<'a> Air<VerifierConstraintFolder<'a, MyConfig>>,
+    {
+        let mut v_challenger = Challenger::new(perm.clone());
+        verify(config, air, &mut v_challenger, proof, pis).unwrap_err().into()
+    }
+
+    #[test]
+    fn test_untampered_proof_verifies() {
+        let (perm, config, proof, public_values) = setup();
+        let mut v_challenger = Challenger::new(perm);
+        verify_and_replay(&config, &mut v_challenger, &proof, &public_values).unwrap();
+    }
+
+    #[test]
+    fn test_proof_shape_failure() {
+        let (perm, config, mut proof, public_values) = setup();
+        proof.opened_values.trace_local.pop();
+        assert_eq!(failure(&perm, &config, &SimpleStateChecked {}, &proof, &public_values), VerifyFailure::ProofShape);
+    }
+
+    #[test]
+    fn test_public_value_arity_failure() {
+        let (perm, config, proof, mut public_values) = setup();
+        public_values.push(Val::one());
+
+        let mut v_challenger = Challenger::new(perm);
+        match verify_and_replay(&config, &mut v_challenger, &proof, &public_values) {
+            Err(CookError::Verification(failure)) => {
+                assert_eq!(failure, VerifyFailure::PublicValues { expected: 2, got: 3 })
+            }
+            other => panic!("expected a public value failure, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_wrong_public_value_fails_proof_of_work() {
+        let (perm, config, proof, mut public_values) = setup();
+        public_values[1] += Val::one();
+
+        let failure = failure(&perm, &config, &SimpleStateChecked {}, &proof, &public_values);
+        assert_eq!(failure, VerifyFailure::ProofOfWork);
+        assert!(failure.reason().contains("public values"));
+    }
+
+    #[test]
+    fn test_proof_of_work_failure() {
+        let (perm, config, mut proof, public_values) = setup();
+        proof.opening_proof.pow_witness += Val::one();
+        assert_eq!(failure(&perm, &config, &SimpleStateChecked {}, &proof, &public_values), VerifyFailure::ProofOfWork);
+    }
+
+    #[test]
+    fn test_low_degree_failure() {
+        let (perm, config, mut proof, public_values) = setup();
+        proof.opening_proof.query_proofs[0].commit_phase_openings[0].sibling_value += Challenge::one();
+
+        let failure = failure(&perm, &config, &SimpleStateChecked {}, &proof, &public_values);
+        assert!(matches!(failure, VerifyFailure::LowDegree(_)), "got {:?}", failure);
+    }
+
+    #[test]
+    fn test_constraint_failure() {
+        // the AIR is not bound into the transcript, so a valid proof checked against another AIR of the same
+        // shape gets all the way to the out-of-domain check
+        let mut rng = StdRng::seed_from_u64(7);
+        let perm = Perm::new_from_rng_128(Poseidon2ExternalMatrixGeneral, DiffusionMatrixBabyBear::default(), &mut rng);
+        let config = default_config(&perm);
+        let trace = random_trace_with_rng::<Val, _>(6, &mut rng);
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &SimpleState {}, &mut p_challenger, trace, &vec![]);
+
+        let failure = failure(&perm, &config, &OtherState {}, &proof, &vec![]);
+        assert_eq!(failure, VerifyFailure::Constraints);
+        assert!(failure.reason().contains("constraints"));
+    }
+}
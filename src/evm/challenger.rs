@@ -0,0 +1,146 @@
+use p3_challenger::{CanObserve, CanSample, CanSampleBits, FieldChallenger, GrindingChallenger};
+use p3_field::{AbstractField, PrimeField32};
+use p3_keccak::Keccak256Hash;
+use p3_symmetric::{CryptographicHasher, Hash};
+use p3_uni_stark::StarkConfig;
+
+use crate::config::{
+    make_pcs_with, Challenge, MyHash, Pcs, Perm, Val, DEFAULT_LOG_BLOWUP, DEFAULT_NUM_QUERIES, DEFAULT_POW_BITS,
+};
+
+// A Fiat-Shamir transcript an EVM verifier can reproduce with `keccak256` and 256-bit arithmetic alone. The
+// state is a 32-byte hash chain; observed field elements are buffered as 32-byte big-endian words, exactly
+// `abi.encode(uint256(x))`, and sampling hashes the state with the buffer:
+//
+//   bytes32 state;  bytes pending;
+//   function observe(uint256 x) { pending = abi.encodePacked(pending, x); }
+//   function sample() returns (uint256) {
+//       state = keccak256(abi.encodePacked(state, pending));
+//       delete pending;
+//       return uint256(state) % P;
+//   }
+//
+// Extension elements are sampled one base coefficient at a time, lowest first, and `sample_bits(n)` is the
+// low `n` bits of a sampled element. Reducing a 256-bit digest mod the 31-bit prime has negligible bias.
+// Only the transcript changes: commitments are still Poseidon2 Merkle roots, observed as their 8 elements.
+
+/// The Keccak transcript described above.
+#[derive(Clone, Debug, Default)]
+pub struct EvmChallenger {
+    state: [u8; 32],
+    pending: Vec<u8>,
+}
+
+impl EvmChallenger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// `uint256(bytes) % P` for a big-endian 32-byte word.
+fn reduce_be<F: PrimeField32>(bytes: &[u8; 32]) -> F {
+    let p = F::ORDER_U32 as u64;
+    let reduced = bytes.iter().fold(0u64, |acc, &b| (acc * 256 + b as u64) % p);
+    F::from_canonical_u32(reduced as u32)
+}
+
+impl CanObserve<Val> for EvmChallenger {
+    fn observe(&mut self, value: Val) {
+        let mut word = [0u8; 32];
+        word[28..].copy_from_slice(&value.as_canonical_u32().to_be_bytes());
+        self.pending.extend_from_slice(&word);
+    }
+}
+
+impl CanObserve<Hash<Val, Val, 8>> for EvmChallenger {
+    fn observe(&mut self, digest: Hash<Val, Val, 8>) {
+        let digest: [Val; 8] = digest.into();
+        self.observe_slice(&digest);
+    }
+}
+
+impl CanSample<Val> for EvmChallenger {
+    fn sample(&mut self) -> Val {
+        let input = self.state.iter().chain(&self.pending).copied();
+        self.state = Keccak256Hash {}.hash_iter(input);
+        self.pending.clear();
+        reduce_be(&self.state)
+    }
+}
+
+impl CanSampleBits<usize> for EvmChallenger {
+    fn sample_bits(&mut self, bits: usize) -> usize {
+        debug_assert!(bits < 31);
+        let x: Val = self.sample();
+        x.as_canonical_u32() as usize & ((1 << bits) - 1)
+    }
+}
+
+impl FieldChallenger<Val> for EvmChallenger {}
+
+impl GrindingChallenger for EvmChallenger {
+    type Witness = Val;
+
+    fn grind(&mut self, bits: usize) -> Val {
+        let witness = (0..Val::ORDER_U32)
+            .map(Val::from_canonical_u32)
+            .find(|&w| self.clone().check_witness(bits, w))
+            .expect("failed to find a proof-of-work witness");
+        assert!(self.check_witness(bits, witness));
+        witness
+    }
+}
+
+/// The default Poseidon2 PCS with the Keccak transcript.
+pub type EvmConfig = StarkConfig<Pcs, Challenge, EvmChallenger>;
+
+/// Builds the EVM-transcript config with the crate's default FRI parameters.
+pub fn evm_config(perm: &Perm) -> EvmConfig {
+    let hash = MyHash::new(perm.clone());
+    EvmConfig::new(make_pcs_with(hash, perm, DEFAULT_LOG_BLOWUP, DEFAULT_NUM_QUERIES, DEFAULT_POW_BITS))
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_uni_stark::{prove, verify};
+
+    use super::*;
+    use crate::config::random_perm;
+    use crate::simple_state::{random_trace, SimpleState};
+
+    #[test]
+    fn test_samples_match_reference_vector() {
+        // from a reference implementation of the Solidity transcript above
+        let mut challenger = EvmChallenger::new();
+        challenger.observe_slice(&[Val::one(), Val::two(), Val::from_canonical_u32(3)]);
+        let samples: [Val; 3] = core::array::from_fn(|_| challenger.sample());
+        assert_eq!(samples, [1686206866, 767179364, 1811073902].map(Val::from_canonical_u32));
+    }
+
+    #[test]
+    fn test_reduction_is_uint256_mod_p() {
+        assert_eq!(reduce_be::<Val>(&[0; 32]), Val::zero());
+        let mut p = [0u8; 32];
+        p[28..].copy_from_slice(&(Val::ORDER_U32 + 5).to_be_bytes());
+        assert_eq!(reduce_be::<Val>(&p), Val::from_canonical_u32(5));
+        // 2^248 mod p
+        let mut high = [0u8; 32];
+        high[0] = 1;
+        assert_eq!(reduce_be::<Val>(&high), Val::two().exp_u64(248));
+    }
+
+    #[test]
+    fn test_evm_config_round_trip() {
+        let perm = random_perm();
+        let config = evm_config(&perm);
+        let trace = random_trace::<Val>(8);
+
+        let mut p_challenger = EvmChallenger::new();
+        let proof = prove(&config, &SimpleState {}, &mut p_challenger, trace, &vec![]);
+        let mut v_challenger = EvmChallenger::new();
+        verify(&config, &SimpleState {}, &mut v_challenger, &proof, &vec![]).unwrap();
+
+        // both sides end in the same transcript state
+        assert_eq!(CanSample::<Val>::sample(&mut p_challenger), CanSample::<Val>::sample(&mut v_challenger));
+    }
+}
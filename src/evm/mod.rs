@@ -0,0 +1,259 @@
+pub mod challenger;
+
+use p3_field::{AbstractExtensionField, AbstractField, PrimeField32};
+use p3_symmetric::Hash;
+use p3_uni_stark::Proof;
+
+use crate::config::{Challenge, MyConfig, Val};
+
+// Flattens a proof into the array of field elements an on-chain verifier reads from calldata, one element
+// per 32-byte big-endian word. Every variable-length list is preceded by its length, so a verifier can walk
+// the array without knowing the AIR's shape up front. Sections, in order:
+//
+//   degree_bits          [log2 trace height]
+//   public_values        [n, v_0 .. v_n-1]
+//   trace_commitment     [8 digest elements]
+//   quotient_commitment  [8 digest elements]
+//   trace_local          [n, 4 coefficients per extension element ..]
+//   trace_next           [n, ..]
+//   quotient_chunks      [chunks, then per chunk: n, 4 coefficients per element ..]
+//   fri_commit_phase     [rounds, 8 digest elements per round ..]
+//   fri_queries          [queries, then per query:
+//                           batches, per batch: matrices, per matrix: n, values ..; path length, 8 per node ..
+//                           steps, per step: 4 sibling coefficients, path length, 8 per node ..]
+//   fri_final_poly       [4 coefficients]
+//   fri_pow_witness      [witness]
+
+pub const SECTIONS: &[&str] = &[
+    "degree_bits",
+    "public_values",
+    "trace_commitment",
+    "quotient_commitment",
+    "trace_local",
+    "trace_next",
+    "quotient_chunks",
+    "fri_commit_phase",
+    "fri_queries",
+    "fri_final_poly",
+    "fri_pow_witness",
+];
+
+/// A named range of the calldata array.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Section {
+    pub name: &'static str,
+    pub offset: usize,
+    pub len: usize,
+}
+
+#[derive(Default)]
+struct Writer {
+    elements: Vec<Val>,
+    sections: Vec<Section>,
+}
+
+impl Writer {
+    fn section(&mut self, name: &'static str, write: impl FnOnce(&mut Self)) {
+        let offset = self.elements.len();
+        write(self);
+        self.sections.push(Section { name, offset, len: self.elements.len() - offset });
+    }
+
+    fn push(&mut self, x: Val) {
+        self.elements.push(x);
+    }
+
+    fn len_prefix(&mut self, n: usize) {
+        self.push(Val::from_canonical_usize(n));
+    }
+
+    fn ext(&mut self, x: &Challenge) {
+        self.elements.extend_from_slice(x.as_base_slice());
+    }
+
+    fn exts(&mut self, xs: &[Challenge]) {
+        self.len_prefix(xs.len());
+        xs.iter().for_each(|x| self.ext(x));
+    }
+
+    fn digest(&mut self, d: &[Val; 8]) {
+        self.elements.extend_from_slice(d);
+    }
+
+    fn commitment(&mut self, c: &Hash<Val, Val, 8>) {
+        let d: [Val; 8] = c.clone().into();
+        self.digest(&d);
+    }
+
+    fn path(&mut self, path: &[[Val; 8]]) {
+        self.len_prefix(path.len());
+        path.iter().for_each(|d| self.digest(d));
+    }
+}
+
+/// The calldata array for `proof`, and where each section starts.
+pub fn calldata_layout(proof: &Proof<MyConfig>, public_values: &[Val]) -> (Vec<Val>, Vec<Section>) {
+    let mut w = Writer::default();
+
+    w.section("degree_bits", |w| w.push(Val::from_canonical_usize(proof.degree_bits)));
+    w.section("public_values", |w| {
+        w.len_prefix(public_values.len());
+        w.elements.extend_from_slice(public_values);
+    });
+    w.section("trace_commitment", |w| w.commitment(&proof.commitments.trace));
+    w.section("quotient_commitment", |w| w.commitment(&proof.commitments.quotient_chunks));
+
+    let opened = &proof.opened_values;
+    w.section("trace_local", |w| w.exts(&opened.trace_local));
+    w.section("trace_next", |w| w.exts(&opened.trace_next));
+    w.section("quotient_chunks", |w| {
+        w.len_prefix(opened.quotient_chunks.len());
+        opened.quotient_chunks.iter().for_each(|chunk| w.exts(chunk));
+    });
+
+    let fri = &proof.opening_proof;
+    w.section("fri_commit_phase", |w| {
+        w.len_prefix(fri.commit_phase_commits.len());
+        fri.commit_phase_commits.iter().for_each(|c| w.commitment(c));
+    });
+    w.section("fri_queries", |w| {
+        w.len_prefix(fri.query_proofs.len());
+        for query in &fri.query_proofs {
+            w.len_prefix(query.input_proof.len());
+            for batch in &query.input_proof {
+                w.len_prefix(batch.opened_values.len());
+                for values in &batch.opened_values {
+                    w.len_prefix(values.len());
+                    w.elements.extend_from_slice(values);
+                }
+                w.path(&batch.opening_proof);
+            }
+
+            w.len_prefix(query.commit_phase_openings.len());
+            for step in &query.commit_phase_openings {
+                w.ext(&step.sibling_value);
+                w.path(&step.opening_proof);
+            }
+        }
+    });
+    w.section("fri_final_poly", |w| w.ext(&fri.final_poly));
+    w.section("fri_pow_witness", |w| w.push(fri.pow_witness));
+
+    (w.elements, w.sections)
+}
+
+/// The calldata array for `proof`; see the module comment for the ordering.
+pub fn calldata_elements(proof: &Proof<MyConfig>, public_values: &[Val]) -> Vec<Val> {
+    calldata_layout(proof, public_values).0
+}
+
+/// Encodes elements as ABI `uint256[]` words: 32 bytes each, big-endian, canonical value.
+pub fn abi_words<F: PrimeField32>(elements: &[F]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(elements.len() * 32);
+    for x in elements {
+        bytes.extend([0u8; 28]);
+        bytes.extend(x.as_canonical_u32().to_be_bytes());
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use p3_keccak::Keccak256Hash;
+    use p3_symmetric::CryptographicHasher;
+    use rand::{rngs::StdRng, SeedableRng};
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::config::{default_config, perm_from_seed, Challenger, DEFAULT_NUM_QUERIES};
+    use crate::determinism::prove_deterministic;
+    use crate::simple_state::{random_checked_trace_with_rng, SimpleStateChecked};
+
+    // The calldata of a fixed-seed proof is pinned by its length and Keccak digest in this file, written with
+    //   COOK_BLESS_GOLDEN=1 cargo test -r calldata_layout_golden
+    // like the golden proofs in `tests/golden.rs`.
+    const GOLDEN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/evm_calldata.toml");
+    const BLESS_VAR: &str = "COOK_BLESS_GOLDEN";
+    const SEED: u64 = 0x65766d;
+
+    #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+    struct CalldataDigest {
+        bytes: usize,
+        keccak: String,
+    }
+
+    fn section<'a>(elements: &'a [Val], sections: &[Section], name: &str) -> &'a [Val] {
+        let s = sections.iter().find(|s| s.name == name).unwrap();
+        &elements[s.offset..s.offset + s.len]
+    }
+
+    #[test]
+    fn test_calldata_layout_golden() {
+        let perm = perm_from_seed(SEED);
+        let config = default_config(&perm);
+        let (trace, public_values) = random_checked_trace_with_rng::<Val, _>(4, &mut StdRng::seed_from_u64(SEED));
+
+        let mut p_challenger = Challenger::new(perm);
+        let proof = prove_deterministic(&config, &SimpleStateChecked {}, &mut p_challenger, trace, &public_values);
+        let (elements, sections) = calldata_layout(&proof, &public_values);
+
+        // sections are contiguous, in the documented order, and cover the whole array
+        assert_eq!(sections.iter().map(|s| s.name).collect::<Vec<_>>(), SECTIONS);
+        let mut offset = 0;
+        for s in &sections {
+            assert_eq!(s.offset, offset, "{} is not contiguous", s.name);
+            offset += s.len;
+        }
+        assert_eq!(offset, elements.len());
+
+        // each section holds the proof field it is named after
+        assert_eq!(section(&elements, &sections, "degree_bits"), &[Val::from_canonical_usize(4)]);
+        let pis = [&[Val::two()][..], &public_values].concat();
+        assert_eq!(section(&elements, &sections, "public_values"), &pis[..]);
+        let trace_digest: [Val; 8] = proof.commitments.trace.clone().into();
+        assert_eq!(section(&elements, &sections, "trace_commitment"), &trace_digest);
+        let quotient_digest: [Val; 8] = proof.commitments.quotient_chunks.clone().into();
+        assert_eq!(section(&elements, &sections, "quotient_commitment"), &quotient_digest);
+
+        let trace_local = section(&elements, &sections, "trace_local");
+        assert_eq!(trace_local[0], Val::from_canonical_usize(proof.opened_values.trace_local.len()));
+        assert_eq!(trace_local.len(), 1 + 4 * proof.opened_values.trace_local.len());
+        assert_eq!(&trace_local[1..5], proof.opened_values.trace_local[0].as_base_slice());
+
+        let queries = section(&elements, &sections, "fri_queries");
+        assert_eq!(queries[0], Val::from_canonical_usize(DEFAULT_NUM_QUERIES));
+
+        assert_eq!(section(&elements, &sections, "fri_final_poly"), proof.opening_proof.final_poly.as_base_slice());
+        assert_eq!(elements.last(), Some(&proof.opening_proof.pow_witness));
+
+        // the leading words are known without proving: degree_bits 4, then 2 public values starting at 100000
+        let calldata = abi_words(&elements);
+        let word = |value: u32| [[0u8; 28].as_slice(), &value.to_be_bytes()].concat();
+        assert_eq!(&calldata[..96], [word(4), word(2), word(100000)].concat());
+
+        let actual = CalldataDigest {
+            bytes: calldata.len(),
+            keccak: Keccak256Hash {}.hash_iter(calldata).iter().map(|b| format!("{:02x}", b)).collect(),
+        };
+        if env::var(BLESS_VAR).is_ok_and(|v| v == "1") {
+            fs::create_dir_all(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden")).unwrap();
+            fs::write(GOLDEN_PATH, toml::to_string(&actual).unwrap()).unwrap();
+            return;
+        }
+        let text = fs::read_to_string(GOLDEN_PATH)
+            .unwrap_or_else(|e| panic!("cannot read {} ({}); run with {}=1 to create it", GOLDEN_PATH, e, BLESS_VAR));
+        let golden: CalldataDigest = toml::from_str(&text).unwrap();
+        assert_eq!(actual, golden, "calldata changed; if intended, re-bless with {}=1", BLESS_VAR);
+    }
+
+    #[test]
+    fn test_abi_words() {
+        let words = abi_words(&[Val::from_canonical_u32(0x01020304), Val::one()]);
+        assert_eq!(words.len(), 64);
+        assert!(words[..28].iter().all(|&b| b == 0));
+        assert_eq!(&words[28..32], &[1, 2, 3, 4]);
+        assert_eq!(words[63], 1);
+    }
+}
@@ -0,0 +1 @@
+pub mod nova_stub;
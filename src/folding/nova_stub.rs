@@ -0,0 +1,174 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilderWithPublicValues, BaseAir};
+use p3_challenger::{CanObserve, CanSample};
+#[cfg(not(feature = "verifier-only"))]
+use p3_commit::Pcs as _;
+use p3_field::Field;
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_symmetric::Hash;
+#[cfg(not(feature = "verifier-only"))]
+use p3_uni_stark::{prove, StarkGenericConfig};
+use p3_uni_stark::{verify, Proof};
+
+use crate::config::{Challenger, MyConfig, Perm, Val};
+use crate::error::CookError;
+
+// The shape of a Nova folding step, with field elements standing in for the group elements.
+//
+// Nova folds two relaxed R1CS instances into one: `folded = instance_1 + r * instance_2 + r^2 * cross_term`,
+// where `r` is drawn only after the prover is bound to both instances and the cross term. In real Nova the
+// instance entries (the witness commitment and the error term) are curve points and the combination is a
+// multi-scalar multiplication; here each row folds one scalar entry, so the AIR documents the interface a
+// step AIR hands to the accumulator (instances plus cross term in, folded instance out) rather than
+// implementing the scheme.
+//
+// `r` is sampled from the challenger after observing a commitment to the three input columns, as in
+// `examples/challenge_const.rs`, and is the single public value.
+
+pub const ACC_ROW_WIDTH: usize = 4;
+
+pub struct AccumulatorAir {}
+
+impl<F> BaseAir<F> for AccumulatorAir {
+    fn width(&self) -> usize {
+        ACC_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues> Air<AB> for AccumulatorAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = main.row_slice(0);
+        let local: &AccumulatorRow<AB::Var> = (*local).borrow();
+
+        let r: AB::Expr = builder.public_values()[0].into();
+
+        builder.assert_eq(
+            local.folded,
+            local.instance_1 + r.clone() * local.instance_2 + r.clone() * r * local.cross_term,
+        );
+    }
+}
+
+pub struct AccumulatorRow<F> {
+    pub instance_1: F,
+    pub instance_2: F,
+    pub cross_term: F,
+    pub folded: F,
+}
+
+impl<F> Borrow<AccumulatorRow<F>> for [F] {
+    fn borrow(&self) -> &AccumulatorRow<F> {
+        debug_assert_eq!(self.len(), ACC_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<AccumulatorRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+/// Folds two instances entrywise.
+pub fn fold<F: Field>(instance_1: &[F], instance_2: &[F], cross_term: &[F], r: F) -> Vec<F> {
+    instance_1
+        .iter()
+        .zip(instance_2)
+        .zip(cross_term)
+        .map(|((&a, &b), &t)| a + r * b + r.square() * t)
+        .collect()
+}
+
+/// The accumulator trace for folding with `r`; one row per instance entry.
+pub fn accumulator_trace<F: Field>(instance_1: &[F], instance_2: &[F], cross_term: &[F], r: F) -> RowMajorMatrix<F> {
+    let n = instance_1.len();
+    assert!(n == instance_2.len() && n == cross_term.len(), "instances and cross term must have the same length");
+    assert!(n.is_power_of_two(), "trace height must be a power of two");
+
+    let folded = fold(instance_1, instance_2, cross_term, r);
+    let values = (0..n).flat_map(|i| [instance_1[i], instance_2[i], cross_term[i], folded[i]]).collect();
+    RowMajorMatrix::new(values, ACC_ROW_WIDTH)
+}
+
+pub struct FoldProof {
+    /// commitment to the `(instance_1, instance_2, cross_term)` columns, observed before drawing `r`
+    pub inputs_commitment: Hash<Val, Val, 8>,
+    pub r: Val,
+    pub proof: Proof<MyConfig>,
+}
+
+fn draw_r(challenger: &mut Challenger, inputs_commitment: &Hash<Val, Val, 8>) -> Val {
+    challenger.observe(inputs_commitment.clone());
+    challenger.sample()
+}
+
+/// Proves one fold, returning the proof and the folded instance.
+#[cfg(not(feature = "verifier-only"))]
+pub fn prove_fold(
+    config: &MyConfig,
+    perm: &Perm,
+    instance_1: &[Val],
+    instance_2: &[Val],
+    cross_term: &[Val],
+) -> (FoldProof, Vec<Val>) {
+    let inputs = (0..instance_1.len()).flat_map(|i| [instance_1[i], instance_2[i], cross_term[i]]).collect();
+    let pcs = config.pcs();
+    let domain = pcs.natural_domain_for_degree(instance_1.len());
+    let (inputs_commitment, _) = pcs.commit(vec![(domain, RowMajorMatrix::new(inputs, 3))]);
+
+    let mut challenger = Challenger::new(perm.clone());
+    let r = draw_r(&mut challenger, &inputs_commitment);
+    let trace = accumulator_trace(instance_1, instance_2, cross_term, r);
+    let folded = trace.values.chunks_exact(ACC_ROW_WIDTH).map(|row| row[3]).collect();
+
+    let proof = prove(config, &AccumulatorAir {}, &mut challenger, trace, &vec![r]);
+    (FoldProof { inputs_commitment, r, proof }, folded)
+}
+
+pub fn verify_fold(config: &MyConfig, perm: &Perm, proof: &FoldProof) -> Result<(), CookError> {
+    let mut challenger = Challenger::new(perm.clone());
+    if draw_r(&mut challenger, &proof.inputs_commitment) != proof.r {
+        return Err(CookError::PublicValues("r was not drawn from the transcript".to_string()));
+    }
+    verify(config, &AccumulatorAir {}, &mut challenger, &proof.proof, &vec![proof.r])
+        .map_err(|e| CookError::Verification(e.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_field::AbstractField;
+    use rand::{thread_rng, Rng};
+
+    use super::*;
+    use crate::config::{default_config, random_perm};
+
+    fn random_vec(n: usize) -> Vec<Val> {
+        let mut rng = thread_rng();
+        (0..n).map(|_| rng.gen()).collect()
+    }
+
+    #[test]
+    fn test_accumulator_constraints() {
+        let (a, b, t) = (random_vec(16), random_vec(16), random_vec(16));
+        let r = Val::from_canonical_u32(5);
+        let mut trace = accumulator_trace(&a, &b, &t, r);
+        crate::assert_constraints_ok!(&AccumulatorAir {}, &trace, &[r]);
+
+        // the folded entry is pinned by r
+        crate::assert_constraints_fail!(&AccumulatorAir {}, &trace, &[r + Val::one()], 0);
+        trace.row_mut(3)[3] += Val::one();
+        crate::assert_constraints_fail!(&AccumulatorAir {}, &trace, &[r], 3);
+    }
+
+    #[test]
+    fn test_fold_round_trip() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let (a, b, t) = (random_vec(32), random_vec(32), random_vec(32));
+
+        let (proof, folded) = prove_fold(&config, &perm, &a, &b, &t);
+        assert_eq!(folded, fold(&a, &b, &t, proof.r));
+        verify_fold(&config, &perm, &proof).unwrap();
+    }
+}
@@ -0,0 +1,202 @@
+use p3_air::AirBuilder;
+use p3_field::{AbstractField, PrimeField32};
+
+pub use super::less_than::{assert_lt, lt_witness};
+use super::inverse_or_zero::inverse_or_zero;
+use super::less_than::{assert_bit_decomposition, bit_decompose};
+
+// The ordered comparisons are all `assert_lt` with the operands swapped or the `- 1` dropped, so they share
+// its range: both operands must be below `2^diff_bits.len()`.
+//
+//   a <  b:  b - a - 1 in [0, 2^n)
+//   a <= b:  b - a     in [0, 2^n)
+//   a >  b:  a - b - 1 in [0, 2^n)
+//   a >= b:  a - b     in [0, 2^n)
+//   a != b:  (a - b) * inv == 1, with `inv` the `InverseOrZero` witness of `a - b`
+
+/// Constrains `a <= b` given the bits of `b - a`.
+pub fn assert_le<AB: AirBuilder>(
+    builder: &mut AB,
+    a: impl Into<AB::Expr>,
+    b: impl Into<AB::Expr>,
+    diff_bits: &[AB::Var],
+) {
+    let (a, b): (AB::Expr, AB::Expr) = (a.into(), b.into());
+    assert_bit_decomposition(builder, b - a, diff_bits);
+}
+
+/// Constrains `a > b` given the bits of `a - b - 1`.
+pub fn assert_gt<AB: AirBuilder>(
+    builder: &mut AB,
+    a: impl Into<AB::Expr>,
+    b: impl Into<AB::Expr>,
+    diff_bits: &[AB::Var],
+) {
+    assert_lt(builder, b, a, diff_bits);
+}
+
+/// Constrains `a >= b` given the bits of `a - b`.
+pub fn assert_ge<AB: AirBuilder>(
+    builder: &mut AB,
+    a: impl Into<AB::Expr>,
+    b: impl Into<AB::Expr>,
+    diff_bits: &[AB::Var],
+) {
+    assert_le(builder, b, a, diff_bits);
+}
+
+/// Constrains `a != b` given `inv = (a - b)^-1`.
+pub fn assert_ne<AB: AirBuilder>(builder: &mut AB, a: impl Into<AB::Expr>, b: impl Into<AB::Expr>, inv: AB::Var) {
+    let (a, b): (AB::Expr, AB::Expr) = (a.into(), b.into());
+    builder.assert_one((a - b) * inv);
+}
+
+/// Witness for `assert_le`.
+pub fn le_witness<F: PrimeField32>(a: F, b: F, n_bits: usize) -> Vec<F> {
+    bit_decompose(b - a, n_bits)
+}
+
+/// Witness for `assert_gt`.
+pub fn gt_witness<F: PrimeField32>(a: F, b: F, n_bits: usize) -> Vec<F> {
+    lt_witness(b, a, n_bits)
+}
+
+/// Witness for `assert_ge`.
+pub fn ge_witness<F: PrimeField32>(a: F, b: F, n_bits: usize) -> Vec<F> {
+    le_witness(b, a, n_bits)
+}
+
+/// Witness for `assert_ne`; zero (and unsatisfiable) when `a == b`.
+pub fn ne_witness<F: PrimeField32>(a: F, b: F) -> F {
+    inverse_or_zero(a - b)
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_air::{Air, BaseAir};
+    use p3_baby_bear::BabyBear;
+    use p3_matrix::dense::RowMajorMatrix;
+    use p3_matrix::Matrix;
+
+    use super::*;
+    use crate::{assert_constraints_fail, assert_constraints_ok};
+
+    const N_BITS: usize = 8;
+
+    #[derive(Clone, Copy)]
+    enum Op {
+        Le,
+        Gt,
+        Ge,
+        Ne,
+    }
+
+    /// `[a, b, witness..]` per row, the witness being `diff_bits` or, for `Ne`, `inv` then zeros.
+    struct CompareAir {
+        op: Op,
+    }
+
+    impl<F> BaseAir<F> for CompareAir {
+        fn width(&self) -> usize {
+            2 + N_BITS
+        }
+    }
+
+    impl<AB: AirBuilder> Air<AB> for CompareAir {
+        fn eval(&self, builder: &mut AB) {
+            let main = builder.main();
+            let local = main.row_slice(0);
+            let (a, b, witness) = (local[0], local[1], &local[2..]);
+            match self.op {
+                Op::Le => assert_le(builder, a, b, witness),
+                Op::Gt => assert_gt(builder, a, b, witness),
+                Op::Ge => assert_ge(builder, a, b, witness),
+                Op::Ne => assert_ne(builder, a, b, witness[0]),
+            }
+        }
+    }
+
+    /// One row per pair, with the honest witness where the relation holds within `N_BITS` and zeros elsewhere.
+    fn trace_of(op: Op, pairs: &[(u32, u32)]) -> RowMajorMatrix<BabyBear> {
+        let values = pairs
+            .iter()
+            .flat_map(|&(a, b)| {
+                let in_range = |diff: Option<u32>| diff.is_some_and(|d| d >> N_BITS == 0);
+                let holds = match op {
+                    Op::Le => in_range(b.checked_sub(a)),
+                    Op::Gt => in_range(a.checked_sub(b + 1)),
+                    Op::Ge => in_range(a.checked_sub(b)),
+                    Op::Ne => a != b,
+                };
+                let mut witness = vec![BabyBear::zero(); N_BITS];
+                if holds {
+                    match op {
+                        Op::Le => witness = le_witness(f(a), f(b), N_BITS),
+                        Op::Gt => witness = gt_witness(f(a), f(b), N_BITS),
+                        Op::Ge => witness = ge_witness(f(a), f(b), N_BITS),
+                        Op::Ne => witness[0] = ne_witness(f(a), f(b)),
+                    }
+                }
+                [vec![f(a), f(b)], witness].concat()
+            })
+            .collect();
+        RowMajorMatrix::new(values, 2 + N_BITS)
+    }
+
+    fn f(x: u32) -> BabyBear {
+        BabyBear::from_canonical_u32(x)
+    }
+
+    fn recompose(bits: &[BabyBear]) -> u32 {
+        bits.iter().rev().fold(0, |acc, b| acc * 2 + b.as_canonical_u32())
+    }
+
+    #[test]
+    fn test_witnesses_encode_differences() {
+        assert_eq!(recompose(&lt_witness(f(3), f(10), 8)), 6);
+        assert_eq!(recompose(&le_witness(f(10), f(10), 8)), 0);
+        assert_eq!(recompose(&gt_witness(f(10), f(3), 8)), 6);
+        assert_eq!(recompose(&ge_witness(f(10), f(3), 8)), 7);
+        assert_eq!(ne_witness(f(10), f(3)) * f(7), BabyBear::one());
+        assert_eq!(ne_witness(f(10), f(10)), BabyBear::zero());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_lt_witness_rejects_ge() {
+        lt_witness(f(10), f(10), 8);
+    }
+
+    #[test]
+    fn test_le_constraints() {
+        let air = CompareAir { op: Op::Le };
+        assert_constraints_ok!(&air, &trace_of(Op::Le, &[(3, 10), (10, 10), (0, 255), (0, 0)]), &[]);
+        assert_constraints_fail!(&air, &trace_of(Op::Le, &[(3, 10), (11, 10), (0, 0), (0, 0)]), &[], 1);
+        // `b - a` out of range
+        assert_constraints_fail!(&air, &trace_of(Op::Le, &[(0, 0), (0, 0), (0, 256), (0, 0)]), &[], 2);
+    }
+
+    #[test]
+    fn test_gt_constraints() {
+        let air = CompareAir { op: Op::Gt };
+        assert_constraints_ok!(&air, &trace_of(Op::Gt, &[(10, 3), (11, 10), (255, 0), (1, 0)]), &[]);
+        // equal values are not greater
+        assert_constraints_fail!(&air, &trace_of(Op::Gt, &[(10, 3), (10, 10), (1, 0), (1, 0)]), &[], 1);
+        assert_constraints_fail!(&air, &trace_of(Op::Gt, &[(10, 3), (11, 10), (3, 10), (1, 0)]), &[], 2);
+    }
+
+    #[test]
+    fn test_ge_constraints() {
+        let air = CompareAir { op: Op::Ge };
+        assert_constraints_ok!(&air, &trace_of(Op::Ge, &[(10, 3), (10, 10), (255, 0), (0, 0)]), &[]);
+        assert_constraints_fail!(&air, &trace_of(Op::Ge, &[(10, 10), (9, 10), (0, 0), (0, 0)]), &[], 1);
+    }
+
+    #[test]
+    fn test_ne_constraints() {
+        let air = CompareAir { op: Op::Ne };
+        assert_constraints_ok!(&air, &trace_of(Op::Ne, &[(10, 3), (3, 10), (0, 1), (255, 0)]), &[]);
+        // equal values have no inverse to offer
+        assert_constraints_fail!(&air, &trace_of(Op::Ne, &[(10, 3), (3, 10), (7, 7), (255, 0)]), &[], 2);
+    }
+}
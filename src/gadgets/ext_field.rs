@@ -0,0 +1,137 @@
+use p3_air::{Air, AirBuilder, BaseAir};
+use p3_field::{AbstractField, Field};
+use p3_matrix::Matrix;
+
+// Multiplication in a degree-`D` extension `F[x] / m(x)`, with `m` monic and irreducible of degree `D`:
+// elements are their `D` coefficients, lowest degree first, and `c == a * b` is the `D x D` schoolbook
+// product, `2D - 1` coefficients, reduced with `x^D == -(m_0 + m_1 x + ... + m_{D-1} x^{D-1})` from the top
+// coefficient down. Every reduced coefficient is linear in the products `a_i * b_j`, so the constraints
+// have degree 2 whatever `m` is.
+//
+// `m` is a parameter of the AIR rather than a column, fixed by whoever builds it, so the verifier checks the
+// extension it expects. Plonky3's `BinomialExtensionField<F, D>` is the case `m(x) = x^D - W`; the tests
+// cover it for `D = 2` over Goldilocks and `D = 4` over BabyBear.
+
+/// Constrains `c == a * b` in `F[x] / m(x)`, where `modulus` holds the coefficients of `m` below `x^D`.
+pub fn assert_ext_mul<AB: AirBuilder, const D: usize>(
+    builder: &mut AB,
+    a: &[AB::Var; D],
+    b: &[AB::Var; D],
+    c: &[AB::Var; D],
+    modulus: &[AB::F; D],
+) {
+    let product = ext_mul::<_, AB::Expr, D>(&a.map(Into::into), &b.map(Into::into), modulus);
+    for (c, product) in c.iter().zip(product) {
+        builder.assert_eq(*c, product);
+    }
+}
+
+/// `a * b` in `F[x] / m(x)` for operands that are expressions, such as a column minus a public value.
+pub fn ext_mul<F, E, const D: usize>(a: &[E; D], b: &[E; D], modulus: &[F; D]) -> [E; D]
+where
+    F: Copy,
+    E: AbstractField + From<F>,
+{
+    let mut product = vec![E::zero(); 2 * D - 1];
+    for i in 0..D {
+        for j in 0..D {
+            product[i + j] += a[i].clone() * b[j].clone();
+        }
+    }
+    for k in (D..2 * D - 1).rev() {
+        let top = product[k].clone();
+        for i in 0..D {
+            product[k - D + i] -= top.clone() * E::from(modulus[i]);
+        }
+    }
+    core::array::from_fn(|i| product[i].clone())
+}
+
+/// One extension multiplication `(a, b, c)` per row.
+pub struct ExtFieldAir<F, const D: usize> {
+    /// the coefficients of the defining polynomial below `x^D`
+    pub modulus: [F; D],
+}
+
+impl<F: Field, const D: usize> ExtFieldAir<F, D> {
+    /// The AIR for `F[x] / (x^D - w)`, Plonky3's binomial extensions.
+    pub fn binomial(w: F) -> Self {
+        let mut modulus = [F::zero(); D];
+        modulus[0] = -w;
+        ExtFieldAir { modulus }
+    }
+}
+
+impl<F: Sync, const D: usize> BaseAir<F> for ExtFieldAir<F, D> {
+    fn width(&self) -> usize {
+        3 * D
+    }
+}
+
+impl<AB: AirBuilder, const D: usize> Air<AB> for ExtFieldAir<AB::F, D> {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local = main.row_slice(0);
+        let operand = |k: usize| -> [AB::Var; D] { local[k * D..(k + 1) * D].try_into().unwrap() };
+        assert_ext_mul(builder, &operand(0), &operand(1), &operand(2), &self.modulus);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_baby_bear::BabyBear;
+    use p3_field::extension::{BinomialExtensionField, BinomiallyExtendable};
+    use p3_field::AbstractExtensionField;
+    use p3_goldilocks::Goldilocks;
+    use p3_matrix::dense::RowMajorMatrix;
+    use rand::distributions::{Distribution, Standard};
+    use rand::{thread_rng, Rng};
+
+    use super::*;
+    use crate::{assert_constraints_fail, assert_constraints_ok};
+
+    /// `W` of `BinomialExtensionField<F, D>`, read off the field itself as `x^D`.
+    fn w<F: BinomiallyExtendable<D>, const D: usize>() -> F {
+        let mut x = [F::zero(); D];
+        x[1] = F::one();
+        let x_d = BinomialExtensionField::<F, D>::from_base_slice(&x).exp_u64(D as u64);
+        x_d.as_base_slice()[0]
+    }
+
+    /// Rows of random `(a, b, a * b)`, multiplied by `BinomialExtensionField`.
+    fn trace<F: BinomiallyExtendable<D>, const D: usize>(height: usize) -> RowMajorMatrix<F>
+    where
+        Standard: Distribution<BinomialExtensionField<F, D>>,
+    {
+        let mut rng = thread_rng();
+        let values = (0..height).flat_map(|_| {
+            let (a, b): (BinomialExtensionField<F, D>, BinomialExtensionField<F, D>) = (rng.gen(), rng.gen());
+            [a.as_base_slice(), b.as_base_slice(), (a * b).as_base_slice()].concat()
+        });
+        RowMajorMatrix::new(values.collect(), 3 * D)
+    }
+
+    #[test]
+    fn test_quadratic_extension() {
+        let air = ExtFieldAir::<Goldilocks, 2>::binomial(w::<Goldilocks, 2>());
+        assert_constraints_ok!(&air, &trace::<Goldilocks, 2>(8), &[]);
+    }
+
+    #[test]
+    fn test_quartic_extension() {
+        let air = ExtFieldAir::<BabyBear, 4>::binomial(w::<BabyBear, 4>());
+        assert_constraints_ok!(&air, &trace::<BabyBear, 4>(8), &[]);
+    }
+
+    #[test]
+    fn test_wrong_product_or_modulus_fails() {
+        let mut trace = trace::<BabyBear, 4>(8);
+        let air = ExtFieldAir::<BabyBear, 4>::binomial(w::<BabyBear, 4>());
+        // another `W` is another field
+        let other = ExtFieldAir::<BabyBear, 4>::binomial(w::<BabyBear, 4>() + BabyBear::one());
+        assert_constraints_fail!(&other, &trace, &[], 0);
+
+        trace.row_mut(5)[3 * 4 - 1] += BabyBear::one();
+        assert_constraints_fail!(&air, &trace, &[], 5);
+    }
+}
@@ -0,0 +1,109 @@
+use p3_air::AirBuilder;
+use p3_field::{AbstractField, PrimeField32};
+
+use crate::gadgets::less_than::{assert_bit_decomposition, bit_decompose};
+
+// Unsigned fixed-point numbers: a real `x` is the field element `round(x * 2^SCALE)`.
+//
+// Addition is field addition. Multiplication needs a shift, `result = (a * b) >> SCALE`, which the prover
+// supplies with the bits it drops:
+//   a * b == result * 2^SCALE + remainder,   0 <= remainder < 2^SCALE,   0 <= result < 2^RESULT_BITS
+// This is an integer identity only while `a * b` doesn't wrap the field, so the operands must keep their
+// product below `2^MUL_BOUND_BITS`; the caller range checks them (e.g. a value below 1.0 times a weight
+// below 0.25). Within that bound the range checks make `result` and `remainder` unique: any other
+// remainder forces a `result` nowhere near `2^RESULT_BITS`.
+//
+// BabyBear leaves little headroom: with 16 fractional bits a product has 32, so exact products of larger
+// values need limbs, as in `examples/bls_stub.rs`.
+
+pub const SCALE: usize = 16;
+/// `a * b` must be below `2^MUL_BOUND_BITS` for `assert_fixed_mul`.
+pub const MUL_BOUND_BITS: usize = 30;
+pub const RESULT_BITS: usize = MUL_BOUND_BITS - SCALE;
+
+/// The prover-supplied bits of a fixed-point product.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FixedMulOverflow<T> {
+    /// the low `SCALE` bits of `a * b`, dropped by the shift
+    pub remainder_bits: [T; SCALE],
+    pub result_bits: [T; RESULT_BITS],
+}
+
+impl<T> FixedMulOverflow<T> {
+    pub const WIDTH: usize = SCALE + RESULT_BITS;
+}
+
+/// Constrains `result == (a * b) >> SCALE`, for operands with `a * b < 2^MUL_BOUND_BITS`.
+pub fn assert_fixed_mul<AB: AirBuilder>(
+    builder: &mut AB,
+    a: impl Into<AB::Expr>,
+    b: impl Into<AB::Expr>,
+    result: impl Into<AB::Expr>,
+    overflow: &FixedMulOverflow<AB::Var>,
+) {
+    let (a, b, result): (AB::Expr, AB::Expr, AB::Expr) = (a.into(), b.into(), result.into());
+
+    let mut remainder = AB::Expr::zero();
+    for &bit in overflow.remainder_bits.iter().rev() {
+        builder.assert_bool(bit);
+        remainder = remainder.double() + bit;
+    }
+    assert_bit_decomposition(builder, result.clone(), &overflow.result_bits);
+
+    builder.assert_eq(a * b, result * AB::Expr::from_canonical_u32(1 << SCALE) + remainder);
+}
+
+/// Constrains `result == a + b`; fixed-point addition is plain field addition.
+pub fn assert_fixed_add<AB: AirBuilder>(
+    builder: &mut AB,
+    a: impl Into<AB::Expr>,
+    b: impl Into<AB::Expr>,
+    result: impl Into<AB::Expr>,
+) {
+    let (a, b): (AB::Expr, AB::Expr) = (a.into(), b.into());
+    builder.assert_eq(result, a + b);
+}
+
+/// `(a * b) >> SCALE` and its overflow bits; panics if the product is out of bounds.
+pub fn fixed_mul_witness<F: PrimeField32>(a: F, b: F) -> (F, FixedMulOverflow<F>) {
+    let product = a.as_canonical_u32() as u64 * b.as_canonical_u32() as u64;
+    assert!(
+        product >> MUL_BOUND_BITS == 0,
+        "{} * {} is out of fixed-point bounds",
+        a.as_canonical_u32(),
+        b.as_canonical_u32()
+    );
+
+    let result = F::from_canonical_u64(product >> SCALE);
+    let remainder = F::from_canonical_u64(product & ((1 << SCALE) - 1));
+    let overflow = FixedMulOverflow {
+        remainder_bits: bit_decompose(remainder, SCALE).try_into().unwrap(),
+        result_bits: bit_decompose(result, RESULT_BITS).try_into().unwrap(),
+    };
+    (result, overflow)
+}
+
+/// The fixed-point encoding of `x`, rounded to the nearest multiple of `2^-SCALE`.
+pub fn to_fixed(x: f64) -> u32 {
+    assert!(x >= 0.0, "fixed-point values are unsigned");
+    (x * (1u64 << SCALE) as f64).round() as u32
+}
+
+pub fn from_fixed(x: u32) -> f64 {
+    x as f64 / (1u64 << SCALE) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_baby_bear::BabyBear;
+
+    use super::*;
+
+    #[test]
+    fn test_fixed_mul_witness() {
+        let (a, b) = (to_fixed(0.75), to_fixed(0.2));
+        let (result, _) = fixed_mul_witness(BabyBear::from_canonical_u32(a), BabyBear::from_canonical_u32(b));
+        assert!((from_fixed(result.as_canonical_u32()) - 0.15).abs() < 2.0 / (1 << SCALE) as f64);
+        assert_eq!(from_fixed(to_fixed(1.5)), 1.5);
+    }
+}
@@ -0,0 +1,34 @@
+use p3_air::AirBuilder;
+use p3_field::{AbstractField, Field};
+
+// `inv` is `x^-1` when `x != 0` and `0` otherwise:
+//   x   * (x * inv - 1) == 0
+//   inv * (x * inv - 1) == 0
+// The first forces `inv` to be the inverse wherever `x` is nonzero, the second forces `inv` to be zero
+// wherever `x` is zero, so the witness is unique.
+//
+// The same witness gives an is-zero indicator: `1 - x * inv` is 1 exactly when `x == 0`.
+
+pub fn assert_inverse_or_zero<AB: AirBuilder>(builder: &mut AB, x: impl Into<AB::Expr>, inv: AB::Var) {
+    let x: AB::Expr = x.into();
+    let x_inv_minus_one = x.clone() * inv - AB::Expr::one();
+    builder.assert_zero(x * x_inv_minus_one.clone());
+    builder.assert_zero(x_inv_minus_one * inv);
+}
+
+/// Witness for `assert_inverse_or_zero`.
+pub fn inverse_or_zero<F: Field>(x: F) -> F {
+    x.try_inverse().unwrap_or(F::zero())
+}
+
+/// Constrains `is_zero` to be 1 when `x == 0` and 0 otherwise, with `inv` as in `assert_inverse_or_zero`.
+pub fn assert_is_zero<AB: AirBuilder>(builder: &mut AB, x: impl Into<AB::Expr>, inv: AB::Var, is_zero: AB::Var) {
+    let x: AB::Expr = x.into();
+    assert_inverse_or_zero(builder, x.clone(), inv);
+    builder.assert_eq(is_zero, AB::Expr::one() - x * inv);
+}
+
+/// Witness for `assert_is_zero`: `(inv, is_zero)`.
+pub fn is_zero_witness<F: Field>(x: F) -> (F, F) {
+    (inverse_or_zero(x), F::from_bool(x.is_zero()))
+}
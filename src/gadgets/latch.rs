@@ -0,0 +1,66 @@
+use p3_air::AirBuilder;
+use p3_field::AbstractField;
+
+// A latch is a boolean column that, once set, stays set: a loop's `done`, a state machine's `halted`. Across
+// a transition the next value is either the current one or 1,
+//   (next - cur) * (next - 1) == 0
+// which for booleans rules out exactly 1 -> 0. It is `zero_row_guard::assert_active_prefix` turned around:
+// there the flag may only drop, here it may only rise, so the rows after the latch is set are a suffix.
+
+/// Constrains `cur` to be boolean and, across the transition to `next`, never to go 1 -> 0.
+pub fn assert_latch<AB: AirBuilder>(builder: &mut AB, cur: AB::Var, next: AB::Var) {
+    builder.assert_bool(cur);
+    builder
+        .when_transition()
+        .assert_zero((next - cur) * (next - AB::Expr::one()));
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_air::{Air, BaseAir};
+    use p3_baby_bear::BabyBear;
+    use p3_matrix::dense::RowMajorMatrix;
+    use p3_matrix::Matrix;
+
+    use super::*;
+    use crate::{assert_constraints_fail, assert_constraints_ok};
+
+    struct LatchAir {}
+
+    impl<F> BaseAir<F> for LatchAir {
+        fn width(&self) -> usize {
+            1
+        }
+    }
+
+    impl<AB: AirBuilder> Air<AB> for LatchAir {
+        fn eval(&self, builder: &mut AB) {
+            let main = builder.main();
+            let (local, next) = (main.row_slice(0), main.row_slice(1));
+            assert_latch(builder, local[0], next[0]);
+        }
+    }
+
+    fn trace_of(flags: &[u32]) -> RowMajorMatrix<BabyBear> {
+        RowMajorMatrix::new_col(flags.iter().map(|&f| BabyBear::from_canonical_u32(f)).collect())
+    }
+
+    #[test]
+    fn test_latch_transitions_pass() {
+        for flags in [[0, 0, 1, 1], [0, 0, 0, 0], [1, 1, 1, 1], [0, 0, 0, 1]] {
+            assert_constraints_ok!(&LatchAir {}, &trace_of(&flags), &[]);
+        }
+    }
+
+    #[test]
+    fn test_unlatching_fails() {
+        assert_constraints_fail!(&LatchAir {}, &trace_of(&[0, 1, 0, 1]), &[], 1);
+        assert_constraints_fail!(&LatchAir {}, &trace_of(&[1, 1, 1, 0]), &[], 2);
+    }
+
+    #[test]
+    fn test_non_boolean_latch_fails() {
+        // 1 -> 2 passes neither `next == cur` nor `next == 1`
+        assert_constraints_fail!(&LatchAir {}, &trace_of(&[0, 0, 1, 2]), &[], 2);
+    }
+}
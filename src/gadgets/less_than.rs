@@ -0,0 +1,73 @@
+use p3_air::AirBuilder;
+use p3_field::{AbstractField, PrimeField32};
+
+// `a < b` for values known to be below `2^diff_bits.len()`.
+//
+// The prover supplies the bits of `b - a - 1`. If `a >= b` the difference wraps around the field to a value
+// near the modulus, which doesn't fit in `diff_bits.len() < 31` bits, so no valid decomposition exists.
+//
+// The indicator form doesn't assert the comparison but computes it: a boolean `lt` picks which difference is
+// range checked, `b - a - 1` when `lt == 1` and `a - b` when `lt == 0`. Exactly one of the two fits, so `lt`
+// is forced to be the truth value of `a < b`.
+
+/// Constrains `bits` to be boolean and to recompose to `value`, i.e. `0 <= value < 2^bits.len()`.
+pub fn assert_bit_decomposition<AB: AirBuilder>(builder: &mut AB, value: impl Into<AB::Expr>, bits: &[AB::Var]) {
+    debug_assert!(bits.len() < 31, "decomposition must not wrap the field");
+
+    let mut acc = AB::Expr::zero();
+    let mut pow = AB::Expr::one();
+    for &bit in bits {
+        builder.assert_bool(bit);
+        acc += pow.clone() * bit;
+        pow = pow.clone() + pow;
+    }
+    builder.assert_eq(acc, value);
+}
+
+/// Constrains `a < b` given the bits of `b - a - 1`.
+pub fn assert_lt<AB: AirBuilder>(
+    builder: &mut AB,
+    a: impl Into<AB::Expr>,
+    b: impl Into<AB::Expr>,
+    diff_bits: &[AB::Var],
+) {
+    let (a, b): (AB::Expr, AB::Expr) = (a.into(), b.into());
+    assert_bit_decomposition(builder, b - a - AB::Expr::one(), diff_bits);
+}
+
+/// Constrains the boolean `lt` to be `a < b`, given `diff`, the difference the indicator selects, and its
+/// bits.
+pub fn assert_lt_indicator<AB: AirBuilder>(
+    builder: &mut AB,
+    a: impl Into<AB::Expr>,
+    b: impl Into<AB::Expr>,
+    lt: AB::Var,
+    diff: AB::Var,
+    diff_bits: &[AB::Var],
+) {
+    let (a, b): (AB::Expr, AB::Expr) = (a.into(), b.into());
+    builder.assert_bool(lt);
+    let if_lt = b.clone() - a.clone() - AB::Expr::one();
+    let if_ge = a - b;
+    builder.assert_eq(diff, if_ge.clone() + (if_lt - if_ge) * lt);
+    assert_bit_decomposition(builder, diff, diff_bits);
+}
+
+/// Little-endian bits of `value`, as field elements.
+pub fn bit_decompose<F: PrimeField32>(value: F, n_bits: usize) -> Vec<F> {
+    let value = value.as_canonical_u32();
+    assert!(n_bits >= 32 || value >> n_bits == 0, "{} doesn't fit in {} bits", value, n_bits);
+    (0..n_bits).map(|i| F::from_canonical_u32((value >> i) & 1)).collect()
+}
+
+/// Witness for `assert_lt`.
+pub fn lt_witness<F: PrimeField32>(a: F, b: F, n_bits: usize) -> Vec<F> {
+    bit_decompose(b - a - F::one(), n_bits)
+}
+
+/// Witness for `assert_lt_indicator`: `(lt, diff, diff_bits)`.
+pub fn lt_indicator_witness<F: PrimeField32>(a: F, b: F, n_bits: usize) -> (F, F, Vec<F>) {
+    let lt = a.as_canonical_u32() < b.as_canonical_u32();
+    let diff = if lt { b - a - F::one() } else { a - b };
+    (F::from_bool(lt), diff, bit_decompose(diff, n_bits))
+}
@@ -0,0 +1,18 @@
+pub mod comparison;
+pub mod ext_field;
+pub mod fixed_point;
+pub mod inverse_or_zero;
+pub mod latch;
+pub mod less_than;
+pub mod one_hot;
+pub mod optional;
+pub mod projection;
+pub mod sentinel;
+pub mod sorted_lookup;
+pub mod subgroup;
+pub mod two_adic;
+pub mod uint256;
+pub mod xor;
+pub mod zero_row_guard;
+
+pub use latch::assert_latch;
@@ -0,0 +1,101 @@
+use p3_air::AirBuilder;
+use p3_field::AbstractField;
+
+// A one-hot vector of `k` selectors picks one of `k` states: every selector is boolean and exactly one is
+// set, which for booleans is the same as their sum being one. The selected value of a list is then the dot
+// product `sum(selectors[i] * values[i])`, adding one to the degree of the values. A single boolean is the
+// two-selector case, `select_if`.
+
+/// Constrains `selectors` to be boolean with exactly one of them set.
+pub fn assert_one_hot<AB: AirBuilder>(builder: &mut AB, selectors: &[AB::Var]) {
+    for &s in selectors {
+        builder.assert_bool(s);
+    }
+    builder.assert_one(selectors.iter().map(|&s| s.into()).sum::<AB::Expr>());
+}
+
+/// The value picked out by a one-hot `selectors`.
+pub fn select<V, E>(selectors: &[V], values: impl IntoIterator<Item = impl Into<E>>) -> E
+where
+    V: Into<E> + Copy,
+    E: AbstractField,
+{
+    let values = values.into_iter().map(Into::into).collect::<Vec<E>>();
+    assert_eq!(selectors.len(), values.len(), "one value per selector");
+    selectors.iter().zip(values).map(|(&s, v)| s.into() * v).sum()
+}
+
+/// `if_true` when the boolean `cond` is 1, `if_false` when it is 0: the two-way `select`.
+pub fn select_if<E: AbstractField>(cond: impl Into<E>, if_true: impl Into<E>, if_false: impl Into<E>) -> E {
+    let cond: E = cond.into();
+    let if_false: E = if_false.into();
+    cond * (if_true.into() - if_false.clone()) + if_false
+}
+
+/// Witness for `assert_one_hot`: `k` selectors with only `index` set.
+pub fn one_hot<F: AbstractField>(index: usize, k: usize) -> Vec<F> {
+    assert!(index < k, "index {} out of range for {} selectors", index, k);
+    (0..k).map(|i| F::from_bool(i == index)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_air::{Air, BaseAir};
+    use p3_baby_bear::BabyBear;
+    use p3_field::PrimeField32;
+    use p3_matrix::Matrix;
+    use p3_matrix::dense::RowMajorMatrix;
+
+    use super::*;
+    use crate::{assert_constraints_fail, assert_constraints_ok};
+
+    // per row: `(s0, s1, s2, selected)`, with `selected` one of 10, 20, 30
+    struct OneHotAir {}
+
+    impl<F> BaseAir<F> for OneHotAir {
+        fn width(&self) -> usize {
+            4
+        }
+    }
+
+    impl<AB: AirBuilder> Air<AB> for OneHotAir {
+        fn eval(&self, builder: &mut AB) {
+            let main = builder.main();
+            let local = main.row_slice(0);
+            assert_one_hot(builder, &local[..3]);
+            let choices = [10, 20, 30].map(AB::Expr::from_canonical_u32);
+            builder.assert_eq(local[3], select::<_, AB::Expr>(&local[..3], choices));
+        }
+    }
+
+    fn trace_of(rows: &[[u32; 4]]) -> RowMajorMatrix<BabyBear> {
+        RowMajorMatrix::new(rows.concat().into_iter().map(BabyBear::from_canonical_u32).collect(), 4)
+    }
+
+    #[test]
+    fn test_one_hot_rows_pass() {
+        let trace = trace_of(&[[1, 0, 0, 10], [0, 1, 0, 20], [0, 0, 1, 30], [0, 1, 0, 20]]);
+        assert_constraints_ok!(&OneHotAir {}, &trace, &[]);
+        assert_eq!(one_hot::<BabyBear>(1, 3), trace.row_slice(1)[..3].to_vec());
+    }
+
+    #[test]
+    fn test_no_selector_fails() {
+        let trace = trace_of(&[[1, 0, 0, 10], [0, 0, 0, 0], [0, 0, 1, 30], [0, 1, 0, 20]]);
+        assert_constraints_fail!(&OneHotAir {}, &trace, &[], 1);
+    }
+
+    #[test]
+    fn test_two_selectors_fail() {
+        let trace = trace_of(&[[1, 0, 0, 10], [0, 1, 0, 20], [1, 0, 1, 40], [0, 1, 0, 20]]);
+        assert_constraints_fail!(&OneHotAir {}, &trace, &[], 2);
+    }
+
+    #[test]
+    fn test_non_boolean_selectors_fail() {
+        // 2 - 1 sums to one but isn't one-hot
+        let minus_one = BabyBear::neg_one().as_canonical_u32();
+        let trace = trace_of(&[[1, 0, 0, 10], [2, minus_one, 0, 0], [0, 0, 1, 30], [0, 1, 0, 20]]);
+        assert_constraints_fail!(&OneHotAir {}, &trace, &[], 1);
+    }
+}
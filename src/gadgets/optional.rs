@@ -0,0 +1,53 @@
+use p3_air::AirBuilder;
+use p3_field::{AbstractField, Field};
+use p3_matrix::dense::RowMajorMatrix;
+
+// Optional columns let rows of different kinds share one trace. A boolean `mode` column says whether the
+// optional columns are in use on a row; when they aren't, they must be zero, so a row can't smuggle data
+// through columns it claims not to use. Constraints that read optional columns should be gated with
+// `builder.when(mode)` so the zero fill doesn't make them fail.
+
+/// Constrains `mode` to be boolean and every column in `columns` to be zero where `mode == 0`.
+pub fn assert_optional_columns<AB: AirBuilder>(builder: &mut AB, mode: AB::Var, columns: &[AB::Var]) {
+    builder.assert_bool(mode);
+    for &col in columns {
+        builder.assert_zero((AB::Expr::one() - mode) * col);
+    }
+}
+
+/// Zero-fills `columns` on every row where the `mode_col` column is 0.
+pub fn zero_fill_unused<F: Field>(trace: &mut RowMajorMatrix<F>, mode_col: usize, columns: &[usize]) {
+    let width = trace.width;
+    for row in trace.values.chunks_exact_mut(width) {
+        if row[mode_col].is_zero() {
+            for &col in columns {
+                row[col] = F::zero();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_baby_bear::BabyBear;
+
+    use super::*;
+
+    #[test]
+    fn test_zero_fill_unused() {
+        let values = [
+            1, 5, 6, //
+            0, 7, 8, //
+            1, 9, 10,
+        ];
+        let mut trace = RowMajorMatrix::new(values.map(BabyBear::from_canonical_u32).to_vec(), 3);
+        zero_fill_unused(&mut trace, 0, &[2]);
+
+        let expected = [
+            1, 5, 6, //
+            0, 7, 0, //
+            1, 9, 10,
+        ];
+        assert_eq!(trace.values, expected.map(BabyBear::from_canonical_u32).to_vec());
+    }
+}
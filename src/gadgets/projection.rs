@@ -0,0 +1,86 @@
+use p3_air::AirBuilder;
+
+// Two sub-AIRs laid side by side in one trace can talk through shared columns: the combined AIR runs each
+// on its own slice of the row and constrains a projection of one slice to equal a projection of the other.
+// On the generation side, `project_row` picks the same columns out of a row, so the values a sub-trace
+// exposes can be copied into the other's columns:
+//   combined row:   [ a: balance, input, output | b: opening, ... ]
+//   assert_column_equality(builder, &[a.balance], &[b.opening])
+// The projection is an ordinary equality on every row; what it means (a value handed from one machine to
+// the other, a shared input) is up to the combined AIR.
+
+/// Constrains `a_cols[i] == b_cols[i]` for every `i`.
+pub fn assert_column_equality<AB: AirBuilder>(builder: &mut AB, a_cols: &[AB::Var], b_cols: &[AB::Var]) {
+    assert_eq!(a_cols.len(), b_cols.len(), "projections must have the same width");
+    for (&a, &b) in a_cols.iter().zip(b_cols) {
+        builder.assert_eq(a, b);
+    }
+}
+
+/// The entries of `row` at `columns`, in that order.
+pub fn project_row<F: Clone>(row: &[F], columns: &[usize]) -> Vec<F> {
+    columns.iter().map(|&c| row[c].clone()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_air::{Air, BaseAir};
+    use p3_baby_bear::BabyBear;
+    use p3_field::AbstractField;
+    use p3_matrix::dense::RowMajorMatrix;
+    use p3_matrix::Matrix;
+
+    use super::*;
+    use crate::simple_state::{random_trace, SS_ROW_WIDTH};
+    use crate::{assert_constraints_fail, assert_constraints_ok};
+
+    /// The balance and input of `SimpleState`'s row, projected onto columns of their own.
+    const SHARED: [usize; 2] = [0, 1];
+
+    /// `SimpleState` next to a sub-AIR that only sees the shared columns and doubles the input.
+    struct Combined {}
+
+    impl<F> BaseAir<F> for Combined {
+        fn width(&self) -> usize {
+            SS_ROW_WIDTH + SHARED.len() + 1
+        }
+    }
+
+    impl<AB: AirBuilder> Air<AB> for Combined {
+        fn eval(&self, builder: &mut AB) {
+            let main = builder.main();
+            let (local, next) = (main.row_slice(0), main.row_slice(1));
+            let (state, rest) = local.split_at(SS_ROW_WIDTH);
+            let (shared, doubled) = (&rest[..SHARED.len()], rest[SHARED.len()]);
+
+            // `SimpleState` on the first slice
+            builder.when_transition().assert_eq(state[0] + state[1] - state[2], next[0]);
+            assert_column_equality(builder, &project_row(state, &SHARED), shared);
+            builder.assert_eq(doubled, shared[1] + shared[1]);
+        }
+    }
+
+    fn combined_trace() -> RowMajorMatrix<BabyBear> {
+        let state = random_trace::<BabyBear>(4);
+        let values = state.values.chunks_exact(SS_ROW_WIDTH).flat_map(|row| {
+            let shared = project_row(row, &SHARED);
+            let doubled = shared[1].double();
+            [row.to_vec(), shared, vec![doubled]].concat()
+        });
+        RowMajorMatrix::new(values.collect(), SS_ROW_WIDTH + SHARED.len() + 1)
+    }
+
+    #[test]
+    fn test_projected_columns_match() {
+        assert_eq!(project_row(&[5, 6, 7], &[2, 0]), [7, 5]);
+        assert_constraints_ok!(&Combined {}, &combined_trace(), &[]);
+    }
+
+    #[test]
+    fn test_diverging_projection_fails() {
+        let mut trace = combined_trace();
+        // the second machine sees a different balance than the first one has
+        trace.row_mut(6)[SS_ROW_WIDTH] += BabyBear::one();
+        assert_constraints_fail!(&Combined {}, &trace, &[], 6);
+    }
+}
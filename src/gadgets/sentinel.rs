@@ -0,0 +1,27 @@
+use p3_air::{AirBuilder, FilteredAirBuilder};
+use p3_field::AbstractField;
+
+// A sentinel column marks where a variable-length trace ends: it is 0 on every active row and 1 on every
+// padding row after them. Being boolean and non-decreasing means it flips from 0 to 1 at most once, so the
+// active rows are always a prefix of the trace.
+//
+// Constraints that only make sense on real data go through `when_active`, which multiplies them by
+// `1 - sentinel` so padding rows can hold anything (usually zeros).
+
+/// Constrains `sentinel` to be boolean and, across the transition to `next_sentinel`, never to go 1 -> 0.
+pub fn assert_sentinel<AB: AirBuilder>(builder: &mut AB, sentinel: AB::Var, next_sentinel: AB::Var) {
+    builder.assert_bool(sentinel);
+    builder.when_transition().assert_zero(sentinel * (AB::Expr::one() - next_sentinel));
+}
+
+/// Runs `f` with every constraint it asserts gated on `sentinel == 0`.
+///
+/// `f` receives a filtered builder rather than `AB` itself, since the gating lives in the filter.
+pub fn when_active<AB: AirBuilder>(
+    builder: &mut AB,
+    sentinel: impl Into<AB::Expr>,
+    f: impl FnOnce(&mut FilteredAirBuilder<'_, AB>),
+) {
+    let sentinel: AB::Expr = sentinel.into();
+    f(&mut builder.when(AB::Expr::one() - sentinel));
+}
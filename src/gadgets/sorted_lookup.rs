@@ -0,0 +1,151 @@
+use p3_air::AirBuilder;
+use p3_field::{AbstractField, PrimeField32};
+
+use super::one_hot::select_if;
+
+// Membership in a fixed, strictly increasing table of `2^LOG_N` entries by binary search, with arithmetic
+// in place of the hashing of a Merkle membership proof.
+//
+// Step `i` compares `value` with `path[i]`, the last entry of the left half of the current interval, and
+// `cmp_bits[i]` records the outcome: 0 keeps the left half (`value <= path[i]`), 1 the right one. The bits
+// are therefore the index of the entry the search ends on, most significant first, and each midpoint is
+// a fixed function of the bits before it, so the constraints are:
+//   every bit is boolean
+//   path[i] == the midpoint selected by cmp_bits[..i]        (degree i)
+//   value   == the entry selected by cmp_bits                  (degree LOG_N)
+// The comparisons themselves need no range check: in a strictly increasing table the entry `value` equals
+// has one index, and the bits of that index are exactly the outcomes of comparing `value` with the
+// midpoints. `LOG_N` is the constraint degree, so tables beyond 16 entries need a larger blowup.
+
+/// Constrains `value` to be an entry of the strictly increasing `table`, given the binary search for it.
+pub fn assert_sorted_membership<AB: AirBuilder, const LOG_N: usize>(
+    builder: &mut AB,
+    value: impl Into<AB::Expr>,
+    table: &[AB::F],
+    path: [AB::Var; LOG_N],
+    cmp_bits: [AB::Var; LOG_N],
+) {
+    assert_eq!(table.len(), 1 << LOG_N, "the table must have 2^LOG_N entries");
+
+    for bit in cmp_bits {
+        builder.assert_bool(bit);
+    }
+    for i in 0..LOG_N {
+        // the midpoints of the `2^i` intervals the search can be in after `i` steps
+        let half = table.len() >> (i + 1);
+        let midpoints = table.iter().skip(half - 1).step_by(2 * half).map(|&t| AB::Expr::from_f(t)).collect();
+        builder.assert_eq(path[i], select_by_bits::<AB>(&cmp_bits[..i], midpoints));
+    }
+    let entries = table.iter().map(|&t| AB::Expr::from_f(t)).collect();
+    builder.assert_eq(value, select_by_bits::<AB>(&cmp_bits, entries));
+}
+
+/// `values[k]`, for `k` given by `bits` most significant first.
+fn select_by_bits<AB: AirBuilder>(bits: &[AB::Var], mut values: Vec<AB::Expr>) -> AB::Expr {
+    debug_assert_eq!(values.len(), 1 << bits.len());
+    for &bit in bits {
+        let right = values.split_off(values.len() / 2);
+        values = values.into_iter().zip(right).map(|(left, right)| select_if(bit, right, left)).collect();
+    }
+    values.pop().unwrap()
+}
+
+/// Witness for `assert_sorted_membership`: the midpoints and comparison bits of searching `table` for
+/// `value`, or `None` if `value` is not an entry.
+pub fn sorted_membership_witness<F: PrimeField32, const LOG_N: usize>(
+    value: F,
+    table: &[F],
+) -> Option<([F; LOG_N], [F; LOG_N])> {
+    assert_eq!(table.len(), 1 << LOG_N, "the table must have 2^LOG_N entries");
+    debug_assert!(
+        table.windows(2).all(|w| w[0].as_canonical_u32() < w[1].as_canonical_u32()),
+        "the table must be strictly increasing"
+    );
+
+    let (mut path, mut cmp_bits) = ([F::zero(); LOG_N], [F::zero(); LOG_N]);
+    let mut lo = 0;
+    for i in 0..LOG_N {
+        let half = table.len() >> (i + 1);
+        path[i] = table[lo + half - 1];
+        if value.as_canonical_u32() > path[i].as_canonical_u32() {
+            cmp_bits[i] = F::one();
+            lo += half;
+        }
+    }
+    (table[lo] == value).then_some((path, cmp_bits))
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_air::{Air, BaseAir};
+    use p3_baby_bear::BabyBear;
+    use p3_matrix::dense::RowMajorMatrix;
+    use p3_matrix::Matrix;
+
+    use super::*;
+    use crate::{assert_constraints_fail, assert_constraints_ok};
+
+    const LOG_N: usize = 3;
+    const PRIMES: [u32; 8] = [2, 3, 5, 7, 11, 13, 17, 19];
+
+    fn table() -> Vec<BabyBear> {
+        PRIMES.map(BabyBear::from_canonical_u32).to_vec()
+    }
+
+    // per row: `(value, path, cmp_bits)`, with `value` one of `PRIMES`
+    struct PrimeAir {}
+
+    impl<F> BaseAir<F> for PrimeAir {
+        fn width(&self) -> usize {
+            1 + 2 * LOG_N
+        }
+    }
+
+    impl<AB: AirBuilder> Air<AB> for PrimeAir {
+        fn eval(&self, builder: &mut AB) {
+            let main = builder.main();
+            let local = main.row_slice(0);
+            let table = PRIMES.map(AB::F::from_canonical_u32);
+            let path: [AB::Var; LOG_N] = local[1..1 + LOG_N].try_into().unwrap();
+            let cmp_bits: [AB::Var; LOG_N] = local[1 + LOG_N..].try_into().unwrap();
+            assert_sorted_membership(builder, local[0], &table, path, cmp_bits);
+        }
+    }
+
+    fn trace_of(values: &[u32]) -> RowMajorMatrix<BabyBear> {
+        let rows = values.iter().flat_map(|&v| {
+            let value = BabyBear::from_canonical_u32(v);
+            let (path, cmp_bits) = sorted_membership_witness::<_, LOG_N>(value, &table()).unwrap();
+            [vec![value], path.to_vec(), cmp_bits.to_vec()].concat()
+        });
+        RowMajorMatrix::new(rows.collect(), 1 + 2 * LOG_N)
+    }
+
+    #[test]
+    fn test_members_pass() {
+        assert_constraints_ok!(&PrimeAir {}, &trace_of(&[2, 19, 7, 11]), &[]);
+        assert_constraints_ok!(&PrimeAir {}, &trace_of(&PRIMES), &[]);
+    }
+
+    #[test]
+    fn test_non_member_has_no_witness() {
+        assert!(sorted_membership_witness::<_, LOG_N>(BabyBear::from_canonical_u32(9), &table()).is_none());
+        assert!(sorted_membership_witness::<_, LOG_N>(BabyBear::from_canonical_u32(23), &table()).is_none());
+    }
+
+    #[test]
+    fn test_non_member_fails() {
+        let mut trace = trace_of(&[2, 19, 7, 11]);
+        // 8 is searched like 7 would be, but the search ends on 7
+        trace.row_mut(2)[0] = BabyBear::from_canonical_u32(8);
+        assert_constraints_fail!(&PrimeAir {}, &trace, &[], 2);
+    }
+
+    #[test]
+    fn test_wrong_midpoint_fails() {
+        let mut trace = trace_of(&[2, 19, 7, 11]);
+        // the second midpoint of 11's search is 13
+        trace.row_mut(3)[2] = BabyBear::from_canonical_u32(17);
+        assert_constraints_fail!(&PrimeAir {}, &trace, &[], 3);
+    }
+}
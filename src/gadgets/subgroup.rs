@@ -0,0 +1,75 @@
+use p3_air::AirBuilder;
+use p3_field::Field;
+
+use crate::gadgets::two_adic::{assert_two_adic_membership, two_adic_powers};
+
+// Membership in BabyBear's two-adic subgroup of order 2^27, the largest NTT domain the field supports.
+//
+// `x` is in the subgroup iff `x^(2^27) == 1`. The prover supplies the chain of squares
+//   powers[0] = x^2, powers[1] = powers[0]^2, ..., powers[26] = x^(2^27)
+// and the last one must be 1. Every constraint is a single squaring, so the gadget stays at degree 2. This is
+// `gadgets::two_adic` at `LOG_N == 27`.
+
+pub const BABYBEAR_TWO_ADICITY: usize = 27;
+
+pub fn assert_in_subgroup<AB: AirBuilder>(
+    builder: &mut AB,
+    x: AB::Var,
+    powers: [AB::Var; BABYBEAR_TWO_ADICITY],
+) {
+    assert_two_adic_membership(builder, x, powers);
+}
+
+/// Witness for `assert_in_subgroup`: the repeated squares of `x`.
+pub fn subgroup_powers<F: Field>(x: F) -> [F; BABYBEAR_TWO_ADICITY] {
+    two_adic_powers(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_air::{Air, BaseAir};
+    use p3_baby_bear::BabyBear;
+    use p3_field::{AbstractField, TwoAdicField};
+    use p3_matrix::Matrix;
+    use p3_matrix::dense::RowMajorMatrix;
+
+    use super::*;
+    use crate::{assert_constraints_fail, assert_constraints_ok};
+
+    // one element per row: `(x, powers...)`
+    struct SubgroupAir {}
+
+    impl<F> BaseAir<F> for SubgroupAir {
+        fn width(&self) -> usize {
+            1 + BABYBEAR_TWO_ADICITY
+        }
+    }
+
+    impl<AB: AirBuilder> Air<AB> for SubgroupAir {
+        fn eval(&self, builder: &mut AB) {
+            let main = builder.main();
+            let local = main.row_slice(0);
+            let powers = core::array::from_fn(|i| local[1 + i]);
+            assert_in_subgroup(builder, local[0], powers);
+        }
+    }
+
+    fn trace_of(xs: &[BabyBear]) -> RowMajorMatrix<BabyBear> {
+        let values = xs.iter().flat_map(|&x| [vec![x], subgroup_powers(x).to_vec()].concat()).collect();
+        RowMajorMatrix::new(values, 1 + BABYBEAR_TWO_ADICITY)
+    }
+
+    #[test]
+    fn test_subgroup_elements_pass() {
+        let omega = BabyBear::two_adic_generator(BABYBEAR_TWO_ADICITY);
+        let xs = [BabyBear::one(), omega, omega.square(), BabyBear::two_adic_generator(3)];
+        assert_constraints_ok!(&SubgroupAir {}, &trace_of(&xs), &[]);
+    }
+
+    #[test]
+    fn test_non_member_fails() {
+        let omega = BabyBear::two_adic_generator(BABYBEAR_TWO_ADICITY);
+        let xs = [omega, BabyBear::two(), omega, omega];
+        assert_constraints_fail!(&SubgroupAir {}, &trace_of(&xs), &[], 1);
+    }
+}
@@ -0,0 +1,91 @@
+use p3_air::AirBuilder;
+use p3_field::Field;
+
+// Membership in the two-adic subgroup of order `2^LOG_N`, the NTT domain of a trace with `2^LOG_N` rows.
+//
+// `x` is in the subgroup iff `x^(2^LOG_N) == 1`. The prover supplies the `LOG_N` squarings
+//   powers[0] = x^2, powers[1] = powers[0]^2, ..., powers[LOG_N - 1] = x^(2^LOG_N)
+// and the last one must be 1, at degree 2 throughout. `gadgets::subgroup` is the `LOG_N == 27` case for the
+// whole of BabyBear's two-adic subgroup.
+
+/// Constrains `x^(2^LOG_N) == 1`, given the repeated squares of `x`.
+pub fn assert_two_adic_membership<AB: AirBuilder, const LOG_N: usize>(
+    builder: &mut AB,
+    x: AB::Var,
+    powers: [AB::Var; LOG_N],
+) {
+    // with `LOG_N == 0` the subgroup is `{1}` and this is `x == 1`
+    let mut prev: AB::Expr = x.into();
+    for power in powers {
+        builder.assert_eq(power, prev.clone() * prev);
+        prev = power.into();
+    }
+    builder.assert_one(prev);
+}
+
+/// Witness for `assert_two_adic_membership`: the repeated squares of `x`.
+pub fn two_adic_powers<F: Field, const LOG_N: usize>(x: F) -> [F; LOG_N] {
+    let mut acc = x;
+    core::array::from_fn(|_| {
+        acc = acc.square();
+        acc
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_air::{Air, BaseAir};
+    use p3_baby_bear::BabyBear;
+    use p3_field::{AbstractField, TwoAdicField};
+    use p3_matrix::Matrix;
+    use p3_matrix::dense::RowMajorMatrix;
+
+    use super::*;
+    use crate::{assert_constraints_fail, assert_constraints_ok};
+
+    const LOG_N: usize = 10;
+
+    // one element per row: `(x, powers...)`
+    struct TwoAdicAir {}
+
+    impl<F> BaseAir<F> for TwoAdicAir {
+        fn width(&self) -> usize {
+            1 + LOG_N
+        }
+    }
+
+    impl<AB: AirBuilder> Air<AB> for TwoAdicAir {
+        fn eval(&self, builder: &mut AB) {
+            let main = builder.main();
+            let local = main.row_slice(0);
+            let powers: [AB::Var; LOG_N] = core::array::from_fn(|i| local[1 + i]);
+            assert_two_adic_membership(builder, local[0], powers);
+        }
+    }
+
+    fn trace_of(xs: &[BabyBear]) -> RowMajorMatrix<BabyBear> {
+        let values = xs
+            .iter()
+            .flat_map(|&x| [vec![x], two_adic_powers::<_, LOG_N>(x).to_vec()].concat())
+            .collect();
+        RowMajorMatrix::new(values, 1 + LOG_N)
+    }
+
+    #[test]
+    fn test_domain_elements_pass() {
+        let omega = BabyBear::two_adic_generator(LOG_N);
+        let xs = [omega, BabyBear::one(), omega.exp_u64(5), BabyBear::two_adic_generator(LOG_N - 3)];
+        assert_constraints_ok!(&TwoAdicAir {}, &trace_of(&xs), &[]);
+    }
+
+    #[test]
+    fn test_non_members_fail() {
+        let omega = BabyBear::two_adic_generator(LOG_N);
+        let xs = [omega, BabyBear::two(), omega, omega];
+        assert_constraints_fail!(&TwoAdicAir {}, &trace_of(&xs), &[], 1);
+
+        // in a larger subgroup, but not in this one
+        let xs = [omega, omega, BabyBear::two_adic_generator(LOG_N + 1), omega];
+        assert_constraints_fail!(&TwoAdicAir {}, &trace_of(&xs), &[], 2);
+    }
+}
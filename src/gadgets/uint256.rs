@@ -0,0 +1,174 @@
+use std::fmt;
+
+use p3_air::AirBuilder;
+use p3_field::AbstractField;
+
+use super::less_than::assert_bit_decomposition;
+
+// 256-bit unsigned integers as 16 limbs of 16 bits, least significant first. A 32-bit limb would be the
+// natural machine word, but it doesn't fit in a 31-bit field element; 16-bit limbs leave room for the sum
+// of two limbs and a carry, `< 2^17 + 1`, so limb arithmetic never wraps the field.
+//
+// Addition is schoolbook with one boolean carry per limb:
+//   a_i + b_i + carry_{i-1} == sum_i + 2^16 * carry_i
+// which pins `sum_i` and `carry_i` only if `sum_i` is known to be a limb, so every sum limb is range checked
+// by its bits. The operands must be limbs already, by a range check of their own or because they are the
+// range-checked result of an earlier addition. The last carry is the overflow past 256 bits: the caller
+// asserts it zero for checked addition or leaves it free for addition mod `2^256`.
+
+pub const LIMB_BITS: usize = 16;
+pub const LIMBS: usize = 256 / LIMB_BITS;
+
+/// Constrains every limb to `LIMB_BITS` bits, given their bits.
+pub fn assert_range<AB: AirBuilder>(builder: &mut AB, limbs: &[AB::Var; LIMBS], bits: &[[AB::Var; LIMB_BITS]; LIMBS]) {
+    for (&limb, limb_bits) in limbs.iter().zip(bits) {
+        assert_bit_decomposition(builder, limb, limb_bits);
+    }
+}
+
+/// Constrains `sum == a + b` limb by limb, with the limbs' `carries` and the bits of `sum`'s limbs.
+pub fn assert_add<AB: AirBuilder>(
+    builder: &mut AB,
+    a: &[AB::Var; LIMBS],
+    b: &[AB::Var; LIMBS],
+    sum: &[AB::Var; LIMBS],
+    sum_bits: &[[AB::Var; LIMB_BITS]; LIMBS],
+    carries: &[AB::Var; LIMBS],
+) {
+    assert_range(builder, sum, sum_bits);
+
+    let base = AB::Expr::from_canonical_u32(1 << LIMB_BITS);
+    let mut carry_in = AB::Expr::zero();
+    for i in 0..LIMBS {
+        builder.assert_bool(carries[i]);
+        builder.assert_eq(a[i] + b[i] + carry_in, sum[i] + base.clone() * carries[i]);
+        carry_in = carries[i].into();
+    }
+}
+
+/// A 256-bit unsigned integer as `LIMBS` limbs of `LIMB_BITS` bits, least significant first.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Uint256(pub [u32; LIMBS]);
+
+impl Uint256 {
+    pub fn from_u128(x: u128) -> Self {
+        Uint256(core::array::from_fn(|i| {
+            if i * LIMB_BITS < 128 {
+                ((x >> (i * LIMB_BITS)) & 0xffff) as u32
+            } else {
+                0
+            }
+        }))
+    }
+
+    pub fn limbs<F: AbstractField>(&self) -> [F; LIMBS] {
+        self.0.map(F::from_canonical_u32)
+    }
+
+    /// The bits of every limb, least significant first.
+    pub fn limb_bits<F: AbstractField>(&self) -> [[F; LIMB_BITS]; LIMBS] {
+        self.0.map(|limb| core::array::from_fn(|j| F::from_canonical_u32((limb >> j) & 1)))
+    }
+}
+
+impl fmt::Display for Uint256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hex = self.0.iter().rev().map(|limb| format!("{:04x}", limb)).collect::<String>();
+        let digits = hex.trim_start_matches('0');
+        write!(f, "0x{}", if digits.is_empty() { "0" } else { digits })
+    }
+}
+
+/// Witness for `assert_add`: `a + b` mod `2^256`, and the carry out of each limb.
+pub fn add_witness(a: &Uint256, b: &Uint256) -> (Uint256, [u32; LIMBS]) {
+    let (mut sum, mut carries) = ([0; LIMBS], [0; LIMBS]);
+    let mut carry = 0;
+    for i in 0..LIMBS {
+        let s = a.0[i] + b.0[i] + carry;
+        (sum[i], carry) = (s & 0xffff, s >> LIMB_BITS);
+        carries[i] = carry;
+    }
+    (Uint256(sum), carries)
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_air::{Air, BaseAir};
+    use p3_baby_bear::BabyBear;
+    use p3_matrix::dense::RowMajorMatrix;
+    use p3_matrix::Matrix;
+
+    use super::*;
+    use crate::{assert_constraints_fail, assert_constraints_ok};
+
+    // per row: `(a, b, sum, sum_bits, carries)`, with `a` and `b` trusted to be limbs
+    const WIDTH: usize = 4 * LIMBS + LIMBS * LIMB_BITS;
+
+    struct AddAir {}
+
+    impl<F> BaseAir<F> for AddAir {
+        fn width(&self) -> usize {
+            WIDTH
+        }
+    }
+
+    impl<AB: AirBuilder> Air<AB> for AddAir {
+        fn eval(&self, builder: &mut AB) {
+            let main = builder.main();
+            let local = main.row_slice(0);
+            let limbs = |k: usize| -> [AB::Var; LIMBS] { local[k * LIMBS..(k + 1) * LIMBS].try_into().unwrap() };
+            let bits: [[AB::Var; LIMB_BITS]; LIMBS] =
+                core::array::from_fn(|i| local[3 * LIMBS + i * LIMB_BITS..][..LIMB_BITS].try_into().unwrap());
+            let carries: [AB::Var; LIMBS] = local[WIDTH - LIMBS..].try_into().unwrap();
+            assert_add(builder, &limbs(0), &limbs(1), &limbs(2), &bits, &carries);
+        }
+    }
+
+    fn row(a: Uint256, b: Uint256) -> Vec<BabyBear> {
+        let (sum, carries) = add_witness(&a, &b);
+        [
+            a.limbs().to_vec(),
+            b.limbs().to_vec(),
+            sum.limbs().to_vec(),
+            sum.limb_bits().concat(),
+            carries.map(BabyBear::from_canonical_u32).to_vec(),
+        ]
+        .concat()
+    }
+
+    fn max() -> Uint256 {
+        Uint256([0xffff; LIMBS])
+    }
+
+    fn trace_of(rows: Vec<Vec<BabyBear>>) -> RowMajorMatrix<BabyBear> {
+        RowMajorMatrix::new(rows.concat(), WIDTH)
+    }
+
+    #[test]
+    fn test_additions_pass() {
+        let rows = vec![
+            row(Uint256::from_u128(2), Uint256::from_u128(3)),
+            row(Uint256::from_u128(0xffff), Uint256::from_u128(1)),
+            row(Uint256::from_u128(u128::MAX), Uint256::from_u128(1)),
+            // wraps mod 2^256, with the last carry set
+            row(max(), Uint256::from_u128(1)),
+        ];
+        assert_constraints_ok!(&AddAir {}, &trace_of(rows), &[]);
+
+        let (sum, carries) = add_witness(&Uint256::from_u128(u128::MAX), &Uint256::from_u128(1));
+        assert_eq!(sum.to_string(), "0x100000000000000000000000000000000");
+        assert_eq!(carries[LIMBS - 1], 0);
+        assert_eq!(add_witness(&max(), &Uint256::from_u128(1)), (Uint256::default(), [1; LIMBS]));
+    }
+
+    #[test]
+    fn test_wrong_sum_fails() {
+        let mut rows = vec![row(Uint256::from_u128(2), Uint256::from_u128(3)); 4];
+        rows[2] = row(Uint256::from_u128(0xffff), Uint256::from_u128(1));
+        // drop the carry into limb 1: the sum limb 0x10000 has no 16-bit decomposition
+        rows[2][2 * LIMBS] = BabyBear::from_canonical_u32(0x10000);
+        rows[2][2 * LIMBS + 1] = BabyBear::zero();
+        rows[2][WIDTH - LIMBS] = BabyBear::zero();
+        assert_constraints_fail!(&AddAir {}, &trace_of(rows), &[], 2);
+    }
+}
@@ -0,0 +1,42 @@
+use p3_air::AirBuilder;
+use p3_field::{AbstractField, PrimeField32};
+
+use crate::gadgets::less_than::{assert_bit_decomposition, bit_decompose};
+
+// `a XOR b` by bit decomposition. With `a_i` and `b_i` boolean, `a_i + b_i - 2 * a_i * b_i` is their XOR
+// (it is 1 exactly when one of them is), and the output is those bits recomposed. Decomposing both operands
+// also range checks them to `bits.len()` bits.
+
+/// The XOR of two boolean expressions, degree 2.
+pub fn xor_bit<AB: AirBuilder>(a: impl Into<AB::Expr>, b: impl Into<AB::Expr>) -> AB::Expr {
+    let (a, b): (AB::Expr, AB::Expr) = (a.into(), b.into());
+    a.clone() + b.clone() - a * b.double()
+}
+
+/// Constrains `out == a XOR b`, given the bits of `a` and `b`.
+pub fn assert_xor<AB: AirBuilder>(
+    builder: &mut AB,
+    a: impl Into<AB::Expr>,
+    b: impl Into<AB::Expr>,
+    out: impl Into<AB::Expr>,
+    a_bits: &[AB::Var],
+    b_bits: &[AB::Var],
+) {
+    debug_assert_eq!(a_bits.len(), b_bits.len(), "operands must have the same width");
+    assert_bit_decomposition(builder, a, a_bits);
+    assert_bit_decomposition(builder, b, b_bits);
+
+    let mut recomposed = AB::Expr::zero();
+    let mut pow = AB::Expr::one();
+    for (&a_i, &b_i) in a_bits.iter().zip(b_bits) {
+        recomposed += pow.clone() * xor_bit::<AB>(a_i, b_i);
+        pow = pow.double();
+    }
+    builder.assert_eq(out, recomposed);
+}
+
+/// The bits of `a` and `b` for `assert_xor`, and their XOR.
+pub fn xor_witness<F: PrimeField32>(a: F, b: F, n_bits: usize) -> (Vec<F>, Vec<F>, F) {
+    let out = F::from_canonical_u32(a.as_canonical_u32() ^ b.as_canonical_u32());
+    (bit_decompose(a, n_bits), bit_decompose(b, n_bits), out)
+}
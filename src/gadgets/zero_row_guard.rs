@@ -0,0 +1,113 @@
+use p3_air::{AirBuilder, FilteredAirBuilder};
+use p3_field::AbstractField;
+
+use crate::gadgets::sentinel::when_active;
+
+// Padding a trace with zero rows only works if every constraint holds on a zero row, and many don't: an
+// inverse `b * b_inv == 1`, a first-row value, a running sum that has to keep going. An `is_active` column,
+// 1 on real rows and 0 on padding, fixes that without touching the AIR's logic: every constraint goes through
+// `with_active_guard`, which multiplies it by `is_active`, so a zero row satisfies all of them.
+//
+// It is the sentinel column (`gadgets::sentinel`) turned around. A sentinel is 1 on padding, so padding rows
+// have to set it; `is_active` is 0 on padding, so zero rows are valid padding as they are and
+// `padding::pad_to_optimal` can pad any AIR guarded this way. `assert_active_prefix` keeps the sentinel's
+// shape: boolean, and once 0 never 1 again, so the real rows are a prefix and the prover can't deactivate a
+// real row in the middle.
+//
+// A transition constraint should be guarded by the *next* row's flag: it relates a row to the one after it,
+// and on the last real row the one after it is padding.
+
+/// Constrains `is_active` to be boolean and, across the transition to `next_is_active`, never to go 0 -> 1.
+pub fn assert_active_prefix<AB: AirBuilder>(builder: &mut AB, is_active: AB::Var, next_is_active: AB::Var) {
+    builder.assert_bool(is_active);
+    builder
+        .when_transition()
+        .assert_zero(next_is_active * (AB::Expr::one() - is_active));
+}
+
+/// Runs `f` with every constraint it asserts gated on `is_active == 1`: `sentinel::when_active` with
+/// `1 - is_active` as the sentinel.
+pub fn with_active_guard<AB: AirBuilder>(
+    builder: &mut AB,
+    is_active: AB::Var,
+    f: impl FnOnce(&mut FilteredAirBuilder<'_, AB>),
+) {
+    when_active(builder, AB::Expr::one() - is_active, f);
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_air::{Air, BaseAir};
+    use p3_field::Field;
+    use p3_matrix::dense::RowMajorMatrix;
+    use p3_matrix::Matrix;
+
+    use super::*;
+    use crate::config::{FriParams, Val};
+    use crate::padding::pad_to_optimal;
+    use crate::{assert_constraints_fail, assert_constraints_ok};
+
+    // `[is_active, a, b, b_inv, q]` with `q = a / b`, and `a` counting up from 1
+    struct RatioAir {
+        guarded: bool,
+    }
+
+    impl<F> BaseAir<F> for RatioAir {
+        fn width(&self) -> usize {
+            5
+        }
+    }
+
+    impl<AB: AirBuilder> Air<AB> for RatioAir {
+        fn eval(&self, builder: &mut AB) {
+            let main = builder.main();
+            let (local, next) = (main.row_slice(0), main.row_slice(1));
+            let (is_active, a, b, b_inv, q) = (local[0], local[1], local[2], local[3], local[4]);
+
+            let constraints = |builder: &mut FilteredAirBuilder<'_, AB>| {
+                builder.assert_one(b * b_inv);
+                builder.assert_eq(a, q * b);
+                builder.when_first_row().assert_one(a);
+            };
+            let transition = |builder: &mut FilteredAirBuilder<'_, AB>| {
+                builder.when_transition().assert_eq(next[1], a + AB::Expr::one());
+            };
+
+            if self.guarded {
+                assert_active_prefix(builder, is_active, next[0]);
+                with_active_guard(builder, is_active, constraints);
+                with_active_guard(builder, next[0], transition);
+            } else {
+                constraints(&mut builder.when(AB::Expr::one()));
+                transition(&mut builder.when(AB::Expr::one()));
+            }
+        }
+    }
+
+    fn ratio_trace(n: usize) -> RowMajorMatrix<Val> {
+        let values = (1..=n as u32)
+            .flat_map(|a| {
+                let (a, b) = (Val::from_canonical_u32(a), Val::from_canonical_u32(7));
+                [Val::one(), a, b, b.inverse(), a / b]
+            })
+            .collect();
+        RowMajorMatrix::new(values, 5)
+    }
+
+    #[test]
+    fn test_guarded_air_accepts_zero_padding() {
+        let padded = pad_to_optimal(ratio_trace(5), &FriParams::default());
+        assert_eq!(padded.height(), 16);
+        assert_constraints_ok!(&RatioAir { guarded: true }, &padded, &[]);
+        // without the guard, the last real row's transition into the padding fails
+        assert_constraints_fail!(&RatioAir { guarded: false }, &padded, &[], 4);
+    }
+
+    #[test]
+    fn test_active_rows_are_a_prefix() {
+        // padding in the middle, real rows after it
+        let mut trace = ratio_trace(8);
+        trace.row_mut(3).fill(Val::zero());
+        assert_constraints_fail!(&RatioAir { guarded: true }, &trace, &[], 3);
+    }
+}
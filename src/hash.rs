@@ -0,0 +1,60 @@
+use p3_field::AbstractField;
+use p3_symmetric::CryptographicHasher;
+
+// Adapters between byte-oriented and field-oriented hashers, so they can share one Merkle tree.
+
+/// Wraps a hasher producing 32-byte digests so it produces 8 field elements instead.
+///
+/// Each little-endian 4-byte word is reduced into the field, so the digest keeps ~31 bits per element, and
+/// the output can be fed straight into a field-native compression function like Poseidon2.
+#[derive(Clone, Copy, Debug)]
+pub struct FieldDigestHasher<H> {
+    inner: H,
+}
+
+impl<H> FieldDigestHasher<H> {
+    pub const fn new(inner: H) -> Self {
+        Self { inner }
+    }
+}
+
+/// Packs a 32-byte digest into 8 field elements, one per little-endian word.
+pub fn bytes_to_field_digest<F: AbstractField>(bytes: [u8; 32]) -> [F; 8] {
+    core::array::from_fn(|i| {
+        let word = u32::from_le_bytes(bytes[4 * i..4 * i + 4].try_into().unwrap());
+        F::from_wrapped_u32(word)
+    })
+}
+
+impl<F, H> CryptographicHasher<F, [F; 8]> for FieldDigestHasher<H>
+where
+    F: AbstractField + Clone,
+    H: CryptographicHasher<F, [u8; 32]>,
+{
+    fn hash_iter<I>(&self, input: I) -> [F; 8]
+    where
+        I: IntoIterator<Item = F>,
+    {
+        bytes_to_field_digest(self.inner.hash_iter(input))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_baby_bear::BabyBear;
+    use p3_field::PrimeField32;
+
+    use super::*;
+
+    #[test]
+    fn test_bytes_to_field_digest_words() {
+        let mut bytes = [0u8; 32];
+        bytes[0] = 1;
+        bytes[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        let digest = bytes_to_field_digest::<BabyBear>(bytes);
+        assert_eq!(digest[0], BabyBear::one());
+        assert_eq!(digest[1].as_canonical_u32(), u32::MAX % BabyBear::ORDER_U32);
+        assert!(digest[2..].iter().all(|x| x.is_zero()));
+    }
+}
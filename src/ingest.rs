@@ -0,0 +1,65 @@
+use p3_field::{AbstractField, PrimeField32};
+use p3_matrix::dense::RowMajorMatrix;
+
+use crate::config::Val;
+use crate::error::CookError;
+
+// Traces from outside Rust usually arrive as a flat `Vec<u32>` in row-major order. `Val::from_canonical_u32`
+// only debug-asserts its input is canonical, and `from_wrapped_u32` would silently reduce it, so raw values
+// are checked here first: a value at or above the modulus is almost always a bug upstream (a signed value,
+// a 64-bit value cut to 32 bits), not something to wrap.
+
+/// A trace of `width` columns from row-major `values`, padded with zero rows to a power-of-two height.
+pub fn trace_from_u32(values: &[u32], width: usize) -> Result<RowMajorMatrix<Val>, CookError> {
+    if width == 0 {
+        return Err(CookError::Trace("the width must be positive".to_string()));
+    }
+    if values.is_empty() {
+        return Err(CookError::Trace("a trace needs at least one row".to_string()));
+    }
+    if values.len() % width != 0 {
+        return Err(CookError::Trace(format!("{} values don't make rows of {}", values.len(), width)));
+    }
+    if let Some(i) = values.iter().position(|&v| v >= Val::ORDER_U32) {
+        return Err(CookError::Trace(format!(
+            "value {} at row {}, column {} is not below the modulus {}",
+            values[i],
+            i / width,
+            i % width,
+            Val::ORDER_U32
+        )));
+    }
+
+    let height = (values.len() / width).next_power_of_two();
+    let mut trace = values.iter().map(|&v| Val::from_canonical_u32(v)).collect::<Vec<_>>();
+    trace.resize(height * width, Val::zero());
+    Ok(RowMajorMatrix::new(trace, width))
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_matrix::Matrix;
+
+    use super::*;
+
+    #[test]
+    fn test_valid_values_are_padded() {
+        let trace = trace_from_u32(&[1, 2, 3, 4, 5, 6], 2).unwrap();
+        assert_eq!(trace.height(), 4);
+        assert_eq!(trace.row_slice(2).to_vec(), vec![Val::from_canonical_u32(5), Val::from_canonical_u32(6)]);
+        assert_eq!(trace.row_slice(3).to_vec(), vec![Val::zero(); 2]);
+    }
+
+    #[test]
+    fn test_over_modulus_value_is_rejected() {
+        match trace_from_u32(&[1, 2, Val::ORDER_U32, 4], 2) {
+            Err(CookError::Trace(reason)) => assert!(reason.contains("row 1, column 0"), "{}", reason),
+            other => panic!("expected a trace error, got {:?}", other.map(|t| t.values)),
+        }
+    }
+
+    #[test]
+    fn test_ragged_length_is_rejected() {
+        assert!(matches!(trace_from_u32(&[1, 2, 3, 4, 5], 2), Err(CookError::Trace(_))));
+    }
+}
@@ -0,0 +1,183 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use p3_challenger::DuplexChallenger;
+use p3_commit::ExtensionMmcs;
+use p3_fri::{FriConfig, TwoAdicFriPcs};
+use p3_merkle_tree::FieldMerkleTreeMmcs;
+use p3_symmetric::{CryptographicPermutation, PaddingFreeSponge, Permutation, TruncatedPermutation};
+use p3_uni_stark::StarkConfig;
+use tracing::info;
+
+use crate::config::{Challenge, Dft, Perm, Val, DEFAULT_LOG_BLOWUP, DEFAULT_NUM_QUERIES, DEFAULT_POW_BITS};
+
+// Counts Poseidon2 calls per call site: leaf hashing, Merkle compression and the challenger's duplexing.
+// Each site gets its own wrapped copy of the permutation, so the counters stay separate even though the
+// permutation is the same.
+//
+// The counted config hashes unpacked values, like the hybrid config. With the packed MMCS one call would
+// run `Packing::WIDTH` permutations at once and the counts would depend on the target's SIMD width.
+
+/// A permutation that counts its invocations. Clones share the counter.
+#[derive(Clone, Debug)]
+pub struct InstrumentedPermutation<P> {
+    inner: P,
+    calls: Arc<AtomicU64>,
+}
+
+impl<P> InstrumentedPermutation<P> {
+    pub fn new(inner: P) -> Self {
+        Self { inner, calls: Arc::default() }
+    }
+
+    pub fn calls(&self) -> u64 {
+        self.calls.load(Ordering::Relaxed)
+    }
+
+    pub fn reset(&self) {
+        self.calls.store(0, Ordering::Relaxed);
+    }
+}
+
+impl<T: Clone, P: Permutation<T>> Permutation<T> for InstrumentedPermutation<P> {
+    fn permute_mut(&self, input: &mut T) {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        self.inner.permute_mut(input);
+    }
+}
+
+impl<T: Clone, P: CryptographicPermutation<T>> CryptographicPermutation<T> for InstrumentedPermutation<P> {}
+
+pub type CountedPerm = InstrumentedPermutation<Perm>;
+pub type CountedHash = PaddingFreeSponge<CountedPerm, 16, 8, 8>;
+pub type CountedCompress = TruncatedPermutation<CountedPerm, 2, 8, 16>;
+pub type CountedMmcs = FieldMerkleTreeMmcs<Val, Val, CountedHash, CountedCompress, 8>;
+pub type CountedChallengeMmcs = ExtensionMmcs<Val, Challenge, CountedMmcs>;
+pub type CountedPcs = TwoAdicFriPcs<Val, Dft, CountedMmcs, CountedChallengeMmcs>;
+pub type CountedChallenger = DuplexChallenger<Val, CountedPerm, 16, 8>;
+pub type CountedConfig = StarkConfig<CountedPcs, Challenge, CountedChallenger>;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PermutationCounts {
+    pub leaf_hashes: u64,
+    pub compressions: u64,
+    pub challenger: u64,
+}
+
+impl PermutationCounts {
+    pub fn total(&self) -> u64 {
+        self.leaf_hashes + self.compressions + self.challenger
+    }
+
+    /// Emits the counts as fields of a single `permutation calls` event.
+    pub fn report(&self) {
+        info!(
+            leaf_hashes = self.leaf_hashes,
+            compressions = self.compressions,
+            challenger = self.challenger,
+            total = self.total(),
+            "permutation calls"
+        );
+    }
+}
+
+/// One counted permutation per call site, and the config and challengers built from them.
+#[derive(Clone, Debug)]
+pub struct PermutationCounters {
+    leaf: CountedPerm,
+    compress: CountedPerm,
+    challenger: CountedPerm,
+}
+
+impl PermutationCounters {
+    pub fn new(perm: &Perm) -> Self {
+        Self {
+            leaf: InstrumentedPermutation::new(perm.clone()),
+            compress: InstrumentedPermutation::new(perm.clone()),
+            challenger: InstrumentedPermutation::new(perm.clone()),
+        }
+    }
+
+    /// The default stark config, with counted leaf hashing and compression.
+    pub fn config(&self) -> CountedConfig {
+        let val_mmcs = CountedMmcs::new(CountedHash::new(self.leaf.clone()), CountedCompress::new(self.compress.clone()));
+        let fri_config = FriConfig {
+            log_blowup: DEFAULT_LOG_BLOWUP,
+            num_queries: DEFAULT_NUM_QUERIES,
+            proof_of_work_bits: DEFAULT_POW_BITS,
+            mmcs: CountedChallengeMmcs::new(val_mmcs.clone()),
+        };
+        CountedConfig::new(CountedPcs::new(Dft {}, val_mmcs, fri_config))
+    }
+
+    /// A fresh challenger whose duplexing is counted.
+    pub fn challenger(&self) -> CountedChallenger {
+        CountedChallenger::new(self.challenger.clone())
+    }
+
+    pub fn counts(&self) -> PermutationCounts {
+        PermutationCounts {
+            leaf_hashes: self.leaf.calls(),
+            compressions: self.compress.calls(),
+            challenger: self.challenger.calls(),
+        }
+    }
+
+    pub fn reset(&self) {
+        self.leaf.reset();
+        self.compress.reset();
+        self.challenger.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_commit::Pcs as _;
+    use p3_matrix::Matrix;
+    use p3_uni_stark::{prove, verify, StarkGenericConfig};
+
+    use super::*;
+    use crate::config::random_perm;
+    use crate::simple_state::{random_trace, SimpleState, SS_ROW_WIDTH};
+
+    #[test]
+    fn test_trace_commit_counts() {
+        let counters = PermutationCounters::new(&random_perm());
+        let config = counters.config();
+        let trace = random_trace::<Val>(6);
+        let lde_height = (trace.height() << DEFAULT_LOG_BLOWUP) as u64;
+
+        let pcs = config.pcs();
+        let domain = pcs.natural_domain_for_degree(trace.height());
+        let _ = pcs.commit(vec![(domain, trace)]);
+
+        // a row of up to RATE = 8 elements is one absorption, and a binary tree over the leaves has one
+        // compression per internal node
+        assert!(SS_ROW_WIDTH <= 8);
+        let counts = counters.counts();
+        assert_eq!(counts.leaf_hashes, lde_height);
+        assert_eq!(counts.compressions, lde_height - 1);
+        assert_eq!(counts.challenger, 0);
+    }
+
+    #[test]
+    fn test_proof_counts() {
+        let counters = PermutationCounters::new(&random_perm());
+        let config = counters.config();
+        let trace = random_trace::<Val>(6);
+        let lde_height = (trace.height() << DEFAULT_LOG_BLOWUP) as u64;
+
+        let proof = prove(&config, &SimpleState {}, &mut counters.challenger(), trace, &vec![]);
+        let counts = counters.counts();
+        counts.report();
+
+        // the trace, the quotient chunks and every FRI round each commit at least one matrix
+        assert!(counts.leaf_hashes > 2 * lde_height);
+        assert!(counts.compressions > 2 * (lde_height - 1));
+        assert!(counts.challenger > 0);
+
+        counters.reset();
+        verify(&config, &SimpleState {}, &mut counters.challenger(), &proof, &vec![]).unwrap();
+        assert!(counters.counts().total() > 0);
+    }
+}
@@ -1 +1,50 @@
-pub mod utils;
\ No newline at end of file
+#[cfg(all(feature = "verifier-only", feature = "parallel"))]
+compile_error!("`parallel` only speeds up proving, which `verifier-only` leaves out");
+
+pub mod aggregate;
+pub mod alloc;
+#[cfg(not(feature = "verifier-only"))]
+pub mod batch;
+pub mod bundle;
+pub mod columns;
+pub mod config;
+pub mod continuation;
+pub mod coverage;
+pub mod crypto;
+pub mod debug;
+pub mod degree;
+#[cfg(not(feature = "verifier-only"))]
+pub mod determinism;
+pub mod error;
+pub mod evm;
+pub mod folding;
+pub mod gadgets;
+pub mod hash;
+pub mod ingest;
+pub mod instrument;
+pub mod lookups;
+pub mod optimization;
+pub mod padding;
+pub mod peek_ahead;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+#[cfg(not(feature = "verifier-only"))]
+pub mod pipeline;
+pub mod poseidon2_air;
+pub mod proof_compress;
+pub mod replay;
+pub mod salt;
+pub mod schema;
+pub mod simple_state;
+pub mod sink;
+pub mod state_trace;
+pub mod statement;
+pub mod streaming_verify;
+pub mod testing;
+pub mod timeout;
+#[cfg(not(feature = "verifier-only"))]
+pub mod timing;
+pub mod transaction;
+#[cfg(not(feature = "verifier-only"))]
+pub mod utils;
+pub mod viz;
@@ -0,0 +1,201 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilderWithPublicValues, BaseAir};
+use p3_field::{AbstractExtensionField, AbstractField, Field};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+
+use super::{assert_ext_eq, ext, ext_add, ext_scale, ext_times, lift, LookupAir, LookupArgument, EXT};
+use crate::config::{Challenge, Val};
+
+// LogUp: the queries are in the table iff, for a random `z`,
+//   sum over table rows of multiplicity / (z - table) == sum over queries of 1 / (z - query)
+// where `multiplicity` counts how often each entry is queried. Each side proves its sum as a running
+// accumulator over its inverses, with `[z, sum]` as public values; the claims balance when the sums are
+// equal. `z`, the inverses and the sums are in `Challenge`, `EXT` columns each.
+
+const TABLE_WIDTH: usize = 2 + 2 * EXT;
+const QUERY_WIDTH: usize = 1 + 2 * EXT;
+
+pub struct LogUpLookup;
+
+impl LookupArgument for LogUpLookup {
+    type TableAir = LogUpTableAir;
+    type QueryAir = LogUpQueryAir;
+
+    const NUM_CHALLENGES: usize = 1;
+
+    fn build_table_air(&self, table: RowMajorMatrix<Val>) -> LogUpTableAir {
+        assert_eq!(table.width(), 1, "lookup tables have a single column");
+        LogUpTableAir { table: table.values }
+    }
+
+    fn build_query_air(&self, _queries: &[Val]) -> LogUpQueryAir {
+        LogUpQueryAir {}
+    }
+
+    fn claims_balance(&self, table_claim: Challenge, query_claim: Challenge) -> bool {
+        table_claim == query_claim
+    }
+}
+
+pub struct LogUpTableAir {
+    table: Vec<Val>,
+}
+
+impl<F> BaseAir<F> for LogUpTableAir {
+    fn width(&self) -> usize {
+        TABLE_WIDTH
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues<F = Val>> Air<AB> for LogUpTableAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &TableRow<AB::Var> = (*local).borrow();
+        let next: &TableRow<AB::Var> = (*next).borrow();
+
+        let pis = builder.public_values();
+        let (z, sum): ([AB::Expr; EXT], [AB::Expr; EXT]) = (ext(&pis[..EXT]), ext(&pis[EXT..]));
+
+        // inv == 1 / (z - table)
+        let difference = ext_add(z, lift(-AB::Expr::from(local.table)));
+        assert_ext_eq(builder, ext_times(&ext(&local.inv), &difference), lift(AB::Expr::one()));
+
+        let term = |row: &TableRow<AB::Var>| ext_scale(ext(&row.inv), row.multiplicity.into());
+        assert_ext_eq(&mut builder.when_first_row(), ext(&local.acc), term(local));
+        assert_ext_eq(&mut builder.when_transition(), ext(&next.acc), ext_add(ext(&local.acc), term(next)));
+        assert_ext_eq(&mut builder.when_last_row(), ext(&local.acc), sum);
+    }
+}
+
+impl LookupAir for LogUpTableAir {
+    fn committed_trace(&self, queries: &[Val]) -> RowMajorMatrix<Val> {
+        let values = self
+            .table
+            .iter()
+            .flat_map(|&entry| [entry, Val::from_canonical_usize(queries.iter().filter(|&&q| q == entry).count())])
+            .collect();
+        RowMajorMatrix::new(values, 2)
+    }
+
+    fn committed_width(&self) -> usize {
+        2
+    }
+
+    fn complete_trace(
+        &self,
+        committed: RowMajorMatrix<Val>,
+        challenges: &[Challenge],
+    ) -> (RowMajorMatrix<Val>, Challenge) {
+        let z = challenges[0];
+        let mut acc = Challenge::zero();
+        let mut values = Vec::with_capacity(committed.height() * TABLE_WIDTH);
+        for row in committed.values.chunks_exact(2) {
+            let inv = (z - row[0]).inverse();
+            acc += inv * row[1];
+            values.extend_from_slice(row);
+            values.extend_from_slice(inv.as_base_slice());
+            values.extend_from_slice(acc.as_base_slice());
+        }
+        (RowMajorMatrix::new(values, TABLE_WIDTH), acc)
+    }
+
+    fn fixed_columns(&self) -> Vec<(usize, Vec<Val>)> {
+        vec![(0, self.table.clone())]
+    }
+}
+
+struct TableRow<F> {
+    pub table: F,
+    pub multiplicity: F,
+    // filled after `z` is drawn
+    pub inv: [F; EXT],
+    pub acc: [F; EXT],
+}
+
+impl<F> Borrow<TableRow<F>> for [F] {
+    fn borrow(&self) -> &TableRow<F> {
+        debug_assert_eq!(self.len(), TABLE_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<TableRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+pub struct LogUpQueryAir {}
+
+impl<F> BaseAir<F> for LogUpQueryAir {
+    fn width(&self) -> usize {
+        QUERY_WIDTH
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues<F = Val>> Air<AB> for LogUpQueryAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &QueryRow<AB::Var> = (*local).borrow();
+        let next: &QueryRow<AB::Var> = (*next).borrow();
+
+        let pis = builder.public_values();
+        let (z, sum): ([AB::Expr; EXT], [AB::Expr; EXT]) = (ext(&pis[..EXT]), ext(&pis[EXT..]));
+
+        // inv == 1 / (z - query)
+        let difference = ext_add(z, lift(-AB::Expr::from(local.query)));
+        assert_ext_eq(builder, ext_times(&ext(&local.inv), &difference), lift(AB::Expr::one()));
+
+        assert_ext_eq(&mut builder.when_first_row(), ext(&local.acc), ext(&local.inv));
+        assert_ext_eq(&mut builder.when_transition(), ext(&next.acc), ext_add(ext(&local.acc), ext(&next.inv)));
+        assert_ext_eq(&mut builder.when_last_row(), ext(&local.acc), sum);
+    }
+}
+
+impl LookupAir for LogUpQueryAir {
+    fn committed_trace(&self, queries: &[Val]) -> RowMajorMatrix<Val> {
+        RowMajorMatrix::new(queries.to_vec(), 1)
+    }
+
+    fn committed_width(&self) -> usize {
+        1
+    }
+
+    fn complete_trace(
+        &self,
+        committed: RowMajorMatrix<Val>,
+        challenges: &[Challenge],
+    ) -> (RowMajorMatrix<Val>, Challenge) {
+        let z = challenges[0];
+        let mut acc = Challenge::zero();
+        let mut values = Vec::with_capacity(committed.height() * QUERY_WIDTH);
+        for &query in &committed.values {
+            let inv = (z - query).inverse();
+            acc += inv;
+            values.push(query);
+            values.extend_from_slice(inv.as_base_slice());
+            values.extend_from_slice(acc.as_base_slice());
+        }
+        (RowMajorMatrix::new(values, QUERY_WIDTH), acc)
+    }
+}
+
+struct QueryRow<F> {
+    pub query: F,
+    // filled after `z` is drawn
+    pub inv: [F; EXT],
+    pub acc: [F; EXT],
+}
+
+impl<F> Borrow<QueryRow<F>> for [F] {
+    fn borrow(&self) -> &QueryRow<F> {
+        debug_assert_eq!(self.len(), QUERY_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<QueryRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
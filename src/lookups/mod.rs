@@ -0,0 +1,11 @@
+pub mod logup;
+pub mod plookup;
+mod r#trait;
+
+pub(crate) use r#trait::{interpolate_at, out_of_domain_point};
+#[cfg(not(feature = "verifier-only"))]
+pub use r#trait::{prove_lookup, prove_two_round};
+pub use r#trait::{
+    assert_ext_eq, ext, ext_add, ext_scale, ext_sub, ext_times, ext_values, lift, verify_lookup, verify_two_round,
+    LookupAir, LookupArgument, LookupProof, ProvableAir, TwoRoundAir, TwoRoundProof, EXT,
+};
@@ -0,0 +1,233 @@
+use p3_air::{Air, AirBuilderWithPublicValues, BaseAir};
+use p3_field::{AbstractExtensionField, AbstractField, Field};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+
+use super::{assert_ext_eq, ext, ext_add, ext_scale, ext_times, lift, LookupAir, LookupArgument, EXT};
+use crate::config::{Challenge, Val};
+
+// Plookup (Gabizon-Williamson): with `s` the queries `f` merged into the table `t` in table order, the
+// queries are in the table iff, for random `beta` and `gamma`,
+//   (1 + beta)^|f| * prod (gamma + f_i) * prod (gamma (1 + beta) + t_i + beta t_{i+1})
+//     == prod (gamma (1 + beta) + s_j + beta s_{j+1})
+// over adjacent pairs of `t` and of `s`. The query side proves the first product as its claim; the table
+// side holds `t` and `s` and proves the second product over the third, and the claims balance when they
+// multiply to 1.
+//
+// `s` has `|f| + |t|` entries, so the table side lays it out `1 + queries_per_entry` to a row, with each
+// row's pair products chained through `denominators` to keep the constraints at degree 3. Row `i` starts
+// with the pair that crosses over from row `i - 1` (1 on the first row), then the pairs within the row.
+// `beta`, `gamma`, the products and the claims are in `Challenge`, `EXT` columns each.
+
+pub struct PlookupLookup {
+    /// how many queries there are per table entry; `|f| = queries_per_entry * |t|`
+    pub queries_per_entry: usize,
+}
+
+impl LookupArgument for PlookupLookup {
+    type TableAir = PlookupTableAir;
+    type QueryAir = PlookupQueryAir;
+
+    const NUM_CHALLENGES: usize = 2;
+
+    fn build_table_air(&self, table: RowMajorMatrix<Val>) -> PlookupTableAir {
+        assert_eq!(table.width(), 1, "lookup tables have a single column");
+        PlookupTableAir { table: table.values, per_row: 1 + self.queries_per_entry }
+    }
+
+    fn build_query_air(&self, _queries: &[Val]) -> PlookupQueryAir {
+        PlookupQueryAir {}
+    }
+
+    fn claims_balance(&self, table_claim: Challenge, query_claim: Challenge) -> bool {
+        table_claim * query_claim == Challenge::one()
+    }
+}
+
+/// `gamma (1 + beta) + a + beta b`, one factor of the products.
+fn pair(beta: Challenge, gamma: Challenge, a: Val, b: Val) -> Challenge {
+    gamma * (Challenge::one() + beta) + a + beta * b
+}
+
+/// Columns: the table entry, `per_row` entries of `s`, `per_row` chained pair products, the accumulator.
+pub struct PlookupTableAir {
+    table: Vec<Val>,
+    per_row: usize,
+}
+
+impl PlookupTableAir {
+    fn s_cols(&self) -> std::ops::Range<usize> {
+        1..1 + self.per_row
+    }
+
+    /// The columns of the `j`th pair product.
+    fn denominator_cols(&self, j: usize) -> std::ops::Range<usize> {
+        let start = 1 + self.per_row + j * EXT;
+        start..start + EXT
+    }
+
+    fn acc_cols(&self) -> std::ops::Range<usize> {
+        self.denominator_cols(self.per_row)
+    }
+}
+
+impl<F> BaseAir<F> for PlookupTableAir {
+    fn width(&self) -> usize {
+        1 + self.per_row + (self.per_row + 1) * EXT
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues<F = Val>> Air<AB> for PlookupTableAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+
+        let pis = builder.public_values();
+        let (beta, gamma, claim): ([AB::Expr; EXT], [AB::Expr; EXT], [AB::Expr; EXT]) =
+            (ext(&pis[..EXT]), ext(&pis[EXT..2 * EXT]), ext(&pis[2 * EXT..]));
+        let shift = ext_times(&gamma, &ext_add(lift(AB::Expr::one()), beta.clone()));
+        let pair_of = |a: AB::Var, b: AB::Var| {
+            ext_add(ext_add(shift.clone(), lift(a.into())), ext_scale(beta.clone(), b.into()))
+        };
+
+        let s = self.s_cols();
+        let (local_s, next_s) = (&local[s.clone()], &next[s]);
+        let local_d = |j: usize| ext::<AB::Expr, _>(&local[self.denominator_cols(j)]);
+        let next_d = |j: usize| ext::<AB::Expr, _>(&next[self.denominator_cols(j)]);
+        let (local_acc, next_acc) = (ext::<AB::Expr, _>(&local[self.acc_cols()]), ext(&next[self.acc_cols()]));
+        let one = lift(AB::Expr::one());
+
+        // the pair products of each row, starting with the pair crossing from the row before
+        assert_ext_eq(&mut builder.when_first_row(), local_d(0), one.clone());
+        assert_ext_eq(&mut builder.when_transition(), next_d(0), pair_of(local_s[self.per_row - 1], next_s[0]));
+        for j in 1..self.per_row {
+            assert_ext_eq(builder, local_d(j), ext_times(&local_d(j - 1), &pair_of(local_s[j - 1], local_s[j])));
+        }
+
+        // the table's pairs over them
+        let last = self.per_row - 1;
+        assert_ext_eq(&mut builder.when_first_row(), ext_times(&local_acc, &local_d(last)), one);
+        assert_ext_eq(
+            &mut builder.when_transition(),
+            ext_times(&next_acc, &next_d(last)),
+            ext_times(&local_acc, &pair_of(local[0], next[0])),
+        );
+        assert_ext_eq(&mut builder.when_last_row(), local_acc, claim);
+    }
+}
+
+impl LookupAir for PlookupTableAir {
+    fn committed_trace(&self, queries: &[Val]) -> RowMajorMatrix<Val> {
+        assert_eq!(queries.len(), (self.per_row - 1) * self.table.len(), "expected queries_per_entry per entry");
+
+        // `s`: each entry followed by its queries; a query missing from the table goes at the end, where the
+        // products won't balance
+        let mut s = Vec::with_capacity(self.per_row * self.table.len());
+        for &entry in &self.table {
+            s.push(entry);
+            s.extend(queries.iter().filter(|&&q| q == entry));
+        }
+        s.extend(queries.iter().filter(|q| !self.table.contains(q)));
+
+        let values = self
+            .table
+            .iter()
+            .zip(s.chunks_exact(self.per_row))
+            .flat_map(|(&entry, row)| [&[entry][..], row].concat())
+            .collect();
+        RowMajorMatrix::new(values, 1 + self.per_row)
+    }
+
+    fn committed_width(&self) -> usize {
+        1 + self.per_row
+    }
+
+    fn complete_trace(
+        &self,
+        committed: RowMajorMatrix<Val>,
+        challenges: &[Challenge],
+    ) -> (RowMajorMatrix<Val>, Challenge) {
+        let (beta, gamma) = (challenges[0], challenges[1]);
+        let width = <Self as BaseAir<Val>>::width(self);
+        let mut values = Vec::with_capacity(committed.height() * width);
+
+        let mut prev: Option<&[Val]> = None;
+        let mut acc = Challenge::one();
+        for row in committed.values.chunks_exact(1 + self.per_row) {
+            let s = &row[1..];
+            let mut denominators = Vec::with_capacity(self.per_row);
+            denominators.push(prev.map_or(Challenge::one(), |prev| pair(beta, gamma, prev[self.per_row], s[0])));
+            for j in 1..self.per_row {
+                denominators.push(denominators[j - 1] * pair(beta, gamma, s[j - 1], s[j]));
+            }
+
+            let numerator = prev.map_or(Challenge::one(), |prev| acc * pair(beta, gamma, prev[0], row[0]));
+            acc = numerator * denominators[self.per_row - 1].inverse();
+
+            values.extend_from_slice(row);
+            for denominator in denominators {
+                values.extend_from_slice(denominator.as_base_slice());
+            }
+            values.extend_from_slice(acc.as_base_slice());
+            prev = Some(row);
+        }
+        (RowMajorMatrix::new(values, width), acc)
+    }
+
+    fn fixed_columns(&self) -> Vec<(usize, Vec<Val>)> {
+        vec![(0, self.table.clone())]
+    }
+}
+
+/// Columns: the query and the running product of `(1 + beta)(gamma + query)`.
+pub struct PlookupQueryAir {}
+
+impl<F> BaseAir<F> for PlookupQueryAir {
+    fn width(&self) -> usize {
+        1 + EXT
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues<F = Val>> Air<AB> for PlookupQueryAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+
+        let pis = builder.public_values();
+        let (beta, gamma, claim): ([AB::Expr; EXT], [AB::Expr; EXT], [AB::Expr; EXT]) =
+            (ext(&pis[..EXT]), ext(&pis[EXT..2 * EXT]), ext(&pis[2 * EXT..]));
+        let one_plus_beta = ext_add(lift(AB::Expr::one()), beta);
+        let factor = |query: AB::Var| ext_times(&one_plus_beta, &ext_add(gamma.clone(), lift(query.into())));
+        let (local_acc, next_acc) = (ext::<AB::Expr, _>(&local[1..]), ext::<AB::Expr, _>(&next[1..]));
+
+        assert_ext_eq(&mut builder.when_first_row(), local_acc.clone(), factor(local[0]));
+        assert_ext_eq(&mut builder.when_transition(), next_acc, ext_times(&local_acc, &factor(next[0])));
+        assert_ext_eq(&mut builder.when_last_row(), local_acc, claim);
+    }
+}
+
+impl LookupAir for PlookupQueryAir {
+    fn committed_trace(&self, queries: &[Val]) -> RowMajorMatrix<Val> {
+        RowMajorMatrix::new(queries.to_vec(), 1)
+    }
+
+    fn committed_width(&self) -> usize {
+        1
+    }
+
+    fn complete_trace(
+        &self,
+        committed: RowMajorMatrix<Val>,
+        challenges: &[Challenge],
+    ) -> (RowMajorMatrix<Val>, Challenge) {
+        let (beta, gamma) = (challenges[0], challenges[1]);
+        let mut acc = Challenge::one();
+        let mut values = Vec::with_capacity(committed.height() * (1 + EXT));
+        for &query in &committed.values {
+            acc *= (Challenge::one() + beta) * (gamma + query);
+            values.push(query);
+            values.extend_from_slice(acc.as_base_slice());
+        }
+        (RowMajorMatrix::new(values, 1 + EXT), acc)
+    }
+}
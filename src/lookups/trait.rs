@@ -0,0 +1,512 @@
+use p3_air::{Air, AirBuilder, BaseAir};
+use p3_challenger::{CanObserve, CanSample, FieldChallenger};
+use p3_commit::Pcs as _;
+use p3_field::{AbstractExtensionField, AbstractField, Field, TwoAdicField};
+use p3_matrix::dense::RowMajorMatrix;
+#[cfg(not(feature = "verifier-only"))]
+use p3_matrix::Matrix;
+use p3_symmetric::Hash;
+#[cfg(debug_assertions)]
+use p3_uni_stark::DebugConstraintBuilder;
+#[cfg(not(feature = "verifier-only"))]
+use p3_uni_stark::prove;
+use p3_uni_stark::{
+    verify, Proof, ProverConstraintFolder, StarkGenericConfig, SymbolicAirBuilder, VerificationError,
+    VerifierConstraintFolder,
+};
+
+use crate::config::{Challenge, Challenger, MyConfig, Pcs, Perm, Val};
+use crate::error::{CookError, VerifyFailure};
+use crate::gadgets::ext_field::ext_mul;
+
+// A lookup argument proves every query is an entry of a fixed table. Each strategy (`LogUpLookup`,
+// `PlookupLookup`) splits its argument into a table side and a query side with one AIR each, proven as two
+// uni-stark proofs on one transcript:
+//   1. each side's witness columns (the queries; the multiplicities, or the sorted merge) are committed;
+//   2. the strategy's challenges are drawn from `Challenge` after both commitments;
+//   3. each side fills its running sum or product under the challenges and proves it, with the challenges
+//      and the side's result, its "claim", as its public values;
+//   4. both first-round traces are opened at the out-of-domain point `zeta` of their side's proof;
+//   5. the verifier redraws the challenges, verifies both proofs and the openings, checks each proven trace
+//      starts with the columns committed in step 1, and checks the two claims balance.
+//
+// Step 4 is what ties the proofs to step 1: a proven trace whose witness columns differ from the committed
+// ones agrees with them at `zeta` with probability `height / |Challenge|`, so the queries and multiplicities
+// are fixed before the challenges are drawn. The challenges, running sums and claims live in `Challenge`,
+// `EXT` columns or public values each, so a prover grinding for a lucky challenge faces the ~124-bit
+// extension rather than the 31-bit base field.
+//
+// The table is a column of the table side's trace, but uni-stark has no preprocessed columns, so nothing in
+// the proof fixes its values. The verifier pins it the same way: the table is interpolated over the trace
+// domain and compared with the column's opening at `zeta`. This costs the verifier `O(height)` field
+// operations, fine for the small tables lookups are used with.
+//
+// A lookup inside one AIR, where both sides are columns of the same trace (a stack's pushes and pops, a
+// stream citing its own events), is a `TwoRoundAir`. `prove_two_round` runs the same steps on one trace:
+// its first `committed_width` columns are committed, the challenges are drawn from `Challenge` after that
+// commitment and any values the verifier also holds, the rest of the trace is filled under them, and the
+// first-round columns are opened at the proof's `zeta` and compared with the proven trace.
+
+/// Coefficients of a `Challenge` over `Val`: the columns of an extension value in a trace.
+pub const EXT: usize = <Challenge as AbstractExtensionField<Val>>::D;
+
+/// The coefficients of `Challenge`'s defining polynomial below `x^EXT`, read off `x^EXT`.
+fn challenge_modulus() -> [Val; EXT] {
+    let x = Challenge::from_base_fn(|i| if i == 1 { Val::one() } else { Val::zero() });
+    let x_ext = x.exp_u64(EXT as u64);
+    core::array::from_fn(|i| -x_ext.as_base_slice()[i])
+}
+
+/// `EXT` consecutive columns or public values as one extension element.
+pub fn ext<E, V: Copy + Into<E>>(values: &[V]) -> [E; EXT] {
+    core::array::from_fn(|i| values[i].into())
+}
+
+/// A base-field expression as an extension element.
+pub fn lift<E: AbstractField>(x: E) -> [E; EXT] {
+    core::array::from_fn(|i| if i == 0 { x.clone() } else { E::zero() })
+}
+
+pub fn ext_add<E: AbstractField>(a: [E; EXT], b: [E; EXT]) -> [E; EXT] {
+    core::array::from_fn(|i| a[i].clone() + b[i].clone())
+}
+
+pub fn ext_sub<E: AbstractField>(a: [E; EXT], b: [E; EXT]) -> [E; EXT] {
+    core::array::from_fn(|i| a[i].clone() - b[i].clone())
+}
+
+pub fn ext_scale<E: AbstractField>(a: [E; EXT], k: E) -> [E; EXT] {
+    a.map(|a| a * k.clone())
+}
+
+pub fn ext_times<E: AbstractField + From<Val>>(a: &[E; EXT], b: &[E; EXT]) -> [E; EXT] {
+    ext_mul(a, b, &challenge_modulus())
+}
+
+pub fn assert_ext_eq<AB: AirBuilder>(builder: &mut AB, a: [AB::Expr; EXT], b: [AB::Expr; EXT]) {
+    for (a, b) in a.into_iter().zip(b) {
+        builder.assert_eq(a, b);
+    }
+}
+
+/// Extension elements as base-field public values, `EXT` each.
+pub fn ext_values(values: &[Challenge]) -> Vec<Val> {
+    values.iter().flat_map(|c| c.as_base_slice().to_vec()).collect()
+}
+
+/// The challenges followed by a side's claim, as base-field public values.
+fn lookup_public_values(challenges: &[Challenge], claim: Challenge) -> Vec<Val> {
+    ext_values(&[challenges, &[claim]].concat())
+}
+
+/// An AIR for one side of a lookup, with what the prover needs to fill its trace.
+pub trait LookupAir: ProvableAir {
+    /// The side's witness columns for a lookup of `queries`, committed before the challenges are drawn.
+    fn committed_trace(&self, queries: &[Val]) -> RowMajorMatrix<Val>;
+
+    /// The number of columns `committed_trace` produces, which the full trace starts with.
+    fn committed_width(&self) -> usize;
+
+    /// The full trace under `challenges`, extending `committed`, and the side's claim.
+    fn complete_trace(
+        &self,
+        committed: RowMajorMatrix<Val>,
+        challenges: &[Challenge],
+    ) -> (RowMajorMatrix<Val>, Challenge);
+
+    /// Columns whose values the verifier knows, as `(column, values)`.
+    fn fixed_columns(&self) -> Vec<(usize, Vec<Val>)> {
+        vec![]
+    }
+}
+
+/// A lookup strategy: the AIRs for the table and the query side, and how their claims close the argument.
+///
+/// The queries are the prover's witness: the query side's constraints must not depend on them, since the
+/// verifier builds it from no queries at all. Both sides are concrete types rather than trait objects:
+/// uni-stark evaluates an AIR with several builders, and a `dyn Air` is only an AIR for one.
+pub trait LookupArgument {
+    type TableAir: LookupAir;
+    type QueryAir: LookupAir;
+
+    /// How many challenges are drawn after the first round.
+    const NUM_CHALLENGES: usize;
+
+    fn build_table_air(&self, table: RowMajorMatrix<Val>) -> Self::TableAir;
+
+    fn build_query_air(&self, queries: &[Val]) -> Self::QueryAir;
+
+    /// Whether a table claim and a query claim close the argument.
+    fn claims_balance(&self, table_claim: Challenge, query_claim: Challenge) -> bool;
+}
+
+/// The AIRs the prover and verifier here accept.
+#[cfg(debug_assertions)]
+pub trait ProvableAir:
+    BaseAir<Val>
+    + Air<SymbolicAirBuilder<Val>>
+    + for<'a> Air<ProverConstraintFolder<'a, MyConfig>>
+    + for<'a> Air<VerifierConstraintFolder<'a, MyConfig>>
+    + for<'a> Air<DebugConstraintBuilder<'a, Val>>
+{
+}
+
+#[cfg(debug_assertions)]
+impl<A> ProvableAir for A where
+    A: BaseAir<Val>
+        + Air<SymbolicAirBuilder<Val>>
+        + for<'a> Air<ProverConstraintFolder<'a, MyConfig>>
+        + for<'a> Air<VerifierConstraintFolder<'a, MyConfig>>
+        + for<'a> Air<DebugConstraintBuilder<'a, Val>>
+{
+}
+
+#[cfg(not(debug_assertions))]
+pub trait ProvableAir:
+    BaseAir<Val>
+    + Air<SymbolicAirBuilder<Val>>
+    + for<'a> Air<ProverConstraintFolder<'a, MyConfig>>
+    + for<'a> Air<VerifierConstraintFolder<'a, MyConfig>>
+{
+}
+
+#[cfg(not(debug_assertions))]
+impl<A> ProvableAir for A where
+    A: BaseAir<Val>
+        + Air<SymbolicAirBuilder<Val>>
+        + for<'a> Air<ProverConstraintFolder<'a, MyConfig>>
+        + for<'a> Air<VerifierConstraintFolder<'a, MyConfig>>
+{
+}
+
+/// An AIR whose trace starts with columns committed before its challenges are drawn.
+///
+/// The public values the AIR sees are the challenges, `EXT` values each, followed by its own.
+pub trait TwoRoundAir: ProvableAir {
+    /// The number of leading columns committed in the first round.
+    fn committed_width(&self) -> usize;
+
+    /// How many challenges are drawn after the first round.
+    fn num_challenges(&self) -> usize;
+
+    /// The number of public values after the challenges.
+    fn num_public_values(&self) -> usize;
+
+    /// Fills the columns after the first `committed_width` under `challenges`, and returns the AIR's own
+    /// public values.
+    fn complete_trace(&self, trace: &mut RowMajorMatrix<Val>, challenges: &[Challenge]) -> Vec<Val>;
+}
+
+pub struct TwoRoundProof {
+    pub commitment: Hash<Val, Val, 8>,
+    /// the challenges, `EXT` values each, followed by the AIR's own public values
+    pub public_values: Vec<Val>,
+    pub proof: Proof<MyConfig>,
+    /// the first-round columns at the proof's `zeta`, and the proof of the opening
+    pub committed_opening: Vec<Challenge>,
+    pub committed_opening_proof: <Pcs as p3_commit::Pcs<Challenge, Challenger>>::Proof,
+}
+
+pub struct LookupProof {
+    pub table_commitment: Hash<Val, Val, 8>,
+    pub query_commitment: Hash<Val, Val, 8>,
+    /// the challenges followed by the side's claim, `EXT` values each
+    pub table_public_values: Vec<Val>,
+    pub query_public_values: Vec<Val>,
+    pub table_proof: Proof<MyConfig>,
+    pub query_proof: Proof<MyConfig>,
+    /// each side's first-round trace at its proof's `zeta`, and the proof of both openings
+    pub table_committed_opening: Vec<Challenge>,
+    pub query_committed_opening: Vec<Challenge>,
+    pub committed_opening_proof: <Pcs as p3_commit::Pcs<Challenge, Challenger>>::Proof,
+}
+
+fn draw_challenges(
+    challenger: &mut Challenger,
+    commitments: &[&Hash<Val, Val, 8>],
+    observed: &[Val],
+    n: usize,
+) -> Vec<Challenge> {
+    for &commitment in commitments {
+        challenger.observe(commitment.clone());
+    }
+    challenger.observe_slice(observed);
+    (0..n).map(|_| challenger.sample_ext_element()).collect()
+}
+
+/// Proves every entry of `queries` is in the single-column `table`, with the strategy `lookup`.
+#[cfg(not(feature = "verifier-only"))]
+pub fn prove_lookup<L: LookupArgument>(
+    config: &MyConfig,
+    perm: &Perm,
+    lookup: &L,
+    table: RowMajorMatrix<Val>,
+    queries: &[Val],
+) -> LookupProof {
+    let pcs = config.pcs();
+    let commit = |trace: &RowMajorMatrix<Val>| {
+        pcs.commit(vec![(pcs.natural_domain_for_degree(trace.height()), trace.clone())])
+    };
+
+    let table_air = lookup.build_table_air(table);
+    let query_air = lookup.build_query_air(queries);
+
+    let table_committed = table_air.committed_trace(queries);
+    let query_committed = query_air.committed_trace(queries);
+    let (table_commitment, table_data) = commit(&table_committed);
+    let (query_commitment, query_data) = commit(&query_committed);
+
+    let mut challenger = Challenger::new(perm.clone());
+    let challenges = draw_challenges(&mut challenger, &[&table_commitment, &query_commitment], &[], L::NUM_CHALLENGES);
+
+    let (table_trace, table_claim) = table_air.complete_trace(table_committed, &challenges);
+    let (query_trace, query_claim) = query_air.complete_trace(query_committed, &challenges);
+    let table_public_values = lookup_public_values(&challenges, table_claim);
+    let query_public_values = lookup_public_values(&challenges, query_claim);
+
+    let before_table = challenger.clone();
+    let table_proof = prove(config, &table_air, &mut challenger, table_trace, &table_public_values);
+    let before_query = challenger.clone();
+    let query_proof = prove(config, &query_air, &mut challenger, query_trace, &query_public_values);
+
+    // the first-round traces, where the proven ones were opened
+    let table_zeta = out_of_domain_point(&before_table, &table_proof, &table_public_values);
+    let query_zeta = out_of_domain_point(&before_query, &query_proof, &query_public_values);
+    let (opened, committed_opening_proof) =
+        pcs.open(vec![(&table_data, vec![vec![table_zeta]]), (&query_data, vec![vec![query_zeta]])], &mut challenger);
+
+    LookupProof {
+        table_commitment,
+        query_commitment,
+        table_public_values,
+        query_public_values,
+        table_proof,
+        query_proof,
+        table_committed_opening: opened[0][0][0].clone(),
+        query_committed_opening: opened[1][0][0].clone(),
+        committed_opening_proof,
+    }
+}
+
+/// Verifies `proof` against `table`, mirroring `prove_lookup`.
+pub fn verify_lookup<L: LookupArgument>(
+    config: &MyConfig,
+    perm: &Perm,
+    lookup: &L,
+    table: RowMajorMatrix<Val>,
+    proof: &LookupProof,
+) -> Result<(), CookError> {
+    let table_air = lookup.build_table_air(table);
+    let query_air = lookup.build_query_air(&[]);
+
+    let mut challenger = Challenger::new(perm.clone());
+    let commitments = [&proof.table_commitment, &proof.query_commitment];
+    let challenges = draw_challenges(&mut challenger, &commitments, &[], L::NUM_CHALLENGES);
+    let num_challenge_values = L::NUM_CHALLENGES * EXT;
+    let drawn = challenges.iter().flat_map(|c| c.as_base_slice().to_vec()).collect::<Vec<_>>();
+    for public_values in [&proof.table_public_values, &proof.query_public_values] {
+        if public_values.len() != num_challenge_values + EXT || public_values[..num_challenge_values] != drawn {
+            return Err(CookError::PublicValues("the challenges were not drawn from the transcript".to_string()));
+        }
+    }
+    let claim = |public_values: &[Val]| Challenge::from_base_slice(&public_values[num_challenge_values..]);
+    if !lookup.claims_balance(claim(&proof.table_public_values), claim(&proof.query_public_values)) {
+        return Err(CookError::PublicValues("the table and query claims don't balance".to_string()));
+    }
+
+    let before_table = challenger.clone();
+    verify(config, &table_air, &mut challenger, &proof.table_proof, &proof.table_public_values)
+        .map_err(|e| CookError::Verification(e.into()))?;
+    let before_query = challenger.clone();
+    verify(config, &query_air, &mut challenger, &proof.query_proof, &proof.query_public_values)
+        .map_err(|e| CookError::Verification(e.into()))?;
+
+    let table_zeta = out_of_domain_point(&before_table, &proof.table_proof, &proof.table_public_values);
+    let query_zeta = out_of_domain_point(&before_query, &proof.query_proof, &proof.query_public_values);
+    let opened = &proof.table_proof.opened_values.trace_local;
+    for (column, values) in table_air.fixed_columns() {
+        if opened.get(column) != Some(&interpolate_at(&values, table_zeta)) {
+            return Err(CookError::PublicValues(format!("column {} of the table side is not the table", column)));
+        }
+    }
+    let (table_width, query_width) = (table_air.committed_width(), query_air.committed_width());
+    check_committed_opening("table side's", table_width, &proof.table_proof, &proof.table_committed_opening)?;
+    check_committed_opening("query side's", query_width, &proof.query_proof, &proof.query_committed_opening)?;
+
+    let pcs = config.pcs();
+    let domain = |proof: &Proof<MyConfig>| pcs.natural_domain_for_degree(1 << proof.degree_bits);
+    let rounds = vec![
+        (
+            proof.table_commitment.clone(),
+            vec![(domain(&proof.table_proof), vec![(table_zeta, proof.table_committed_opening.clone())])],
+        ),
+        (
+            proof.query_commitment.clone(),
+            vec![(domain(&proof.query_proof), vec![(query_zeta, proof.query_committed_opening.clone())])],
+        ),
+    ];
+    pcs.verify(rounds, &proof.committed_opening_proof, &mut challenger)
+        .map_err(|e| CookError::Verification(VerificationError::InvalidOpeningArgument(e).into()))
+}
+
+/// Checks a proven trace starts with the `width` columns committed before the challenges were drawn.
+fn check_committed_opening(
+    whose: &str,
+    width: usize,
+    proof: &Proof<MyConfig>,
+    committed_opening: &[Challenge],
+) -> Result<(), CookError> {
+    if committed_opening.len() != width || proof.opened_values.trace_local.len() < width {
+        return Err(CookError::Verification(VerifyFailure::ProofShape));
+    }
+    if proof.opened_values.trace_local[..width] != *committed_opening {
+        let reason = format!("the {} proven trace is not the one committed first", whose);
+        return Err(CookError::PublicValues(reason));
+    }
+    Ok(())
+}
+
+/// Proves `air` on `trace`, whose columns after the first `air.committed_width()` are filled under
+/// challenges drawn after the first ones are committed. `observed` are values the verifier also holds that
+/// the challenges must depend on.
+#[cfg(not(feature = "verifier-only"))]
+pub fn prove_two_round<A: TwoRoundAir>(
+    config: &MyConfig,
+    perm: &Perm,
+    air: &A,
+    mut trace: RowMajorMatrix<Val>,
+    observed: &[Val],
+) -> TwoRoundProof {
+    let pcs = config.pcs();
+    let width = air.committed_width();
+    let committed = trace.values.chunks_exact(trace.width()).flat_map(|row| row[..width].to_vec()).collect();
+    let domain = pcs.natural_domain_for_degree(trace.height());
+    let (commitment, data) = pcs.commit(vec![(domain, RowMajorMatrix::new(committed, width))]);
+
+    let mut challenger = Challenger::new(perm.clone());
+    let challenges = draw_challenges(&mut challenger, &[&commitment], observed, air.num_challenges());
+    let own_public_values = air.complete_trace(&mut trace, &challenges);
+    let public_values = [ext_values(&challenges), own_public_values].concat();
+
+    let before = challenger.clone();
+    let proof = prove(config, air, &mut challenger, trace, &public_values);
+
+    // the first-round columns, where the proven trace was opened
+    let zeta = out_of_domain_point(&before, &proof, &public_values);
+    let (opened, committed_opening_proof) = pcs.open(vec![(&data, vec![vec![zeta]])], &mut challenger);
+
+    TwoRoundProof {
+        commitment,
+        public_values,
+        proof,
+        committed_opening: opened[0][0][0].clone(),
+        committed_opening_proof,
+    }
+}
+
+/// Verifies `proof`, mirroring `prove_two_round`, and returns the challenges so the caller can check public
+/// values it derives from them.
+pub fn verify_two_round<A: TwoRoundAir>(
+    config: &MyConfig,
+    perm: &Perm,
+    air: &A,
+    observed: &[Val],
+    proof: &TwoRoundProof,
+) -> Result<Vec<Challenge>, CookError> {
+    let mut challenger = Challenger::new(perm.clone());
+    let challenges = draw_challenges(&mut challenger, &[&proof.commitment], observed, air.num_challenges());
+    let drawn = ext_values(&challenges);
+    let expected = drawn.len() + air.num_public_values();
+    if proof.public_values.len() != expected {
+        let got = proof.public_values.len();
+        return Err(CookError::Verification(VerifyFailure::PublicValues { expected, got }));
+    }
+    if proof.public_values[..drawn.len()] != drawn {
+        return Err(CookError::PublicValues("the challenges were not drawn from the transcript".to_string()));
+    }
+
+    let before = challenger.clone();
+    verify(config, air, &mut challenger, &proof.proof, &proof.public_values)
+        .map_err(|e| CookError::Verification(e.into()))?;
+
+    let zeta = out_of_domain_point(&before, &proof.proof, &proof.public_values);
+    check_committed_opening("AIR's", air.committed_width(), &proof.proof, &proof.committed_opening)?;
+
+    let pcs = config.pcs();
+    let domain = pcs.natural_domain_for_degree(1 << proof.proof.degree_bits);
+    let rounds = vec![(proof.commitment.clone(), vec![(domain, vec![(zeta, proof.committed_opening.clone())])])];
+    pcs.verify(rounds, &proof.committed_opening_proof, &mut challenger)
+        .map_err(|e| CookError::Verification(VerificationError::InvalidOpeningArgument(e).into()))?;
+    Ok(challenges)
+}
+
+/// The point `verify` will open `proof` at, from a copy of the transcript just before it.
+pub(crate) fn out_of_domain_point(challenger: &Challenger, proof: &Proof<MyConfig>, public_values: &Vec<Val>) -> Challenge {
+    let mut challenger = challenger.clone();
+    challenger.observe(proof.commitments.trace.clone());
+    challenger.observe_slice(public_values);
+    let _alpha: Challenge = challenger.sample_ext_element();
+    challenger.observe(proof.commitments.quotient_chunks.clone());
+    challenger.sample()
+}
+
+/// The polynomial taking `values` on the subgroup of order `values.len()`, at `point`, by the barycentric
+/// formula `(point^n - 1) / n * sum of values[i] * g^i / (point - g^i)`.
+pub(crate) fn interpolate_at(values: &[Val], point: Challenge) -> Challenge {
+    let n = values.len();
+    assert!(n.is_power_of_two(), "the table height must be a power of two");
+    let log_n = n.trailing_zeros() as usize;
+    let g = Val::two_adic_generator(log_n);
+
+    let mut sum = Challenge::zero();
+    let mut x = Val::one();
+    for &value in values {
+        sum += (point - x).inverse() * (x * value);
+        x *= g;
+    }
+    sum * (point.exp_power_of_2(log_n) - Challenge::one()) * Val::from_canonical_usize(n).inverse()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{thread_rng, Rng};
+
+    use super::*;
+    use crate::config::{default_config, random_perm};
+    use crate::lookups::logup::LogUpLookup;
+
+    #[test]
+    fn test_proven_trace_bound_to_first_round() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let table = RowMajorMatrix::new((0..16).map(Val::from_canonical_usize).collect(), 1);
+        let queries = (0..64).map(|i| Val::from_canonical_usize(i % 16)).collect::<Vec<_>>();
+
+        let mut proof = prove_lookup(&config, &perm, &LogUpLookup, table.clone(), &queries);
+        verify_lookup(&config, &perm, &LogUpLookup, table.clone(), &proof).unwrap();
+
+        // a query column that isn't the committed one shows up at `zeta`
+        proof.query_committed_opening[0] += Challenge::one();
+        assert!(matches!(
+            verify_lookup(&config, &perm, &LogUpLookup, table, &proof),
+            Err(CookError::PublicValues(_))
+        ));
+    }
+
+    #[test]
+    fn test_interpolate_at_matches_lagrange() {
+        let mut rng = thread_rng();
+        let values: Vec<Val> = (0..16).map(|_| rng.gen()).collect();
+        let point: Challenge = rng.gen();
+
+        let subgroup: Vec<Val> = (0..16).map(|i| Val::two_adic_generator(4).exp_u64(i)).collect();
+        let expected = subgroup.iter().zip(&values).fold(Challenge::zero(), |acc, (&xi, &vi)| {
+            let basis = subgroup
+                .iter()
+                .filter(|&&xj| xj != xi)
+                .fold(Challenge::one(), |basis, &xj| basis * (point - xj) * (xi - xj).inverse());
+            acc + basis * vi
+        });
+        assert_eq!(interpolate_at(&values, point), expected);
+    }
+}
@@ -1,3 +1,26 @@
+use std::{env, process};
+
+use plonky3_cook::config::supported_backends;
+
+// The crate's command-line entry point; the tools with their own arguments are the binaries in `src/bin`.
+//
+//   plonky3-cook backends    lists the field/PCS combinations the crate has a config for
+
+const USAGE: &str = "usage: plonky3-cook backends";
+
 fn main() {
-    println!("Hello, world!");
+    match env::args().nth(1).as_deref() {
+        Some("backends") => {
+            for b in supported_backends() {
+                println!(
+                    "{:<24} {} (degree {} extension) + {}: {}",
+                    b.constructor, b.field, b.extension_degree, b.pcs, b.description
+                );
+            }
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    }
 }
@@ -0,0 +1,333 @@
+use std::collections::BTreeSet;
+use std::mem::size_of;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_field::Field;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+use p3_uni_stark::{Entry, SymbolicExpression, SymbolicVariable};
+
+use crate::viz::main_cells;
+
+// Column order for wide traces. A constraint reads its columns from one row, so columns that appear in the
+// same constraints are cheaper to evaluate when they share a cache line. `ColumnAccessTracker` runs `eval`
+// symbolically and records the columns each constraint reads; `optimize_column_order` packs them into
+// cache lines greedily: each line starts from the unplaced column with the most co-accesses, then takes the
+// unplaced column co-accessed most often with the line so far, until the line is full.
+//
+// The order is applied by proving `permute_columns(trace, &order)` against
+// `ReorderedAir::new(inner, &order)`, whose builder hands the inner AIR its columns back in the original
+// order, so the AIR runs unchanged.
+// Whether the new order helps depends on how the prover walks the trace; measure before relying on it.
+
+const CACHE_LINE_BYTES: usize = 64;
+
+/// A symbolic builder that records the main-trace columns each constraint reads.
+pub struct ColumnAccessTracker<F: Field> {
+    main: RowMajorMatrix<SymbolicVariable<F>>,
+    public_values: Vec<SymbolicVariable<F>>,
+    /// the columns of each constraint, local and next row alike, in the order `eval` asserts them
+    pub accesses: Vec<BTreeSet<usize>>,
+}
+
+impl<F: Field> ColumnAccessTracker<F> {
+    pub fn new(width: usize, num_public_values: usize) -> Self {
+        let main = (0..2)
+            .flat_map(|offset| (0..width).map(move |col| SymbolicVariable::new(Entry::Main { offset }, col)))
+            .collect();
+        let public_values = (0..num_public_values).map(|i| SymbolicVariable::new(Entry::Public, i)).collect();
+        ColumnAccessTracker { main: RowMajorMatrix::new(main, width), public_values, accesses: vec![] }
+    }
+}
+
+impl<F: Field> AirBuilder for ColumnAccessTracker<F> {
+    type F = F;
+    type Expr = SymbolicExpression<F>;
+    type Var = SymbolicVariable<F>;
+    type M = RowMajorMatrix<SymbolicVariable<F>>;
+
+    fn main(&self) -> Self::M {
+        self.main.clone()
+    }
+
+    fn is_first_row(&self) -> Self::Expr {
+        SymbolicExpression::IsFirstRow
+    }
+
+    fn is_last_row(&self) -> Self::Expr {
+        SymbolicExpression::IsLastRow
+    }
+
+    fn is_transition_window(&self, size: usize) -> Self::Expr {
+        if size == 2 {
+            SymbolicExpression::IsTransition
+        } else {
+            panic!("uni-stark only supports a window size of 2")
+        }
+    }
+
+    fn assert_zero<I: Into<Self::Expr>>(&mut self, x: I) {
+        let mut cells = BTreeSet::new();
+        main_cells(&x.into(), &mut cells);
+        self.accesses.push(cells.into_iter().map(|(_, col)| col).collect());
+    }
+}
+
+impl<F: Field> AirBuilderWithPublicValues for ColumnAccessTracker<F> {
+    type PublicVar = SymbolicVariable<F>;
+
+    fn public_values(&self) -> &[Self::PublicVar] {
+        &self.public_values
+    }
+}
+
+/// A column order for `air`'s trace: `order[i]` is the original column to place at position `i`.
+pub fn optimize_column_order<F, A>(air: &A, num_public_values: usize) -> Vec<usize>
+where
+    F: Field,
+    A: Air<ColumnAccessTracker<F>>,
+{
+    let width = air.width();
+    let mut tracker = ColumnAccessTracker::new(width, num_public_values);
+    air.eval(&mut tracker);
+
+    // weights[a][b]: the number of constraints reading both `a` and `b`
+    let mut weights = vec![vec![0usize; width]; width];
+    for columns in &tracker.accesses {
+        for &a in columns {
+            for &b in columns {
+                if a != b {
+                    weights[a][b] += 1;
+                }
+            }
+        }
+    }
+
+    pack_lines(&weights, (CACHE_LINE_BYTES / size_of::<F>()).max(1))
+}
+
+/// Greedily fills lines of `per_line` columns with the columns `weights` says are co-accessed most.
+fn pack_lines(weights: &[Vec<usize>], per_line: usize) -> Vec<usize> {
+    let mut unplaced = (0..weights.len()).collect::<BTreeSet<_>>();
+    let mut order = Vec::with_capacity(weights.len());
+
+    // ties go to the lowest column, so an AIR without co-accesses keeps its order
+    let best = |unplaced: &BTreeSet<usize>, score: &dyn Fn(usize) -> usize| {
+        unplaced.iter().copied().max_by_key(|&c| (score(c), std::cmp::Reverse(c)))
+    };
+
+    while let Some(seed) = best(&unplaced, &|c| weights[c].iter().sum()) {
+        unplaced.remove(&seed);
+        let mut line = vec![seed];
+        while line.len() < per_line {
+            let Some(next) = best(&unplaced, &|c| line.iter().map(|&l| weights[l][c]).sum()) else {
+                break;
+            };
+            unplaced.remove(&next);
+            line.push(next);
+        }
+        order.extend(line);
+    }
+    order
+}
+
+/// The inverse of a column order: `positions[c]` is where original column `c` was placed.
+pub fn inverse_order(order: &[usize]) -> Vec<usize> {
+    let mut positions = vec![0; order.len()];
+    for (i, &c) in order.iter().enumerate() {
+        positions[c] = i;
+    }
+    positions
+}
+
+/// Copies `trace` with its columns in `order`.
+pub fn permute_columns<F: Clone + Send + Sync>(trace: &RowMajorMatrix<F>, order: &[usize]) -> RowMajorMatrix<F> {
+    assert_eq!(order.len(), trace.width(), "the order must place every column");
+    let values = trace
+        .values
+        .chunks_exact(trace.width())
+        .flat_map(|row| order.iter().map(|&c| row[c].clone()))
+        .collect();
+    RowMajorMatrix::new(values, trace.width())
+}
+
+/// `A` proven over a trace whose columns were permuted with `permute_columns(trace, &order)`.
+pub struct ReorderedAir<A> {
+    inner: A,
+    positions: Vec<usize>,
+}
+
+impl<A> ReorderedAir<A> {
+    pub fn new(inner: A, order: &[usize]) -> Self {
+        ReorderedAir { inner, positions: inverse_order(order) }
+    }
+}
+
+impl<F, A: BaseAir<F>> BaseAir<F> for ReorderedAir<A> {
+    fn width(&self) -> usize {
+        self.inner.width()
+    }
+}
+
+impl<AB, A> Air<AB> for ReorderedAir<A>
+where
+    AB: AirBuilderWithPublicValues,
+    A: BaseAir<AB::F> + for<'b> Air<ReorderedBuilder<'b, AB>>,
+{
+    fn eval(&self, builder: &mut AB) {
+        self.inner.eval(&mut ReorderedBuilder { inner: builder, positions: &self.positions });
+    }
+}
+
+/// A builder that forwards everything to `inner` but shows the trace columns in their original order.
+pub struct ReorderedBuilder<'a, AB> {
+    inner: &'a mut AB,
+    positions: &'a [usize],
+}
+
+impl<'a, AB: AirBuilder> AirBuilder for ReorderedBuilder<'a, AB> {
+    type F = AB::F;
+    type Expr = AB::Expr;
+    type Var = AB::Var;
+    type M = PermutedColumns<'a, AB::M>;
+
+    fn main(&self) -> Self::M {
+        PermutedColumns { inner: self.inner.main(), positions: self.positions }
+    }
+
+    fn is_first_row(&self) -> Self::Expr {
+        self.inner.is_first_row()
+    }
+
+    fn is_last_row(&self) -> Self::Expr {
+        self.inner.is_last_row()
+    }
+
+    fn is_transition_window(&self, size: usize) -> Self::Expr {
+        self.inner.is_transition_window(size)
+    }
+
+    fn assert_zero<I: Into<Self::Expr>>(&mut self, x: I) {
+        self.inner.assert_zero(x);
+    }
+}
+
+impl<'a, AB: AirBuilderWithPublicValues> AirBuilderWithPublicValues for ReorderedBuilder<'a, AB> {
+    type PublicVar = AB::PublicVar;
+
+    fn public_values(&self) -> &[Self::PublicVar] {
+        self.inner.public_values()
+    }
+}
+
+/// A matrix whose column `c` is column `positions[c]` of `inner`.
+#[derive(Clone, Copy, Debug)]
+pub struct PermutedColumns<'a, M> {
+    inner: M,
+    positions: &'a [usize],
+}
+
+impl<'a, M> PermutedColumns<'a, M> {
+    pub(crate) fn new(inner: M, positions: &'a [usize]) -> Self {
+        PermutedColumns { inner, positions }
+    }
+}
+
+impl<'a, T: Clone + Send + Sync, M: Matrix<T>> Matrix<T> for PermutedColumns<'a, M> {
+    fn width(&self) -> usize {
+        self.positions.len()
+    }
+
+    fn height(&self) -> usize {
+        self.inner.height()
+    }
+
+    type Row<'b> = std::vec::IntoIter<T> where Self: 'b;
+
+    fn row(&self, r: usize) -> Self::Row<'_> {
+        let row = self.inner.row(r).collect::<Vec<_>>();
+        self.positions.iter().map(|&p| row[p].clone()).collect::<Vec<_>>().into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_uni_stark::{prove, verify};
+    use rand::{thread_rng, Rng};
+
+    use super::*;
+    use crate::config::{default_config, random_perm, Challenger, Val};
+    use crate::simple_state::{random_checked_trace, SimpleStateChecked};
+    use crate::{assert_constraints_fail, assert_constraints_ok};
+
+    // BabyBear fits 16 columns in a cache line; column `i` is only ever read with column `i + 16`
+    const HALF: usize = 16;
+
+    struct PairsAir {}
+
+    impl<F> BaseAir<F> for PairsAir {
+        fn width(&self) -> usize {
+            2 * HALF
+        }
+    }
+
+    impl<AB: AirBuilder> Air<AB> for PairsAir {
+        fn eval(&self, builder: &mut AB) {
+            let main = builder.main();
+            let (local, next) = (main.row_slice(0), main.row_slice(1));
+            for i in 0..HALF {
+                builder.when_transition().assert_eq(next[i], local[i] + local[i + HALF]);
+                builder.when_transition().assert_eq(next[i + HALF], local[i + HALF]);
+            }
+        }
+    }
+
+    fn pairs_trace(height: usize) -> RowMajorMatrix<Val> {
+        let mut rng = thread_rng();
+        let mut values = (0..2 * HALF).map(|_| rng.gen::<Val>()).collect::<Vec<_>>();
+        for r in 1..height {
+            let prev = values[(r - 1) * 2 * HALF..r * 2 * HALF].to_vec();
+            values.extend((0..HALF).map(|i| prev[i] + prev[i + HALF]));
+            values.extend_from_slice(&prev[HALF..]);
+        }
+        RowMajorMatrix::new(values, 2 * HALF)
+    }
+
+    #[test]
+    fn test_co_accessed_columns_share_a_line() {
+        let order = optimize_column_order::<Val, _>(&PairsAir {}, 0);
+        assert_eq!(order.iter().copied().collect::<BTreeSet<_>>(), (0..2 * HALF).collect());
+
+        let positions = inverse_order(&order);
+        for i in 0..HALF {
+            assert_eq!(positions[i] / HALF, positions[i + HALF] / HALF, "columns {} and {}", i, i + HALF);
+        }
+    }
+
+    #[test]
+    fn test_reordered_air_accepts_permuted_trace() {
+        let order = optimize_column_order::<Val, _>(&PairsAir {}, 0);
+        let trace = pairs_trace(8);
+        let air = ReorderedAir::new(PairsAir {}, &order);
+
+        assert_constraints_ok!(&air, &permute_columns(&trace, &order), &[]);
+        // the trace in its original order no longer fits
+        assert_constraints_fail!(&air, &trace, &[], 0);
+    }
+
+    #[test]
+    fn test_reordered_round_trip() {
+        let order = optimize_column_order::<Val, _>(&SimpleStateChecked {}, 2);
+        let (trace, public_values) = random_checked_trace(6);
+        let air = ReorderedAir::new(SimpleStateChecked {}, &order);
+
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &air, &mut p_challenger, permute_columns(&trace, &order), &public_values);
+        let mut v_challenger = Challenger::new(perm);
+        verify(&config, &air, &mut v_challenger, &proof, &public_values).unwrap();
+
+        assert_eq!(inverse_order(&inverse_order(&order)), order);
+    }
+}
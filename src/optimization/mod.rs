@@ -0,0 +1 @@
+pub mod column_reorder;
@@ -0,0 +1,72 @@
+use p3_field::Field;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+use tracing::warn;
+
+use crate::config::FriParams;
+
+// Every FRI query opens one position of the LDE, which has `height << log_blowup` positions. When a trace is
+// so short that the LDE has fewer positions than there are queries, the queries necessarily repeat: the
+// proof pays for `num_queries` Merkle openings while covering the whole committed domain several times
+// over, and the soundness the query count suggests is capped by the number of distinct positions.
+//
+// The smallest height where that stops is where the LDE has a position for every query. Padding up to it
+// costs one Merkle level per doubling, which is small next to the fixed per-query cost it puts to use.
+// Padding rows are all zero, so the AIR must accept zero rows after the real ones (e.g. with an activity
+// flag that is 0 on padding, see `gadgets::zero_row_guard`).
+
+/// The smallest power-of-two height whose LDE has a distinct position for every query.
+pub fn optimal_min_height(params: &FriParams) -> usize {
+    let log_queries = params.num_queries.next_power_of_two().trailing_zeros() as usize;
+    // transitions need at least two rows
+    1 << log_queries.saturating_sub(params.log_blowup).max(1)
+}
+
+/// Pads `trace` with zero rows to the next power of two, and at least to `optimal_min_height`; warns if the
+/// trace was below that.
+pub fn pad_to_optimal<F: Field>(mut trace: RowMajorMatrix<F>, params: &FriParams) -> RowMajorMatrix<F> {
+    let height = trace.height();
+    let min_height = optimal_min_height(params);
+    if height < min_height {
+        warn!(
+            "trace of {} rows is below the {} rows where {} queries at blowup 2^{} stop repeating; padding",
+            height, min_height, params.num_queries, params.log_blowup
+        );
+    }
+
+    let target = height.next_power_of_two().max(min_height);
+    let width = trace.width();
+    trace.values.resize(target * width, F::zero());
+    trace
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_field::AbstractField;
+
+    use super::*;
+    use crate::config::Val;
+    use crate::simple_state::random_trace;
+
+    #[test]
+    fn test_tiny_trace_is_padded() {
+        let params = FriParams::default();
+        // 40 queries at blowup 4: 64 LDE positions, 16 rows
+        assert_eq!(optimal_min_height(&params), 16);
+
+        let trace = random_trace::<Val>(1);
+        let padded = pad_to_optimal(trace.clone(), &params);
+        assert_eq!(padded.height(), 16);
+        assert_eq!(padded.values[..trace.values.len()], trace.values[..]);
+        assert!(padded.values[trace.values.len()..].iter().all(|v| *v == Val::zero()));
+    }
+
+    #[test]
+    fn test_large_trace_is_unchanged() {
+        let params = FriParams { log_blowup: 1, num_queries: 100, proof_of_work_bits: 16 };
+        assert_eq!(optimal_min_height(&params), 64);
+
+        let trace = random_trace::<Val>(7);
+        assert_eq!(pad_to_optimal(trace.clone(), &params).values, trace.values);
+    }
+}
@@ -0,0 +1,64 @@
+use p3_air::Air;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::{prove, Proof, ProverConstraintFolder, StarkGenericConfig, SymbolicAirBuilder, Val};
+#[cfg(debug_assertions)]
+use p3_uni_stark::DebugConstraintBuilder;
+use rayon::{ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
+
+// With the `parallel` feature Plonky3's DFT, Merkle hashing and quotient evaluation run on rayon, which by
+// default means the global pool sized to every core. Proving inside `ThreadPool::install` routes all of
+// that work to a dedicated pool instead, so the prover can be capped or kept away from other services.
+
+/// A pool for proving with `num_threads` workers.
+pub fn build_prover_pool(num_threads: usize) -> Result<ThreadPool, ThreadPoolBuildError> {
+    ThreadPoolBuilder::new()
+        .num_threads(num_threads)
+        .thread_name(|i| format!("cook-prover-{}", i))
+        .build()
+}
+
+/// `prove`, with all of its parallel work confined to `pool`.
+pub fn prove_in_pool<
+    SC,
+    #[cfg(debug_assertions)] A: for<'a> Air<DebugConstraintBuilder<'a, Val<SC>>>,
+    #[cfg(not(debug_assertions))] A,
+>(
+    pool: &ThreadPool,
+    config: &SC,
+    air: &A,
+    challenger: &mut SC::Challenger,
+    trace: RowMajorMatrix<Val<SC>>,
+    public_values: &Vec<Val<SC>>,
+) -> Proof<SC>
+where
+    SC: StarkGenericConfig + Sync,
+    SC::Challenger: Send,
+    A: Air<SymbolicAirBuilder<Val<SC>>> + for<'a> Air<ProverConstraintFolder<'a, SC>> + Sync,
+    Proof<SC>: Send,
+{
+    pool.install(|| prove(config, air, challenger, trace, public_values))
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_uni_stark::verify;
+
+    use super::*;
+    use crate::config::{default_config, random_perm, Challenger, Val};
+    use crate::simple_state::{random_trace, SimpleState};
+
+    #[test]
+    fn test_prove_in_two_thread_pool() {
+        let pool = build_prover_pool(2).unwrap();
+        assert_eq!(pool.install(rayon::current_num_threads), 2);
+
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let trace = random_trace::<Val>(12);
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove_in_pool(&pool, &config, &SimpleState {}, &mut p_challenger, trace, &vec![]);
+        let mut v_challenger = Challenger::new(perm);
+        verify(&config, &SimpleState {}, &mut v_challenger, &proof, &vec![]).unwrap();
+    }
+}
@@ -0,0 +1,190 @@
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+
+// Constraints that read more than one row ahead. uni-stark evaluates constraints on the window
+// `(local, next)` only, so to see row `i + k` for `k` up to `K`, row `i` carries copies of the next `K - 1`
+// rows after its own columns:
+//   row i:   main[i] | main[i + 1] | ... | main[i + K - 1]
+// and each copy is tied to the row below it,
+//   copy_1 == next.main,   copy_k == next.copy_{k-1}
+// which puts `main[i + K]` in `next.copy_{K-1}`. The copies are constrained on every row, not only under
+// `when_transition`: the trace domain is cyclic, so on the last rows they hold the first rows again, and a
+// peek past the end wraps around exactly as `next` does on the last row. An inner AIR whose peeks must not
+// wrap guards them the way it would guard `next`, with a selector that is zero on the last `K` rows.
+//
+// `PeekAhead<A, K>` is the inner AIR `A` proven over `expand_trace::<K>(trace)`. `A` sees a builder whose
+// `main` is its own columns of `(local, next)`, and which adds `peek(k)`, row `i + k` for `k <= K`.
+
+/// `A` with `K - 1` copies of the following rows appended to each row, so that it can read `K` rows ahead.
+pub struct PeekAhead<A, const K: usize> {
+    pub inner: A,
+}
+
+impl<F, A: BaseAir<F>, const K: usize> BaseAir<F> for PeekAhead<A, K> {
+    fn width(&self) -> usize {
+        self.inner.width() * K
+    }
+}
+
+impl<AB, A, const K: usize> Air<AB> for PeekAhead<A, K>
+where
+    AB: AirBuilderWithPublicValues,
+    A: BaseAir<AB::F> + for<'b> Air<PeekAheadAirBuilder<'b, AB, K>>,
+{
+    fn eval(&self, builder: &mut AB) {
+        assert!(K > 0, "a window reaches at least the next row");
+        let width = self.inner.width();
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+
+        // copy k of this row is copy k - 1 of the next, copy 0 being the row itself
+        for k in 1..K {
+            for c in 0..width {
+                builder.assert_eq(local[k * width + c], next[(k - 1) * width + c]);
+            }
+        }
+
+        let mut window = (0..K).map(|k| local[k * width..(k + 1) * width].to_vec()).collect::<Vec<_>>();
+        window.push(next[(K - 1) * width..K * width].to_vec());
+        self.inner.eval(&mut PeekAheadAirBuilder { inner: builder, width, window });
+    }
+}
+
+/// Copies `trace` with each row followed by the `K - 1` rows after it, wrapping around the end.
+pub fn expand_trace<F: Clone + Send + Sync, const K: usize>(trace: &RowMajorMatrix<F>) -> RowMajorMatrix<F> {
+    let (width, height) = (trace.width(), trace.height());
+    let mut values = Vec::with_capacity(height * width * K);
+    for i in 0..height {
+        for k in 0..K {
+            let r = (i + k) % height;
+            values.extend_from_slice(&trace.values[r * width..(r + 1) * width]);
+        }
+    }
+    RowMajorMatrix::new(values, width * K)
+}
+
+/// A builder that forwards everything to `inner`, shows only the inner AIR's columns as `main`, and reads
+/// up to `K` rows ahead with `peek`.
+pub struct PeekAheadAirBuilder<'a, AB: AirBuilder, const K: usize> {
+    inner: &'a mut AB,
+    width: usize,
+    /// rows `i` to `i + K`, the inner AIR's columns of each
+    window: Vec<Vec<AB::Var>>,
+}
+
+impl<'a, AB: AirBuilder, const K: usize> PeekAheadAirBuilder<'a, AB, K> {
+    /// Row `i + k` of the inner trace, for `k <= K`.
+    pub fn peek(&self, k: usize) -> &[AB::Var] {
+        assert!(k <= K, "the window reaches {} rows ahead, not {}", K, k);
+        &self.window[k]
+    }
+}
+
+impl<'a, AB: AirBuilder, const K: usize> AirBuilder for PeekAheadAirBuilder<'a, AB, K> {
+    type F = AB::F;
+    type Expr = AB::Expr;
+    type Var = AB::Var;
+    type M = RowMajorMatrix<AB::Var>;
+
+    fn main(&self) -> Self::M {
+        RowMajorMatrix::new([self.window[0].clone(), self.window[1].clone()].concat(), self.width)
+    }
+
+    fn is_first_row(&self) -> Self::Expr {
+        self.inner.is_first_row()
+    }
+
+    fn is_last_row(&self) -> Self::Expr {
+        self.inner.is_last_row()
+    }
+
+    fn is_transition_window(&self, size: usize) -> Self::Expr {
+        self.inner.is_transition_window(size)
+    }
+
+    fn assert_zero<I: Into<Self::Expr>>(&mut self, x: I) {
+        self.inner.assert_zero(x);
+    }
+}
+
+impl<'a, AB: AirBuilderWithPublicValues, const K: usize> AirBuilderWithPublicValues
+    for PeekAheadAirBuilder<'a, AB, K>
+{
+    type PublicVar = AB::PublicVar;
+
+    fn public_values(&self) -> &[Self::PublicVar] {
+        self.inner.public_values()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_field::AbstractField;
+    use p3_uni_stark::{prove, verify};
+    use rand::{thread_rng, Rng};
+
+    use super::*;
+    use crate::config::{default_config, random_perm, Challenger, Val};
+    use crate::{assert_constraints_fail, assert_constraints_ok};
+
+    /// `s[i] == x[i] + x[i + 1] + x[i + 2]`, cyclically.
+    struct WindowSum {}
+
+    impl<F> BaseAir<F> for WindowSum {
+        fn width(&self) -> usize {
+            2
+        }
+    }
+
+    impl<'a, AB: AirBuilderWithPublicValues> Air<PeekAheadAirBuilder<'a, AB, 2>> for WindowSum {
+        fn eval(&self, builder: &mut PeekAheadAirBuilder<'a, AB, 2>) {
+            let (x0, x1, x2) = (builder.peek(0)[0], builder.peek(1)[0], builder.peek(2)[0]);
+            let s = builder.peek(0)[1];
+            builder.assert_eq(s, x0 + x1 + x2);
+        }
+    }
+
+    fn window_sum_trace(height: usize) -> RowMajorMatrix<Val> {
+        let mut rng = thread_rng();
+        let x = (0..height).map(|_| rng.gen()).collect::<Vec<Val>>();
+        let values = (0..height).flat_map(|i| [x[i], x[i] + x[(i + 1) % height] + x[(i + 2) % height]]);
+        RowMajorMatrix::new(values.collect(), 2)
+    }
+
+    const AIR: PeekAhead<WindowSum, 2> = PeekAhead { inner: WindowSum {} };
+
+    #[test]
+    fn test_peek_two_rows_ahead() {
+        let trace = window_sum_trace(16);
+        assert_constraints_ok!(&AIR, &expand_trace::<_, 2>(&trace), &[]);
+        // the bare trace has no copies to read
+        assert_eq!(expand_trace::<_, 1>(&trace).values, trace.values);
+    }
+
+    #[test]
+    fn test_tampered_row_fails() {
+        let mut trace = window_sum_trace(16);
+        trace.row_mut(5)[0] += Val::one();
+
+        // the first window reaching row 5
+        assert_constraints_fail!(&AIR, &expand_trace::<_, 2>(&trace), &[], 3);
+
+        // a row changed without its copies breaks at the row above, which holds one
+        let mut expanded = expand_trace::<_, 2>(&window_sum_trace(16));
+        expanded.row_mut(5)[0] += Val::one();
+        assert_constraints_fail!(&AIR, &expanded, &[], 4);
+    }
+
+    #[test]
+    fn test_peek_ahead_round_trip() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let trace = expand_trace::<_, 2>(&window_sum_trace(64));
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &AIR, &mut p_challenger, trace, &vec![]);
+        let mut v_challenger = Challenger::new(perm);
+        verify(&config, &AIR, &mut v_challenger, &proof, &vec![]).unwrap();
+    }
+}
@@ -0,0 +1,363 @@
+use std::collections::BTreeMap;
+use std::panic;
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+
+use p3_air::Air;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::{prove, Proof, ProverConstraintFolder, StarkGenericConfig, SymbolicAirBuilder, Val};
+#[cfg(debug_assertions)]
+use p3_uni_stark::DebugConstraintBuilder;
+#[cfg(feature = "parallel")]
+use rayon::ThreadPool;
+
+#[cfg(feature = "parallel")]
+use crate::parallel::prove_in_pool;
+
+// Segments are independent proofs, so generating the trace of one can overlap with proving another.
+// Generator threads pull segments in order and push finished traces into a bounded channel; the returned
+// iterator proves them on the caller's thread, where `prove`'s parallel work goes to the global rayon pool
+// (or whichever pool the iterator is consumed in). With the `parallel` feature, `prove_stream_in_pool` runs
+// each proof inside a dedicated pool's `install` instead, as `parallel::prove_in_pool` does. The
+// channel bound caps how many traces wait in memory, and a reorder buffer puts traces finished out of
+// order by several generators back in segment order. A generator only starts a segment at most
+// `queue_depth` past the one the prover is waiting for, so a slow segment can't let the others pile up in
+// the reorder buffer behind it.
+
+#[derive(Clone, Copy, Debug)]
+pub struct PipelineOptions {
+    /// threads generating traces
+    pub generator_threads: usize,
+    /// generated traces allowed to wait for the prover
+    pub queue_depth: usize,
+}
+
+impl Default for PipelineOptions {
+    fn default() -> Self {
+        Self { generator_threads: 1, queue_depth: 2 }
+    }
+}
+
+pub struct SegmentProof<SC: StarkGenericConfig> {
+    pub public_values: Vec<Val<SC>>,
+    pub proof: Proof<SC>,
+}
+
+type Generated<F> = (usize, RowMajorMatrix<F>, Vec<F>);
+
+/// The segment the consumer waits for, shared with the generators, and whether they should stop.
+struct Progress {
+    state: Mutex<(usize, bool)>,
+    changed: Condvar,
+}
+
+impl Progress {
+    /// Blocks until `index` is at most `depth` past the awaited segment; false if the pipeline stopped.
+    fn wait_for(&self, index: usize, depth: usize) -> bool {
+        let mut state = self.state.lock().unwrap();
+        while !state.1 && index > state.0 + depth {
+            state = self.changed.wait(state).unwrap();
+        }
+        !state.1
+    }
+
+    fn advance(&self, next_index: usize) {
+        self.state.lock().unwrap().0 = next_index;
+        self.changed.notify_all();
+    }
+
+    fn stop(&self) {
+        self.state.lock().unwrap().1 = true;
+        self.changed.notify_all();
+    }
+}
+
+/// Stops the pipeline when dropped: by the consumer when it stops early, by a generator when it panics.
+struct StopGuard {
+    progress: Arc<Progress>,
+    only_when_panicking: bool,
+}
+
+impl Drop for StopGuard {
+    fn drop(&mut self) {
+        if !self.only_when_panicking || thread::panicking() {
+            self.progress.stop();
+        }
+    }
+}
+
+/// Generates and proves `segments` in a pipeline, yielding the proofs in segment order.
+///
+/// `generate` turns a segment into its trace and public values; each proof starts from a clone of
+/// `challenger`. A panic in `generate` is re-raised on the consuming thread.
+pub fn prove_stream<
+    'a,
+    SC,
+    #[cfg(debug_assertions)] A: for<'b> Air<DebugConstraintBuilder<'b, Val<SC>>>,
+    #[cfg(not(debug_assertions))] A,
+    S,
+    G,
+>(
+    config: &'a SC,
+    air: &'a A,
+    challenger: SC::Challenger,
+    segments: S,
+    generate: G,
+    options: PipelineOptions,
+) -> impl Iterator<Item = SegmentProof<SC>> + 'a
+where
+    SC: StarkGenericConfig,
+    SC::Challenger: Clone + 'a,
+    A: Air<SymbolicAirBuilder<Val<SC>>> + for<'b> Air<ProverConstraintFolder<'b, SC>>,
+    Val<SC>: Send,
+    S: IntoIterator,
+    S::IntoIter: Send + 'static,
+    G: Fn(S::Item) -> (RowMajorMatrix<Val<SC>>, Vec<Val<SC>>) + Send + Sync + 'static,
+{
+    generate_in_order(segments.into_iter(), generate, options).map(move |(trace, public_values)| {
+        let mut challenger = challenger.clone();
+        let proof = prove(config, air, &mut challenger, trace, &public_values);
+        SegmentProof { public_values, proof }
+    })
+}
+
+/// `prove_stream`, with each proof's parallel work confined to `pool`.
+#[cfg(feature = "parallel")]
+pub fn prove_stream_in_pool<
+    'a,
+    SC,
+    #[cfg(debug_assertions)] A: for<'b> Air<DebugConstraintBuilder<'b, Val<SC>>>,
+    #[cfg(not(debug_assertions))] A,
+    S,
+    G,
+>(
+    pool: &'a ThreadPool,
+    config: &'a SC,
+    air: &'a A,
+    challenger: SC::Challenger,
+    segments: S,
+    generate: G,
+    options: PipelineOptions,
+) -> impl Iterator<Item = SegmentProof<SC>> + 'a
+where
+    SC: StarkGenericConfig + Sync,
+    SC::Challenger: Clone + Send + 'a,
+    A: Air<SymbolicAirBuilder<Val<SC>>> + for<'b> Air<ProverConstraintFolder<'b, SC>> + Sync,
+    Proof<SC>: Send,
+    Val<SC>: Send,
+    S: IntoIterator,
+    S::IntoIter: Send + 'static,
+    G: Fn(S::Item) -> (RowMajorMatrix<Val<SC>>, Vec<Val<SC>>) + Send + Sync + 'static,
+{
+    generate_in_order(segments.into_iter(), generate, options).map(move |(trace, public_values)| {
+        let mut challenger = challenger.clone();
+        let proof = prove_in_pool(pool, config, air, &mut challenger, trace, &public_values);
+        SegmentProof { public_values, proof }
+    })
+}
+
+/// The generator threads, as an iterator over the generated traces in segment order.
+fn generate_in_order<F, I, G>(
+    segments: I,
+    generate: G,
+    options: PipelineOptions,
+) -> impl Iterator<Item = (RowMajorMatrix<F>, Vec<F>)>
+where
+    F: Send + 'static,
+    I: Iterator + Send + 'static,
+    G: Fn(I::Item) -> (RowMajorMatrix<F>, Vec<F>) + Send + Sync + 'static,
+{
+    assert!(options.generator_threads > 0, "at least one generator thread is needed");
+
+    let (tx, rx) = sync_channel::<Generated<F>>(options.queue_depth);
+    let segments = Arc::new(Mutex::new(segments.enumerate()));
+    let generate = Arc::new(generate);
+    let progress = Arc::new(Progress { state: Mutex::new((0, false)), changed: Condvar::new() });
+
+    let mut workers = (0..options.generator_threads)
+        .map(|_| {
+            let (tx, segments, generate) = (tx.clone(), segments.clone(), generate.clone());
+            let progress = progress.clone();
+            thread::spawn(move || {
+                let _guard = StopGuard { progress: progress.clone(), only_when_panicking: true };
+                loop {
+                    // the lock is held only to take the next segment, not to generate it
+                    let Some((index, segment)) = segments.lock().unwrap().next() else { return };
+                    if !progress.wait_for(index, options.queue_depth) {
+                        return;
+                    }
+                    let (trace, public_values) = generate(segment);
+                    if tx.send((index, trace, public_values)).is_err() {
+                        // the consumer stopped early
+                        return;
+                    }
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    drop(tx);
+
+    let stop_on_drop = StopGuard { progress, only_when_panicking: false };
+    let mut pending = BTreeMap::new();
+    let mut next_index = 0;
+    std::iter::from_fn(move || {
+        let generated = next_in_order(&rx, &mut pending, next_index, &mut workers)?;
+        next_index += 1;
+        stop_on_drop.progress.advance(next_index);
+        Some(generated)
+    })
+}
+
+fn next_in_order<F>(
+    rx: &Receiver<Generated<F>>,
+    pending: &mut BTreeMap<usize, (RowMajorMatrix<F>, Vec<F>)>,
+    index: usize,
+    workers: &mut Vec<JoinHandle<()>>,
+) -> Option<(RowMajorMatrix<F>, Vec<F>)> {
+    loop {
+        if let Some(generated) = pending.remove(&index) {
+            return Some(generated);
+        }
+        match rx.recv() {
+            Ok((i, trace, public_values)) => {
+                pending.insert(i, (trace, public_values));
+            }
+            Err(_) => {
+                // every generator is done; if one of them panicked, a segment is missing
+                for worker in workers.drain(..) {
+                    if let Err(e) = worker.join() {
+                        panic::resume_unwind(e);
+                    }
+                }
+                return None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use p3_uni_stark::verify;
+
+    use super::*;
+    use crate::config::{default_config, random_perm, Challenger, Val};
+    use crate::simple_state::SimpleStateChecked;
+    use crate::transaction::{trace_from_transactions, validate_all, Transaction};
+
+    /// Segments of `SimpleStateChecked` transactions, each starting from the previous one's final balance.
+    fn chained_segments(lens: &[usize]) -> Vec<(u32, Vec<Transaction>)> {
+        let mut balance = 1000;
+        lens.iter()
+            .enumerate()
+            .map(|(s, &len)| {
+                let txs = (0..len as u32).map(|i| Transaction::new(s as u32 + i, i % 3)).collect::<Vec<_>>();
+                let initial = balance;
+                balance = *validate_all(initial, &txs).unwrap().last().unwrap();
+                (initial, txs)
+            })
+            .collect()
+    }
+
+    fn generate((initial, txs): (u32, Vec<Transaction>)) -> (RowMajorMatrix<Val>, Vec<Val>) {
+        trace_from_transactions(initial, &txs).unwrap()
+    }
+
+    #[test]
+    fn test_proofs_come_out_in_segment_order() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+
+        // the large first segment finishes last on a multi-threaded generator
+        let segments = chained_segments(&[1 << 10, 4, 8, 2, 16, 4]);
+        let expected = segments.iter().map(|(initial, txs)| generate((*initial, txs.clone())).1).collect::<Vec<_>>();
+
+        let options = PipelineOptions { generator_threads: 3, queue_depth: 1 };
+        let challenger = Challenger::new(perm.clone());
+        let proofs = prove_stream(&config, &SimpleStateChecked {}, challenger, segments, generate, options)
+            .collect::<Vec<_>>();
+
+        assert_eq!(proofs.len(), expected.len());
+        for (segment, expected) in proofs.iter().zip(&expected) {
+            assert_eq!(&segment.public_values, expected);
+
+            let mut v_challenger = Challenger::new(perm.clone());
+            verify(&config, &SimpleStateChecked {}, &mut v_challenger, &segment.proof, &segment.public_values)
+                .unwrap();
+        }
+
+        // consecutive segments chain: each starts where the previous ended
+        for pair in proofs.windows(2) {
+            assert_eq!(pair[0].public_values[1], pair[1].public_values[0]);
+        }
+    }
+
+    #[test]
+    fn test_generators_stay_within_queue_depth() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+
+        // `consumed` trails the prover's position by at most the proof in progress
+        let consumed = Arc::new(AtomicUsize::new(0));
+        let depth = 1;
+        let generate_bounded = {
+            let consumed = consumed.clone();
+            move |(index, segment): (usize, (u32, Vec<Transaction>))| {
+                assert!(index <= consumed.load(Ordering::SeqCst) + depth + 1, "segment {} started too early", index);
+                generate(segment)
+            }
+        };
+
+        // a slow first segment, which the other generators must not run far ahead of
+        let segments = chained_segments(&[1 << 10, 4, 8, 2, 16, 4, 2, 8]).into_iter().enumerate().collect::<Vec<_>>();
+        let options = PipelineOptions { generator_threads: 4, queue_depth: depth };
+        let challenger = Challenger::new(perm);
+        let proofs = prove_stream(&config, &SimpleStateChecked {}, challenger, segments, generate_bounded, options)
+            .inspect(|_| {
+                consumed.fetch_add(1, Ordering::SeqCst);
+            })
+            .count();
+        assert_eq!(proofs, 8);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_stream_proves_in_parallel_pool() {
+        let pool = crate::parallel::build_prover_pool(2).unwrap();
+        let perm = random_perm();
+        let config = default_config(&perm);
+
+        let segments = chained_segments(&[8, 4, 16]);
+        let challenger = Challenger::new(perm.clone());
+        let (air, options) = (SimpleStateChecked {}, PipelineOptions::default());
+        let proofs =
+            prove_stream_in_pool(&pool, &config, &air, challenger, segments, generate, options).collect::<Vec<_>>();
+
+        assert_eq!(proofs.len(), 3);
+        for segment in &proofs {
+            let mut v_challenger = Challenger::new(perm.clone());
+            verify(&config, &SimpleStateChecked {}, &mut v_challenger, &segment.proof, &segment.public_values)
+                .unwrap();
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "bad segment")]
+    fn test_generator_panic_reaches_consumer() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+
+        let challenger = Challenger::new(perm);
+        let stream = prove_stream(
+            &config,
+            &SimpleStateChecked {},
+            challenger,
+            vec![0u32, 1],
+            |s| if s == 1 { panic!("bad segment") } else { generate((1000, vec![Transaction::new(1, 0)])) },
+            PipelineOptions::default(),
+        );
+        stream.for_each(drop);
+    }
+}
@@ -0,0 +1,248 @@
+use std::borrow::Borrow;
+
+use p3_air::AirBuilder;
+use p3_baby_bear::DiffusionMatrixBabyBear;
+use p3_field::AbstractField;
+use p3_poseidon2::Poseidon2ExternalMatrixGeneral;
+use p3_symmetric::Permutation;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::config::{Perm, Val};
+
+// Constraints for one BabyBear width-16 Poseidon2 permutation laid out on a single row: the inputs, then the
+// initial linear layer, 4 full rounds, 13 partial rounds and 4 full rounds left to right. Each S-box `x^7`
+// takes two columns, `cube = x^3` and `out = cube^2 * x`, keeping constraints at degree 3.
+//
+// The linear layers are constant matrices read off the native `Poseidon2ExternalMatrixGeneral` and
+// `DiffusionMatrixBabyBear` by applying them to unit vectors. The round constants are generated here and
+// handed to `Perm::new`, so the AIR and the native permutation are the same instance; a `Perm` built by
+// `random_perm` keeps its constants private and can't be mirrored.
+
+pub const WIDTH: usize = 16;
+pub const HALF_FULL_ROUNDS: usize = 4;
+pub const PARTIAL_ROUNDS: usize = 13;
+
+pub struct Poseidon2Constants {
+    pub external: Vec<[Val; WIDTH]>,
+    pub internal: Vec<Val>,
+    /// `external_matrix[i][j]`: row `i`, column `j`
+    pub external_matrix: [[Val; WIDTH]; WIDTH],
+    /// the internal layer is `state[i] = sum(state) + internal_diag[i] * state[i]`
+    pub internal_diag: [Val; WIDTH],
+}
+
+/// Column `j` of a linear layer is its image of the `j`th unit vector.
+fn columns_of(layer: impl Permutation<[Val; WIDTH]>) -> [[Val; WIDTH]; WIDTH] {
+    core::array::from_fn(|j| layer.permute(core::array::from_fn(|i| Val::from_bool(i == j))))
+}
+
+impl Poseidon2Constants {
+    pub fn from_seed(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let external = (0..2 * HALF_FULL_ROUNDS).map(|_| core::array::from_fn(|_| rng.gen())).collect();
+        let internal = (0..PARTIAL_ROUNDS).map(|_| rng.gen()).collect();
+
+        let external_columns = columns_of(Poseidon2ExternalMatrixGeneral);
+        let internal_columns = columns_of(DiffusionMatrixBabyBear::default());
+        Poseidon2Constants {
+            external,
+            internal,
+            external_matrix: core::array::from_fn(|i| core::array::from_fn(|j| external_columns[j][i])),
+            internal_diag: core::array::from_fn(|i| internal_columns[i][i] - Val::one()),
+        }
+    }
+
+    /// The native permutation with the same constants.
+    pub fn perm(&self) -> Perm {
+        Perm::new(
+            2 * HALF_FULL_ROUNDS,
+            self.external.clone(),
+            Poseidon2ExternalMatrixGeneral,
+            PARTIAL_ROUNDS,
+            self.internal.clone(),
+            DiffusionMatrixBabyBear::default(),
+        )
+    }
+
+    pub fn external_layer<E>(&self, state: &[E; WIDTH]) -> [E; WIDTH]
+    where
+        E: AbstractField + std::ops::Mul<Val, Output = E>,
+    {
+        core::array::from_fn(|i| state.iter().zip(self.external_matrix[i]).map(|(s, m)| s.clone() * m).sum())
+    }
+
+    pub fn internal_layer<E>(&self, state: &[E; WIDTH]) -> [E; WIDTH]
+    where
+        E: AbstractField + std::ops::Mul<Val, Output = E>,
+    {
+        let sum: E = state.iter().cloned().sum();
+        core::array::from_fn(|i| sum.clone() + state[i].clone() * self.internal_diag[i])
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct SBox<F> {
+    pub cube: F,
+    pub out: F,
+}
+
+pub const PERMUTATION_WIDTH: usize = WIDTH + 2 * (2 * HALF_FULL_ROUNDS * WIDTH + PARTIAL_ROUNDS);
+
+/// The columns of one permutation, `PERMUTATION_WIDTH` wide; embed it in a row struct.
+pub struct PermutationCols<F> {
+    pub inputs: [F; WIDTH],
+    pub beginning_full_rounds: [[SBox<F>; WIDTH]; HALF_FULL_ROUNDS],
+    pub partial_rounds: [SBox<F>; PARTIAL_ROUNDS],
+    pub ending_full_rounds: [[SBox<F>; WIDTH]; HALF_FULL_ROUNDS],
+}
+
+impl<F> Borrow<PermutationCols<F>> for [F] {
+    fn borrow(&self) -> &PermutationCols<F> {
+        debug_assert_eq!(self.len(), PERMUTATION_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<PermutationCols<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+fn eval_sbox<AB: AirBuilder>(builder: &mut AB, x: AB::Expr, sbox: &SBox<AB::Var>) {
+    builder.assert_eq(sbox.cube, x.clone() * x.clone() * x.clone());
+    builder.assert_eq(sbox.out, sbox.cube * sbox.cube * x);
+}
+
+/// Constrains `cols` to be a permutation of `cols.inputs`; returns the output state.
+pub fn eval_permutation<AB: AirBuilder<F = Val>>(
+    builder: &mut AB,
+    c: &Poseidon2Constants,
+    cols: &PermutationCols<AB::Var>,
+) -> [AB::Expr; WIDTH] {
+    let mut state: [AB::Expr; WIDTH] = c.external_layer(&cols.inputs.map(Into::into));
+
+    for (r, round) in cols.beginning_full_rounds.iter().enumerate() {
+        for (i, sbox) in round.iter().enumerate() {
+            eval_sbox(builder, state[i].clone() + c.external[r][i], sbox);
+        }
+        state = c.external_layer(&round.map(|sbox| sbox.out.into()));
+    }
+
+    for (r, sbox) in cols.partial_rounds.iter().enumerate() {
+        eval_sbox(builder, state[0].clone() + c.internal[r], sbox);
+        state[0] = sbox.out.into();
+        state = c.internal_layer(&state);
+    }
+
+    for (r, round) in cols.ending_full_rounds.iter().enumerate() {
+        for (i, sbox) in round.iter().enumerate() {
+            eval_sbox(builder, state[i].clone() + c.external[HALF_FULL_ROUNDS + r][i], sbox);
+        }
+        state = c.external_layer(&round.map(|sbox| sbox.out.into()));
+    }
+
+    state
+}
+
+fn sbox(x: Val) -> SBox<Val> {
+    let cube = x.cube();
+    SBox { cube, out: cube * cube * x }
+}
+
+/// Fills `cols` with the permutation of `inputs`; returns the output state.
+pub fn generate_permutation(
+    c: &Poseidon2Constants,
+    inputs: [Val; WIDTH],
+    cols: &mut PermutationCols<Val>,
+) -> [Val; WIDTH] {
+    cols.inputs = inputs;
+    let mut state = c.external_layer(&inputs);
+
+    for (r, round) in cols.beginning_full_rounds.iter_mut().enumerate() {
+        *round = core::array::from_fn(|i| sbox(state[i] + c.external[r][i]));
+        state = c.external_layer(&round.map(|s| s.out));
+    }
+    for (r, round) in cols.partial_rounds.iter_mut().enumerate() {
+        *round = sbox(state[0] + c.internal[r]);
+        state[0] = round.out;
+        state = c.internal_layer(&state);
+    }
+    for (r, round) in cols.ending_full_rounds.iter_mut().enumerate() {
+        *round = core::array::from_fn(|i| sbox(state[i] + c.external[HALF_FULL_ROUNDS + r][i]));
+        state = c.external_layer(&round.map(|s| s.out));
+    }
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Borrow;
+
+    use p3_air::{Air, BaseAir};
+    use p3_matrix::Matrix;
+    use p3_matrix::dense::RowMajorMatrix;
+    use rand::thread_rng;
+
+    use super::*;
+    use crate::{assert_constraints_fail, assert_constraints_ok};
+
+    // one permutation per row, output unconstrained
+    struct PermutationAir {
+        constants: Poseidon2Constants,
+    }
+
+    impl<F> BaseAir<F> for PermutationAir {
+        fn width(&self) -> usize {
+            PERMUTATION_WIDTH
+        }
+    }
+
+    impl<AB: AirBuilder<F = Val>> Air<AB> for PermutationAir {
+        fn eval(&self, builder: &mut AB) {
+            let main = builder.main();
+            let local = main.row_slice(0);
+            let local: &PermutationCols<AB::Var> = (*local).borrow();
+            eval_permutation(builder, &self.constants, local);
+        }
+    }
+
+    fn random_state() -> [Val; WIDTH] {
+        core::array::from_fn(|_| thread_rng().gen())
+    }
+
+    fn trace_of(c: &Poseidon2Constants, inputs: &[[Val; WIDTH]]) -> (RowMajorMatrix<Val>, Vec<[Val; WIDTH]>) {
+        let mut trace = RowMajorMatrix::new(vec![Val::zero(); inputs.len() * PERMUTATION_WIDTH], PERMUTATION_WIDTH);
+        let (_, rows, _) = unsafe { trace.values.align_to_mut::<PermutationCols<Val>>() };
+        let outputs = rows.iter_mut().zip(inputs).map(|(row, &input)| generate_permutation(c, input, row)).collect();
+        (trace, outputs)
+    }
+
+    #[test]
+    fn test_linear_layers_match_native() {
+        let c = Poseidon2Constants::from_seed(1);
+        let state = random_state();
+        assert_eq!(c.external_layer(&state), Poseidon2ExternalMatrixGeneral.permute(state));
+        assert_eq!(c.internal_layer(&state), DiffusionMatrixBabyBear::default().permute(state));
+    }
+
+    #[test]
+    fn test_generated_permutation_matches_native() {
+        let c = Poseidon2Constants::from_seed(1);
+        let inputs = [random_state(), [Val::zero(); WIDTH]];
+        let (_, outputs) = trace_of(&c, &inputs);
+        for (input, output) in inputs.iter().zip(outputs) {
+            assert_eq!(output, c.perm().permute(*input));
+        }
+    }
+
+    #[test]
+    fn test_permutation_constraints() {
+        let air = PermutationAir { constants: Poseidon2Constants::from_seed(1) };
+        let (mut trace, _) = trace_of(&air.constants, &[random_state(), random_state(), random_state(), random_state()]);
+        assert_constraints_ok!(&air, &trace, &[]);
+
+        // a partial-round S-box output
+        let col = WIDTH + 2 * HALF_FULL_ROUNDS * WIDTH + 1;
+        trace.row_mut(2)[col] += Val::one();
+        assert_constraints_fail!(&air, &trace, &[], 2);
+    }
+}
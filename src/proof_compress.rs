@@ -0,0 +1,168 @@
+use std::marker::PhantomData;
+
+use p3_uni_stark::{Proof, StarkGenericConfig};
+
+use crate::error::VerifyFailure;
+
+// FRI query positions are not part of a uni-stark proof: the verifier re-samples them from the transcript,
+// so there are no absolute positions to delta-encode. What a proof does carry is the bincode encoding of
+// Merkle paths, opened values and length prefixes, and the length prefixes (u64s holding small counts) and
+// the high bytes of small field elements are long runs of zeros. The compressed form run-length codes those
+// zero runs, which is cheap, lossless and byte-for-byte reversible.
+
+/// A proof serialized with bincode and zero-run-length coded.
+#[derive(Clone, Debug)]
+pub struct CompressedProof<SC> {
+    bytes: Vec<u8>,
+    raw_len: usize,
+    _phantom: PhantomData<SC>,
+}
+
+impl<SC> CompressedProof<SC> {
+    /// Size of the compressed encoding in bytes.
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Size of the plain bincode encoding in bytes.
+    pub fn raw_len(&self) -> usize {
+        self.raw_len
+    }
+
+    /// Compressed size over plain size; below 1 means the coding saved space.
+    pub fn ratio(&self) -> f64 {
+        self.bytes.len() as f64 / self.raw_len as f64
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+pub fn compress_proof<SC: StarkGenericConfig>(proof: Proof<SC>) -> CompressedProof<SC> {
+    let raw = bincode::serialize(&proof).expect("proofs always serialize");
+    CompressedProof {
+        bytes: encode_zero_runs(&raw),
+        raw_len: raw.len(),
+        _phantom: PhantomData,
+    }
+}
+
+pub fn decompress_proof<SC: StarkGenericConfig>(compressed: CompressedProof<SC>) -> Proof<SC> {
+    // `CompressedProof` can only be built by `compress_proof`, so the bytes always decode
+    let raw = decode_zero_runs(&compressed.bytes).expect("compressed proof was produced by compress_proof");
+    debug_assert_eq!(raw.len(), compressed.raw_len);
+    bincode::deserialize(&raw).expect("compressed proof was produced by compress_proof")
+}
+
+/// Decodes the bytes of a `CompressedProof` (`as_bytes`) received from elsewhere.
+pub fn decompress<SC: StarkGenericConfig>(bytes: &[u8]) -> Result<Proof<SC>, VerifyFailure> {
+    let raw = decode_zero_runs(bytes).ok_or(VerifyFailure::ProofShape)?;
+    bincode::deserialize(&raw).map_err(|_| VerifyFailure::ProofShape)
+}
+
+/// Replaces every run of 1..=255 zero bytes with `[0, run_length]`; other bytes are copied as is.
+fn encode_zero_runs(raw: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(raw.len());
+    let mut i = 0;
+    while i < raw.len() {
+        if raw[i] == 0 {
+            let run = raw[i..].iter().take(u8::MAX as usize).take_while(|&&b| b == 0).count();
+            out.extend([0, run as u8]);
+            i += run;
+        } else {
+            out.push(raw[i]);
+            i += 1;
+        }
+    }
+    out
+}
+
+/// Inverts `encode_zero_runs`; `None` if the input ends in a zero run without a length.
+fn decode_zero_runs(encoded: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(encoded.len() * 2);
+    let mut bytes = encoded.iter();
+    while let Some(&b) = bytes.next() {
+        if b == 0 {
+            let run = *bytes.next()?;
+            out.resize(out.len() + run as usize, 0);
+        } else {
+            out.push(b);
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_uni_stark::{prove, verify};
+
+    use super::*;
+    use crate::config::{default_config, random_perm, Challenger, MyConfig, Val};
+    use crate::simple_state::{random_trace, SimpleState};
+
+    #[test]
+    fn test_zero_runs_round_trip() {
+        let mut raw = vec![1, 0, 0, 2, 0];
+        raw.extend(vec![0; 600]);
+        raw.push(3);
+
+        let encoded = encode_zero_runs(&raw);
+        assert!(encoded.len() < raw.len());
+        assert_eq!(decode_zero_runs(&encoded), Some(raw));
+        assert_eq!(decode_zero_runs(&encode_zero_runs(&[])), Some(vec![]));
+        assert_eq!(decode_zero_runs(&[1, 0]), None);
+    }
+
+    #[test]
+    fn test_compressed_proof_verifies() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let trace = random_trace::<Val>(10);
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &SimpleState {}, &mut p_challenger, trace, &vec![]);
+        let raw = bincode::serialize(&proof).unwrap();
+
+        let compressed = compress_proof(proof);
+        assert_eq!(compressed.raw_len(), raw.len());
+        // shown with `--nocapture`
+        println!(
+            "compressed a {} byte proof to {} bytes, ratio {:.3}",
+            raw.len(),
+            compressed.as_bytes().len(),
+            compressed.ratio()
+        );
+        assert!(compressed.ratio() < 1.0, "no space saved: ratio {:.3}", compressed.ratio());
+
+        let proof = decompress_proof(compressed);
+        assert_eq!(bincode::serialize(&proof).unwrap(), raw);
+
+        let mut v_challenger = Challenger::new(perm);
+        verify(&config, &SimpleState {}, &mut v_challenger, &proof, &vec![]).unwrap();
+    }
+
+    #[test]
+    fn test_decompress_round_trip() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let trace = random_trace::<Val>(8);
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &SimpleState {}, &mut p_challenger, trace, &vec![]);
+        let raw = bincode::serialize(&proof).unwrap();
+        let bytes = compress_proof(proof).as_bytes().to_vec();
+
+        let proof = decompress::<MyConfig>(&bytes).unwrap();
+        assert_eq!(bincode::serialize(&proof).unwrap(), raw);
+        let mut v_challenger = Challenger::new(perm);
+        verify(&config, &SimpleState {}, &mut v_challenger, &proof, &vec![]).unwrap();
+
+        assert!(matches!(decompress::<MyConfig>(&bytes[..bytes.len() / 2]), Err(VerifyFailure::ProofShape)));
+        assert!(matches!(decompress::<MyConfig>(&[1, 0]), Err(VerifyFailure::ProofShape)));
+    }
+}
@@ -0,0 +1,86 @@
+use p3_field::PrimeField32;
+use p3_uni_stark::{verify, Proof};
+
+use crate::config::{Challenger, MyConfig, Val};
+use crate::error::{CookError, VerifyFailure};
+use crate::simple_state::SimpleStateChecked;
+
+// Downstream systems want the proven result, not the proof. For `SimpleStateChecked` the public values
+// `[initial, final]` already pin down the whole summary state, so replaying is just decoding them; nothing
+// about the individual transactions is needed (or available).
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReplayedState {
+    pub initial_balance: u32,
+    pub final_balance: u32,
+    /// total inputs minus total outputs over the whole trace
+    pub net_flow: i64,
+}
+
+/// Decodes `SimpleStateChecked` public values into the state they certify.
+pub fn replay<F: PrimeField32>(public_values: &[F]) -> Result<ReplayedState, CookError> {
+    let [initial, final_] = public_values else {
+        return Err(CookError::PublicValues(format!(
+            "expected [initial, final], got {} public values",
+            public_values.len()
+        )));
+    };
+
+    let initial_balance = initial.as_canonical_u32();
+    let final_balance = final_.as_canonical_u32();
+    Ok(ReplayedState {
+        initial_balance,
+        final_balance,
+        net_flow: final_balance as i64 - initial_balance as i64,
+    })
+}
+
+/// Verifies `proof` and only then replays its public values.
+pub fn verify_and_replay(
+    config: &MyConfig,
+    challenger: &mut Challenger,
+    proof: &Proof<MyConfig>,
+    public_values: &Vec<Val>,
+) -> Result<ReplayedState, CookError> {
+    if public_values.len() != 2 {
+        return Err(VerifyFailure::PublicValues { expected: 2, got: public_values.len() }.into());
+    }
+    verify(config, &SimpleStateChecked {}, challenger, proof, public_values)
+        .map_err(|e| CookError::Verification(e.into()))?;
+    replay(public_values)
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_field::AbstractField;
+    use p3_matrix::Matrix;
+    use p3_uni_stark::prove;
+
+    use super::*;
+    use crate::config::{default_config, random_perm};
+    use crate::simple_state::random_checked_trace;
+
+    #[test]
+    fn test_replay_matches_final_row() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let (trace, public_values) = random_checked_trace::<Val>(8);
+
+        let last = trace.row_slice(trace.height() - 1).to_vec();
+        let final_balance = (last[0] + last[1] - last[2]).as_canonical_u32();
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &SimpleStateChecked {}, &mut p_challenger, trace, &public_values);
+        let mut v_challenger = Challenger::new(perm);
+        let state = verify_and_replay(&config, &mut v_challenger, &proof, &public_values).unwrap();
+
+        assert_eq!(state.initial_balance, 100000);
+        assert_eq!(state.final_balance, final_balance);
+        assert_eq!(state.net_flow, final_balance as i64 - 100000);
+    }
+
+    #[test]
+    fn test_replay_rejects_wrong_arity() {
+        assert!(replay(&[Val::one()]).is_err());
+    }
+}
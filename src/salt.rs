@@ -0,0 +1,178 @@
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_field::Field;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+use rand::distributions::{Distribution, Standard};
+use rand::Rng;
+
+// Salting a trace: `salt_trace` appends `salt_width` columns of random values, and `Salted<A>` is `A` with
+// those extra columns, which no constraint reads. The inner AIR sees a builder whose `main` stops at its own
+// width, so it runs unchanged.
+//
+// What it buys: every Merkle leaf of the trace LDE now includes random values, so the root on its own is a
+// hiding commitment, and two proofs of the same trace and statement differ from the first commitment on, so
+// proofs can't be linked by comparing commitments.
+//
+// What it doesn't: it is not zero-knowledge, and a low-entropy trace can still be guessed from a proof. The
+// out-of-domain openings (`opened_values.trace_local` and `trace_next`) are the real columns' polynomials at
+// `zeta`, unmasked: anyone holding a guess of the trace interpolates it, evaluates it at `zeta` and compares.
+// The FRI query openings likewise reveal the real columns at about `num_queries` points each, and the
+// quotient is a function of the real columns only. Hiding those needs the trace polynomials themselves randomized
+// (random rows beyond the ones the constraints look at, or adding multiples of the vanishing polynomial)
+// and a prover that masks its quotient, which uni-stark at this revision doesn't do.
+
+/// `A` with `salt_width` unconstrained columns appended to its trace.
+pub struct Salted<A> {
+    pub inner: A,
+    pub salt_width: usize,
+}
+
+impl<F, A: BaseAir<F>> BaseAir<F> for Salted<A> {
+    fn width(&self) -> usize {
+        self.inner.width() + self.salt_width
+    }
+}
+
+impl<AB, A> Air<AB> for Salted<A>
+where
+    AB: AirBuilderWithPublicValues,
+    A: BaseAir<AB::F> + for<'b> Air<UnsaltedBuilder<'b, AB>>,
+{
+    fn eval(&self, builder: &mut AB) {
+        let width = self.inner.width();
+        self.inner.eval(&mut UnsaltedBuilder { inner: builder, width });
+    }
+}
+
+/// Copies `trace` with `salt_width` random columns appended.
+pub fn salt_trace<F, R>(trace: &RowMajorMatrix<F>, salt_width: usize, rng: &mut R) -> RowMajorMatrix<F>
+where
+    F: Field,
+    R: Rng,
+    Standard: Distribution<F>,
+{
+    let width = trace.width() + salt_width;
+    let mut values = Vec::with_capacity(trace.height() * width);
+    for row in trace.values.chunks_exact(trace.width()) {
+        values.extend_from_slice(row);
+        values.extend((0..salt_width).map(|_| rng.gen::<F>()));
+    }
+    RowMajorMatrix::new(values, width)
+}
+
+/// A builder that forwards everything to `inner` but shows only the first `width` trace columns.
+pub struct UnsaltedBuilder<'a, AB> {
+    inner: &'a mut AB,
+    width: usize,
+}
+
+impl<'a, AB: AirBuilder> AirBuilder for UnsaltedBuilder<'a, AB> {
+    type F = AB::F;
+    type Expr = AB::Expr;
+    type Var = AB::Var;
+    type M = FirstColumns<AB::M>;
+
+    fn main(&self) -> Self::M {
+        FirstColumns { inner: self.inner.main(), width: self.width }
+    }
+
+    fn is_first_row(&self) -> Self::Expr {
+        self.inner.is_first_row()
+    }
+
+    fn is_last_row(&self) -> Self::Expr {
+        self.inner.is_last_row()
+    }
+
+    fn is_transition_window(&self, size: usize) -> Self::Expr {
+        self.inner.is_transition_window(size)
+    }
+
+    fn assert_zero<I: Into<Self::Expr>>(&mut self, x: I) {
+        self.inner.assert_zero(x);
+    }
+}
+
+impl<'a, AB: AirBuilderWithPublicValues> AirBuilderWithPublicValues for UnsaltedBuilder<'a, AB> {
+    type PublicVar = AB::PublicVar;
+
+    fn public_values(&self) -> &[Self::PublicVar] {
+        self.inner.public_values()
+    }
+}
+
+/// The first `width` columns of a matrix.
+#[derive(Clone, Copy, Debug)]
+pub struct FirstColumns<M> {
+    inner: M,
+    width: usize,
+}
+
+impl<T: Send + Sync, M: Matrix<T>> Matrix<T> for FirstColumns<M> {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.inner.height()
+    }
+
+    type Row<'a> = core::iter::Take<M::Row<'a>> where Self: 'a;
+
+    fn row(&self, r: usize) -> Self::Row<'_> {
+        self.inner.row(r).take(self.width)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_field::AbstractField;
+    use p3_uni_stark::{prove, verify};
+    use rand::thread_rng;
+
+    use super::*;
+    use crate::config::{default_config, random_perm, Challenger, Val};
+    use crate::determinism::first_proof_difference;
+    use crate::simple_state::{random_checked_trace, SimpleStateChecked};
+    use crate::{assert_constraints_fail, assert_constraints_ok};
+
+    const SALT_WIDTH: usize = 4;
+
+    fn salted() -> Salted<SimpleStateChecked> {
+        Salted { inner: SimpleStateChecked {}, salt_width: SALT_WIDTH }
+    }
+
+    #[test]
+    fn test_salt_is_unconstrained() {
+        let (trace, public_values) = random_checked_trace::<Val>(4);
+        let salted_trace = salt_trace(&trace, SALT_WIDTH, &mut thread_rng());
+        assert_eq!(salted_trace.width(), trace.width() + SALT_WIDTH);
+        assert_constraints_ok!(&salted(), &salted_trace, &public_values);
+
+        // the real columns are still checked
+        let mut bad = salted_trace.clone();
+        bad.row_mut(3)[0] += Val::from_canonical_u32(1);
+        assert_constraints_fail!(&salted(), &bad, &public_values, 2);
+    }
+
+    #[test]
+    fn test_salted_proofs_differ() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let (trace, public_values) = random_checked_trace::<Val>(6);
+
+        let proofs = (0..2)
+            .map(|_| {
+                let salted_trace = salt_trace(&trace, SALT_WIDTH, &mut thread_rng());
+                let mut challenger = Challenger::new(perm.clone());
+                prove(&config, &salted(), &mut challenger, salted_trace, &public_values)
+            })
+            .collect::<Vec<_>>();
+
+        for proof in &proofs {
+            let mut challenger = Challenger::new(perm.clone());
+            verify(&config, &salted(), &mut challenger, proof, &public_values).unwrap();
+        }
+        assert_eq!(first_proof_difference(&proofs[0], &proofs[1]).as_deref(), Some("commitments.trace"));
+    }
+}
@@ -0,0 +1,178 @@
+use p3_air::Air;
+use p3_commit::Pcs;
+use p3_keccak::Keccak256Hash;
+use p3_symmetric::CryptographicHasher;
+use p3_uni_stark::{
+    verify, Proof, StarkGenericConfig, SymbolicAirBuilder, Val, VerificationError, VerifierConstraintFolder,
+};
+
+use crate::columns::NamedColumns;
+use crate::error::{CookError, VerifyFailure};
+use crate::simple_state::{SimpleState, SimpleStateChecked};
+
+// A bare serialized proof says nothing about the AIR it was produced for, and a verifier holding another
+// AIR of the same shape only fails deep inside the constraint check, if at all. Encoded proofs start with a
+// header binding them to the AIR:
+//   b"COOK" | format version (1 byte) | keccak(schema) (32 bytes) | bincode(proof)
+// where the schema is the AIR id followed by the column names in trace order. The verifier recomputes the
+// hash from its own AIR and rejects a mismatch before decoding the proof.
+
+pub const PROOF_MAGIC: &[u8; 4] = b"COOK";
+pub const PROOF_FORMAT_VERSION: u8 = 1;
+
+const HEADER_LEN: usize = PROOF_MAGIC.len() + 1 + 32;
+
+/// An AIR with a stable identifier and named columns, which together make up its schema.
+pub trait AirSchema: NamedColumns {
+    fn air_id(&self) -> &'static str;
+}
+
+impl AirSchema for SimpleState {
+    fn air_id(&self) -> &'static str {
+        "simple_state"
+    }
+}
+
+impl AirSchema for SimpleStateChecked {
+    fn air_id(&self) -> &'static str {
+        "simple_state_checked"
+    }
+}
+
+/// Keccak digest of the AIR id and column names, each null-terminated.
+pub fn schema_hash<A: AirSchema>(air: &A) -> [u8; 32] {
+    let mut bytes = air.air_id().as_bytes().to_vec();
+    bytes.push(0);
+    for name in air.column_names() {
+        bytes.extend_from_slice(name.as_bytes());
+        bytes.push(0);
+    }
+    Keccak256Hash {}.hash_iter(bytes)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Serializes `proof` behind a header naming `air`'s schema.
+pub fn encode_proof<SC: StarkGenericConfig, A: AirSchema>(air: &A, proof: &Proof<SC>) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(HEADER_LEN);
+    bytes.extend_from_slice(PROOF_MAGIC);
+    bytes.push(PROOF_FORMAT_VERSION);
+    bytes.extend_from_slice(&schema_hash(air));
+    bincode::serialize_into(&mut bytes, proof).expect("proofs always serialize");
+    bytes
+}
+
+/// Decodes a proof written by `encode_proof`, if its header names `air`'s schema.
+pub fn decode_proof<SC: StarkGenericConfig, A: AirSchema>(air: &A, bytes: &[u8]) -> Result<Proof<SC>, VerifyFailure> {
+    if bytes.len() < HEADER_LEN || &bytes[..4] != PROOF_MAGIC || bytes[4] != PROOF_FORMAT_VERSION {
+        return Err(VerifyFailure::ProofShape);
+    }
+    let expected = schema_hash(air);
+    if bytes[5..HEADER_LEN] != expected {
+        return Err(VerifyFailure::Schema { expected: hex(&expected), got: hex(&bytes[5..HEADER_LEN]) });
+    }
+    bincode::deserialize(&bytes[HEADER_LEN..]).map_err(|_| VerifyFailure::ProofShape)
+}
+
+/// Decodes and verifies a proof written by `encode_proof`.
+pub fn verify_encoded<SC, A>(
+    config: &SC,
+    air: &A,
+    challenger: &mut SC::Challenger,
+    bytes: &[u8],
+    public_values: &Vec<Val<SC>>,
+) -> Result<(), CookError>
+where
+    SC: StarkGenericConfig,
+    A: AirSchema + Air<SymbolicAirBuilder<Val<SC>>> + for<'a> Air<VerifierConstraintFolder<'a, SC>>,
+    VerifyFailure: From<VerificationError<<SC::Pcs as Pcs<SC::Challenge, SC::Challenger>>::Error>>,
+{
+    let proof = decode_proof(air, bytes)?;
+    verify(config, air, challenger, &proof, public_values).map_err(|e| CookError::Verification(e.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_air::{AirBuilder, BaseAir};
+    use p3_matrix::Matrix;
+    use p3_uni_stark::prove;
+
+    use super::*;
+    use crate::config::{default_config, random_perm, Challenger, Val};
+    use crate::simple_state::random_trace;
+
+    struct FibonacciAir {}
+
+    impl<F> BaseAir<F> for FibonacciAir {
+        fn width(&self) -> usize {
+            2
+        }
+    }
+
+    impl<AB: AirBuilder> Air<AB> for FibonacciAir {
+        fn eval(&self, builder: &mut AB) {
+            let main = builder.main();
+            let (local, next) = (main.row_slice(0), main.row_slice(1));
+            builder.when_transition().assert_eq(next[0], local[1]);
+            builder.when_transition().assert_eq(next[1], local[0] + local[1]);
+        }
+    }
+
+    impl NamedColumns for FibonacciAir {
+        fn column_names(&self) -> Vec<String> {
+            ["left", "right"].map(String::from).to_vec()
+        }
+    }
+
+    impl AirSchema for FibonacciAir {
+        fn air_id(&self) -> &'static str {
+            "fibonacci"
+        }
+    }
+
+    #[test]
+    fn test_schemas_differ() {
+        assert_ne!(schema_hash(&SimpleState {}), schema_hash(&SimpleStateChecked {}));
+        assert_ne!(schema_hash(&SimpleState {}), schema_hash(&FibonacciAir {}));
+    }
+
+    #[test]
+    fn test_encoded_proof_round_trip() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &SimpleState {}, &mut p_challenger, random_trace::<Val>(6), &vec![]);
+        let bytes = encode_proof(&SimpleState {}, &proof);
+
+        let mut v_challenger = Challenger::new(perm);
+        verify_encoded(&config, &SimpleState {}, &mut v_challenger, &bytes, &vec![]).unwrap();
+    }
+
+    #[test]
+    fn test_proof_for_other_air_is_rejected() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &SimpleState {}, &mut p_challenger, random_trace::<Val>(6), &vec![]);
+        let bytes = encode_proof(&SimpleState {}, &proof);
+
+        let mut v_challenger = Challenger::new(perm);
+        match verify_encoded(&config, &FibonacciAir {}, &mut v_challenger, &bytes, &vec![]) {
+            Err(CookError::Verification(VerifyFailure::Schema { expected, got })) => {
+                assert_eq!(expected, hex(&schema_hash(&FibonacciAir {})));
+                assert_eq!(got, hex(&schema_hash(&SimpleState {})));
+            }
+            other => panic!("expected a schema failure, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_truncated_header_is_a_shape_failure() {
+        let result = decode_proof::<crate::config::MyConfig, _>(&SimpleState {}, &PROOF_MAGIC[..]);
+        assert_eq!(result.err(), Some(VerifyFailure::ProofShape));
+    }
+}
@@ -0,0 +1,221 @@
+use std::borrow::Borrow;
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_field::PrimeField32;
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use rand::{distributions::{Distribution, Standard}, thread_rng, Rng};
+
+use crate::gadgets::less_than::assert_bit_decomposition;
+use crate::transaction::{trace_from_transactions, Transaction};
+
+pub const SS_ROW_WIDTH: usize = 3;
+
+pub struct SimpleState {}
+
+impl<F> BaseAir<F> for SimpleState {
+    fn width(&self) -> usize {
+        SS_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilder> Air<AB> for SimpleState {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let (local, next) = (StateColumns::view(&local), StateColumns::view(&next));
+
+        builder.when_transition().assert_eq(local.balance() + local.input() - local.output(), next.balance());
+    }
+}
+
+// this enables both `Var` and `Val` 
+pub struct SimStateRow<F> {
+    pub balance: F,
+    pub input: F,
+    pub output: F
+}
+
+impl<F> Borrow<SimStateRow<F>> for [F] {
+    fn borrow(&self) -> &SimStateRow<F> {
+        debug_assert_eq!(self.len(), SS_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<SimStateRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+crate::columns! {
+    /// `SimpleState`'s row, by column name.
+    pub struct StateColumns { balance / set_balance, input / set_input, output / set_output }
+}
+
+// fn generate_next_ss_row<F: PrimeField32>(cur_row: &SimStateRow<F>, next_input: F, next_output: F) -> SimStateRow<F> {
+//     let next_balance = cur_row.balance + cur_row.input - cur_row.output;
+//     debug_assert!(next_balance + next_input >= next_output, "invalid transaction");
+
+//     SimStateRow { balance: next_balance, input: next_input, output: next_output }
+// }
+
+pub fn random_trace<F: PrimeField32>(log_n: usize) -> RowMajorMatrix<F> where Standard: Distribution<F> {
+    random_trace_with_rng(log_n, &mut thread_rng())
+}
+
+pub fn random_trace_with_rng<F: PrimeField32, R: Rng>(log_n: usize, rng: &mut R) -> RowMajorMatrix<F> where Standard: Distribution<F> {
+    let n = 1 << log_n;
+    let mut trace = RowMajorMatrix::new(vec![F::zero(); n * SS_ROW_WIDTH], SS_ROW_WIDTH);
+
+    let mut balance = F::from_canonical_u32(100000);
+    let mut input = F::from_canonical_u32(12345);
+    let mut output = F::from_canonical_u32(54321);
+
+    for (i, row) in trace.values.chunks_exact_mut(SS_ROW_WIDTH).enumerate() {
+        if i > 0 {
+            balance = balance + input - output;
+            input = rng.gen();
+            let high = balance.as_canonical_u32() + input.as_canonical_u32();
+            let low = high * 2 / 3;
+            output = F::from_canonical_u32(rng.gen_range(low..high));
+        }
+
+        let row = StateColumns::view_mut(row);
+        row.set_balance(balance);
+        row.set_input(input);
+        row.set_output(output);
+    }
+
+    trace
+}
+
+/// A `SimpleState` trace that is valid except for the transition from `fault_row` to `fault_row + 1`.
+///
+/// The fault is an extra unit of output on `fault_row`, so only that row's transition breaks: the balance
+/// carried into `fault_row` and every later row is untouched.
+pub fn random_trace_with_fault<F: PrimeField32>(log_n: usize, fault_row: usize) -> RowMajorMatrix<F> where Standard: Distribution<F> {
+    let mut trace = random_trace(log_n);
+    assert!(fault_row + 1 < trace.height(), "the last row has no transition to break");
+
+    let row = StateColumns::view_mut(trace.row_mut(fault_row));
+    row.set_output(row.output() + F::one());
+    trace
+}
+
+// SimpleState with the underflow closed: the balance after each transaction is range checked into
+// `BALANCE_BITS` bits, so `output` can't exceed `balance + input` by wrapping around the field. The initial and
+// final balances are the public values `[initial, final]`, which also pins down the first and last rows.
+
+pub const BALANCE_BITS: usize = 30;
+pub const SSC_ROW_WIDTH: usize = SS_ROW_WIDTH + BALANCE_BITS;
+
+pub struct SimpleStateChecked {}
+
+impl<F> BaseAir<F> for SimpleStateChecked {
+    fn width(&self) -> usize {
+        SSC_ROW_WIDTH
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues> Air<AB> for SimpleStateChecked {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+        let local: &CheckedRow<AB::Var> = (*local).borrow();
+        let next: &CheckedRow<AB::Var> = (*next).borrow();
+
+        let initial_balance: AB::Expr = builder.public_values()[0].into();
+        let final_balance: AB::Expr = builder.public_values()[1].into();
+
+        let new_balance = local.state.balance + local.state.input - local.state.output;
+        assert_bit_decomposition(builder, new_balance.clone(), &local.new_balance_bits);
+
+        builder.when_first_row().assert_eq(local.state.balance, initial_balance);
+        builder.when_transition().assert_eq(new_balance.clone(), next.state.balance);
+        builder.when_last_row().assert_eq(new_balance, final_balance);
+    }
+}
+
+pub struct CheckedRow<F> {
+    pub state: SimStateRow<F>,
+    pub new_balance_bits: [F; BALANCE_BITS],
+}
+
+impl<F> Borrow<CheckedRow<F>> for [F] {
+    fn borrow(&self) -> &CheckedRow<F> {
+        debug_assert_eq!(self.len(), SSC_ROW_WIDTH);
+        let (prefix, shorts, suffix) = unsafe { self.align_to::<CheckedRow<F>>() };
+        debug_assert!(prefix.is_empty(), "Alignment should match");
+        debug_assert!(suffix.is_empty(), "Alignment should match");
+        debug_assert_eq!(shorts.len(), 1);
+        &shorts[0]
+    }
+}
+
+/// A random `SimpleStateChecked` trace and its public values `[initial, final]`.
+pub fn random_checked_trace<F: PrimeField32>(log_n: usize) -> (RowMajorMatrix<F>, Vec<F>) {
+    random_checked_trace_with_rng(log_n, &mut thread_rng())
+}
+
+pub fn random_checked_trace_with_rng<F: PrimeField32, R: Rng>(log_n: usize, rng: &mut R) -> (RowMajorMatrix<F>, Vec<F>) {
+
+    let initial_balance = 100000u32;
+    let mut balance = initial_balance;
+    let mut transactions = Vec::with_capacity(1 << log_n);
+    for _ in 0..1 << log_n {
+        let input = rng.gen_range(0..1 << 16);
+        let output = rng.gen_range(0..=balance + input);
+        balance = balance + input - output;
+        transactions.push(Transaction::new(input, output));
+    }
+
+    trace_from_transactions(initial_balance, &transactions).expect("generated transactions are valid")
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_baby_bear::BabyBear;
+    use p3_field::AbstractField;
+
+    use super::*;
+
+    #[test]
+    fn test_random_trace_satisfies_constraints() {
+        let trace = random_trace::<BabyBear>(12);
+        crate::assert_constraints_ok!(&SimpleState {}, &trace, &[]);
+    }
+
+    #[test]
+    fn test_broken_balance_is_caught() {
+        let mut trace = random_trace::<BabyBear>(12);
+        trace.row_mut(100)[0] += BabyBear::one();
+
+        // the transition into row 100 is the first one to break
+        crate::assert_constraints_fail!(&SimpleState {}, &trace, &[], 99);
+    }
+
+    #[test]
+    fn test_fault_is_reported_on_its_row_only() {
+        for fault_row in [0, 37, (1 << 8) - 2] {
+            let trace = random_trace_with_fault::<BabyBear>(8, fault_row);
+            let failures = crate::debug::debug_check_constraints(&SimpleState {}, &trace, &[]).unwrap_err();
+            assert_eq!(failures.iter().map(|f| f.row).collect::<Vec<_>>(), vec![fault_row]);
+        }
+    }
+
+    #[test]
+    fn test_checked_trace_satisfies_constraints() {
+        let (trace, public_values) = random_checked_trace::<BabyBear>(12);
+        crate::assert_constraints_ok!(&SimpleStateChecked {}, &trace, &public_values);
+    }
+
+    #[test]
+    fn test_checked_rejects_underflow() {
+        let (mut trace, public_values) = random_checked_trace::<BabyBear>(8);
+
+        // spend more than the balance on row 10
+        let row = trace.row_mut(10);
+        row[2] = row[0] + row[1] + BabyBear::one();
+        crate::assert_constraints_fail!(&SimpleStateChecked {}, &trace, &public_values, 10);
+    }
+}
@@ -0,0 +1,163 @@
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+
+use p3_uni_stark::{Proof, StarkGenericConfig};
+
+// Where proofs go once they're made. Code that produces proofs takes a `ProofSink` and leaves the
+// destination to its caller: a file per proof (`FileSink`), memory (`BufferSink`), or any `Write`, such as
+// stdout or a socket (`WriterSink`). Proofs are bincode-encoded. A `WriterSink` can carry many proofs over
+// one stream, so each one is framed with its length as a little-endian `u64`, and `read_framed` reads them
+// back.
+
+pub trait ProofSink {
+    /// Takes one encoded proof.
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()>;
+
+    fn write_proof<SC: StarkGenericConfig>(&mut self, proof: &Proof<SC>) -> io::Result<()>
+    where
+        Self: Sized,
+    {
+        let bytes = bincode::serialize(proof).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        self.write_bytes(&bytes)
+    }
+}
+
+impl<S: ProofSink + ?Sized> ProofSink for &mut S {
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        (**self).write_bytes(bytes)
+    }
+}
+
+impl<S: ProofSink + ?Sized> ProofSink for Box<S> {
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        (**self).write_bytes(bytes)
+    }
+}
+
+/// Writes each proof to `path`, replacing what was there.
+pub struct FileSink {
+    pub path: PathBuf,
+}
+
+impl FileSink {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        FileSink { path: path.into() }
+    }
+}
+
+impl ProofSink for FileSink {
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let mut file = File::create(&self.path)?;
+        file.write_all(bytes)?;
+        file.sync_all()
+    }
+}
+
+/// Keeps every proof in memory, in the order written.
+#[derive(Default)]
+pub struct BufferSink {
+    pub proofs: Vec<Vec<u8>>,
+}
+
+impl ProofSink for BufferSink {
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.proofs.push(bytes.to_vec());
+        Ok(())
+    }
+}
+
+/// Writes length-framed proofs to `W`, flushing after each.
+pub struct WriterSink<W: Write>(pub W);
+
+impl<W: Write> ProofSink for WriterSink<W> {
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.0.write_all(&(bytes.len() as u64).to_le_bytes())?;
+        self.0.write_all(bytes)?;
+        self.0.flush()
+    }
+}
+
+/// Reads the next proof written by a `WriterSink`, or `None` at the end of the stream.
+pub fn read_framed<R: Read>(r: &mut R) -> io::Result<Option<Vec<u8>>> {
+    let mut len = [0u8; 8];
+    match r.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let mut bytes = vec![0u8; u64::from_le_bytes(len) as usize];
+    r.read_exact(&mut bytes)?;
+    Ok(Some(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use p3_uni_stark::{prove, verify};
+
+    use super::*;
+    use crate::config::{default_config, random_perm, Challenger, MyConfig, Perm, Val};
+    use crate::simple_state::{random_checked_trace, SimpleStateChecked};
+
+    fn proof_and_publics(perm: &Perm) -> (Proof<MyConfig>, Vec<Val>) {
+        let (trace, public_values) = random_checked_trace::<Val>(6);
+        let mut challenger = Challenger::new(perm.clone());
+        let proof = prove(&default_config(perm), &SimpleStateChecked {}, &mut challenger, trace, &public_values);
+        (proof, public_values)
+    }
+
+    fn verifies(perm: &Perm, bytes: &[u8], public_values: &Vec<Val>) -> bool {
+        let proof: Proof<MyConfig> = bincode::deserialize(bytes).unwrap();
+        let mut challenger = Challenger::new(perm.clone());
+        verify(&default_config(perm), &SimpleStateChecked {}, &mut challenger, &proof, public_values).is_ok()
+    }
+
+    #[test]
+    fn test_file_sink() {
+        let perm = random_perm();
+        let (proof, public_values) = proof_and_publics(&perm);
+        let path = std::env::temp_dir().join(format!("cook-file-sink-{}.bin", std::process::id()));
+
+        let mut sink = FileSink::new(&path);
+        sink.write_proof(&proof).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(bytes, bincode::serialize(&proof).unwrap());
+        assert!(verifies(&perm, &bytes, &public_values));
+    }
+
+    #[test]
+    fn test_buffer_sink() {
+        let perm = random_perm();
+        let (first, first_publics) = proof_and_publics(&perm);
+        let (second, second_publics) = proof_and_publics(&perm);
+
+        let mut sink = BufferSink::default();
+        sink.write_proof(&first).unwrap();
+        sink.write_proof(&second).unwrap();
+
+        assert_eq!(sink.proofs.len(), 2);
+        assert!(verifies(&perm, &sink.proofs[0], &first_publics));
+        assert!(verifies(&perm, &sink.proofs[1], &second_publics));
+    }
+
+    #[test]
+    fn test_writer_sink_frames_proofs() {
+        let perm = random_perm();
+        let (proof, public_values) = proof_and_publics(&perm);
+
+        let mut sink = WriterSink(Vec::new());
+        sink.write_proof(&proof).unwrap();
+        sink.write_proof(&proof).unwrap();
+
+        let mut stream = &sink.0[..];
+        for _ in 0..2 {
+            let bytes = read_framed(&mut stream).unwrap().unwrap();
+            assert!(verifies(&perm, &bytes, &public_values));
+        }
+        assert!(read_framed(&mut stream).unwrap().is_none());
+    }
+}
@@ -0,0 +1,133 @@
+use p3_matrix::dense::RowMajorMatrix;
+
+// Traces of state machines, row by row. Each `apply` step computes the next row from the previous one, so
+// whatever the AIR carries from row to row (a balance, a counter, a hash state) is chained by the step
+// itself rather than by index arithmetic over a flat buffer:
+//   StateTrace::new(first_row)
+//       .apply(|prev| vec![prev[0] + prev[1] - prev[2], input, output])
+//       .apply_n(6, |prev| ...)
+//       .build()
+// `build` pads the height to a power of two with the padding step, which by default repeats the last row;
+// an AIR whose transitions a repeated row doesn't satisfy (most of them) sets its own with `pad_with`.
+
+type Step<F> = Box<dyn FnMut(&[F]) -> Vec<F>>;
+
+pub struct StateTrace<F> {
+    width: usize,
+    values: Vec<F>,
+    padding: Option<Step<F>>,
+}
+
+impl<F: Clone + Send + Sync + 'static> StateTrace<F> {
+    /// A trace whose first row is `initial`, which also fixes the width.
+    pub fn new(initial: impl Into<Vec<F>>) -> Self {
+        let values = initial.into();
+        assert!(!values.is_empty(), "a row has at least one column");
+        StateTrace { width: values.len(), values, padding: None }
+    }
+
+    /// Appends the row `step` computes from the last one.
+    pub fn apply(mut self, step: impl FnOnce(&[F]) -> Vec<F>) -> Self {
+        let next = step(self.last_row());
+        self.push(next);
+        self
+    }
+
+    /// Appends `n` rows, each computed by `step` from the one before.
+    pub fn apply_n(mut self, n: usize, mut step: impl FnMut(&[F]) -> Vec<F>) -> Self {
+        for _ in 0..n {
+            let next = step(self.last_row());
+            self.push(next);
+        }
+        self
+    }
+
+    /// Pads with rows computed by `step` instead of copies of the last row.
+    pub fn pad_with(mut self, step: impl FnMut(&[F]) -> Vec<F> + 'static) -> Self {
+        self.padding = Some(Box::new(step));
+        self
+    }
+
+    pub fn height(&self) -> usize {
+        self.values.len() / self.width
+    }
+
+    /// The trace, padded to a power-of-two height.
+    pub fn build(mut self) -> RowMajorMatrix<F> {
+        let padded_height = self.height().next_power_of_two();
+        let mut padding = self.padding.take().unwrap_or_else(|| Box::new(|last: &[F]| last.to_vec()));
+        while self.height() < padded_height {
+            let next = padding(self.last_row());
+            self.push(next);
+        }
+        RowMajorMatrix::new(self.values, self.width)
+    }
+
+    fn last_row(&self) -> &[F] {
+        &self.values[self.values.len() - self.width..]
+    }
+
+    fn push(&mut self, row: Vec<F>) {
+        assert_eq!(row.len(), self.width, "row {} has the wrong width", self.height());
+        self.values.extend(row);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_field::{AbstractField, PrimeField32};
+    use p3_matrix::Matrix;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+    use crate::config::Val;
+    use crate::simple_state::{random_trace_with_rng, SimpleState, StateColumns};
+    use crate::{assert_constraints_fail, assert_constraints_ok};
+
+    /// The balance the next `SimpleState` row starts from.
+    fn next_balance(prev: &[Val]) -> Val {
+        let prev = StateColumns::view(prev);
+        prev.balance() + prev.input() - prev.output()
+    }
+
+    #[test]
+    fn test_reproduces_random_trace() {
+        let log_n = 6;
+        let mut rng = StdRng::seed_from_u64(5);
+        let first = [100000, 12345, 54321].map(Val::from_canonical_u32);
+        let trace = StateTrace::new(first)
+            .apply_n((1 << log_n) - 1, |prev| {
+                let balance = next_balance(prev);
+                let input: Val = rng.gen();
+                let high = balance.as_canonical_u32() + input.as_canonical_u32();
+                let output = Val::from_canonical_u32(rng.gen_range(high * 2 / 3..high));
+                vec![balance, input, output]
+            })
+            .build();
+
+        assert_eq!(trace.values, random_trace_with_rng::<Val, _>(log_n, &mut StdRng::seed_from_u64(5)).values);
+    }
+
+    #[test]
+    fn test_padding() {
+        let deposit = |prev: &[Val]| vec![next_balance(prev), Val::from_canonical_u32(10), Val::zero()];
+        let state = || StateTrace::new([100, 10, 0].map(Val::from_canonical_u32)).apply(deposit).apply(deposit);
+
+        // a copy of the last row deposits again without the balance going up
+        let repeated = state().build();
+        assert_eq!(repeated.height(), 4);
+        assert_eq!(repeated.row_slice(3).to_vec(), repeated.row_slice(2).to_vec());
+        assert_constraints_fail!(&SimpleState {}, &repeated, &[], 2);
+
+        // empty transactions carry the balance forward
+        let padded = state().pad_with(|prev| vec![next_balance(prev), Val::zero(), Val::zero()]).build();
+        assert_eq!(padded.get(3, 0), Val::from_canonical_u32(130));
+        assert_constraints_ok!(&SimpleState {}, &padded, &[]);
+    }
+
+    #[test]
+    #[should_panic(expected = "row 1 has the wrong width")]
+    fn test_wrong_width_panics() {
+        StateTrace::new([Val::one(); 3]).apply(|prev| prev[..2].to_vec());
+    }
+}
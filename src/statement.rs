@@ -0,0 +1,168 @@
+use p3_air::Air;
+use p3_challenger::CanObserve;
+use p3_commit::Pcs;
+use p3_field::PrimeField32;
+use p3_keccak::Keccak256Hash;
+#[cfg(not(feature = "verifier-only"))]
+use p3_matrix::{dense::RowMajorMatrix, Matrix};
+use p3_symmetric::CryptographicHasher;
+use p3_uni_stark::{
+    verify, Proof, StarkGenericConfig, SymbolicAirBuilder, Val, VerificationError, VerifierConstraintFolder,
+};
+#[cfg(not(feature = "verifier-only"))]
+use p3_uni_stark::{prove, ProverConstraintFolder};
+#[cfg(all(debug_assertions, not(feature = "verifier-only")))]
+use p3_uni_stark::DebugConstraintBuilder;
+
+use crate::error::{CookError, VerifyFailure};
+use crate::hash::bytes_to_field_digest;
+
+// Everything the prover and verifier bind into Fiat-Shamir before `uni_stark` takes over is observed here,
+// so the two sides can't drift apart. The order is fixed:
+//   1. keccak digest of the statement id
+//   2. the number of public values
+//   3. the public values, or their keccak digest if there are more than `MAX_INLINE_PUBLIC_VALUES`
+//   4. the trace commitment
+
+/// Public-value vectors longer than this are observed as a fixed-size digest.
+pub const MAX_INLINE_PUBLIC_VALUES: usize = 8;
+
+pub fn observe_statement<F, C, Com>(
+    challenger: &mut C,
+    statement_id: &str,
+    public_values: &[F],
+    trace_commitment_digest: &Com,
+) where
+    F: PrimeField32,
+    C: CanObserve<F> + CanObserve<Com>,
+    Com: Clone,
+{
+    challenger.observe_slice(&bytes_to_field_digest::<F>(Keccak256Hash {}.hash_iter(statement_id.bytes())));
+
+    challenger.observe(F::from_canonical_usize(public_values.len()));
+    if public_values.len() <= MAX_INLINE_PUBLIC_VALUES {
+        challenger.observe_slice(public_values);
+    } else {
+        challenger.observe_slice(&digest_public_values(public_values));
+    }
+
+    challenger.observe(trace_commitment_digest.clone());
+}
+
+/// Keccak digest of the canonical little-endian encoding of `public_values`, as 8 field elements.
+pub fn digest_public_values<F: PrimeField32>(public_values: &[F]) -> [F; 8] {
+    let bytes = public_values.iter().flat_map(|v| v.as_canonical_u32().to_le_bytes());
+    bytes_to_field_digest(Keccak256Hash {}.hash_iter(bytes))
+}
+
+/// `prove` with the statement observed first.
+///
+/// The trace is committed once here to learn its digest; `prove` commits it again internally, so this costs
+/// one extra trace commitment.
+#[cfg(not(feature = "verifier-only"))]
+pub fn prove_statement<
+    SC,
+    #[cfg(debug_assertions)] A: for<'a> Air<DebugConstraintBuilder<'a, Val<SC>>>,
+    #[cfg(not(debug_assertions))] A,
+>(
+    config: &SC,
+    air: &A,
+    challenger: &mut SC::Challenger,
+    statement_id: &str,
+    trace: RowMajorMatrix<Val<SC>>,
+    public_values: &Vec<Val<SC>>,
+) -> Proof<SC>
+where
+    SC: StarkGenericConfig,
+    Val<SC>: PrimeField32,
+    A: Air<SymbolicAirBuilder<Val<SC>>> + for<'a> Air<ProverConstraintFolder<'a, SC>>,
+{
+    let pcs = config.pcs();
+    let trace_domain = pcs.natural_domain_for_degree(trace.height());
+    let (trace_commit, _) = pcs.commit(vec![(trace_domain, trace.clone())]);
+
+    observe_statement(challenger, statement_id, public_values, &trace_commit);
+    prove(config, air, challenger, trace, public_values)
+}
+
+/// `verify` with the statement observed first, mirroring `prove_statement`.
+pub fn verify_statement<SC, A>(
+    config: &SC,
+    air: &A,
+    challenger: &mut SC::Challenger,
+    statement_id: &str,
+    proof: &Proof<SC>,
+    public_values: &Vec<Val<SC>>,
+) -> Result<(), CookError>
+where
+    SC: StarkGenericConfig,
+    Val<SC>: PrimeField32,
+    A: Air<SymbolicAirBuilder<Val<SC>>> + for<'a> Air<VerifierConstraintFolder<'a, SC>>,
+    VerifyFailure: From<VerificationError<<SC::Pcs as Pcs<SC::Challenge, SC::Challenger>>::Error>>,
+{
+    observe_statement(challenger, statement_id, public_values, &proof.commitments.trace);
+    verify(config, air, challenger, proof, public_values).map_err(|e| CookError::Verification(e.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_challenger::CanSample;
+    use p3_field::AbstractField;
+
+    use super::*;
+    use crate::config::{default_config, random_perm, Challenger, Val};
+    use crate::simple_state::{random_trace, SimpleState};
+
+    fn first_challenge(challenger: &Challenger, public_values: &[Val]) -> Val {
+        let mut challenger = challenger.clone();
+        observe_statement(&mut challenger, "test", public_values, &[Val::zero(); 8]);
+        challenger.sample()
+    }
+
+    #[test]
+    fn test_every_public_value_changes_first_challenge() {
+        let challenger = Challenger::new(random_perm());
+
+        // short vectors are observed inline, long ones through the digest
+        for len in [4, 32] {
+            let public_values = (0..len).map(Val::from_canonical_usize).collect::<Vec<_>>();
+            let base = first_challenge(&challenger, &public_values);
+
+            for i in 0..len {
+                let mut changed = public_values.clone();
+                changed[i] += Val::one();
+                assert_ne!(base, first_challenge(&challenger, &changed), "public value {} is not bound", i);
+            }
+        }
+    }
+
+    #[test]
+    fn test_statement_id_changes_first_challenge() {
+        let challenger = Challenger::new(random_perm());
+
+        let mut a = challenger.clone();
+        observe_statement(&mut a, "simple_state", &[Val::one()], &[Val::zero(); 8]);
+        let mut b = challenger;
+        observe_statement(&mut b, "fibonacci", &[Val::one()], &[Val::zero(); 8]);
+
+        assert_ne!(CanSample::<Val>::sample(&mut a), CanSample::<Val>::sample(&mut b));
+    }
+
+    #[test]
+    fn test_prover_and_verifier_transcripts_match() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let trace = random_trace::<Val>(6);
+        let public_values = (0..16).map(Val::from_canonical_usize).collect::<Vec<_>>();
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove_statement(&config, &SimpleState {}, &mut p_challenger, "simple_state", trace, &public_values);
+
+        let mut v_challenger = Challenger::new(perm);
+        verify_statement(&config, &SimpleState {}, &mut v_challenger, "simple_state", &proof, &public_values).unwrap();
+
+        let p_samples: Vec<Val> = (0..4).map(|_| p_challenger.sample()).collect();
+        let v_samples: Vec<Val> = (0..4).map(|_| v_challenger.sample()).collect();
+        assert_eq!(p_samples, v_samples);
+    }
+}
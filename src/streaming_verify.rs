@@ -0,0 +1,481 @@
+use std::io::{self, Read, Write};
+
+use p3_air::{Air, BaseAir};
+use p3_challenger::{CanObserve, CanSample, CanSampleBits, FieldChallenger, GrindingChallenger};
+use p3_commit::{Mmcs, Pcs as _, PolynomialSpace, TwoAdicMultiplicativeCoset};
+use p3_field::{AbstractExtensionField, AbstractField, Field, TwoAdicField};
+use p3_fri::{BatchOpening, QueryProof};
+use p3_matrix::dense::RowMajorMatrixView;
+use p3_matrix::stack::VerticalPair;
+use p3_matrix::Dimensions;
+use p3_symmetric::Hash;
+use p3_uni_stark::{
+    get_log_quotient_degree, Commitments, OpenedValues, Proof, StarkGenericConfig, SymbolicAirBuilder,
+    VerifierConstraintFolder,
+};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{
+    Challenge, ChallengeMmcs, Challenger, FriParams, MyCompress, MyConfig, MyHash, Perm, Val, ValMmcs,
+};
+use crate::error::VerifyFailure;
+
+// Verifying a proof without holding it in memory. Most of a proof is its FRI query responses, `num_queries`
+// of them, each a Merkle opening into the trace and quotient commitments plus one opening per folding round.
+// Everything else (the commitments, the out-of-domain openings, the FRI round commitments, the final
+// polynomial and the proof-of-work witness) is small. A streamed proof is that head followed by the query
+// responses, each a length-prefixed bincode chunk, and the verifier reads one chunk at a time:
+//   1. the head: shape checks, the transcript up to the query indices, the constraint check at `zeta` and
+//      the proof-of-work
+//   2. each query: its index comes off the transcript, its input openings are checked against the trace and
+//      quotient roots and reduced, and the folding rounds are checked against the FRI roots down to the
+//      final polynomial
+// so peak memory is the head plus one query response. This follows uni-stark's `verify` and the two-adic
+// FRI PCS step for step, for the crate's default config; uni-stark only exposes whole-proof verification,
+// which is why the query loop lives here.
+
+type Com = Hash<Val, Val, 8>;
+type InputProof = Vec<BatchOpening<Val, ValMmcs>>;
+type Query = QueryProof<Challenge, ChallengeMmcs, InputProof>;
+type Domain = TwoAdicMultiplicativeCoset<Val>;
+
+/// The largest chunk the reader will allocate for; a head or a query response is far below this.
+const MAX_CHUNK_LEN: u64 = 1 << 24;
+
+#[derive(Serialize)]
+struct HeadRef<'a> {
+    commitments: &'a Commitments<Com>,
+    opened_values: &'a OpenedValues<Challenge>,
+    degree_bits: usize,
+    commit_phase_commits: &'a Vec<Com>,
+    final_poly: &'a Challenge,
+    pow_witness: &'a Val,
+    num_queries: usize,
+}
+
+#[derive(Deserialize)]
+struct Head {
+    commitments: Commitments<Com>,
+    opened_values: OpenedValues<Challenge>,
+    degree_bits: usize,
+    commit_phase_commits: Vec<Com>,
+    final_poly: Challenge,
+    pow_witness: Val,
+    num_queries: usize,
+}
+
+fn write_chunk<T: Serialize, W: Write>(w: &mut W, value: &T) -> io::Result<()> {
+    let bytes = bincode::serialize(value).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    w.write_all(&(bytes.len() as u64).to_le_bytes())?;
+    w.write_all(&bytes)
+}
+
+fn read_chunk<T: DeserializeOwned, R: Read>(r: &mut R) -> Result<T, VerifyFailure> {
+    let mut len = [0u8; 8];
+    r.read_exact(&mut len).map_err(|_| VerifyFailure::ProofShape)?;
+    let len = u64::from_le_bytes(len);
+    if len > MAX_CHUNK_LEN {
+        return Err(VerifyFailure::ProofShape);
+    }
+    let mut bytes = vec![0u8; len as usize];
+    r.read_exact(&mut bytes).map_err(|_| VerifyFailure::ProofShape)?;
+    bincode::deserialize(&bytes).map_err(|_| VerifyFailure::ProofShape)
+}
+
+/// Writes `proof` as a head chunk followed by one chunk per FRI query.
+pub fn write_streamed<W: Write>(proof: &Proof<MyConfig>, mut w: W) -> io::Result<()> {
+    let fri = &proof.opening_proof;
+    let head = HeadRef {
+        commitments: &proof.commitments,
+        opened_values: &proof.opened_values,
+        degree_bits: proof.degree_bits,
+        commit_phase_commits: &fri.commit_phase_commits,
+        final_poly: &fri.final_poly,
+        pow_witness: &fri.pow_witness,
+        num_queries: fri.query_proofs.len(),
+    };
+    write_chunk(&mut w, &head)?;
+    for query in &fri.query_proofs {
+        write_chunk(&mut w, query)?;
+    }
+    w.flush()
+}
+
+fn reverse_bits_len(x: usize, bits: usize) -> usize {
+    if bits == 0 {
+        0
+    } else {
+        x.reverse_bits() >> (usize::BITS as usize - bits)
+    }
+}
+
+fn log2_strict(n: usize) -> usize {
+    assert!(n.is_power_of_two(), "{} is not a power of two", n);
+    n.trailing_zeros() as usize
+}
+
+/// A verifier part way through a streamed proof: the head has been checked, the queries are read on demand.
+pub struct StreamingVerifier<R> {
+    reader: R,
+    params: FriParams,
+    input_mmcs: ValMmcs,
+    fri_mmcs: ChallengeMmcs,
+    head: Head,
+    challenger: Challenger,
+    /// the domains and opening points of the trace round and the quotient round
+    rounds: [Vec<(Domain, Vec<(Challenge, Vec<Challenge>)>)>; 2],
+    /// the PCS's batch combination challenge
+    alpha: Challenge,
+    betas: Vec<Challenge>,
+    log_max_height: usize,
+    queries_checked: usize,
+}
+
+impl<R: Read> StreamingVerifier<R> {
+    /// Reads and checks the head of a proof made with `params.config(perm)`.
+    pub fn begin<A>(
+        perm: &Perm,
+        params: FriParams,
+        air: &A,
+        public_values: &Vec<Val>,
+        mut reader: R,
+    ) -> Result<Self, VerifyFailure>
+    where
+        A: Air<SymbolicAirBuilder<Val>> + for<'a> Air<VerifierConstraintFolder<'a, MyConfig>>,
+    {
+        let head: Head = read_chunk(&mut reader)?;
+        let config = params.config(perm);
+        let pcs = config.pcs();
+
+        // every domain is sized from `degree_bits`, and FRI folds the LDE of the trace height down to a
+        // constant, one round per bit, so both have to be checked before any shift or index uses them
+        let log_quotient_degree = get_log_quotient_degree::<Val, A>(air, 0, public_values.len());
+        let log_lde_height = head.degree_bits + params.log_blowup.max(log_quotient_degree);
+        if log_lde_height > Val::TWO_ADICITY || head.commit_phase_commits.len() != head.degree_bits {
+            return Err(VerifyFailure::ProofShape);
+        }
+
+        let degree = 1 << head.degree_bits;
+        let quotient_degree = 1 << log_quotient_degree;
+        let trace_domain = pcs.natural_domain_for_degree(degree);
+        let quotient_domain = trace_domain.create_disjoint_domain(1 << (head.degree_bits + log_quotient_degree));
+        let quotient_chunks_domains = quotient_domain.split_domains(quotient_degree);
+
+        let opened = &head.opened_values;
+        let air_width = <A as BaseAir<Val>>::width(air);
+        let ext_degree = <Challenge as AbstractExtensionField<Val>>::D;
+        let valid_shape = opened.trace_local.len() == air_width
+            && opened.trace_next.len() == air_width
+            && opened.quotient_chunks.len() == quotient_degree
+            && opened.quotient_chunks.iter().all(|chunk| chunk.len() == ext_degree)
+            && head.num_queries == params.num_queries;
+        if !valid_shape {
+            return Err(VerifyFailure::ProofShape);
+        }
+
+        // the uni-stark transcript
+        let mut challenger = Challenger::new(perm.clone());
+        challenger.observe(head.commitments.trace.clone());
+        challenger.observe_slice(public_values);
+        let constraint_alpha: Challenge = challenger.sample_ext_element();
+        challenger.observe(head.commitments.quotient_chunks.clone());
+        let zeta: Challenge = challenger.sample();
+        let zeta_next = trace_domain.next_point(zeta).unwrap();
+
+        // the constraints at `zeta`, recombined from the quotient chunks
+        let zps = quotient_chunks_domains
+            .iter()
+            .enumerate()
+            .map(|(i, domain)| {
+                quotient_chunks_domains
+                    .iter()
+                    .enumerate()
+                    .filter(|(j, _)| *j != i)
+                    .map(|(_, other)| other.zp_at_point(zeta) * other.zp_at_point(domain.first_point()).inverse())
+                    .product::<Challenge>()
+            })
+            .collect::<Vec<_>>();
+        let quotient = opened
+            .quotient_chunks
+            .iter()
+            .zip(&zps)
+            .map(|(chunk, &zp)| {
+                chunk.iter().enumerate().map(|(e, &c)| zp * Challenge::monomial(e) * c).sum::<Challenge>()
+            })
+            .sum::<Challenge>();
+
+        let sels = trace_domain.selectors_at_point(zeta);
+        let mut folder = VerifierConstraintFolder {
+            main: VerticalPair::new(
+                RowMajorMatrixView::new_row(&opened.trace_local),
+                RowMajorMatrixView::new_row(&opened.trace_next),
+            ),
+            public_values,
+            is_first_row: sels.is_first_row,
+            is_last_row: sels.is_last_row,
+            is_transition: sels.is_transition,
+            alpha: constraint_alpha,
+            accumulator: Challenge::zero(),
+        };
+        air.eval(&mut folder);
+        if folder.accumulator * sels.inv_zeroifier != quotient {
+            return Err(VerifyFailure::Constraints);
+        }
+
+        let rounds = [
+            vec![(trace_domain, vec![(zeta, opened.trace_local.clone()), (zeta_next, opened.trace_next.clone())])],
+            quotient_chunks_domains
+                .into_iter()
+                .zip(&opened.quotient_chunks)
+                .map(|(domain, values)| (domain, vec![(zeta, values.clone())]))
+                .collect(),
+        ];
+
+        // the PCS and FRI transcript, up to the query indices
+        let alpha: Challenge = challenger.sample_ext_element();
+        let betas = head
+            .commit_phase_commits
+            .iter()
+            .map(|commit| {
+                challenger.observe(commit.clone());
+                challenger.sample_ext_element()
+            })
+            .collect();
+        if !challenger.check_witness(params.proof_of_work_bits, head.pow_witness) {
+            return Err(VerifyFailure::ProofOfWork);
+        }
+        let log_max_height = head.commit_phase_commits.len() + params.log_blowup;
+
+        let input_mmcs = ValMmcs::new(MyHash::new(perm.clone()), MyCompress::new(perm.clone()));
+        let fri_mmcs = ChallengeMmcs::new(input_mmcs.clone());
+        Ok(StreamingVerifier {
+            reader,
+            params,
+            input_mmcs,
+            fri_mmcs,
+            head,
+            challenger,
+            rounds,
+            alpha,
+            betas,
+            log_max_height,
+            queries_checked: 0,
+        })
+    }
+
+    pub fn queries_remaining(&self) -> usize {
+        self.head.num_queries - self.queries_checked
+    }
+
+    /// Reads and checks the next query response.
+    pub fn verify_next_query(&mut self) -> Result<(), VerifyFailure> {
+        assert!(self.queries_remaining() > 0, "all queries have been checked");
+        let query: Query = read_chunk(&mut self.reader)?;
+        let index = self.challenger.sample_bits(self.log_max_height);
+
+        let reduced_openings = self.reduce_openings(index, &query.input_proof)?;
+        let folded = self.fold(index, &query, &reduced_openings)?;
+        if folded != self.head.final_poly {
+            return Err(VerifyFailure::LowDegree("the folded queries do not match the final polynomial".to_string()));
+        }
+
+        self.queries_checked += 1;
+        Ok(())
+    }
+
+    /// Checks every remaining query.
+    pub fn finish(mut self) -> Result<(), VerifyFailure> {
+        while self.queries_remaining() > 0 {
+            self.verify_next_query()?;
+        }
+        Ok(())
+    }
+
+    /// Checks the query's openings of the trace and quotient commitments, and combines them into one value
+    /// per LDE height.
+    fn reduce_openings(&self, index: usize, input_proof: &InputProof) -> Result<[Challenge; 32], VerifyFailure> {
+        if input_proof.len() != self.rounds.len() {
+            return Err(VerifyFailure::ProofShape);
+        }
+        let log_blowup = self.params.log_blowup;
+        let commits = [&self.head.commitments.trace, &self.head.commitments.quotient_chunks];
+
+        let mut reduced = [Challenge::zero(); 32];
+        let mut alpha_pow = [Challenge::one(); 32];
+        for ((batch, commit), mats) in input_proof.iter().zip(commits).zip(&self.rounds) {
+            if batch.opened_values.len() != mats.len() {
+                return Err(VerifyFailure::ProofShape);
+            }
+            let heights = mats.iter().map(|(domain, _)| domain.size() << log_blowup).collect::<Vec<_>>();
+            let dims = heights.iter().map(|&height| Dimensions { width: 0, height }).collect::<Vec<_>>();
+            let log_batch_height = log2_strict(*heights.iter().max().unwrap());
+            let batch_index = index >> (self.log_max_height - log_batch_height);
+            self.input_mmcs
+                .verify_batch(commit, &dims, batch_index, &batch.opened_values, &batch.opening_proof)
+                .map_err(|e| {
+                    VerifyFailure::LowDegree(format!("a trace or quotient Merkle opening is invalid ({:?})", e))
+                })?;
+
+            for (mat_opening, (domain, points_and_values)) in batch.opened_values.iter().zip(mats) {
+                let log_height = log2_strict(domain.size()) + log_blowup;
+                let rev_index = reverse_bits_len(index >> (self.log_max_height - log_height), log_height);
+                let x = Val::generator() * Val::two_adic_generator(log_height).exp_u64(rev_index as u64);
+                for (z, values_at_z) in points_and_values {
+                    if mat_opening.len() != values_at_z.len() {
+                        return Err(VerifyFailure::ProofShape);
+                    }
+                    for (&p_at_x, &p_at_z) in mat_opening.iter().zip(values_at_z) {
+                        let quotient = (-p_at_z + p_at_x) / (-*z + x);
+                        reduced[log_height] += alpha_pow[log_height] * quotient;
+                        alpha_pow[log_height] *= self.alpha;
+                    }
+                }
+            }
+        }
+        Ok(reduced)
+    }
+
+    /// Walks the query through the folding rounds, returning the value it folds to.
+    fn fold(&self, mut index: usize, query: &Query, reduced: &[Challenge; 32]) -> Result<Challenge, VerifyFailure> {
+        let steps = &query.commit_phase_openings;
+        if steps.len() != self.head.commit_phase_commits.len() {
+            return Err(VerifyFailure::ProofShape);
+        }
+
+        let mut folded = Challenge::zero();
+        let mut x = Challenge::two_adic_generator(self.log_max_height)
+            .exp_u64(reverse_bits_len(index, self.log_max_height) as u64);
+        let log_heights = (self.params.log_blowup..self.log_max_height).rev();
+        for (((log_folded_height, commit), step), &beta) in
+            log_heights.zip(&self.head.commit_phase_commits).zip(steps).zip(&self.betas)
+        {
+            folded += reduced[log_folded_height + 1];
+
+            let index_sibling = index ^ 1;
+            let index_pair = index >> 1;
+            let mut evals = vec![folded; 2];
+            evals[index_sibling % 2] = step.sibling_value;
+
+            let dims = [Dimensions { width: 2, height: 1 << log_folded_height }];
+            self.fri_mmcs
+                .verify_batch(commit, &dims, index_pair, &[evals.clone()], &step.opening_proof)
+                .map_err(|e| {
+                    VerifyFailure::LowDegree(format!("a commit-phase Merkle opening is invalid ({:?})", e))
+                })?;
+
+            let mut xs = [x; 2];
+            xs[index_sibling % 2] *= Challenge::two_adic_generator(1);
+            folded = evals[0] + (beta - xs[0]) * (evals[1] - evals[0]) / (xs[1] - xs[0]);
+
+            index = index_pair;
+            x = x.square();
+        }
+        Ok(folded)
+    }
+}
+
+/// Verifies a streamed proof from `reader`, one query response at a time.
+pub fn verify_streamed<A, R: Read>(
+    perm: &Perm,
+    params: FriParams,
+    air: &A,
+    public_values: &Vec<Val>,
+    reader: R,
+) -> Result<(), VerifyFailure>
+where
+    A: Air<SymbolicAirBuilder<Val>> + for<'a> Air<VerifierConstraintFolder<'a, MyConfig>>,
+{
+    StreamingVerifier::begin(perm, params, air, public_values, reader)?.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use p3_uni_stark::{prove, verify};
+
+    use super::*;
+    use crate::config::random_perm;
+    use crate::simple_state::{random_checked_trace, SimpleStateChecked};
+
+    fn proof_and_publics(perm: &Perm, params: FriParams) -> (Proof<MyConfig>, Vec<Val>) {
+        let (trace, public_values) = random_checked_trace::<Val>(8);
+        let mut challenger = Challenger::new(perm.clone());
+        let proof = prove(&params.config(perm), &SimpleStateChecked {}, &mut challenger, trace, &public_values);
+        (proof, public_values)
+    }
+
+    fn streamed(proof: &Proof<MyConfig>) -> Vec<u8> {
+        let mut bytes = vec![];
+        write_streamed(proof, &mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_streamed_proof_verifies() {
+        let perm = random_perm();
+        let params = FriParams::default();
+        let (proof, public_values) = proof_and_publics(&perm, params);
+
+        let mut challenger = Challenger::new(perm.clone());
+        verify(&params.config(&perm), &SimpleStateChecked {}, &mut challenger, &proof, &public_values).unwrap();
+
+        let bytes = streamed(&proof);
+        let mut verifier =
+            StreamingVerifier::begin(&perm, params, &SimpleStateChecked {}, &public_values, Cursor::new(&bytes))
+                .unwrap();
+        assert_eq!(verifier.queries_remaining(), params.num_queries);
+        verifier.verify_next_query().unwrap();
+        assert_eq!(verifier.queries_remaining(), params.num_queries - 1);
+        verifier.finish().unwrap();
+    }
+
+    #[test]
+    fn test_streamed_rejections() {
+        let perm = random_perm();
+        let params = FriParams::default();
+        let (proof, public_values) = proof_and_publics(&perm, params);
+        let air = SimpleStateChecked {};
+
+        // a public value: the transcript diverges
+        let mut other_publics = public_values.clone();
+        other_publics[1] += Val::one();
+        assert!(verify_streamed(&perm, params, &air, &other_publics, Cursor::new(streamed(&proof))).is_err());
+
+        // a folding sibling in the last query
+        let mut tampered = proof;
+        let last = tampered.opening_proof.query_proofs.last_mut().unwrap();
+        last.commit_phase_openings[0].sibling_value += Challenge::one();
+        let bytes = streamed(&tampered);
+        assert!(matches!(
+            verify_streamed(&perm, params, &air, &public_values, Cursor::new(&bytes)),
+            Err(VerifyFailure::LowDegree(_))
+        ));
+
+        // a stream cut off before the last query
+        let cut = &bytes[..bytes.len() - 16];
+        assert_eq!(
+            verify_streamed(&perm, params, &air, &public_values, Cursor::new(cut)),
+            Err(VerifyFailure::ProofShape)
+        );
+
+        // a chunk length too large to allocate
+        let mut huge = u64::MAX.to_le_bytes().to_vec();
+        huge.extend_from_slice(&bytes[8..]);
+        assert_eq!(
+            verify_streamed(&perm, params, &air, &public_values, Cursor::new(huge)),
+            Err(VerifyFailure::ProofShape)
+        );
+
+        // a degree past the two-adicity, and one the FRI rounds do not match
+        let degree_bits = tampered.degree_bits;
+        for bad_degree_bits in [64, degree_bits + 1] {
+            tampered.degree_bits = bad_degree_bits;
+            assert_eq!(
+                verify_streamed(&perm, params, &air, &public_values, Cursor::new(streamed(&tampered))),
+                Err(VerifyFailure::ProofShape)
+            );
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use p3_air::Air;
+use p3_commit::Pcs;
+use p3_uni_stark::{
+    verify, Proof, StarkGenericConfig, SymbolicAirBuilder, Val, VerificationError, VerifierConstraintFolder,
+};
+
+use crate::error::VerifyFailure;
+
+// A proof is bound to its constraint system only through the checks the verifier runs with its own AIR: the
+// AIR is not hashed into the transcript. Verifying a proof under another AIR therefore fails at one of two
+// places, depending on how much the two AIRs have in common:
+//   - a different width (or constraint degree, so a different number of quotient chunks) gives openings of
+//     the wrong size, and the proof is rejected at the shape check;
+//   - the same width and degree gets through the shape check and the PCS opening, which know nothing about
+//     the constraints, and fails at the out-of-domain check, where the openings don't satisfy the other
+//     AIR's constraints except with negligible probability.
+// Both are sound rejections, but the second one is reached only after the full FRI verification and says
+// nothing about why. The robust binding is `schema::encode_proof`: the header carries a hash of the AIR's
+// id and column names, and `schema::verify_encoded` rejects a proof for another schema before decoding it.
+
+/// Verifies `proof` under `air`, which it was not produced for, and returns why it was rejected.
+///
+/// Panics if the proof verifies: `air` accepts a proof of another statement.
+pub fn assert_rejected_by_other_air<SC, A>(
+    config: &SC,
+    air: &A,
+    challenger: &mut SC::Challenger,
+    proof: &Proof<SC>,
+    public_values: &Vec<Val<SC>>,
+) -> VerifyFailure
+where
+    SC: StarkGenericConfig,
+    A: Air<SymbolicAirBuilder<Val<SC>>> + for<'a> Air<VerifierConstraintFolder<'a, SC>>,
+    VerifyFailure: From<VerificationError<<SC::Pcs as Pcs<SC::Challenge, SC::Challenger>>::Error>>,
+{
+    match verify(config, air, challenger, proof, public_values) {
+        Ok(()) => panic!("the proof verified under an AIR it was not produced for"),
+        Err(e) => e.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_air::{AirBuilder, BaseAir};
+    use p3_matrix::Matrix;
+    use p3_uni_stark::prove;
+
+    use super::*;
+    use crate::columns::NamedColumns;
+    use crate::config::{default_config, random_perm, Challenger, MyConfig, Perm, Val};
+    use crate::error::CookError;
+    use crate::schema::{encode_proof, verify_encoded, AirSchema};
+    use crate::simple_state::{random_trace, SimpleState};
+
+    /// Fibonacci in its first two columns; at width 3 it has the width and constraint degree of `SimpleState`.
+    struct FibonacciAir {
+        width: usize,
+    }
+
+    impl<F> BaseAir<F> for FibonacciAir {
+        fn width(&self) -> usize {
+            self.width
+        }
+    }
+
+    impl<AB: AirBuilder> Air<AB> for FibonacciAir {
+        fn eval(&self, builder: &mut AB) {
+            let main = builder.main();
+            let (local, next) = (main.row_slice(0), main.row_slice(1));
+            builder.when_transition().assert_eq(next[0], local[1]);
+            builder.when_transition().assert_eq(next[1], local[0] + local[1]);
+        }
+    }
+
+    impl NamedColumns for FibonacciAir {
+        fn column_names(&self) -> Vec<String> {
+            (0..self.width).map(|i| format!("fib{}", i)).collect()
+        }
+    }
+
+    impl AirSchema for FibonacciAir {
+        fn air_id(&self) -> &'static str {
+            "fibonacci"
+        }
+    }
+
+    fn simple_state_proof() -> (Perm, MyConfig, Proof<MyConfig>) {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &SimpleState {}, &mut p_challenger, random_trace::<Val>(6), &vec![]);
+        (perm, config, proof)
+    }
+
+    #[test]
+    fn test_other_width_fails_shape_check() {
+        let (perm, config, proof) = simple_state_proof();
+        let mut v_challenger = Challenger::new(perm);
+        let air = FibonacciAir { width: 2 };
+        let failure = assert_rejected_by_other_air(&config, &air, &mut v_challenger, &proof, &vec![]);
+        assert_eq!(failure, VerifyFailure::ProofShape);
+    }
+
+    #[test]
+    fn test_same_width_fails_constraints() {
+        let (perm, config, proof) = simple_state_proof();
+        let mut v_challenger = Challenger::new(perm);
+        let air = FibonacciAir { width: 3 };
+        let failure = assert_rejected_by_other_air(&config, &air, &mut v_challenger, &proof, &vec![]);
+        assert_eq!(failure, VerifyFailure::Constraints);
+    }
+
+    #[test]
+    fn test_schema_hash_rejects_before_verifying() {
+        let (perm, config, proof) = simple_state_proof();
+        let bytes = encode_proof(&SimpleState {}, &proof);
+        let mut v_challenger = Challenger::new(perm);
+        match verify_encoded(&config, &FibonacciAir { width: 3 }, &mut v_challenger, &bytes, &vec![]) {
+            Err(CookError::Verification(VerifyFailure::Schema { .. })) => {}
+            other => panic!("expected a schema failure, got {:?}", other),
+        }
+    }
+}
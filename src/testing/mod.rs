@@ -0,0 +1,2 @@
+pub mod adversarial;
+pub mod random_satisfying;
@@ -0,0 +1,296 @@
+use p3_air::Air;
+use p3_field::Field;
+use p3_matrix::dense::RowMajorMatrix;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::debug::{eval_constraints_with, EvalBuilder, Selectors};
+
+// A trace generator that needs nothing but the AIR: it finds a witness by evaluating the constraints, so a
+// new constraint system can be checked for having one before anyone writes its trace generator.
+//
+// Rows are filled in order. Row `r` must satisfy the window `(r - 1, r)`, now that row `r - 1` is fixed,
+// and the constraints of the window `(r, r + 1)` that don't read row `r + 1`. The last row's window wraps
+// to the first row, which is fixed by then. Within a row the solver works column by column:
+//   - it probes which columns each constraint reads, by perturbing one column at a time;
+//   - a constraint left with one open column that it is affine in fixes that column;
+//   - failing that, a constraint left with one open column that it isn't affine in has the column sampled
+//     until it holds (small values first, in random order, then random ones);
+//   - failing that, the lowest open column gets a random value.
+// The solver is greedy: a row that can't be completed restarts the whole trace, a few times. Constraints
+// that only a planned choice satisfies, like a last-row equality with a running sum, usually defeat it.
+// It is a test helper, not a witness generator: `None` means it found nothing, not that nothing exists.
+
+/// The builder the generator evaluates constraints with: a native `EvalBuilder` without public values.
+pub type ConstraintSatisfier<'a, F> = EvalBuilder<'a, F>;
+
+const TRACE_ATTEMPTS: usize = 8;
+const SMALL_CANDIDATES: u64 = 16;
+const RANDOM_CANDIDATES: usize = 64;
+
+/// A random `n_rows`-row trace satisfying every constraint of `air`, or `None` if the solver finds none.
+///
+/// The same `seed` gives the same trace. AIRs that read public values are out of scope: the constraints are
+/// evaluated with none, so indexing them panics.
+pub fn generate_satisfying_trace<F, A>(air: &A, n_rows: usize, seed: u64) -> Option<RowMajorMatrix<F>>
+where
+    F: Field,
+    A: for<'a> Air<ConstraintSatisfier<'a, F>>,
+{
+    assert!(n_rows > 0, "a trace needs at least one row");
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..TRACE_ATTEMPTS).find_map(|_| try_trace(air, n_rows, &mut rng))
+}
+
+fn try_trace<F, A>(air: &A, n_rows: usize, rng: &mut StdRng) -> Option<RowMajorMatrix<F>>
+where
+    F: Field,
+    A: for<'a> Air<ConstraintSatisfier<'a, F>>,
+{
+    let width = air.width();
+    let mut values = Vec::with_capacity(n_rows * width);
+    for r in 0..n_rows {
+        let row = RowSystem::new(air, r, n_rows, &values, rng).solve(rng)?;
+        values.extend(row);
+    }
+    Some(RowMajorMatrix::new(values, width))
+}
+
+/// The constraints row `r` must satisfy, given the rows before it.
+struct RowSystem<'a, F, A> {
+    air: &'a A,
+    r: usize,
+    n_rows: usize,
+    width: usize,
+    previous: Option<&'a [F]>,
+    first: &'a [F],
+    /// stands in for row `r + 1`
+    placeholder: Vec<F>,
+    /// which of the window `(r, r + 1)`'s constraints don't read row `r + 1`
+    own: Vec<bool>,
+}
+
+impl<'a, F, A> RowSystem<'a, F, A>
+where
+    F: Field,
+    A: for<'b> Air<ConstraintSatisfier<'b, F>>,
+{
+    fn new(air: &'a A, r: usize, n_rows: usize, rows: &'a [F], rng: &mut StdRng) -> Self {
+        let width = air.width();
+        let mut system = RowSystem {
+            air,
+            r,
+            n_rows,
+            width,
+            previous: (r > 0).then(|| &rows[(r - 1) * width..r * width]),
+            first: &rows[..width.min(rows.len())],
+            placeholder: vec![F::zero(); width],
+            own: vec![],
+        };
+
+        // a constraint reads row `r + 1` if changing it changes the residual
+        let x = random_row(width, rng);
+        let with_zeros = system.own_window(&x);
+        system.placeholder = random_row(width, rng);
+        let with_random = system.own_window(&x);
+        system.own = with_zeros.iter().zip(&with_random).map(|(a, b)| a == b).collect();
+        system
+    }
+
+    /// The window `(r, r + 1)`, with row `r + 1` from the trace if it wraps and the placeholder otherwise.
+    fn own_window(&self, x: &[F]) -> Vec<F> {
+        let next = match (self.r == self.n_rows - 1, self.r == 0) {
+            (true, true) => x,
+            (true, false) => self.first,
+            (false, _) => &self.placeholder[..],
+        };
+        eval_constraints_with(self.air, x, next, &[], Selectors::for_row(self.r, self.n_rows))
+    }
+
+    /// The residual of every constraint on row `r` when it holds `x`.
+    fn residuals(&self, x: &[F]) -> Vec<F> {
+        let mut residuals = match self.previous {
+            Some(previous) => {
+                let selectors = Selectors::for_row(self.r - 1, self.n_rows);
+                eval_constraints_with(self.air, previous, x, &[], selectors)
+            }
+            None => vec![],
+        };
+        let own = self.own_window(x);
+        residuals.extend(own.into_iter().zip(&self.own).filter(|(_, &own)| own).map(|(v, _)| v));
+        residuals
+    }
+
+    fn solve(&self, rng: &mut StdRng) -> Option<Vec<F>> {
+        // the columns each constraint reads
+        let base = random_row(self.width, rng);
+        let base_residuals = self.residuals(&base);
+        let mut reads = vec![vec![]; base_residuals.len()];
+        for k in 0..self.width {
+            let mut probe = base.clone();
+            probe[k] += F::one() + F::from_wrapped_u64(rng.gen());
+            for (j, residual) in self.residuals(&probe).into_iter().enumerate() {
+                if residual != base_residuals[j] {
+                    reads[j].push(k);
+                }
+            }
+        }
+
+        let mut x: Vec<Option<F>> = vec![None; self.width];
+        while let Some(lowest_open) = x.iter().position(Option::is_none) {
+            let point = x.iter().map(|v| v.unwrap_or_else(|| random_value(rng))).collect::<Vec<_>>();
+            let residual = |j: usize, k: usize, v: F| {
+                let mut p = point.clone();
+                p[k] = v;
+                self.residuals(&p)[j]
+            };
+
+            // constraints with exactly one open column
+            let single = reads
+                .iter()
+                .enumerate()
+                .filter_map(|(j, cols)| {
+                    let mut open = cols.iter().filter(|&&k| x[k].is_none());
+                    match (open.next(), open.next()) {
+                        (Some(&k), None) => Some((j, k)),
+                        _ => None,
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            let solved = single.iter().find_map(|&(j, k)| solve_affine(|v| residual(j, k, v), rng).map(|v| (k, v)));
+            let (k, v) = match (solved, single.first()) {
+                (Some(solved), _) => solved,
+                (None, Some(&(j, k))) => (k, candidates(rng).find(|&v| residual(j, k, v).is_zero())?),
+                (None, None) => (lowest_open, random_value(rng)),
+            };
+            x[k] = Some(v);
+        }
+
+        let row = x.into_iter().map(Option::unwrap).collect::<Vec<_>>();
+        self.residuals(&row).iter().all(F::is_zero).then_some(row)
+    }
+}
+
+/// The root of `f` if it is affine with a nonzero slope, checked at a random point.
+fn solve_affine<F: Field>(f: impl Fn(F) -> F, rng: &mut StdRng) -> Option<F> {
+    let (at_zero, slope) = (f(F::zero()), f(F::one()) - f(F::zero()));
+    let t = random_value::<F>(rng);
+    if slope.is_zero() || f(t) != at_zero + slope * t {
+        return None;
+    }
+    Some(-at_zero * slope.inverse())
+}
+
+/// Values to try for a column a constraint isn't affine in: the small ones in random order, then random ones.
+fn candidates<F: Field>(rng: &mut StdRng) -> impl Iterator<Item = F> + '_ {
+    let mut small = (0..SMALL_CANDIDATES).map(F::from_canonical_u64).collect::<Vec<_>>();
+    small.shuffle(rng);
+    small.into_iter().chain((0..RANDOM_CANDIDATES).map(|_| random_value(rng)))
+}
+
+fn random_value<F: Field>(rng: &mut StdRng) -> F {
+    F::from_wrapped_u64(rng.gen())
+}
+
+fn random_row<F: Field>(width: usize, rng: &mut StdRng) -> Vec<F> {
+    (0..width).map(|_| random_value(rng)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_air::{AirBuilder, BaseAir};
+    use p3_field::AbstractField;
+    use p3_matrix::Matrix;
+
+    use super::*;
+    use crate::assert_constraints_ok;
+    use crate::config::Val;
+    use crate::gadgets::less_than::assert_bit_decomposition;
+    use crate::simple_state::SimpleState;
+
+    // Fibonacci from (0, 1): every column is determined
+    struct FibonacciAir {}
+
+    impl<F> BaseAir<F> for FibonacciAir {
+        fn width(&self) -> usize {
+            2
+        }
+    }
+
+    impl<AB: AirBuilder> Air<AB> for FibonacciAir {
+        fn eval(&self, builder: &mut AB) {
+            let main = builder.main();
+            let (local, next) = (main.row_slice(0), main.row_slice(1));
+            builder.when_first_row().assert_zero(local[0]);
+            builder.when_first_row().assert_one(local[1]);
+            builder.when_transition().assert_eq(next[0], local[1]);
+            builder.when_transition().assert_eq(next[1], local[0] + local[1]);
+        }
+    }
+
+    // a value and its 8 bits, so the bits are boolean: non-linear constraints
+    struct ByteAir {}
+
+    impl<F> BaseAir<F> for ByteAir {
+        fn width(&self) -> usize {
+            9
+        }
+    }
+
+    impl<AB: AirBuilder> Air<AB> for ByteAir {
+        fn eval(&self, builder: &mut AB) {
+            let main = builder.main();
+            let local = main.row_slice(0);
+            assert_bit_decomposition(builder, local[0], &local[1..]);
+        }
+    }
+
+    struct ContradictionAir {}
+
+    impl<F> BaseAir<F> for ContradictionAir {
+        fn width(&self) -> usize {
+            1
+        }
+    }
+
+    impl<AB: AirBuilder> Air<AB> for ContradictionAir {
+        fn eval(&self, builder: &mut AB) {
+            let main = builder.main();
+            let local = main.row_slice(0);
+            builder.assert_zero(local[0]);
+            builder.assert_one(local[0]);
+        }
+    }
+
+    #[test]
+    fn test_linear_constraints() {
+        let trace = generate_satisfying_trace::<Val, _>(&SimpleState {}, 16, 1).unwrap();
+        assert_constraints_ok!(&SimpleState {}, &trace, &[]);
+
+        let trace = generate_satisfying_trace::<Val, _>(&FibonacciAir {}, 8, 1).unwrap();
+        assert_constraints_ok!(&FibonacciAir {}, &trace, &[]);
+        assert_eq!(trace.row_slice(7)[1], Val::from_canonical_u32(21));
+    }
+
+    #[test]
+    fn test_non_linear_constraints() {
+        let trace = generate_satisfying_trace::<Val, _>(&ByteAir {}, 16, 1).unwrap();
+        assert_constraints_ok!(&ByteAir {}, &trace, &[]);
+        // the bits are sampled, not all zero
+        assert!((0..16).any(|r| !trace.row_slice(r)[0].is_zero()));
+    }
+
+    #[test]
+    fn test_seed_determines_trace() {
+        let a = generate_satisfying_trace::<Val, _>(&ByteAir {}, 8, 7).unwrap();
+        let b = generate_satisfying_trace::<Val, _>(&ByteAir {}, 8, 7).unwrap();
+        assert_eq!(a.values, b.values);
+    }
+
+    #[test]
+    fn test_unsatisfiable_air() {
+        assert!(generate_satisfying_trace::<Val, _>(&ContradictionAir {}, 4, 1).is_none());
+    }
+}
@@ -0,0 +1,131 @@
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+use p3_air::Air;
+use p3_commit::Pcs;
+use p3_uni_stark::{
+    verify, Proof, StarkGenericConfig, SymbolicAirBuilder, Val, VerificationError, VerifierConstraintFolder,
+};
+
+use crate::error::{CookError, VerifyFailure};
+
+// A verifier exposed to untrusted proofs may want to bound how long it waits on one. Verification itself
+// can't be interrupted, so it runs on a worker thread and the caller stops waiting when the budget runs
+// out.
+
+/// `verify` on a worker thread, giving up after `budget` with `CookError::Timeout`.
+///
+/// The arguments are moved to the worker, hence owned. A worker that times out is abandoned, not killed:
+/// it runs to completion in the background and its result is dropped, so the budget bounds the caller's
+/// wait, not the CPU time spent. A verifier that panics (on a proof malformed in a way `verify` doesn't
+/// check for) is reported as `VerifyFailure::ProofShape`.
+pub fn verify_with_timeout<SC, A>(
+    config: SC,
+    air: A,
+    mut challenger: SC::Challenger,
+    proof: Proof<SC>,
+    public_values: Vec<Val<SC>>,
+    budget: Duration,
+) -> Result<(), CookError>
+where
+    SC: StarkGenericConfig + Send + 'static,
+    SC::Challenger: Send,
+    Proof<SC>: Send,
+    A: Air<SymbolicAirBuilder<Val<SC>>> + for<'a> Air<VerifierConstraintFolder<'a, SC>> + Send + 'static,
+    VerifyFailure: From<VerificationError<<SC::Pcs as Pcs<SC::Challenge, SC::Challenger>>::Error>>,
+{
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let result = verify(&config, &air, &mut challenger, &proof, &public_values)
+            .map_err(|e| CookError::Verification(e.into()));
+        // the caller may have stopped listening
+        let _ = sender.send(result);
+    });
+
+    match receiver.recv_timeout(budget) {
+        Ok(result) => result,
+        Err(RecvTimeoutError::Timeout) => Err(CookError::Timeout { budget }),
+        Err(RecvTimeoutError::Disconnected) => Err(CookError::Verification(VerifyFailure::ProofShape)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_field::AbstractField;
+    use p3_symmetric::CryptographicHasher;
+    use p3_uni_stark::prove;
+
+    use super::*;
+    use crate::config::{default_config, make_config_with, random_perm, Challenger, ConfigWith, MyConfig, MyHash, Perm};
+    use crate::config::{DEFAULT_LOG_BLOWUP, DEFAULT_NUM_QUERIES, DEFAULT_POW_BITS};
+    use crate::simple_state::{random_checked_trace, SimpleStateChecked};
+
+    const GENEROUS: Duration = Duration::from_secs(60);
+
+    // hashes like `inner`, but sleeps first
+    #[derive(Clone)]
+    struct SlowHash<H> {
+        inner: H,
+        delay: Duration,
+    }
+
+    impl<F, H: CryptographicHasher<F, [F; 8]>> CryptographicHasher<F, [F; 8]> for SlowHash<H> {
+        fn hash_iter<I>(&self, input: I) -> [F; 8]
+        where
+            I: IntoIterator<Item = F>,
+        {
+            thread::sleep(self.delay);
+            self.inner.hash_iter(input)
+        }
+    }
+
+    fn setup() -> (Perm, MyConfig, Proof<MyConfig>, Vec<Val<MyConfig>>) {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let (trace, public_values) = random_checked_trace(6);
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let proof = prove(&config, &SimpleStateChecked {}, &mut p_challenger, trace, &public_values);
+        (perm, config, proof, public_values)
+    }
+
+    #[test]
+    fn test_verifies_within_budget() {
+        let (perm, config, proof, public_values) = setup();
+        let challenger = Challenger::new(perm);
+        verify_with_timeout(config, SimpleStateChecked {}, challenger, proof, public_values, GENEROUS).unwrap();
+    }
+
+    #[test]
+    fn test_rejection_is_not_a_timeout() {
+        let (perm, config, proof, mut public_values) = setup();
+        public_values[0] += Val::<MyConfig>::one();
+
+        let challenger = Challenger::new(perm);
+        match verify_with_timeout(config, SimpleStateChecked {}, challenger, proof, public_values, GENEROUS) {
+            Err(CookError::Verification(_)) => {}
+            other => panic!("expected a verification failure, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_slow_config_times_out() {
+        let (perm, _, proof, public_values) = setup();
+
+        // the slow hash computes the same digests, so the proof is valid under it too, but every opened leaf
+        // now costs 20ms: far beyond the budget over 40 queries
+        let slow = SlowHash { inner: MyHash::new(perm.clone()), delay: Duration::from_millis(20) };
+        let slow_config: ConfigWith<SlowHash<MyHash>> =
+            make_config_with(slow, &perm, DEFAULT_LOG_BLOWUP, DEFAULT_NUM_QUERIES, DEFAULT_POW_BITS);
+        let proof: Proof<ConfigWith<SlowHash<MyHash>>> =
+            bincode::deserialize(&bincode::serialize(&proof).unwrap()).unwrap();
+        let challenger = Challenger::new(perm);
+
+        let budget = Duration::from_millis(50);
+        match verify_with_timeout(slow_config, SimpleStateChecked {}, challenger, proof, public_values, budget) {
+            Err(CookError::Timeout { budget: b }) => assert_eq!(b, budget),
+            other => panic!("expected a timeout, got {:?}", other),
+        }
+    }
+}
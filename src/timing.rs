@@ -0,0 +1,326 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use p3_air::Air;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::{prove, Proof, ProverConstraintFolder, StarkGenericConfig, SymbolicAirBuilder, Val};
+use serde::{Deserialize, Serialize};
+#[cfg(debug_assertions)]
+use p3_uni_stark::DebugConstraintBuilder;
+use tracing::span::Id;
+use tracing::{info, Subscriber};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{Layer, Registry};
+
+// The prover phases live inside `uni_stark::prove`, so rather than forking it we time the tracing spans it
+// already opens. These names are the seam: if a Plonky3 bump renames a span, its phase reads as zero and
+// the names below are the only thing to update.
+
+/// span around the trace LDE and its Merkle commitment
+pub const TRACE_COMMIT_SPANS: &[&str] = &["commit to trace data"];
+/// spans around evaluating the constraints on the quotient domain and committing the quotient chunks
+pub const QUOTIENT_SPANS: &[&str] = &["compute quotient polynomial", "commit to quotient poly chunks"];
+/// span around the PCS opening, which runs FRI
+pub const FRI_SPANS: &[&str] = &["open"];
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PhaseTimings {
+    pub trace_commit: Duration,
+    pub quotient: Duration,
+    pub fri: Duration,
+    pub total: Duration,
+}
+
+impl PhaseTimings {
+    /// Time spent outside the three phases, e.g. observing into the challenger.
+    pub fn other(&self) -> Duration {
+        self.total.saturating_sub(self.trace_commit + self.quotient + self.fri)
+    }
+}
+
+#[derive(Clone, Default)]
+struct SpanTimer {
+    busy: Arc<Mutex<HashMap<&'static str, Duration>>>,
+}
+
+struct EnteredAt(Instant);
+
+impl<S> Layer<S> for SpanTimer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().replace(EnteredAt(Instant::now()));
+        }
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(EnteredAt(start)) = span.extensions_mut().remove::<EnteredAt>() {
+                *self.busy.lock().unwrap().entry(span.name()).or_default() += start.elapsed();
+            }
+        }
+    }
+}
+
+impl SpanTimer {
+    fn sum(&self, names: &[&str]) -> Duration {
+        let busy = self.busy.lock().unwrap();
+        names.iter().filter_map(|name| busy.get(name)).sum()
+    }
+}
+
+/// `prove`, returning the time spent in each prover phase alongside the proof.
+///
+/// The spans are captured with a thread-local subscriber, so while this runs the prover's spans don't reach
+/// the global subscriber; the timings are re-emitted as fields of a single `prover phases` event instead.
+pub fn prove_timed<
+    SC,
+    #[cfg(debug_assertions)] A: for<'a> Air<DebugConstraintBuilder<'a, Val<SC>>>,
+    #[cfg(not(debug_assertions))] A,
+>(
+    config: &SC,
+    air: &A,
+    challenger: &mut SC::Challenger,
+    trace: RowMajorMatrix<Val<SC>>,
+    public_values: &Vec<Val<SC>>,
+) -> (Proof<SC>, PhaseTimings)
+where
+    SC: StarkGenericConfig,
+    A: Air<SymbolicAirBuilder<Val<SC>>> + for<'a> Air<ProverConstraintFolder<'a, SC>>,
+{
+    let timer = SpanTimer::default();
+    let subscriber = Registry::default().with(timer.clone());
+
+    let start = Instant::now();
+    let proof = tracing::subscriber::with_default(subscriber, || {
+        prove(config, air, challenger, trace, public_values)
+    });
+
+    let timings = PhaseTimings {
+        trace_commit: timer.sum(TRACE_COMMIT_SPANS),
+        quotient: timer.sum(QUOTIENT_SPANS),
+        fri: timer.sum(FRI_SPANS),
+        total: start.elapsed(),
+    };
+    info!(
+        trace_commit_ms = timings.trace_commit.as_millis() as u64,
+        quotient_ms = timings.quotient.as_millis() as u64,
+        fri_ms = timings.fri.as_millis() as u64,
+        total_ms = timings.total.as_millis() as u64,
+        "prover phases"
+    );
+
+    (proof, timings)
+}
+
+/// One prove/verify run in a parameter sweep: the parameters, then either the measurements, the reason the
+/// combination was skipped, or why its proof failed to verify. This is the row schema of the `bench-matrix`
+/// JSON and CSV reports.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunStats {
+    pub field: String,
+    pub hash: String,
+    pub log_blowup: usize,
+    pub log_n: usize,
+    pub skipped: Option<String>,
+    /// the proof did not verify; unlike a skip, this is a bug
+    #[serde(default)]
+    pub failed: Option<String>,
+    pub trace_commit_ms: Option<f64>,
+    pub quotient_ms: Option<f64>,
+    pub fri_ms: Option<f64>,
+    pub prove_ms: Option<f64>,
+    pub verify_ms: Option<f64>,
+    pub proof_bytes: Option<usize>,
+}
+
+impl RunStats {
+    pub const CSV_HEADER: &'static str =
+        "field,hash,log_blowup,log_n,skipped,failed,trace_commit_ms,quotient_ms,fri_ms,prove_ms,verify_ms,proof_bytes";
+
+    /// A run that measured nothing, for a combination that can't be proven.
+    pub fn skipped(field: &str, hash: &str, log_blowup: usize, log_n: usize, reason: impl Into<String>) -> Self {
+        RunStats {
+            field: field.to_string(),
+            hash: hash.to_string(),
+            log_blowup,
+            log_n,
+            skipped: Some(reason.into()),
+            ..Default::default()
+        }
+    }
+
+    /// A run whose proof did not verify.
+    pub fn failed(field: &str, hash: &str, log_blowup: usize, log_n: usize, reason: impl Into<String>) -> Self {
+        RunStats {
+            field: field.to_string(),
+            hash: hash.to_string(),
+            log_blowup,
+            log_n,
+            failed: Some(reason.into()),
+            ..Default::default()
+        }
+    }
+
+    /// A run with its measurements.
+    pub fn measured(
+        field: &str,
+        hash: &str,
+        log_blowup: usize,
+        log_n: usize,
+        phases: &PhaseTimings,
+        verify: Duration,
+        proof_bytes: usize,
+    ) -> Self {
+        let ms = |d: Duration| Some(d.as_secs_f64() * 1000.0);
+        RunStats {
+            field: field.to_string(),
+            hash: hash.to_string(),
+            log_blowup,
+            log_n,
+            skipped: None,
+            failed: None,
+            trace_commit_ms: ms(phases.trace_commit),
+            quotient_ms: ms(phases.quotient),
+            fri_ms: ms(phases.fri),
+            prove_ms: ms(phases.total),
+            verify_ms: ms(verify),
+            proof_bytes: Some(proof_bytes),
+        }
+    }
+
+    /// The parameters identifying the run, which a resumed sweep uses to skip work already done.
+    pub fn key(&self) -> (String, String, usize, usize) {
+        (self.field.clone(), self.hash.clone(), self.log_blowup, self.log_n)
+    }
+
+    /// The run as a line under `CSV_HEADER`; missing values are empty and the skip and failure reasons are
+    /// quoted.
+    pub fn to_csv_row(&self) -> String {
+        fn opt<T: ToString>(v: &Option<T>) -> String {
+            v.as_ref().map(|v| v.to_string()).unwrap_or_default()
+        }
+        fn quoted(reason: &Option<String>) -> String {
+            reason.as_ref().map(|r| format!("\"{}\"", r.replace('"', "\"\""))).unwrap_or_default()
+        }
+        format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{}",
+            self.field,
+            self.hash,
+            self.log_blowup,
+            self.log_n,
+            quoted(&self.skipped),
+            quoted(&self.failed),
+            opt(&self.trace_commit_ms),
+            opt(&self.quotient_ms),
+            opt(&self.fri_ms),
+            opt(&self.prove_ms),
+            opt(&self.verify_ms),
+            opt(&self.proof_bytes),
+        )
+    }
+
+    /// Parses a line written by `to_csv_row`.
+    pub fn from_csv_row(line: &str) -> Result<Self, String> {
+        let cells = split_csv_row(line)?;
+        let [
+            field, hash, log_blowup, log_n, skipped, failed,
+            trace_commit_ms, quotient_ms, fri_ms, prove_ms, verify_ms, proof_bytes,
+        ] = &cells[..]
+        else {
+            return Err(format!("expected 12 cells, got {}: {}", cells.len(), line));
+        };
+
+        fn opt<T: std::str::FromStr>(cell: &str) -> Result<Option<T>, String> {
+            match cell {
+                "" => Ok(None),
+                _ => cell.parse().map(Some).map_err(|_| format!("bad cell {:?}", cell)),
+            }
+        }
+        Ok(RunStats {
+            field: field.clone(),
+            hash: hash.clone(),
+            log_blowup: log_blowup.parse().map_err(|_| format!("bad log_blowup {:?}", log_blowup))?,
+            log_n: log_n.parse().map_err(|_| format!("bad log_n {:?}", log_n))?,
+            skipped: opt(skipped)?,
+            failed: opt(failed)?,
+            trace_commit_ms: opt(trace_commit_ms)?,
+            quotient_ms: opt(quotient_ms)?,
+            fri_ms: opt(fri_ms)?,
+            prove_ms: opt(prove_ms)?,
+            verify_ms: opt(verify_ms)?,
+            proof_bytes: opt(proof_bytes)?,
+        })
+    }
+}
+
+/// Splits a CSV line on commas outside double quotes, unescaping `""` inside them.
+fn split_csv_row(line: &str) -> Result<Vec<String>, String> {
+    let mut cells = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                cells.last_mut().unwrap().push('"');
+            }
+            ('"', _) => quoted = !quoted,
+            (',', false) => cells.push(String::new()),
+            _ => cells.last_mut().unwrap().push(c),
+        }
+    }
+    if quoted {
+        return Err(format!("unterminated quote: {}", line));
+    }
+    Ok(cells)
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_uni_stark::verify;
+
+    use super::*;
+    use crate::config::{default_config, random_perm, Challenger, Val};
+    use crate::simple_state::{random_trace, SimpleState};
+
+    #[test]
+    fn test_phases_are_timed() {
+        let perm = random_perm();
+        let config = default_config(&perm);
+        let trace = random_trace::<Val>(12);
+
+        let mut p_challenger = Challenger::new(perm.clone());
+        let (proof, timings) = prove_timed(&config, &SimpleState {}, &mut p_challenger, trace, &vec![]);
+
+        let mut v_challenger = Challenger::new(perm);
+        verify(&config, &SimpleState {}, &mut v_challenger, &proof, &vec![]).unwrap();
+
+        assert!(timings.trace_commit > Duration::ZERO);
+        assert!(timings.quotient > Duration::ZERO);
+        assert!(timings.fri > Duration::ZERO);
+        assert!(timings.trace_commit + timings.quotient + timings.fri <= timings.total);
+    }
+
+    #[test]
+    fn test_run_stats_csv_round_trip() {
+        let skipped = RunStats::skipped("m31", "poseidon2", 1, 10, "not two-adic, \"circle\" PCS needed");
+        assert_eq!(RunStats::from_csv_row(&skipped.to_csv_row()).unwrap(), skipped);
+        let failed = RunStats::failed("babybear", "poseidon2", 1, 10, "verification failed: InvalidOpeningArgument");
+        assert_eq!(RunStats::from_csv_row(&failed.to_csv_row()).unwrap(), failed);
+
+        let phases = PhaseTimings {
+            trace_commit: Duration::from_millis(3),
+            quotient: Duration::from_millis(5),
+            fri: Duration::from_millis(7),
+            total: Duration::from_millis(17),
+        };
+        let measured = RunStats::measured("babybear", "hybrid", 2, 12, &phases, Duration::from_millis(2), 91234);
+        assert_eq!(RunStats::from_csv_row(&measured.to_csv_row()).unwrap(), measured);
+        assert_eq!(measured.to_csv_row().split(',').count(), RunStats::CSV_HEADER.split(',').count());
+    }
+}
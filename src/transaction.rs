@@ -0,0 +1,198 @@
+use std::fmt;
+
+use p3_field::PrimeField32;
+use p3_matrix::dense::RowMajorMatrix;
+
+use crate::config::Val;
+use crate::gadgets::less_than::bit_decompose;
+use crate::simple_state::{BALANCE_BITS, SSC_ROW_WIDTH};
+
+// Application-level checks happen here, before a trace exists. A transaction that would overdraw the
+// balance is still rejected by `SimpleStateChecked`, but only as an unprovable trace; rejecting it up front
+// says which transaction was at fault. Amounts are field elements in the trace, so an amount at or above the
+// field order would wrap; those are rejected too.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Transaction {
+    pub input: u32,
+    pub output: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LedgerError {
+    /// `output > balance + input`
+    Overdraft { index: usize, balance: u32, input: u32, output: u32 },
+    /// the resulting balance doesn't fit in `BALANCE_BITS` bits
+    BalanceOverflow { index: usize, balance: u64 },
+    /// a balance, input or output at or above the field order
+    NotInField { index: usize, value: u32 },
+}
+
+impl fmt::Display for LedgerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LedgerError::Overdraft { index, balance, input, output } => write!(
+                f,
+                "transaction {} overdraws: output {} > balance {} + input {}",
+                index, output, balance, input
+            ),
+            LedgerError::BalanceOverflow { index, balance } => write!(
+                f,
+                "transaction {} overflows: balance {} needs more than {} bits",
+                index, balance, BALANCE_BITS
+            ),
+            LedgerError::NotInField { index, value } => write!(
+                f,
+                "transaction {} has an amount {} that is not below the field order {}",
+                index,
+                value,
+                Val::ORDER_U32
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LedgerError {}
+
+impl Transaction {
+    pub fn new(input: u32, output: u32) -> Self {
+        Self { input, output }
+    }
+
+    pub fn validate(&self, balance: u32) -> Result<(), LedgerError> {
+        self.apply(balance).map(|_| ())
+    }
+
+    /// Validates the transaction and returns the new balance.
+    pub fn apply(&self, balance: u32) -> Result<u32, LedgerError> {
+        if let Some(&value) = [balance, self.input, self.output].iter().find(|&&v| v >= Val::ORDER_U32) {
+            return Err(LedgerError::NotInField { index: 0, value });
+        }
+
+        let funds = balance as u64 + self.input as u64;
+        if self.output as u64 > funds {
+            return Err(LedgerError::Overdraft { index: 0, balance, input: self.input, output: self.output });
+        }
+
+        let new_balance = funds - self.output as u64;
+        if new_balance >> BALANCE_BITS != 0 {
+            return Err(LedgerError::BalanceOverflow { index: 0, balance: new_balance });
+        }
+        Ok(new_balance as u32)
+    }
+}
+
+/// Runs `transactions` against `initial_balance`, stopping at the first invalid one.
+pub fn validate_all(initial_balance: u32, transactions: &[Transaction]) -> Result<Vec<u32>, LedgerError> {
+    let mut balances = Vec::with_capacity(transactions.len() + 1);
+    let mut balance = initial_balance;
+    balances.push(balance);
+
+    for (i, tx) in transactions.iter().enumerate() {
+        balance = tx.apply(balance).map_err(|e| e.at(i))?;
+        balances.push(balance);
+    }
+    Ok(balances)
+}
+
+impl LedgerError {
+    fn at(self, i: usize) -> Self {
+        match self {
+            LedgerError::Overdraft { balance, input, output, .. } => {
+                LedgerError::Overdraft { index: i, balance, input, output }
+            }
+            LedgerError::BalanceOverflow { balance, .. } => LedgerError::BalanceOverflow { index: i, balance },
+            LedgerError::NotInField { value, .. } => LedgerError::NotInField { index: i, value },
+        }
+    }
+}
+
+/// Builds a `SimpleStateChecked` trace and its public values `[initial, final]` from validated
+/// transactions. The trace is padded to a power of two with empty transactions.
+pub fn trace_from_transactions<F: PrimeField32>(
+    initial_balance: u32,
+    transactions: &[Transaction],
+) -> Result<(RowMajorMatrix<F>, Vec<F>), LedgerError> {
+    let balances = validate_all(initial_balance, transactions)?;
+
+    let n = transactions.len().next_power_of_two().max(2);
+    let final_balance = *balances.last().unwrap();
+    let padding = Transaction::new(0, 0);
+
+    let mut values = Vec::with_capacity(n * SSC_ROW_WIDTH);
+    for i in 0..n {
+        let (balance, tx) = match transactions.get(i) {
+            Some(tx) => (balances[i], tx),
+            None => (final_balance, &padding),
+        };
+        // validated above, so this is below 2^BALANCE_BITS
+        let new_balance = (balance as u64 + tx.input as u64 - tx.output as u64) as u32;
+
+        values.extend([balance, tx.input, tx.output].map(F::from_canonical_u32));
+        values.extend(bit_decompose(F::from_canonical_u32(new_balance), BALANCE_BITS));
+    }
+
+    let public_values = vec![F::from_canonical_u32(initial_balance), F::from_canonical_u32(final_balance)];
+    Ok((RowMajorMatrix::new(values, SSC_ROW_WIDTH), public_values))
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_baby_bear::BabyBear;
+    use p3_field::AbstractField;
+
+    use super::*;
+    use crate::simple_state::SimpleStateChecked;
+
+    #[test]
+    fn test_valid_transactions() {
+        assert_eq!(Transaction::new(10, 25).apply(20), Ok(5));
+        assert_eq!(Transaction::new(0, 20).apply(20), Ok(0));
+
+        let txs = [Transaction::new(50, 0), Transaction::new(0, 120), Transaction::new(7, 3)];
+        let (trace, public_values) = trace_from_transactions::<BabyBear>(100, &txs).unwrap();
+        assert_eq!(public_values, vec![BabyBear::from_canonical_u32(100), BabyBear::from_canonical_u32(34)]);
+        crate::assert_constraints_ok!(&SimpleStateChecked {}, &trace, &public_values);
+    }
+
+    #[test]
+    fn test_overdraft_is_rejected() {
+        assert_eq!(
+            Transaction::new(10, 31).validate(20),
+            Err(LedgerError::Overdraft { index: 0, balance: 20, input: 10, output: 31 })
+        );
+
+        let txs = [Transaction::new(0, 50), Transaction::new(0, 60)];
+        assert_eq!(
+            trace_from_transactions::<BabyBear>(100, &txs).unwrap_err(),
+            LedgerError::Overdraft { index: 1, balance: 50, input: 0, output: 60 }
+        );
+    }
+
+    #[test]
+    fn test_overflow_is_rejected() {
+        let err = Transaction::new(1 << BALANCE_BITS, 0).validate(0).unwrap_err();
+        assert!(matches!(err, LedgerError::BalanceOverflow { .. }));
+    }
+
+    #[test]
+    fn test_amounts_outside_the_field_are_rejected() {
+        let p = Val::ORDER_U32;
+        assert_eq!(Transaction::new(p, p).validate(0), Err(LedgerError::NotInField { index: 0, value: p }));
+        assert_eq!(
+            Transaction::new(0, u32::MAX).validate(0),
+            Err(LedgerError::NotInField { index: 0, value: u32::MAX })
+        );
+
+        // a large input spent down to a valid balance is fine, the same amounts past the field order are not
+        let txs = [Transaction::new(5, 0), Transaction::new(p - 1, p - 1)];
+        let (trace, public_values) = trace_from_transactions::<BabyBear>(0, &txs).unwrap();
+        crate::assert_constraints_ok!(&SimpleStateChecked {}, &trace, &public_values);
+
+        let txs = [Transaction::new(5, 0), Transaction::new(p + 3, p)];
+        assert_eq!(
+            trace_from_transactions::<BabyBear>(0, &txs).unwrap_err(),
+            LedgerError::NotInField { index: 1, value: p + 3 }
+        );
+    }
+}
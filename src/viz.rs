@@ -0,0 +1,245 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::ops::Range;
+
+use p3_air::Air;
+use p3_field::{Field, PrimeField32};
+use p3_matrix::Matrix;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::{get_symbolic_constraints, Entry, SymbolicAirBuilder, SymbolicExpression};
+
+use crate::debug::{debug_check_constraints, EvalBuilder};
+
+// Static HTML rendering of a trace, for teaching and debugging.
+//
+// The debugger reports failures as (row, constraint index). The symbolic constraints from `uni_stark` come
+// out in the same order as `eval` asserts them, so the index also finds the constraint expression, and the
+// variables in that expression are the cells the failure implicates.
+
+/// Id of the `<td>` holding `(row, col)`.
+pub fn cell_id(row: usize, col: usize) -> String {
+    format!("cell-{}-{}", row, col)
+}
+
+/// Renders a symbolic constraint with main-trace variables named after `columns`.
+pub fn render_constraint<F: Field>(expr: &SymbolicExpression<F>, columns: &[String]) -> String {
+    match expr {
+        SymbolicExpression::Variable(v) => match v.entry {
+            Entry::Main { offset } => {
+                let name = columns.get(v.index).cloned().unwrap_or_else(|| format!("col{}", v.index));
+                if offset == 0 { format!("local.{}", name) } else { format!("next.{}", name) }
+            }
+            Entry::Public => format!("pis[{}]", v.index),
+            _ => format!("{:?}", v),
+        },
+        SymbolicExpression::IsFirstRow => "is_first_row".to_string(),
+        SymbolicExpression::IsLastRow => "is_last_row".to_string(),
+        SymbolicExpression::IsTransition => "is_transition".to_string(),
+        SymbolicExpression::Constant(c) => c.to_string(),
+        SymbolicExpression::Add { x, y, .. } => {
+            format!("({} + {})", render_constraint(x, columns), render_constraint(y, columns))
+        }
+        SymbolicExpression::Sub { x, y, .. } => {
+            format!("({} - {})", render_constraint(x, columns), render_constraint(y, columns))
+        }
+        SymbolicExpression::Neg { x, .. } => format!("-{}", render_constraint(x, columns)),
+        SymbolicExpression::Mul { x, y, .. } => {
+            format!("{} * {}", render_constraint(x, columns), render_constraint(y, columns))
+        }
+    }
+}
+
+/// `(row offset, column)` of every main-trace variable in `expr`.
+pub(crate) fn main_cells<F: Field>(expr: &SymbolicExpression<F>, cells: &mut BTreeSet<(usize, usize)>) {
+    match expr {
+        SymbolicExpression::Variable(v) => {
+            if let Entry::Main { offset } = v.entry {
+                cells.insert((offset, v.index));
+            }
+        }
+        SymbolicExpression::Add { x, y, .. }
+        | SymbolicExpression::Sub { x, y, .. }
+        | SymbolicExpression::Mul { x, y, .. } => {
+            main_cells(x, cells);
+            main_cells(y, cells);
+        }
+        SymbolicExpression::Neg { x, .. } => main_cells(x, cells),
+        _ => {}
+    }
+}
+
+/// Renders `rows` of `trace` as a standalone HTML page, highlighting cells implicated in failed constraints.
+pub fn render_html<F, A>(
+    air: &A,
+    columns: &[String],
+    trace: &RowMajorMatrix<F>,
+    public_values: &[F],
+    rows: Range<usize>,
+) -> String
+where
+    F: PrimeField32,
+    A: Air<SymbolicAirBuilder<F>> + for<'a> Air<EvalBuilder<'a, F>>,
+{
+    let height = trace.height();
+    let constraints = get_symbolic_constraints(air, 0, public_values.len());
+    let rendered = constraints.iter().map(|c| render_constraint(c, columns)).collect::<Vec<_>>();
+
+    // cell -> failed constraints touching it
+    let mut implicated: BTreeMap<(usize, usize), Vec<usize>> = BTreeMap::new();
+    let failures = debug_check_constraints(air, trace, public_values).err().unwrap_or_default();
+    for failure in &failures {
+        for &k in &failure.constraints {
+            let mut cells = BTreeSet::new();
+            main_cells(&constraints[k], &mut cells);
+            for (offset, col) in cells {
+                implicated.entry(((failure.row + offset) % height, col)).or_default().push(k);
+            }
+        }
+    }
+
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>trace</title>\n<style>\n");
+    html.push_str("table { border-collapse: collapse; font-family: monospace; }\n");
+    html.push_str("td, th { border: 1px solid #ccc; padding: 2px 6px; text-align: right; }\n");
+    html.push_str("td.fail { background: #f44; color: #fff; }\n");
+    html.push_str("</style>\n</head>\n<body>\n<table>\n<tr><th>row</th>");
+    for name in columns {
+        write!(html, "<th>{}</th>", escape(name)).unwrap();
+    }
+    html.push_str("</tr>\n");
+
+    for r in rows.start..rows.end.min(height) {
+        write!(html, "<tr><th>{}</th>", r).unwrap();
+        for (c, v) in trace.row_slice(r).iter().enumerate() {
+            match implicated.get(&(r, c)) {
+                Some(ks) => {
+                    let title = ks.iter().map(|&k| format!("#{}: {} == 0", k, rendered[k])).collect::<Vec<_>>();
+                    write!(
+                        html,
+                        "<td id=\"{}\" class=\"fail\" title=\"{}\">{}</td>",
+                        cell_id(r, c),
+                        escape(&title.join("\n")),
+                        v.as_canonical_u32()
+                    )
+                    .unwrap();
+                }
+                None => write!(html, "<td id=\"{}\">{}</td>", cell_id(r, c), v.as_canonical_u32()).unwrap(),
+            }
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</table>\n");
+
+    if failures.is_empty() {
+        html.push_str("<p>all constraints hold</p>\n");
+    } else {
+        html.push_str("<h3>failed constraints</h3>\n<ul>\n");
+        for failure in &failures {
+            for &k in &failure.constraints {
+                writeln!(html, "<li>row {}: #{}: {} == 0</li>", failure.row, k, escape(&rendered[k])).unwrap();
+            }
+        }
+        html.push_str("</ul>\n");
+    }
+    html.push_str("</body>\n</html>\n");
+
+    html
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Parses a CSV trace dump of canonical values. A non-numeric first line is taken as a header and skipped.
+pub fn trace_from_csv<F: PrimeField32>(csv: &str) -> Result<RowMajorMatrix<F>, String> {
+    let mut values = vec![];
+    let mut width = None;
+
+    for (i, line) in csv.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+        let cells = line.split(',').map(|c| c.trim().parse::<u32>()).collect::<Result<Vec<_>, _>>();
+        let cells = match cells {
+            Ok(cells) => cells,
+            Err(_) if i == 0 => continue,
+            Err(e) => return Err(format!("line {}: {}", i + 1, e)),
+        };
+
+        if *width.get_or_insert(cells.len()) != cells.len() {
+            return Err(format!("line {}: expected {} cells, got {}", i + 1, width.unwrap(), cells.len()));
+        }
+        for c in cells {
+            if c >= F::ORDER_U32 {
+                return Err(format!("line {}: {} is not below the field modulus", i + 1, c));
+            }
+            values.push(F::from_canonical_u32(c));
+        }
+    }
+
+    match width {
+        Some(width) => Ok(RowMajorMatrix::new(values, width)),
+        None => Err("empty trace".to_string()),
+    }
+}
+
+/// Parses a comma-separated list of canonical public values, rejecting any not below the modulus.
+pub fn publics_from_csv<F: PrimeField32>(csv: &str) -> Result<Vec<F>, String> {
+    csv.split(',')
+        .map(|v| {
+            let v = v.trim().parse::<u32>().map_err(|e| format!("{:?}: {}", v, e))?;
+            if v >= F::ORDER_U32 {
+                return Err(format!("{} is not below the field modulus", v));
+            }
+            Ok(F::from_canonical_u32(v))
+        })
+        .collect()
+}
+
+/// Dumps a trace as CSV with a header row.
+pub fn trace_to_csv<F: PrimeField32>(trace: &RowMajorMatrix<F>, columns: &[String]) -> String {
+    let mut csv = columns.join(",");
+    csv.push('\n');
+    for r in 0..trace.height() {
+        let cells = trace.row_slice(r).iter().map(|v| v.as_canonical_u32().to_string()).collect::<Vec<_>>();
+        csv.push_str(&cells.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_baby_bear::BabyBear;
+    use p3_field::AbstractField;
+
+    use super::*;
+    use crate::columns::NamedColumns;
+    use crate::simple_state::{random_trace, SimpleState};
+
+    #[test]
+    fn test_corrupted_cell_is_highlighted() {
+        let air = SimpleState {};
+        let mut trace = random_trace::<BabyBear>(4);
+        trace.row_mut(5)[0] += BabyBear::one();
+
+        let html = render_html(&air, &air.column_names(), &trace, &[], 0..16);
+        assert!(html.contains(&format!("id=\"{}\" class=\"fail\"", cell_id(5, 0))));
+        assert!(html.contains("local.balance"));
+        // untouched rows stay plain
+        assert!(html.contains(&format!("<td id=\"{}\">", cell_id(10, 0))));
+    }
+
+    #[test]
+    fn test_csv_round_trip() {
+        let air = SimpleState {};
+        let trace = random_trace::<BabyBear>(3);
+        let csv = trace_to_csv(&trace, &air.column_names());
+        assert_eq!(trace_from_csv::<BabyBear>(&csv).unwrap().values, trace.values);
+    }
+
+    #[test]
+    fn test_publics_below_the_modulus() {
+        let publics = publics_from_csv::<BabyBear>("1, 2").unwrap();
+        assert_eq!(publics, vec![BabyBear::one(), BabyBear::two()]);
+        assert!(publics_from_csv::<BabyBear>(&BabyBear::ORDER_U32.to_string()).is_err());
+        assert!(publics_from_csv::<BabyBear>("1,x").is_err());
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::BTreeMap;
+use std::{env, fs};
+
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+use p3_challenger::{CanObserve, CanSample};
+use p3_field::{AbstractField, PrimeField32};
+use p3_keccak::Keccak256Hash;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
+use p3_symmetric::CryptographicHasher;
+use p3_uni_stark::{verify, Proof};
+use plonky3_cook::config::{default_config, perm_from_seed, Challenger, MyConfig, Perm, Val};
+use plonky3_cook::determinism::{proof_bytes, prove_deterministic};
+use plonky3_cook::simple_state::{random_trace_with_rng, SimpleState};
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+// Golden proofs: two small fixtures are proven in the deterministic mode (seeded Poseidon2 constants, seeded
+// traces, `prove_deterministic`) and Keccak digests of the results are compared against
+// `tests/golden/digests.toml`. A Plonky3 bump that changes any of them is a breaking change for stored
+// proofs and cached verification results.
+//
+// Three digests are pinned per fixture, in the order the proof is built, so a mismatch says which knob moved:
+//   trace_commitment  the Merkle root of the trace LDE: Poseidon2 constants, leaf hashing, DFT/LDE layout
+//   first_challenge   a challenge sampled after observing that root: the challenger (duplexing, sampling)
+//   proof             the bincode bytes of the whole proof: serialization or the later prover phases
+// The first digest that differs is the one to look at; the later ones differ as a consequence.
+//
+// After an intentional change, re-bless with
+//   COOK_BLESS_GOLDEN=1 cargo test -r --test golden
+// and commit the rewritten digests file. A missing digests file fails the test unless it is being blessed.
+
+const GOLDEN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/digests.toml");
+const BLESS_VAR: &str = "COOK_BLESS_GOLDEN";
+const SEED: u64 = 0x676f6c64;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Digests {
+    trace_commitment: String,
+    first_challenge: String,
+    proof: String,
+}
+
+struct FibonacciAir {}
+
+impl<F> BaseAir<F> for FibonacciAir {
+    fn width(&self) -> usize {
+        2
+    }
+}
+
+impl<AB: AirBuilderWithPublicValues> Air<AB> for FibonacciAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let (local, next) = (main.row_slice(0), main.row_slice(1));
+
+        let pis = builder.public_values();
+        let (a, b, x): (AB::Expr, AB::Expr, AB::Expr) = (pis[0].into(), pis[1].into(), pis[2].into());
+
+        builder.when_first_row().assert_eq(local[0], a);
+        builder.when_first_row().assert_eq(local[1], b);
+        builder.when_transition().assert_eq(next[0], local[1]);
+        builder.when_transition().assert_eq(next[1], local[0] + local[1]);
+        builder.when_last_row().assert_eq(local[1], x);
+    }
+}
+
+fn fibonacci_trace(log_n: usize) -> (RowMajorMatrix<Val>, Vec<Val>) {
+    let (mut a, mut b) = (Val::zero(), Val::one());
+    let mut values = Vec::with_capacity(2 << log_n);
+    for _ in 0..1 << log_n {
+        values.extend([a, b]);
+        (a, b) = (b, a + b);
+    }
+    let x = values[values.len() - 1];
+    (RowMajorMatrix::new(values, 2), vec![Val::zero(), Val::one(), x])
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn keccak_hex(bytes: impl IntoIterator<Item = u8>) -> String {
+    hex(&Keccak256Hash {}.hash_iter(bytes))
+}
+
+fn field_bytes(values: &[Val]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.as_canonical_u32().to_le_bytes()).collect()
+}
+
+fn digests(perm: &Perm, proof: &Proof<MyConfig>) -> Digests {
+    let root: [Val; 8] = proof.commitments.trace.clone().into();
+
+    let mut challenger = Challenger::new(perm.clone());
+    challenger.observe(proof.commitments.trace.clone());
+    let challenge: Val = challenger.sample();
+
+    Digests {
+        trace_commitment: keccak_hex(field_bytes(&root)),
+        first_challenge: keccak_hex(field_bytes(&[challenge])),
+        proof: keccak_hex(proof_bytes(proof)),
+    }
+}
+
+fn prove_fixtures() -> BTreeMap<String, Digests> {
+    let perm = perm_from_seed(SEED);
+    let config = default_config(&perm);
+    let mut fixtures = BTreeMap::new();
+
+    let trace = random_trace_with_rng::<Val, _>(6, &mut StdRng::seed_from_u64(SEED));
+    let proof = prove_deterministic(&config, &SimpleState {}, &mut Challenger::new(perm.clone()), trace, &vec![]);
+    verify(&config, &SimpleState {}, &mut Challenger::new(perm.clone()), &proof, &vec![]).unwrap();
+    fixtures.insert("simple_state".to_string(), digests(&perm, &proof));
+
+    let (trace, pis) = fibonacci_trace(6);
+    let proof = prove_deterministic(&config, &FibonacciAir {}, &mut Challenger::new(perm.clone()), trace, &pis);
+    verify(&config, &FibonacciAir {}, &mut Challenger::new(perm.clone()), &proof, &pis).unwrap();
+    fixtures.insert("fibonacci".to_string(), digests(&perm, &proof));
+
+    fixtures
+}
+
+/// Names the most likely cause of a mismatch from the first digest that diverged.
+fn diagnose(name: &str, golden: &Digests, actual: &Digests) -> Option<String> {
+    let knob = if golden.trace_commitment != actual.trace_commitment {
+        "the trace commitment changed: Poseidon2 round constants, leaf hashing or the LDE layout"
+    } else if golden.first_challenge != actual.first_challenge {
+        "the trace commitment matches but the first challenge changed: the challenger/transcript"
+    } else if golden.proof != actual.proof {
+        "the commitment and first challenge match but the proof bytes changed: serialization (Proof fields, \
+         bincode layout) or the later prover phases (quotient, FRI)"
+    } else {
+        return None;
+    };
+    Some(format!("{}: {}\n  golden {:?}\n  actual {:?}", name, knob, golden, actual))
+}
+
+#[test]
+fn golden_proofs() {
+    let actual = prove_fixtures();
+
+    if env::var(BLESS_VAR).is_ok_and(|v| v == "1") {
+        fs::create_dir_all(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden")).unwrap();
+        fs::write(GOLDEN_PATH, toml::to_string(&actual).unwrap()).unwrap();
+        eprintln!("blessed {}", GOLDEN_PATH);
+        return;
+    }
+
+    let text = fs::read_to_string(GOLDEN_PATH)
+        .unwrap_or_else(|e| panic!("cannot read {} ({}); run with {}=1 to create it", GOLDEN_PATH, e, BLESS_VAR));
+    let golden: BTreeMap<String, Digests> = toml::from_str(&text).unwrap();
+
+    let mut failures = vec![];
+    for (name, actual) in &actual {
+        match golden.get(name) {
+            Some(golden) => failures.extend(diagnose(name, golden, actual)),
+            None => failures.push(format!("{}: no golden digests", name)),
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "golden proofs changed; if intended, re-bless with {}=1\n{}",
+        BLESS_VAR,
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn diagnose_names_first_diverging_digest() {
+    let golden = Digests {
+        trace_commitment: "a".to_string(),
+        first_challenge: "b".to_string(),
+        proof: "c".to_string(),
+    };
+    assert_eq!(diagnose("f", &golden, &golden), None);
+
+    let changed = |t: &str, c: &str, p: &str| Digests {
+        trace_commitment: t.to_string(),
+        first_challenge: c.to_string(),
+        proof: p.to_string(),
+    };
+    assert!(diagnose("f", &golden, &changed("x", "y", "z")).unwrap().contains("Poseidon2 round constants"));
+    assert!(diagnose("f", &golden, &changed("a", "y", "z")).unwrap().contains("challenger/transcript"));
+    assert!(diagnose("f", &golden, &changed("a", "b", "z")).unwrap().contains("serialization"));
+}
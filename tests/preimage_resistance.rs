@@ -0,0 +1,50 @@
+use p3_field::{AbstractField, PrimeField32};
+use p3_symmetric::CryptographicHasher;
+use plonky3_cook::config::{perm_from_seed, MyHash, Val};
+use rand::{thread_rng, Rng};
+
+// Why `examples/preimage_knowledge.rs` needs a proof at all: without the witness, the only way to come up
+// with a preimage of a Poseidon2 digest is to query the hash and compare, treating it as a random oracle.
+// Each query matches an 8-element digest with probability about `p^-8`, so an honest search is hopeless for
+// any target whose preimage has real entropy, while the holder of the preimage proves knowledge of it in
+// milliseconds.
+//
+// `brute_force_preimage` is that search. It enumerates single-element inputs in order, so it succeeds only
+// for targets it can reach by counting, such as `Poseidon2(0)`, and those are exactly the targets that
+// shouldn't be used: a preimage from a small or guessable domain gives no security, proof or not.
+
+/// Tries the inputs `[0]`, `[1]`, ... up to `max_iters` of them, returning the first whose digest is `target`.
+fn brute_force_preimage<F, H>(hasher: &H, target: &[F], max_iters: u64) -> Option<Vec<F>>
+where
+    F: PrimeField32,
+    H: CryptographicHasher<F, [F; 8]>,
+{
+    (0..max_iters).map(|i| vec![F::from_wrapped_u64(i)]).find(|input| hasher.hash_iter(input.clone()) == *target)
+}
+
+fn hasher() -> MyHash {
+    MyHash::new(perm_from_seed(7))
+}
+
+#[test]
+fn test_finds_preimage_of_zero() {
+    let hasher = hasher();
+    let target = hasher.hash_iter([Val::zero()]);
+    assert_eq!(brute_force_preimage(&hasher, &target, 1), Some(vec![Val::zero()]));
+}
+
+#[test]
+fn test_finds_preimages_from_a_small_domain() {
+    let hasher = hasher();
+    let target = hasher.hash_iter([Val::from_canonical_u32(999)]);
+    assert_eq!(brute_force_preimage(&hasher, &target, 999), None);
+    assert_eq!(brute_force_preimage(&hasher, &target, 1000), Some(vec![Val::from_canonical_u32(999)]));
+}
+
+#[test]
+fn test_random_preimage_is_out_of_reach() {
+    let hasher = hasher();
+    let secret: [Val; 2] = [thread_rng().gen(), thread_rng().gen()];
+    let target = hasher.hash_iter(secret);
+    assert_eq!(brute_force_preimage(&hasher, &target, 1 << 14), None);
+}
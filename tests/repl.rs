@@ -0,0 +1,46 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+// Drives the `repl` binary through the scripted session in `tests/fixtures/repl_session.txt`.
+
+const SESSION: &str = include_str!("fixtures/repl_session.txt");
+
+fn run_session(input: &str) -> (bool, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_repl"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("the repl binary runs");
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    (output.status.success(), String::from_utf8(output.stdout).unwrap())
+}
+
+#[test]
+fn test_scripted_session() {
+    let (success, stdout) = run_session(SESSION);
+    assert!(success, "the repl exited with an error:\n{}", stdout);
+
+    let lines = stdout.lines().skip(1).collect::<Vec<_>>();
+    assert_eq!(lines[0], "balance: 100");
+    assert_eq!(lines[1], "balance: 70");
+    assert_eq!(lines[2], "error: transaction 2 overdraws: output 500 > balance 70 + input 0");
+    assert_eq!(lines[3], "error: not an amount: lots");
+    assert_eq!(lines[4], "error: missing amount");
+    assert!(lines[5].starts_with("error: unknown command: frobnicate"));
+    // the rejected transactions left the session as it was
+    assert_eq!(lines[6], "balance: 70");
+    assert_eq!(lines[7].split_whitespace().collect::<Vec<_>>(), ["0", "in", "100", "out", "0"]);
+    assert_eq!(lines[8].split_whitespace().collect::<Vec<_>>(), ["1", "in", "0", "out", "30"]);
+    assert!(lines[9].starts_with("proof verified: 2 transaction(s), balance 0 -> 70"), "{}", lines[9]);
+    // nothing after `quit` runs
+    assert_eq!(lines.len(), 10);
+}
+
+#[test]
+fn test_empty_session_proves() {
+    let (success, stdout) = run_session("prove\n");
+    assert!(success);
+    assert!(stdout.contains("proof verified: 0 transaction(s), balance 0 -> 0"), "{}", stdout);
+}
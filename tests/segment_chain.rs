@@ -0,0 +1,109 @@
+use p3_field::AbstractField;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_uni_stark::verify;
+use plonky3_cook::config::{default_config, perm_from_seed, Challenger, MyConfig, Val};
+use plonky3_cook::error::VerifyFailure;
+use plonky3_cook::pipeline::{prove_stream, PipelineOptions, SegmentProof};
+use plonky3_cook::simple_state::SimpleStateChecked;
+use plonky3_cook::transaction::{trace_from_transactions, validate_all, Transaction};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+// A long `SimpleStateChecked` run proven in segments. 4096 transactions are cut into four segments of 1024,
+// each proven on its own by `prove_stream`; a segment's public values `[initial, final]` are the balances it
+// starts from and ends at, so the segments form one run exactly when each starts where the previous ended.
+// Checking the whole run is then two things:
+//   every proof verifies against its own public values
+//   segment i's final balance is segment i + 1's initial balance
+// The proofs alone say nothing about the second: a segment proven from the wrong starting balance is a valid
+// proof of the wrong run, which only the boundary check catches.
+
+const TRANSACTIONS: usize = 4096;
+const SEGMENTS: usize = 4;
+const SEGMENT_LEN: usize = TRANSACTIONS / SEGMENTS;
+
+#[derive(Debug)]
+enum ChainError {
+    /// the proof of this segment doesn't verify
+    Segment(usize, VerifyFailure),
+    /// this segment doesn't start where the one before it ended
+    Boundary(usize),
+}
+
+/// Verifies every segment of a run and the boundaries between them.
+fn verify_chain(perm_seed: u64, segments: &[SegmentProof<MyConfig>]) -> Result<(), ChainError> {
+    let perm = perm_from_seed(perm_seed);
+    let config = default_config(&perm);
+    for (i, segment) in segments.iter().enumerate() {
+        let mut challenger = Challenger::new(perm.clone());
+        verify(&config, &SimpleStateChecked {}, &mut challenger, &segment.proof, &segment.public_values)
+            .map_err(|e| ChainError::Segment(i, VerifyFailure::from(e)))?;
+    }
+    for (i, pair) in segments.windows(2).enumerate() {
+        if pair[0].public_values[1] != pair[1].public_values[0] {
+            return Err(ChainError::Boundary(i + 1));
+        }
+    }
+    Ok(())
+}
+
+/// A run of `TRANSACTIONS` valid transactions, cut into `(initial_balance, transactions)` segments.
+fn run(seed: u64) -> Vec<(u32, Vec<Transaction>)> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut balance = 100000u32;
+    let transactions = (0..TRANSACTIONS)
+        .map(|_| {
+            let input = rng.gen_range(0..1 << 16);
+            let output = rng.gen_range(0..=balance + input);
+            balance = balance + input - output;
+            Transaction::new(input, output)
+        })
+        .collect::<Vec<_>>();
+
+    let mut initial = 100000u32;
+    transactions
+        .chunks(SEGMENT_LEN)
+        .map(|txs| {
+            let segment = (initial, txs.to_vec());
+            initial = *validate_all(initial, txs).unwrap().last().unwrap();
+            segment
+        })
+        .collect()
+}
+
+fn generate((initial, txs): (u32, Vec<Transaction>)) -> (RowMajorMatrix<Val>, Vec<Val>) {
+    trace_from_transactions(initial, &txs).unwrap()
+}
+
+fn prove_segments(perm_seed: u64, segments: Vec<(u32, Vec<Transaction>)>) -> Vec<SegmentProof<MyConfig>> {
+    let perm = perm_from_seed(perm_seed);
+    let config = default_config(&perm);
+    let options = PipelineOptions { generator_threads: 2, queue_depth: 2 };
+    prove_stream(&config, &SimpleStateChecked {}, Challenger::new(perm.clone()), segments, generate, options)
+        .collect()
+}
+
+#[test]
+fn test_four_segments_chain() {
+    let segments = prove_segments(1, run(7));
+    assert_eq!(segments.len(), SEGMENTS);
+    verify_chain(1, &segments).unwrap();
+}
+
+#[test]
+fn test_broken_boundary_fails() {
+    // segment 3 proven from one more than segment 2 left: both proofs are valid, the run is not
+    let mut segments = run(7);
+    segments[2].0 += 1;
+    let proofs = prove_segments(1, segments);
+    assert!(matches!(verify_chain(1, &proofs), Err(ChainError::Boundary(2))));
+}
+
+#[test]
+fn test_mended_boundary_fails() {
+    // claiming segment 3 started where segment 2 ended, without proving it, breaks segment 3's proof instead
+    let mut segments = run(7);
+    segments[2].0 += 1;
+    let mut proofs = prove_segments(1, segments);
+    proofs[2].public_values[0] -= Val::one();
+    assert!(matches!(verify_chain(1, &proofs), Err(ChainError::Segment(2, _))));
+}
@@ -0,0 +1,21 @@
+use std::env;
+use std::process::Command;
+
+// The `verifier-only` build: the library has to compile with the feature on, or the gating in `lib.rs` and
+// around the `prove_*` functions has drifted. It is a separate `cargo check` with its own target directory,
+// so it doesn't fight this test run for the build lock, and it rebuilds the Plonky3 crates the first time.
+// CI runs it with
+//   cargo test --test verifier_only -- --ignored
+
+#[test]
+#[ignore = "checks the whole crate again in a fresh target directory"]
+fn test_library_builds_verifier_only() {
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let status = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()))
+        .args(["check", "--lib", "--features", "verifier-only"])
+        .current_dir(manifest_dir)
+        .env("CARGO_TARGET_DIR", format!("{}/target/verifier-only", manifest_dir))
+        .status()
+        .expect("cargo runs");
+    assert!(status.success(), "the library does not build with `verifier-only`");
+}